@@ -137,9 +137,61 @@ fn bench_mesh_space_greedy_sizes(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_mesh_space_greedy_fast_path(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mesh_space_greedy_fast_path");
+
+    let config = WorldConfig {
+        chunk_size: 16,
+        max_height: 256,
+        max_light_level: 15,
+        sub_chunks: 8,
+        ..Default::default()
+    };
+
+    let registry = create_test_registry();
+    let mut mesher_registry = registry.to_mesher_registry();
+    mesher_registry.build_cache();
+    let chunks = create_test_chunks(&config);
+    let coords = Vec2(0, 0);
+
+    let space = chunks
+        .make_space(&coords, 1)
+        .needs_voxels()
+        .needs_height_maps()
+        .build();
+
+    let min_arr = [0, 0, 0];
+    let max_arr = [16, 32, 16];
+
+    group.bench_function("bitmask", |b| {
+        b.iter(|| {
+            voxelize_mesher::mesh_space_greedy(
+                black_box(&min_arr),
+                black_box(&max_arr),
+                black_box(&space),
+                black_box(&mesher_registry),
+            )
+        })
+    });
+
+    group.bench_function("scalar", |b| {
+        b.iter(|| {
+            voxelize_mesher::mesh_space_greedy_scalar(
+                black_box(&min_arr),
+                black_box(&max_arr),
+                black_box(&space),
+                black_box(&mesher_registry),
+            )
+        })
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_mesh_space_greedy,
-    bench_mesh_space_greedy_sizes
+    bench_mesh_space_greedy_sizes,
+    bench_mesh_space_greedy_fast_path
 );
 criterion_main!(benches);
@@ -310,6 +310,70 @@ impl BlockFace {
     }
 }
 
+/// A texture a [`TextureVariantRule`] can swap onto a face: its name, and
+/// where the registry placed it in the atlas.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct VariantTexture {
+    pub name: String,
+    #[serde(default)]
+    pub range: UV,
+}
+
+impl VariantTexture {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            range: UV::default(),
+        }
+    }
+}
+
+/// How the mesher varies the texture of a block's faces from voxel to voxel,
+/// so large surfaces don't look tiled. Every choice is a hash of the voxel's
+/// position, so any mesher produces the same result.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TextureVariantRule {
+    /// Names of the faces the rule applies to; empty for every face.
+    #[serde(default)]
+    pub faces: Vec<String>,
+
+    /// Textures picked at random in place of the face's own, which stays one
+    /// of the choices.
+    #[serde(default)]
+    pub variants: Vec<VariantTexture>,
+
+    /// Sixteen textures for connected faces, indexed by which same-block
+    /// neighbors in the face's plane it joins: `1` above, `2` right, `4`
+    /// below, `8` left. Takes precedence over `variants`.
+    #[serde(default)]
+    pub connected: Vec<VariantTexture>,
+
+    /// Turn top and bottom faces a random number of quarter turns.
+    #[serde(default)]
+    pub random_rotation: bool,
+
+    /// Mirror side faces left to right at random.
+    #[serde(default)]
+    pub random_mirror: bool,
+}
+
+impl TextureVariantRule {
+    pub fn applies_to(&self, face_name: &str) -> bool {
+        self.faces.is_empty()
+            || self
+                .faces
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(face_name))
+    }
+
+    /// Every texture the rule refers to.
+    pub fn textures_mut(&mut self) -> impl Iterator<Item = &mut VariantTexture> {
+        self.variants.iter_mut().chain(self.connected.iter_mut())
+    }
+}
+
 const PI_2: f32 = PI / 2.0;
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
@@ -1,14 +1,19 @@
 pub mod mesher;
 
 pub use mesher::{
-    compute_section_connectivity, connectivity_pair_bit, mesh_chunk, mesh_chunk_with_registry,
-    mesh_chunk_with_registry_chunks, mesh_space_greedy, Block, ChunkData, GeometryProtocol,
-    MeshConfig, MeshInput, MeshInputNoRegistry, MeshOutput, Registry, CONNECTIVITY_FACES,
-    CONNECTIVITY_FULL, CONNECTIVITY_SEALED,
+    compute_section_connectivity, connectivity_pair_bit, export_chunks, export_region, mesh_chunk,
+    mesh_chunk_grid, mesh_chunk_with_registry, mesh_chunk_with_registry_chunks, mesh_space_greedy,
+    mesh_space_greedy_scalar, pack_vertices, remesh_voxels, sort_back_to_front,
+    sort_translucent_geometries, unpack_vertices, Block, BlockModel, BlockModels, ChunkData,
+    CollisionMesh, CompactMeshError, ElementFace, ElementRotation, ExportAlpha, ExportMaterial,
+    ExportMesh, ExportOptions, ExportPrimitive, GeometryProtocol, MeshConfig, MeshInput,
+    MeshInputNoRegistry, MeshOutput, MeshSource, MeshSpan, ModelAxis, ModelElement, ModelError,
+    ModelGeometry, ModelSide, Registry, RemeshOutput, RenderLayer, VertexBuffers, VoxelCollider,
+    COMPACT_MESH_V1, CONNECTIVITY_FACES, CONNECTIVITY_FULL, CONNECTIVITY_SEALED,
 };
 
 pub use voxelize_core::{
     BlockConditionalPart, BlockDynamicPattern, BlockFace, BlockRotation, BlockRule, BlockRuleLogic,
-    BlockSimpleRule, BlockUtils, CornerData, LightColor, LightUtils, VoxelAccess, AABB, UV,
-    Y_ROT_SEGMENTS,
+    BlockSimpleRule, BlockUtils, CornerData, LightColor, LightUtils, TextureVariantRule,
+    VariantTexture, VoxelAccess, AABB, UV, Y_ROT_SEGMENTS,
 };
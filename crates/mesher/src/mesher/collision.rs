@@ -0,0 +1,157 @@
+//! Collision meshes: the solid volume of a range of voxels as a few boxes.
+//!
+//! Terrain is mostly runs of solid unit cubes, which collide exactly like
+//! one box covering the run. Those are merged greedily, columns first, then
+//! rows of columns, then slabs of rows. Every other shape keeps the boxes of
+//! its own voxel. Shapes that depend on their neighbors can't be settled
+//! ahead of time and are only listed, for the caller to resolve per query.
+
+use voxelize_core::AABB;
+
+/// What one voxel contributes to collision.
+#[derive(Debug, Clone)]
+pub enum VoxelCollider {
+    /// Nothing to collide with.
+    None,
+    /// A solid unit cube, merged with the cubes around it.
+    Cube,
+    /// Boxes in the voxel's own unit space, already rotated.
+    Boxes(Vec<AABB>),
+    /// A shape decided by the voxel's surroundings.
+    Dynamic,
+}
+
+/// The collision volume of the voxels from `min` (inclusive) to `max`
+/// (exclusive).
+#[derive(Debug, Clone, Default)]
+pub struct CollisionMesh {
+    pub min: [i32; 3],
+    pub max: [i32; 3],
+    /// World space boxes: merged runs of cubes, then the boxes of every
+    /// other static shape.
+    pub boxes: Vec<AABB>,
+    /// Voxels whose shape is resolved when queried.
+    pub dynamic: Vec<[i32; 3]>,
+    /// One bit per voxel holding any collider, indexed like the range.
+    solid: Vec<u64>,
+}
+
+impl CollisionMesh {
+    /// Build the mesh of `min..max`, asking `collider` about every voxel.
+    pub fn build(
+        min: &[i32; 3],
+        max: &[i32; 3],
+        mut collider: impl FnMut(i32, i32, i32) -> VoxelCollider,
+    ) -> Self {
+        let [size_x, size_y, size_z] =
+            [0, 1, 2].map(|axis| (max[axis] - min[axis]).max(0) as usize);
+        let index = |x: usize, y: usize, z: usize| (x * size_z + z) * size_y + y;
+
+        let mut mesh = Self {
+            min: *min,
+            max: *max,
+            solid: vec![0; (size_x * size_y * size_z).div_ceil(64)],
+            ..Default::default()
+        };
+        let mut cubes = vec![false; size_x * size_y * size_z];
+
+        for x in 0..size_x {
+            for z in 0..size_z {
+                for y in 0..size_y {
+                    let [vx, vy, vz] = [min[0] + x as i32, min[1] + y as i32, min[2] + z as i32];
+                    let shape = collider(vx, vy, vz);
+                    if matches!(shape, VoxelCollider::None) {
+                        continue;
+                    }
+
+                    let cell = index(x, y, z);
+                    mesh.solid[cell / 64] |= 1 << (cell % 64);
+                    match shape {
+                        VoxelCollider::Cube => cubes[cell] = true,
+                        VoxelCollider::Boxes(boxes) => {
+                            mesh.boxes.extend(boxes.into_iter().map(|mut aabb| {
+                                aabb.translate(vx as f32, vy as f32, vz as f32);
+                                aabb
+                            }))
+                        }
+                        VoxelCollider::Dynamic => mesh.dynamic.push([vx, vy, vz]),
+                        VoxelCollider::None => {}
+                    }
+                }
+            }
+        }
+
+        let mut merged = Vec::new();
+        for x in 0..size_x {
+            for z in 0..size_z {
+                for y in 0..size_y {
+                    if !cubes[index(x, y, z)] {
+                        continue;
+                    }
+
+                    let mut height = 1;
+                    while y + height < size_y && cubes[index(x, y + height, z)] {
+                        height += 1;
+                    }
+
+                    let mut depth = 1;
+                    while z + depth < size_z
+                        && (y..y + height).all(|y| cubes[index(x, y, z + depth)])
+                    {
+                        depth += 1;
+                    }
+
+                    let mut width = 1;
+                    while x + width < size_x
+                        && (z..z + depth)
+                            .all(|z| (y..y + height).all(|y| cubes[index(x + width, y, z)]))
+                    {
+                        width += 1;
+                    }
+
+                    for x in x..x + width {
+                        for z in z..z + depth {
+                            for y in y..y + height {
+                                cubes[index(x, y, z)] = false;
+                            }
+                        }
+                    }
+
+                    let [vx, vy, vz] = [min[0] + x as i32, min[1] + y as i32, min[2] + z as i32];
+                    merged.push(AABB::create(
+                        vx as f32,
+                        vy as f32,
+                        vz as f32,
+                        (vx + width as i32) as f32,
+                        (vy + height as i32) as f32,
+                        (vz + depth as i32) as f32,
+                    ));
+                }
+            }
+        }
+
+        merged.append(&mut mesh.boxes);
+        mesh.boxes = merged;
+        mesh
+    }
+
+    /// Whether the voxel holds any collider. Voxels outside the range hold
+    /// none.
+    pub fn is_solid(&self, vx: i32, vy: i32, vz: i32) -> bool {
+        let [x, y, z] = [vx - self.min[0], vy - self.min[1], vz - self.min[2]];
+        let [size_x, size_y, size_z] = [0, 1, 2].map(|axis| self.max[axis] - self.min[axis]);
+        if x < 0 || y < 0 || z < 0 || x >= size_x || y >= size_y || z >= size_z {
+            return false;
+        }
+
+        let cell = ((x * size_z + z) * size_y + y) as usize;
+        self.solid[cell / 64] & (1 << (cell % 64)) != 0
+    }
+
+    /// The static boxes overlapping `region`.
+    pub fn boxes_within<'a>(&'a self, region: &'a AABB) -> impl Iterator<Item = &'a AABB> + 'a {
+        self.boxes
+            .iter()
+            .filter(move |aabb| aabb.intersects(region))
+    }
+}
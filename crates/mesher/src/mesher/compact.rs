@@ -0,0 +1,329 @@
+//! The compact vertex layout: a geometry's vertex buffers packed into one
+//! versioned byte payload, a fraction of the size of the float buffers.
+//!
+//! Every face the mesher emits is a quad of four vertices triangulated
+//! along one of two diagonals, and every quad samples one rectangle of the
+//! texture atlas. The payload leans on both:
+//!
+//! - positions are quantized to 1/1024 of a block, relative to the meshed
+//!   range's minimum corner, in `u16`s stored one axis after another, which
+//!   compresses far better than interleaved triples;
+//! - each quad names its atlas rectangle by index into a per-geometry tile
+//!   table, and its corners carry `u8` coordinates within that rectangle;
+//! - light and AO stay one packed `u32` per vertex;
+//! - indices are implied by each quad's diagonal bit, unless the triangles
+//!   were reordered (back-to-front sorting), in which case they ship as is.
+//!
+//! The tile table deliberately doesn't index the registry's atlas grid.
+//! The mesher never sees the grid, independent and isolated faces sample
+//! textures of their own outside it, and partial faces (slabs, stairs,
+//! fluids) sample sub-rectangles of a cell, so a grid index would still
+//! need per-quad bounds. The table costs 16 bytes per distinct rectangle,
+//! a handful per geometry; quads index it with the `u16` a grid index
+//! would take anyway.
+//!
+//! Layout, little-endian, each section aligned for typed-array views:
+//!
+//! ```text
+//! u8 version, u8 flags, u16 tile count, u32 vertex count
+//! tiles:     [f32; 4] start u, end u, start v, end v
+//! lights:    u32 per vertex
+//! quads:     u16 per quad, tile index | diagonal bit (1 << 15)
+//! positions: u16 per vertex for x, then for y, then for z
+//! uvs:       [u8; 2] per vertex, zero-padded to 4 bytes
+//! indices:   u32 count, u32 each (only with FLAG_EXPLICIT_INDICES)
+//! ```
+
+use hashbrown::HashMap;
+use thiserror::Error;
+
+use super::*;
+
+/// Version byte of the current compact payload layout.
+pub const COMPACT_MESH_V1: u8 = 1;
+
+/// Position resolution: 1/1024 of a block.
+const POSITION_SCALE: f32 = 1024.0;
+
+/// Offset added before quantizing, so faces hanging past the range's
+/// minimum corner stay representable. With the scale this covers positions
+/// from -16 to 48 blocks.
+const POSITION_BIAS: f32 = 16.0;
+
+/// Steps across a tile per texture coordinate.
+const UV_STEPS: f32 = 255.0;
+
+const FLAG_EXPLICIT_INDICES: u8 = 1 << 0;
+const DIAGONAL_BIT: u16 = 1 << 15;
+const HEADER_LEN: usize = 8;
+
+/// The two ways a quad is split into triangles, relative to its first
+/// vertex: along the 1-2 diagonal, and along the 0-3 one.
+const QUAD_INDICES: [i32; 6] = [0, 1, 2, 2, 1, 3];
+const QUAD_INDICES_FLIPPED: [i32; 6] = [0, 1, 3, 3, 2, 0];
+
+#[derive(Debug, Error)]
+pub enum CompactMeshError {
+    #[error("Unsupported compact mesh version {0}")]
+    Version(u8),
+
+    #[error("Compact mesh payload ends early")]
+    Truncated,
+
+    #[error("Compact mesh quad refers to missing tile {0}")]
+    Tile(u16),
+}
+
+/// Vertex buffers in the layout `GeometryProtocol` carries them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VertexBuffers {
+    pub positions: Vec<f32>,
+    pub uvs: Vec<f32>,
+    pub indices: Vec<i32>,
+    pub lights: Vec<i32>,
+}
+
+/// Pack a geometry's vertex buffers into the compact layout, or `None` when
+/// they don't fit it: a vertex count that isn't whole quads, a position out
+/// of the quantized range, or more tiles than a quad can index. Such
+/// geometries ship in the float layout instead.
+pub fn pack_vertices(
+    positions: &[f32],
+    uvs: &[f32],
+    indices: &[i32],
+    lights: &[i32],
+) -> Option<Vec<u8>> {
+    let vertex_count = lights.len();
+    let quad_count = vertex_count / 4;
+    if !vertex_count.is_multiple_of(4)
+        || positions.len() != vertex_count * 3
+        || uvs.len() != vertex_count * 2
+        || vertex_count > u32::MAX as usize
+    {
+        return None;
+    }
+
+    let diagonals: Option<Vec<bool>> = (indices.len() == quad_count * 6)
+        .then(|| {
+            indices
+                .chunks_exact(6)
+                .enumerate()
+                .map(|(quad, triangles)| {
+                    let base = (quad * 4) as i32;
+                    let local: Vec<i32> = triangles.iter().map(|index| index - base).collect();
+                    if local == QUAD_INDICES {
+                        Some(false)
+                    } else if local == QUAD_INDICES_FLIPPED {
+                        Some(true)
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        })
+        .flatten();
+
+    let mut tiles: Vec<[f32; 4]> = vec![];
+    let mut tile_indices: HashMap<[u32; 4], u16> = HashMap::new();
+    let mut quads = Vec::with_capacity(quad_count);
+    let mut local_uvs = Vec::with_capacity(vertex_count * 2);
+
+    for (quad, corners) in uvs.chunks_exact(8).enumerate() {
+        let mut tile = [f32::MAX, f32::MIN, f32::MAX, f32::MIN];
+        for corner in corners.chunks_exact(2) {
+            tile[0] = tile[0].min(corner[0]);
+            tile[1] = tile[1].max(corner[0]);
+            tile[2] = tile[2].min(corner[1]);
+            tile[3] = tile[3].max(corner[1]);
+        }
+
+        let index = match tile_indices.get(&tile.map(f32::to_bits)) {
+            Some(index) => *index,
+            None => {
+                let index = tiles.len() as u16;
+                if index >= DIAGONAL_BIT {
+                    return None;
+                }
+                tile_indices.insert(tile.map(f32::to_bits), index);
+                tiles.push(tile);
+                index
+            }
+        };
+
+        let flipped = diagonals.as_ref().is_some_and(|diagonals| diagonals[quad]);
+        quads.push(index | if flipped { DIAGONAL_BIT } else { 0 });
+
+        for corner in corners.chunks_exact(2) {
+            local_uvs.push(uv_step(corner[0], tile[0], tile[1]));
+            local_uvs.push(uv_step(corner[1], tile[2], tile[3]));
+        }
+    }
+
+    let mut quantized = Vec::with_capacity(positions.len());
+    for axis in 0..3 {
+        for position in positions.iter().skip(axis).step_by(3) {
+            let value = ((position + POSITION_BIAS) * POSITION_SCALE).round();
+            if !(0.0..=u16::MAX as f32).contains(&value) {
+                return None;
+            }
+            quantized.push(value as u16);
+        }
+    }
+
+    let flags = if diagonals.is_some() {
+        0
+    } else {
+        FLAG_EXPLICIT_INDICES
+    };
+
+    let mut bytes = Vec::with_capacity(HEADER_LEN + tiles.len() * 16 + vertex_count * 12);
+    bytes.push(COMPACT_MESH_V1);
+    bytes.push(flags);
+    bytes.extend((tiles.len() as u16).to_le_bytes());
+    bytes.extend((vertex_count as u32).to_le_bytes());
+    for tile in &tiles {
+        for value in tile {
+            bytes.extend(value.to_le_bytes());
+        }
+    }
+    for light in lights {
+        bytes.extend(light.to_le_bytes());
+    }
+    for quad in &quads {
+        bytes.extend(quad.to_le_bytes());
+    }
+    for position in &quantized {
+        bytes.extend(position.to_le_bytes());
+    }
+    bytes.extend(&local_uvs);
+    bytes.resize(bytes.len().next_multiple_of(4), 0);
+
+    if flags & FLAG_EXPLICIT_INDICES != 0 {
+        bytes.extend((indices.len() as u32).to_le_bytes());
+        for index in indices {
+            bytes.extend(index.to_le_bytes());
+        }
+    }
+
+    Some(bytes)
+}
+
+/// Unpack a payload produced by [`pack_vertices`] into float buffers.
+/// Positions come back to within half a quantization step, and texture
+/// coordinates on a tile's edges come back exactly.
+pub fn unpack_vertices(bytes: &[u8]) -> Result<VertexBuffers, CompactMeshError> {
+    let mut reader = Reader { bytes, offset: 0 };
+
+    let version = reader.take::<1>()?[0];
+    if version != COMPACT_MESH_V1 {
+        return Err(CompactMeshError::Version(version));
+    }
+    let flags = reader.take::<1>()?[0];
+    let tile_count = u16::from_le_bytes(reader.take()?) as usize;
+    let vertex_count = u32::from_le_bytes(reader.take()?) as usize;
+    let quad_count = vertex_count / 4;
+
+    let tiles = (0..tile_count)
+        .map(|_| {
+            let mut tile = [0.0; 4];
+            for value in tile.iter_mut() {
+                *value = f32::from_le_bytes(reader.take()?);
+            }
+            Ok(tile)
+        })
+        .collect::<Result<Vec<_>, CompactMeshError>>()?;
+    let lights = (0..vertex_count)
+        .map(|_| Ok(i32::from_le_bytes(reader.take()?)))
+        .collect::<Result<Vec<_>, CompactMeshError>>()?;
+    let quads = (0..quad_count)
+        .map(|_| Ok(u16::from_le_bytes(reader.take()?)))
+        .collect::<Result<Vec<_>, CompactMeshError>>()?;
+    let mut positions = vec![0.0; vertex_count * 3];
+    for axis in 0..3 {
+        for position in positions.iter_mut().skip(axis).step_by(3) {
+            let value = u16::from_le_bytes(reader.take()?);
+            *position = value as f32 / POSITION_SCALE - POSITION_BIAS;
+        }
+    }
+
+    let mut uvs = Vec::with_capacity(vertex_count * 2);
+    for quad in &quads {
+        let index = quad & !DIAGONAL_BIT;
+        let [start_u, end_u, start_v, end_v] = *tiles
+            .get(index as usize)
+            .ok_or(CompactMeshError::Tile(index))?;
+        for _ in 0..4 {
+            let [u, v] = reader.take::<2>()?;
+            uvs.push(uv_at(u, start_u, end_u));
+            uvs.push(uv_at(v, start_v, end_v));
+        }
+    }
+    reader.offset = reader.offset.next_multiple_of(4);
+
+    let indices = if flags & FLAG_EXPLICIT_INDICES != 0 {
+        let count = u32::from_le_bytes(reader.take()?) as usize;
+        (0..count)
+            .map(|_| Ok(i32::from_le_bytes(reader.take()?)))
+            .collect::<Result<Vec<_>, CompactMeshError>>()?
+    } else {
+        quads
+            .iter()
+            .enumerate()
+            .flat_map(|(quad, bits)| {
+                let pattern = if bits & DIAGONAL_BIT != 0 {
+                    QUAD_INDICES_FLIPPED
+                } else {
+                    QUAD_INDICES
+                };
+                pattern.map(|index| index + (quad * 4) as i32)
+            })
+            .collect()
+    };
+
+    Ok(VertexBuffers {
+        positions,
+        uvs,
+        indices,
+        lights,
+    })
+}
+
+impl GeometryProtocol {
+    /// This geometry's vertex buffers in the compact layout; see
+    /// [`pack_vertices`].
+    pub fn to_compact(&self) -> Option<Vec<u8>> {
+        pack_vertices(&self.positions, &self.uvs, &self.indices, &self.lights)
+    }
+}
+
+fn uv_step(value: f32, start: f32, end: f32) -> u8 {
+    if end > start {
+        ((value - start) / (end - start) * UV_STEPS).round() as u8
+    } else {
+        0
+    }
+}
+
+fn uv_at(step: u8, start: f32, end: f32) -> f32 {
+    match step {
+        0 => start,
+        255 => end,
+        step => start + (end - start) * step as f32 / UV_STEPS,
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], CompactMeshError> {
+        let bytes = self
+            .bytes
+            .get(self.offset..self.offset + N)
+            .ok_or(CompactMeshError::Truncated)?;
+        self.offset += N;
+        Ok(bytes.try_into().unwrap())
+    }
+}
@@ -0,0 +1,990 @@
+//! Export meshed regions as standard 3D assets: binary glTF 2.0 (`.glb`) or
+//! Wavefront OBJ with an MTL library.
+//!
+//! Geometry comes from the greedy mesher, so an export looks like the game:
+//! faces are split into one material per block face, UVs point into the
+//! registry's texture atlas, and the baked voxel light (sunlight, torch
+//! colors and ambient occlusion) rides along as vertex colors. Greedy quads
+//! are split back into one quad per voxel, since a viewer stretches a quad's
+//! atlas tile across it where the client's shader repeats it per voxel.
+
+use hashbrown::HashMap;
+use serde_json::{json, Value};
+use voxelize_core::{LightUtils, VoxelAccess};
+
+use super::*;
+
+/// The client's default ambient occlusion table, out of 255, indexed by the
+/// two AO bits of a vertex.
+const AO_TABLE: [f32; 4] = [45.0, 105.0, 180.0, 255.0];
+
+/// Light levels are nibbles.
+const MAX_LIGHT_LEVEL: f32 = 15.0;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_VERSION: u32 = 2;
+const GLB_CHUNK_JSON: u32 = 0x4E4F_534A;
+const GLB_CHUNK_BIN: u32 = 0x004E_4942;
+
+const GL_ARRAY_BUFFER: u32 = 34962;
+const GL_ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const GL_FLOAT: u32 = 5126;
+const GL_UNSIGNED_INT: u32 = 5125;
+const GL_NEAREST: u32 = 9728;
+
+/// How a region is exported.
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// Path or URI of the texture atlas image, relative to the exported file.
+    /// Without it, materials carry no texture and only vertex colors show.
+    pub atlas: Option<String>,
+
+    /// Multiplier on sunlight when baking vertex colors, like the client's
+    /// sunlight intensity: 1.0 is noon, lower bakes a darker scene in which
+    /// torch light stands out.
+    pub sunlight_intensity: f32,
+
+    /// Whether to darken vertex colors by ambient occlusion.
+    pub ambient_occlusion: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            atlas: None,
+            sunlight_intensity: 1.0,
+            ambient_occlusion: true,
+        }
+    }
+}
+
+/// How a material blends with what is behind it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportAlpha {
+    Opaque,
+    /// Cut out by the texture's alpha, like leaves and glass.
+    Mask,
+    /// Blended, like fluids.
+    Blend,
+}
+
+/// The material of one face of one block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportMaterial {
+    /// `block` or `block_face`, lowercase with spaces as underscores.
+    pub name: String,
+    pub block: u32,
+    pub face: Option<String>,
+    pub alpha: ExportAlpha,
+}
+
+/// The triangles of one material.
+#[derive(Debug, Clone, Default)]
+pub struct ExportPrimitive {
+    /// Index into `ExportMesh::materials`.
+    pub material: usize,
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    /// Atlas UVs, origin at the bottom left as in OBJ and the client.
+    pub uvs: Vec<[f32; 2]>,
+    /// Baked light, linear 0.0 to 1.0.
+    pub colors: Vec<[f32; 3]>,
+    pub indices: Vec<u32>,
+}
+
+/// Meshed geometry gathered for export, split by material.
+#[derive(Debug, Clone, Default)]
+pub struct ExportMesh {
+    pub materials: Vec<ExportMaterial>,
+    pub primitives: Vec<ExportPrimitive>,
+    options: ExportOptions,
+    lookup: HashMap<(u32, Option<String>), usize>,
+}
+
+impl ExportMesh {
+    pub fn new(options: ExportOptions) -> Self {
+        Self {
+            options,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.primitives
+            .iter()
+            .all(|primitive| primitive.indices.is_empty())
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.primitives
+            .iter()
+            .map(|primitive| primitive.positions.len())
+            .sum()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.primitives
+            .iter()
+            .map(|primitive| primitive.indices.len() / 3)
+            .sum()
+    }
+
+    /// Add mesher output, moving its positions by `offset`. Geometry meshed
+    /// from `min` is relative to `min`, so a region meshed in several pieces
+    /// is stitched back together by offsetting each piece by how far its
+    /// `min` is from the region's.
+    pub fn add_geometries(
+        &mut self,
+        geometries: &[GeometryProtocol],
+        offset: [f32; 3],
+        registry: &Registry,
+    ) {
+        for geometry in geometries {
+            let Some(block) = registry.get_block_by_id(geometry.voxel) else {
+                continue;
+            };
+            let geometry = &split_greedy_quads(geometry);
+            // Vertices shared by the triangles of a quad stay shared.
+            let mut remap: HashMap<(usize, usize), u32> = HashMap::new();
+
+            for triangle in geometry.indices.chunks_exact(3) {
+                let corners = [triangle[0], triangle[1], triangle[2]].map(|index| index as usize);
+                if corners
+                    .iter()
+                    .any(|&index| index * 3 + 2 >= geometry.positions.len())
+                {
+                    continue;
+                }
+
+                let position = |index: usize| {
+                    [
+                        geometry.positions[index * 3] + offset[0],
+                        geometry.positions[index * 3 + 1] + offset[1],
+                        geometry.positions[index * 3 + 2] + offset[2],
+                    ]
+                };
+                let normal = triangle_normal(
+                    &position(corners[0]),
+                    &position(corners[1]),
+                    &position(corners[2]),
+                );
+
+                // Faces of one block share a geometry unless independent; the
+                // face a triangle belongs to is the one facing its way.
+                let face = geometry.face_name.clone().or_else(|| {
+                    block
+                        .faces
+                        .iter()
+                        .find(|face| {
+                            face.dir != [0, 0, 0]
+                                && face
+                                    .dir
+                                    .iter()
+                                    .zip(normal.iter())
+                                    .all(|(&dir, &n)| (dir as f32 - n).abs() < 1e-3)
+                        })
+                        .map(|face| face.name.clone())
+                });
+                let material = self.material(block, face);
+                let primitive = &mut self.primitives[material];
+
+                for index in corners {
+                    let vertex = *remap.entry((material, index)).or_insert_with(|| {
+                        let light = geometry.lights.get(index).copied().unwrap_or(0);
+                        let uv = [
+                            geometry.uvs.get(index * 2).copied().unwrap_or(0.0),
+                            geometry.uvs.get(index * 2 + 1).copied().unwrap_or(0.0),
+                        ];
+
+                        primitive.positions.push(position(index));
+                        primitive.normals.push(normal);
+                        primitive.uvs.push(uv);
+                        primitive.colors.push(bake_light(light, &self.options));
+                        primitive.positions.len() as u32 - 1
+                    });
+                    primitive.indices.push(vertex);
+                }
+            }
+        }
+    }
+
+    /// The index of the material (and its primitive) for a block's face.
+    fn material(&mut self, block: &Block, face: Option<String>) -> usize {
+        let key = (block.id, face);
+        if let Some(&index) = self.lookup.get(&key) {
+            return index;
+        }
+
+        let name = match &key.1 {
+            Some(face) => format!("{}_{}", block.name, face),
+            None => block.name.clone(),
+        }
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("_");
+
+        let alpha = if block.is_fluid {
+            ExportAlpha::Blend
+        } else if block.is_see_through {
+            ExportAlpha::Mask
+        } else {
+            ExportAlpha::Opaque
+        };
+
+        let index = self.materials.len();
+        self.materials.push(ExportMaterial {
+            name,
+            block: block.id,
+            face: key.1.clone(),
+            alpha,
+        });
+        self.primitives.push(ExportPrimitive {
+            material: index,
+            ..Default::default()
+        });
+        self.lookup.insert(key, index);
+        index
+    }
+
+    /// Write the mesh as OBJ, returning the OBJ and the MTL library it refers
+    /// to as `mtl_name`. Vertex colors use the widely read `v x y z r g b`
+    /// extension.
+    pub fn to_obj(&self, mtl_name: &str) -> (String, String) {
+        let mut obj = format!("# Exported by voxelize-mesher\nmtllib {}\n", mtl_name);
+        let mut mtl = String::from("# Exported by voxelize-mesher\n");
+        let mut base = 1;
+
+        for primitive in &self.primitives {
+            if primitive.indices.is_empty() {
+                continue;
+            }
+            let material = &self.materials[primitive.material];
+            obj.push_str(&format!("o {}\nusemtl {}\n", material.name, material.name));
+
+            for (position, color) in primitive.positions.iter().zip(&primitive.colors) {
+                obj.push_str(&format!(
+                    "v {} {} {} {} {} {}\n",
+                    position[0], position[1], position[2], color[0], color[1], color[2]
+                ));
+            }
+            for uv in &primitive.uvs {
+                obj.push_str(&format!("vt {} {}\n", uv[0], uv[1]));
+            }
+            for normal in &primitive.normals {
+                obj.push_str(&format!("vn {} {} {}\n", normal[0], normal[1], normal[2]));
+            }
+            for triangle in primitive.indices.chunks_exact(3) {
+                let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|index| index + base);
+                obj.push_str(&format!("f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}\n"));
+            }
+            base += primitive.positions.len() as u32;
+
+            mtl.push_str(&format!(
+                "\nnewmtl {}\nKa 1 1 1\nKd 1 1 1\nKs 0 0 0\nillum 1\n",
+                material.name
+            ));
+            if material.alpha == ExportAlpha::Blend {
+                mtl.push_str("d 0.8\n");
+            }
+            if let Some(atlas) = &self.options.atlas {
+                mtl.push_str(&format!("map_Kd {}\n", atlas));
+                if material.alpha != ExportAlpha::Opaque {
+                    mtl.push_str(&format!("map_d {}\n", atlas));
+                }
+            }
+        }
+
+        (obj, mtl)
+    }
+
+    /// Write the mesh as a binary glTF 2.0 file: one mesh with a primitive
+    /// per material, with positions, normals, atlas UVs and baked light as
+    /// `COLOR_0`.
+    pub fn to_glb(&self) -> Vec<u8> {
+        let mut bin: Vec<u8> = vec![];
+        let mut buffer_views: Vec<Value> = vec![];
+        let mut accessors: Vec<Value> = vec![];
+        let mut primitives: Vec<Value> = vec![];
+
+        let mut push_view = |bin: &mut Vec<u8>, bytes: Vec<u8>, target: u32| {
+            let offset = bin.len();
+            bin.extend_from_slice(&bytes);
+            buffer_views.push(json!({
+                "buffer": 0,
+                "byteOffset": offset,
+                "byteLength": bytes.len(),
+                "target": target,
+            }));
+            buffer_views.len() - 1
+        };
+
+        for primitive in &self.primitives {
+            if primitive.indices.is_empty() {
+                continue;
+            }
+
+            let (min, max) = bounds(&primitive.positions);
+            let count = primitive.positions.len();
+
+            let view = push_view(
+                &mut bin,
+                floats(primitive.positions.iter().flatten()),
+                GL_ARRAY_BUFFER,
+            );
+            accessors.push(json!({
+                "bufferView": view,
+                "componentType": GL_FLOAT,
+                "count": count,
+                "type": "VEC3",
+                "min": min,
+                "max": max,
+            }));
+            let position = accessors.len() - 1;
+
+            let view = push_view(
+                &mut bin,
+                floats(primitive.normals.iter().flatten()),
+                GL_ARRAY_BUFFER,
+            );
+            accessors.push(json!({
+                "bufferView": view,
+                "componentType": GL_FLOAT,
+                "count": count,
+                "type": "VEC3",
+            }));
+            let normal = accessors.len() - 1;
+
+            // glTF puts the UV origin at the top left.
+            let uvs: Vec<f32> = primitive
+                .uvs
+                .iter()
+                .flat_map(|&[u, v]| [u, 1.0 - v])
+                .collect();
+            let view = push_view(&mut bin, floats(uvs.iter()), GL_ARRAY_BUFFER);
+            accessors.push(json!({
+                "bufferView": view,
+                "componentType": GL_FLOAT,
+                "count": count,
+                "type": "VEC2",
+            }));
+            let uv = accessors.len() - 1;
+
+            let view = push_view(
+                &mut bin,
+                floats(primitive.colors.iter().flatten()),
+                GL_ARRAY_BUFFER,
+            );
+            accessors.push(json!({
+                "bufferView": view,
+                "componentType": GL_FLOAT,
+                "count": count,
+                "type": "VEC3",
+            }));
+            let color = accessors.len() - 1;
+
+            let indices: Vec<u8> = primitive
+                .indices
+                .iter()
+                .flat_map(|index| index.to_le_bytes())
+                .collect();
+            let view = push_view(&mut bin, indices, GL_ELEMENT_ARRAY_BUFFER);
+            accessors.push(json!({
+                "bufferView": view,
+                "componentType": GL_UNSIGNED_INT,
+                "count": primitive.indices.len(),
+                "type": "SCALAR",
+            }));
+            let indices = accessors.len() - 1;
+
+            primitives.push(json!({
+                "attributes": {
+                    "POSITION": position,
+                    "NORMAL": normal,
+                    "TEXCOORD_0": uv,
+                    "COLOR_0": color,
+                },
+                "indices": indices,
+                "material": primitive.material,
+            }));
+        }
+
+        let materials: Vec<Value> = self
+            .materials
+            .iter()
+            .map(|material| {
+                let mut pbr = json!({
+                    "metallicFactor": 0.0,
+                    "roughnessFactor": 1.0,
+                });
+                if self.options.atlas.is_some() {
+                    pbr["baseColorTexture"] = json!({ "index": 0 });
+                }
+                let mut value = json!({
+                    "name": material.name,
+                    "pbrMetallicRoughness": pbr,
+                });
+                match material.alpha {
+                    ExportAlpha::Opaque => {}
+                    ExportAlpha::Mask => {
+                        value["alphaMode"] = json!("MASK");
+                        value["alphaCutoff"] = json!(0.5);
+                        value["doubleSided"] = json!(true);
+                    }
+                    ExportAlpha::Blend => {
+                        value["alphaMode"] = json!("BLEND");
+                    }
+                }
+                value
+            })
+            .collect();
+
+        let mut gltf = json!({
+            "asset": { "version": "2.0", "generator": "voxelize-mesher" },
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0, "name": "region" }],
+            "meshes": [{ "name": "region", "primitives": primitives }],
+            "materials": materials,
+            "accessors": accessors,
+            "bufferViews": buffer_views,
+            "buffers": [{ "byteLength": bin.len() }],
+        });
+        if let Some(atlas) = &self.options.atlas {
+            // Nearest filtering keeps pixel art crisp.
+            gltf["samplers"] = json!([{ "magFilter": GL_NEAREST, "minFilter": GL_NEAREST }]);
+            gltf["images"] = json!([{ "uri": atlas }]);
+            gltf["textures"] = json!([{ "sampler": 0, "source": 0 }]);
+        }
+
+        let mut json = serde_json::to_vec(&gltf).unwrap_or_default();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        while !bin.len().is_multiple_of(4) {
+            bin.push(0);
+        }
+
+        let length = 12 + 8 + json.len() + 8 + bin.len();
+        let mut glb = Vec::with_capacity(length);
+        glb.extend_from_slice(&GLB_MAGIC.to_le_bytes());
+        glb.extend_from_slice(&GLB_VERSION.to_le_bytes());
+        glb.extend_from_slice(&(length as u32).to_le_bytes());
+        glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&GLB_CHUNK_JSON.to_le_bytes());
+        glb.extend_from_slice(&json);
+        glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&GLB_CHUNK_BIN.to_le_bytes());
+        glb.extend_from_slice(&bin);
+        glb
+    }
+}
+
+/// Mesh the voxels from `min` (inclusive) to `max` (exclusive) of any space
+/// for export, with positions relative to `min`.
+pub fn export_region<S: VoxelAccess>(
+    min: &[i32; 3],
+    max: &[i32; 3],
+    space: &S,
+    registry: &Registry,
+    options: ExportOptions,
+) -> ExportMesh {
+    let mut mesh = ExportMesh::new(options);
+    let geometries = mesh_space_greedy(min, max, space, registry);
+    mesh.add_geometries(&geometries, [0.0; 3], registry);
+    mesh
+}
+
+/// Mesh a region of a grid of chunks for export. `chunks` is laid out like
+/// the 3x3 slice of a mesh job, row by row along z, but `width` chunks wide
+/// and as many rows deep as it holds; missing chunks read as empty.
+pub fn export_chunks(
+    chunks: &[Option<ChunkData>],
+    width: usize,
+    min: &[i32; 3],
+    max: &[i32; 3],
+    registry: &Registry,
+    config: &MeshConfig,
+    options: ExportOptions,
+) -> ExportMesh {
+    let width = width.max(1);
+    let Some(origin) = grid_origin(chunks, width, config.chunk_size) else {
+        return ExportMesh::new(options);
+    };
+    let space = VoxelSpace::grid(chunks, config.chunk_size, origin, width as i32);
+
+    export_region(min, max, &space, registry, options)
+}
+
+/// A copy of `geometry` with every greedy quad split into one quad per voxel
+/// it covers. The voxels a quad merged had equal UVs, light and ambient
+/// occlusion at each corner, so every piece copies them from the quad's
+/// matching corner.
+fn split_greedy_quads(geometry: &GeometryProtocol) -> GeometryProtocol {
+    let mut split = GeometryProtocol {
+        voxel: geometry.voxel,
+        at: geometry.at,
+        face_name: geometry.face_name.clone(),
+        layer: geometry.layer,
+        ..Default::default()
+    };
+    let vertex_count = geometry.positions.len() / 3;
+    let position = |index: usize| {
+        [0, 1, 2].map(|axis| {
+            geometry
+                .positions
+                .get(index * 3 + axis)
+                .copied()
+                .unwrap_or(0.0)
+        })
+    };
+    let push_vertex = |split: &mut GeometryProtocol, position: [f32; 3], from: usize| {
+        split.positions.extend_from_slice(&position);
+        split
+            .uvs
+            .push(geometry.uvs.get(from * 2).copied().unwrap_or(0.0));
+        split
+            .uvs
+            .push(geometry.uvs.get(from * 2 + 1).copied().unwrap_or(0.0));
+        split
+            .lights
+            .push(geometry.lights.get(from).copied().unwrap_or(0));
+        (split.positions.len() / 3 - 1) as i32
+    };
+    // Vertices copied whole, so the triangles sharing them still do.
+    let mut copied: HashMap<usize, i32> = HashMap::new();
+
+    let mut cursor = 0;
+    while cursor + 3 <= geometry.indices.len() {
+        // A greedy quad is four vertices of its own and the six indices
+        // after them, starting at its first vertex.
+        let quad = geometry.indices.get(cursor..cursor + 6).filter(|quad| {
+            let base = quad[0];
+            base >= 0
+                && base as usize + 4 <= vertex_count
+                && quad.iter().all(|&index| (base..base + 4).contains(&index))
+                && (base..base + 4).all(|index| {
+                    geometry
+                        .lights
+                        .get(index as usize)
+                        .is_some_and(|&light| light & GREEDY_BIT != 0)
+                })
+        });
+
+        let Some(quad) = quad else {
+            let triangle = &geometry.indices[cursor..cursor + 3];
+            cursor += 3;
+            if triangle
+                .iter()
+                .any(|&index| index < 0 || index as usize >= vertex_count)
+            {
+                continue;
+            }
+            for &index in triangle {
+                let index = index as usize;
+                let vertex = match copied.get(&index) {
+                    Some(&vertex) => vertex,
+                    None => {
+                        let vertex = push_vertex(&mut split, position(index), index);
+                        copied.insert(index, vertex);
+                        vertex
+                    }
+                };
+                split.indices.push(vertex);
+            }
+            continue;
+        };
+
+        // Corners 1 and 2 are one edge along each side of corner 0, and
+        // corner 3 is across from it.
+        let base = quad[0] as usize;
+        let origin = position(base);
+        let edges = [position(base + 1), position(base + 2)]
+            .map(|corner| [0, 1, 2].map(|axis| corner[axis] - origin[axis]));
+        let [across, down] = edges.map(|edge| {
+            let length = edge.iter().map(|d| d * d).sum::<f32>().sqrt();
+            (length.round() as usize).max(1)
+        });
+
+        for i in 0..across {
+            for j in 0..down {
+                let first = (split.positions.len() / 3) as i32;
+                for corner in 0..4 {
+                    let s = (i + (corner & 1)) as f32 / across as f32;
+                    let t = (j + (corner >> 1)) as f32 / down as f32;
+                    let at = [0, 1, 2]
+                        .map(|axis| origin[axis] + edges[0][axis] * s + edges[1][axis] * t);
+                    push_vertex(&mut split, at, base + corner);
+                }
+                for &index in quad {
+                    split.indices.push(first + index - quad[0]);
+                }
+            }
+        }
+        cursor += 6;
+    }
+
+    split
+}
+
+/// The baked color of a packed vertex light: the brighter of sunlight and
+/// each torch color, darkened by ambient occlusion. Emissive faces glow at
+/// full brightness regardless.
+fn bake_light(light: i32, options: &ExportOptions) -> [f32; 3] {
+    if light & EMISSIVE_BIT != 0 {
+        return [1.0; 3];
+    }
+
+    let raw = (light & LIGHT_MASK) as u32;
+    let sunlight =
+        LightUtils::extract_sunlight(raw) as f32 / MAX_LIGHT_LEVEL * options.sunlight_intensity;
+    let ao = if options.ambient_occlusion {
+        AO_TABLE[((light >> AO_SHIFT) & AO_BITS) as usize] / 255.0
+    } else {
+        1.0
+    };
+
+    [
+        LightUtils::extract_red_light(raw),
+        LightUtils::extract_green_light(raw),
+        LightUtils::extract_blue_light(raw),
+    ]
+    .map(|torch| (torch as f32 / MAX_LIGHT_LEVEL).max(sunlight).min(1.0) * ao)
+}
+
+fn triangle_normal(a: &[f32; 3], b: &[f32; 3], c: &[f32; 3]) -> [f32; 3] {
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let n = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    if length <= f32::EPSILON {
+        return [0.0, 1.0, 0.0];
+    }
+    n.map(|component| component / length)
+}
+
+fn bounds(positions: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
+    positions
+        .iter()
+        .fold(([f32::MAX; 3], [f32::MIN; 3]), |(min, max), position| {
+            (
+                [0, 1, 2].map(|i| min[i].min(position[i])),
+                [0, 1, 2].map(|i| max[i].max(position[i])),
+            )
+        })
+}
+
+fn floats<'a>(values: impl Iterator<Item = &'a f32>) -> Vec<u8> {
+    values.flat_map(|value| value.to_le_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use voxelize_core::{BlockFace, AABB, UV};
+
+    use super::*;
+
+    const STONE: u32 = 1;
+    const HEIGHT: usize = 4;
+    const TILE: UV = UV {
+        start_u: 0.25,
+        end_u: 0.5,
+        start_v: 0.5,
+        end_v: 0.75,
+    };
+
+    fn registry() -> Registry {
+        let faces = [
+            ("px", [1, 0, 0]),
+            ("nx", [-1, 0, 0]),
+            ("py", [0, 1, 0]),
+            ("ny", [0, -1, 0]),
+            ("pz", [0, 0, 1]),
+            ("nz", [0, 0, -1]),
+        ]
+        .into_iter()
+        .map(|(name, dir)| BlockFace {
+            name: name.to_string(),
+            name_lower: name.to_string(),
+            dir,
+            range: TILE,
+            ..Default::default()
+        })
+        .collect();
+
+        let air = Block {
+            id: 0,
+            name: "Air".to_string(),
+            name_lower: "air".to_string(),
+            rotatable: false,
+            y_rotatable: false,
+            is_empty: true,
+            is_fluid: false,
+            is_waterloggable: false,
+            is_waterlogging_fluid: false,
+            is_opaque: false,
+            is_see_through: false,
+            is_transparent: [true; 6],
+            transparent_standalone: false,
+            occludes_fluid: false,
+            is_plant: false,
+            stack_group: 0,
+            faces: vec![],
+            aabbs: vec![],
+            dynamic_patterns: None,
+            texture_rules: vec![],
+        };
+        let stone = Block {
+            id: STONE,
+            name: "Mossy Stone".to_string(),
+            is_empty: false,
+            is_opaque: true,
+            is_transparent: [false; 6],
+            faces,
+            aabbs: vec![AABB {
+                min_x: 0.0,
+                min_y: 0.0,
+                min_z: 0.0,
+                max_x: 1.0,
+                max_y: 1.0,
+                max_z: 1.0,
+            }],
+            ..air.clone()
+        };
+
+        let mut registry = Registry::new(vec![(0, air), (STONE, stone)]);
+        registry.build_cache();
+        registry
+    }
+
+    fn chunk(cx: i32, cz: i32, stones: &[[i32; 3]]) -> ChunkData {
+        let size = 16;
+        let mut voxels = vec![0; size * HEIGHT * size];
+        for &[vx, vy, vz] in stones {
+            let [lx, lz] = [vx - cx * 16, vz - cz * 16].map(|local| local as usize);
+            voxels[lx * HEIGHT * size + vy as usize * size + lz] = STONE;
+        }
+
+        ChunkData {
+            voxels,
+            lights: vec![LightUtils::insert_sunlight(0, 15); size * HEIGHT * size],
+            shape: [size, HEIGHT, size],
+            min: [cx * 16, 0, cz * 16],
+        }
+    }
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn a_block_exports_a_material_per_face_with_baked_light() {
+        let registry = registry();
+        let chunks = vec![Some(chunk(0, 0, &[[1, 2, 1]]))];
+        let mesh = export_chunks(
+            &chunks,
+            1,
+            &[0, 0, 0],
+            &[16, HEIGHT as i32, 16],
+            &registry,
+            &MeshConfig::default(),
+            ExportOptions::default(),
+        );
+
+        assert_eq!((mesh.triangle_count(), mesh.vertex_count()), (12, 24));
+        let mut names: Vec<_> = mesh.materials.iter().map(|m| m.name.as_str()).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "mossy_stone_nx",
+                "mossy_stone_ny",
+                "mossy_stone_nz",
+                "mossy_stone_px",
+                "mossy_stone_py",
+                "mossy_stone_pz"
+            ]
+        );
+
+        let top = mesh
+            .materials
+            .iter()
+            .position(|material| material.face.as_deref() == Some("py"))
+            .unwrap();
+        let top = &mesh.primitives[top];
+        assert!(top.normals.iter().all(|normal| *normal == [0.0, 1.0, 0.0]));
+        assert!(top.positions.iter().all(|position| position[1] == 3.0));
+
+        // Full sunlight, nothing around to occlude.
+        assert!(top.colors.iter().all(|color| *color == [1.0; 3]));
+        let dusk = ExportOptions {
+            sunlight_intensity: 0.5,
+            ..Default::default()
+        };
+        let light = LightUtils::insert_red_light(LightUtils::insert_sunlight(0, 15), 12) as i32;
+        assert_eq!(bake_light(light | (3 << AO_SHIFT), &dusk), [0.8, 0.5, 0.5]);
+        assert_eq!(
+            bake_light(light, &dusk),
+            [0.8 * 45.0 / 255.0, 0.5 * 45.0 / 255.0, 0.5 * 45.0 / 255.0]
+        );
+    }
+
+    #[test]
+    fn regions_spanning_chunks_export_seamlessly_relative_to_their_min() {
+        let registry = registry();
+        let chunks = vec![
+            Some(chunk(0, 0, &[[15, 0, 0]])),
+            Some(chunk(1, 0, &[[16, 0, 0]])),
+        ];
+        let mesh = export_chunks(
+            &chunks,
+            2,
+            &[8, 0, 0],
+            &[24, HEIGHT as i32, 16],
+            &registry,
+            &MeshConfig::default(),
+            ExportOptions::default(),
+        );
+
+        // The shared face is culled, and the faces that merged across the
+        // border are exported one per voxel.
+        assert_eq!(mesh.triangle_count(), 20);
+        let (min, max) = bounds(
+            &mesh
+                .primitives
+                .iter()
+                .flat_map(|primitive| primitive.positions.clone())
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!((min, max), ([7.0, 0.0, 0.0], [9.0, 1.0, 1.0]));
+    }
+
+    #[test]
+    fn merged_faces_export_a_whole_tile_per_voxel() {
+        let registry = registry();
+        let stones: Vec<_> = (0..4)
+            .flat_map(|x| (0..3).map(move |z| [x, 1, z]))
+            .collect();
+        let chunks = vec![Some(chunk(0, 0, &stones))];
+        let mesh = export_chunks(
+            &chunks,
+            1,
+            &[0, 0, 0],
+            &[16, HEIGHT as i32, 16],
+            &registry,
+            &MeshConfig::default(),
+            ExportOptions::default(),
+        );
+
+        // 12 voxels on top and bottom, 2 * (4 + 3) around the sides.
+        assert_eq!(mesh.triangle_count(), 2 * (2 * 12 + 2 * 7));
+        for primitive in &mesh.primitives {
+            for triangle in primitive.indices.chunks_exact(3) {
+                let corners = triangle.iter().map(|&index| index as usize);
+                let (min, max) = bounds(
+                    &corners
+                        .clone()
+                        .map(|index| primitive.positions[index])
+                        .collect::<Vec<_>>(),
+                );
+                assert!((0..3).all(|axis| max[axis] - min[axis] <= 1.0));
+
+                let uvs: Vec<_> = corners.map(|index| primitive.uvs[index]).collect();
+                assert!(uvs.iter().all(|&[u, v]| {
+                    (u == TILE.start_u || u == TILE.end_u) && (v == TILE.start_v || v == TILE.end_v)
+                }));
+                assert!(uvs.iter().any(|uv| uv[0] != uvs[0][0]));
+                assert!(uvs.iter().any(|uv| uv[1] != uvs[0][1]));
+            }
+        }
+    }
+
+    #[test]
+    fn glb_files_are_well_formed() {
+        let registry = registry();
+        let chunks = vec![Some(chunk(0, 0, &[[1, 2, 1], [1, 3, 1]]))];
+        let mesh = export_chunks(
+            &chunks,
+            1,
+            &[0, 0, 0],
+            &[16, HEIGHT as i32, 16],
+            &registry,
+            &MeshConfig::default(),
+            ExportOptions {
+                atlas: Some("atlas.png".to_string()),
+                ..Default::default()
+            },
+        );
+        let glb = mesh.to_glb();
+
+        assert_eq!(u32_at(&glb, 0), GLB_MAGIC);
+        assert_eq!(u32_at(&glb, 4), 2);
+        assert_eq!(u32_at(&glb, 8) as usize, glb.len());
+
+        let json_length = u32_at(&glb, 12) as usize;
+        assert_eq!(u32_at(&glb, 16), GLB_CHUNK_JSON);
+        let gltf: Value = serde_json::from_slice(&glb[20..20 + json_length]).unwrap();
+        let bin_length = u32_at(&glb, 20 + json_length) as usize;
+        assert_eq!(u32_at(&glb, 24 + json_length), GLB_CHUNK_BIN);
+        assert_eq!(28 + json_length + bin_length, glb.len());
+        assert_eq!(gltf["buffers"][0]["byteLength"], json!(bin_length));
+
+        let primitives = gltf["meshes"][0]["primitives"].as_array().unwrap();
+        assert_eq!(primitives.len(), 6);
+        let vertices: u64 = primitives
+            .iter()
+            .map(|primitive| {
+                let position = primitive["attributes"]["POSITION"].as_u64().unwrap() as usize;
+                gltf["accessors"][position]["count"].as_u64().unwrap()
+            })
+            .sum();
+        assert_eq!(vertices as usize, mesh.vertex_count());
+        for view in gltf["bufferViews"].as_array().unwrap() {
+            let end = view["byteOffset"].as_u64().unwrap() + view["byteLength"].as_u64().unwrap();
+            assert!(end as usize <= bin_length);
+        }
+        assert_eq!(gltf["images"][0]["uri"], json!("atlas.png"));
+        assert_eq!(
+            gltf["materials"][0]["pbrMetallicRoughness"]["baseColorTexture"]["index"],
+            json!(0)
+        );
+    }
+
+    #[test]
+    fn obj_files_reference_their_materials() {
+        let registry = registry();
+        let chunks = vec![Some(chunk(0, 0, &[[1, 2, 1]]))];
+        let mesh = export_chunks(
+            &chunks,
+            1,
+            &[0, 0, 0],
+            &[16, HEIGHT as i32, 16],
+            &registry,
+            &MeshConfig::default(),
+            ExportOptions {
+                atlas: Some("atlas.png".to_string()),
+                ..Default::default()
+            },
+        );
+        let (obj, mtl) = mesh.to_obj("region.mtl");
+
+        let count = |prefix: &str| obj.lines().filter(|line| line.starts_with(prefix)).count();
+        assert!(obj.contains("mtllib region.mtl"));
+        assert_eq!(count("v "), mesh.vertex_count());
+        assert_eq!(count("vt "), mesh.vertex_count());
+        assert_eq!(count("vn "), mesh.vertex_count());
+        assert_eq!(count("f "), 12);
+        assert_eq!(count("usemtl "), 6);
+
+        // Face indices are 1-based and global across objects.
+        let last = obj.lines().rfind(|line| line.starts_with("f ")).unwrap();
+        assert!(last.contains(&format!("{0}/{0}/{0}", mesh.vertex_count())));
+
+        assert_eq!(mtl.matches("newmtl ").count(), 6);
+        assert_eq!(mtl.matches("map_Kd atlas.png").count(), 6);
+    }
+}
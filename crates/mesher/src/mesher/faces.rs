@@ -41,6 +41,7 @@ pub(super) fn can_greedy_mesh_block(block: &Block, rotation: &BlockRotation) ->
         && block.is_full_cube()
         && block.faces.iter().all(|face| !face.isolated)
         && !has_diagonal_faces(block)
+        && block.texture_rules.is_empty()
 }
 
 pub(super) fn should_render_face<S: VoxelAccess>(
@@ -256,6 +256,78 @@ pub fn mesh_space_greedy<S: VoxelAccess>(
     max: &[i32; 3],
     space: &S,
     registry: &Registry,
+) -> Vec<GeometryProtocol> {
+    mesh_space_greedy_in(min, max, space, registry, None, true)
+}
+
+/// [`mesh_space_greedy`] without the occupancy bitmask fast path: every
+/// voxel goes through the per-voxel neighbor checks. Produces the same mesh;
+/// kept as the reference the fast path is tested and benchmarked against.
+pub fn mesh_space_greedy_scalar<S: VoxelAccess>(
+    min: &[i32; 3],
+    max: &[i32; 3],
+    space: &S,
+    registry: &Registry,
+) -> Vec<GeometryProtocol> {
+    mesh_space_greedy_in(min, max, space, registry, None, false)
+}
+
+/// The greedy mask entry for `face` of the greedy-meshable `block` at
+/// `voxel`, whose face towards `dir` is drawn.
+fn greedy_face_data<S: VoxelAccess>(
+    [vx, vy, vz]: [i32; 3],
+    dir: [i32; 3],
+    block: &Block,
+    face: &BlockFace,
+    space: &S,
+    registry: &Registry,
+) -> FaceData {
+    let uv_range = face.range.clone();
+    let neighbors = NeighborCache::populate(vx, vy, vz, space);
+    let (aos, lights) = compute_face_ao_and_light(dir, block, &neighbors, registry);
+    let is_water_exposed = space.get_voxel_waterlogged(vx, vy, vz)
+        || space.get_voxel_waterlogged(vx + dir[0], vy + dir[1], vz + dir[2])
+        || registry
+            .get_block_by_id(space.get_voxel(vx + dir[0], vy + dir[1], vz + dir[2]))
+            .map(|b| b.is_fluid)
+            .unwrap_or(false);
+
+    let key = FaceKey {
+        block_id: block.id,
+        face_name: face.name.clone(),
+        independent: face.independent,
+        is_water_exposed,
+        ao: aos,
+        light: lights,
+        uv_start_u: (uv_range.start_u * 1000000.0) as u32,
+        uv_end_u: (uv_range.end_u * 1000000.0) as u32,
+        uv_start_v: (uv_range.start_v * 1000000.0) as u32,
+        uv_end_v: (uv_range.end_v * 1000000.0) as u32,
+    };
+
+    FaceData {
+        key,
+        uv_range,
+        is_see_through: block.is_see_through,
+        is_fluid: block.is_fluid,
+        emissive_bits: ao_or_emissive_bits(0, face.emissive),
+    }
+}
+
+/// Mesh `min..max`, or with `dirty` only its dirty planes and the faces of
+/// its dirty non-greedy voxels. A dirty plane is meshed across the whole
+/// range, so its greedy runs split and merge as a full mesh would.
+///
+/// With `fast_path`, plain cubes are culled from occupancy bitmasks (see
+/// [`Occupancy`]) and the slice masks are dense; everything else takes the
+/// per-voxel path either way.
+pub(super) fn mesh_space_greedy_in<S: VoxelAccess>(
+    min: &[i32; 3],
+    max: &[i32; 3],
+    space: &S,
+    registry: &Registry,
+    dirty: Option<&DirtyRegion>,
+    fast_path: bool,
 ) -> Vec<GeometryProtocol> {
     let mut map: HashMap<String, GeometryProtocol> = HashMap::new();
     let mut processed_non_greedy: HashSet<(i32, i32, i32)> = HashSet::new();
@@ -264,13 +336,19 @@ pub fn mesh_space_greedy<S: VoxelAccess>(
 
     let [min_x, min_y, min_z] = *min;
     let [max_x, max_y, max_z] = *max;
-    let (scan_min, scan_max) = match find_sparse_non_empty_bounds(min, max, space, registry) {
-        ScanBounds::Empty => return Vec::new(),
-        ScanBounds::Sparse { min, max } => (min, max),
-        ScanBounds::Dense => (*min, *max),
+    let (scan_min, scan_max) = if dirty.is_some() {
+        (*min, *max)
+    } else {
+        match find_sparse_non_empty_bounds(min, max, space, registry) {
+            ScanBounds::Empty => return Vec::new(),
+            ScanBounds::Sparse { min, max } => (min, max),
+            ScanBounds::Dense => (*min, *max),
+        }
     };
     let [scan_min_x, scan_min_y, scan_min_z] = scan_min;
     let [scan_max_x, scan_max_y, scan_max_z] = scan_max;
+    let occupancy = fast_path.then(|| Occupancy::build(&scan_min, &scan_max, space, registry));
+    let mut dense_mask = GreedyMask::default();
 
     let directions: [(i32, i32, i32); 6] = [
         (1, 0, 0),
@@ -298,7 +376,7 @@ pub fn mesh_space_greedy<S: VoxelAccess>(
         bool,
     )> = Vec::new();
 
-    for (dx, dy, dz) in directions {
+    for (direction, (dx, dy, dz)) in directions.into_iter().enumerate() {
         let dir = [dx, dy, dz];
 
         let (axis, u_axis, v_axis) = if dx != 0 {
@@ -328,7 +406,15 @@ pub fn mesh_space_greedy<S: VoxelAccess>(
         };
 
         for slice in slice_range {
+            let plane = slice + if dir[axis] > 0 { 1 } else { 0 };
+            if dirty.is_some_and(|dirty| !dirty.planes[axis].contains(&plane)) {
+                continue;
+            }
+
             greedy_mask.clear();
+            if occupancy.is_some() {
+                dense_mask.reset(u_range.0, u_range.1, v_range.0, v_range.1);
+            }
             non_greedy_faces.clear();
 
             for u in u_range.0..u_range.1 {
@@ -340,6 +426,32 @@ pub fn mesh_space_greedy<S: VoxelAccess>(
                         _ => continue,
                     };
 
+                    let fast_face = occupancy.as_ref().map_or(FastFace::Fallback, |occupancy| {
+                        occupancy.face(vx, vy, vz, direction)
+                    });
+                    match fast_face {
+                        FastFace::Hidden => continue,
+                        FastFace::Visible => {
+                            let voxel_id = space.get_voxel(vx, vy, vz);
+                            let Some(block) = registry.get_block_by_id(voxel_id) else {
+                                continue;
+                            };
+                            if let Some(face) = block.faces.iter().find(|face| face.dir == dir) {
+                                let data = greedy_face_data(
+                                    [vx, vy, vz],
+                                    dir,
+                                    block,
+                                    face,
+                                    space,
+                                    registry,
+                                );
+                                dense_mask.insert(u, v, data);
+                            }
+                            continue;
+                        }
+                        FastFace::Fallback => {}
+                    }
+
                     let voxel_id = space.get_voxel(vx, vy, vz);
                     if !registry.has_type(voxel_id) {
                         continue;
@@ -355,12 +467,16 @@ pub fn mesh_space_greedy<S: VoxelAccess>(
                         continue;
                     }
 
+                    let is_dirty_voxel =
+                        dirty.is_none_or(|dirty| dirty.voxels.contains(&(vx, vy, vz)));
+
                     // A waterlogged voxel draws its block *and* the fluid it
                     // holds. Without the fluid pass the surrounding water,
                     // which culls its faces against this voxel, would leave a
                     // block-shaped hole in the tank.
                     if let Some((fluid_id, fluid_block)) = waterlogging_fluid {
-                        if space.get_voxel_waterlogged(vx, vy, vz)
+                        if is_dirty_voxel
+                            && space.get_voxel_waterlogged(vx, vy, vz)
                             && has_standard_six_faces(&fluid_block.faces)
                             && processed_waterlogged.insert((vx, vy, vz))
                         {
@@ -430,48 +546,17 @@ pub fn mesh_space_greedy<S: VoxelAccess>(
                             continue;
                         }
 
-                        let uv_range = face.range.clone();
-                        let neighbors = NeighborCache::populate(vx, vy, vz, space);
-                        let (aos, lights) =
-                            compute_face_ao_and_light(dir, block, &neighbors, registry);
-                        let is_water_exposed = space.get_voxel_waterlogged(vx, vy, vz)
-                            || space.get_voxel_waterlogged(
-                                vx + dir[0],
-                                vy + dir[1],
-                                vz + dir[2],
-                            )
-                            || registry
-                                .get_block_by_id(space.get_voxel(
-                                    vx + dir[0],
-                                    vy + dir[1],
-                                    vz + dir[2],
-                                ))
-                                .map(|b| b.is_fluid)
-                                .unwrap_or(false);
-
-                        let key = FaceKey {
-                            block_id: block.id,
-                            face_name: face.name.clone(),
-                            independent: face.independent,
-                            is_water_exposed,
-                            ao: aos,
-                            light: lights,
-                            uv_start_u: (uv_range.start_u * 1000000.0) as u32,
-                            uv_end_u: (uv_range.end_u * 1000000.0) as u32,
-                            uv_start_v: (uv_range.start_v * 1000000.0) as u32,
-                            uv_end_v: (uv_range.end_v * 1000000.0) as u32,
-                        };
+                        let data =
+                            greedy_face_data([vx, vy, vz], dir, block, face, space, registry);
+                        if occupancy.is_some() {
+                            dense_mask.insert(u, v, data);
+                        } else {
+                            greedy_mask.insert((u, v), data);
+                        }
+                        continue;
+                    }
 
-                        greedy_mask.insert(
-                            (u, v),
-                            FaceData {
-                                key,
-                                uv_range,
-                                is_see_through,
-                                is_fluid,
-                                emissive_bits: ao_or_emissive_bits(0, face.emissive),
-                            },
-                        );
+                    if !is_dirty_voxel {
                         continue;
                     }
 
@@ -493,7 +578,20 @@ pub fn mesh_space_greedy<S: VoxelAccess>(
                     processed_non_greedy.insert((vx, vy, vz));
 
                     for (face, world_space) in faces.iter() {
-                        let uv_range = face.range.clone();
+                        let mut face = face.clone();
+                        let mut uv_range = face.range.clone();
+                        if !is_fluid {
+                            apply_texture_rules(
+                                &block.texture_rules,
+                                &mut face,
+                                &mut uv_range,
+                                vx,
+                                vy,
+                                vz,
+                                voxel_id,
+                                space,
+                            );
+                        }
                         non_greedy_faces.push((
                             vx,
                             vy,
@@ -501,7 +599,7 @@ pub fn mesh_space_greedy<S: VoxelAccess>(
                             voxel_id,
                             rotation.clone(),
                             block.clone(),
-                            face.clone(),
+                            face,
                             uv_range,
                             is_see_through,
                             is_fluid,
@@ -512,8 +610,11 @@ pub fn mesh_space_greedy<S: VoxelAccess>(
                 }
             }
 
-            let quads =
-                extract_greedy_quads(&mut greedy_mask, u_range.0, u_range.1, v_range.0, v_range.1);
+            let quads = if occupancy.is_some() {
+                dense_mask.extract_quads()
+            } else {
+                extract_greedy_quads(&mut greedy_mask, u_range.0, u_range.1, v_range.0, v_range.1)
+            };
 
             for quad in quads {
                 let block = match registry.get_block_by_id(quad.data.key.block_id) {
@@ -533,13 +634,22 @@ pub fn mesh_space_greedy<S: VoxelAccess>(
                 let geometry = map.entry(geo_key).or_insert_with(|| {
                     let mut g = GeometryProtocol::default();
                     g.voxel = quad.data.key.block_id;
+                    g.layer = block.render_layer();
                     if quad.data.key.independent {
                         g.face_name = Some(quad.data.key.face_name.clone());
                     }
                     g
                 });
 
+                let start = geometry.positions.len() / 3;
                 process_greedy_quad(&quad, axis, slice, dir, min, block, geometry);
+                geometry.record_span(
+                    MeshSource::Plane {
+                        axis: axis as u8,
+                        plane,
+                    },
+                    start,
+                );
             }
 
             for (
@@ -574,6 +684,7 @@ pub fn mesh_space_greedy<S: VoxelAccess>(
                 let geometry = map.entry(geo_key).or_insert_with(|| {
                     let mut g = GeometryProtocol::default();
                     g.voxel = voxel_id;
+                    g.layer = block.render_layer();
                     if face.independent || face.isolated {
                         g.face_name = Some(face.name.clone());
                     }
@@ -586,6 +697,7 @@ pub fn mesh_space_greedy<S: VoxelAccess>(
                 let mut uv_map = HashMap::new();
                 uv_map.insert(face.name.clone(), uv_range);
 
+                let start = geometry.positions.len() / 3;
                 let neighbors = NeighborCache::populate(vx, vy, vz, space);
                 process_face(
                     vx,
@@ -608,12 +720,16 @@ pub fn mesh_space_greedy<S: VoxelAccess>(
                     min,
                     world_space,
                 );
+                geometry.record_span(MeshSource::Voxel([vx, vy, vz]), start);
             }
         }
     }
 
-    map.into_iter()
+    let mut geometries: Vec<_> = map
+        .into_iter()
         .map(|(_, geometry)| geometry)
         .filter(|geometry| !geometry.indices.is_empty())
-        .collect()
+        .collect();
+    geometries.sort_by_key(|geometry| geometry.layer);
+    geometries
 }
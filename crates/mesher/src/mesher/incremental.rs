@@ -0,0 +1,215 @@
+//! Incremental remeshing: patching a previous mesh after a few voxels
+//! change instead of meshing the whole range again.
+//!
+//! Every run of vertices a geometry holds remembers what produced it, a
+//! greedy plane or a non-greedy voxel (see [`MeshSpan`]). A change dirties
+//! the planes and voxels whose faces could have read the changed voxel;
+//! only those are meshed again and spliced in place of their old vertices.
+
+use hashbrown::HashSet;
+use serde::{Deserialize, Serialize};
+
+use voxelize_core::VoxelAccess;
+
+use super::greedy::mesh_space_greedy_in;
+use super::*;
+
+/// What produced a run of a geometry's vertices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MeshSource {
+    /// Greedy quads lying in the plane at `plane` across `axis` (0 is x),
+    /// in world coordinates.
+    Plane { axis: u8, plane: i32 },
+    /// The faces of one non-greedy voxel, including the fluid a
+    /// waterlogged voxel holds.
+    Voxel([i32; 3]),
+}
+
+/// `vertices` consecutive vertices of a geometry, all from `source`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshSpan {
+    pub source: MeshSource,
+    pub vertices: u32,
+}
+
+/// The planes and non-greedy voxels a set of changed voxels invalidates.
+pub(super) struct DirtyRegion {
+    pub(super) planes: [HashSet<i32>; 3],
+    pub(super) voxels: HashSet<(i32, i32, i32)>,
+}
+
+impl DirtyRegion {
+    /// Faces read the voxels around their own within one step, so a change
+    /// reaches the planes from one before the voxel to two past it along
+    /// each axis, and the non-greedy voxels next to it. Non-greedy voxels
+    /// also pack where they sit in their fluid column or plant stack, which
+    /// they walk up to [`STACK_MAX`] voxels for, so a change reaches that far
+    /// up and down its own column too.
+    pub(super) fn around(changed: &[[i32; 3]]) -> Self {
+        let mut planes: [HashSet<i32>; 3] = Default::default();
+        let mut voxels = HashSet::new();
+
+        for &[vx, vy, vz] in changed {
+            for (axis, coordinate) in [vx, vy, vz].into_iter().enumerate() {
+                planes[axis].extend(coordinate - 1..=coordinate + 2);
+            }
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        voxels.insert((vx + dx, vy + dy, vz + dz));
+                    }
+                }
+            }
+            let reach = STACK_MAX as i32 - 1;
+            voxels.extend((vy - reach..=vy + reach).map(|y| (vx, y, vz)));
+        }
+
+        Self { planes, voxels }
+    }
+
+    fn contains(&self, source: &MeshSource) -> bool {
+        match *source {
+            MeshSource::Plane { axis, plane } => self.planes[axis as usize].contains(&plane),
+            MeshSource::Voxel([vx, vy, vz]) => self.voxels.contains(&(vx, vy, vz)),
+        }
+    }
+}
+
+impl GeometryProtocol {
+    /// Attribute the vertices appended since vertex `start` to `source`.
+    pub(super) fn record_span(&mut self, source: MeshSource, start: usize) {
+        let vertices = (self.positions.len() / 3 - start) as u32;
+        if vertices == 0 {
+            return;
+        }
+
+        match self.spans.last_mut() {
+            Some(span) if span.source == source => span.vertices += vertices,
+            _ => self.spans.push(MeshSpan { source, vertices }),
+        }
+    }
+
+    /// This geometry without the vertices, and the triangles using them,
+    /// that `dirty` covers. Triangle order is kept, so a sorted translucent
+    /// geometry stays sorted.
+    fn without(&self, dirty: &DirtyRegion) -> Self {
+        let mut kept = Self {
+            voxel: self.voxel,
+            at: self.at,
+            face_name: self.face_name.clone(),
+            layer: self.layer,
+            ..Default::default()
+        };
+        let mut remap = Vec::with_capacity(self.positions.len() / 3);
+
+        let mut start = 0;
+        for span in &self.spans {
+            let end = start + span.vertices as usize;
+            if dirty.contains(&span.source) {
+                remap.resize(end, None);
+            } else {
+                let base = kept.positions.len() / 3;
+                remap.extend((base..base + end - start).map(|index| Some(index as i32)));
+                kept.positions
+                    .extend_from_slice(&self.positions[start * 3..end * 3]);
+                kept.uvs.extend_from_slice(&self.uvs[start * 2..end * 2]);
+                kept.lights.extend_from_slice(&self.lights[start..end]);
+                kept.spans.push(*span);
+            }
+            start = end;
+        }
+
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+            if let [Some(a), Some(b), Some(c)] = [a, b, c].map(|index| remap[index as usize]) {
+                kept.indices.extend([a, b, c]);
+            }
+        }
+
+        kept
+    }
+
+    /// Append `other`'s vertices and triangles to this geometry.
+    fn append(&mut self, other: Self) {
+        let base = (self.positions.len() / 3) as i32;
+        self.positions.extend(other.positions);
+        self.uvs.extend(other.uvs);
+        self.lights.extend(other.lights);
+        self.indices
+            .extend(other.indices.into_iter().map(|index| index + base));
+        self.spans.extend(other.spans);
+    }
+}
+
+/// A patched mesh, and whether the patch changed which faces of the range
+/// can see each other.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemeshOutput {
+    pub output: MeshOutput,
+    pub connectivity_changed: bool,
+}
+
+/// Bring `previous`, the mesh of `min..max`, up to date with `space` after
+/// the voxels at `changed` were edited. Voxels whose light changed count as
+/// changed too. The result matches meshing the range from scratch, up to
+/// the order of geometries and triangles; translucent geometries that
+/// gained faces need sorting again.
+///
+/// Geometries carry their spans only in memory, so a `previous` that went
+/// through serialization is meshed from scratch instead.
+pub fn remesh_voxels<S: VoxelAccess>(
+    previous: &MeshOutput,
+    changed: &[[i32; 3]],
+    min: &[i32; 3],
+    max: &[i32; 3],
+    space: &S,
+    registry: &Registry,
+) -> RemeshOutput {
+    let connectivity = compute_section_connectivity(min, max, space, registry);
+    let connectivity_changed = connectivity != previous.connectivity;
+
+    let is_patchable = previous
+        .geometries
+        .iter()
+        .all(|geometry| !geometry.spans.is_empty() || geometry.indices.is_empty());
+
+    if !is_patchable {
+        return RemeshOutput {
+            output: MeshOutput {
+                geometries: mesh_space_greedy(min, max, space, registry),
+                connectivity,
+            },
+            connectivity_changed,
+        };
+    }
+
+    let dirty = DirtyRegion::around(changed);
+    let mut geometries: Vec<_> = previous
+        .geometries
+        .iter()
+        .map(|geometry| geometry.without(&dirty))
+        .collect();
+
+    for fresh in mesh_space_greedy_in(min, max, space, registry, Some(&dirty), true) {
+        match geometries.iter_mut().find(|geometry| {
+            geometry.voxel == fresh.voxel
+                && geometry.face_name == fresh.face_name
+                && geometry.at == fresh.at
+        }) {
+            Some(geometry) => geometry.append(fresh),
+            None => geometries.push(fresh),
+        }
+    }
+
+    geometries.retain(|geometry| !geometry.indices.is_empty());
+    geometries.sort_by_key(|geometry| geometry.layer);
+
+    RemeshOutput {
+        output: MeshOutput {
+            geometries,
+            connectivity,
+        },
+        connectivity_changed,
+    }
+}
@@ -0,0 +1,61 @@
+//! Ordering of translucent geometry, which blends correctly only when its
+//! triangles are drawn farthest first.
+
+use super::*;
+
+/// Reorder `geometry`'s triangles so the one farthest from `eye` comes
+/// first. `eye` is in the geometry's own space, relative to the meshed
+/// range's minimum corner. Triangles at the same distance keep their order.
+pub fn sort_back_to_front(geometry: &mut GeometryProtocol, eye: [f32; 3]) {
+    let positions = &geometry.positions;
+    let distance = |triangle: &[i32]| -> f32 {
+        let mut center = [0.0; 3];
+        for &index in triangle {
+            let offset = index as usize * 3;
+            for (axis, value) in center.iter_mut().enumerate() {
+                *value += positions[offset + axis] / 3.0;
+            }
+        }
+        center
+            .iter()
+            .zip(eye)
+            .map(|(value, eye)| (value - eye) * (value - eye))
+            .sum()
+    };
+
+    let mut triangles: Vec<(f32, &[i32])> = geometry
+        .indices
+        .chunks_exact(3)
+        .map(|triangle| (distance(triangle), triangle))
+        .collect();
+    triangles.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+    geometry.indices = triangles
+        .into_iter()
+        .flat_map(|(_, triangle)| triangle.iter().copied())
+        .collect();
+}
+
+/// Sort every translucent geometry back to front as seen from the center of
+/// `camera_chunk`, at the vertical middle of the meshed range.
+pub fn sort_translucent_geometries(
+    geometries: &mut [GeometryProtocol],
+    camera_chunk: [i32; 2],
+    min: &[i32; 3],
+    max: &[i32; 3],
+    chunk_size: i32,
+) {
+    let [cx, cz] = camera_chunk;
+    let eye = [
+        ((cx as f32 + 0.5) * chunk_size as f32) - min[0] as f32,
+        (max[1] - min[1]) as f32 / 2.0,
+        ((cz as f32 + 0.5) * chunk_size as f32) - min[2] as f32,
+    ];
+
+    for geometry in geometries
+        .iter_mut()
+        .filter(|geometry| geometry.layer == RenderLayer::Translucent)
+    {
+        sort_back_to_front(geometry, eye);
+    }
+}
@@ -1,27 +1,44 @@
+mod collision;
+mod compact;
 mod connectivity;
+mod export;
 mod faces;
 mod fluid;
 mod greedy;
+mod incremental;
+mod layers;
 mod lighting;
+mod model;
+mod occupancy;
 mod space;
 mod types;
+mod variants;
 mod vertex_light;
 #[cfg(test)]
 mod tests;
 
+pub use collision::*;
+pub use compact::*;
 pub use connectivity::{
     compute_section_connectivity, connectivity_pair_bit, CONNECTIVITY_FACES, CONNECTIVITY_FULL,
     CONNECTIVITY_SEALED,
 };
-pub use greedy::mesh_space_greedy;
+pub use export::*;
+pub use greedy::{mesh_space_greedy, mesh_space_greedy_scalar};
+pub use incremental::{remesh_voxels, MeshSource, MeshSpan, RemeshOutput};
+pub use layers::*;
+pub use model::*;
 pub use types::*;
 pub use vertex_light::*;
 
 use connectivity::*;
 use faces::*;
 use fluid::*;
+use incremental::*;
 use lighting::*;
+use occupancy::*;
 use space::*;
+use variants::*;
 
 
 
@@ -40,10 +57,7 @@ pub fn mesh_chunk_with_registry_chunks(
 ) -> MeshOutput {
     let center_chunk = chunks.get(4).and_then(|c| c.as_ref());
     if center_chunk.is_none() {
-        return MeshOutput {
-            geometries: vec![],
-            connectivity: CONNECTIVITY_FULL,
-        };
+        return empty_mesh();
     }
 
     let center_chunk = center_chunk.unwrap();
@@ -53,16 +67,61 @@ pub fn mesh_chunk_with_registry_chunks(
     ];
 
     if !chunk_range_has_non_empty_voxel(center_chunk, &min, &max, registry) {
-        return MeshOutput {
-            geometries: vec![],
-            connectivity: CONNECTIVITY_FULL,
-        };
+        return empty_mesh();
     }
 
     let space = VoxelSpace::new(chunks, config.chunk_size, center_coords);
+    mesh_section(&min, &max, &space, &config, registry)
+}
+
+/// Mesh `min..max` of a grid of chunks laid out as for [`export_chunks`],
+/// `width` chunks wide. The chunk holding `min` stands in for the center
+/// chunk of a mesh job, so the sub-chunks of a whole batch can be meshed
+/// against one grid instead of a 3x3 slice each.
+pub fn mesh_chunk_grid(
+    chunks: &[Option<ChunkData>],
+    width: usize,
+    min: [i32; 3],
+    max: [i32; 3],
+    config: &MeshConfig,
+    registry: &Registry,
+) -> MeshOutput {
+    let width = width.max(1);
+    let Some(origin) = grid_origin(chunks, width, config.chunk_size) else {
+        return empty_mesh();
+    };
+    let space = VoxelSpace::grid(chunks, config.chunk_size, origin, width as i32);
+
+    let Some(center_chunk) = space.get_chunk(space.map_voxel_to_chunk(min[0], min[2])) else {
+        return empty_mesh();
+    };
+    if !chunk_range_has_non_empty_voxel(center_chunk, &min, &max, registry) {
+        return empty_mesh();
+    }
 
-    let geometries = mesh_space_greedy(&min, &max, &space, registry);
-    let connectivity = compute_section_connectivity(&min, &max, &space, registry);
+    mesh_section(&min, &max, &space, config, registry)
+}
+
+/// Nothing to draw, and nothing blocking sight through the section.
+fn empty_mesh() -> MeshOutput {
+    MeshOutput {
+        geometries: vec![],
+        connectivity: CONNECTIVITY_FULL,
+    }
+}
+
+fn mesh_section(
+    min: &[i32; 3],
+    max: &[i32; 3],
+    space: &VoxelSpace,
+    config: &MeshConfig,
+    registry: &Registry,
+) -> MeshOutput {
+    let mut geometries = mesh_space_greedy(min, max, space, registry);
+    if let Some(camera_chunk) = config.camera_chunk {
+        sort_translucent_geometries(&mut geometries, camera_chunk, min, max, config.chunk_size);
+    }
+    let connectivity = compute_section_connectivity(min, max, space, registry);
 
     MeshOutput {
         geometries,
@@ -0,0 +1,695 @@
+//! Block models: non-cube block shapes described in JSON instead of Rust.
+//!
+//! A model is a list of elements, each a box from `from` to `to` in
+//! sixteenths of a block, optionally rotated about one axis, with a textured
+//! face per side. Models may name a `parent` to inherit its elements and
+//! texture variables, overriding what they redefine:
+//!
+//! ```json
+//! {
+//!   "parent": "block/slab",
+//!   "textures": { "side": "oak planks", "top": "#side" },
+//!   "elements": [{
+//!     "from": [0, 0, 0], "to": [16, 8, 16],
+//!     "faces": {
+//!       "py": { "texture": "#top" },
+//!       "pz": { "texture": "#side", "uv": [0, 8, 16, 16], "rotation": 90 }
+//!     }
+//!   }]
+//! }
+//! ```
+//!
+//! Sides are `px`/`nx`/`py`/`ny`/`pz`/`nz`, or `east`/`west`/`up`/`down`/
+//! `south`/`north`. A face's `uv` is `[u1, v1, u2, v2]` in sixteenths with
+//! `v` running down from the top of the texture, and defaults to the part of
+//! the texture the face covers. Textures resolve through `#variables` to a
+//! name that becomes the face's texture group, so faces sharing a texture
+//! share an atlas slot.
+//!
+//! [`BlockModels::compile`] turns a model into the [`BlockFace`]s and
+//! [`AABB`]s a [`Block`] is made of.
+
+use std::{fs, io, path::Path};
+
+use hashbrown::{HashMap, HashSet};
+use serde::Deserialize;
+use voxelize_core::{BlockFace, CornerData, AABB};
+
+use super::Block;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ModelError {
+    #[error("model i/o failed: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("could not parse model {name}: {source}")]
+    Parse {
+        name: String,
+        source: serde_json::Error,
+    },
+
+    #[error("model {0} does not exist")]
+    Missing(String),
+
+    #[error("model {0} is its own ancestor")]
+    ParentCycle(String),
+
+    #[error("model {model} never defines texture {texture}")]
+    UnresolvedTexture { model: String, texture: String },
+
+    #[error("model {model} rotates a face by {rotation} degrees, not a multiple of 90")]
+    FaceRotation { model: String, rotation: i32 },
+}
+
+/// A side of an element, named the way block faces are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum ModelSide {
+    #[serde(rename = "px", alias = "east")]
+    PX,
+    #[serde(rename = "nx", alias = "west")]
+    NX,
+    #[serde(rename = "py", alias = "up")]
+    PY,
+    #[serde(rename = "ny", alias = "down")]
+    NY,
+    #[serde(rename = "pz", alias = "south")]
+    PZ,
+    #[serde(rename = "nz", alias = "north")]
+    NZ,
+}
+
+impl ModelSide {
+    pub const ALL: [ModelSide; 6] = [
+        ModelSide::PX,
+        ModelSide::PY,
+        ModelSide::PZ,
+        ModelSide::NX,
+        ModelSide::NY,
+        ModelSide::NZ,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModelSide::PX => "px",
+            ModelSide::NX => "nx",
+            ModelSide::PY => "py",
+            ModelSide::NY => "ny",
+            ModelSide::PZ => "pz",
+            ModelSide::NZ => "nz",
+        }
+    }
+
+    pub fn dir(&self) -> [i32; 3] {
+        match self {
+            ModelSide::PX => [1, 0, 0],
+            ModelSide::NX => [-1, 0, 0],
+            ModelSide::PY => [0, 1, 0],
+            ModelSide::NY => [0, -1, 0],
+            ModelSide::PZ => [0, 0, 1],
+            ModelSide::NZ => [0, 0, -1],
+        }
+    }
+
+    /// The corners of this side of a unit cube, with their texture
+    /// coordinates, in the order and winding `BlockFaces::six_faces` uses.
+    fn corners(&self) -> [([f32; 3], [f32; 2]); 4] {
+        match self {
+            ModelSide::PX => [
+                ([1.0, 1.0, 1.0], [0.0, 1.0]),
+                ([1.0, 0.0, 1.0], [0.0, 0.0]),
+                ([1.0, 1.0, 0.0], [1.0, 1.0]),
+                ([1.0, 0.0, 0.0], [1.0, 0.0]),
+            ],
+            ModelSide::NX => [
+                ([0.0, 1.0, 0.0], [0.0, 1.0]),
+                ([0.0, 0.0, 0.0], [0.0, 0.0]),
+                ([0.0, 1.0, 1.0], [1.0, 1.0]),
+                ([0.0, 0.0, 1.0], [1.0, 0.0]),
+            ],
+            ModelSide::PY => [
+                ([0.0, 1.0, 1.0], [1.0, 1.0]),
+                ([1.0, 1.0, 1.0], [0.0, 1.0]),
+                ([0.0, 1.0, 0.0], [1.0, 0.0]),
+                ([1.0, 1.0, 0.0], [0.0, 0.0]),
+            ],
+            ModelSide::NY => [
+                ([1.0, 0.0, 1.0], [1.0, 0.0]),
+                ([0.0, 0.0, 1.0], [0.0, 0.0]),
+                ([1.0, 0.0, 0.0], [1.0, 1.0]),
+                ([0.0, 0.0, 0.0], [0.0, 1.0]),
+            ],
+            ModelSide::PZ => [
+                ([0.0, 0.0, 1.0], [0.0, 0.0]),
+                ([1.0, 0.0, 1.0], [1.0, 0.0]),
+                ([0.0, 1.0, 1.0], [0.0, 1.0]),
+                ([1.0, 1.0, 1.0], [1.0, 1.0]),
+            ],
+            ModelSide::NZ => [
+                ([1.0, 0.0, 0.0], [0.0, 0.0]),
+                ([0.0, 0.0, 0.0], [1.0, 0.0]),
+                ([1.0, 1.0, 0.0], [0.0, 1.0]),
+                ([0.0, 1.0, 0.0], [1.0, 1.0]),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelAxis {
+    X,
+    Y,
+    Z,
+}
+
+/// A rotation of an element about one axis through `origin`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ElementRotation {
+    #[serde(default = "block_center")]
+    pub origin: [f32; 3],
+    pub axis: ModelAxis,
+    /// Degrees, counter-clockwise looking down the axis.
+    pub angle: f32,
+    /// Stretch the element across the two other axes so a 45 degree
+    /// rotation still spans the whole block, as cross-shaped plants want.
+    #[serde(default)]
+    pub rescale: bool,
+}
+
+fn block_center() -> [f32; 3] {
+    [8.0, 8.0, 8.0]
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ElementFace {
+    /// `#variable` or a texture name.
+    pub texture: String,
+    pub uv: Option<[f32; 4]>,
+    /// Degrees, clockwise, in steps of 90.
+    #[serde(default)]
+    pub rotation: i32,
+    #[serde(default)]
+    pub independent: bool,
+    #[serde(default)]
+    pub isolated: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModelElement {
+    /// Prefixes this element's face names as `<name>_<side>`, so they can be
+    /// told apart from other elements' faces. Faces of unnamed elements are
+    /// named by their side alone, the canonical `py` and friends the
+    /// client's per-face texture calls address.
+    pub name: Option<String>,
+    pub from: [f32; 3],
+    pub to: [f32; 3],
+    pub rotation: Option<ElementRotation>,
+    #[serde(default)]
+    pub faces: HashMap<ModelSide, ElementFace>,
+    /// Whether the element is part of the block's collision boxes.
+    #[serde(default = "default_collision")]
+    pub collision: bool,
+}
+
+fn default_collision() -> bool {
+    true
+}
+
+/// One model file, before its parents are resolved.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BlockModel {
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub textures: HashMap<String, String>,
+    pub elements: Option<Vec<ModelElement>>,
+}
+
+/// The faces and collision boxes a model compiles to.
+#[derive(Debug, Clone, Default)]
+pub struct ModelGeometry {
+    pub faces: Vec<BlockFace>,
+    pub aabbs: Vec<AABB>,
+}
+
+/// A set of models that may inherit from each other, by name.
+#[derive(Debug, Clone, Default)]
+pub struct BlockModels {
+    models: HashMap<String, BlockModel>,
+}
+
+impl BlockModels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load every `.json` file under `directory`, named by its path relative
+    /// to it without the extension, such as `block/stairs`.
+    pub fn load(directory: impl AsRef<Path>) -> Result<Self, ModelError> {
+        let mut models = Self::new();
+        models.load_dir(directory.as_ref(), "")?;
+        Ok(models)
+    }
+
+    fn load_dir(&mut self, directory: &Path, prefix: &str) -> Result<(), ModelError> {
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let name = format!("{}{}", prefix, stem);
+
+            if path.is_dir() {
+                self.load_dir(&path, &format!("{}/", name))?;
+            } else if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                self.parse(&name, &fs::read_to_string(&path)?)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add a model from its JSON.
+    pub fn parse(&mut self, name: &str, json: &str) -> Result<(), ModelError> {
+        let model = serde_json::from_str(json).map_err(|source| ModelError::Parse {
+            name: name.to_owned(),
+            source,
+        })?;
+        self.insert(name, model);
+        Ok(())
+    }
+
+    pub fn insert(&mut self, name: &str, model: BlockModel) {
+        self.models.insert(name.to_owned(), model);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BlockModel> {
+        self.models.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.models.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+
+    /// Resolve a model's parents and compile its elements into block faces
+    /// and collision boxes, in block units.
+    pub fn compile(&self, name: &str) -> Result<ModelGeometry, ModelError> {
+        let (textures, elements) = self.resolve(name)?;
+        let mut geometry = ModelGeometry::default();
+
+        for element in &elements {
+            for side in ModelSide::ALL {
+                let Some(face) = element.faces.get(&side) else {
+                    continue;
+                };
+
+                let texture = resolve_texture(name, &face.texture, &textures)?;
+                if face.rotation % 90 != 0 {
+                    return Err(ModelError::FaceRotation {
+                        model: name.to_owned(),
+                        rotation: face.rotation,
+                    });
+                }
+
+                let face_name = match &element.name {
+                    Some(prefix) => format!("{}_{}", prefix, side.name()),
+                    None => side.name().to_owned(),
+                };
+                geometry
+                    .faces
+                    .push(compile_face(&face_name, side, element, face, texture));
+            }
+
+            if element.collision {
+                geometry.aabbs.push(element_bounds(element));
+            }
+        }
+
+        Ok(geometry)
+    }
+
+    /// The texture variables and elements of a model with its ancestors'
+    /// merged in; the closest definition wins.
+    fn resolve(
+        &self,
+        name: &str,
+    ) -> Result<(HashMap<String, String>, Vec<ModelElement>), ModelError> {
+        let mut textures = HashMap::new();
+        let mut elements = None;
+        let mut visited = HashSet::new();
+        let mut current = Some(name.to_owned());
+
+        while let Some(model_name) = current {
+            if !visited.insert(model_name.clone()) {
+                return Err(ModelError::ParentCycle(name.to_owned()));
+            }
+
+            let model = self
+                .models
+                .get(&model_name)
+                .ok_or_else(|| ModelError::Missing(model_name.clone()))?;
+
+            for (variable, texture) in &model.textures {
+                textures
+                    .entry(variable.clone())
+                    .or_insert_with(|| texture.clone());
+            }
+            if elements.is_none() {
+                elements = model.elements.clone();
+            }
+
+            current = model.parent.clone();
+        }
+
+        Ok((textures, elements.unwrap_or_default()))
+    }
+}
+
+/// Follow `#variable` references to a texture name.
+fn resolve_texture(
+    model: &str,
+    texture: &str,
+    textures: &HashMap<String, String>,
+) -> Result<String, ModelError> {
+    let mut texture = texture;
+
+    for _ in 0..=textures.len() {
+        let Some(variable) = texture.strip_prefix('#') else {
+            return Ok(texture.to_owned());
+        };
+
+        texture = textures
+            .get(variable)
+            .ok_or_else(|| ModelError::UnresolvedTexture {
+                model: model.to_owned(),
+                texture: variable.to_owned(),
+            })?;
+    }
+
+    Err(ModelError::UnresolvedTexture {
+        model: model.to_owned(),
+        texture: texture.to_owned(),
+    })
+}
+
+fn compile_face(
+    name: &str,
+    side: ModelSide,
+    element: &ModelElement,
+    face: &ElementFace,
+    texture: String,
+) -> BlockFace {
+    let template = side.corners();
+    let [u1, v1, u2, v2] = face.uv.unwrap_or_else(|| default_uv(&template, element));
+    let turns = face.rotation.rem_euclid(360) / 90;
+
+    let corners = template.map(|(unit, [fu, fv])| {
+        let pos = [0, 1, 2].map(|axis| {
+            let from = element.from[axis].min(element.to[axis]);
+            let to = element.from[axis].max(element.to[axis]);
+            (from + (to - from) * unit[axis]) / 16.0
+        });
+
+        // Turn the texture clockwise under the face a quarter at a time.
+        let (mut tu, mut tv) = (fu, fv);
+        for _ in 0..turns {
+            (tu, tv) = (1.0 - tv, tu);
+        }
+
+        CornerData {
+            pos: rotate_point(pos, element.rotation.as_ref()),
+            uv: [
+                (u1 + (u2 - u1) * tu) / 16.0,
+                1.0 - (v1 + (v2 - v1) * (1.0 - tv)) / 16.0,
+            ],
+        }
+    });
+
+    let mut block_face = BlockFace::new(
+        name.to_owned(),
+        face.independent,
+        face.isolated,
+        rotate_dir(side.dir(), element.rotation.as_ref()),
+        corners,
+    );
+    block_face.texture_group = Some(texture);
+    block_face
+}
+
+/// The part of the texture a face covers: the texture laid over the whole
+/// side of the block, cut to the element.
+fn default_uv(template: &[([f32; 3], [f32; 2]); 4], element: &ModelElement) -> [f32; 4] {
+    let span = |coordinate: usize| {
+        (0..3)
+            .find_map(|axis| {
+                let from = element.from[axis].min(element.to[axis]);
+                let to = element.from[axis].max(element.to[axis]);
+                if template
+                    .iter()
+                    .all(|(unit, uv)| unit[axis] == uv[coordinate])
+                {
+                    Some((from, to))
+                } else if template
+                    .iter()
+                    .all(|(unit, uv)| unit[axis] == 1.0 - uv[coordinate])
+                {
+                    Some((16.0 - to, 16.0 - from))
+                } else {
+                    None
+                }
+            })
+            .unwrap_or((0.0, 16.0))
+    };
+
+    let (u1, u2) = span(0);
+    let (v_bottom, v_top) = span(1);
+    [u1, 16.0 - v_top, u2, 16.0 - v_bottom]
+}
+
+fn rotation_parts(rotation: &ElementRotation) -> (usize, usize, usize, f32, f32) {
+    let (axis, a, b) = match rotation.axis {
+        ModelAxis::X => (0, 1, 2),
+        ModelAxis::Y => (1, 2, 0),
+        ModelAxis::Z => (2, 0, 1),
+    };
+    let radians = rotation.angle.to_radians();
+    (axis, a, b, radians.cos(), radians.sin())
+}
+
+fn rotate_point(pos: [f32; 3], rotation: Option<&ElementRotation>) -> [f32; 3] {
+    let Some(rotation) = rotation else {
+        return pos;
+    };
+
+    let (_, a, b, cos, sin) = rotation_parts(rotation);
+    let origin = rotation.origin.map(|coordinate| coordinate / 16.0);
+    let scale = if rotation.rescale && cos.abs() > f32::EPSILON {
+        1.0 / cos.abs()
+    } else {
+        1.0
+    };
+
+    let (da, db) = (pos[a] - origin[a], pos[b] - origin[b]);
+    let mut rotated = pos;
+    rotated[a] = origin[a] + (da * cos - db * sin) * scale;
+    rotated[b] = origin[b] + (da * sin + db * cos) * scale;
+    rotated
+}
+
+/// A side's direction once its element is rotated: the nearest axis, or
+/// none at all for a face turned a full 45 degrees, which the mesher then
+/// treats as a diagonal.
+fn rotate_dir(dir: [i32; 3], rotation: Option<&ElementRotation>) -> [i32; 3] {
+    let Some(rotation) = rotation else {
+        return dir;
+    };
+
+    let (_, a, b, cos, sin) = rotation_parts(rotation);
+    let mut normal = dir.map(|component| component as f32);
+    let (na, nb) = (normal[a], normal[b]);
+    normal[a] = na * cos - nb * sin;
+    normal[b] = na * sin + nb * cos;
+
+    if (normal[a].abs() - normal[b].abs()).abs() < 1e-4 && normal[a].abs() > 1e-4 {
+        return [0, 0, 0];
+    }
+    normal.map(|component| component.round() as i32)
+}
+
+/// The axis-aligned box around an element, in block units.
+fn element_bounds(element: &ModelElement) -> AABB {
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+
+    for x in [element.from[0], element.to[0]] {
+        for y in [element.from[1], element.to[1]] {
+            for z in [element.from[2], element.to[2]] {
+                let corner =
+                    rotate_point([x / 16.0, y / 16.0, z / 16.0], element.rotation.as_ref());
+                for axis in 0..3 {
+                    min[axis] = min[axis].min(corner[axis]);
+                    max[axis] = max[axis].max(corner[axis]);
+                }
+            }
+        }
+    }
+
+    AABB {
+        min_x: min[0],
+        min_y: min[1],
+        min_z: min[2],
+        max_x: max[0],
+        max_y: max[1],
+        max_z: max[2],
+    }
+}
+
+impl Block {
+    /// Give this block a compiled model's faces and collision boxes.
+    pub fn set_model(&mut self, model: &ModelGeometry) {
+        self.faces = model.faces.clone();
+        self.aabbs = model.aabbs.clone();
+        self.compute_name_lower();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn models() -> BlockModels {
+        let mut models = BlockModels::new();
+        models
+            .parse(
+                "block/cube",
+                r##"{
+                    "elements": [{
+                        "from": [0, 0, 0], "to": [16, 16, 16],
+                        "faces": {
+                            "east": { "texture": "#side" }, "west": { "texture": "#side" },
+                            "up": { "texture": "#top" }, "down": { "texture": "#top" },
+                            "south": { "texture": "#side" }, "north": { "texture": "#side" }
+                        }
+                    }]
+                }"##,
+            )
+            .unwrap();
+        models
+            .parse(
+                "stone",
+                r##"{ "parent": "block/cube", "textures": { "side": "stone", "top": "#side" } }"##,
+            )
+            .unwrap();
+        models
+            .parse(
+                "slab",
+                r##"{
+                    "textures": { "all": "planks" },
+                    "elements": [{
+                        "name": "slab",
+                        "from": [0, 0, 0], "to": [16, 8, 16],
+                        "faces": {
+                            "pz": { "texture": "#all" },
+                            "nz": { "texture": "#all", "uv": [0, 0, 16, 8], "rotation": 180 }
+                        }
+                    }]
+                }"##,
+            )
+            .unwrap();
+        models
+    }
+
+    #[test]
+    fn full_cubes_match_the_built_in_six_faces() {
+        let geometry = models().compile("stone").unwrap();
+
+        assert_eq!(geometry.faces.len(), 6);
+        assert_eq!(geometry.aabbs.len(), 1);
+        assert!(geometry
+            .faces
+            .iter()
+            .all(|face| face.texture_group.as_deref() == Some("stone")));
+
+        for (face, side) in geometry.faces.iter().zip(ModelSide::ALL) {
+            assert_eq!(face.name, side.name());
+            assert_eq!(face.dir, side.dir());
+            for (corner, (pos, uv)) in face.corners.iter().zip(side.corners()) {
+                assert_eq!(corner.pos, pos);
+                assert_eq!(corner.uv, uv);
+            }
+        }
+    }
+
+    #[test]
+    fn partial_elements_cut_the_texture_and_rotate_it() {
+        let geometry = models().compile("slab").unwrap();
+        let aabb = &geometry.aabbs[0];
+        assert_eq!((aabb.max_y, aabb.max_z), (0.5, 1.0));
+
+        // The side of a bottom slab shows the bottom half of the texture.
+        let pz = &geometry.faces[0];
+        assert_eq!(pz.name, "slab_pz");
+        assert_eq!(pz.corners[0].uv, [0.0, 0.0]);
+        assert_eq!(pz.corners[3].uv, [1.0, 0.5]);
+        assert_eq!(pz.corners[3].pos, [1.0, 0.5, 1.0]);
+
+        // An explicit top-half uv, turned half way round.
+        let nz = &geometry.faces[1];
+        assert_eq!(nz.corners[0].uv, [1.0, 1.0]);
+        assert_eq!(nz.corners[3].uv, [0.0, 0.5]);
+    }
+
+    #[test]
+    fn rotated_elements_become_diagonal_faces() {
+        let mut models = BlockModels::new();
+        models
+            .parse(
+                "cross",
+                r##"{
+                    "elements": [{
+                        "from": [0.8, 0, 8], "to": [15.2, 16, 8],
+                        "rotation": { "axis": "y", "angle": 45, "rescale": true },
+                        "collision": false,
+                        "faces": { "pz": { "texture": "grass" } }
+                    }]
+                }"##,
+            )
+            .unwrap();
+
+        let geometry = models.compile("cross").unwrap();
+        assert!(geometry.aabbs.is_empty());
+        assert_eq!(geometry.faces[0].dir, [0, 0, 0]);
+    }
+
+    #[test]
+    fn broken_models_are_reported() {
+        let mut models = models();
+        models
+            .parse("loop", r##"{ "parent": "loop", "textures": {} }"##)
+            .unwrap();
+        models
+            .parse("bare", r##"{ "parent": "block/cube" }"##)
+            .unwrap();
+
+        assert!(matches!(
+            models.compile("loop"),
+            Err(ModelError::ParentCycle(_))
+        ));
+        assert!(matches!(
+            models.compile("bare"),
+            Err(ModelError::UnresolvedTexture { .. })
+        ));
+        assert!(matches!(
+            models.compile("nope"),
+            Err(ModelError::Missing(_))
+        ));
+        assert!(matches!(
+            models.parse("bad", "{ \"elements\": 3 }"),
+            Err(ModelError::Parse { .. })
+        ));
+    }
+}
@@ -0,0 +1,305 @@
+//! Occupancy bitmasks for the greedy mesher's fast path.
+//!
+//! Terrain is mostly full-cube opaque blocks buried in other full-cube
+//! opaque blocks. Rather than asking the space about every neighbor of every
+//! voxel once per direction, the fast path reads the range once into bit
+//! columns along y, one bit per voxel, and works out which faces of those
+//! plain cubes are visible with a few shifts and masks per column. Voxels
+//! that aren't plain cubes (fluids, see-through, rotated, dynamic or
+//! waterlogged ones) are left to the per-voxel path.
+
+use voxelize_core::{BlockRotation, VoxelAccess};
+
+use super::*;
+
+/// What the fast path knows about one face of one voxel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum FastFace {
+    /// A plain cube whose face is covered.
+    Hidden,
+    /// A plain cube whose face is drawn.
+    Visible,
+    /// Not a plain cube; the per-voxel path decides.
+    Fallback,
+}
+
+/// Bit columns along y over a range padded by one voxel on every side, so
+/// each voxel in the range has its six neighbors at hand.
+pub(super) struct Occupancy {
+    /// The padded range's minimum corner.
+    origin: [i32; 3],
+    /// Padded extent along x and z, in columns.
+    size: [usize; 2],
+    /// `u64` words per column.
+    words: usize,
+    /// Plain cubes in the range.
+    plain: Vec<u64>,
+    /// Per direction, in [`VOXEL_NEIGHBORS`] order: plain cubes whose face
+    /// that way is drawn.
+    visible: [Vec<u64>; 6],
+}
+
+/// A block the fast path can mesh: an opaque, unrotated full cube with no
+/// per-voxel faces or textures.
+fn is_plain_cube(block: &Block) -> bool {
+    block.is_opaque
+        && !block.is_see_through
+        && !block.is_empty
+        && can_greedy_mesh_block(block, &BlockRotation::PY(0.0))
+}
+
+impl Occupancy {
+    pub(super) fn build<S: VoxelAccess>(
+        min: &[i32; 3],
+        max: &[i32; 3],
+        space: &S,
+        registry: &Registry,
+    ) -> Self {
+        let origin = [min[0] - 1, min[1] - 1, min[2] - 1];
+        let [size_x, size_y, size_z] =
+            [0, 1, 2].map(|axis| (max[axis] - min[axis] + 2).max(0) as usize);
+        let words = size_y.div_ceil(64).max(1);
+        let len = size_x * size_z * words;
+
+        let mut plain = vec![0u64; len];
+        // Opaque by id, as the per-voxel path's buried-voxel check sees it.
+        let mut opaque = vec![0u64; len];
+        // Neighbors that cover a plain cube's face: known opaque blocks and
+        // unknown ids, except past the edge of the space.
+        let mut covering = vec![0u64; len];
+
+        for x in 0..size_x {
+            for z in 0..size_z {
+                let column = (x * size_z + z) * words;
+                let is_inner_column = x > 0 && x + 1 < size_x && z > 0 && z + 1 < size_z;
+
+                for y in 0..size_y {
+                    let [vx, vy, vz] = [
+                        origin[0] + x as i32,
+                        origin[1] + y as i32,
+                        origin[2] + z as i32,
+                    ];
+                    let word = column + y / 64;
+                    let bit = 1u64 << (y % 64);
+
+                    let block = registry.get_block_by_id(space.get_voxel(vx, vy, vz));
+                    let is_opaque = block.is_some_and(|block| block.is_opaque);
+                    if is_opaque {
+                        opaque[word] |= bit;
+                    }
+                    if space.contains(vx, vy, vz) && (block.is_none() || is_opaque) {
+                        covering[word] |= bit;
+                    }
+
+                    let is_inner = is_inner_column && y > 0 && y + 1 < size_y;
+                    if is_inner
+                        && block.is_some_and(is_plain_cube)
+                        && !space.get_voxel_waterlogged(vx, vy, vz)
+                        && matches!(
+                            space.get_voxel_rotation(vx, vy, vz),
+                            BlockRotation::PY(rotation) if rotation == 0.0
+                        )
+                    {
+                        plain[word] |= bit;
+                    }
+                }
+            }
+        }
+
+        let mut visible: [Vec<u64>; 6] = Default::default();
+        for faces in visible.iter_mut() {
+            faces.resize(len, 0);
+        }
+
+        let column_at = |x: usize, z: usize| (x * size_z + z) * words;
+        for x in 1..size_x.saturating_sub(1) {
+            for z in 1..size_z.saturating_sub(1) {
+                let column = column_at(x, z);
+                let sides = [
+                    column_at(x + 1, z),
+                    column_at(x - 1, z),
+                    column_at(x, z + 1),
+                    column_at(x, z - 1),
+                ];
+                let own_covering = &covering[column..column + words];
+                let own_opaque = &opaque[column..column + words];
+
+                for word in 0..words {
+                    let plain = plain[column + word];
+                    if plain == 0 {
+                        continue;
+                    }
+
+                    let buried = sides
+                        .iter()
+                        .fold(u64::MAX, |buried, side| buried & opaque[side + word])
+                        & above(own_opaque, word)
+                        & below(own_opaque, word);
+                    let open = plain & !buried;
+
+                    let covered = [
+                        covering[sides[0] + word],
+                        covering[sides[1] + word],
+                        above(own_covering, word),
+                        below(own_covering, word),
+                        covering[sides[2] + word],
+                        covering[sides[3] + word],
+                    ];
+                    for (faces, covered) in visible.iter_mut().zip(covered) {
+                        faces[column + word] = open & !covered;
+                    }
+                }
+            }
+        }
+
+        Self {
+            origin,
+            size: [size_x, size_z],
+            words,
+            plain,
+            visible,
+        }
+    }
+
+    /// The face of the voxel at `vx, vy, vz` facing `VOXEL_NEIGHBORS[direction]`.
+    pub(super) fn face(&self, vx: i32, vy: i32, vz: i32, direction: usize) -> FastFace {
+        let [x, y, z] = [
+            vx - self.origin[0],
+            vy - self.origin[1],
+            vz - self.origin[2],
+        ];
+        if x < 0 || y < 0 || z < 0 {
+            return FastFace::Fallback;
+        }
+        let [x, y, z] = [x as usize, y as usize, z as usize];
+        if x >= self.size[0] || z >= self.size[1] || y >= self.words * 64 {
+            return FastFace::Fallback;
+        }
+
+        let word = (x * self.size[1] + z) * self.words + y / 64;
+        let bit = 1u64 << (y % 64);
+        if self.plain[word] & bit == 0 {
+            FastFace::Fallback
+        } else if self.visible[direction][word] & bit != 0 {
+            FastFace::Visible
+        } else {
+            FastFace::Hidden
+        }
+    }
+}
+
+/// Bit `y` of the result is bit `y + 1` of `column`: the voxel above.
+fn above(column: &[u64], word: usize) -> u64 {
+    (column[word] >> 1) | column.get(word + 1).map_or(0, |next| next << 63)
+}
+
+/// Bit `y` of the result is bit `y - 1` of `column`: the voxel below.
+fn below(column: &[u64], word: usize) -> u64 {
+    (column[word] << 1)
+        | word
+            .checked_sub(1)
+            .map_or(0, |previous| column[previous] >> 63)
+}
+
+/// A slice's greedy mask, dense, with a bit row per `v` marking the cells
+/// that still hold a face, so runs are found by scanning bits.
+#[derive(Default)]
+pub(super) struct GreedyMask {
+    min: [i32; 2],
+    size: [usize; 2],
+    words: usize,
+    rows: Vec<u64>,
+    cells: Vec<Option<FaceData>>,
+}
+
+impl GreedyMask {
+    /// Empty the mask and size it for `min_u..max_u` by `min_v..max_v`.
+    pub(super) fn reset(&mut self, min_u: i32, max_u: i32, min_v: i32, max_v: i32) {
+        self.min = [min_u, min_v];
+        self.size = [
+            (max_u - min_u).max(0) as usize,
+            (max_v - min_v).max(0) as usize,
+        ];
+        self.words = self.size[0].div_ceil(64);
+        self.rows.clear();
+        self.rows.resize(self.words * self.size[1], 0);
+        self.cells.clear();
+        self.cells.resize(self.size[0] * self.size[1], None);
+    }
+
+    pub(super) fn insert(&mut self, u: i32, v: i32, data: FaceData) {
+        let [u, v] = [(u - self.min[0]) as usize, (v - self.min[1]) as usize];
+        self.rows[v * self.words + u / 64] |= 1 << (u % 64);
+        self.cells[v * self.size[0] + u] = Some(data);
+    }
+
+    fn holds(&self, u: usize, v: usize) -> bool {
+        self.rows[v * self.words + u / 64] & (1 << (u % 64)) != 0
+    }
+
+    fn take(&mut self, u: usize, v: usize) -> FaceData {
+        self.rows[v * self.words + u / 64] &= !(1 << (u % 64));
+        self.cells[v * self.size[0] + u].take().unwrap()
+    }
+
+    fn key_at(&self, u: usize, v: usize) -> Option<&FaceKey> {
+        self.holds(u, v)
+            .then(|| &self.cells[v * self.size[0] + u].as_ref().unwrap().key)
+    }
+
+    /// The first cell at or after `u` in row `v` that holds a face.
+    fn next_in_row(&self, u: usize, v: usize) -> Option<usize> {
+        let row = &self.rows[v * self.words..(v + 1) * self.words];
+        let mut word = u / 64;
+        let mut bits = row.get(word)? & (u64::MAX << (u % 64));
+        loop {
+            if bits != 0 {
+                return Some(word * 64 + bits.trailing_zeros() as usize);
+            }
+            word += 1;
+            bits = *row.get(word)?;
+        }
+    }
+
+    /// Merge the mask into quads, as [`extract_greedy_quads`] does: rows in
+    /// order, each run widened along `u` first, then grown along `v` while
+    /// every cell under it matches.
+    pub(super) fn extract_quads(&mut self) -> Vec<GreedyQuad> {
+        let mut quads = Vec::new();
+        let [width_limit, height_limit] = self.size;
+
+        for v in 0..height_limit {
+            let mut from = 0;
+            while let Some(u) = self.next_in_row(from, v) {
+                let data = self.take(u, v);
+
+                let mut width = 1;
+                while u + width < width_limit && self.key_at(u + width, v) == Some(&data.key) {
+                    self.take(u + width, v);
+                    width += 1;
+                }
+
+                let mut height = 1;
+                while v + height < height_limit
+                    && (u..u + width).all(|cell| self.key_at(cell, v + height) == Some(&data.key))
+                {
+                    for cell in u..u + width {
+                        self.take(cell, v + height);
+                    }
+                    height += 1;
+                }
+
+                quads.push(GreedyQuad {
+                    x: self.min[0] + u as i32,
+                    y: self.min[1] + v as i32,
+                    w: width as i32,
+                    h: height as i32,
+                    data,
+                });
+                from = u + width;
+            }
+        }
+
+        quads
+    }
+}
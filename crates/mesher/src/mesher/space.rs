@@ -9,15 +9,36 @@ use super::*;
 pub(super) struct VoxelSpace<'a> {
     chunks: &'a [Option<ChunkData>],
     chunk_size: i32,
-    center_coords: [i32; 2],
+    /// Coordinates of the chunk at index 0 of `chunks`.
+    origin: [i32; 2],
+    /// How many chunks a row of `chunks` spans along x.
+    width: i32,
 }
 
 impl<'a> VoxelSpace<'a> {
+    /// The 3x3 chunks around `center_coords`, as sent with a mesh job.
     pub(super) fn new(chunks: &'a [Option<ChunkData>], chunk_size: i32, center_coords: [i32; 2]) -> Self {
+        Self::grid(
+            chunks,
+            chunk_size,
+            [center_coords[0] - 1, center_coords[1] - 1],
+            3,
+        )
+    }
+
+    /// A grid of chunks `width` wide, laid out row by row along z from
+    /// `origin`, like the 3x3 slice of a mesh job.
+    pub(super) fn grid(
+        chunks: &'a [Option<ChunkData>],
+        chunk_size: i32,
+        origin: [i32; 2],
+        width: i32,
+    ) -> Self {
         Self {
             chunks,
             chunk_size,
-            center_coords,
+            origin,
+            width,
         }
     }
 
@@ -31,12 +52,12 @@ impl<'a> VoxelSpace<'a> {
 
     #[inline]
     pub(super) fn get_chunk(&self, coords: [i32; 2]) -> Option<&ChunkData> {
-        let dx = coords[0] - self.center_coords[0];
-        let dz = coords[1] - self.center_coords[1];
-        if dx < -1 || dx > 1 || dz < -1 || dz > 1 {
+        let dx = coords[0] - self.origin[0];
+        let dz = coords[1] - self.origin[1];
+        if dx < 0 || dx >= self.width || dz < 0 {
             return None;
         }
-        let index = ((dz + 1) * 3 + (dx + 1)) as usize;
+        let index = (dz * self.width + dx) as usize;
         self.chunks.get(index).and_then(|c| c.as_ref())
     }
 
@@ -208,6 +229,24 @@ impl<'a> VoxelAccess for VoxelSpace<'a> {
     }
 }
 
+/// Coordinates of the first chunk of a grid `width` chunks wide, worked out
+/// from the first chunk it holds, or `None` for a grid of missing chunks.
+pub(super) fn grid_origin(
+    chunks: &[Option<ChunkData>],
+    width: usize,
+    chunk_size: i32,
+) -> Option<[i32; 2]> {
+    let (index, first) = chunks
+        .iter()
+        .enumerate()
+        .find_map(|(index, chunk)| chunk.as_ref().map(|chunk| (index, chunk)))?;
+
+    Some([
+        first.min[0].div_euclid(chunk_size) - (index % width) as i32,
+        first.min[2].div_euclid(chunk_size) - (index / width) as i32,
+    ])
+}
+
 pub(super) fn chunk_range_has_non_empty_voxel(
     chunk: &ChunkData,
     min: &[i32; 3],
@@ -1,8 +1,11 @@
 use super::fluid::WATERLOG_FLUID_INSET;
 use super::*;
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 
-use voxelize_core::{BlockFace, BlockRotation, CornerData, LightColor, LightUtils, VoxelAccess, AABB, UV};
+use voxelize_core::{
+    BlockFace, BlockRotation, CornerData, LightColor, LightUtils, TextureVariantRule,
+    VariantTexture, VoxelAccess, AABB, UV,
+};
 
 struct SingleVoxelSpace {
     voxel_id: u32,
@@ -119,6 +122,7 @@ fn full_block_diagonal_block() -> Block {
             max_z: 1.0,
         }],
         dynamic_patterns: None,
+        texture_rules: vec![],
     }
 }
 
@@ -515,6 +519,7 @@ fn upward_stair_face_samples_light_from_opaque_block_above() {
         faces: vec![],
         aabbs: stairs_aabbs(),
         dynamic_patterns: None,
+        texture_rules: vec![],
     };
 
     let stone_block = Block {
@@ -544,6 +549,7 @@ fn upward_stair_face_samples_light_from_opaque_block_above() {
             max_z: 1.0,
         }],
         dynamic_patterns: None,
+        texture_rules: vec![],
     };
 
     let mut registry = Registry::new(vec![(1, stair_block), (2, stone_block)]);
@@ -641,6 +647,7 @@ fn plain_block(id: u32, name: &str) -> Block {
         faces: vec![],
         aabbs: full_cube_aabb(),
         dynamic_patterns: None,
+        texture_rules: vec![],
     }
 }
 
@@ -1202,3 +1209,943 @@ fn greedy_quads_carry_emissive_bits() {
     }
     assert!(packed_lights > 0, "the glowstone meshed nothing");
 }
+
+/// A row of one block along +x from the origin, in an otherwise empty space.
+/// A row of voxels along +x from the origin.
+struct RowSpace {
+    voxels: Vec<u32>,
+}
+
+impl VoxelAccess for RowSpace {
+    fn get_voxel(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        if vx >= 0 && vy == 0 && vz == 0 {
+            self.voxels.get(vx as usize).copied().unwrap_or(0)
+        } else {
+            0
+        }
+    }
+
+    fn get_raw_voxel(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        self.get_voxel(vx, vy, vz)
+    }
+
+    fn get_voxel_rotation(&self, _vx: i32, _vy: i32, _vz: i32) -> BlockRotation {
+        BlockRotation::PY(0.0)
+    }
+
+    fn get_voxel_stage(&self, _vx: i32, _vy: i32, _vz: i32) -> u32 {
+        0
+    }
+
+    fn get_voxel_waterlogged(&self, _vx: i32, _vy: i32, _vz: i32) -> bool {
+        false
+    }
+
+    fn get_voxel_fluid_level(&self, _vx: i32, _vy: i32, _vz: i32) -> u32 {
+        0
+    }
+
+    fn get_sunlight(&self, _vx: i32, _vy: i32, _vz: i32) -> u32 {
+        0
+    }
+
+    fn get_torch_light(&self, _vx: i32, _vy: i32, _vz: i32, _color: LightColor) -> u32 {
+        0
+    }
+
+    fn get_all_lights(&self, _vx: i32, _vy: i32, _vz: i32) -> (u32, u32, u32, u32) {
+        (0, 0, 0, 0)
+    }
+
+    fn get_max_height(&self, _vx: i32, _vz: i32) -> u32 {
+        1
+    }
+
+    fn contains(&self, vx: i32, vy: i32, vz: i32) -> bool {
+        (-1..=self.voxels.len() as i32).contains(&vx)
+            && (-1..=1).contains(&vy)
+            && (-1..=1).contains(&vz)
+    }
+}
+
+/// A variant texture whose range starts at `u`, so tests can tell which was
+/// picked.
+fn variant_at(u: f32) -> VariantTexture {
+    VariantTexture {
+        name: format!("variant {}", u),
+        range: UV {
+            start_u: u,
+            end_u: u,
+            start_v: 0.0,
+            end_v: 1.0,
+        },
+    }
+}
+
+fn face_with_dir(name: &str, dir: [i32; 3]) -> BlockFace {
+    six_faces()
+        .into_iter()
+        .find(|face| face.name == name && face.dir == dir)
+        .map(|mut face| {
+            face.corners = [
+                CornerData {
+                    pos: [0.0; 3],
+                    uv: [0.0, 1.0],
+                },
+                CornerData {
+                    pos: [0.0; 3],
+                    uv: [0.0, 0.0],
+                },
+                CornerData {
+                    pos: [0.0; 3],
+                    uv: [1.0, 1.0],
+                },
+                CornerData {
+                    pos: [0.0; 3],
+                    uv: [1.0, 0.0],
+                },
+            ];
+            face
+        })
+        .unwrap()
+}
+
+#[test]
+fn connected_textures_join_same_block_neighbors_in_the_face_plane() {
+    let rules = vec![TextureVariantRule {
+        connected: (0..16).map(|index| variant_at(index as f32)).collect(),
+        ..Default::default()
+    }];
+    let space = RowSpace {
+        voxels: vec![1; 3],
+    };
+
+    let connection = |name: &str, dir: [i32; 3], vx: i32| {
+        let mut face = face_with_dir(name, dir);
+        let mut range = UV::default();
+        apply_texture_rules(&rules, &mut face, &mut range, vx, 0, 0, 1, &space);
+        range.start_u as usize
+    };
+
+    // Seen from +z the row runs left to right: right is +x.
+    assert_eq!(connection("pz", [0, 0, 1], 0), 2);
+    assert_eq!(connection("pz", [0, 0, 1], 1), 2 | 8);
+    assert_eq!(connection("pz", [0, 0, 1], 2), 8);
+    // Seen from -z, and from above, right is -x.
+    assert_eq!(connection("nz", [0, 0, -1], 0), 8);
+    assert_eq!(connection("py", [0, 1, 0], 2), 2);
+    // Nothing joins the ends of the row.
+    assert_eq!(connection("px", [1, 0, 0], 2), 0);
+}
+
+#[test]
+fn random_variants_and_turns_are_fixed_per_position() {
+    let rules = vec![TextureVariantRule {
+        faces: vec!["py".to_string(), "pz".to_string()],
+        variants: vec![variant_at(1.0), variant_at(2.0)],
+        random_rotation: true,
+        random_mirror: true,
+        ..Default::default()
+    }];
+    let space = SingleVoxelSpace::dry(0);
+
+    let textured = |name: &str, dir: [i32; 3], vx: i32, vz: i32| {
+        let mut face = face_with_dir(name, dir);
+        let mut range = UV::default();
+        apply_texture_rules(&rules, &mut face, &mut range, vx, 0, vz, 1, &space);
+        (
+            range.start_u as usize,
+            face.corners.map(|corner| corner.uv),
+        )
+    };
+
+    let mut picked = [0; 3];
+    let mut top_turns = HashSet::new();
+    let mut side_mirrors = HashSet::new();
+    for vx in 0..16 {
+        for vz in 0..16 {
+            let (choice, top) = textured("py", [0, 1, 0], vx, vz);
+            assert_eq!((choice, top), textured("py", [0, 1, 0], vx, vz));
+            picked[choice] += 1;
+            top_turns.insert(top.map(|uv| uv.map(|c| c as i32)));
+
+            let (_, side) = textured("pz", [0, 0, 1], vx, vz);
+            // Sides are mirrored, never turned: v is untouched.
+            assert_eq!(side.map(|uv| uv[1]), [1.0, 0.0, 1.0, 0.0]);
+            side_mirrors.insert(side[0][0] as i32);
+
+            // Faces the rule doesn't name keep their texture.
+            let (choice, bottom) = textured("ny", [0, -1, 0], vx, vz);
+            assert_eq!(choice, 0);
+            assert_eq!(bottom, face_with_dir("ny", [0, -1, 0]).corners.map(|c| c.uv));
+        }
+    }
+
+    assert!(picked.iter().all(|&count| count > 40), "{:?}", picked);
+    assert_eq!(top_turns.len(), 4);
+    assert_eq!(side_mirrors.len(), 2);
+}
+
+#[test]
+fn blocks_with_texture_rules_are_meshed_face_by_face() {
+    let air = Block {
+        is_empty: true,
+        aabbs: vec![],
+        ..plain_block(0, "Air")
+    };
+    let stone = Block {
+        is_opaque: true,
+        faces: six_faces(),
+        texture_rules: vec![TextureVariantRule {
+            random_rotation: true,
+            ..Default::default()
+        }],
+        ..plain_block(1, "Stone")
+    };
+    assert!(!can_greedy_mesh_block(&stone, &BlockRotation::PY(0.0)));
+
+    let mut registry = Registry::new(vec![(0, air), (1, stone)]);
+    registry.build_cache();
+
+    let space = RowSpace {
+        voxels: vec![1; 4],
+    };
+    let geometries = mesh_space_greedy(&[-1, -1, -1], &[5, 2, 2], &space, &registry);
+    let lights: Vec<_> = geometries.iter().flat_map(|g| g.lights.iter()).collect();
+
+    // Four voxels' worth of top, bottom and side faces, plus the two ends.
+    assert_eq!(lights.len(), (4 * 4 + 2) * 4);
+    assert!(lights.iter().all(|&&packed| packed & GREEDY_BIT == 0));
+}
+
+#[test]
+fn geometries_are_tagged_and_ordered_by_render_layer() {
+    let air = Block {
+        is_empty: true,
+        aabbs: vec![],
+        ..plain_block(0, "Air")
+    };
+    let stone = Block {
+        is_opaque: true,
+        faces: six_faces(),
+        ..plain_block(1, "Stone")
+    };
+    let glass = Block {
+        is_see_through: true,
+        is_transparent: [true; 6],
+        faces: six_faces(),
+        ..plain_block(2, "Glass")
+    };
+    let leaves = Block {
+        is_see_through: true,
+        is_transparent: [true; 6],
+        transparent_standalone: true,
+        faces: six_faces(),
+        ..plain_block(3, "Leaves")
+    };
+    let torch = Block {
+        is_transparent: [true; 6],
+        faces: six_faces(),
+        ..plain_block(4, "Torch")
+    };
+
+    assert_eq!(stone.render_layer(), RenderLayer::Opaque);
+    assert_eq!(glass.render_layer(), RenderLayer::Translucent);
+    assert_eq!(leaves.render_layer(), RenderLayer::Cutout);
+    assert_eq!(torch.render_layer(), RenderLayer::Cutout);
+
+    let mut registry = Registry::new(vec![
+        (0, air),
+        (1, stone),
+        (2, glass),
+        (3, leaves),
+        (4, torch),
+    ]);
+    registry.build_cache();
+
+    let space = RowSpace {
+        voxels: vec![2, 0, 3, 0, 1, 0, 4],
+    };
+    let geometries = mesh_space_greedy(&[-1, -1, -1], &[8, 2, 2], &space, &registry);
+    let tagged: Vec<_> = geometries.iter().map(|g| (g.voxel, g.layer)).collect();
+
+    assert_eq!(tagged.len(), 4);
+    assert_eq!(tagged[0], (1, RenderLayer::Opaque));
+    assert!(tagged[1..3].contains(&(3, RenderLayer::Cutout)));
+    assert!(tagged[1..3].contains(&(4, RenderLayer::Cutout)));
+    assert_eq!(tagged[3], (2, RenderLayer::Translucent));
+}
+
+/// One unit quad facing +z at `z`, as two triangles.
+fn push_quad(geometry: &mut GeometryProtocol, z: f32) {
+    let base = (geometry.positions.len() / 3) as i32;
+    for [x, y] in [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]] {
+        geometry.positions.extend([x, y, z]);
+    }
+    geometry
+        .indices
+        .extend([base, base + 1, base + 2, base + 2, base + 1, base + 3]);
+}
+
+#[test]
+fn translucent_triangles_are_sorted_back_to_front() {
+    let mut translucent = GeometryProtocol {
+        layer: RenderLayer::Translucent,
+        ..Default::default()
+    };
+    for z in [2.0, 9.0, 5.0] {
+        push_quad(&mut translucent, z);
+    }
+    let mut opaque = GeometryProtocol {
+        layer: RenderLayer::Opaque,
+        ..translucent.clone()
+    };
+    let opaque_indices = opaque.indices.clone();
+
+    let mut geometries = vec![opaque, translucent];
+    // The camera chunk's center sits at z = -8, in front of every quad.
+    sort_translucent_geometries(&mut geometries, [0, -1], &[0, 0, 0], &[16, 16, 16], 16);
+    [opaque, translucent] = geometries.try_into().unwrap();
+
+    assert_eq!(opaque.indices, opaque_indices);
+    let depths: Vec<_> = translucent
+        .indices
+        .chunks_exact(3)
+        .map(|triangle| translucent.positions[triangle[0] as usize * 3 + 2])
+        .collect();
+    assert_eq!(depths, [9.0, 9.0, 5.0, 5.0, 2.0, 2.0]);
+
+    // Seen from the far side the order flips.
+    sort_back_to_front(&mut translucent, [0.5, 0.5, 20.0]);
+    let first = translucent.indices[0] as usize;
+    assert_eq!(translucent.positions[first * 3 + 2], 2.0);
+}
+
+/// Sunlight, then red, green and blue torch light.
+type Lights = (u32, u32, u32, u32);
+
+/// Voxels by position; everywhere else is air, dark and dry.
+#[derive(Default)]
+struct MapSpace {
+    voxels: HashMap<(i32, i32, i32), u32>,
+    lights: HashMap<(i32, i32, i32), Lights>,
+    fluid_levels: HashMap<(i32, i32, i32), u32>,
+    waterlogged: HashSet<(i32, i32, i32)>,
+}
+
+impl VoxelAccess for MapSpace {
+    fn get_voxel(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        self.voxels.get(&(vx, vy, vz)).copied().unwrap_or(0)
+    }
+
+    fn get_raw_voxel(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        self.get_voxel(vx, vy, vz)
+    }
+
+    fn get_voxel_rotation(&self, _vx: i32, _vy: i32, _vz: i32) -> BlockRotation {
+        BlockRotation::PY(0.0)
+    }
+
+    fn get_voxel_stage(&self, _vx: i32, _vy: i32, _vz: i32) -> u32 {
+        0
+    }
+
+    fn get_voxel_waterlogged(&self, vx: i32, vy: i32, vz: i32) -> bool {
+        self.waterlogged.contains(&(vx, vy, vz))
+    }
+
+    fn get_voxel_fluid_level(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        self.fluid_levels.get(&(vx, vy, vz)).copied().unwrap_or(0)
+    }
+
+    fn get_sunlight(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        self.get_all_lights(vx, vy, vz).0
+    }
+
+    fn get_torch_light(&self, vx: i32, vy: i32, vz: i32, color: LightColor) -> u32 {
+        let (sunlight, red, green, blue) = self.get_all_lights(vx, vy, vz);
+        match color {
+            LightColor::Sunlight => sunlight,
+            LightColor::Red => red,
+            LightColor::Green => green,
+            LightColor::Blue => blue,
+        }
+    }
+
+    fn get_all_lights(&self, vx: i32, vy: i32, vz: i32) -> (u32, u32, u32, u32) {
+        self.lights.get(&(vx, vy, vz)).copied().unwrap_or_default()
+    }
+
+    fn get_max_height(&self, _vx: i32, _vz: i32) -> u32 {
+        0
+    }
+
+    fn contains(&self, _vx: i32, _vy: i32, _vz: i32) -> bool {
+        true
+    }
+}
+
+/// Air, stone, glass, a stone with random turns that meshes face by face,
+/// water, and a plant that can stand in it.
+fn remesh_registry() -> Registry {
+    let air = Block {
+        is_empty: true,
+        aabbs: vec![],
+        ..plain_block(0, "Air")
+    };
+    let stone = Block {
+        is_opaque: true,
+        faces: six_faces(),
+        ..plain_block(1, "Stone")
+    };
+    let glass = Block {
+        is_see_through: true,
+        is_transparent: [true; 6],
+        faces: six_faces(),
+        ..plain_block(2, "Glass")
+    };
+    let cobble = Block {
+        is_opaque: true,
+        faces: six_faces()
+            .iter()
+            .map(|face| face_with_dir(&face.name, face.dir))
+            .collect(),
+        texture_rules: vec![TextureVariantRule {
+            random_rotation: true,
+            ..Default::default()
+        }],
+        ..plain_block(3, "Cobble")
+    };
+    let water = Block {
+        is_fluid: true,
+        is_waterlogging_fluid: true,
+        is_see_through: true,
+        is_transparent: [true; 6],
+        faces: six_faces(),
+        ..plain_block(4, "Water")
+    };
+    let reed = Block {
+        id: 5,
+        name: "Reed".to_owned(),
+        name_lower: "reed".to_owned(),
+        is_waterloggable: true,
+        stack_group: 1,
+        is_see_through: true,
+        is_transparent: [true; 6],
+        ..full_block_diagonal_block()
+    };
+
+    let mut registry = Registry::new(vec![
+        (0, air),
+        (1, stone),
+        (2, glass),
+        (3, cobble),
+        (4, water),
+        (5, reed),
+    ]);
+    registry.build_cache();
+    registry
+}
+
+type CanonicalVertex = ([u32; 3], [u32; 2], i32);
+type CanonicalGeometry = (u32, Option<String>, RenderLayer, Vec<[CanonicalVertex; 3]>);
+
+/// A mesh with the order of its geometries and triangles factored out.
+fn canonical_mesh(geometries: &[GeometryProtocol]) -> Vec<CanonicalGeometry> {
+    let mut canonical: Vec<_> = geometries
+        .iter()
+        .map(|geometry| {
+            let vertex = |index: i32| {
+                let index = index as usize;
+                let [x, y, z] = [0, 1, 2].map(|axis| geometry.positions[index * 3 + axis]);
+                let [u, v] = [0, 1].map(|axis| geometry.uvs[index * 2 + axis]);
+                (
+                    [x.to_bits(), y.to_bits(), z.to_bits()],
+                    [u.to_bits(), v.to_bits()],
+                    geometry.lights[index],
+                )
+            };
+            let mut triangles: Vec<_> = geometry
+                .indices
+                .chunks_exact(3)
+                .map(|triangle| {
+                    [
+                        vertex(triangle[0]),
+                        vertex(triangle[1]),
+                        vertex(triangle[2]),
+                    ]
+                })
+                .collect();
+            triangles.sort();
+            (
+                geometry.voxel,
+                geometry.face_name.clone(),
+                geometry.layer,
+                triangles,
+            )
+        })
+        .collect();
+    canonical.sort();
+    canonical
+}
+
+/// Meshes `space` over `0..8`, then applies `edit` forty times, checking
+/// each patched mesh against meshing the range from scratch. `edit` makes
+/// its changes and returns the voxels it changed.
+fn assert_remeshing_matches_a_full_remesh(
+    mut space: MapSpace,
+    mut edit: impl FnMut(&mut MapSpace, u32, &mut u32) -> Vec<[i32; 3]>,
+) {
+    let registry = remesh_registry();
+    let (min, max) = ([0, 0, 0], [8, 8, 8]);
+
+    let mut output = MeshOutput {
+        geometries: mesh_space_greedy(&min, &max, &space, &registry),
+        connectivity: compute_section_connectivity(&min, &max, &space, &registry),
+    };
+
+    let mut seed = 7u32;
+    for step in 0..40 {
+        let changed = edit(&mut space, step, &mut seed);
+
+        let patched = remesh_voxels(&output, &changed, &min, &max, &space, &registry);
+        let full = mesh_space_greedy(&min, &max, &space, &registry);
+
+        assert_eq!(
+            canonical_mesh(&patched.output.geometries),
+            canonical_mesh(&full),
+            "step {} changed {:?}",
+            step,
+            changed
+        );
+        assert_eq!(
+            patched.output.connectivity,
+            compute_section_connectivity(&min, &max, &space, &registry)
+        );
+        output = patched.output;
+    }
+}
+
+/// The next pseudo-random position in `0..8` and a value to go with it.
+fn next_random_voxel(seed: &mut u32) -> ([i32; 3], u32) {
+    *seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+    let position = [*seed >> 8, *seed >> 12, *seed >> 16].map(|bits| (bits % 8) as i32);
+    (position, *seed >> 20)
+}
+
+#[test]
+fn incremental_remeshing_matches_a_full_remesh() {
+    let mut space = MapSpace::default();
+    for vx in 0..8 {
+        for vz in 0..8 {
+            let height = 2 + (vx * 3 + vz * 5) % 4;
+            for vy in 0..height {
+                let id = if (vx + vz) % 5 == 0 { 3 } else { 1 };
+                space.voxels.insert((vx, vy, vz), id);
+            }
+        }
+    }
+
+    // Digging, building and swapping blocks, at the range's edges as well
+    // as inside it, sometimes several voxels at once.
+    assert_remeshing_matches_a_full_remesh(space, |space, step, seed| {
+        let mut changed = vec![];
+        for _ in 0..1 + step % 3 {
+            let ([vx, vy, vz], value) = next_random_voxel(seed);
+            space.voxels.insert((vx, vy, vz), value % 4);
+            changed.push([vx, vy, vz]);
+        }
+        changed
+    });
+}
+
+#[test]
+fn incremental_remeshing_matches_a_full_remesh_with_light_and_fluids() {
+    // A stone basin holding water at uneven levels, with reeds standing in
+    // it, lit unevenly from above and by a few colored torches.
+    let mut space = MapSpace::default();
+    for vx in 0..8 {
+        for vz in 0..8 {
+            space.voxels.insert((vx, 0, vz), 1);
+            for vy in 1..4 {
+                let position = (vx, vy, vz);
+                if (vx * 7 + vz * 3 + vy) % 6 == 0 {
+                    space.voxels.insert(position, 5);
+                    space.waterlogged.insert(position);
+                } else {
+                    space.voxels.insert(position, 4);
+                }
+                space
+                    .fluid_levels
+                    .insert(position, ((vx + vz + vy) % 7) as u32);
+            }
+            for vy in 0..8 {
+                let sunlight = ((vx + vz) % 4 + vy * 2).min(15) as u32;
+                let torch = ((vx * vz + vy) % 5) as u32;
+                space
+                    .lights
+                    .insert((vx, vy, vz), (sunlight, torch * 3, torch, 0));
+            }
+        }
+    }
+
+    // Relighting voxels, raising and draining water, waterlogging and
+    // drying reeds, and placing stone and glass into the basin. Voxels
+    // whose light changed are reported as changed, like edited ones.
+    assert_remeshing_matches_a_full_remesh(space, |space, step, seed| {
+        let mut changed = vec![];
+        for _ in 0..1 + step % 3 {
+            let ([vx, vy, vz], value) = next_random_voxel(seed);
+            let position = (vx, vy, vz);
+            match value % 5 {
+                0 => {
+                    space.lights.insert(
+                        position,
+                        (value % 16, (value >> 4) % 16, 0, (value >> 8) % 16),
+                    );
+                }
+                1 => {
+                    space.voxels.insert(position, 4);
+                    space.waterlogged.remove(&position);
+                    space.fluid_levels.insert(position, (value >> 4) % 8);
+                }
+                2 => {
+                    space.voxels.insert(position, 5);
+                    if (value >> 4) % 2 == 0 {
+                        space.waterlogged.insert(position);
+                    } else {
+                        space.waterlogged.remove(&position);
+                    }
+                }
+                3 => {
+                    space.voxels.insert(position, 1 + (value >> 4) % 2);
+                    space.waterlogged.remove(&position);
+                    space.fluid_levels.remove(&position);
+                }
+                _ => {
+                    space.voxels.insert(position, 0);
+                    space.waterlogged.remove(&position);
+                    space.fluid_levels.remove(&position);
+                }
+            }
+            changed.push([vx, vy, vz]);
+        }
+        changed
+    });
+}
+
+#[test]
+fn remeshing_reports_connectivity_changes_and_survives_serialization() {
+    let registry = remesh_registry();
+    let (min, max) = ([0, 0, 0], [4, 4, 4]);
+
+    // A floor with one hole in it.
+    let mut space = MapSpace::default();
+    for vx in 0..4 {
+        for vz in 0..4 {
+            if (vx, vz) != (3, 3) {
+                space.voxels.insert((vx, 1, vz), 1);
+            }
+        }
+    }
+    let output = MeshOutput {
+        geometries: mesh_space_greedy(&min, &max, &space, &registry),
+        connectivity: compute_section_connectivity(&min, &max, &space, &registry),
+    };
+
+    space.voxels.insert((1, 3, 1), 2);
+    let patched = remesh_voxels(&output, &[[1, 3, 1]], &min, &max, &space, &registry);
+    assert!(!patched.connectivity_changed);
+
+    // Plugging the hole seals the floor. The serialized mesh lost its
+    // spans, so it's meshed from scratch, to the same result.
+    space.voxels.insert((3, 1, 3), 1);
+    let serialized: MeshOutput =
+        serde_json::from_str(&serde_json::to_string(&patched.output).unwrap()).unwrap();
+    assert!(serialized
+        .geometries
+        .iter()
+        .all(|geometry| geometry.spans.is_empty()));
+
+    let sealed = remesh_voxels(&serialized, &[[3, 1, 3]], &min, &max, &space, &registry);
+    assert!(sealed.connectivity_changed);
+    assert_eq!(
+        canonical_mesh(&sealed.output.geometries),
+        canonical_mesh(&mesh_space_greedy(&min, &max, &space, &registry))
+    );
+}
+
+#[test]
+fn compact_vertices_round_trip_a_greedy_mesh() {
+    let mut registry = remesh_registry();
+    for (id, block) in registry.blocks_by_id.iter_mut() {
+        for (index, face) in block.faces.iter_mut().enumerate() {
+            face.range = UV {
+                start_u: index as f32 / 8.0,
+                end_u: (index + 1) as f32 / 8.0,
+                start_v: *id as f32 / 4.0,
+                end_v: (*id + 1) as f32 / 4.0,
+            };
+        }
+    }
+    registry.build_cache();
+
+    let (min, max) = ([0, 0, 0], [16, 16, 16]);
+    let mut space = MapSpace::default();
+    for vx in 0..16 {
+        for vz in 0..16 {
+            for vy in 0..4 + (vx * 7 + vz * 3) % 9 {
+                let id = [1, 1, 2, 3][((vx + vy * 3 + vz * 5) % 4) as usize];
+                space.voxels.insert((vx, vy, vz), id);
+            }
+        }
+    }
+
+    let geometries = mesh_space_greedy(&min, &max, &space, &registry);
+    assert!(!geometries.is_empty());
+
+    let (mut float_bytes, mut packed_bytes, mut table_bytes) = (0, 0, 0);
+    for geometry in &geometries {
+        let packed = geometry.to_compact().unwrap();
+        let unpacked = unpack_vertices(&packed).unwrap();
+        table_bytes += 16 * u16::from_le_bytes([packed[2], packed[3]]) as usize;
+
+        assert_eq!(unpacked.indices, geometry.indices);
+        assert_eq!(unpacked.lights, geometry.lights);
+        for (unpacked, original) in unpacked.positions.iter().zip(&geometry.positions) {
+            assert!((unpacked - original).abs() <= 0.5 / 1024.0);
+        }
+        for (unpacked, original) in unpacked.uvs.iter().zip(&geometry.uvs) {
+            assert!((unpacked - original).abs() <= 1e-6);
+        }
+
+        float_bytes += 4
+            * (geometry.positions.len()
+                + geometry.uvs.len()
+                + geometry.indices.len()
+                + geometry.lights.len());
+        packed_bytes += packed.len();
+    }
+
+    // Light and AO keep their full u32 per vertex, so before compression
+    // the payload stays a little under half the float buffers' size.
+    assert!(
+        packed_bytes * 2 < float_bytes,
+        "{} packed bytes against {} float bytes",
+        packed_bytes,
+        float_bytes
+    );
+
+    // Quads would name their rectangle with the same u16 if they indexed
+    // the atlas grid instead, so the tile table is all the local table
+    // costs: one entry per distinct rectangle, well under 1% of the payload.
+    assert!(
+        table_bytes * 100 < packed_bytes,
+        "{} tile table bytes in {} packed bytes",
+        table_bytes,
+        packed_bytes
+    );
+}
+
+#[test]
+fn compact_vertices_keep_reordered_triangles_and_refuse_what_they_cannot_hold() {
+    let mut geometry = GeometryProtocol::default();
+    for z in [2.0, 9.0, 5.0] {
+        push_quad(&mut geometry, z);
+    }
+    geometry.uvs = vec![0.0; 24];
+    geometry.lights = vec![0; 12];
+    sort_back_to_front(&mut geometry, [0.5, 0.5, -8.0]);
+
+    let packed = geometry.to_compact().unwrap();
+    assert_eq!(unpack_vertices(&packed).unwrap().indices, geometry.indices);
+
+    assert!(matches!(
+        unpack_vertices(&packed[..packed.len() - 1]),
+        Err(CompactMeshError::Truncated)
+    ));
+    let mut future = packed.clone();
+    future[0] = COMPACT_MESH_V1 + 1;
+    assert!(matches!(
+        unpack_vertices(&future),
+        Err(CompactMeshError::Version(_))
+    ));
+
+    // Past the quantized range, the geometry has to ship as floats.
+    geometry.positions[2] = 100.0;
+    assert!(geometry.to_compact().is_none());
+}
+
+#[test]
+fn compact_vertices_hold_positions_from_sixteen_below_to_forty_eight_above_the_range() {
+    let quad_at = |z: f32| {
+        let mut geometry = GeometryProtocol::default();
+        push_quad(&mut geometry, z);
+        geometry.uvs = vec![0.0; 8];
+        geometry.lights = vec![0; 4];
+        geometry
+    };
+
+    for z in [-16.0, 0.0, 47.999] {
+        let packed = quad_at(z).to_compact().unwrap();
+        let unpacked = unpack_vertices(&packed).unwrap();
+        assert!((unpacked.positions[2] - z).abs() <= 0.5 / 1024.0, "{}", z);
+    }
+    for z in [-16.001, 48.0] {
+        assert!(quad_at(z).to_compact().is_none(), "{}", z);
+    }
+}
+
+#[test]
+fn bitmask_culling_matches_the_scalar_mesher() {
+    let registry = remesh_registry();
+    let (min, max) = ([0, 0, 0], [16, 80, 16]);
+
+    let mut space = MapSpace::default();
+    for vx in -1..17i32 {
+        for vz in -1..17i32 {
+            let height = 20 + (vx * 13 + vz * 7).rem_euclid(60);
+            for vy in 0..height {
+                let id = match (vx * 5 + vy * 3 + vz * 11).rem_euclid(17) {
+                    0 => 2,
+                    1 => 3,
+                    2 if vy > 10 => 0,
+                    3 => 9,
+                    _ => 1,
+                };
+                space.voxels.insert((vx, vy, vz), id);
+            }
+        }
+    }
+
+    let fast = mesh_space_greedy(&min, &max, &space, &registry);
+    let scalar = mesh_space_greedy_scalar(&min, &max, &space, &registry);
+    assert!(!fast.is_empty());
+    assert_eq!(canonical_mesh(&fast), canonical_mesh(&scalar));
+
+    let (min, max) = ([3, 5, 2], [9, 70, 13]);
+    let fast = mesh_space_greedy(&min, &max, &space, &registry);
+    let scalar = mesh_space_greedy_scalar(&min, &max, &space, &registry);
+    assert_eq!(canonical_mesh(&fast), canonical_mesh(&scalar));
+}
+
+/// A 16x16x16 chunk of uneven stone and glass terrain.
+fn terrain_chunk(cx: i32, cz: i32) -> ChunkData {
+    let (size, height) = (16, 16);
+    let mut voxels = vec![0; size * height * size];
+    for lx in 0..size {
+        for lz in 0..size {
+            let [vx, vz] = [cx * 16 + lx as i32, cz * 16 + lz as i32];
+            for vy in 0..4 + (vx * 7 + vz * 3).rem_euclid(9) {
+                let id = if (vx + vy + vz) % 5 == 0 { 2 } else { 1 };
+                voxels[lx * height * size + vy as usize * size + lz] = id;
+            }
+        }
+    }
+
+    ChunkData {
+        voxels,
+        lights: vec![0; size * height * size],
+        shape: [size, height, size],
+        min: [cx * 16, 0, cz * 16],
+    }
+}
+
+#[test]
+fn a_chunk_grid_meshes_each_section_as_its_own_job_would() {
+    let registry = remesh_registry();
+    let config = MeshConfig {
+        chunk_size: 16,
+        camera_chunk: None,
+    };
+
+    // Four chunks wide and three deep, from chunk (-1, -1), with a hole.
+    let grid: Vec<_> = (-1..2)
+        .flat_map(|cz| (-1..3).map(move |cx| (cx, cz)))
+        .map(|(cx, cz)| ((cx, cz) != (2, 1)).then(|| terrain_chunk(cx, cz)))
+        .collect();
+
+    for (cx, cz) in [(0, 0), (1, 0)] {
+        let job: Vec<_> = (-1..2)
+            .flat_map(|dz| (-1..2).map(move |dx| (cx + dx, cz + dz)))
+            .map(|(x, z)| ((x, z) != (2, 1)).then(|| terrain_chunk(x, z)))
+            .collect();
+        let (min, max) = ([cx * 16, 4, cz * 16], [cx * 16 + 16, 12, cz * 16 + 16]);
+
+        let expected = mesh_chunk_with_registry_chunks(&job, min, max, config.clone(), &registry);
+        let meshed = mesh_chunk_grid(&grid, 4, min, max, &config, &registry);
+
+        assert!(!meshed.geometries.is_empty());
+        assert_eq!(
+            canonical_mesh(&meshed.geometries),
+            canonical_mesh(&expected.geometries)
+        );
+        assert_eq!(meshed.connectivity, expected.connectivity);
+    }
+
+    let outside = mesh_chunk_grid(&grid, 4, [48, 0, 16], [64, 16, 32], &config, &registry);
+    assert!(outside.geometries.is_empty());
+}
+
+#[test]
+fn collision_meshes_merge_cubes_and_keep_other_shapes() {
+    let (min, max) = ([-4, 0, 8], [12, 20, 24]);
+    let collider = |vx: i32, vy: i32, vz: i32| match (vx, vy, vz) {
+        (3, 10, 15) => VoxelCollider::Dynamic,
+        (5, 10, 15) => VoxelCollider::Boxes(vec![AABB::create(0.0, 0.0, 0.0, 1.0, 0.5, 1.0)]),
+        (6, 10, 15) => VoxelCollider::Boxes(vec![]),
+        _ if vy < 4 + (vx * 3 + vz).rem_euclid(5) => VoxelCollider::Cube,
+        _ => VoxelCollider::None,
+    };
+    let mesh = CollisionMesh::build(&min, &max, collider);
+
+    let merged: Vec<_> = mesh
+        .boxes
+        .iter()
+        .filter(|aabb| aabb.max_y - aabb.min_y != 0.5)
+        .collect();
+    let cubes: usize = merged
+        .iter()
+        .map(|aabb| {
+            ((aabb.max_x - aabb.min_x) * (aabb.max_y - aabb.min_y) * (aabb.max_z - aabb.min_z))
+                as usize
+        })
+        .sum();
+
+    let mut expected = 0;
+    for vx in min[0]..max[0] {
+        for vy in min[1]..max[1] {
+            for vz in min[2]..max[2] {
+                let center = AABB::create(
+                    vx as f32 + 0.4,
+                    vy as f32 + 0.4,
+                    vz as f32 + 0.4,
+                    vx as f32 + 0.6,
+                    vy as f32 + 0.6,
+                    vz as f32 + 0.6,
+                );
+                let covering = merged
+                    .iter()
+                    .filter(|aabb| aabb.intersects(&center))
+                    .count();
+                let is_cube = matches!(collider(vx, vy, vz), VoxelCollider::Cube);
+                assert_eq!(covering, is_cube as usize, "cube cover at {vx} {vy} {vz}");
+                assert_eq!(
+                    mesh.is_solid(vx, vy, vz),
+                    !matches!(collider(vx, vy, vz), VoxelCollider::None)
+                );
+                expected += is_cube as usize;
+            }
+        }
+    }
+    assert_eq!(cubes, expected);
+    assert!(merged.len() * 5 < expected);
+
+    let slab = mesh.boxes.last().unwrap();
+    assert_eq!([slab.min_x, slab.min_y, slab.max_y], [5.0, 10.0, 10.5]);
+    assert_eq!(mesh.dynamic, vec![[3, 10, 15]]);
+    assert!(!mesh.is_solid(3, 10, 7));
+
+    let region = AABB::create(5.0, 10.0, 15.0, 6.0, 11.0, 16.0);
+    assert_eq!(mesh.boxes_within(&region).count(), 1);
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 use voxelize_core::{
-    BlockDynamicPattern, BlockFace, LightUtils, VoxelAccess, AABB, UV,
+    BlockDynamicPattern, BlockFace, LightUtils, TextureVariantRule, VoxelAccess, AABB, UV,
 };
 
 use super::*;
@@ -39,6 +39,10 @@ pub struct Block {
     pub faces: Vec<BlockFace>,
     pub aabbs: Vec<AABB>,
     pub dynamic_patterns: Option<Vec<BlockDynamicPattern>>,
+    /// Per-voxel texture variation of this block's faces. Blocks with rules
+    /// are meshed face by face, since no two neighbors can share a quad.
+    #[serde(default)]
+    pub texture_rules: Vec<TextureVariantRule>,
 }
 
 impl Block {
@@ -75,6 +79,26 @@ impl Block {
             &self.name_lower
         }
     }
+
+    /// The pass this block's geometry is drawn in. Fluids and see-through
+    /// blocks that merge with their own kind (glass) blend; see-through
+    /// blocks meshed standalone (leaves) and blocks that only partly fill
+    /// their cell (plants, torches) are alpha-tested.
+    pub fn render_layer(&self) -> RenderLayer {
+        if self.is_fluid {
+            RenderLayer::Translucent
+        } else if self.is_see_through {
+            if self.transparent_standalone {
+                RenderLayer::Cutout
+            } else {
+                RenderLayer::Translucent
+            }
+        } else if self.is_transparent.iter().any(|transparent| *transparent) {
+            RenderLayer::Cutout
+        } else {
+            RenderLayer::Opaque
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -152,27 +176,55 @@ impl Registry {
     }
 }
 
+/// The render pass a geometry belongs to. Opaque geometry writes depth
+/// without blending, cutout geometry discards texels below an alpha
+/// threshold, and translucent geometry blends and is drawn last, back to
+/// front.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RenderLayer {
+    #[default]
+    Opaque = 0,
+    Cutout = 1,
+    Translucent = 2,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GeometryProtocol {
     pub voxel: u32,
     pub at: Option<[i32; 3]>,
     pub face_name: Option<String>,
+    #[serde(default)]
+    pub layer: RenderLayer,
     pub positions: Vec<f32>,
     pub indices: Vec<i32>,
     pub uvs: Vec<f32>,
     pub lights: Vec<i32>,
+    /// Where each run of vertices came from, for [`remesh_voxels`]. Kept in
+    /// memory only.
+    #[serde(skip)]
+    pub spans: Vec<MeshSpan>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MeshConfig {
     pub chunk_size: i32,
+    /// The chunk the camera is in. When set, translucent geometries have
+    /// their triangles ordered back to front as seen from its center.
+    #[serde(default)]
+    pub camera_chunk: Option<[i32; 2]>,
 }
 
 impl Default for MeshConfig {
     fn default() -> Self {
-        Self { chunk_size: 16 }
+        Self {
+            chunk_size: 16,
+            camera_chunk: None,
+        }
     }
 }
 
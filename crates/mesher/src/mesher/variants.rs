@@ -0,0 +1,113 @@
+//! Texture variation: which texture a voxel's face shows, and which way
+//! round, when its block has [`TextureVariantRule`]s.
+//!
+//! Every choice comes from the voxel's position and its neighbors alone, so
+//! the server and the wasm mesher agree on every face.
+
+use voxelize_core::{BlockFace, TextureVariantRule, VoxelAccess, UV};
+
+const VARIANT_SALT: u32 = 0x9e37_79b9;
+const ROTATION_SALT: u32 = 0x85eb_ca6b;
+const MIRROR_SALT: u32 = 0xc2b2_ae35;
+
+/// A well-mixed hash of a voxel position. `salt` keeps the choices made
+/// from it independent of each other.
+pub(super) fn texture_hash(vx: i32, vy: i32, vz: i32, salt: u32) -> u32 {
+    let mut h = (vx as u32).wrapping_mul(73856093)
+        ^ (vy as u32).wrapping_mul(19349663)
+        ^ (vz as u32).wrapping_mul(83492791)
+        ^ salt;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^= h >> 16;
+    h
+}
+
+/// The directions a face's texture runs in, right and then up, matching
+/// the corner layout of `BlockFaces::six_faces` and the greedy quads.
+pub(super) fn texture_axes(dir: [i32; 3]) -> Option<([i32; 3], [i32; 3])> {
+    match dir {
+        [1, 0, 0] => Some(([0, 0, -1], [0, 1, 0])),
+        [-1, 0, 0] => Some(([0, 0, 1], [0, 1, 0])),
+        [0, 1, 0] => Some(([-1, 0, 0], [0, 0, 1])),
+        [0, -1, 0] => Some(([1, 0, 0], [0, 0, -1])),
+        [0, 0, 1] => Some(([1, 0, 0], [0, 1, 0])),
+        [0, 0, -1] => Some(([-1, 0, 0], [0, 1, 0])),
+        _ => None,
+    }
+}
+
+/// The connected-texture index of a face: which neighbors in its plane are
+/// the same block, `1` above, `2` right, `4` below, `8` left.
+pub(super) fn connection_mask<S: VoxelAccess>(
+    vx: i32,
+    vy: i32,
+    vz: i32,
+    voxel_id: u32,
+    dir: [i32; 3],
+    space: &S,
+) -> Option<usize> {
+    let (right, up) = texture_axes(dir)?;
+    let down = up.map(|component| -component);
+    let left = right.map(|component| -component);
+
+    Some(
+        [(up, 1), (right, 2), (down, 4), (left, 8)]
+            .into_iter()
+            .filter(|([dx, dy, dz], _)| space.get_voxel(vx + dx, vy + dy, vz + dz) == voxel_id)
+            .map(|(_, bit)| bit)
+            .sum(),
+    )
+}
+
+/// Apply the first of `rules` covering `face` to the voxel at `vx, vy, vz`:
+/// swap in its texture's range, and turn or mirror its corners' texture
+/// coordinates.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn apply_texture_rules<S: VoxelAccess>(
+    rules: &[TextureVariantRule],
+    face: &mut BlockFace,
+    uv_range: &mut UV,
+    vx: i32,
+    vy: i32,
+    vz: i32,
+    voxel_id: u32,
+    space: &S,
+) {
+    let Some(rule) = rules.iter().find(|rule| rule.applies_to(&face.name)) else {
+        return;
+    };
+
+    if rule.connected.len() == 16 {
+        if let Some(mask) = connection_mask(vx, vy, vz, voxel_id, face.dir, space) {
+            *uv_range = rule.connected[mask].range.clone();
+        }
+    } else if !rule.variants.is_empty() {
+        let choice = texture_hash(vx, vy, vz, VARIANT_SALT) as usize % (rule.variants.len() + 1);
+        if choice > 0 {
+            *uv_range = rule.variants[choice - 1].range.clone();
+        }
+    }
+
+    let [dx, dy, dz] = face.dir;
+    if rule.random_rotation && dx == 0 && dz == 0 && dy != 0 {
+        let turns = texture_hash(vx, vy, vz, ROTATION_SALT) % 4;
+        for corner in face.corners.iter_mut() {
+            for _ in 0..turns {
+                corner.uv = [corner.uv[1], 1.0 - corner.uv[0]];
+            }
+        }
+    }
+
+    if rule.random_mirror
+        && dy == 0
+        && (dx != 0 || dz != 0)
+        && texture_hash(vx, vy, vz, MIRROR_SALT) % 2 == 1
+    {
+        for corner in face.corners.iter_mut() {
+            corner.uv[0] = 1.0 - corner.uv[0];
+        }
+    }
+}
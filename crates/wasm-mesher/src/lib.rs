@@ -1,9 +1,17 @@
 use std::cell::RefCell;
 use wasm_bindgen::prelude::*;
-use js_sys::{Array, Uint32Array};
+use js_sys::{Array, Float32Array, Int32Array, Object, Reflect, Uint32Array};
 
 pub use voxelize_mesher::*;
 
+#[cfg(feature = "threads")]
+mod threads;
+
+/// Numbers per chunk in a batch's chunk table: its min, then its shape.
+const BATCH_CHUNK_FIELDS: usize = 6;
+/// Numbers per job in a batch's job table: its min, then its max.
+const BATCH_JOB_FIELDS: usize = 6;
+
 thread_local! {
     static CACHED_REGISTRY: RefCell<Option<Registry>> = const { RefCell::new(None) };
     static CHUNK_SCRATCH: RefCell<Vec<Option<ChunkData>>> = RefCell::new(Vec::new());
@@ -135,6 +143,7 @@ pub fn mesh_chunk_fast(
                 [max[0], max[1], max[2]],
                 MeshConfig {
                     chunk_size,
+                    camera_chunk: None,
                 },
                 registry,
             )
@@ -144,6 +153,177 @@ pub fn mesh_chunk_fast(
     serde_wasm_bindgen::to_value(&output).unwrap()
 }
 
+/// Mesh a batch of sub-chunks in one call, straight from typed arrays.
+///
+/// `chunks` describes a grid of chunks `width` wide, laid out row by row
+/// along z: six numbers per chunk, its min then its shape, with a zero
+/// shape for a missing chunk. `voxels` and `lights` hold the chunks' data
+/// back to back in the same order. `jobs` holds six numbers per sub-chunk
+/// to mesh, its min then its max.
+///
+/// Returns `{ meshes, buffers }`: a `{ geometries, connectivity }` per job,
+/// with vertex data in typed arrays of their own, and every `ArrayBuffer`
+/// behind them to pass along as a `postMessage` transfer list. Built with
+/// the `threads` feature and a pool started, jobs mesh in parallel.
+#[wasm_bindgen]
+pub fn mesh_batch(
+    voxels: &[u32],
+    lights: &[u32],
+    chunks: &[i32],
+    width: usize,
+    jobs: &[i32],
+    chunk_size: i32,
+) -> JsValue {
+    let grid = batch_grid(voxels, lights, chunks);
+
+    let config = MeshConfig {
+        chunk_size,
+        camera_chunk: None,
+    };
+    let outputs = CACHED_REGISTRY.with(|registry_cell| {
+        let registry_ref = registry_cell.borrow();
+        let registry = registry_ref
+            .as_ref()
+            .expect("Registry not set. Call set_registry first.");
+        mesh_jobs(&grid, width, jobs, &config, registry)
+    });
+
+    let meshes = Array::new();
+    let buffers = Array::new();
+    for output in outputs {
+        let geometries = Array::new();
+        for geometry in output.geometries {
+            geometries.push(&geometry_to_js(geometry, &buffers));
+        }
+
+        let mesh = Object::new();
+        set(&mesh, "geometries", &geometries);
+        set(&mesh, "connectivity", &output.connectivity.into());
+        meshes.push(&mesh);
+    }
+
+    let batch = Object::new();
+    set(&batch, "meshes", &meshes);
+    set(&batch, "buffers", &buffers);
+    batch.into()
+}
+
+/// The chunks of a batch's chunk table, with their data sliced out of
+/// `voxels` and `lights`.
+fn batch_grid(voxels: &[u32], lights: &[u32], chunks: &[i32]) -> Vec<Option<ChunkData>> {
+    let mut offset = 0;
+    chunks
+        .chunks_exact(BATCH_CHUNK_FIELDS)
+        .map(|chunk| {
+            let shape = [chunk[3], chunk[4], chunk[5]].map(|extent| extent.max(0) as usize);
+            let len = shape.iter().product::<usize>();
+            let range = offset..offset + len;
+            offset += len;
+            if len == 0 {
+                return None;
+            }
+
+            // Like a chunk mid-transfer, data cut short reads as air.
+            Some(ChunkData {
+                voxels: voxels.get(range.clone()).unwrap_or_default().to_vec(),
+                lights: lights.get(range).unwrap_or_default().to_vec(),
+                shape,
+                min: [chunk[0], chunk[1], chunk[2]],
+            })
+        })
+        .collect()
+}
+
+fn mesh_job(
+    grid: &[Option<ChunkData>],
+    width: usize,
+    job: &[i32],
+    config: &MeshConfig,
+    registry: &Registry,
+) -> MeshOutput {
+    let min = [job[0], job[1], job[2]];
+    let max = [job[3], job[4], job[5]];
+    mesh_chunk_grid(grid, width, min, max, config, registry)
+}
+
+#[cfg(feature = "threads")]
+fn mesh_jobs(
+    grid: &[Option<ChunkData>],
+    width: usize,
+    jobs: &[i32],
+    config: &MeshConfig,
+    registry: &Registry,
+) -> Vec<MeshOutput> {
+    use rayon::prelude::*;
+
+    if !threads::is_pool_started() {
+        return jobs
+            .chunks_exact(BATCH_JOB_FIELDS)
+            .map(|job| mesh_job(grid, width, job, config, registry))
+            .collect();
+    }
+
+    jobs.par_chunks_exact(BATCH_JOB_FIELDS)
+        .map(|job| mesh_job(grid, width, job, config, registry))
+        .collect()
+}
+
+#[cfg(not(feature = "threads"))]
+fn mesh_jobs(
+    grid: &[Option<ChunkData>],
+    width: usize,
+    jobs: &[i32],
+    config: &MeshConfig,
+    registry: &Registry,
+) -> Vec<MeshOutput> {
+    jobs.chunks_exact(BATCH_JOB_FIELDS)
+        .map(|job| mesh_job(grid, width, job, config, registry))
+        .collect()
+}
+
+/// A geometry as the JS side expects it, with its vertex data copied out of
+/// wasm memory into fresh buffers, which are added to `buffers`.
+fn geometry_to_js(geometry: GeometryProtocol, buffers: &Array) -> Object {
+    let positions = Float32Array::from(geometry.positions.as_slice());
+    let indices: Vec<u32> = geometry.indices.iter().map(|&index| index as u32).collect();
+    let indices = Uint32Array::from(indices.as_slice());
+    let uvs = Float32Array::from(geometry.uvs.as_slice());
+    let lights = Int32Array::from(geometry.lights.as_slice());
+    for buffer in [
+        positions.buffer(),
+        indices.buffer(),
+        uvs.buffer(),
+        lights.buffer(),
+    ] {
+        buffers.push(&buffer);
+    }
+
+    let at = match geometry.at {
+        Some(at) => at
+            .iter()
+            .map(|&value| JsValue::from(value))
+            .collect::<Array>()
+            .into(),
+        None => JsValue::NULL,
+    };
+    let layer = serde_wasm_bindgen::to_value(&geometry.layer).unwrap();
+
+    let object = Object::new();
+    set(&object, "voxel", &geometry.voxel.into());
+    set(&object, "at", &at);
+    set(&object, "faceName", &geometry.face_name.into());
+    set(&object, "layer", &layer);
+    set(&object, "positions", &positions);
+    set(&object, "indices", &indices);
+    set(&object, "uvs", &uvs);
+    set(&object, "lights", &lights);
+    object
+}
+
+fn set(target: &Object, key: &str, value: &JsValue) {
+    Reflect::set(target, &key.into(), value).unwrap();
+}
+
 #[wasm_bindgen]
 pub fn mesh_chunk_full(input: JsValue) -> JsValue {
     let mut input: MeshInput = serde_wasm_bindgen::from_value(input).unwrap();
@@ -151,3 +331,162 @@ pub fn mesh_chunk_full(input: JsValue) -> JsValue {
     let output = voxelize_mesher::mesh_chunk(input);
     serde_wasm_bindgen::to_value(&output).unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHUNK_SIZE: i32 = 4;
+    const HEIGHT: usize = 8;
+
+    fn block(id: u32, name: &str, is_empty: bool) -> Block {
+        Block {
+            id,
+            name: name.to_owned(),
+            name_lower: name.to_lowercase(),
+            rotatable: false,
+            y_rotatable: false,
+            is_empty,
+            is_fluid: false,
+            is_waterloggable: false,
+            is_waterlogging_fluid: false,
+            is_opaque: !is_empty,
+            is_see_through: false,
+            is_transparent: [false; 6],
+            transparent_standalone: false,
+            occludes_fluid: false,
+            is_plant: false,
+            stack_group: 0,
+            faces: if is_empty {
+                vec![]
+            } else {
+                [
+                    ("px", [1, 0, 0]),
+                    ("nx", [-1, 0, 0]),
+                    ("py", [0, 1, 0]),
+                    ("ny", [0, -1, 0]),
+                    ("pz", [0, 0, 1]),
+                    ("nz", [0, 0, -1]),
+                ]
+                .into_iter()
+                .map(|(name, dir)| BlockFace {
+                    name: name.to_owned(),
+                    name_lower: name.to_owned(),
+                    dir,
+                    ..Default::default()
+                })
+                .collect()
+            },
+            aabbs: if is_empty {
+                vec![]
+            } else {
+                vec![AABB {
+                    min_x: 0.0,
+                    min_y: 0.0,
+                    min_z: 0.0,
+                    max_x: 1.0,
+                    max_y: 1.0,
+                    max_z: 1.0,
+                }]
+            },
+            dynamic_patterns: None,
+            texture_rules: vec![],
+        }
+    }
+
+    fn registry() -> Registry {
+        let mut registry = Registry::new(vec![
+            (0, block(0, "Air", true)),
+            (1, block(1, "Stone", false)),
+        ]);
+        registry.build_cache();
+        registry
+    }
+
+    /// A 3x3 grid of hilly chunks, flattened into a batch's typed arrays:
+    /// voxels, lights and the chunk table.
+    fn batch_input() -> (Vec<u32>, Vec<u32>, Vec<i32>) {
+        let (mut voxels, mut lights, mut chunks) = (vec![], vec![], vec![]);
+        for cz in -1..=1 {
+            for cx in -1..=1 {
+                let min = [cx * CHUNK_SIZE, 0, cz * CHUNK_SIZE];
+                let size = CHUNK_SIZE as usize;
+                for lx in 0..size {
+                    for ly in 0..HEIGHT {
+                        for lz in 0..size {
+                            let (vx, vz) = (min[0] + lx as i32, min[2] + lz as i32);
+                            let height = 2 + (vx * 3 + vz * 5).rem_euclid(5) as usize;
+                            voxels.push(u32::from(ly < height));
+                            lights.push(((ly as u32 * 2) % 16) << 12);
+                        }
+                    }
+                }
+                chunks.extend(min);
+                chunks.extend([CHUNK_SIZE, HEIGHT as i32, CHUNK_SIZE]);
+            }
+        }
+        (voxels, lights, chunks)
+    }
+
+    fn assert_same_mesh(batched: &MeshOutput, alone: &MeshOutput) {
+        assert_eq!(batched.connectivity, alone.connectivity);
+        assert_eq!(batched.geometries.len(), alone.geometries.len());
+        for (batched, alone) in batched.geometries.iter().zip(&alone.geometries) {
+            assert_eq!(batched.voxel, alone.voxel);
+            assert_eq!(batched.positions, alone.positions);
+            assert_eq!(batched.indices, alone.indices);
+            assert_eq!(batched.uvs, alone.uvs);
+            assert_eq!(batched.lights, alone.lights);
+        }
+    }
+
+    #[test]
+    fn batch_grid_slices_each_chunk_and_skips_missing_ones() {
+        let (voxels, lights, mut chunks) = batch_input();
+        // The corner chunk is missing, and the last one is cut short.
+        chunks[3..6].copy_from_slice(&[0, 0, 0]);
+        let volume = CHUNK_SIZE as usize * HEIGHT * CHUNK_SIZE as usize;
+        let voxels = &voxels[volume..voxels.len() - 1];
+        let lights = &lights[volume..];
+
+        let grid = batch_grid(voxels, lights, &chunks);
+        assert_eq!(grid.len(), 9);
+        assert!(grid[0].is_none());
+
+        let center = grid[4].as_ref().unwrap();
+        assert_eq!(center.min, [0, 0, 0]);
+        assert_eq!(center.shape, [4, 8, 4]);
+        assert_eq!(center.voxels, batch_input().0[volume * 4..volume * 5]);
+
+        let last = grid[8].as_ref().unwrap();
+        assert!(last.voxels.is_empty());
+        assert_eq!(last.lights.len(), volume);
+    }
+
+    #[test]
+    fn batched_sub_chunks_mesh_like_each_one_alone() {
+        let registry = registry();
+        let (voxels, lights, chunks) = batch_input();
+        let grid = batch_grid(&voxels, &lights, &chunks);
+        let config = MeshConfig {
+            chunk_size: CHUNK_SIZE,
+            camera_chunk: None,
+        };
+
+        let levels = [[0, 0, 0, 4, 4, 4], [0, 4, 0, 4, 8, 4]];
+        let outputs = mesh_jobs(&grid, 3, levels.as_flattened(), &config, &registry);
+        assert_eq!(outputs.len(), levels.len());
+
+        for (output, level) in outputs.iter().zip(levels) {
+            let alone = mesh_chunk_with_registry_chunks(
+                &batch_grid(&voxels, &lights, &chunks),
+                [level[0], level[1], level[2]],
+                [level[3], level[4], level[5]],
+                config.clone(),
+                &registry,
+            );
+            assert_same_mesh(output, &alone);
+        }
+        assert!(!outputs[0].geometries.is_empty());
+    }
+}
@@ -0,0 +1,51 @@
+//! A rayon pool on wasm threads for `mesh_batch`.
+//!
+//! Only a shared-memory build (`pnpm build:threads`) can run it. The host
+//! starts the pool with `init_thread_pool`, whose `spawn` callback gets one
+//! handle per pool thread. Each handle goes to a new worker, together with
+//! this module and its memory. That worker instantiates the module on the
+//! shared memory and calls `run_pool_thread` with the handle. Until the pool
+//! is up, batches mesh on the calling thread.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use js_sys::Function;
+use rayon::{ThreadBuilder, ThreadPoolBuilder};
+use wasm_bindgen::prelude::*;
+
+static POOL_STARTED: AtomicBool = AtomicBool::new(false);
+
+#[wasm_bindgen]
+pub fn init_thread_pool(threads: usize, spawn: Function) -> Result<(), JsValue> {
+    ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .spawn_handler(move |thread| {
+            let handle = Box::into_raw(Box::new(thread)) as usize;
+            spawn
+                .call1(&JsValue::NULL, &handle.into())
+                .map(drop)
+                .map_err(|error| io::Error::other(format!("{error:?}")))
+        })
+        .build_global()
+        .map_err(|error| JsValue::from_str(&error.to_string()))?;
+
+    POOL_STARTED.store(true, Ordering::Release);
+    Ok(())
+}
+
+/// Run the pool thread behind `handle` until the pool shuts down.
+#[wasm_bindgen]
+pub fn run_pool_thread(handle: usize) {
+    // SAFETY: handles only come from `init_thread_pool`'s spawn handler,
+    // which leaks each box for exactly one worker to reclaim here.
+    let thread = unsafe { Box::from_raw(handle as *mut ThreadBuilder) };
+    thread.run();
+}
+
+/// Whether batches can mesh on the pool. Outside wasm, rayon starts its
+/// global pool on demand, which is how the crate's tests run the parallel
+/// path.
+pub(crate) fn is_pool_started() -> bool {
+    !cfg!(target_arch = "wasm32") || POOL_STARTED.load(Ordering::Acquire)
+}
@@ -1267,6 +1267,7 @@ class Peers extends VOXELIZE.Peers<VOXELIZE.Character, PeersMeta> {
   };
 }
 const peers = new Peers(controls.object);
+peers.getOwnMovementInputs = controls.takeMovementInputs;
 
 // createPeer code found in Peers class
 
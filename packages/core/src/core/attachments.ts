@@ -0,0 +1,88 @@
+import { Object3D, Quaternion, Vector3 } from "three";
+
+import { Coords3 } from "../types";
+
+/**
+ * Metadata key the server sets on an entity or peer riding another (a mount,
+ * cart or seat). The server places the rider itself; clients place it against
+ * their interpolated parent so the two never drift apart on screen.
+ */
+export const ATTACHMENT_METADATA_KEY = "attachment";
+
+/**
+ * How many riders can be stacked on one another, mirroring the server. Longer
+ * chains are not followed when placing riders.
+ */
+export const MAX_ATTACHMENT_DEPTH = 8;
+
+/**
+ * A rider's `attachment` metadata.
+ */
+export type Attachment = {
+  /**
+   * ID of the entity or peer being ridden.
+   */
+  parent: string;
+
+  /**
+   * Where the rider sits relative to the parent's position, in the parent's
+   * frame: local +z faces the parent's yaw.
+   */
+  offset: Coords3;
+};
+
+/**
+ * Read the attachment out of an entity's or peer's metadata.
+ *
+ * @param metadata The metadata to read.
+ * @returns The attachment, or `null` when the metadata has none.
+ */
+export function readAttachment(
+  metadata: { [key: string]: any } | null | undefined,
+): Attachment | null {
+  const attachment = metadata?.[ATTACHMENT_METADATA_KEY];
+  if (
+    !attachment ||
+    typeof attachment.parent !== "string" ||
+    !Array.isArray(attachment.offset) ||
+    attachment.offset.length !== 3
+  ) {
+    return null;
+  }
+  return attachment as Attachment;
+}
+
+const Y_AXIS = new Vector3(0, 1, 0);
+const scratchQuaternion = new Quaternion();
+const scratchForward = new Vector3();
+const scratchPosition = new Vector3();
+
+/**
+ * Place `rider` at its attachment's offset from `parent`, turned with the
+ * parent's yaw the way the server places it.
+ *
+ * @param rider The object riding.
+ * @param attachment The rider's attachment.
+ * @param parent The object being ridden.
+ */
+export function placeAttached(
+  rider: Object3D,
+  attachment: Attachment,
+  parent: Object3D,
+) {
+  scratchForward
+    .set(0, 0, 1)
+    .applyQuaternion(parent.getWorldQuaternion(scratchQuaternion));
+  const yaw = Math.atan2(scratchForward.x, scratchForward.z);
+
+  const [ox, oy, oz] = attachment.offset;
+  scratchPosition
+    .set(ox, oy, oz)
+    .applyAxisAngle(Y_AXIS, yaw)
+    .add(parent.getWorldPosition(scratchForward));
+
+  if (rider.parent) {
+    rider.parent.worldToLocal(scratchPosition);
+  }
+  rider.position.copy(scratchPosition);
+}
@@ -17,6 +17,11 @@ import { Coords3 } from "../types";
 import { ChunkUtils } from "../utils";
 
 import { Inputs } from "./inputs";
+import {
+  MovementCorrection,
+  MovementInput,
+  MovementInputBuffer,
+} from "./movement-inputs";
 import { NetIntercept } from "./network";
 import { World } from "./world";
 
@@ -468,6 +473,14 @@ export class RigidControls extends EventEmitter implements NetIntercept {
    */
   public body: RigidBody;
 
+  /**
+   * The inputs recorded for the server on worlds with authoritative movement,
+   * or `null` on other worlds. Set once the world is initialized, after which
+   * the controls step their body once per fixed step instead of letting the
+   * world's physics step it each frame.
+   */
+  public movementInputs: MovementInputBuffer | null = null;
+
   /**
    * Whether or not the client has certain movement potentials. For example, if the forward
    * key is pressed, then "front" would be `true`. Vice versa for "back".
@@ -654,6 +667,23 @@ export class RigidControls extends EventEmitter implements NetIntercept {
               this.body.applyImpulse([x, y, z]);
               break;
             }
+
+            case "vox-builtin:movement-correction": {
+              this.applyMovementCorrection(event.payload);
+              break;
+            }
+
+            case "vox-builtin:carry": {
+              // Carried along by the platform underfoot; a lift back onto
+              // its top also stops the fall into it.
+              const [x, y, z] = event.payload;
+              const [px, py, pz] = this.body.getPosition();
+              this.body.setPosition([px + x, py + y, pz + z]);
+              if (y > 0 && this.body.velocity[1] < 0) {
+                this.body.velocity[1] = 0;
+              }
+              break;
+            }
           }
         }
 
@@ -710,7 +740,22 @@ export class RigidControls extends EventEmitter implements NetIntercept {
     if (this.arm) this.arm.update();
 
     this.moveRigidBody();
-    this.updateRigidBody(delta);
+
+    const movementInputs = this.getMovementInputs();
+    if (movementInputs) {
+      this.stepMovementInputs(movementInputs, delta);
+    } else {
+      this.updateRigidBody(delta);
+    }
+  };
+
+  /**
+   * Take the movement inputs recorded since the last call, to send with the
+   * client's next peer update. Always empty on worlds without authoritative
+   * movement. See {@link Peers.getOwnMovementInputs}.
+   */
+  takeMovementInputs = (): MovementInput[] => {
+    return this.movementInputs ? this.movementInputs.takeOutgoing() : [];
   };
 
   /**
@@ -1238,6 +1283,101 @@ export class RigidControls extends EventEmitter implements NetIntercept {
     return ChunkUtils.mapVoxelToChunk(this.voxel, this.world.options.chunkSize);
   }
 
+  /**
+   * The movement inputs buffer, created the first time it is asked for on an
+   * initialized world with authoritative movement.
+   */
+  private getMovementInputs = () => {
+    if (this.movementInputs || !this.world.isInitialized) {
+      return this.movementInputs;
+    }
+
+    const { authoritativeMovement, fixedTimestep } = this.world.options;
+    if (!authoritativeMovement || !fixedTimestep) {
+      return null;
+    }
+
+    this.movementInputs = new MovementInputBuffer({
+      hz: fixedTimestep.hz,
+      maxBufferedInputs: authoritativeMovement.maxBufferedInputs,
+      maxStepsPerFrame: fixedTimestep.maxCatchupSteps,
+    });
+
+    // From here on the body moves in the server's fixed steps, so the
+    // world's per-frame physics must leave it alone.
+    this.world.physics.removeBody(this.body);
+
+    return this.movementInputs;
+  };
+
+  /**
+   * Step the body through the fixed steps due this frame, recording the
+   * input of each one. Like the server, nothing is stepped while the chunk
+   * under the body is still loading.
+   */
+  private stepMovementInputs = (
+    movementInputs: MovementInputBuffer,
+    delta: number,
+  ) => {
+    const steps = movementInputs.steps(delta);
+
+    for (let i = 0; i < steps; i++) {
+      const [px, py, pz] = this.body.getPosition();
+      const chunk = this.world.getChunkByPosition(px, py, pz);
+      if (!chunk || !chunk.isReady) {
+        return;
+      }
+
+      this.stepBody(movementInputs.dt);
+
+      const { heading, running, sprinting, jumping } = this.state;
+      movementInputs.record(
+        { heading, running, sprinting, jumping },
+        this.body.getPosition() as Coords3,
+      );
+    }
+  };
+
+  /**
+   * Snap the body to the server's state and replay every input the server
+   * had not simulated yet on top of it.
+   */
+  private applyMovementCorrection = (correction: MovementCorrection) => {
+    const { movementInputs } = this;
+    if (!movementInputs) return;
+
+    this.body.setPosition(correction.position);
+    this.body.velocity = [...correction.velocity];
+
+    const { heading, running, sprinting, jumping } = this.state;
+
+    movementInputs.correct(correction, (input) => {
+      this.state.heading = input.heading;
+      this.state.running = input.running;
+      this.state.sprinting = input.sprinting;
+      this.state.jumping = input.jumping;
+
+      this.stepBody(movementInputs.dt);
+      return this.body.getPosition() as Coords3;
+    });
+
+    Object.assign(this.state, { heading, running, sprinting, jumping });
+  };
+
+  /**
+   * Drive the body with the current state and advance it by one step of `dt`.
+   */
+  private stepBody = (dt: number) => {
+    const [gx, gy, gz] = this.world.options.gravity;
+
+    this.updateRigidBody(dt);
+    this.world.physics.iterateBody(
+      this.body,
+      dt,
+      gx ** 2 + gy ** 2 + gz ** 2 < 0.01,
+    );
+  };
+
   /**
    * Move the client's rigid body according to the current movement state.
    */
@@ -9,6 +9,7 @@ type ProbeData = {
   rigidBody?: { isInFluid: boolean; fluidRatio: number };
   target?: { targetType?: string; position?: [number, number, number] | null };
   name?: string;
+  attachment?: { parent: string; offset: [number, number, number] };
 };
 
 class ProbeEntity extends Entity<ProbeData> {
@@ -545,3 +546,42 @@ describe("Entities out-of-order state protection", () => {
     expect(entities.getEntityById("a")).toBeDefined();
   });
 });
+
+describe("Entities riding", () => {
+  it("places riders against their parents, turned with the parent", () => {
+    const entities = makeEntities();
+    entities.onMessage(entityMessage("CREATE", "horse"));
+    entities.onMessage(
+      entityMessage("CREATE", "rider", {
+        position: [0, 0, 0],
+        attachment: { parent: "horse", offset: [0, 1, 0.5] },
+      }),
+    );
+    const horse = entities.getEntityById("horse") as ProbeEntity;
+    horse.position.set(4, 20, 4);
+    horse.rotation.y = Math.PI / 2;
+
+    entities.update();
+
+    // Facing +x, the horse's forward offset points along +x.
+    const rider = entities.getEntityById("rider") as ProbeEntity;
+    expect(rider.position.x).toBeCloseTo(4.5);
+    expect(rider.position.y).toBeCloseTo(21);
+    expect(rider.position.z).toBeCloseTo(4);
+  });
+
+  it("forgets the attachment of a rider that dismounted", () => {
+    const entities = makeEntities();
+    entities.onMessage(
+      entityMessage("CREATE", "rider", {
+        position: [0, 0, 0],
+        attachment: { parent: "horse", offset: [0, 1, 0] },
+      }),
+    );
+
+    entities.onMessage(entityMessage("UPDATE", "rider", { name: "walker" }));
+
+    const rider = entities.getEntityById("rider") as ProbeEntity;
+    expect(rider.metadata?.attachment).toBeUndefined();
+  });
+});
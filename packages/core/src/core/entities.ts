@@ -4,8 +4,14 @@ import {
   EntityProtocol,
   MessageProtocol,
 } from "@voxelize/protocol";
-import { Group, Vector3 } from "three";
+import { Group, Object3D, Vector3 } from "three";
 
+import {
+  ATTACHMENT_METADATA_KEY,
+  MAX_ATTACHMENT_DEPTH,
+  placeAttached,
+  readAttachment,
+} from "./attachments";
 import { EntityLivenessTracker } from "./entity-liveness";
 import { NetIntercept } from "./network";
 import { isPerfLogging, logPerf } from "./perf";
@@ -67,6 +73,11 @@ function mergeMetadataUpdate(
   if (isJsonObject(previousTarget) && isJsonObject(incomingTarget)) {
     merged.target = { ...previousTarget, ...incomingTarget };
   }
+  // Both lanes carry every non-motion key, so a rider that dismounted
+  // arrives without its attachment.
+  if (incoming && !(ATTACHMENT_METADATA_KEY in incoming)) {
+    delete merged[ATTACHMENT_METADATA_KEY];
+  }
   return merged;
 }
 
@@ -211,6 +222,13 @@ export class Entities extends Group implements NetIntercept {
     this.liveness = new EntityLivenessTracker(this.options);
   }
 
+  /**
+   * Look up the parent of a riding entity that is not itself an entity, e.g.
+   * `(id) => peers.getPeerById(id)` for entities riding players. Riders are
+   * placed against their parents at the end of {@link Entities.update}.
+   */
+  getAttachmentParent?: (id: string) => Object3D | undefined;
+
   setClass = (
     type: string,
     entity: (new (id: string) => Entity) | ((id: string) => Entity),
@@ -471,6 +489,8 @@ export class Entities extends Group implements NetIntercept {
 
       entity.update?.();
     });
+
+    this.placeRiders();
   };
 
   snapAllToTarget = () => {
@@ -479,6 +499,35 @@ export class Entities extends Group implements NetIntercept {
     });
   };
 
+  /**
+   * Place every riding entity at its attachment's offset from its parent,
+   * parents first so riders of riders land on their already placed parents.
+   */
+  private placeRiders = () => {
+    const placed = new Set<string>();
+
+    const place = (entity: Entity, depth: number) => {
+      if (placed.has(entity.entId)) return;
+      placed.add(entity.entId);
+
+      const attachment = readAttachment(entity.metadata);
+      if (!attachment) return;
+
+      const parentEntity = this.map.get(attachment.parent);
+      if (parentEntity && depth < MAX_ATTACHMENT_DEPTH) {
+        place(parentEntity, depth + 1);
+      }
+
+      const parent =
+        parentEntity ?? this.getAttachmentParent?.(attachment.parent);
+      if (parent) {
+        placeAttached(entity, attachment, parent);
+      }
+    };
+
+    this.map.forEach((entity) => place(entity, 0));
+  };
+
   private releaseEntity = (object: Entity, metadata: Entity["metadata"]) => {
     this.map.delete(object.entId);
     this.liveness.forget(object.entId);
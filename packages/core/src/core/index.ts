@@ -1,3 +1,4 @@
+export * from "./attachments";
 export * from "./chat";
 export * from "./controls";
 export * from "./mobile-controls";
@@ -6,6 +7,7 @@ export * from "./entity-liveness";
 export * from "./events";
 export * from "./inputs";
 export * from "./method";
+export * from "./movement-inputs";
 export * from "./network";
 export * from "./perf";
 export * from "./peers";
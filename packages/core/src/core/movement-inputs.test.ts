@@ -0,0 +1,120 @@
+import { describe, expect, it } from "vitest";
+
+import { Coords3 } from "../types";
+
+import {
+  MovementCorrection,
+  MovementInput,
+  MovementInputBuffer,
+} from "./movement-inputs";
+
+const idle = { heading: 0, running: false, sprinting: false, jumping: false };
+
+// 64 steps per second keeps the step math exact in binary floating point.
+const makeBuffer = (maxBufferedInputs = 32) =>
+  new MovementInputBuffer({ hz: 64, maxBufferedInputs });
+
+describe("MovementInputBuffer.steps", () => {
+  it("runs one step per whole fixed step and carries the remainder", () => {
+    const buffer = makeBuffer();
+
+    expect(buffer.steps(1 / 128)).toBe(0);
+    expect(buffer.steps(1 / 128)).toBe(1);
+    expect(buffer.steps(2.5 / 64)).toBe(2);
+    expect(buffer.steps(0.5 / 64)).toBe(1);
+  });
+
+  it("caps the steps a single frame runs", () => {
+    const buffer = makeBuffer();
+
+    expect(buffer.steps(1)).toBe(5);
+    expect(buffer.steps(0)).toBe(0);
+  });
+});
+
+describe("MovementInputBuffer.record", () => {
+  it("numbers inputs in order and hands each out once", () => {
+    const buffer = makeBuffer();
+
+    buffer.record(idle, [0, 10, 0]);
+    buffer.record({ ...idle, running: true }, [0, 10, 0.1]);
+
+    const outgoing = buffer.takeOutgoing();
+    expect(outgoing.map(({ seq }) => seq)).toEqual([0, 1]);
+    expect(outgoing[1]).toMatchObject({
+      running: true,
+      position: [0, 10, 0.1],
+    });
+    expect(buffer.takeOutgoing()).toEqual([]);
+
+    buffer.record(idle, [0, 10, 0.1]);
+    expect(buffer.takeOutgoing().map(({ seq }) => seq)).toEqual([2]);
+  });
+
+  it("keeps no more unsent inputs than the server buffers", () => {
+    const buffer = makeBuffer(3);
+
+    for (let i = 0; i < 5; i++) {
+      buffer.record(idle, [0, 0, i]);
+    }
+
+    expect(buffer.takeOutgoing().map(({ seq }) => seq)).toEqual([2, 3, 4]);
+  });
+});
+
+describe("MovementInputBuffer.correct", () => {
+  it("replays only the inputs after the corrected one", () => {
+    const buffer = makeBuffer();
+    for (let i = 0; i < 5; i++) {
+      buffer.record({ ...idle, running: true }, [0, 20, i]);
+    }
+    buffer.takeOutgoing();
+
+    // The server stood the client on the ground after input 2; each replayed
+    // input walks one block from wherever the last one left it.
+    let body: Coords3 = [0, 10, 2];
+    const replayed: MovementInput[] = [];
+    buffer.correct({ seq: 2, position: body, velocity: [0, 0, 0] }, (input) => {
+      replayed.push(input);
+      body = [body[0], body[1], body[2] + 1];
+      return body;
+    });
+
+    expect(replayed.map(({ seq }) => seq)).toEqual([3, 4]);
+    expect(replayed.map(({ position }) => position)).toEqual([
+      [0, 10, 3],
+      [0, 10, 4],
+    ]);
+
+    // A later correction replays only what is still unconfirmed.
+    replayed.length = 0;
+    const correction: MovementCorrection = {
+      seq: 3,
+      position: [0, 10, 3],
+      velocity: [0, 0, 0],
+    };
+    buffer.correct(correction, (input) => {
+      replayed.push(input);
+      return input.position;
+    });
+    expect(replayed.map(({ seq }) => seq)).toEqual([4]);
+  });
+
+  it("updates the prediction of inputs not sent yet", () => {
+    const buffer = makeBuffer();
+    buffer.record(idle, [0, 20, 0]);
+    buffer.takeOutgoing();
+    buffer.record(idle, [0, 20, 0]);
+
+    const correction: MovementCorrection = {
+      seq: 0,
+      position: [0, 10, 0],
+      velocity: [0, 0, 0],
+    };
+    buffer.correct(correction, () => [0, 10, 0]);
+
+    expect(buffer.takeOutgoing()).toEqual([
+      { ...idle, seq: 1, position: [0, 10, 0] },
+    ]);
+  });
+});
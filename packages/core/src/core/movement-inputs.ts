@@ -0,0 +1,169 @@
+import { Coords3 } from "../types";
+
+/**
+ * What the client did during one fixed step, sent in the `inputs` of its peer
+ * updates on worlds with authoritative movement. Mirrors the server's
+ * `MovementInput`.
+ */
+export type MovementInput = {
+  /**
+   * Strictly increasing per client. Corrections name the input they were
+   * computed after.
+   */
+  seq: number;
+
+  /**
+   * Facing in radians around the y axis, like
+   * {@link RigidControlState.heading}.
+   */
+  heading: number;
+
+  running: boolean;
+  sprinting: boolean;
+  jumping: boolean;
+
+  /**
+   * Where the client's own simulation put its body after this input.
+   */
+  position: Coords3;
+};
+
+/**
+ * The server's state for the client's body after simulating input `seq`,
+ * received as the `vox-builtin:movement-correction` event.
+ */
+export type MovementCorrection = {
+  seq: number;
+  position: Coords3;
+  velocity: Coords3;
+};
+
+/**
+ * The server's authoritative movement options, sent with the world's options.
+ */
+export type AuthoritativeMovementOptions = {
+  correctionThreshold: number;
+  maxBufferedInputs: number;
+};
+
+export type MovementInputBufferOptions = {
+  /**
+   * The world's fixed steps per second. One input is recorded per step.
+   */
+  hz: number;
+
+  /**
+   * How many inputs the server buffers per client. The client keeps as many
+   * unsent and as many unconfirmed inputs, since the server drops the rest.
+   */
+  maxBufferedInputs: number;
+
+  /**
+   * Steps run for a single frame at most, so a stalled tab does not replay
+   * seconds of movement at once. Defaults to `5`.
+   */
+  maxStepsPerFrame?: number;
+};
+
+/**
+ * Records the client's movement once per fixed step on worlds with
+ * authoritative movement, queues the inputs for the next peer update, and
+ * replays the ones newer than a correction from the server's state.
+ */
+export class MovementInputBuffer {
+  /**
+   * The length of a fixed step in seconds.
+   */
+  public readonly dt: number;
+
+  private accumulator = 0;
+
+  private nextSeq = 0;
+
+  /**
+   * Inputs the server may not have simulated yet, oldest first.
+   */
+  private unconfirmed: MovementInput[] = [];
+
+  /**
+   * Inputs not yet handed to a peer update, oldest first.
+   */
+  private outgoing: MovementInput[] = [];
+
+  constructor(private options: MovementInputBufferOptions) {
+    this.dt = 1 / options.hz;
+  }
+
+  /**
+   * Add a frame's time and return how many fixed steps are now due.
+   *
+   * @param delta The frame's length in seconds.
+   */
+  steps(delta: number) {
+    const { maxStepsPerFrame = 5 } = this.options;
+
+    this.accumulator += delta;
+    const steps = Math.floor(this.accumulator / this.dt);
+    this.accumulator -= steps * this.dt;
+
+    return Math.min(steps, maxStepsPerFrame);
+  }
+
+  /**
+   * Record the input of a step the client just simulated.
+   *
+   * @param input What the client did during the step.
+   * @param position Where the step left the client's body.
+   * @returns The recorded input.
+   */
+  record(input: Omit<MovementInput, "seq" | "position">, position: Coords3) {
+    const recorded: MovementInput = {
+      ...input,
+      seq: this.nextSeq++,
+      position: [...position] as Coords3,
+    };
+
+    this.push(this.unconfirmed, recorded);
+    this.push(this.outgoing, recorded);
+
+    return recorded;
+  }
+
+  /**
+   * Take the inputs recorded since the last call, for a peer update.
+   */
+  takeOutgoing() {
+    const outgoing = this.outgoing;
+    this.outgoing = [];
+    return outgoing;
+  }
+
+  /**
+   * Apply a correction. The caller has already put its body in the
+   * corrected state; `replay` then steps the body through each input newer
+   * than the corrected one and returns where it ended up, which becomes
+   * that input's prediction.
+   *
+   * @param correction The correction from the server.
+   * @param replay Simulates one step of an input from the body's state.
+   */
+  correct(
+    correction: MovementCorrection,
+    replay: (input: MovementInput) => Coords3,
+  ) {
+    this.unconfirmed = this.unconfirmed.filter(
+      ({ seq }) => seq > correction.seq,
+    );
+
+    this.unconfirmed.forEach((input) => {
+      input.position = [...replay(input)] as Coords3;
+    });
+  }
+
+  private push(inputs: MovementInput[], input: MovementInput) {
+    inputs.push(input);
+    if (inputs.length > this.options.maxBufferedInputs) {
+      inputs.shift();
+    }
+  }
+}
@@ -182,6 +182,21 @@ export class Network {
 
   private useWebRTC = false;
 
+  /**
+   * Resume token from the last INIT, presented when rejoining after a drop.
+   * Only set when the server has session resume enabled.
+   */
+  private resumeToken: string | null = null;
+
+  /** Highest reliable sequence applied with no gaps below it. */
+  private appliedSeq = 0;
+
+  /**
+   * Applied sequences above `appliedSeq`. The control and bulk lanes
+   * interleave, so sequences can arrive out of order.
+   */
+  private appliedSeqsAhead = new Set<number>();
+
   constructor(options: Partial<NetworkOptions> = {}) {
     this.options = {
       ...defaultOptions,
@@ -414,6 +429,19 @@ export class Network {
     this.initPacketReceived = false;
     this.joinStartTime = performance.now();
 
+    // Rejoining after a dropped socket: ask the server to resume the
+    // suspended session. It replays what was missed past `lastSeq`, or falls
+    // back to a full INIT when it can't.
+    const resume = this.resumeToken
+      ? {
+          token: this.resumeToken,
+          lastSeq: this.appliedSeq,
+          chunks: this.intercepts.flatMap(
+            (intercept) => intercept.resumeChunks?.() ?? [],
+          ),
+        }
+      : undefined;
+
     this.send({
       type: "JOIN",
       json: {
@@ -421,7 +449,7 @@ export class Network {
         username: this.clientInfo.username,
         // Protocol capabilities this client supports; servers only use a
         // path a client advertised, so older servers simply ignore this.
-        capabilities: ["motion.v1"],
+        capabilities: ["motion.v1", "mesh.v1"],
         // Wire protocol version. Deterministic (fixed-step) worlds assert
         // strict equality and refuse a mismatch; non-deterministic worlds
         // ignore it, so this is always safe to send.
@@ -431,6 +459,7 @@ export class Network {
           typeof this.clientInfo.metadata.preferences === "object"
             ? this.clientInfo.metadata.preferences
             : {},
+        resume,
       },
     });
   };
@@ -473,6 +502,7 @@ export class Network {
     }
 
     this.joined = false;
+    this.resumeToken = null;
 
     this.send({
       type: "LEAVE",
@@ -652,6 +682,7 @@ export class Network {
 
     this.connected = false;
     this.joined = false;
+    this.resumeToken = null;
     this.waitingForInit = false;
     this.initPacketReceived = false;
     this.packetQueue = [];
@@ -759,9 +790,32 @@ export class Network {
     return this.rtc?.isConnected ?? false;
   }
 
+  /**
+   * Record a reliable sequence as applied. Returns false for a sequence that
+   * was already applied (a resume replays from the last gap-free sequence,
+   * so anything received above it arrives twice).
+   */
+  private applySequence = (seq: number) => {
+    if (seq <= this.appliedSeq || this.appliedSeqsAhead.has(seq)) {
+      return false;
+    }
+
+    this.appliedSeqsAhead.add(seq);
+    while (this.appliedSeqsAhead.delete(this.appliedSeq + 1)) {
+      this.appliedSeq += 1;
+    }
+
+    return true;
+  };
+
   private onMessage = (message: MessageProtocol) => {
     const { type } = message;
     logIncomingMessage(message);
+
+    if (message.seq && !this.applySequence(message.seq)) {
+      return;
+    }
+
     if (type === "ERROR") {
       const { text } = message;
       console.error("[NETWORK] Received ERROR:", text);
@@ -773,7 +827,13 @@ export class Network {
     }
 
     if (type === "INIT") {
-      const { id } = message.json;
+      const { id, resumed, resumeToken, seq } = message.json;
+
+      this.resumeToken = resumeToken ?? null;
+      if (!resumed) {
+        this.appliedSeq = seq ?? 0;
+        this.appliedSeqsAhead.clear();
+      }
 
       if (id) {
         if (this.clientInfo.id && this.clientInfo.id !== id) {
@@ -786,9 +846,15 @@ export class Network {
       }
     }
 
-    this.intercepts.forEach((intercept) => {
-      intercept.onMessage?.(message, this.clientInfo);
-    });
+    // A resumed INIT only acknowledges the rejoin: the session, its entities
+    // and chunks are intact, so interceptors must not reset for it.
+    const isResumedInit = type === "INIT" && message.json?.resumed;
+
+    if (!isResumedInit) {
+      this.intercepts.forEach((intercept) => {
+        intercept.onMessage?.(message, this.clientInfo);
+      });
+    }
 
     if (type === "INIT") {
       this.waitingForInit = false;
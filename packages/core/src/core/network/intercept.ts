@@ -36,4 +36,10 @@ export interface NetIntercept {
    * sent to the server after every `network.flush()` call.
    */
   packets?: MessageProtocol[];
+
+  /**
+   * Chunk coordinates this interceptor still holds. Reported when resuming a
+   * dropped session so the server only re-sends chunks the client lost.
+   */
+  resumeChunks?: () => [number, number][];
 }
@@ -4,6 +4,7 @@ import {
   coerceMotionBytes,
   decodeMotion,
   normalizeEntityMotion,
+  unpackCompactGeometry,
 } from "./decode-utils";
 
 const FLAG_IN_FLUID = 1 << 0;
@@ -179,3 +180,61 @@ describe("normalizeEntityMotion", () => {
     expect("motion" in absent).toBe(false);
   });
 });
+
+// One quad on one atlas tile, laid out as crates/mesher/src/mesher/compact.rs
+// packs it, so the layout contract is pinned from both sides of the wire.
+function packQuad(diagonal: boolean): Uint8Array {
+  const buffer = new ArrayBuffer(76);
+  const view = new DataView(buffer);
+  view.setUint8(0, 1);
+  view.setUint8(1, 0);
+  view.setUint16(2, 1, true);
+  view.setUint32(4, 4, true);
+  [0.25, 0.5, 0.75, 1].forEach((value, i) =>
+    view.setFloat32(8 + i * 4, value, true),
+  );
+  for (let vertex = 0; vertex < 4; vertex++) {
+    view.setInt32(24 + vertex * 4, 0xf00 + vertex, true);
+  }
+  view.setUint16(40, diagonal ? 1 << 15 : 0, true);
+  const corners = [
+    [0, 0],
+    [1, 0],
+    [0, 1],
+    [1, 1],
+  ];
+  corners.forEach(([x, z], vertex) => {
+    view.setUint16(42 + vertex * 2, (x + 16) * 1024, true);
+    view.setUint16(50 + vertex * 2, (0.5 + 16) * 1024, true);
+    view.setUint16(58 + vertex * 2, (z + 16) * 1024, true);
+    view.setUint8(66 + vertex * 2, x * 255);
+    view.setUint8(67 + vertex * 2, z * 255);
+  });
+  return new Uint8Array(buffer);
+}
+
+describe("unpackCompactGeometry", () => {
+  it("restores positions, tile uvs, lights and implied indices", () => {
+    const unpacked = unpackCompactGeometry(packQuad(false))!;
+
+    expect(Array.from(unpacked.positions)).toEqual([
+      0, 0.5, 0, 1, 0.5, 0, 0, 0.5, 1, 1, 0.5, 1,
+    ]);
+    expect(Array.from(unpacked.uvs)).toEqual([
+      0.25, 0.75, 0.5, 0.75, 0.25, 1, 0.5, 1,
+    ]);
+    expect(Array.from(unpacked.lights)).toEqual([0xf00, 0xf01, 0xf02, 0xf03]);
+    expect(Array.from(unpacked.indices)).toEqual([0, 1, 2, 2, 1, 3]);
+
+    const flipped = unpackCompactGeometry(packQuad(true))!;
+    expect(Array.from(flipped.indices)).toEqual([0, 1, 3, 3, 2, 0]);
+  });
+
+  it("rejects unknown versions and truncated payloads", () => {
+    const payload = packQuad(false);
+    expect(unpackCompactGeometry(payload.subarray(0, 40))).toBeUndefined();
+
+    payload[0] = 2;
+    expect(unpackCompactGeometry(payload)).toBeUndefined();
+  });
+});
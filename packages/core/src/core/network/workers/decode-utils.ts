@@ -2,7 +2,7 @@ import { protocol } from "@voxelize/protocol";
 import * as fflate from "fflate";
 import * as lz4 from "lz4js";
 
-const { Message, Entity } = protocol;
+const { Message, Entity, RenderLayer } = protocol;
 
 const LZ4_FRAME_MAGIC = [0x04, 0x22, 0x4d, 0x18];
 const ZLIB_MAGIC_0 = 0x78;
@@ -217,6 +217,122 @@ export function decodeMotion(payload: Uint8Array): DecodedMotion | undefined {
   return motion;
 }
 
+const COMPACT_MESH_V1 = 1;
+const MESH_POSITION_SCALE = 1024;
+const MESH_POSITION_BIAS = 16;
+const MESH_UV_STEPS = 255;
+const MESH_FLAG_EXPLICIT_INDICES = 1 << 0;
+const MESH_DIAGONAL_BIT = 1 << 15;
+const QUAD_INDICES = [0, 1, 2, 2, 1, 3];
+const QUAD_INDICES_FLIPPED = [0, 1, 3, 3, 2, 0];
+
+type UnpackedGeometry = {
+  positions: Float32Array;
+  uvs: Float32Array;
+  indices: Uint16Array | Uint32Array;
+  lights: Int32Array;
+};
+
+/**
+ * Unpack a compact vertex payload (kept in byte-for-byte sync with
+ * `crates/mesher/src/mesher/compact.rs`) into the float buffers a legacy
+ * geometry carries. Returns undefined for unknown versions or truncated
+ * payloads, in which case the geometry is dropped instead of rendering
+ * garbage.
+ */
+export function unpackCompactGeometry(
+  payload: Uint8Array,
+): UnpackedGeometry | undefined {
+  if (payload.length < 8 || payload[0] !== COMPACT_MESH_V1) {
+    return undefined;
+  }
+  const view = new DataView(
+    payload.buffer,
+    payload.byteOffset,
+    payload.byteLength,
+  );
+  const flags = payload[1];
+  const tileCount = view.getUint16(2, true);
+  const vertexCount = view.getUint32(4, true);
+  const quadCount = vertexCount >> 2;
+
+  const tilesAt = 8;
+  const lightsAt = tilesAt + tileCount * 16;
+  const quadsAt = lightsAt + vertexCount * 4;
+  const positionsAt = quadsAt + quadCount * 2;
+  const uvsAt = positionsAt + vertexCount * 6;
+  const indicesAt = (uvsAt + vertexCount * 2 + 3) & ~3;
+  if (indicesAt > payload.length) return undefined;
+
+  const lights = new Int32Array(vertexCount);
+  for (let i = 0; i < vertexCount; i++) {
+    lights[i] = view.getInt32(lightsAt + i * 4, true);
+  }
+
+  // Stored one axis after another: every x, then every y, then every z.
+  const positions = new Float32Array(vertexCount * 3);
+  for (let axis = 0; axis < 3; axis++) {
+    for (let i = 0; i < vertexCount; i++) {
+      const at = positionsAt + (axis * vertexCount + i) * 2;
+      positions[i * 3 + axis] =
+        view.getUint16(at, true) / MESH_POSITION_SCALE - MESH_POSITION_BIAS;
+    }
+  }
+
+  const uvAt = (step: number, start: number, end: number) =>
+    step === 0
+      ? start
+      : step === MESH_UV_STEPS
+        ? end
+        : start + ((end - start) * step) / MESH_UV_STEPS;
+
+  const uvs = new Float32Array(vertexCount * 2);
+  const diagonals = new Uint8Array(quadCount);
+  for (let quad = 0; quad < quadCount; quad++) {
+    const bits = view.getUint16(quadsAt + quad * 2, true);
+    const tile = bits & ~MESH_DIAGONAL_BIT;
+    if (tile >= tileCount) return undefined;
+    diagonals[quad] = bits & MESH_DIAGONAL_BIT ? 1 : 0;
+
+    const tileAt = tilesAt + tile * 16;
+    const startU = view.getFloat32(tileAt, true);
+    const endU = view.getFloat32(tileAt + 4, true);
+    const startV = view.getFloat32(tileAt + 8, true);
+    const endV = view.getFloat32(tileAt + 12, true);
+    for (let corner = 0; corner < 4; corner++) {
+      const vertex = quad * 4 + corner;
+      uvs[vertex * 2] = uvAt(payload[uvsAt + vertex * 2], startU, endU);
+      uvs[vertex * 2 + 1] = uvAt(
+        payload[uvsAt + vertex * 2 + 1],
+        startV,
+        endV,
+      );
+    }
+  }
+
+  const IndexArray = vertexCount > 65536 ? Uint32Array : Uint16Array;
+  let indices: Uint16Array | Uint32Array;
+  if (flags & MESH_FLAG_EXPLICIT_INDICES) {
+    if (indicesAt + 4 > payload.length) return undefined;
+    const count = view.getUint32(indicesAt, true);
+    if (indicesAt + 4 + count * 4 > payload.length) return undefined;
+    indices = new IndexArray(count);
+    for (let i = 0; i < count; i++) {
+      indices[i] = view.getInt32(indicesAt + 4 + i * 4, true);
+    }
+  } else {
+    indices = new IndexArray(quadCount * 6);
+    for (let quad = 0; quad < quadCount; quad++) {
+      const pattern = diagonals[quad] ? QUAD_INDICES_FLIPPED : QUAD_INDICES;
+      for (let i = 0; i < 6; i++) {
+        indices[quad * 6 + i] = quad * 4 + pattern[i];
+      }
+    }
+  }
+
+  return { positions, uvs, indices, lights };
+}
+
 export function decodeMessage(
   buffer: Uint8Array,
   transferables: ArrayBuffer[],
@@ -306,6 +422,28 @@ export function decodeMessage(
             for (let k = 0; k < geometries.length; k++) {
               const geo = geometries[k];
               if (geo) {
+                if (typeof geo.layer === "number") {
+                  geo.layer = RenderLayer[geo.layer];
+                }
+                const packed = geo.packed as Uint8Array | null | undefined;
+                delete geo.packed;
+                if (packed && packed.length > 0) {
+                  const unpacked = unpackCompactGeometry(
+                    decompressLz4Block(packed),
+                  );
+                  if (!unpacked) {
+                    geometries.splice(k--, 1);
+                    continue;
+                  }
+                  Object.assign(geo, unpacked);
+                  transferables.push(
+                    unpacked.positions.buffer as ArrayBuffer,
+                    unpacked.uvs.buffer as ArrayBuffer,
+                    unpacked.indices.buffer as ArrayBuffer,
+                    unpacked.lights.buffer as ArrayBuffer,
+                  );
+                  continue;
+                }
                 if (geo.indices) {
                   const decompressedI32 = decompressToInt32Array(
                     geo.indices as Uint8Array,
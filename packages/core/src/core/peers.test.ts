@@ -175,3 +175,86 @@ describe("Peers.collectShadowCasters", () => {
     expect(out).toHaveLength(2);
   });
 });
+
+describe("Peers riding", () => {
+  const peerMessage = (
+    peers: { id: string; metadata: Record<string, unknown> }[],
+  ): Msg =>
+    ({
+      type: "PEER",
+      peers: peers.map((peer) => ({ ...peer, username: peer.id })),
+    }) as Msg;
+
+  it("places riding peers against their parents and tracks its own mount", () => {
+    const { peers, message } = makePeers();
+    peers.onPeerUpdate = () => undefined;
+    const cart = new Object3D();
+    cart.position.set(2, 10, 2);
+    peers.getAttachmentParent = (id) => (id === "cart" ? cart : undefined);
+    message(init("me"));
+
+    const attachment = { parent: "cart", offset: [0, 1, 0] };
+    message(
+      peerMessage([
+        { id: "a", metadata: { attachment } },
+        { id: "me", metadata: { attachment } },
+      ]),
+    );
+    peers.update();
+
+    const rider = peers.getPeerById("a");
+    expect(rider?.position.toArray()).toEqual([2, 11, 2]);
+    expect(peers.ownAttachment).toEqual(attachment);
+
+    message(
+      peerMessage([
+        { id: "a", metadata: {} },
+        { id: "me", metadata: {} },
+      ]),
+    );
+    rider?.position.set(0, 0, 0);
+    peers.update();
+
+    expect(rider?.position.toArray()).toEqual([0, 0, 0]);
+    expect(peers.ownAttachment).toBeNull();
+  });
+});
+
+describe("Peers movement inputs", () => {
+  it("sends new inputs with an update even when the info is unchanged", () => {
+    const { peers, message } = makePeers();
+    message(init("me"));
+
+    const input = {
+      seq: 0,
+      heading: 0,
+      running: true,
+      sprinting: false,
+      jumping: false,
+      position: [0, 10, 0] as [number, number, number],
+    };
+    let inputs = [input];
+    peers.getOwnMovementInputs = () => {
+      const taken = inputs;
+      inputs = [];
+      return taken;
+    };
+
+    peers.update();
+    peers.update();
+
+    expect(peers.packets).toHaveLength(1);
+    expect(peers.packets[0].peers[0].metadata.inputs).toEqual([input]);
+
+    inputs = [{ ...input, seq: 1 }];
+    peers.update();
+
+    expect(peers.packets).toHaveLength(2);
+    expect(peers.packets[1].peers[0].metadata.inputs).toEqual([
+      { ...input, seq: 1 },
+    ]);
+    expect(peers.packets[1].peers[0].metadata.position).toEqual(
+      peers.packets[0].peers[0].metadata.position,
+    );
+  });
+});
@@ -3,6 +3,8 @@ import { Group, Object3D, Quaternion, Vector3 } from "three";
 
 import { Character } from "../libs";
 
+import { Attachment, placeAttached, readAttachment } from "./attachments";
+import { MovementInput } from "./movement-inputs";
 import { NetIntercept } from "./network";
 
 const emptyQ = new Quaternion();
@@ -104,6 +106,14 @@ export class Peers<
 
   public ownPeer?: C;
 
+  /**
+   * What the client itself is riding, from the `attachment` the server
+   * replicates, or `null`. The server ignores the client's own position
+   * while it rides, so hold the controls at the mount, e.g. with
+   * {@link RigidControls.teleportToExact}.
+   */
+  public ownAttachment: Attachment | null = null;
+
   /**
    * A list of packets that will be sent to the server.
    *
@@ -118,6 +128,11 @@ export class Peers<
    */
   public map: Map<string, C> = new Map();
 
+  /**
+   * Maps the ID of each riding peer to its attachment.
+   */
+  private attachments: Map<string, Attachment> = new Map();
+
   /**
    * Create a peers manager to add multiplayer functionality to your Voxelize game.
    *
@@ -174,6 +189,21 @@ export class Peers<
    */
   onPeerLeave: (id: string, peer: C) => void;
 
+  /**
+   * Look up the parent of a riding peer that is not itself a peer, e.g.
+   * `(id) => entities.getEntityById(id)` for players riding entities. Riders
+   * are placed against their parents at the end of {@link Peers.update}.
+   */
+  getAttachmentParent?: (id: string) => Object3D | undefined;
+
+  /**
+   * Take the movement inputs to send with the client's next update, e.g.
+   * `controls.takeMovementInputs`. Worlds with authoritative movement move
+   * the client only by these inputs and ignore the position it reports.
+   * An update carrying inputs is sent even when the packed info is unchanged.
+   */
+  getOwnMovementInputs?: () => MovementInput[];
+
   /**
    * The network intercept implementation for peers.
    *
@@ -232,6 +262,7 @@ export class Peers<
 
         if (peer && this.options.autoAddToSelf) this.remove(peer);
         this.map.delete(id);
+        this.attachments.delete(id);
 
         this.onPeerLeave?.(id, peer as C);
         break;
@@ -266,6 +297,19 @@ export class Peers<
       peers.forEach((peer: any) => {
         const self = this.ownID && peer.id === this.ownID;
 
+        // Peer metadata arrives whole, so a peer without an attachment has
+        // dismounted.
+        if (peer.metadata) {
+          const attachment = readAttachment(peer.metadata);
+          if (self) {
+            this.ownAttachment = attachment;
+          } else if (attachment) {
+            this.attachments.set(peer.id, attachment);
+          } else {
+            this.attachments.delete(peer.id);
+          }
+        }
+
         if (!this.options.countSelf && self) return;
 
         let object = self ? this.ownPeer : this.getPeerById(peer.id);
@@ -407,13 +451,18 @@ export class Peers<
 
     if (info) {
       const newInfoJson = JSON.stringify(info);
+      const inputs = this.getOwnMovementInputs?.() ?? [];
 
-      if (this.infoJsonCache !== newInfoJson) {
+      if (this.infoJsonCache !== newInfoJson || inputs.length) {
         this.infoJsonCache = newInfoJson;
 
         const event: MessageProtocol = {
           type: "PEER",
-          peers: [info],
+          peers: [
+            inputs.length
+              ? { ...info, metadata: { ...info.metadata, inputs } }
+              : info,
+          ],
         };
 
         this.packets.push(event);
@@ -429,6 +478,16 @@ export class Peers<
         }
       });
     }
+
+    this.attachments.forEach((attachment, id) => {
+      const rider = this.map.get(id);
+      const parent =
+        this.map.get(attachment.parent) ??
+        this.getAttachmentParent?.(attachment.parent);
+      if (rider && parent) {
+        placeAttached(rider, attachment, parent);
+      }
+    });
   }
 
   snapAllToTarget() {
@@ -47,6 +47,41 @@ export interface BlockDynamicPattern {
   parts: BlockConditionalPart[];
 }
 
+/**
+ * A texture a {@link TextureVariantRule} can swap onto a face. It takes its
+ * own atlas slot, shared with any texture group of the same name, so it is
+ * painted with `World.applyTextureGroup`.
+ */
+export type VariantTexture = {
+  name: string;
+  range: UV;
+};
+
+/**
+ * Per-voxel variation of a block's face textures, applied by the mesher.
+ */
+export type TextureVariantRule = {
+  /**
+   * Names of the faces the rule applies to; empty for every face.
+   */
+  faces: string[];
+
+  /**
+   * Textures picked at random in place of the face's own.
+   */
+  variants: VariantTexture[];
+
+  /**
+   * Sixteen textures for connected faces, indexed by which same-block
+   * neighbors in the face's plane it joins. Takes precedence over `variants`.
+   */
+  connected: VariantTexture[];
+
+  randomRotation: boolean;
+
+  randomMirror: boolean;
+};
+
 /**
  * A block type in the world. This is defined by the server.
  */
@@ -198,6 +233,11 @@ export type Block = {
 
   dynamicPatterns: BlockDynamicPattern[];
 
+  /**
+   * Per-voxel texture variation the mesher applies to this block's faces.
+   */
+  textureRules?: TextureVariantRule[];
+
   /**
    * If this block is dynamic, this function will be called to generate the faces and AABB's. By default, this
    * just returns the faces and AABB's that are defined in the block data.
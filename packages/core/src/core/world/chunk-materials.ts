@@ -406,6 +406,14 @@ export async function loadChunkMaterials(
         ungroupedFaces++;
       }
     }
+
+    // Textures swapped in by texture rules get slots of their own, shared
+    // with any texture group of the same name, as the server lays them out.
+    for (const rule of block.textureRules ?? []) {
+      for (const texture of [...rule.variants, ...rule.connected]) {
+        textureGroups.add(texture.name);
+      }
+    }
   }
   const totalSlots = textureGroups.size + ungroupedFaces;
   const countPerSide = perSide(totalSlots);
@@ -1167,43 +1167,12 @@ export class World<T = any> extends Scene implements NetIntercept {
     const strategy = WorkerTransfer.getStrategy();
     const serializeStart = performance.now();
 
-    const neighbors = [
-      [-1, -1],
-      [0, -1],
-      [1, -1],
-      [-1, 0],
-      [0, 0],
-      [1, 0],
-      [-1, 1],
-      [0, 1],
-      [1, 1],
-    ];
-
-    const chunks = neighbors.map(([dx, dz]) =>
-      this.getChunkByCoords(cx + dx, cz + dz),
-    );
-
-    const centerChunk = chunks[4];
-    if (!centerChunk) {
+    if (!this.getChunkByCoords(cx, cz)) {
       return null;
     }
 
-    const { min, max } = centerChunk;
-    const heightPerSubChunk = Math.floor(
-      this.options.maxHeight / this.options.subChunks,
-    );
-    // Raised to the chunk's cull line while it is distant, so the level the
-    // line runs through meshes only its upper part and the ones entirely under
-    // it produce nothing at all.
-    const floorY = this.chunkDetailFloor.get(ChunkUtils.getChunkName([cx, cz]));
-    const subChunkMin = [
-      min[0],
-      Math.max(heightPerSubChunk * level, floorY ?? 0),
-      min[2],
-    ];
-    const subChunkMax = [max[0], heightPerSubChunk * (level + 1), max[2]];
-
-    if (subChunkMin[1] >= subChunkMax[1]) {
+    const range = this.getSubChunkMeshRange(cx, cz, level);
+    if (!range) {
       return {
         geometries: [],
         connectivity: CONNECTIVITY_FULL,
@@ -1213,23 +1182,10 @@ export class World<T = any> extends Scene implements NetIntercept {
         outputBytes: 0,
       };
     }
+    const [subChunkMin, subChunkMax] = range;
 
-    const chunksData: unknown[] = [];
-    const arrayBuffers: ArrayBuffer[] = [];
-    let inputBytes = 0;
-
-    for (const chunk of chunks) {
-      if (!chunk || !chunk.isReady) {
-        chunksData.push(null);
-        continue;
-      }
-
-      const [chunkData, chunkArrayBuffers] = chunk.serialize();
-
-      chunksData.push(chunkData);
-      arrayBuffers.push(...chunkArrayBuffers);
-      inputBytes += chunk.voxels.data.byteLength + chunk.lights.data.byteLength;
-    }
+    const { chunksData, arrayBuffers, inputBytes } =
+      this.serializeMeshStencil(cx, cz);
 
     const serializeMs = performance.now() - serializeStart;
 
@@ -1296,6 +1252,138 @@ export class World<T = any> extends Scene implements NetIntercept {
     };
   }
 
+  /**
+   * Mesh several levels of one chunk in a single worker job, against one
+   * serialized copy of the chunk's 3x3 neighborhood instead of a copy per
+   * level. Resolves a result per level, in order, each `null` where the
+   * level failed and has to be retried.
+   */
+  private async dispatchMeshBatch(
+    cx: number,
+    cz: number,
+    levels: number[],
+    isPriority: boolean,
+  ): Promise<
+    ({ geometries: GeometryProtocol[]; connectivity: number } | null)[]
+  > {
+    if (!this.getChunkByCoords(cx, cz)) {
+      return levels.map(() => null);
+    }
+
+    // Levels the chunk's cull line hides entirely need no worker at all.
+    const ranges = levels.map((level) =>
+      this.getSubChunkMeshRange(cx, cz, level),
+    );
+    const jobs = ranges.filter(
+      (range): range is [Coords3, Coords3] => !!range,
+    );
+    const empty = () => ({ geometries: [], connectivity: CONNECTIVITY_FULL });
+    if (jobs.length === 0) {
+      return levels.map(empty);
+    }
+
+    const { chunksData, arrayBuffers } = this.serializeMeshStencil(cx, cz);
+
+    const name = ChunkUtils.getChunkName([cx, cz]);
+    if (this.chunkPipeline.isInStage(name, "processing")) {
+      return levels.map(() => null);
+    }
+
+    const meshWorkerPool = isPriority
+      ? this.urgentMeshWorkerPool
+      : this.meshWorkerPool;
+    const workerResult = await new Promise<{
+      meshes: { geometries: GeometryProtocol[]; connectivity: number }[] | null;
+    } | null>((resolve) => {
+      meshWorkerPool.addJob({
+        message: {
+          type: "batch",
+          chunksData,
+          width: 3,
+          jobs,
+          options: this.options,
+        },
+        buffers: arrayBuffers,
+        timeoutMs: this.options.meshJobTimeoutMs,
+        resolve,
+      });
+    });
+
+    const meshes = workerResult?.meshes;
+    if (!meshes || this.chunkPipeline.isInStage(name, "processing")) {
+      return levels.map(() => null);
+    }
+
+    let next = 0;
+    return ranges.map((range) => (range ? meshes[next++] ?? null : empty()));
+  }
+
+  /**
+   * Serialize the 3x3 chunks around a chunk for a mesh worker, row by row
+   * along z. Chunks missing or not ready yet go as `null`.
+   */
+  private serializeMeshStencil(cx: number, cz: number) {
+    const chunksData: unknown[] = [];
+    const arrayBuffers: ArrayBuffer[] = [];
+    let inputBytes = 0;
+
+    for (let dz = -1; dz <= 1; dz++) {
+      for (let dx = -1; dx <= 1; dx++) {
+        const chunk = this.getChunkByCoords(cx + dx, cz + dz);
+        if (!chunk || !chunk.isReady) {
+          chunksData.push(null);
+          continue;
+        }
+
+        const [chunkData, chunkArrayBuffers] = chunk.serialize();
+
+        chunksData.push(chunkData);
+        arrayBuffers.push(...chunkArrayBuffers);
+        inputBytes +=
+          chunk.voxels.data.byteLength + chunk.lights.data.byteLength;
+      }
+    }
+
+    return { chunksData, arrayBuffers, inputBytes };
+  }
+
+  /**
+   * The `[min, max]` a level of a chunk meshes, or `null` when there is
+   * nothing of it to mesh.
+   */
+  private getSubChunkMeshRange(
+    cx: number,
+    cz: number,
+    level: number,
+  ): [Coords3, Coords3] | null {
+    const chunk = this.getChunkByCoords(cx, cz);
+    if (!chunk) return null;
+
+    const { min, max } = chunk;
+    const heightPerSubChunk = Math.floor(
+      this.options.maxHeight / this.options.subChunks,
+    );
+    // Raised to the chunk's cull line while it is distant, so the level the
+    // line runs through meshes only its upper part and the ones entirely under
+    // it produce nothing at all.
+    const floorY = this.chunkDetailFloor.get(ChunkUtils.getChunkName([cx, cz]));
+    const subChunkMin: Coords3 = [
+      min[0],
+      Math.max(heightPerSubChunk * level, floorY ?? 0),
+      min[2],
+    ];
+    const subChunkMax: Coords3 = [
+      max[0],
+      heightPerSubChunk * (level + 1),
+      max[2],
+    ];
+
+    if (subChunkMin[1] >= subChunkMax[1]) {
+      return null;
+    }
+    return [subChunkMin, subChunkMax];
+  }
+
   private estimateGeometryProtocolBytes(
     geometries: GeometryProtocol[],
   ): number {
@@ -1609,7 +1697,21 @@ export class World<T = any> extends Scene implements NetIntercept {
       }
     }
 
-    if (facesInGroup.length === 0) {
+    // Texture rule variants of the same name own a slot of their own, drawn
+    // through their block's atlas material like a grouped face.
+    const variantsInGroup: { blockId: number; range: UV }[] = [];
+
+    for (const [id, block] of this.registry.blocksById) {
+      for (const rule of block.textureRules ?? []) {
+        for (const texture of [...rule.variants, ...rule.connected]) {
+          if (texture.name === groupName) {
+            variantsInGroup.push({ blockId: id, range: texture.range });
+          }
+        }
+      }
+    }
+
+    if (facesInGroup.length === 0 && variantsInGroup.length === 0) {
       console.warn(`No faces found with texture group "${groupName}"`);
       return;
     }
@@ -1642,6 +1744,19 @@ export class World<T = any> extends Scene implements NetIntercept {
       }
     }
 
+    for (const { blockId, range } of variantsInGroup) {
+      const mat = this.getBlockFaceMaterial(blockId);
+      if (!mat) {
+        console.warn(
+          `No material found for texture group "${groupName}" (block ${blockId}, variant)`,
+        );
+        continue;
+      }
+      const atlas = mat.map as AtlasTexture;
+      atlas.drawImageToRange(range, source);
+      mat.map.needsUpdate = true;
+    }
+
     for (const { blockId, face } of facesInGroup) {
       if (!isOwnTextureFace(face)) continue;
 
@@ -3743,8 +3858,17 @@ export class World<T = any> extends Scene implements NetIntercept {
     await loadChunkMaterials(this);
 
     const registryData = this.registry.serialize();
-    this.meshWorkerPool.postMessage({ type: "init", registryData });
-    this.urgentMeshWorkerPool.postMessage({ type: "init", registryData });
+    const { meshPoolThreads } = this.options;
+    this.meshWorkerPool.postMessage({
+      type: "init",
+      registryData,
+      meshPoolThreads,
+    });
+    this.urgentMeshWorkerPool.postMessage({
+      type: "init",
+      registryData,
+      meshPoolThreads,
+    });
     this.lightWorkerPool.postMessage({ type: "init", registryData });
 
     this.isInitialized = true;
@@ -4067,6 +4191,20 @@ export class World<T = any> extends Scene implements NetIntercept {
     return this._deleteRadius;
   }
 
+  /**
+   * Coordinates of every loaded chunk, reported when the network resumes a
+   * dropped session so the server skips re-sending them.
+   *
+   * @hidden
+   */
+  resumeChunks = (): [number, number][] => {
+    const coords: [number, number][] = [];
+    this.chunkPipeline.forEachLoaded((chunk) => {
+      coords.push([chunk.coords[0], chunk.coords[1]]);
+    });
+    return coords;
+  };
+
   private resyncChunkStagesAfterRejoin() {
     this.chunkRefreshQueue.clear();
     for (const name of this.chunkPipeline.resyncForRejoin()) {
@@ -5736,22 +5874,59 @@ export class World<T = any> extends Scene implements NetIntercept {
       return;
     }
 
-    const workerPromises = keysToProcess.map((key) => {
-      const { cx, cz, level } = MeshPipeline.parseKey(key);
-      const isPriority = this.meshPipeline.isUrgent(key);
-      const generation = this.meshPipeline.startJob(key);
+    // Levels of one chunk go to a worker together, meshed against a single
+    // copy of the chunk's neighborhood; a lone level takes the plain path.
+    const keysByChunk = new Map<string, string[]>();
+    for (const key of keysToProcess) {
+      const { cx, cz } = MeshPipeline.parseKey(key);
+      const name = ChunkUtils.getChunkName([cx, cz]);
+      const keys = keysByChunk.get(name);
+      if (keys) {
+        keys.push(key);
+      } else {
+        keysByChunk.set(name, [key]);
+      }
+    }
 
-      return this.dispatchMeshWorker(cx, cz, level, isPriority).then(
-        (result) =>
-          ({
-            cx,
-            cz,
-            level,
-            generation,
-            key,
-            geometries: result?.geometries ?? null,
-            connectivity: result?.connectivity ?? CONNECTIVITY_FULL,
-          } as const),
+    const workerPromises = Array.from(keysByChunk.values()).map((keys) => {
+      const jobs = keys.map((key) => ({
+        ...MeshPipeline.parseKey(key),
+        key,
+        generation: this.meshPipeline.startJob(key),
+      }));
+      const isPriority = keys.some((key) => this.meshPipeline.isUrgent(key));
+      const [first] = jobs;
+
+      const dispatched =
+        jobs.length === 1
+          ? this.dispatchMeshWorker(
+              first.cx,
+              first.cz,
+              first.level,
+              isPriority,
+            ).then((result) => [result])
+          : this.dispatchMeshBatch(
+              first.cx,
+              first.cz,
+              jobs.map(({ level }) => level),
+              isPriority,
+            );
+
+      return dispatched.then(
+        (results) =>
+          jobs.map(
+            ({ cx, cz, level, generation, key }, index) =>
+              ({
+                cx,
+                cz,
+                level,
+                generation,
+                key,
+                geometries: results[index]?.geometries ?? null,
+                connectivity:
+                  results[index]?.connectivity ?? CONNECTIVITY_FULL,
+              } as const),
+          ),
         (error) => {
           // A dispatch that throws (e.g. payload serialization failing an
           // array-buffer allocation under memory pressure) must still settle:
@@ -5759,21 +5934,24 @@ export class World<T = any> extends Scene implements NetIntercept {
           // failJob in the batch, and leaves those generations in flight
           // forever — wedging the whole mesh pipeline on chunks that will
           // never be retried.
-          console.error(`[world] mesh dispatch failed for ${key}`, error);
-          return {
-            cx,
-            cz,
-            level,
-            generation,
-            key,
-            geometries: null,
-            connectivity: CONNECTIVITY_FULL,
-          } as const;
+          console.error(`[world] mesh dispatch failed for ${keys}`, error);
+          return jobs.map(
+            ({ cx, cz, level, generation, key }) =>
+              ({
+                cx,
+                cz,
+                level,
+                generation,
+                key,
+                geometries: null,
+                connectivity: CONNECTIVITY_FULL,
+              } as const),
+          );
         },
       );
     });
 
-    const results = await Promise.all(workerPromises);
+    const results = (await Promise.all(workerPromises)).flat();
     const isUrgentBatch = urgentKeys.length > 0;
 
     for (const result of results) {
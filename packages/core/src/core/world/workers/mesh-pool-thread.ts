@@ -0,0 +1,21 @@
+import init, * as mesher from "@voxelize/wasm-mesher";
+
+/**
+ * A thread of a mesh worker's rayon pool. It instantiates the shared-memory
+ * mesher on the mesh worker's memory and runs the pool thread behind the
+ * handle it was spawned for, which only returns when the pool shuts down.
+ */
+onmessage = async function (e) {
+  const { module, memory, handle } = e.data as {
+    module: WebAssembly.Module;
+    memory: WebAssembly.Memory;
+    handle: number;
+  };
+
+  await init({ module_or_path: module, memory });
+  (mesher as unknown as ThreadedMesher).run_pool_thread(handle);
+};
+
+type ThreadedMesher = {
+  run_pool_thread: (handle: number) => void;
+};
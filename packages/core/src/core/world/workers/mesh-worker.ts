@@ -1,8 +1,14 @@
-import init, { mesh_chunk_fast, set_registry } from "@voxelize/wasm-mesher";
+import init, * as mesher from "@voxelize/wasm-mesher";
+import {
+  mesh_batch,
+  mesh_chunk_fast,
+  set_registry,
+} from "@voxelize/wasm-mesher";
 
 import { Coords3 } from "../../../types";
 import { type WorldOptions } from "../index";
 import { type SerializedChunkPayload } from "../raw-chunk";
+import MeshPoolThread from "./mesh-pool-thread.ts?worker";
 import {
   isMainThreadSortedBlock,
   POSITION_BLOCK_BIAS,
@@ -85,6 +91,18 @@ type WasmBlock = {
         }[];
       }[]
     | null;
+  textureRules: {
+    faces: string[];
+    variants: WasmVariantTexture[];
+    connected: WasmVariantTexture[];
+    randomRotation: boolean;
+    randomMirror: boolean;
+  }[];
+};
+
+type WasmVariantTexture = {
+  name: string;
+  range: { startU: number; endU: number; startV: number; endV: number };
 };
 
 type WasmRegistry = {
@@ -126,6 +144,24 @@ type RawWasmDynamicPattern = {
   }[];
 };
 
+type RawWasmVariantTexture = {
+  name: string;
+  range?: {
+    startU?: number;
+    endU?: number;
+    startV?: number;
+    endV?: number;
+  };
+};
+
+type RawWasmTextureRule = {
+  faces?: string[];
+  variants?: RawWasmVariantTexture[];
+  connected?: RawWasmVariantTexture[];
+  randomRotation?: boolean;
+  randomMirror?: boolean;
+};
+
 type RawWasmBlock = {
   id: number;
   name: string;
@@ -146,16 +182,22 @@ type RawWasmBlock = {
   faces: RawWasmFace[];
   aabbs: RawWasmAabb[];
   dynamicPatterns?: RawWasmDynamicPattern[] | null;
+  textureRules?: RawWasmTextureRule[];
 };
 
 type GeometryProtocol = {
   voxel: number;
   at: Coords3 | null;
   faceName: string | null;
-  positions: number[];
-  indices: number[];
-  uvs: number[];
-  lights: number[];
+  positions: number[] | Float32Array;
+  indices: number[] | Uint32Array;
+  uvs: number[] | Float32Array;
+  lights: number[] | Int32Array;
+};
+
+type MeshBatchResult = {
+  meshes: { geometries: GeometryProtocol[]; connectivity: number }[];
+  buffers: ArrayBuffer[];
 };
 
 function workerComputeNormals(
@@ -224,8 +266,48 @@ function workerComputeBoundingSphere(positions: Float32Array): {
 }
 
 let wasmInitialized = false;
+let wasmMemory: WebAssembly.Memory | null = null;
+let isPoolStarted = false;
 let sortedBlockIds = new Set<number>();
 
+/**
+ * Exports only the shared-memory build of the mesher has; see
+ * `crates/wasm-mesher/src/threads.rs`.
+ */
+type ThreadedMesher = {
+  init_thread_pool?: (threads: number, spawn: (handle: number) => void) => void;
+};
+
+/**
+ * Start the mesher's thread pool for batches, once, when the build and the
+ * page support it. Each pool thread is a worker of its own, instantiating
+ * the module on this worker's memory.
+ */
+function startMeshPool(threads: number) {
+  const { init_thread_pool } = mesher as unknown as ThreadedMesher;
+  const module = (init as unknown as { __wbindgen_wasm_module?: unknown })
+    .__wbindgen_wasm_module;
+  if (
+    isPoolStarted ||
+    threads <= 0 ||
+    !init_thread_pool ||
+    !module ||
+    !wasmMemory ||
+    !self.crossOriginIsolated
+  ) {
+    return;
+  }
+
+  isPoolStarted = true;
+  try {
+    init_thread_pool(threads, (handle) => {
+      new MeshPoolThread().postMessage({ module, memory: wasmMemory, handle });
+    });
+  } catch (error) {
+    console.warn("[MeshWorker] Meshing batches without a thread pool:", error);
+  }
+}
+
 const minArray = new Int32Array(3);
 const maxArray = new Int32Array(3);
 const emptyUint32Array = new Uint32Array(0);
@@ -249,9 +331,10 @@ onmessage = async function (e) {
 
   if (type && type.toLowerCase() === "init") {
     if (!wasmInitialized) {
-      await init();
+      wasmMemory = (await init()).memory;
       wasmInitialized = true;
     }
+    startMeshPool(e.data.meshPoolThreads ?? 0);
 
     const rawRegistry = e.data.registryData;
     const wasmRegistry = convertRegistryToWasm(rawRegistry);
@@ -274,41 +357,24 @@ onmessage = async function (e) {
 
   if (!wasmInitialized) {
     console.warn("[mesh-worker] mesh request before init; returning empty");
+    // A batch has no empty answer that would not drop its levels' meshes,
+    // so it fails and its levels are retried.
     // @ts-expect-error postMessage typing
-    postMessage({ geometries: [] }, []);
+    postMessage(type === "batch" ? { meshes: null } : { geometries: [] }, []);
     return;
   }
 
-  const { chunksData, min, max } = e.data;
   const options = e.data.options as WorldOptions;
+  if (type === "batch") {
+    meshBatch(e.data.chunksData, e.data.width, e.data.jobs, options);
+    return;
+  }
+
+  const { chunksData, min, max } = e.data;
   const { chunkSize } = options;
   const positionUnits = positionUnitsPerBlock(options);
 
-  const chunks = chunksData.map(
-    (chunkData: SerializedChunkPayload | null): ChunkData | null => {
-      if (!chunkData) return null;
-
-      const { x, z, voxels, lights, options } = chunkData;
-      const { size, maxHeight } = options;
-      const voxelView = toUint32View(
-        voxels,
-        chunkData.voxelsByteOffset,
-        chunkData.voxelsLength,
-      );
-      const lightView = toUint32View(
-        lights,
-        chunkData.lightsByteOffset,
-        chunkData.lightsLength,
-      );
-
-      return {
-        voxels: voxelView,
-        lights: lightView,
-        shape: [size, maxHeight, size] as [number, number, number],
-        min: [x * size, 0, z * size] as [number, number, number],
-      };
-    },
-  );
+  const chunks = chunksData.map(toChunkData);
 
   minArray[0] = min[0];
   minArray[1] = min[1];
@@ -337,22 +403,138 @@ onmessage = async function (e) {
     postMessage({ geometries: null, isWorkerPoisoned: true }, []);
     return;
   }
-  const geometries = result.geometries;
+  const arrayBuffers = new Set<ArrayBuffer>();
+  const payload = {
+    geometries: packGeometries(result.geometries, positionUnits, arrayBuffers),
+    connectivity: result.connectivity,
+  };
+  // @ts-expect-error postMessage typing
+  postMessage(payload, [...arrayBuffers]);
+};
+
+function toChunkData(
+  chunkData: SerializedChunkPayload | null,
+): ChunkData | null {
+  if (!chunkData) return null;
+
+  const { x, z, voxels, lights, options } = chunkData;
+  const { size, maxHeight } = options;
+  const voxelView = toUint32View(
+    voxels,
+    chunkData.voxelsByteOffset,
+    chunkData.voxelsLength,
+  );
+  const lightView = toUint32View(
+    lights,
+    chunkData.lightsByteOffset,
+    chunkData.lightsLength,
+  );
+
+  return {
+    voxels: voxelView,
+    lights: lightView,
+    shape: [size, maxHeight, size] as [number, number, number],
+    min: [x * size, 0, z * size] as [number, number, number],
+  };
+}
+
+/**
+ * Mesh several sub-chunks against one grid of chunks in a single wasm call.
+ * `chunksData` is laid out row by row along z, `width` chunks wide, and each
+ * job is the `[min, max]` of a sub-chunk. Posts a mesh per job, in order.
+ */
+function meshBatch(
+  chunksData: (SerializedChunkPayload | null)[],
+  width: number,
+  jobs: [Coords3, Coords3][],
+  options: WorldOptions,
+) {
+  const chunks = chunksData.map(toChunkData);
+
+  let voxelCount = 0;
+  for (const chunk of chunks) {
+    if (chunk) voxelCount += chunk.shape[0] * chunk.shape[1] * chunk.shape[2];
+  }
+  const voxels = new Uint32Array(voxelCount);
+  const lights = new Uint32Array(voxelCount);
+  const table = new Int32Array(chunks.length * 6);
+
+  let offset = 0;
+  chunks.forEach((chunk, index) => {
+    if (!chunk) return;
+    // Each chunk takes its shape's volume in the batch; data cut short
+    // leaves the rest as air.
+    const [sx, sy, sz] = chunk.shape;
+    const length = sx * sy * sz;
+    voxels.set(chunk.voxels.subarray(0, length), offset);
+    lights.set(chunk.lights.subarray(0, length), offset);
+    table.set([...chunk.min, sx, sy, sz], index * 6);
+    offset += length;
+  });
+
+  const jobTable = new Int32Array(jobs.length * 6);
+  jobs.forEach(([min, max], index) => {
+    jobTable.set([...min, ...max], index * 6);
+  });
+
+  let result: MeshBatchResult;
+  try {
+    result = mesh_batch(
+      voxels,
+      lights,
+      table,
+      width,
+      jobTable,
+      options.chunkSize,
+    ) as MeshBatchResult;
+  } catch (error) {
+    console.error(
+      "[mesh-worker] wasm mesher trapped; requesting replacement",
+      error,
+    );
+    // @ts-expect-error postMessage typing
+    postMessage({ meshes: null, isWorkerPoisoned: true }, []);
+    return;
+  }
 
-  const arrayBuffers: ArrayBuffer[] = [];
-  const geometriesPacked = geometries
+  // The batch's vertex data already sits in buffers of its own, outside
+  // wasm memory; whichever of them the packed geometries keep go along
+  // without another copy.
+  const positionUnits = positionUnitsPerBlock(options);
+  const arrayBuffers = new Set<ArrayBuffer>(result.buffers);
+  const meshes = result.meshes.map((mesh) => ({
+    geometries: packGeometries(mesh.geometries, positionUnits, arrayBuffers),
+    connectivity: mesh.connectivity,
+  }));
+  // @ts-expect-error postMessage typing
+  postMessage({ meshes }, [...arrayBuffers]);
+}
+
+/**
+ * Pack geometries for the main thread, adding every buffer they hold to
+ * `arrayBuffers` for transfer. Typed arrays the mesher handed over are kept
+ * as they are rather than copied.
+ */
+function packGeometries(
+  geometries: GeometryProtocol[],
+  positionUnits: number,
+  arrayBuffers: Set<ArrayBuffer>,
+) {
+  return geometries
     .map((geometry) => {
-      const positions = new Float32Array(geometry.positions);
+      const positions = asTypedArray(geometry.positions, Float32Array);
       // Dense geometries (e.g. hand-authored canopies) exceed 65535 vertices
       // per chunk; Uint16 silently wraps those indices and tears wedge-shaped
       // holes through the mesh.
       const vertexCount = positions.length / 3;
-      const indices =
-        vertexCount > 65535
-          ? new Uint32Array(geometry.indices.length)
-          : new Uint16Array(geometry.indices.length);
-      for (let i = 0; i < geometry.indices.length; i++) {
-        indices[i] = geometry.indices[i];
+      let indices: Uint16Array | Uint32Array;
+      if (vertexCount > 65535) {
+        indices = asTypedArray(geometry.indices, Uint32Array);
+      } else {
+        indices = new Uint16Array(geometry.indices.length);
+        for (let i = 0; i < geometry.indices.length; i++) {
+          indices[i] = geometry.indices[i];
+        }
       }
 
       const normals = workerComputeNormals(positions, indices);
@@ -361,13 +543,13 @@ onmessage = async function (e) {
       const isQuantized = !sortedBlockIds.has(geometry.voxel);
       const packedGeometry = {
         indices,
-        lights: new Int32Array(geometry.lights),
+        lights: asTypedArray(geometry.lights, Int32Array),
         positions: isQuantized
           ? quantizePositions(positions, positionUnits)
           : positions,
         uvs: isQuantized
           ? quantizeUvs(geometry.uvs)
-          : new Float32Array(geometry.uvs),
+          : asTypedArray(geometry.uvs, Float32Array),
         normals: isQuantized ? quantizeNormals(normals) : normals,
         bsCenter: isQuantized
           ? (bs.center.map(
@@ -380,23 +562,25 @@ onmessage = async function (e) {
         at: geometry.at,
       };
 
-      arrayBuffers.push(packedGeometry.indices.buffer);
-      arrayBuffers.push(packedGeometry.lights.buffer);
-      arrayBuffers.push(packedGeometry.positions.buffer as ArrayBuffer);
-      arrayBuffers.push(packedGeometry.uvs.buffer as ArrayBuffer);
-      arrayBuffers.push(packedGeometry.normals.buffer as ArrayBuffer);
+      arrayBuffers.add(packedGeometry.indices.buffer as ArrayBuffer);
+      arrayBuffers.add(packedGeometry.lights.buffer as ArrayBuffer);
+      arrayBuffers.add(packedGeometry.positions.buffer as ArrayBuffer);
+      arrayBuffers.add(packedGeometry.uvs.buffer as ArrayBuffer);
+      arrayBuffers.add(packedGeometry.normals.buffer as ArrayBuffer);
 
       return packedGeometry;
     })
     .filter((geometry) => geometry.positions.length > 0);
+}
 
-  const payload = {
-    geometries: geometriesPacked,
-    connectivity: result.connectivity,
-  };
-  // @ts-expect-error postMessage typing
-  postMessage(payload, arrayBuffers);
-};
+function asTypedArray<T extends Float32Array | Int32Array | Uint32Array>(
+  values: number[] | T,
+  type: { new (values: number[]): T },
+): T {
+  return values instanceof type
+    ? (values as T)
+    : new type(values as number[]);
+}
 
 function convertRegistryToWasm(rawRegistry: {
   blocksById: [number, RawWasmBlock][];
@@ -425,6 +609,7 @@ function convertRegistryToWasm(rawRegistry: {
         dynamicPatterns: block.dynamicPatterns
           ? convertDynamicPatterns(block.dynamicPatterns)
           : null,
+        textureRules: convertTextureRules(block.textureRules),
       };
       return [id, wasmBlock];
     },
@@ -455,6 +640,29 @@ function convertFaces(faces: RawWasmFace[] | undefined): WasmBlock["faces"] {
   }));
 }
 
+function convertTextureRules(
+  rules: RawWasmTextureRule[] | undefined,
+): WasmBlock["textureRules"] {
+  if (!rules) return [];
+  const convertTextures = (textures: RawWasmVariantTexture[] | undefined) =>
+    (textures ?? []).map((texture) => ({
+      name: texture.name,
+      range: {
+        startU: texture.range?.startU ?? 0,
+        endU: texture.range?.endU ?? 1,
+        startV: texture.range?.startV ?? 0,
+        endV: texture.range?.endV ?? 1,
+      },
+    }));
+  return rules.map((rule) => ({
+    faces: rule.faces ?? [],
+    variants: convertTextures(rule.variants),
+    connected: convertTextures(rule.connected),
+    randomRotation: rule.randomRotation ?? false,
+    randomMirror: rule.randomMirror ?? false,
+  }));
+}
+
 function convertAabbs(aabbs: RawWasmAabb[] | undefined): WasmBlock["aabbs"] {
   if (!aabbs) return [];
   return aabbs.map((aabb) => ({
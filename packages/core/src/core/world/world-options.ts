@@ -1,3 +1,5 @@
+import { type AuthoritativeMovementOptions } from "../movement-inputs";
+
 import { ChunkRenderer } from "./chunk-renderer";
 import { CloudsOptions } from "./clouds";
 import { LocalLightsOptions } from "./local-lights/types";
@@ -60,6 +62,14 @@ export type WorldClientOptions = {
    */
   maxUrgentMeshWorkers: number;
 
+  /**
+   * Threads each mesh worker meshes batched sub-chunks on. Only takes effect
+   * with the shared-memory build of the mesher (`pnpm build:threads` in
+   * `@voxelize/wasm-mesher`) on a cross-origin isolated page. Defaults to
+   * `0`, meshing batches on the worker itself.
+   */
+  meshPoolThreads: number;
+
   /**
    * World Y under which distant chunks are left unmeshed, trading the terrain
    * below it for a much larger render radius. Only chunks farther away than
@@ -389,6 +399,7 @@ export const defaultWorldClientOptions: WorldClientOptions = {
   maxLightsUpdateTime: 5, // ms
   maxMeshesPerUpdate: 8,
   maxUrgentMeshWorkers: 4,
+  meshPoolThreads: 0,
   isCullingChunksByFrustum: true,
   chunkCullShadowSafeDistance: 160,
   isCullingChunksByOcclusion: true,
@@ -506,6 +517,18 @@ export type WorldServerOptions = {
    * The nominal water level of this world, in blocks.
    */
   waterLevel: number;
+
+  /**
+   * The world's fixed simulation step, or `null` when it steps with real time.
+   */
+  fixedTimestep?: { hz: number; maxCatchupSteps: number; seed: number } | null;
+
+  /**
+   * Set when the server simulates client movement from per-step inputs
+   * instead of applying reported positions. {@link RigidControls} then record
+   * an input each fixed step and replay them on corrections.
+   */
+  authoritativeMovement?: AuthoritativeMovementOptions | null;
 };
 
 /**
@@ -21,10 +21,22 @@ export const PROTOCOL_VERSION = protocolVersion.version;
  */
 export const PROTOCOL_MISMATCH_CLOSE_CODE = protocolVersion.mismatchCloseCode;
 
+/**
+ * The render pass a geometry is drawn in. Translucent geometry blends and is
+ * drawn last, back to front.
+ */
+export type RenderLayer = "OPAQUE" | "CUTOUT" | "TRANSLUCENT";
+
+/**
+ * A decoded chunk geometry. Servers may ship its buffers in the compact
+ * `mesh.v1` layout negotiated through the JOIN capabilities; the network
+ * worker unpacks those into the same float buffers before they get here.
+ */
 export type GeometryProtocol = {
   voxel: number;
   at?: [number, number, number];
   faceName?: string;
+  layer?: RenderLayer;
   positions: Float32Array | Uint16Array;
   uvs: Float32Array | Uint16Array;
   indices: Uint32Array;
@@ -148,6 +160,12 @@ export type MessageProtocol<
    */
   tick?: number;
 
+  /**
+   * Per-session reliable sequence number, only stamped for sessions that were
+   * issued a resume token. Zero when the session is not resumable.
+   */
+  seq?: number;
+
   chat?: ChatProtocol;
   method?: MethodProtocol<Method>;
 
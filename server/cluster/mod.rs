@@ -0,0 +1,103 @@
+//! Running worlds across several server processes.
+//!
+//! A single [`Server`](crate::Server) actor owns every world it hosts, so one
+//! process is the unit of scale. A cluster splits worlds across processes:
+//!
+//! - Every process is a *node*: an ordinary Voxelize server that additionally
+//!   mounts [`ClusterNode::configure`], which lets a world be drained into a
+//!   [`WorldSnapshot`](crate::WorldSnapshot) and adopted from one.
+//! - A lightweight [`ClusterRouter`] is the only address clients see. It
+//!   assigns each world to a node, forwards every `/ws/` session to the node
+//!   that owns the world named in its JOIN, and migrates worlds between nodes
+//!   on request.
+//!
+//! Nodes and the router only talk HTTP/WebSocket to each other, so they can
+//! be separate processes on one machine (loopback) or on separate hosts.
+//!
+//! Worlds are re-created on the receiving node from a [`WorldConfig`], so
+//! only worlds a node can build from config alone (the same worlds
+//! [`CreateWorld`](crate::CreateWorld) can create) can migrate.
+//!
+//! [`WorldConfig`]: crate::WorldConfig
+
+mod node;
+mod router;
+
+pub use node::*;
+pub use router::*;
+
+/// Header carrying the cluster secret on node and router admin routes.
+pub const CLUSTER_SECRET_HEADER: &str = "x-voxelize-cluster-secret";
+
+/// Largest world snapshot a node accepts or the router forwards.
+pub const MAX_WORLD_SNAPSHOT_BYTES: usize = 256 * 1024 * 1024;
+
+/// Why a node or router can't be built with a cluster secret.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ClusterSecretError {
+    /// An empty secret would admit anyone sending an empty header.
+    #[error("the cluster secret must not be empty")]
+    Empty,
+}
+
+/// Check a secret before a node or router is built with it.
+fn validate_cluster_secret(secret: &str) -> Result<(), ClusterSecretError> {
+    if secret.is_empty() {
+        return Err(ClusterSecretError::Empty);
+    }
+    Ok(())
+}
+
+/// Whether a request carries the cluster secret. The admin routes move whole
+/// worlds between processes, so a cluster can't run without one.
+fn has_cluster_secret(req: &actix_web::HttpRequest, secret: &str) -> bool {
+    req.headers()
+        .get(CLUSTER_SECRET_HEADER)
+        .is_some_and(|value| constant_time_eq(value.as_bytes(), secret.as_bytes()))
+}
+
+/// Compare in time that depends only on the lengths, so response times
+/// don't reveal how much of a guessed secret was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let diff = a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y));
+    std::hint::black_box(diff) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    #[test]
+    fn only_the_exact_secret_is_accepted() {
+        let request = |value: &str| {
+            TestRequest::default()
+                .insert_header((CLUSTER_SECRET_HEADER, value))
+                .to_http_request()
+        };
+
+        assert!(has_cluster_secret(&request("hunter2"), "hunter2"));
+        assert!(!has_cluster_secret(&request("hunter3"), "hunter2"));
+        assert!(!has_cluster_secret(&request("hunter"), "hunter2"));
+        assert!(!has_cluster_secret(&request("hunter22"), "hunter2"));
+        assert!(!has_cluster_secret(&request(""), "hunter2"));
+        assert!(!has_cluster_secret(
+            &TestRequest::default().to_http_request(),
+            "hunter2"
+        ));
+    }
+
+    #[test]
+    fn an_empty_secret_is_refused() {
+        assert_eq!(
+            ClusterRouter::new("").err(),
+            Some(ClusterSecretError::Empty)
+        );
+        assert!(ClusterRouter::new("hunter2").is_ok());
+    }
+}
@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use actix::Addr;
+use actix_web::{web, HttpRequest, HttpResponse};
+use log::warn;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    AdoptWorld, DrainWorld, GcPolicy, ListWorlds, Server, WorldConfig, WorldLifecycleError,
+    WorldSnapshot,
+};
+
+use super::{
+    has_cluster_secret, validate_cluster_secret, ClusterSecretError, MAX_WORLD_SNAPSHOT_BYTES,
+};
+
+/// Looks up the config a world should be created with when this node adopts
+/// it. `None` means this node cannot host the world.
+pub type WorldConfigResolver = Arc<dyn Fn(&str) -> Option<WorldConfig> + Send + Sync>;
+
+/// The node side of a cluster: admin routes that let a [`ClusterRouter`]
+/// move worlds on and off this server. Every route requires the cluster
+/// secret in [`super::CLUSTER_SECRET_HEADER`].
+///
+/// - `GET /cluster/worlds` — names of the worlds live on this node
+/// - `POST /cluster/drain` — `{ "world": name }`; detaches the world and
+///   answers its [`WorldSnapshot`]
+/// - `POST /cluster/adopt` — a [`WorldSnapshot`] body; creates the world
+///   from the resolved config and restores the snapshot into it
+///
+/// Mount it next to the engine routes:
+/// `App::new().configure(voxelize.configure()).configure(node.configure())`.
+///
+/// [`ClusterRouter`]: crate::ClusterRouter
+#[derive(Clone)]
+pub struct ClusterNode {
+    server: Addr<Server>,
+    secret: String,
+    configs: WorldConfigResolver,
+    gc_policy: GcPolicy,
+}
+
+#[derive(Deserialize)]
+struct DrainRequest {
+    world: String,
+}
+
+impl ClusterNode {
+    /// Serve cluster routes for `server` to callers holding `secret`,
+    /// creating adopted worlds with the configs `configs` resolves. Fails if
+    /// `secret` is empty.
+    pub fn new<F>(
+        server: Addr<Server>,
+        secret: &str,
+        configs: F,
+    ) -> Result<Self, ClusterSecretError>
+    where
+        F: Fn(&str) -> Option<WorldConfig> + Send + Sync + 'static,
+    {
+        validate_cluster_secret(secret)?;
+
+        Ok(Self {
+            server,
+            secret: secret.to_owned(),
+            configs: Arc::new(configs),
+            gc_policy: GcPolicy::Never,
+        })
+    }
+
+    /// Garbage-collection policy for adopted worlds. Defaults to
+    /// [`GcPolicy::Never`].
+    pub fn with_gc_policy(mut self, gc_policy: GcPolicy) -> Self {
+        self.gc_policy = gc_policy;
+        self
+    }
+
+    /// Mount the cluster routes onto an Actix `App`.
+    pub fn configure(&self) -> impl FnOnce(&mut web::ServiceConfig) {
+        let node = self.clone();
+        move |cfg| {
+            cfg.app_data(web::Data::new(node))
+                .route("/cluster/worlds", web::get().to(list_route))
+                .route("/cluster/drain", web::post().to(drain_route))
+                .service(
+                    web::resource("/cluster/adopt")
+                        .app_data(web::JsonConfig::default().limit(MAX_WORLD_SNAPSHOT_BYTES))
+                        .route(web::post().to(adopt_route)),
+                );
+        }
+    }
+}
+
+fn forbidden() -> HttpResponse {
+    HttpResponse::Forbidden().json(json!({
+        "ok": false,
+        "error": "wrong cluster secret",
+    }))
+}
+
+fn unreachable(error: actix::MailboxError) -> HttpResponse {
+    HttpResponse::ServiceUnavailable().json(json!({
+        "ok": false,
+        "error": format!("server actor unreachable: {}", error),
+    }))
+}
+
+fn lifecycle_error(error: WorldLifecycleError) -> HttpResponse {
+    let mut response = match error {
+        WorldLifecycleError::NotFound(_) => HttpResponse::NotFound(),
+        WorldLifecycleError::DuplicateName(_) | WorldLifecycleError::TeardownInFlight(_) => {
+            HttpResponse::Conflict()
+        }
+        WorldLifecycleError::CapacityReached { .. } => HttpResponse::ServiceUnavailable(),
+        WorldLifecycleError::InvalidConfig(_) => HttpResponse::BadRequest(),
+        WorldLifecycleError::SnapshotFailed(_) => HttpResponse::InternalServerError(),
+    };
+    response.json(json!({
+        "ok": false,
+        "error": error.to_string(),
+    }))
+}
+
+async fn list_route(req: HttpRequest, node: web::Data<ClusterNode>) -> HttpResponse {
+    if !has_cluster_secret(&req, &node.secret) {
+        return forbidden();
+    }
+
+    match node.server.send(ListWorlds).await {
+        Ok(worlds) => {
+            let mut names: Vec<String> = worlds.into_iter().map(|world| world.name).collect();
+            names.sort();
+            HttpResponse::Ok().json(names)
+        }
+        Err(error) => unreachable(error),
+    }
+}
+
+async fn drain_route(
+    req: HttpRequest,
+    node: web::Data<ClusterNode>,
+    body: web::Json<DrainRequest>,
+) -> HttpResponse {
+    if !has_cluster_secret(&req, &node.secret) {
+        return forbidden();
+    }
+
+    match node
+        .server
+        .send(DrainWorld {
+            name: body.into_inner().world,
+        })
+        .await
+    {
+        Ok(Ok(snapshot)) => HttpResponse::Ok().json(snapshot),
+        Ok(Err(error)) => lifecycle_error(error),
+        Err(error) => unreachable(error),
+    }
+}
+
+async fn adopt_route(
+    req: HttpRequest,
+    node: web::Data<ClusterNode>,
+    snapshot: web::Json<WorldSnapshot>,
+) -> HttpResponse {
+    if !has_cluster_secret(&req, &node.secret) {
+        return forbidden();
+    }
+
+    let snapshot = snapshot.into_inner();
+    let Some(config) = (node.configs)(&snapshot.name) else {
+        warn!(
+            "Refusing to adopt world {:?}: no config resolves for it on this node",
+            snapshot.name
+        );
+        return HttpResponse::NotFound().json(json!({
+            "ok": false,
+            "error": format!("no config for world '{}' on this node", snapshot.name),
+        }));
+    };
+
+    match node
+        .server
+        .send(AdoptWorld {
+            snapshot,
+            config,
+            gc_policy: node.gc_policy.clone(),
+        })
+        .await
+    {
+        Ok(Ok(handle)) => HttpResponse::Ok().json(json!({
+            "ok": true,
+            "world": handle.name,
+        })),
+        Ok(Err(error)) => lifecycle_error(error),
+        Err(error) => unreachable(error),
+    }
+}
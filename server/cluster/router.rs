@@ -0,0 +1,695 @@
+use std::collections::BTreeMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use actix_web::{
+    http::header,
+    web::{self, Bytes},
+    Error, HttpRequest, HttpResponse,
+};
+use actix_ws::{AggregatedMessage, CloseCode, CloseReason};
+use awc::{error::WsProtocolError, ws};
+use futures_util::{Sink, SinkExt, Stream, StreamExt};
+use hashbrown::{HashMap, HashSet};
+use log::{error, info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+
+use crate::{decode_message, MessageType};
+
+use super::{
+    has_cluster_secret, validate_cluster_secret, ClusterSecretError, CLUSTER_SECRET_HEADER,
+    MAX_WORLD_SNAPSHOT_BYTES,
+};
+
+/// Upper bound on one router-to-node admin request (draining or adopting a
+/// large world can take a while).
+const NODE_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long a JOIN for a migrating world waits for the migration to finish
+/// before the session is refused.
+const MIGRATION_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+const MIGRATION_POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// Frames a client may send before its first JOIN names a world. They are
+/// held and replayed to the node once the session is routed.
+const MAX_UNROUTED_FRAMES: usize = 64;
+
+/// Matches the engine's own `/ws/` frame limit.
+const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// Close description sent to clients whose world moved to another node.
+pub const WORLD_MIGRATED_REASON: &str = "World migrated to another node; reconnect to rejoin.";
+
+/// Typed failure of a router operation.
+#[derive(Debug, thiserror::Error)]
+pub enum ClusterRouterError {
+    #[error("the cluster has no nodes")]
+    NoNodes,
+    #[error("node '{0}' is not part of the cluster")]
+    UnknownNode(String),
+    #[error("world '{0}' is already being migrated")]
+    MigrationInFlight(String),
+    #[error("timed out waiting for world '{0}' to finish migrating")]
+    MigrationTimedOut(String),
+    #[error("node '{node}' could not be reached: {reason}")]
+    NodeUnreachable { node: String, reason: String },
+    #[error("node '{node}' refused the request ({status}): {body}")]
+    NodeRefused {
+        node: String,
+        status: u16,
+        body: String,
+    },
+}
+
+/// A client session the router is forwarding, and the world it last joined.
+struct ProxiedSession {
+    world: String,
+    evict: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Default)]
+struct RouterState {
+    /// Node id -> base HTTP URL.
+    nodes: BTreeMap<String, String>,
+    /// Explicit world -> node id assignments.
+    assignments: HashMap<String, String>,
+    migrating: HashSet<String>,
+    sessions: HashMap<u64, ProxiedSession>,
+    next_session: u64,
+}
+
+impl RouterState {
+    /// The node owning `world`: its explicit assignment, or else the node
+    /// rendezvous hashing picks, so unassigned worlds spread across nodes
+    /// and keep their node while the node set is unchanged.
+    fn owner(&self, world: &str) -> Option<(String, String)> {
+        let node = self
+            .assignments
+            .get(world)
+            .filter(|node| self.nodes.contains_key(*node))
+            .or_else(|| {
+                self.nodes
+                    .keys()
+                    .max_by_key(|node| rendezvous_weight(world, node))
+            })?;
+        Some((node.to_owned(), self.nodes[node].to_owned()))
+    }
+}
+
+/// FNV-1a over the world and node ids. Stable across processes and builds,
+/// so separate router processes agree on unassigned worlds.
+fn rendezvous_weight(world: &str, node: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in world.bytes().chain([0xff]).chain(node.bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// The front door of a cluster: assigns worlds to nodes, forwards client
+/// sessions to the node owning their world, and migrates worlds between
+/// nodes.
+///
+/// - `GET /ws/` — accepts a client session and forwards it, frame for frame,
+///   to the owning node's `/ws/` (with the same query string). The target
+///   node is picked from the world in the client's JOIN, and a later JOIN
+///   for a world on a different node re-dials.
+/// - `GET /cluster/assignments` — the nodes and explicit world assignments
+/// - `POST /cluster/migrate` — `{ "world": name, "to": node }`; drains the
+///   world from its node, adopts it on `to`, and closes every session that
+///   was in it with [`CloseCode::Restart`] so clients reconnect and rejoin
+///   on the new node
+///
+/// ```no_run
+/// use actix_web::{App, HttpServer};
+/// use voxelize::ClusterRouter;
+///
+/// #[actix_web::main]
+/// async fn main() -> std::io::Result<()> {
+///     let router = ClusterRouter::new("cluster-secret")
+///         .expect("the secret is not empty")
+///         .node("a", "http://127.0.0.1:4001")
+///         .node("b", "http://127.0.0.1:4002")
+///         .assign("lobby", "a");
+///
+///     HttpServer::new(move || App::new().configure(router.configure()))
+///         .bind(("0.0.0.0", 4000))?
+///         .run()
+///         .await
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ClusterRouter {
+    state: Arc<Mutex<RouterState>>,
+    secret: String,
+}
+
+impl ClusterRouter {
+    /// A router with no nodes. `secret` is required on the router's admin
+    /// routes and sent to nodes, which must share it. Fails if `secret` is
+    /// empty.
+    pub fn new(secret: &str) -> Result<Self, ClusterSecretError> {
+        validate_cluster_secret(secret)?;
+
+        Ok(Self {
+            state: Arc::default(),
+            secret: secret.to_owned(),
+        })
+    }
+
+    /// Add a node, reachable at `url` (e.g. `http://10.0.0.2:4000`).
+    pub fn node(self, id: &str, url: &str) -> Self {
+        self.state()
+            .nodes
+            .insert(id.to_owned(), url.trim_end_matches('/').to_owned());
+        self
+    }
+
+    /// Pin `world` to `node`. Worlds a node registers at startup must be
+    /// pinned to it; unpinned worlds go wherever rendezvous hashing puts them.
+    pub fn assign(self, world: &str, node: &str) -> Self {
+        self.state()
+            .assignments
+            .insert(world.to_owned(), node.to_owned());
+        self
+    }
+
+    /// The node id currently owning `world`.
+    pub fn owner(&self, world: &str) -> Option<String> {
+        self.state().owner(world).map(|(node, _)| node)
+    }
+
+    /// Explicit world -> node assignments, including every completed
+    /// migration.
+    pub fn assignments(&self) -> BTreeMap<String, String> {
+        self.state()
+            .assignments
+            .iter()
+            .map(|(world, node)| (world.to_owned(), node.to_owned()))
+            .collect()
+    }
+
+    /// Move `world` to node `to`. New sessions for the world wait while it
+    /// moves; sessions already in it are closed once it is drained. If the
+    /// target refuses the snapshot, it is handed back to the node it came
+    /// from.
+    pub async fn migrate(&self, world: &str, to: &str) -> Result<(), ClusterRouterError> {
+        let (from, from_url, to_url) = {
+            let mut state = self.state();
+            let to_url = state
+                .nodes
+                .get(to)
+                .cloned()
+                .ok_or_else(|| ClusterRouterError::UnknownNode(to.to_owned()))?;
+            let (from, from_url) = state.owner(world).ok_or(ClusterRouterError::NoNodes)?;
+            if from == to {
+                return Ok(());
+            }
+            if !state.migrating.insert(world.to_owned()) {
+                return Err(ClusterRouterError::MigrationInFlight(world.to_owned()));
+            }
+            (from, from_url, to_url)
+        };
+
+        let started = Instant::now();
+        let result = self
+            .hand_off(world, (&from, &from_url), (to, &to_url))
+            .await;
+
+        let mut state = self.state();
+        state.migrating.remove(world);
+        if result.is_ok() {
+            state.assignments.insert(world.to_owned(), to.to_owned());
+            info!(
+                "[Cluster] Migrated world {:?} from node {:?} to {:?} in {:?}",
+                world,
+                from,
+                to,
+                started.elapsed()
+            );
+        }
+        result
+    }
+
+    /// Mount the router routes onto an Actix `App`.
+    pub fn configure(&self) -> impl FnOnce(&mut web::ServiceConfig) {
+        let router = self.clone();
+        move |cfg| {
+            cfg.app_data(web::Data::new(router))
+                .route("/ws/", web::get().to(proxy_route))
+                .route("/cluster/assignments", web::get().to(assignments_route))
+                .route("/cluster/migrate", web::post().to(migrate_route));
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, RouterState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    async fn hand_off(
+        &self,
+        world: &str,
+        (from, from_url): (&str, &str),
+        (to, to_url): (&str, &str),
+    ) -> Result<(), ClusterRouterError> {
+        let drain = Bytes::from(json!({ "world": world }).to_string());
+        let snapshot = self
+            .post_to_node(from, &format!("{}/cluster/drain", from_url), drain)
+            .await?;
+
+        self.evict(world);
+
+        if let Err(error) = self
+            .post_to_node(to, &format!("{}/cluster/adopt", to_url), snapshot.clone())
+            .await
+        {
+            if let Err(rollback) = self
+                .post_to_node(from, &format!("{}/cluster/adopt", from_url), snapshot)
+                .await
+            {
+                error!(
+                    "[Cluster] World {:?} could not be handed back to node {:?} after a failed \
+                     migration ({}); it is offline until adopted manually",
+                    world, from, rollback
+                );
+            }
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    async fn post_to_node(
+        &self,
+        node: &str,
+        url: &str,
+        body: Bytes,
+    ) -> Result<Bytes, ClusterRouterError> {
+        let unreachable = |reason: String| ClusterRouterError::NodeUnreachable {
+            node: node.to_owned(),
+            reason,
+        };
+
+        let mut response = awc::Client::builder()
+            .timeout(NODE_REQUEST_TIMEOUT)
+            .finish()
+            .post(url)
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .insert_header((CLUSTER_SECRET_HEADER, self.secret.as_str()))
+            .send_body(body)
+            .await
+            .map_err(|error| unreachable(error.to_string()))?;
+        let payload = response
+            .body()
+            .limit(MAX_WORLD_SNAPSHOT_BYTES)
+            .await
+            .map_err(|error| unreachable(error.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(ClusterRouterError::NodeRefused {
+                node: node.to_owned(),
+                status: response.status().as_u16(),
+                body: String::from_utf8_lossy(&payload).into_owned(),
+            });
+        }
+
+        Ok(payload)
+    }
+
+    /// The node to route a JOIN for `world` to, waiting out a migration in
+    /// flight.
+    async fn route(&self, world: &str) -> Result<(String, String), ClusterRouterError> {
+        let deadline = Instant::now() + MIGRATION_WAIT_TIMEOUT;
+        loop {
+            {
+                let state = self.state();
+                if !state.migrating.contains(world) {
+                    return state.owner(world).ok_or(ClusterRouterError::NoNodes);
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err(ClusterRouterError::MigrationTimedOut(world.to_owned()));
+            }
+            actix_web::rt::time::sleep(MIGRATION_POLL_INTERVAL).await;
+        }
+    }
+
+    async fn dial(
+        &self,
+        (node, url): (String, String),
+        world: &str,
+        query: &str,
+    ) -> Result<Upstream, ClusterRouterError> {
+        let ws_url = if query.is_empty() {
+            format!("{}/ws/", url)
+        } else {
+            format!("{}/ws/?{}", url, query)
+        };
+
+        let (_, framed) = awc::Client::builder()
+            .timeout(NODE_REQUEST_TIMEOUT)
+            .finish()
+            .ws(ws_url)
+            .max_frame_size(MAX_FRAME_SIZE)
+            .connect()
+            .await
+            .map_err(|error| ClusterRouterError::NodeUnreachable {
+                node: node.clone(),
+                reason: error.to_string(),
+            })?;
+        let (sink, stream) = framed.split();
+
+        let (evict, evicted) = oneshot::channel();
+        let key = {
+            let mut state = self.state();
+            state.next_session += 1;
+            let key = state.next_session;
+            state.sessions.insert(
+                key,
+                ProxiedSession {
+                    world: world.to_owned(),
+                    evict: Some(evict),
+                },
+            );
+            key
+        };
+
+        Ok(Upstream {
+            node,
+            key,
+            sink: Box::pin(sink),
+            stream: Box::pin(stream),
+            evicted,
+        })
+    }
+
+    /// Record that a routed session joined another world on the same node.
+    fn rebind(&self, key: u64, world: &str) {
+        if let Some(session) = self.state().sessions.get_mut(&key) {
+            session.world = world.to_owned();
+        }
+    }
+
+    fn forget(&self, key: u64) {
+        self.state().sessions.remove(&key);
+    }
+
+    /// Close every routed session in `world`.
+    fn evict(&self, world: &str) {
+        let mut state = self.state();
+        let mut evicted = 0;
+        for session in state.sessions.values_mut() {
+            if session.world == world {
+                if let Some(evict) = session.evict.take() {
+                    let _ = evict.send(());
+                    evicted += 1;
+                }
+            }
+        }
+        info!(
+            "[Cluster] Closing {} session(s) in migrating world {:?}",
+            evicted, world
+        );
+    }
+}
+
+/// A routed session's connection to its node.
+struct Upstream {
+    node: String,
+    key: u64,
+    sink: Pin<Box<dyn Sink<ws::Message, Error = WsProtocolError>>>,
+    stream: Pin<Box<dyn Stream<Item = Result<ws::Frame, WsProtocolError>>>>,
+    evicted: oneshot::Receiver<()>,
+}
+
+enum UpstreamEvent {
+    Frame(Option<Result<ws::Frame, WsProtocolError>>),
+    Evicted,
+}
+
+/// The next thing that happens on the node side; never resolves while the
+/// session is not routed yet.
+async fn next_upstream_event(upstream: &mut Option<Upstream>) -> UpstreamEvent {
+    let Some(upstream) = upstream else {
+        return std::future::pending().await;
+    };
+    tokio::select! {
+        frame = upstream.stream.next() => UpstreamEvent::Frame(frame),
+        _ = &mut upstream.evicted => UpstreamEvent::Evicted,
+    }
+}
+
+/// The world a client frame joins, if it is a JOIN.
+fn joined_world(bytes: &[u8]) -> Option<String> {
+    let message = decode_message(bytes).ok()?;
+    if message.r#type != MessageType::Join as i32 {
+        return None;
+    }
+    let json: Value = serde_json::from_str(&message.json).ok()?;
+    json.get("world")?.as_str().map(str::to_owned)
+}
+
+/// `GET /ws/` on the router — accept the client and forward it to a node.
+async fn proxy_route(
+    req: HttpRequest,
+    body: web::Payload,
+    router: web::Data<ClusterRouter>,
+) -> Result<HttpResponse, Error> {
+    let (response, session, stream) = actix_ws::handle(&req, body)?;
+
+    let stream = stream
+        .max_frame_size(MAX_FRAME_SIZE)
+        .aggregate_continuations()
+        .max_continuation_size(MAX_FRAME_SIZE);
+
+    actix_web::rt::spawn(run_proxied_session(
+        router.get_ref().clone(),
+        req.query_string().to_owned(),
+        session,
+        stream,
+    ));
+
+    Ok(response)
+}
+
+/// Forward one client session until either side closes or its world
+/// migrates. Pings are forwarded rather than answered, so the node's idle
+/// reaping still sees a dead client.
+async fn run_proxied_session(
+    router: ClusterRouter,
+    query: String,
+    mut session: actix_ws::Session,
+    mut stream: impl Stream<Item = Result<AggregatedMessage, actix_ws::ProtocolError>> + Unpin,
+) {
+    let mut upstream: Option<Upstream> = None;
+    let mut unrouted: Vec<Bytes> = Vec::new();
+    let mut close_reason: Option<CloseReason> = None;
+
+    loop {
+        tokio::select! {
+            event = next_upstream_event(&mut upstream) => match event {
+                UpstreamEvent::Frame(Some(Ok(ws::Frame::Binary(bytes)))) => {
+                    if session.binary(bytes).await.is_err() {
+                        break;
+                    }
+                }
+                UpstreamEvent::Frame(Some(Ok(ws::Frame::Ping(data)))) => {
+                    if session.ping(&data).await.is_err() {
+                        break;
+                    }
+                }
+                UpstreamEvent::Frame(Some(Ok(ws::Frame::Pong(data)))) => {
+                    if session.pong(&data).await.is_err() {
+                        break;
+                    }
+                }
+                UpstreamEvent::Frame(Some(Ok(ws::Frame::Close(reason)))) => {
+                    close_reason = reason;
+                    break;
+                }
+                UpstreamEvent::Frame(Some(Ok(_))) => {}
+                UpstreamEvent::Frame(Some(Err(_)) | None) => break,
+                UpstreamEvent::Evicted => {
+                    close_reason = Some(CloseReason {
+                        code: CloseCode::Restart,
+                        description: Some(WORLD_MIGRATED_REASON.to_owned()),
+                    });
+                    break;
+                }
+            },
+            msg = stream.next() => match msg {
+                Some(Ok(AggregatedMessage::Binary(bytes))) => {
+                    if let Some(world) = joined_world(&bytes) {
+                        let target = match router.route(&world).await {
+                            Ok(target) => target,
+                            Err(error) => {
+                                warn!("[Cluster] Could not route a JOIN for {:?}: {}", world, error);
+                                close_reason = Some(CloseReason {
+                                    code: CloseCode::Again,
+                                    description: Some(error.to_string()),
+                                });
+                                break;
+                            }
+                        };
+
+                        match &upstream {
+                            Some(current) if current.node == target.0 => {
+                                router.rebind(current.key, &world);
+                            }
+                            _ => {
+                                if let Some(mut previous) = upstream.take() {
+                                    let _ = previous.sink.send(ws::Message::Close(None)).await;
+                                    router.forget(previous.key);
+                                }
+                                match router.dial(target, &world, &query).await {
+                                    Ok(dialed) => upstream = Some(dialed),
+                                    Err(error) => {
+                                        warn!("[Cluster] Could not forward a session for {:?}: {}", world, error);
+                                        close_reason = Some(CloseReason {
+                                            code: CloseCode::Again,
+                                            description: Some(error.to_string()),
+                                        });
+                                        break;
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    let Some(current) = upstream.as_mut() else {
+                        if unrouted.len() >= MAX_UNROUTED_FRAMES {
+                            warn!("[Cluster] Dropping a session that sent {} frames without a JOIN", unrouted.len());
+                            break;
+                        }
+                        unrouted.push(bytes);
+                        continue;
+                    };
+
+                    let mut forwarded = true;
+                    for frame in unrouted.drain(..).chain([bytes]) {
+                        if current.sink.send(ws::Message::Binary(frame)).await.is_err() {
+                            forwarded = false;
+                            break;
+                        }
+                    }
+                    if !forwarded {
+                        break;
+                    }
+                }
+                Some(Ok(AggregatedMessage::Ping(data))) => match upstream.as_mut() {
+                    Some(current) => {
+                        if current.sink.send(ws::Message::Ping(data)).await.is_err() {
+                            break;
+                        }
+                    }
+                    None => {
+                        if session.pong(&data).await.is_err() {
+                            break;
+                        }
+                    }
+                },
+                Some(Ok(AggregatedMessage::Pong(data))) => {
+                    if let Some(current) = upstream.as_mut() {
+                        if current.sink.send(ws::Message::Pong(data)).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+                Some(Ok(AggregatedMessage::Close(reason))) => {
+                    close_reason = reason;
+                    break;
+                }
+                Some(Ok(_)) => {}
+                Some(Err(error)) => {
+                    warn!("[Cluster] Protocol error on a routed session: {:?}", error);
+                    break;
+                }
+                None => break,
+            },
+        }
+    }
+
+    if let Some(mut current) = upstream {
+        let _ = current
+            .sink
+            .send(ws::Message::Close(close_reason.clone()))
+            .await;
+        router.forget(current.key);
+    }
+    let _ = session.close(close_reason).await;
+}
+
+#[derive(Deserialize)]
+struct MigrateRequest {
+    world: String,
+    to: String,
+}
+
+fn router_error(error: &ClusterRouterError) -> HttpResponse {
+    let mut response = match error {
+        ClusterRouterError::NoNodes | ClusterRouterError::UnknownNode(_) => {
+            HttpResponse::NotFound()
+        }
+        ClusterRouterError::MigrationInFlight(_) => HttpResponse::Conflict(),
+        ClusterRouterError::MigrationTimedOut(_) => HttpResponse::GatewayTimeout(),
+        ClusterRouterError::NodeUnreachable { .. } | ClusterRouterError::NodeRefused { .. } => {
+            HttpResponse::BadGateway()
+        }
+    };
+    response.json(json!({
+        "ok": false,
+        "error": error.to_string(),
+    }))
+}
+
+fn forbidden() -> HttpResponse {
+    HttpResponse::Forbidden().json(json!({
+        "ok": false,
+        "error": "wrong cluster secret",
+    }))
+}
+
+async fn assignments_route(req: HttpRequest, router: web::Data<ClusterRouter>) -> HttpResponse {
+    if !has_cluster_secret(&req, &router.secret) {
+        return forbidden();
+    }
+
+    let nodes = router.state().nodes.clone();
+    HttpResponse::Ok().json(json!({
+        "nodes": nodes,
+        "assignments": router.assignments(),
+    }))
+}
+
+async fn migrate_route(
+    req: HttpRequest,
+    router: web::Data<ClusterRouter>,
+    body: web::Json<MigrateRequest>,
+) -> HttpResponse {
+    if !has_cluster_secret(&req, &router.secret) {
+        return forbidden();
+    }
+
+    let MigrateRequest { world, to } = body.into_inner();
+    match router.migrate(&world, &to).await {
+        Ok(()) => HttpResponse::Ok().json(json!({
+            "ok": true,
+            "world": world,
+            "node": to,
+        })),
+        Err(error) => {
+            warn!(
+                "[Cluster] Migrating {:?} to {:?} failed: {}",
+                world, to, error
+            );
+            router_error(&error)
+        }
+    }
+}
@@ -1,3 +1,4 @@
+mod cluster;
 mod common;
 mod errors;
 mod libs;
@@ -13,13 +14,19 @@ use std::sync::Arc;
 use hashbrown::HashMap;
 use tokio::sync::{mpsc, Mutex};
 
+pub use cluster::*;
 pub use common::*;
 pub use libs::*;
 pub use runtime::*;
 pub use server::*;
 pub use types::*;
-pub use webrtc::signaling::{rtc_candidate, rtc_offer, WebRTCPeers};
-pub use webrtc::{create_webrtc_api, datachannel::fragment_message};
+pub use webrtc::signaling::{
+    is_state_channel, rtc_candidate, rtc_offer, RtcIceServers, WebRTCPeers,
+};
+pub use webrtc::{
+    create_webrtc_api,
+    datachannel::{fragment_message, FragmentAssembler},
+};
 pub use world::system_profiler::{
     clear_timing_data_for_world, get_all_world_names, get_timing_summary_for_world, SystemTimer,
     TimedDispatcherBuilder, TimedSystem, WorldTimingContext,
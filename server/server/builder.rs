@@ -1,6 +1,9 @@
+use std::{path::PathBuf, time::Duration};
+
 use hashbrown::{HashMap, HashSet};
+use log::info;
 
-use crate::world::Registry;
+use crate::world::{AtlasOptions, BlockModels, Registry, TextureAtlas};
 
 use super::lifecycle::{PoolConfig, WorldLifecycleMetrics};
 use super::{
@@ -22,7 +25,10 @@ pub struct ServerBuilder {
     interval: u64,
     secret: Option<String>,
     registry: Option<Registry>,
+    models: Option<PathBuf>,
+    atlas: Option<(PathBuf, PathBuf, AtlasOptions)>,
     build_identity: BuildIdentity,
+    session_resume_grace: Option<Duration>,
     pub(super) max_worlds: Option<usize>,
     pub(super) world_pool: Option<PoolConfig>,
 }
@@ -38,7 +44,10 @@ impl ServerBuilder {
             interval: DEFAULT_INTERVAL,
             secret: None,
             registry: None,
+            models: None,
+            atlas: None,
             build_identity: BuildIdentity::default(),
+            session_resume_grace: None,
             max_worlds: None,
             world_pool: None,
         }
@@ -87,6 +96,24 @@ impl ServerBuilder {
         self
     }
 
+    /// Load the block models in `directory` (see [`BlockModels::load`]) when
+    /// the server is built, and compile them into the faces and bounding
+    /// boxes of the blocks built with `BlockBuilder::model_name`. Panics on
+    /// build if a model fails to load or compile.
+    pub fn models(mut self, directory: &str) -> Self {
+        self.models = Some(directory.into());
+        self
+    }
+
+    /// Pack the block textures in `textures` into `atlas.png` and
+    /// `atlas.json` in `output` when the server is built, and point the
+    /// registry's face UVs into it. Put `output` under the served folder so
+    /// clients can load the same image. Panics on build if packing fails.
+    pub fn atlas(mut self, textures: &str, output: &str, options: AtlasOptions) -> Self {
+        self.atlas = Some((textures.into(), output.into(), options));
+        self
+    }
+
     /// Stamp the compile-time identity this server reports on `/info`.
     /// Without it every identity field reads "unknown".
     pub fn build_identity(mut self, build_identity: BuildIdentity) -> Self {
@@ -94,15 +121,44 @@ impl ServerBuilder {
         self
     }
 
+    /// Let clients whose socket drops resume their session within `grace`:
+    /// the entity stays in its world, and a reconnect that presents the
+    /// resume token from its INIT gets the reliable messages it missed
+    /// replayed instead of a full rejoin and chunk reload. Off by default.
+    pub fn session_resume_grace(mut self, grace: Duration) -> Self {
+        self.session_resume_grace = Some(grace);
+        self
+    }
+
     /// Instantiate a voxelize server instance.
     pub fn build(self) -> Server {
         let mut registry = self.registry.unwrap_or(Registry::new());
+        let modeled = self.models.as_ref().map(|directory| {
+            BlockModels::load(directory)
+                .and_then(|models| registry.load_models(&models))
+                .unwrap_or_else(|error| panic!("Failed to load block models: {}", error))
+        });
         registry.generate();
 
         if self.debug {
             Server::setup_logger();
         }
 
+        if let Some(modeled) = modeled {
+            info!("Compiled block models for {} blocks.", modeled);
+        }
+
+        if let Some((textures, output, options)) = &self.atlas {
+            let atlas = TextureAtlas::pack(textures, &registry, options)
+                .and_then(|atlas| atlas.write(output, "atlas").map(|_| atlas))
+                .unwrap_or_else(|error| panic!("Failed to build texture atlas: {}", error));
+            let faces = registry.apply_atlas(&atlas.manifest);
+            info!(
+                "Packed block textures into a {}x{} atlas of {} cells per side for {} block faces.",
+                atlas.width, atlas.height, atlas.manifest.count_per_side, faces
+            );
+        }
+
         Server {
             port: self.port,
             addr: self.addr,
@@ -116,6 +172,9 @@ impl ServerBuilder {
             started: false,
 
             connections: HashMap::default(),
+            suspended_sessions: HashMap::default(),
+            resume_tokens: HashMap::default(),
+            session_resume_grace: self.session_resume_grace,
             lost_sessions: HashMap::default(),
             transport_sessions: HashMap::default(),
             pending_world_ticks: HashSet::default(),
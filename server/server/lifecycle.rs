@@ -15,15 +15,16 @@
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use actix::fut::wrap_future;
+use actix::fut::{self, wrap_future};
 use actix::{
     ActorFutureExt, Addr, AsyncContext, Context, Handler, Message as ActixMessage, MessageResult,
-    SpawnHandle,
+    ResponseActFuture, SpawnHandle,
 };
 use log::info;
 
 use crate::{
-    perf, InboundStateBuffer, ResetWorld, Server, SyncWorld, Teardown, World, WorldConfig,
+    perf, InboundStateBuffer, LoadReplay, Replay, ResetWorld, RestoreWorld, Server, SnapshotWorld,
+    SyncWorld, Teardown, World, WorldConfig, WorldSnapshot, WORLD_SNAPSHOT_VERSION,
 };
 
 /// Immutable, cheaply-cloneable descriptor of a live world, snapshotted at
@@ -74,6 +75,8 @@ pub enum WorldLifecycleError {
     TeardownInFlight(String),
     #[error("invalid world config: {0}")]
     InvalidConfig(String),
+    #[error("world '{0}' stopped before its snapshot was taken")]
+    SnapshotFailed(String),
 }
 
 /// Warm pool for the many-small-worlds use case: worlds kept ready so
@@ -240,6 +243,39 @@ pub struct LifecycleMetricsSnapshot {
 #[rtype(result = "LifecycleMetricsSnapshot")]
 pub struct GetLifecycleMetrics;
 
+/// Hand a world off: detach it exactly like `DestroyWorld { force: true }`,
+/// but capture a [`WorldSnapshot`] on the world's own thread first so it can
+/// be resumed elsewhere with [`AdoptWorld`]. Sessions in the world are ejected
+/// server-side; closing their sockets is up to whoever routes them.
+#[derive(ActixMessage)]
+#[rtype(result = "Result<WorldSnapshot, WorldLifecycleError>")]
+pub struct DrainWorld {
+    pub name: String,
+}
+
+/// Create a world from a drained [`WorldSnapshot`]. `config` must describe the
+/// same chunk shape the snapshot was taken with; the world is registered like
+/// `CreateWorld` and its state restored before any join can reach it.
+#[derive(ActixMessage)]
+#[rtype(result = "Result<WorldHandle, WorldLifecycleError>")]
+pub struct AdoptWorld {
+    pub snapshot: WorldSnapshot,
+    pub config: WorldConfig,
+    pub gc_policy: GcPolicy,
+}
+
+/// Create a read-only world that plays back a recorded [`Replay`]. `config`
+/// must describe the chunk shape the replay was recorded with; the world is
+/// registered like `CreateWorld` and starts playing as soon as it is up.
+#[derive(ActixMessage)]
+#[rtype(result = "Result<WorldHandle, WorldLifecycleError>")]
+pub struct OpenReplay {
+    pub name: String,
+    pub replay: Replay,
+    pub config: WorldConfig,
+    pub gc_policy: GcPolicy,
+}
+
 // ─── ServerBuilder additions ─────────────────────────────────────────────────
 
 impl super::ServerBuilder {
@@ -277,6 +313,71 @@ impl Handler<DestroyWorld> for Server {
     }
 }
 
+impl Handler<DrainWorld> for Server {
+    type Result = ResponseActFuture<Self, Result<WorldSnapshot, WorldLifecycleError>>;
+
+    fn handle(&mut self, msg: DrainWorld, ctx: &mut Context<Self>) -> Self::Result {
+        let name = msg.name;
+        let Some(addr) = self.worlds.get(&name).cloned() else {
+            return Box::pin(fut::ready(Err(WorldLifecycleError::NotFound(name))));
+        };
+
+        // Queue the snapshot ahead of the detach: it lands FIFO behind any
+        // in-flight tick, and no tick or join can be queued after it.
+        let snapshot = addr.send(SnapshotWorld);
+        let stop = match self.detach_world(&name, true, ctx) {
+            Ok(stop) => stop,
+            Err(error) => return Box::pin(fut::ready(Err(error))),
+        };
+
+        Box::pin(
+            wrap_future(snapshot).map(move |result, _act: &mut Server, ctx| {
+                // Teardown is only queued once the snapshot is back, so it can
+                // never overtake it.
+                if let Some(addr) = stop {
+                    ctx.spawn(
+                        wrap_future(addr.send(Teardown)).map(|_result, _act: &mut Server, _ctx| ()),
+                    );
+                }
+
+                let snapshot =
+                    result.map_err(|_| WorldLifecycleError::SnapshotFailed(name.clone()))?;
+                perf::log(
+                    "world_drained",
+                    &name,
+                    serde_json::json!({
+                        "chunks": snapshot.chunks.len(),
+                        "entities": snapshot.entities.len(),
+                    }),
+                );
+                info!(
+                    "world lifecycle: drained world '{}' ({} chunks, {} entities)",
+                    name,
+                    snapshot.chunks.len(),
+                    snapshot.entities.len()
+                );
+                Ok(snapshot)
+            }),
+        )
+    }
+}
+
+impl Handler<AdoptWorld> for Server {
+    type Result = MessageResult<AdoptWorld>;
+
+    fn handle(&mut self, msg: AdoptWorld, _: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.adopt_world(msg))
+    }
+}
+
+impl Handler<OpenReplay> for Server {
+    type Result = MessageResult<OpenReplay>;
+
+    fn handle(&mut self, msg: OpenReplay, _: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.open_replay(msg))
+    }
+}
+
 impl Handler<ListWorlds> for Server {
     type Result = MessageResult<ListWorlds>;
 
@@ -347,10 +448,18 @@ impl Server {
     /// registry (authoritative server-side membership, updated synchronously on
     /// join/leave/switch/disconnect).
     pub(crate) fn world_player_count(&self, name: &str) -> usize {
-        self.connections
+        let connected = self
+            .connections
             .values()
             .filter(|(_, world_name, _)| world_name == name)
-            .count()
+            .count();
+        // Suspended sessions still own their entity until they resume or expire.
+        let suspended = self
+            .suspended_sessions
+            .values()
+            .filter(|(world_name, _)| world_name == name)
+            .count();
+        connected + suspended
     }
 
     /// Per-world join cap (`usize::MAX` = unbounded) for the join-full check.
@@ -482,6 +591,90 @@ impl Server {
         })
     }
 
+    /// `AdoptWorld` body: check the snapshot fits the config, create the world
+    /// through `create_world`, then queue the restore ahead of any join.
+    fn adopt_world(&mut self, msg: AdoptWorld) -> Result<WorldHandle, WorldLifecycleError> {
+        let AdoptWorld {
+            snapshot,
+            config,
+            gc_policy,
+        } = msg;
+
+        if snapshot.version != WORLD_SNAPSHOT_VERSION {
+            return Err(WorldLifecycleError::InvalidConfig(format!(
+                "snapshot version {} is not supported (expected {})",
+                snapshot.version, WORLD_SNAPSHOT_VERSION
+            )));
+        }
+        if snapshot.chunk_size != config.chunk_size || snapshot.max_height != config.max_height {
+            return Err(WorldLifecycleError::InvalidConfig(format!(
+                "snapshot chunks are {}x{} but the config expects {}x{}",
+                snapshot.chunk_size, snapshot.max_height, config.chunk_size, config.max_height
+            )));
+        }
+
+        let chunks = snapshot.chunks.len();
+        let entities = snapshot.entities.len();
+        let handle = self.create_world(CreateWorld {
+            name: snapshot.name.clone(),
+            config,
+            gc_policy,
+        })?;
+        handle.addr.do_send(RestoreWorld(snapshot));
+
+        perf::log(
+            "world_adopted",
+            &handle.name,
+            serde_json::json!({ "chunks": chunks, "entities": entities }),
+        );
+        info!(
+            "world lifecycle: adopted world '{}' ({} chunks, {} entities)",
+            handle.name, chunks, entities
+        );
+
+        Ok(handle)
+    }
+
+    /// `OpenReplay` body: check the replay fits the config, create the world
+    /// through `create_world`, then queue the replay ahead of any join.
+    fn open_replay(&mut self, msg: OpenReplay) -> Result<WorldHandle, WorldLifecycleError> {
+        let OpenReplay {
+            name,
+            replay,
+            config,
+            gc_policy,
+        } = msg;
+
+        let header = &replay.header;
+        if header.chunk_size != config.chunk_size || header.max_height != config.max_height {
+            return Err(WorldLifecycleError::InvalidConfig(format!(
+                "replay chunks are {}x{} but the config expects {}x{}",
+                header.chunk_size, header.max_height, config.chunk_size, config.max_height
+            )));
+        }
+
+        let recorded = header.world.clone();
+        let frames = replay.frames.len();
+        let handle = self.create_world(CreateWorld {
+            name,
+            config,
+            gc_policy,
+        })?;
+        handle.addr.do_send(LoadReplay(replay));
+
+        perf::log(
+            "replay_opened",
+            &handle.name,
+            serde_json::json!({ "recorded": recorded, "frames": frames }),
+        );
+        info!(
+            "world lifecycle: opened a replay of '{}' as world '{}' ({} frames)",
+            recorded, handle.name, frames
+        );
+
+        Ok(handle)
+    }
+
     /// Pop a warm slot matching `fingerprint`, reset + rename it for reuse, and
     /// return its address and (cleared) inbound state. `None` when pooling is
     /// off, no slot matches, or the reset policy is not `ReuseWarm`.
@@ -570,7 +763,10 @@ impl Server {
         for id in &bound {
             self.connections.remove(id);
             self.lost_sessions.remove(id);
+            self.resume_tokens.remove(id);
         }
+        self.suspended_sessions
+            .retain(|_, (world_name, _)| world_name != name);
 
         let mut entry = self.world_entries.remove(name);
         if let Some(entry) = &mut entry {
@@ -651,6 +847,9 @@ impl Server {
         for (_, world_name, _) in self.connections.values() {
             *counts.entry(world_name.clone()).or_insert(0) += 1;
         }
+        for (world_name, _) in self.suspended_sessions.values() {
+            *counts.entry(world_name.clone()).or_insert(0) += 1;
+        }
 
         let names: Vec<String> = self.world_entries.keys().cloned().collect();
         for name in names {
@@ -526,3 +526,231 @@ fn malformed_join_payload_is_a_typed_error_not_a_panic() {
         assert!(error.unwrap().contains("Malformed JOIN payload"));
     });
 }
+
+const RESUME_GRACE: Duration = Duration::from_secs(30);
+
+fn build_server_with_resume() -> Server {
+    let mut server = Server::new()
+        .debug(false)
+        .session_resume_grace(RESUME_GRACE)
+        .build();
+    let config = WorldConfig::new().build();
+    server
+        .add_world(World::new(WORLD, &config))
+        .expect("world should register");
+    server
+}
+
+fn resume_join_message(token: &str, last_seq: u64) -> Message {
+    Message::new(&MessageType::Join)
+        .json(
+            &json!({
+                "world": WORLD,
+                "username": "tester",
+                "resume": { "token": token, "lastSeq": last_seq, "chunks": [] },
+            })
+            .to_string(),
+        )
+        .build()
+}
+
+fn init_json(messages: &[Message]) -> Value {
+    let init = messages
+        .iter()
+        .find(|m| m.r#type == MessageType::Init as i32)
+        .expect("INIT ack");
+    serde_json::from_str(&init.json).unwrap()
+}
+
+fn chat_message(body: &str) -> Message {
+    Message::new(&MessageType::Chat)
+        .chat(ChatMessageProtocol {
+            r#type: "chat".into(),
+            sender: "visB".into(),
+            body: body.into(),
+            ..Default::default()
+        })
+        .build()
+}
+
+#[test]
+fn dropped_session_resumes_its_entity_and_replays_missed_events() {
+    actix::System::new().block_on(async {
+        let mut server = build_server_with_resume();
+        let world_addr = server.worlds.get(WORLD).unwrap().clone();
+        let tick = |n: usize| {
+            let world_addr = world_addr.clone();
+            async move {
+                for _ in 0..n {
+                    world_addr.send(crate::Tick).await.unwrap();
+                }
+            }
+        };
+
+        let (sender_a, mut rx_a) = fake_socket();
+        let (id_a, token_a) = server.register_session(Some("resA".into()), false, sender_a.clone());
+        assert_eq!(
+            on_request(&mut server, &id_a, &token_a, join_message(WORLD)),
+            None
+        );
+        let (sender_b, mut rx_b) = fake_socket();
+        let (id_b, token_b) = server.register_session(Some("resB".into()), false, sender_b.clone());
+        assert_eq!(
+            on_request(&mut server, &id_b, &token_b, join_message(WORLD)),
+            None
+        );
+        tick(4).await;
+
+        let a_messages = drain_messages(&sender_a, &mut rx_a);
+        let resume_token = init_json(&a_messages)["resumeToken"]
+            .as_str()
+            .expect("resumable sessions get a resume token at INIT")
+            .to_owned();
+        let last_seq = a_messages.iter().map(|m| m.seq).max().unwrap_or(0);
+        assert!(last_seq > 0, "queued reliable traffic is sequenced");
+        drain_messages(&sender_b, &mut rx_b);
+
+        // A's socket drops: the session is suspended, not removed.
+        drop(rx_a);
+        assert!(server.unregister_session(&id_a, &token_a).is_some());
+        assert!(server.suspended_sessions.contains_key(&id_a));
+        assert_eq!(world_client_count(&server).await, 2, "entity kept");
+
+        // B chats while A is away.
+        assert_eq!(
+            on_request(
+                &mut server,
+                &id_b,
+                &token_b,
+                chat_message("while you were out")
+            ),
+            None
+        );
+        tick(2).await;
+
+        // A reconnects within the grace window and resumes.
+        let (sender_a, mut rx_a) = fake_socket();
+        let (_, token_a) = server.register_session(Some("resA".into()), false, sender_a.clone());
+        assert_eq!(
+            on_request(
+                &mut server,
+                &id_a,
+                &token_a,
+                resume_join_message(&resume_token, last_seq)
+            ),
+            None
+        );
+        assert_eq!(world_client_count(&server).await, 2, "no duplicate entity");
+        assert!(!server.suspended_sessions.contains_key(&id_a));
+
+        let a_messages = drain_messages(&sender_a, &mut rx_a);
+        assert_eq!(a_messages[0].r#type, MessageType::Init as i32);
+        let init = init_json(&a_messages);
+        assert_eq!(init["resumed"], json!(true));
+        assert!(init["resumeToken"].is_string());
+        let replayed_chat = a_messages
+            .iter()
+            .find(|m| m.r#type == MessageType::Chat as i32)
+            .expect("missed chat is replayed");
+        assert_eq!(
+            replayed_chat.chat.as_ref().unwrap().body,
+            "while you were out"
+        );
+        assert!(replayed_chat.seq > last_seq);
+        assert!(
+            a_messages.iter().all(|m| m.seq == 0 || m.seq > last_seq),
+            "nothing the client already applied is replayed"
+        );
+
+        // B never saw A leave or re-join.
+        tick(2).await;
+        let b_types = drain_message_types(&sender_b, &mut rx_b);
+        assert!(!b_types.contains(&(MessageType::Leave as i32)));
+        assert!(!b_types.contains(&(MessageType::Join as i32)));
+    });
+}
+
+#[test]
+fn suspended_session_is_released_when_its_grace_expires() {
+    actix::System::new().block_on(async {
+        let mut server = build_server_with_resume();
+
+        let (sender, rx) = fake_socket();
+        let (id, token) = server.register_session(Some("bot".into()), false, sender.clone());
+        assert_eq!(
+            on_request(&mut server, &id, &token, join_message(WORLD)),
+            None
+        );
+        drop(rx);
+
+        let resume_token = server
+            .unregister_session(&id, &token)
+            .expect("in-world session is suspended");
+        assert_eq!(world_client_count(&server).await, 1);
+
+        // A stale expiry (from an earlier drop) must not release it.
+        server.expire_suspended_session(&id, "stale");
+        assert_eq!(world_client_count(&server).await, 1);
+
+        server.expire_suspended_session(&id, &resume_token);
+        assert_eq!(world_client_count(&server).await, 0, "membership removed");
+        assert!(server.suspended_sessions.is_empty());
+    });
+}
+
+#[test]
+fn resume_with_an_unknown_token_falls_back_to_a_full_rejoin() {
+    actix::System::new().block_on(async {
+        let mut server = build_server_with_resume();
+
+        let (sender, rx) = fake_socket();
+        let (id, token) = server.register_session(Some("bot".into()), false, sender.clone());
+        assert_eq!(
+            on_request(&mut server, &id, &token, join_message(WORLD)),
+            None
+        );
+        drop(rx);
+        server.unregister_session(&id, &token);
+
+        let (sender, mut rx) = fake_socket();
+        let (_, token) = server.register_session(Some("bot".into()), false, sender.clone());
+        assert_eq!(
+            on_request(&mut server, &id, &token, resume_join_message("forged", 0)),
+            None
+        );
+        assert_eq!(world_client_count(&server).await, 1, "entity reattached");
+        assert!(server.suspended_sessions.is_empty());
+
+        let init = init_json(&drain_messages(&sender, &mut rx));
+        assert!(init.get("resumed").is_none(), "full INIT, not a resume");
+        assert!(init["resumeToken"].is_string());
+    });
+}
+
+#[test]
+fn sessions_are_not_resumable_without_a_grace_period() {
+    actix::System::new().block_on(async {
+        let mut server = build_server_with_world();
+
+        let (sender, mut rx) = fake_socket();
+        let (id, token) = server.register_session(Some("bot".into()), false, sender.clone());
+        assert_eq!(
+            on_request(&mut server, &id, &token, join_message(WORLD)),
+            None
+        );
+        tick_world(&server, 2).await;
+        let messages = drain_messages(&sender, &mut rx);
+        assert!(init_json(&messages).get("resumeToken").is_none());
+        assert!(messages.iter().all(|m| m.seq == 0), "no sequencing");
+
+        assert!(server.unregister_session(&id, &token).is_none());
+        assert_eq!(world_client_count(&server).await, 0);
+    });
+}
+
+async fn tick_world(server: &Server, n: usize) {
+    let world = server.worlds.get(WORLD).unwrap();
+    for _ in 0..n {
+        world.send(crate::Tick).await.unwrap();
+    }
+}
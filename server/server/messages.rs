@@ -1,6 +1,6 @@
 use actix::{
-    fut::wrap_future, Actor, ActorFutureExt, Addr, Context, Handler, Message as ActixMessage,
-    MessageResult, ResponseActFuture,
+    fut::wrap_future, Actor, ActorFutureExt, Addr, AsyncContext, Context, Handler,
+    Message as ActixMessage, MessageResult, ResponseActFuture,
 };
 use serde_json::Value;
 
@@ -98,12 +98,21 @@ impl Handler<Connect> for Server {
 /// Handler for Disconnect message.
 /// Only cleans up session state if the connection token matches the currently
 /// registered token, preventing stale disconnects from kicked sessions from
-/// removing the new session's state.
+/// removing the new session's state. A session suspended for resume is
+/// released once its grace period runs out without a resume.
 impl Handler<Disconnect> for Server {
     type Result = ();
 
     fn handle(&mut self, msg: Disconnect, ctx: &mut Context<Self>) {
-        self.unregister_session(&msg.id, &msg.token);
+        if let Some(resume_token) = self.unregister_session(&msg.id, &msg.token) {
+            if let Some(grace) = self.session_resume_grace {
+                let id = msg.id;
+                ctx.run_later(grace, move |act, ctx| {
+                    act.expire_suspended_session(&id, &resume_token);
+                    act.reconcile_gc(ctx);
+                });
+            }
+        }
         self.reconcile_gc(ctx);
     }
 }
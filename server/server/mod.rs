@@ -42,11 +42,11 @@ use crate::{
     errors::AddWorldError,
     perf,
     world::{
-        check_protocol, Chunks, ClientPreferencesPatch, InboundStateBuffer, MotionProtocol, Registry,
-        World, PROTOCOL_MISMATCH_CLOSE_CODE, PROTOCOL_VERSION,
+        check_protocol, Chunks, ClientPreferencesPatch, InboundStateBuffer, MeshFormat,
+        MotionProtocol, Registry, World, PROTOCOL_MISMATCH_CLOSE_CODE, PROTOCOL_VERSION,
     },
-    ClientJoinRequest, ClientLeaveRequest, ClientRequest, GetInfo, Preload, Prepare, RtcSenders,
-    SyncWorld, Tick, TransportJoinRequest, TransportLeaveRequest,
+    ClientJoinRequest, ClientLeaveRequest, ClientRequest, ClientResumeRequest, GetInfo, Preload,
+    Prepare, RtcSenders, SyncWorld, Tick, TransportJoinRequest, TransportLeaveRequest, Vec2,
 };
 
 pub use lifecycle::*;
@@ -130,6 +130,13 @@ impl WsSender {
         Ok(())
     }
 
+    /// Whether the connection's write loop has gone away (the socket closed).
+    /// A world keeps sending to a suspended session's last sender until it
+    /// resumes; this lets it hold latest-wins state instead of dropping it.
+    pub fn is_closed(&self) -> bool {
+        self.control.is_closed()
+    }
+
     pub fn mark_control_written(&self) {
         self.control_depth.fetch_sub(1, Ordering::Relaxed);
     }
@@ -161,7 +168,8 @@ pub struct OnJoinRequest {
     #[serde(default)]
     preferences: Option<ClientPreferencesPatch>,
     /// Optional protocol capabilities this client supports (e.g.
-    /// "motion.v1" for the compact entity motion path). Absent for pinned
+    /// "motion.v1" for the compact entity motion path, "mesh.v1" for the
+    /// compact chunk mesh layout). Absent for pinned
     /// legacy clients, which keeps them on the JSON wire shape.
     #[serde(default)]
     capabilities: Vec<String>,
@@ -171,6 +179,23 @@ pub struct OnJoinRequest {
     /// it, so existing clients are unaffected.
     #[serde(default)]
     protocol: Option<u32>,
+    /// Present when a client that dropped reconnects and asks to resume its
+    /// suspended session instead of rejoining from scratch.
+    #[serde(default)]
+    resume: Option<OnResumeRequest>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OnResumeRequest {
+    /// The resume token handed out in the session's INIT.
+    token: String,
+    /// Highest contiguous reliable sequence the client applied.
+    #[serde(default)]
+    last_seq: u64,
+    /// Chunks the client still holds.
+    #[serde(default)]
+    chunks: Vec<Vec2<i32>>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -406,6 +431,18 @@ pub struct Server {
     /// Value: (sender, world_name, connection_token)
     pub connections: HashMap<String, (WsSender, String, String)>,
 
+    /// In-world sessions whose socket dropped, kept (entity and all) until the
+    /// client resumes or the resume grace runs out.
+    /// Value: (world_name, resume_token)
+    pub suspended_sessions: HashMap<String, (String, String)>,
+
+    /// Resume tokens of in-world sessions, issued at JOIN when resume is on.
+    resume_tokens: HashMap<String, String>,
+
+    /// How long a dropped in-world session stays resumable. `None` disables
+    /// session resume: a dropped socket leaves its world immediately.
+    session_resume_grace: Option<Duration>,
+
     /// Worlds with a tick already queued or running.
     pending_world_ticks: HashSet<String>,
 
@@ -604,6 +641,7 @@ impl Server {
             if let Some(world) = self.worlds.get_mut(&data.text) {
                 if let Some((sender, _, token)) = self.connections.remove(id) {
                     self.lost_sessions.insert(id.to_owned(), (sender, token));
+                    self.resume_tokens.remove(id);
 
                     world.do_send(ClientLeaveRequest { id: id.to_owned() });
                 }
@@ -740,11 +778,12 @@ impl Server {
     /// fatal error — a fatal error here is what caused live
     /// join -> ack unanswered -> retry -> "already in world" -> disconnect
     /// loops in live deployments.
-    fn on_join(&mut self, id: &str, json: OnJoinRequest) -> Option<String> {
+    fn on_join(&mut self, id: &str, mut json: OnJoinRequest) -> Option<String> {
         let preferences = json
             .flat_preferences
             .merge(json.preferences.unwrap_or_default());
         let motion_protocol = MotionProtocol::negotiate(&json.capabilities);
+        let mesh_format = MeshFormat::negotiate(&json.capabilities);
 
         if !self.worlds.contains_key(&json.world) {
             return Some(format!(
@@ -784,7 +823,9 @@ impl Server {
         let is_replay = self
             .connections
             .get(id)
-            .map(|(_, world_name, _)| world_name == &json.world)
+            .map(|(_, world_name, _)| world_name)
+            .or_else(|| self.suspended_sessions.get(id).map(|(world_name, _)| world_name))
+            .map(|world_name| world_name == &json.world)
             .unwrap_or(false);
         if !is_replay {
             let live = self.world_player_count(&json.world);
@@ -802,12 +843,57 @@ impl Server {
             }
         }
 
+        // A session suspended by a dropped socket. A matching resume token
+        // reattaches it to its entity with a replay of what it missed; any
+        // other JOIN releases the suspension and proceeds below, which for
+        // the same world reattaches the entity with a full INIT.
+        if self.lost_sessions.contains_key(id) {
+            if let Some((world_name, resume_token)) = self.suspended_sessions.remove(id) {
+                let resume = json
+                    .resume
+                    .take()
+                    .filter(|resume| world_name == json.world && resume.token == resume_token);
+
+                if let Some(resume) = resume {
+                    let (sender, token) = self.lost_sessions.remove(id).unwrap();
+                    let resume_token = self.issue_resume_token(id);
+                    let world = self.worlds.get_mut(&json.world).unwrap();
+                    world.do_send(ClientResumeRequest {
+                        join: ClientJoinRequest {
+                            id: id.to_owned(),
+                            username: json.username,
+                            sender: sender.clone(),
+                            preferences,
+                            motion_protocol,
+                            mesh_format,
+                            resume_token,
+                        },
+                        last_seq: resume.last_seq,
+                        chunks: resume.chunks,
+                    });
+                    self.connections
+                        .insert(id.to_owned(), (sender, json.world, token));
+                    return None;
+                }
+
+                if world_name != json.world {
+                    if let Some(old) = self.worlds.get_mut(&world_name) {
+                        old.do_send(ClientLeaveRequest { id: id.to_owned() });
+                    }
+                }
+            }
+        }
+
         if let Some((sender, world_name, _)) = self.connections.get(id) {
             if *world_name == json.world {
                 // Idempotent replay: this session already joined this world.
                 // Re-issue the join; the world-side handler replays the INIT
                 // ack without creating a duplicate entity.
                 let sender = sender.clone();
+                let resume_token = match self.resume_tokens.get(id) {
+                    Some(token) => Some(token.clone()),
+                    None => self.issue_resume_token(id),
+                };
                 perf::log(
                     "client_join_replayed",
                     &json.world,
@@ -821,6 +907,8 @@ impl Server {
                     sender,
                     preferences,
                     motion_protocol,
+                    mesh_format,
+                    resume_token,
                 });
                 return None;
             }
@@ -841,6 +929,7 @@ impl Server {
         }
 
         if let Some((sender, token)) = self.lost_sessions.remove(id) {
+            let resume_token = self.issue_resume_token(id);
             let world = self.worlds.get_mut(&json.world).unwrap();
             world.do_send(ClientJoinRequest {
                 id: id.to_owned(),
@@ -848,6 +937,8 @@ impl Server {
                 sender: sender.clone(),
                 preferences,
                 motion_protocol,
+                mesh_format,
+                resume_token,
             });
             self.connections
                 .insert(id.to_owned(), (sender, json.world, token));
@@ -860,6 +951,39 @@ impl Server {
         ))
     }
 
+    /// Issue a fresh resume token for a session entering a world, or `None`
+    /// when session resume is disabled.
+    fn issue_resume_token(&mut self, id: &str) -> Option<String> {
+        self.session_resume_grace?;
+        let token = nanoid!();
+        self.resume_tokens.insert(id.to_owned(), token.clone());
+        Some(token)
+    }
+
+    /// Release a suspended session whose resume grace ran out, removing its
+    /// entity from the world. Token-checked so the expiry of an earlier drop
+    /// cannot release a session that has since resumed and dropped again.
+    pub(crate) fn expire_suspended_session(&mut self, id: &str, resume_token: &str) {
+        let is_current = self
+            .suspended_sessions
+            .get(id)
+            .is_some_and(|(_, token)| token == resume_token);
+        if !is_current {
+            return;
+        }
+
+        let (world_name, _) = self.suspended_sessions.remove(id).unwrap();
+        if let Some(world) = self.worlds.get_mut(&world_name) {
+            world.do_send(ClientLeaveRequest { id: id.to_owned() });
+        }
+        perf::log(
+            "session_resume_expired",
+            &world_name,
+            json!({ "clientId": id }),
+        );
+        info!("Resume grace expired for {} in world {}", id, world_name);
+    }
+
     /// Register a new session, kicking any previous session with the same
     /// client id (its world membership is released so the new session can
     /// join cleanly). Returns (client_id, connection_token); the token
@@ -901,6 +1025,7 @@ impl Server {
         if let Some((old_sender, world_name, _old_token)) = self.connections.remove(&id) {
             info!("Kicking duplicate in-world session: {}", id);
             let _ = old_sender.send(kick_msg);
+            self.resume_tokens.remove(&id);
             if let Some(world) = self.worlds.get_mut(&world_name) {
                 world.do_send(ClientLeaveRequest { id: id.clone() });
             }
@@ -916,11 +1041,23 @@ impl Server {
     /// Deterministically release a disconnected session's registration and
     /// world membership. Token-checked so a stale disconnect from a kicked
     /// socket cannot remove its replacement's state.
-    pub(crate) fn unregister_session(&mut self, id: &str, token: &str) {
+    ///
+    /// With session resume enabled, an in-world session is suspended instead
+    /// of leaving its world; its resume token is returned so the caller can
+    /// schedule [`Server::expire_suspended_session`] after the grace period.
+    pub(crate) fn unregister_session(&mut self, id: &str, token: &str) -> Option<String> {
+        let mut suspended = None;
+
         if let Some((_, _, current_token)) = self.connections.get(id) {
             if current_token == token {
                 let (_, world_name, _) = self.connections.remove(id).unwrap();
-                if let Some(world) = self.worlds.get_mut(&world_name) {
+                let resume_token = self.resume_tokens.remove(id);
+                if let (Some(resume_token), Some(_)) = (resume_token, self.session_resume_grace) {
+                    info!("Suspending session {} in world {} for resume", id, world_name);
+                    self.suspended_sessions
+                        .insert(id.to_owned(), (world_name, resume_token.clone()));
+                    suspended = Some(resume_token);
+                } else if let Some(world) = self.worlds.get_mut(&world_name) {
                     world.do_send(ClientLeaveRequest { id: id.to_owned() });
                 }
             } else {
@@ -941,6 +1078,8 @@ impl Server {
                 self.lost_sessions.remove(id);
             }
         }
+
+        suspended
     }
 
     /// Prepare all worlds on the server to start.
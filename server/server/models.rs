@@ -1,11 +1,15 @@
-use std::io::{Cursor, Write};
+use std::io::{Cursor, Read, Write};
+use std::sync::{Arc, OnceLock};
 
 use actix::Message as ActixMessage;
+use log::debug;
 use lz4_flex::block::compress_prepend_size;
-use lz4_flex::frame::FrameEncoder;
-use prost::Message as ProstMesssage;
+use lz4_flex::frame::{FrameDecoder, FrameEncoder};
+use prost::{encoding, Message as ProstMesssage};
+use voxelize_mesher::{pack_vertices, RenderLayer};
 
 use crate::libs::Ndarray;
+use crate::MeshFormat;
 
 const COMPRESSION_THRESHOLD: usize = 4096;
 
@@ -76,8 +80,50 @@ pub fn encode_message(message: &Message) -> Vec<u8> {
     }
 }
 
-/// Decode protocol buffers into a message struct.
+/// Magic number that opens every LZ4 frame produced by [`encode_message`].
+/// No protobuf message can start with it (field 0 is not a valid tag).
+const LZ4_FRAME_MAGIC: [u8; 4] = [0x04, 0x22, 0x4d, 0x18];
+
+/// Append a per-client reliable sequence number (`Message.seq`) to an already
+/// encoded message. Protobuf decoders keep the last value of a repeated scalar
+/// field, so one encoded broadcast can be stamped per recipient without
+/// re-encoding its payload. Compressed frames are decompressed, stamped and
+/// recompressed.
+pub fn stamp_sequence(data: &[u8], seq: u64) -> Vec<u8> {
+    if data.starts_with(&LZ4_FRAME_MAGIC) {
+        let mut decoder = FrameDecoder::new(data);
+        let mut buf = Vec::new();
+        if decoder.read_to_end(&mut buf).is_ok() {
+            append_sequence(&mut buf, seq);
+            let mut encoder = FrameEncoder::new(Vec::new());
+            encoder.write_all(&buf).unwrap();
+            return encoder.finish().unwrap();
+        }
+    }
+
+    let mut buf = data.to_vec();
+    append_sequence(&mut buf, seq);
+    buf
+}
+
+fn append_sequence(buf: &mut Vec<u8>, seq: u64) {
+    encoding::encode_key(16, encoding::WireType::Varint, buf);
+    encoding::encode_varint(seq, buf);
+}
+
+/// Decode protocol buffers into a message struct, inflating the LZ4 frames
+/// [`encode_message`] wraps large messages in.
 pub fn decode_message(buf: &[u8]) -> Result<Message, prost::DecodeError> {
+    if buf.starts_with(&LZ4_FRAME_MAGIC) {
+        let mut decompressed = Vec::new();
+        if FrameDecoder::new(buf)
+            .read_to_end(&mut decompressed)
+            .is_ok()
+        {
+            return Message::decode(&mut Cursor::new(decompressed));
+        }
+    }
+
     Message::decode(&mut Cursor::new(buf))
 }
 
@@ -87,10 +133,37 @@ pub struct GeometryProtocol {
     pub voxel: u32,
     pub at: Vec<i32>,
     pub face_name: Option<String>,
+    pub layer: RenderLayer,
     pub positions: Vec<f32>,
     pub indices: Vec<i32>,
     pub uvs: Vec<f32>,
     pub lights: Vec<i32>,
+    /// The buffers in the compact layout, LZ4-compressed, or `None` inside
+    /// when the layout can't hold them. Packed the first time the geometry
+    /// goes to a client that negotiated the layout, and shared by clones, so
+    /// a chunk's stored mesh is packed once however many clients it reaches.
+    /// The buffers must not change once it's filled.
+    pub packed: Arc<OnceLock<Option<Vec<u8>>>>,
+}
+
+impl GeometryProtocol {
+    /// The geometry in the compact layout, LZ4-compressed, packed on first
+    /// use. `None` when the geometry has to ship in the float buffers.
+    pub fn packed(&self) -> Option<&[u8]> {
+        self.packed
+            .get_or_init(|| {
+                let packed = pack_vertices(&self.positions, &self.uvs, &self.indices, &self.lights);
+                if packed.is_none() {
+                    debug!(
+                        "Geometry of voxel {} (face {:?}) doesn't fit the compact mesh layout, shipping float buffers",
+                        self.voxel,
+                        self.face_name
+                    );
+                }
+                packed.map(|packed| compress_prepend_size(&packed))
+            })
+            .as_deref()
+    }
 }
 
 /// Protocol buffer compatible mesh data structure.
@@ -184,6 +257,8 @@ pub struct MessageBuilder {
     events: Option<Vec<EventProtocol>>,
     chunks: Option<Vec<ChunkProtocol>>,
     updates: Option<Vec<UpdateProtocol>>,
+
+    mesh_format: MeshFormat,
 }
 
 impl MessageBuilder {
@@ -237,6 +312,12 @@ impl MessageBuilder {
         self
     }
 
+    /// Configure the vertex layout the chunks' geometries are encoded in.
+    pub fn mesh_format(mut self, mesh_format: MeshFormat) -> Self {
+        self.mesh_format = mesh_format;
+        self
+    }
+
     /// Configure the voxel update data of the protocol.
     pub fn updates(mut self, updates: &[UpdateProtocol]) -> Self {
         self.updates = Some(updates.to_vec());
@@ -302,6 +383,8 @@ impl MessageBuilder {
                 .collect()
         }
 
+        let is_compact = self.mesh_format.is_compact();
+
         if let Some(chunks) = self.chunks {
             message.chunks = chunks
                 .into_iter()
@@ -316,14 +399,32 @@ impl MessageBuilder {
                             geometries: mesh
                                 .geometries
                                 .into_iter()
-                                .map(|geo| protocols::Geometry {
-                                    voxel: geo.voxel,
-                                    at: geo.at,
-                                    face_name: geo.face_name,
-                                    indices: compress_i32_array(&geo.indices),
-                                    positions: compress_f32_array(&geo.positions),
-                                    lights: compress_i32_array(&geo.lights),
-                                    uvs: compress_f32_array(&geo.uvs),
+                                .map(|geo| {
+                                    let packed = is_compact
+                                        .then(|| geo.packed().map(<[u8]>::to_vec))
+                                        .flatten();
+
+                                    let mut geometry = protocols::Geometry {
+                                        voxel: geo.voxel,
+                                        at: geo.at,
+                                        face_name: geo.face_name,
+                                        layer: Some(geo.layer as i32),
+                                        ..Default::default()
+                                    };
+
+                                    match packed {
+                                        Some(packed) => {
+                                            geometry.packed = Some(packed);
+                                        }
+                                        None => {
+                                            geometry.indices = compress_i32_array(&geo.indices);
+                                            geometry.positions = compress_f32_array(&geo.positions);
+                                            geometry.lights = compress_i32_array(&geo.lights);
+                                            geometry.uvs = compress_f32_array(&geo.uvs);
+                                        }
+                                    }
+
+                                    geometry
                                 })
                                 .collect(),
                         })
@@ -370,3 +471,157 @@ impl MessageBuilder {
         message
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_frame(data: &[u8]) -> Message {
+        if data.starts_with(&LZ4_FRAME_MAGIC) {
+            let mut decompressed = Vec::new();
+            FrameDecoder::new(data)
+                .read_to_end(&mut decompressed)
+                .unwrap();
+            return decode_message(&decompressed).unwrap();
+        }
+        decode_message(data).unwrap()
+    }
+
+    #[test]
+    fn stamped_sequence_survives_decoding() {
+        let message = Message::new(&MessageType::Chat).text("hello").build();
+        let stamped = stamp_sequence(&encode_message(&message), 42);
+
+        let decoded = decode_frame(&stamped);
+        assert_eq!(decoded.seq, 42);
+        assert_eq!(decoded.text, "hello");
+    }
+
+    #[test]
+    fn stamped_sequence_survives_compression() {
+        let text = "x".repeat(COMPRESSION_THRESHOLD * 2);
+        let message = Message::new(&MessageType::Event).text(&text).build();
+        let encoded = encode_message(&message);
+        assert!(encoded.starts_with(&LZ4_FRAME_MAGIC));
+
+        let stamped = stamp_sequence(&encoded, 7);
+        assert!(stamped.starts_with(&LZ4_FRAME_MAGIC));
+        let decoded = decode_frame(&stamped);
+        assert_eq!(decoded.seq, 7);
+        assert_eq!(decoded.text, text);
+    }
+
+    /// A chunk's worth of top faces, one quad per column on one of a few
+    /// atlas tiles, with lights that vary across the chunk.
+    fn terrain_geometry() -> GeometryProtocol {
+        let mut geometry = GeometryProtocol {
+            voxel: 1,
+            ..Default::default()
+        };
+        for x in 0..16 {
+            for z in 0..16 {
+                let base = (geometry.positions.len() / 3) as i32;
+                let y = ((x * 7 + z * 3) % 9) as f32;
+                let tile = ((x + z) % 4) as f32 / 4.0;
+                for [dx, dz] in [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]] {
+                    geometry
+                        .positions
+                        .extend([x as f32 + dx, y + 1.0, z as f32 + dz]);
+                    geometry.uvs.extend([tile + dx / 4.0, 0.5 + dz / 4.0]);
+                    geometry.lights.push((x * 16 + z) << 4 | 0xf);
+                }
+                geometry
+                    .indices
+                    .extend([base, base + 1, base + 2, base + 2, base + 1, base + 3]);
+            }
+        }
+        geometry
+    }
+
+    #[test]
+    fn compact_mesh_format_ships_packed_geometries() {
+        let mut unpackable = terrain_geometry();
+        unpackable.positions[1] = 1000.0;
+        let chunk = ChunkProtocol {
+            id: "chunk".to_owned(),
+            meshes: vec![MeshProtocol {
+                geometries: vec![terrain_geometry(), unpackable],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        // Clients that didn't negotiate the compact layout never see it.
+        let legacy = Message::new(&MessageType::Load)
+            .chunks(std::slice::from_ref(&chunk))
+            .build();
+        assert!(legacy.chunks[0].meshes[0]
+            .geometries
+            .iter()
+            .all(|geometry| geometry.packed.is_none()));
+
+        let compact = Message::new(&MessageType::Load)
+            .chunks(&[chunk])
+            .mesh_format(MeshFormat::CompactV1)
+            .build();
+
+        let decoded = decode_frame(&encode_message(&compact));
+        let [packed, fallback] = &decoded.chunks[0].meshes[0].geometries[..] else {
+            panic!("expected two geometries");
+        };
+
+        let bytes =
+            lz4_flex::block::decompress_size_prepended(packed.packed.as_ref().unwrap()).unwrap();
+        let unpacked = voxelize_mesher::unpack_vertices(&bytes).unwrap();
+        let original = terrain_geometry();
+        assert!(packed.positions.is_empty() && packed.uvs.is_empty());
+        assert_eq!(unpacked.indices, original.indices);
+        assert_eq!(unpacked.lights, original.lights);
+        assert_eq!(unpacked.positions, original.positions);
+        assert_eq!(unpacked.uvs, original.uvs);
+
+        // A geometry the compact layout can't hold still reaches the client.
+        assert!(fallback.packed.is_none());
+        assert!(!fallback.positions.is_empty());
+    }
+
+    #[test]
+    fn geometries_are_packed_once_however_many_clients_receive_them() {
+        let stored = terrain_geometry();
+        let chunk = ChunkProtocol {
+            id: "chunk".to_owned(),
+            meshes: vec![MeshProtocol {
+                geometries: vec![stored.clone()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        assert!(stored.packed.get().is_none());
+        let first = Message::new(&MessageType::Load)
+            .chunks(std::slice::from_ref(&chunk))
+            .mesh_format(MeshFormat::CompactV1)
+            .build();
+
+        // The message's copy of the chunk packed into the cache the stored
+        // geometry shares, so the next client's message reuses the payload.
+        let cached = stored.packed.get().unwrap().as_ref().unwrap();
+        assert_eq!(
+            first.chunks[0].meshes[0].geometries[0].packed.as_ref(),
+            Some(cached)
+        );
+        assert!(Arc::ptr_eq(
+            &stored.packed,
+            &chunk.meshes[0].geometries[0].packed
+        ));
+
+        let second = Message::new(&MessageType::Load)
+            .chunks(&[chunk])
+            .mesh_format(MeshFormat::CompactV1)
+            .build();
+        assert_eq!(
+            second.chunks[0].meshes[0].geometries[0].packed,
+            first.chunks[0].meshes[0].geometries[0].packed
+        );
+    }
+}
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use actix_web::{web, Error, HttpResponse};
 use bytes::Bytes;
-use log::info;
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
 use tokio::sync::{mpsc, Mutex};
 use webrtc::api::API;
@@ -21,6 +21,31 @@ use hashbrown::HashMap;
 
 pub type WebRTCPeers = Arc<Mutex<HashMap<String, Arc<RTCPeerConnection>>>>;
 
+/// STUN/TURN urls handed to every peer connection [`rtc_offer`] creates.
+/// Register it as app data to override the public STUN default, e.g. with an
+/// empty list for LAN deployments and loopback tests where host candidates
+/// are enough.
+#[derive(Clone, Debug)]
+pub struct RtcIceServers(pub Vec<String>);
+
+impl Default for RtcIceServers {
+    fn default() -> Self {
+        Self(vec!["stun:stun.l.google.com:19302".to_owned()])
+    }
+}
+
+/// Whether a client-opened data channel may carry latest-wins state.
+///
+/// State is only ever routed over an UNORDERED channel: a reordered or lost
+/// sample is harmless because a newer one follows, whereas head-of-line
+/// blocking behind a retransmit is exactly the staleness the state lane
+/// exists to avoid. Reliability is not checked separately because DCEP
+/// reports `maxRetransmits: 0` and a fully reliable unordered channel with
+/// the same fields, so the two cannot be told apart here.
+pub fn is_state_channel(dc: &RTCDataChannel) -> bool {
+    !dc.ordered()
+}
+
 #[derive(Deserialize)]
 pub struct RtcOfferRequest {
     pub sdp: String,
@@ -46,15 +71,23 @@ pub async fn rtc_offer(
     peers: web::Data<WebRTCPeers>,
     rtc_senders: web::Data<RtcSenders>,
     server: web::Data<Addr<Server>>,
+    ice_servers: Option<web::Data<RtcIceServers>>,
 ) -> Result<HttpResponse, Error> {
     let client_id = body.client_id.clone();
+    let ice_urls = ice_servers
+        .map(|servers| servers.0.clone())
+        .unwrap_or_else(|| RtcIceServers::default().0);
 
     let pc = api
         .new_peer_connection(webrtc::peer_connection::configuration::RTCConfiguration {
-            ice_servers: vec![webrtc::ice_transport::ice_server::RTCIceServer {
-                urls: vec!["stun:stun.l.google.com:19302".to_string()],
-                ..Default::default()
-            }],
+            ice_servers: if ice_urls.is_empty() {
+                vec![]
+            } else {
+                vec![webrtc::ice_transport::ice_server::RTCIceServer {
+                    urls: ice_urls,
+                    ..Default::default()
+                }]
+            },
             ..Default::default()
         })
         .await
@@ -88,6 +121,18 @@ pub async fn rtc_offer(
         let rtc_rx_opt = rtc_rx_opt.clone();
 
         Box::pin(async move {
+            // Only an unordered channel may take over the client's state
+            // lane. Anything else is ignored and the client keeps receiving
+            // everything over its WebSocket.
+            if !is_state_channel(&dc) {
+                warn!(
+                    "[WebRTC] Ignoring ordered DataChannel '{}' from {}: state needs an unordered channel",
+                    dc.label(),
+                    client_id
+                );
+                return;
+            }
+
             info!(
                 "[WebRTC] DataChannel '{}' opened for {}",
                 dc.label(),
@@ -96,6 +141,17 @@ pub async fn rtc_offer(
 
             rtc_senders.lock().await.insert(client_id.clone(), rtc_tx);
 
+            let close_senders = rtc_senders.clone();
+            let close_client_id = client_id.clone();
+            dc.on_close(Box::new(move || {
+                let rtc_senders = close_senders.clone();
+                let client_id = close_client_id.clone();
+                Box::pin(async move {
+                    info!("[WebRTC] DataChannel closed for {}", client_id);
+                    rtc_senders.lock().await.remove(&client_id);
+                })
+            }));
+
             if let Some(mut rtc_rx) = rtc_rx_opt.lock().await.take() {
                 let dc_send = dc.clone();
                 tokio::spawn(async move {
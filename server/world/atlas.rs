@@ -0,0 +1,745 @@
+//! Texture atlas packing: the block textures packed into one image on the
+//! server, so the UV ranges it sends every client point into a picture it
+//! actually has.
+//!
+//! The atlas is laid out on the same uniform grid as `Registry::generate`
+//! and the client's `AtlasTexture`: a power-of-two number of cells per side,
+//! one per texture group (texture rule textures included) and one per
+//! ungrouped face, each texture drawn over the middle half of its cell with
+//! its edge extruded into the quarter cell around it. Clients that build
+//! their atlas from their own images and clients that load this one see the
+//! same layout, and the chunk shaders' `uAtlasSize` is the manifest's
+//! `count_per_side` either way.
+//!
+//! Textures are looked up in a folder by block and face name. For a face
+//! `px` of a block `Grass Block`, the first of these that exists is used:
+//!
+//! - `<texture group>.png`, when the face is in a texture group; the faces
+//!   of a group share its cell, so they share its texture too,
+//! - `grass block/px.png`, a texture for that one face,
+//! - `grass block.png`, one texture for every face of the block.
+//!
+//! Names are matched case-insensitively. A texture taller than it is wide
+//! whose height is a whole number of widths is an animation strip of square
+//! frames, top to bottom: its cell holds the first frame, and the client
+//! plays the strip into that cell. Faces with no texture get a checkerboard,
+//! so every face still points into the atlas.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use hashbrown::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+
+use super::{Registry, UV};
+
+/// The texture key of the checkerboard given to faces without a texture.
+pub const MISSING_TEXTURE: &str = "#missing";
+
+const MISSING_TEXTURE_SIZE: u32 = 16;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AtlasError {
+    #[error("atlas i/o failed: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("could not decode texture {path}: {source}")]
+    Decode {
+        path: PathBuf,
+        source: png::DecodingError,
+    },
+
+    #[error("could not encode atlas image: {0}")]
+    Encode(#[from] png::EncodingError),
+
+    #[error("could not write atlas manifest: {0}")]
+    Manifest(#[from] serde_json::Error),
+
+    #[error("textures do not fit in a {0}x{0} atlas")]
+    TooLarge(u32),
+}
+
+/// How the atlas is packed.
+#[derive(Debug, Clone)]
+pub struct AtlasOptions {
+    /// The largest atlas side, in pixels, before packing gives up.
+    pub max_size: u32,
+}
+
+impl Default for AtlasOptions {
+    fn default() -> Self {
+        Self { max_size: 8192 }
+    }
+}
+
+/// One cell of the atlas and the texture drawn in it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AtlasTexture {
+    /// The texture's key: its path in the source folder without its
+    /// extension, lowercased, or [`MISSING_TEXTURE`].
+    pub texture: String,
+
+    /// Where the texture is drawn in the cell.
+    pub range: UV,
+
+    /// How many frames the texture's animation strip has; one for a still
+    /// texture. Only the first is drawn.
+    pub frames: u32,
+}
+
+/// The atlas layout written next to the image: which cell each block face
+/// and texture rule texture was given.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtlasManifest {
+    pub width: u32,
+    pub height: u32,
+
+    /// Cells per side of the grid.
+    pub count_per_side: u32,
+
+    /// Block name -> face name -> cell.
+    pub faces: HashMap<String, HashMap<String, AtlasTexture>>,
+
+    /// Texture rule texture name -> cell.
+    #[serde(default)]
+    pub variants: HashMap<String, AtlasTexture>,
+}
+
+impl AtlasManifest {
+    /// The cell a block face was given, if the block was packed.
+    pub fn face_texture(&self, block: &str, face: &str) -> Option<&AtlasTexture> {
+        self.faces.get(block).and_then(|faces| faces.get(face))
+    }
+
+    /// The cell a texture rule's variant or connected texture was given.
+    pub fn variant_texture(&self, name: &str) -> Option<&AtlasTexture> {
+        self.variants.get(name)
+    }
+}
+
+/// A decoded RGBA8 image.
+struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    fn checkerboard(size: u32) -> Self {
+        let mut pixels = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let is_magenta = (x < size / 2) == (y < size / 2);
+                pixels.extend_from_slice(if is_magenta {
+                    &[255, 0, 255, 255]
+                } else {
+                    &[0, 0, 0, 255]
+                });
+            }
+        }
+
+        Self {
+            width: size,
+            height: size,
+            pixels,
+        }
+    }
+
+    fn decode(path: &Path) -> Result<Self, AtlasError> {
+        let decode_error = |source| AtlasError::Decode {
+            path: path.to_owned(),
+            source,
+        };
+
+        let mut decoder = png::Decoder::new(fs::File::open(path)?);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(decode_error)?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).map_err(decode_error)?;
+        buffer.truncate(info.buffer_size());
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => buffer
+                .chunks_exact(3)
+                .flat_map(|p| [p[0], p[1], p[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .flat_map(|p| [p[0], p[0], p[0], p[1]])
+                .collect(),
+            png::ColorType::Grayscale => buffer.iter().flat_map(|&p| [p, p, p, 255]).collect(),
+            png::ColorType::Indexed => unreachable!("indexed images are expanded on decode"),
+        };
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    /// The frame size and count: square frames for an animation strip,
+    /// otherwise the whole image as one frame.
+    fn frames(&self) -> (u32, u32, u32) {
+        if self.height > self.width && self.height.is_multiple_of(self.width) {
+            (self.width, self.width, self.height / self.width)
+        } else {
+            (self.width, self.height, 1)
+        }
+    }
+}
+
+/// The block textures packed into one RGBA image, with its manifest.
+pub struct TextureAtlas {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+    pub manifest: AtlasManifest,
+}
+
+impl TextureAtlas {
+    /// Pack the textures in `source` for every face of every block in the
+    /// registry into the atlas grid. Textures are drawn at the size of the
+    /// largest frame, rounded up to a power of two, so cells are twice that
+    /// and smaller textures are scaled up.
+    pub fn pack(
+        source: impl AsRef<Path>,
+        registry: &Registry,
+        options: &AtlasOptions,
+    ) -> Result<Self, AtlasError> {
+        let files = Self::index(source.as_ref())?;
+
+        let mut blocks: Vec<_> = registry.blocks_by_id.values().collect();
+        blocks.sort_by_key(|block| block.id);
+
+        let texture_key = |candidates: &[String]| {
+            candidates
+                .iter()
+                .find(|key| files.contains_key(*key))
+                .cloned()
+                .unwrap_or_else(|| MISSING_TEXTURE.to_owned())
+        };
+
+        // Cells are counted and handed out as `Registry::generate` does:
+        // texture groups and texture rule textures first, then each
+        // ungrouped face.
+        let mut groups: HashMap<String, String> = HashMap::new();
+        let mut rule_textures: HashSet<String> = HashSet::new();
+        let mut faces = vec![];
+        for block in &blocks {
+            let block_key = block.name.to_lowercase();
+            for face in &block.faces {
+                if face.independent || face.isolated {
+                    continue;
+                }
+                match &face.texture_group {
+                    Some(group) => {
+                        groups.entry(group.clone()).or_insert_with(|| {
+                            texture_key(&[group.to_lowercase(), block_key.clone()])
+                        });
+                    }
+                    None => faces.push((
+                        block.name.clone(),
+                        face.name.clone(),
+                        texture_key(&[
+                            format!("{}/{}", block_key, face.name.to_lowercase()),
+                            block_key.clone(),
+                        ]),
+                    )),
+                }
+            }
+            for rule in &block.texture_rules {
+                for texture in rule.variants.iter().chain(rule.connected.iter()) {
+                    groups
+                        .entry(texture.name.clone())
+                        .or_insert_with(|| texture_key(&[texture.name.to_lowercase()]));
+                    rule_textures.insert(texture.name.clone());
+                }
+            }
+        }
+
+        let mut images: HashMap<String, Image> = HashMap::new();
+        for key in groups.values().chain(faces.iter().map(|(_, _, key)| key)) {
+            if !images.contains_key(key) {
+                let image = match files.get(key) {
+                    Some(path) => Image::decode(path)?,
+                    None => Image::checkerboard(MISSING_TEXTURE_SIZE),
+                };
+                images.insert(key.clone(), image);
+            }
+        }
+
+        let tile = images
+            .values()
+            .map(|image| {
+                let (width, height, _) = image.frames();
+                width.max(height)
+            })
+            .max()
+            .unwrap_or(MISSING_TEXTURE_SIZE)
+            .next_power_of_two();
+        let count_per_side = (((groups.len() + faces.len()) as f32).sqrt().ceil() as u32)
+            .max(1)
+            .next_power_of_two();
+        let size = count_per_side as u64 * tile as u64 * 2;
+        if size > options.max_size as u64 {
+            return Err(AtlasError::TooLarge(options.max_size));
+        }
+        let size = size as u32;
+
+        let mut atlas = Self {
+            width: size,
+            height: size,
+            pixels: vec![0; (size * size * 4) as usize],
+            manifest: AtlasManifest {
+                width: size,
+                height: size,
+                count_per_side,
+                ..Default::default()
+            },
+        };
+
+        let mut next_cell = 0;
+        let mut place = |atlas: &mut Self, key: &str| {
+            let range = grid_uv(
+                count_per_side,
+                next_cell % count_per_side,
+                next_cell / count_per_side,
+            );
+            next_cell += 1;
+
+            let image = &images[key];
+            let x = (range.start_u * size as f32).round() as u32;
+            let y = ((1.0 - range.end_v) * size as f32).round() as u32;
+            atlas.blit(image, x, y, tile);
+
+            AtlasTexture {
+                texture: key.to_owned(),
+                range,
+                frames: image.frames().2,
+            }
+        };
+
+        let mut groups: Vec<_> = groups.into_iter().collect();
+        groups.sort();
+        let groups: HashMap<String, AtlasTexture> = groups
+            .into_iter()
+            .map(|(group, key)| {
+                let texture = place(&mut atlas, &key);
+                (group, texture)
+            })
+            .collect();
+
+        for block in &blocks {
+            for face in &block.faces {
+                if let Some(texture) = face.texture_group.as_ref().and_then(|g| groups.get(g)) {
+                    if !face.independent && !face.isolated {
+                        atlas
+                            .manifest
+                            .faces
+                            .entry(block.name.clone())
+                            .or_default()
+                            .insert(face.name.clone(), texture.clone());
+                    }
+                }
+            }
+        }
+        for (block, face, key) in &faces {
+            let texture = place(&mut atlas, key);
+            atlas
+                .manifest
+                .faces
+                .entry(block.clone())
+                .or_default()
+                .insert(face.clone(), texture);
+        }
+        atlas.manifest.variants = groups
+            .into_iter()
+            .filter(|(name, _)| rule_textures.contains(name))
+            .collect();
+
+        Ok(atlas)
+    }
+
+    /// The PNG files directly in `source` and in its subfolders, by
+    /// lowercased path without extension.
+    fn index(source: &Path) -> Result<HashMap<String, PathBuf>, AtlasError> {
+        let mut files = HashMap::new();
+
+        for entry in fs::read_dir(source)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                let folder = file_key(&path);
+                for entry in fs::read_dir(&path)? {
+                    let path = entry?.path();
+                    if is_png(&path) {
+                        files.insert(format!("{}/{}", folder, file_key(&path)), path);
+                    }
+                }
+            } else if is_png(&path) {
+                files.insert(file_key(&path), path);
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Draw the first frame of `image` scaled to `tile` pixels square at
+    /// `(x, y)`, extruding its edge half a tile out to the cell's border.
+    fn blit(&mut self, image: &Image, x: u32, y: u32, tile: u32) {
+        let (frame_width, frame_height, _) = image.frames();
+        let margin = (tile / 2) as i64;
+        let tile = tile as i64;
+
+        for dy in -margin..tile + margin {
+            for dx in -margin..tile + margin {
+                let sx = (dx.clamp(0, tile - 1) * frame_width as i64 / tile) as u32;
+                let sy = (dy.clamp(0, tile - 1) * frame_height as i64 / tile) as u32;
+                let from = ((sy * image.width + sx) * 4) as usize;
+
+                let tx = (x as i64 + dx) as u32;
+                let ty = (y as i64 + dy) as u32;
+                let to = ((ty * self.width + tx) * 4) as usize;
+
+                self.pixels[to..to + 4].copy_from_slice(&image.pixels[from..from + 4]);
+            }
+        }
+    }
+
+    /// The atlas image as PNG bytes.
+    pub fn to_png(&self) -> Result<Vec<u8>, AtlasError> {
+        let mut bytes = vec![];
+
+        let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+
+        Ok(bytes)
+    }
+
+    /// Write the atlas to `<name>.png` and its manifest to `<name>.json` in
+    /// `directory`, creating the directory if needed.
+    pub fn write(&self, directory: impl AsRef<Path>, name: &str) -> Result<(), AtlasError> {
+        let directory = directory.as_ref();
+        fs::create_dir_all(directory)?;
+
+        fs::write(directory.join(format!("{}.png", name)), self.to_png()?)?;
+        fs::write(
+            directory.join(format!("{}.json", name)),
+            serde_json::to_string(&self.manifest)?,
+        )?;
+
+        Ok(())
+    }
+}
+
+impl Registry {
+    /// Point every packed block face at its texture in the atlas, returning
+    /// how many faces were updated. Faces of dynamic patterns follow the
+    /// block face of the same name, as in [`Registry::generate`], and
+    /// texture rules take the ranges of their packed textures.
+    pub fn apply_atlas(&mut self, manifest: &AtlasManifest) -> usize {
+        let mut updated = 0;
+
+        for block in self.blocks_by_id.values_mut() {
+            for face in block.faces.iter_mut() {
+                if let Some(texture) = manifest.face_texture(&block.name, &face.name) {
+                    face.range = texture.range.clone();
+                    updated += 1;
+                }
+            }
+
+            for rule in block.texture_rules.iter_mut() {
+                for texture in rule.textures_mut() {
+                    if let Some(packed) = manifest.variant_texture(&texture.name) {
+                        texture.range = packed.range.to_mesher_uv();
+                    }
+                }
+            }
+
+            if let Some(dynamic_patterns) = block.dynamic_patterns.as_mut() {
+                for pattern in dynamic_patterns {
+                    for part in &mut pattern.parts {
+                        for face in &mut part.faces {
+                            if let Some(existing) = block.faces.iter().find(|f| f.name == face.name)
+                            {
+                                face.range = existing.range.clone();
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for block in self.blocks_by_id.values() {
+            if let Some(block_by_name) = self.blocks_by_name.get_mut(&block.name.to_lowercase()) {
+                block_by_name.faces = block.faces.clone();
+                block_by_name.dynamic_patterns = block.dynamic_patterns.clone();
+                block_by_name.texture_rules = block.texture_rules.clone();
+            }
+        }
+
+        updated
+    }
+}
+
+/// The range of the middle half of a cell of a grid `count_per_side` cells
+/// wide, with `v` running up from the bottom of the image as the client
+/// samples it, exactly as `Registry::generate` hands them out.
+fn grid_uv(count_per_side: u32, col: u32, row: u32) -> UV {
+    let count = count_per_side as f32;
+    let offset = 1.0 / (count * 4.0);
+
+    UV {
+        start_u: col as f32 / count + offset,
+        end_u: (col + 1) as f32 / count - offset,
+        start_v: row as f32 / count + offset,
+        end_v: (row + 1) as f32 / count - offset,
+    }
+}
+
+fn is_png(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("png"))
+}
+
+fn file_key(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use nanoid::nanoid;
+
+    use super::*;
+    use crate::{Block, BlockFaces, TextureVariantRule, VariantTexture};
+
+    fn write_png(path: &Path, width: u32, height: u32, color: impl Fn(u32, u32) -> [u8; 4]) {
+        let mut atlas = TextureAtlas {
+            width,
+            height,
+            pixels: vec![],
+            manifest: AtlasManifest::default(),
+        };
+        for y in 0..height {
+            for x in 0..width {
+                atlas.pixels.extend_from_slice(&color(x, y));
+            }
+        }
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, atlas.to_png().unwrap()).unwrap();
+    }
+
+    fn pixel_at(atlas: &TextureAtlas, u: f32, v: f32) -> [u8; 4] {
+        let x = (u * atlas.width as f32) as u32;
+        let y = ((1.0 - v) * atlas.height as f32) as u32;
+        let index = ((y * atlas.width + x) * 4) as usize;
+        atlas.pixels[index..index + 4].try_into().unwrap()
+    }
+
+    fn center(uv: &UV) -> (f32, f32) {
+        ((uv.start_u + uv.end_u) / 2.0, (uv.start_v + uv.end_v) / 2.0)
+    }
+
+    fn registry() -> Registry {
+        let mut registry = Registry::new();
+        registry.register_block(&Block::new("Stone").id(1).build());
+        registry.register_block(
+            &Block::new("Grass Block")
+                .id(2)
+                .faces(
+                    &BlockFaces::six_faces()
+                        .texture_group_at(1, "Grass Top")
+                        .build()
+                        .to_vec(),
+                )
+                .build(),
+        );
+        registry.register_block(&Block::new("Glass").id(3).build());
+        registry
+    }
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const GREEN: [u8; 4] = [0, 255, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+    #[test]
+    fn faces_take_the_most_specific_texture_and_missing_ones_get_a_checkerboard() {
+        let source = std::env::temp_dir().join(format!("voxelize-atlas-{}", nanoid!()));
+        write_png(&source.join("Stone.png"), 16, 16, |_, _| RED);
+        write_png(&source.join("stone/PX.png"), 16, 16, |_, _| BLUE);
+        write_png(&source.join("grass top.png"), 8, 8, |_, _| GREEN);
+        write_png(&source.join("grass block.png"), 16, 16, |_, _| RED);
+
+        let atlas = TextureAtlas::pack(&source, &registry(), &AtlasOptions::default()).unwrap();
+        let manifest = &atlas.manifest;
+
+        let face = |block: &str, face: &str| {
+            let (u, v) = center(&manifest.face_texture(block, face).unwrap().range);
+            pixel_at(&atlas, u, v)
+        };
+        assert_eq!(face("Stone", "px"), BLUE);
+        assert_eq!(face("Stone", "nx"), RED);
+        assert_eq!(face("Grass Block", "py"), GREEN);
+        assert_eq!(face("Grass Block", "ny"), RED);
+        assert_eq!(
+            manifest.face_texture("Glass", "pz").unwrap().texture,
+            MISSING_TEXTURE
+        );
+
+        // One group and 23 ungrouped faces make an 8x8 grid of 32px cells,
+        // each texture drawn over the middle 16px of its cell and its edge
+        // repeated out to the cell's border.
+        assert_eq!(manifest.count_per_side, 8);
+        assert_eq!(atlas.width, 256);
+        let grass_top = &manifest.face_texture("Grass Block", "py").unwrap().range;
+        assert_eq!(
+            (grass_top.end_u - grass_top.start_u) * atlas.width as f32,
+            16.0
+        );
+        let edge = pixel_at(
+            &atlas,
+            grass_top.start_u - 7.5 / atlas.width as f32,
+            center(grass_top).1,
+        );
+        assert_eq!(edge, GREEN);
+        for texture in manifest.faces.values().flat_map(|faces| faces.values()) {
+            let x = texture.range.start_u * atlas.width as f32;
+            assert_eq!(x % 32.0, 8.0);
+        }
+
+        fs::remove_dir_all(source).unwrap();
+    }
+
+    #[test]
+    fn animation_strips_show_their_first_frame() {
+        let source = std::env::temp_dir().join(format!("voxelize-atlas-{}", nanoid!()));
+        let colors = [RED, GREEN, BLUE];
+        write_png(&source.join("stone.png"), 4, 12, |_, y| {
+            colors[(y / 4) as usize]
+        });
+
+        let atlas = TextureAtlas::pack(&source, &registry(), &AtlasOptions::default()).unwrap();
+        let stone = atlas.manifest.face_texture("Stone", "px").unwrap();
+
+        assert_eq!(stone.frames, 3);
+        let (u, v) = center(&stone.range);
+        assert_eq!(pixel_at(&atlas, u, v), RED);
+
+        fs::remove_dir_all(source).unwrap();
+    }
+
+    #[test]
+    fn atlases_are_written_and_applied_on_the_generated_grid() {
+        let source = std::env::temp_dir().join(format!("voxelize-atlas-{}", nanoid!()));
+        write_png(&source.join("stone.png"), 16, 16, |_, _| RED);
+
+        let mut registry = registry();
+        registry.generate();
+        let generated = registry.get_block_by_name("stone").faces[0].range.clone();
+
+        let atlas = TextureAtlas::pack(&source, &registry, &AtlasOptions::default()).unwrap();
+        atlas.write(source.join("out"), "atlas").unwrap();
+
+        let manifest: AtlasManifest =
+            serde_json::from_str(&fs::read_to_string(source.join("out/atlas.json")).unwrap())
+                .unwrap();
+        let image = png::Decoder::new(fs::File::open(source.join("out/atlas.png")).unwrap())
+            .read_info()
+            .unwrap();
+        assert_eq!(image.info().width, manifest.width);
+
+        // Air, stone, grass and glass, six faces each.
+        assert_eq!(registry.apply_atlas(&manifest), 24);
+        let stone = registry.get_block_by_name("stone");
+        assert_eq!(
+            stone.faces[0].range,
+            manifest
+                .face_texture("Stone", &stone.faces[0].name)
+                .unwrap()
+                .range
+        );
+
+        // The cells are the size `Registry::generate` made them, so clients
+        // size their atlas grid the same either way.
+        let width = |uv: &UV| uv.end_u - uv.start_u;
+        assert_eq!(width(&stone.faces[0].range), width(&generated));
+
+        fs::remove_dir_all(source).unwrap();
+    }
+
+    #[test]
+    fn texture_rule_variants_are_packed_and_applied() {
+        let source = std::env::temp_dir().join(format!("voxelize-atlas-{}", nanoid!()));
+        write_png(&source.join("stone.png"), 16, 16, |_, _| RED);
+        write_png(&source.join("mossy stone.png"), 16, 16, |_, _| GREEN);
+
+        let mut registry = registry();
+        registry.register_block(
+            &Block::new("Cobble")
+                .id(4)
+                .texture_rule(TextureVariantRule {
+                    variants: vec![
+                        VariantTexture::new("Mossy Stone"),
+                        VariantTexture::new("Cracked Stone"),
+                    ],
+                    ..Default::default()
+                })
+                .build(),
+        );
+
+        let atlas = TextureAtlas::pack(&source, &registry, &AtlasOptions::default()).unwrap();
+        let mossy = atlas.manifest.variant_texture("Mossy Stone").unwrap();
+        assert_eq!(mossy.texture, "mossy stone");
+        assert_eq!(
+            atlas
+                .manifest
+                .variant_texture("Cracked Stone")
+                .unwrap()
+                .texture,
+            MISSING_TEXTURE
+        );
+        assert!(atlas.manifest.variant_texture("Grass Top").is_none());
+
+        let (u, v) = center(&mossy.range);
+        assert_eq!(pixel_at(&atlas, u, v), GREEN);
+
+        registry.apply_atlas(&atlas.manifest);
+        let cobble = registry.get_block_by_name("cobble");
+        assert_eq!(
+            cobble.texture_rules[0].variants[0].range,
+            mossy.range.to_mesher_uv()
+        );
+
+        fs::remove_dir_all(source).unwrap();
+    }
+
+    #[test]
+    fn packing_fails_past_the_maximum_size() {
+        let source = std::env::temp_dir().join(format!("voxelize-atlas-{}", nanoid!()));
+        write_png(&source.join("stone.png"), 64, 64, |_, _| RED);
+
+        let options = AtlasOptions { max_size: 64 };
+        assert!(matches!(
+            TextureAtlas::pack(&source, &registry(), &options),
+            Err(AtlasError::TooLarge(64))
+        ));
+
+        fs::remove_dir_all(source).unwrap();
+    }
+}
@@ -69,8 +69,14 @@ pub fn apply_client_swim_pose_state(
 /// The default client metadata parser, parses PositionComp and DirectionComp, and updates RigidBodyComp.
 /// Position updates are clamped to a maximum per-message delta so clients cannot
 /// teleport past server reach checks (mine/place/stations).
+///
+/// On worlds with authoritative movement the update's movement inputs are
+/// buffered for `AuthoritativeMovementSystem` to simulate, and its reported
+/// position, flying and ghost state are ignored. A client riding
+/// something (see [`AttachmentComp`]) is placed by its mount, so its reported
+/// position is ignored too.
 pub fn default_client_parser(world: &mut World, metadata: &str, client_ent: Entity) {
-    let peer_update: PeerUpdate = match serde_json::from_str(metadata) {
+    let mut peer_update: PeerUpdate = match serde_json::from_str(metadata) {
         Ok(metadata) => metadata,
         Err(_e) => {
             warn!("Could not parse peer update: {}", metadata);
@@ -78,6 +84,30 @@ pub fn default_client_parser(world: &mut World, metadata: &str, client_ent: Enti
         }
     };
 
+    if world
+        .read_component::<AttachmentComp>()
+        .contains(client_ent)
+    {
+        peer_update.position = None;
+    }
+
+    let authoritative_movement = world.config().authoritative_movement;
+
+    if let Some(authoritative_movement) = authoritative_movement {
+        let inputs = peer_update.inputs.take();
+        let mut storage = world.write_component::<MovementInputsComp>();
+        if let (Some(buffered), Some(inputs)) = (storage.get_mut(client_ent), inputs) {
+            let capacity = authoritative_movement.max_buffered_inputs as usize;
+            for input in inputs {
+                buffered.push(input, capacity);
+            }
+        }
+
+        peer_update.position = None;
+        peer_update.is_flying = None;
+        peer_update.is_ghost = None;
+    }
+
     if let Some(position) = peer_update.position {
         // Max plausible movement per peer packet (dash/knockback/lag margin).
         // Far beyond this is treated as a cheat teleport and clamped.
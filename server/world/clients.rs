@@ -2,7 +2,7 @@ use hashbrown::HashMap;
 
 use specs::Entity;
 
-use crate::{server::WsSender, MotionProtocol};
+use crate::{server::WsSender, MeshFormat, MotionProtocol};
 
 /// A client of the server.
 #[derive(Clone)]
@@ -22,6 +22,10 @@ pub struct Client {
     /// How this client receives entity motion, negotiated from the JOIN
     /// request's capabilities (see `world::replication::motion`).
     pub motion_protocol: MotionProtocol,
+
+    /// The vertex layout this client's chunk meshes ship in, negotiated the
+    /// same way (see `world::voxels::mesh_format`).
+    pub mesh_format: MeshFormat,
 }
 
 pub type Clients = HashMap<String, Client>;
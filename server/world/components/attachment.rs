@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use specs::{Component, Entity, VecStorage};
+
+/// Makes an entity or client ride another: each physics tick the rider is
+/// placed at `offset` from its parent, turned with the parent's yaw, instead
+/// of being simulated on its own. Replicated as the `attachment` metadata so
+/// clients can place riders against their interpolated parents.
+#[derive(Debug, Clone, PartialEq, Component, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[storage(VecStorage)]
+pub struct AttachmentComp {
+    /// ID of the entity or client being ridden.
+    pub parent: String,
+
+    /// Where the rider sits relative to the parent's position, in the
+    /// parent's frame: local +z faces the parent's yaw.
+    pub offset: [f32; 3],
+}
+
+impl AttachmentComp {
+    pub fn new(parent: &str, offset: [f32; 3]) -> Self {
+        Self {
+            parent: parent.to_owned(),
+            offset,
+        }
+    }
+}
+
+/// The platform a body is standing on, maintained by the physics system:
+/// the body is carried by however far the platform moved and turned each
+/// tick.
+#[derive(Debug, Clone, Copy, Component)]
+#[storage(VecStorage)]
+pub struct StandingOnComp(pub Entity);
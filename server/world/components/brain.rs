@@ -123,16 +123,22 @@ impl BrainComp {
 
     /// Operate brain state upon a rigid body
     pub fn operate(&mut self, target: &Vec3<f32>, body: &mut RigidBody, dt: f32) {
-        // move implementation originally written as external module
-        //   see https://github.com/andyhall/voxel-fps-controller
-        //   for original code
         let origin = body.get_position();
 
         let dx = target.0 - origin.0;
         let dz = target.2 - origin.2;
 
-        let angle = dx.atan2(dz);
-        self.state.heading = angle;
+        self.operate_heading(dx.atan2(dz), body, dt);
+    }
+
+    /// Operate brain state upon a rigid body facing `heading`, in radians
+    /// around the y axis. The body turns to face it, along with its shape.
+    pub fn operate_heading(&mut self, heading: f32, body: &mut RigidBody, dt: f32) {
+        // move implementation originally written as external module
+        //   see https://github.com/andyhall/voxel-fps-controller
+        //   for original code
+        self.state.heading = heading;
+        body.yaw = heading;
 
         // jumping
         let on_ground = body.at_rest_y() < 0;
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use specs::{Component, VecStorage};
+
+use crate::{BlockUtils, RigidBody, AABB};
+
+/// The entity type of a gravity-affected voxel in flight.
+pub const FALLING_BLOCK_ETYPE: &str = "vox-builtin::falling-block";
+
+/// Just under a voxel wide, so a block falling down a one-wide shaft never
+/// catches on the walls.
+const FALLING_BLOCK_SIZE: f32 = 0.98;
+
+/// A voxel that lost its support and is falling as an entity.
+#[derive(Debug, Default, Clone, Component, Serialize, Deserialize)]
+#[storage(VecStorage)]
+pub struct FallingBlockComp {
+    /// The raw voxel (id, rotation and stage) to place where it lands.
+    pub raw: u32,
+
+    /// The block id, duplicated so clients can pick a model without unpacking.
+    pub id: u32,
+}
+
+impl FallingBlockComp {
+    /// Create a new component carrying the raw voxel value.
+    pub fn new(raw: u32) -> Self {
+        Self {
+            raw,
+            id: BlockUtils::extract_id(raw),
+        }
+    }
+
+    /// The rigid body a falling block is simulated with.
+    pub fn body() -> RigidBody {
+        RigidBody::new(
+            &AABB::new()
+                .scale_x(FALLING_BLOCK_SIZE)
+                .scale_y(FALLING_BLOCK_SIZE)
+                .scale_z(FALLING_BLOCK_SIZE)
+                .build(),
+        )
+        .build()
+    }
+}
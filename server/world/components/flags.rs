@@ -14,3 +14,9 @@ pub struct ClientFlag;
 #[derive(Default, Component)]
 #[storage(NullStorage)]
 pub struct DoNotPersistComp;
+
+/// A flag for entities that carry the bodies standing on top of them, such as
+/// elevators and moving platforms.
+#[derive(Default, Component)]
+#[storage(NullStorage)]
+pub struct PlatformFlag;
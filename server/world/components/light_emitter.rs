@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use specs::{Component, VecStorage};
+
+/// Makes an entity or client glow, like a held torch or a glowing mob. Its
+/// light floods out from the voxel it is in as a transient overlay (see
+/// `DynamicLights`) that follows it without touching chunk light, and is
+/// replicated as the `lightEmitter` metadata for clients to render.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Component, Serialize, Deserialize)]
+#[storage(VecStorage)]
+pub struct LightEmitterComp {
+    pub red: u32,
+    pub green: u32,
+    pub blue: u32,
+}
+
+impl LightEmitterComp {
+    /// Create a light emitter of the given torch light levels.
+    pub fn new(red: u32, green: u32, blue: u32) -> Self {
+        Self { red, green, blue }
+    }
+
+    /// Whether it emits no light at all.
+    pub fn is_dark(&self) -> bool {
+        self.red == 0 && self.green == 0 && self.blue == 0
+    }
+}
@@ -73,6 +73,11 @@ impl MetadataComp {
         }
     }
 
+    /// Remove a component's metadata, e.g. once the component itself is gone.
+    pub fn remove(&mut self, component: &str) -> Option<Value> {
+        self.map.remove(component)
+    }
+
     /// Get a component's metadata. Malformed metadata (a stale persisted
     /// shape, a bad client payload) reads as absent with a loud error
     /// instead of panicking: the panic unwound into the world actor's
@@ -1,4 +1,5 @@
 mod addr;
+mod attachment;
 mod brain;
 mod chunk_requests;
 mod client_preferences;
@@ -6,21 +7,26 @@ mod collisions;
 mod current_chunk;
 mod direction;
 mod etype;
+mod falling_block;
 mod flags;
 mod id;
 mod interactor;
 mod json;
+mod light_emitter;
 mod metadata;
+mod movement_inputs;
 mod name;
 mod path;
 mod position;
+mod projectile;
 mod rewind;
 mod rigidbody;
 mod target;
 mod voxel;
 
 pub use addr::AddrComp;
-pub use brain::BrainComp;
+pub use attachment::{AttachmentComp, StandingOnComp};
+pub use brain::{BrainComp, BrainOptions, BrainState};
 pub use chunk_requests::ChunkRequestsComp;
 pub use client_preferences::{
     parse_preferences_patch, ClientPreferences, ClientPreferencesComp, ClientPreferencesPatch,
@@ -29,14 +35,18 @@ pub use collisions::*;
 pub use current_chunk::CurrentChunkComp;
 pub use direction::DirectionComp;
 pub use etype::ETypeComp;
-pub use flags::{ClientFlag, DoNotPersistComp, EntityFlag};
+pub use falling_block::{FallingBlockComp, FALLING_BLOCK_ETYPE};
+pub use flags::{ClientFlag, DoNotPersistComp, EntityFlag, PlatformFlag};
 pub use id::IDComp;
 pub use interactor::InteractorComp;
 pub use json::*;
+pub use light_emitter::LightEmitterComp;
 pub use metadata::MetadataComp;
+pub use movement_inputs::MovementInputsComp;
 pub use name::NameComp;
 pub use path::PathComp;
 pub use position::PositionComp;
+pub use projectile::{ProjectileBuilder, ProjectileComp};
 pub use rewind::RewindEligibleComp;
 pub use rigidbody::RigidBodyComp;
 pub use target::*;
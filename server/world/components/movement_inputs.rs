@@ -0,0 +1,47 @@
+use std::collections::VecDeque;
+
+use specs::{Component, VecStorage};
+
+use crate::MovementInput;
+
+/// Movement inputs a client sent that the server has yet to simulate, on
+/// worlds with authoritative movement.
+#[derive(Default, Component)]
+#[storage(VecStorage)]
+pub struct MovementInputsComp {
+    pending: VecDeque<MovementInput>,
+
+    /// Sequence number of the newest accepted input; resent or reordered
+    /// inputs at or below it are ignored.
+    last_seq: Option<u64>,
+}
+
+impl MovementInputsComp {
+    /// Buffer an input, keeping at most `capacity` by dropping the oldest.
+    /// Returns whether the input was accepted.
+    pub fn push(&mut self, input: MovementInput, capacity: usize) -> bool {
+        if self.last_seq.is_some_and(|last| input.seq <= last) {
+            return false;
+        }
+
+        self.last_seq = Some(input.seq);
+        if self.pending.len() >= capacity {
+            self.pending.pop_front();
+        }
+        self.pending.push_back(input);
+        true
+    }
+
+    /// Take the oldest input awaiting simulation.
+    pub fn pop(&mut self) -> Option<MovementInput> {
+        self.pending.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
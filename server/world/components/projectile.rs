@@ -0,0 +1,172 @@
+use hashbrown::HashSet;
+use serde::{Deserialize, Serialize};
+use specs::{Component, VecStorage};
+
+use crate::{RewindAnchor, Vec3};
+
+/// A ballistic projectile (arrow, bolt, fireball), simulated by
+/// `ProjectilesSystem` instead of the rigid-body physics: each tick it falls
+/// and slows by its own gravity and drag, and the segment it travels is swept
+/// against voxels and entity hitboxes so fast projectiles never tunnel.
+///
+/// Built with [`ProjectileBuilder`] and spawned through
+/// `World::spawn_projectile`, which replicates the launch state once as the `projectile` metadata; after that only the position and
+/// heading ride the motion lane.
+#[derive(Debug, Clone, Component, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[storage(VecStorage)]
+pub struct ProjectileComp {
+    /// Velocity in blocks per second.
+    pub velocity: Vec3<f32>,
+
+    /// Multiplier on the world's gravity. 0.0 flies straight.
+    pub gravity_multiplier: f32,
+
+    /// Exponential air drag per second: speed is scaled by `exp(-drag * dt)`.
+    pub drag: f32,
+
+    /// How many more entities it passes through before an entity hit stops it.
+    pub pierce: u32,
+
+    /// How many more times it bounces off voxels before a voxel hit stops it.
+    pub bounces: u32,
+
+    /// Fraction of the speed along the hit face's normal kept on a bounce.
+    pub restitution: f32,
+
+    /// Seconds it flies before despawning without a hit.
+    pub lifetime: f32,
+
+    /// Seconds it has flown so far.
+    pub age: f32,
+
+    /// ID of the entity or client that fired it, which it never hits.
+    pub shooter: Option<String>,
+
+    /// Half extents of the box rewound hits test entities without a compound
+    /// shape as. Live hits use each target's own rigid body instead.
+    pub hitbox: [f32; 3],
+
+    /// Fire-time anchor for lag-compensated worlds: when set, entity hits are
+    /// resolved against the rewound poses of rewind-eligible entities instead
+    /// of their current hitboxes.
+    #[serde(skip)]
+    pub rewind: Option<RewindAnchor>,
+
+    /// Entities it already hit, so a piercing projectile never hits the same
+    /// target twice.
+    #[serde(skip)]
+    pub pierced: HashSet<u64>,
+}
+
+impl ProjectileComp {
+    /// The unit heading of the projectile, or `None` when it is at rest.
+    pub fn heading(&self) -> Option<Vec3<f32>> {
+        let speed = self.velocity.len();
+        if speed <= f32::EPSILON {
+            return None;
+        }
+        Some(self.velocity.scale(1.0 / speed))
+    }
+}
+
+#[derive(Default)]
+pub struct ProjectileBuilder {
+    velocity: Vec3<f32>,
+    gravity_multiplier: f32,
+    drag: f32,
+    pierce: u32,
+    bounces: u32,
+    restitution: f32,
+    lifetime: f32,
+    shooter: Option<String>,
+    hitbox: [f32; 3],
+    rewind: Option<RewindAnchor>,
+}
+
+impl ProjectileBuilder {
+    /// Create a new projectile with the builder pattern.
+    pub fn new(velocity: &Vec3<f32>) -> Self {
+        Self {
+            velocity: velocity.to_owned(),
+            gravity_multiplier: 1.0,
+            restitution: 0.5,
+            lifetime: 10.0,
+            hitbox: [0.4, 0.9, 0.4],
+            ..Default::default()
+        }
+    }
+
+    /// Configure the multiplier on the world's gravity. Default is 1.0.
+    pub fn gravity_multiplier(mut self, gravity_multiplier: f32) -> Self {
+        self.gravity_multiplier = gravity_multiplier;
+        self
+    }
+
+    /// Configure the exponential air drag per second. Default is 0.0.
+    pub fn drag(mut self, drag: f32) -> Self {
+        self.drag = drag;
+        self
+    }
+
+    /// Configure how many entities it passes through. Default is 0.
+    pub fn pierce(mut self, pierce: u32) -> Self {
+        self.pierce = pierce;
+        self
+    }
+
+    /// Configure how many times it bounces off voxels. Default is 0.
+    pub fn bounces(mut self, bounces: u32) -> Self {
+        self.bounces = bounces;
+        self
+    }
+
+    /// Configure the speed kept along the normal on a bounce. Default is 0.5.
+    pub fn restitution(mut self, restitution: f32) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    /// Configure how many seconds it flies before despawning. Default is 10.0.
+    pub fn lifetime(mut self, lifetime: f32) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// Configure the ID of the entity or client that fired it.
+    pub fn shooter(mut self, shooter: &str) -> Self {
+        self.shooter = Some(shooter.to_owned());
+        self
+    }
+
+    /// Configure the rewound hitbox half extents. Default is a player-sized
+    /// [0.4, 0.9, 0.4].
+    pub fn hitbox(mut self, half_extents: [f32; 3]) -> Self {
+        self.hitbox = half_extents;
+        self
+    }
+
+    /// Resolve entity hits against rewound poses, anchored at fire time.
+    pub fn rewind(mut self, anchor: RewindAnchor) -> Self {
+        self.rewind = Some(anchor);
+        self
+    }
+
+    /// Create a projectile instance.
+    pub fn build(self) -> ProjectileComp {
+        ProjectileComp {
+            velocity: self.velocity,
+            gravity_multiplier: self.gravity_multiplier,
+            drag: self.drag,
+            pierce: self.pierce,
+            bounces: self.bounces,
+            restitution: self.restitution,
+            lifetime: self.lifetime,
+            age: 0.0,
+            shooter: self.shooter,
+            hitbox: self.hitbox,
+            rewind: self.rewind,
+            pierced: HashSet::new(),
+        }
+    }
+}
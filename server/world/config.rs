@@ -3,6 +3,7 @@ use serde::Serialize;
 use super::fixed_step::FixedStepConfig;
 use super::generators::NoiseOptions;
 use super::lag_comp::LagCompConfig;
+use super::movement::AuthoritativeMovementConfig;
 
 /// World configuration, storing information of how a world is constructed.
 #[derive(Clone, Serialize)]
@@ -198,6 +199,14 @@ pub struct WorldConfig {
     /// tick-anchored; a `Some` here without a fixed step is rejected at build
     /// time.
     pub lag_comp: Option<LagCompConfig>,
+
+    /// Opt-in server-authoritative client movement. `None` (default) applies
+    /// the positions clients report, as before. `Some(..)` has clients send
+    /// per-step movement inputs that the server simulates itself, correcting
+    /// clients whose predictions diverge (see [`AuthoritativeMovementConfig`]).
+    /// Requires [`Self::fixed_timestep`], since inputs are per fixed step; a
+    /// `Some` here without a fixed step is rejected at build time.
+    pub authoritative_movement: Option<AuthoritativeMovementConfig>,
 }
 
 impl Default for WorldConfig {
@@ -314,6 +323,7 @@ pub struct WorldConfigBuilder {
     peer_visible_radius: Option<f32>,
     fixed_timestep: Option<FixedStepConfig>,
     lag_comp: Option<LagCompConfig>,
+    authoritative_movement: Option<AuthoritativeMovementConfig>,
 }
 
 impl WorldConfigBuilder {
@@ -365,6 +375,7 @@ impl WorldConfigBuilder {
             peer_visible_radius: None,
             fixed_timestep: None,
             lag_comp: None,
+            authoritative_movement: None,
         }
     }
 
@@ -631,6 +642,18 @@ impl WorldConfigBuilder {
         self
     }
 
+    /// Opt into (or out of) server-authoritative client movement. `None`
+    /// (default) applies client-reported positions; `Some(..)` simulates
+    /// client inputs on the server. Requires [`Self::fixed_timestep`];
+    /// validated at [`Self::build`].
+    pub fn authoritative_movement(
+        mut self,
+        authoritative_movement: Option<AuthoritativeMovementConfig>,
+    ) -> Self {
+        self.authoritative_movement = authoritative_movement;
+        self
+    }
+
     /// Create a world configuration.
     pub fn build(self) -> WorldConfig {
         // Make sure there are still chunks in the world.
@@ -693,6 +716,15 @@ impl WorldConfigBuilder {
             }
         }
 
+        if let Some(authoritative_movement) = &self.authoritative_movement {
+            if let Err(error) = authoritative_movement.validate() {
+                panic!("Invalid authoritative_movement config: {}", error);
+            }
+            if self.fixed_timestep.is_none() {
+                panic!("authoritative_movement requires fixed_timestep to be set (inputs are per step)");
+            }
+        }
+
         WorldConfig {
             max_clients: self.max_clients,
             chunk_size: self.chunk_size,
@@ -754,6 +786,7 @@ impl WorldConfigBuilder {
             peer_visible_radius: self.peer_visible_radius,
             fixed_timestep: self.fixed_timestep,
             lag_comp: self.lag_comp,
+            authoritative_movement: self.authoritative_movement,
         }
     }
 }
@@ -843,3 +876,32 @@ mod lag_comp_config_tests {
             .build();
     }
 }
+
+#[cfg(test)]
+mod authoritative_movement_config_tests {
+    use super::{AuthoritativeMovementConfig, FixedStepConfig, WorldConfig};
+
+    #[test]
+    #[should_panic(expected = "authoritative_movement requires fixed_timestep")]
+    fn authoritative_movement_without_fixed_timestep_is_rejected() {
+        WorldConfig::new()
+            .authoritative_movement(Some(AuthoritativeMovementConfig::default()))
+            .build();
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid authoritative_movement config")]
+    fn invalid_authoritative_movement_is_rejected() {
+        WorldConfig::new()
+            .fixed_timestep(Some(FixedStepConfig {
+                hz: 60,
+                max_catchup_steps: 5,
+                seed: 1,
+            }))
+            .authoritative_movement(Some(AuthoritativeMovementConfig {
+                correction_threshold: 0.0,
+                max_buffered_inputs: 32,
+            }))
+            .build();
+    }
+}
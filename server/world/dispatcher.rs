@@ -16,12 +16,33 @@ pub(super) fn dispatcher() -> TimedDispatcherBuilder<'static, 'static> {
         )
         .with(ChunkSendingSystem, "chunk-sending", &["chunk-generation"])
         .with(ChunkSavingSystem, "chunk-saving", &["chunk-generation"])
+        .with(
+            AuthoritativeMovementSystem,
+            "authoritative-movement",
+            &["current-chunk", "update-stats", "chunk-updating"],
+        )
         .with(
             PhysicsSystem,
             "physics",
-            &["current-chunk", "update-stats", "chunk-updating"],
+            &[
+                "current-chunk",
+                "update-stats",
+                "chunk-updating",
+                "authoritative-movement",
+            ],
+        )
+        .with(FallingBlocksSystem, "falling-blocks", &["physics"])
+        .with(ProjectilesSystem, "projectiles", &["physics"])
+        .with(
+            DynamicLightsSystem,
+            "dynamic-lights",
+            &["physics", "projectiles"],
+        )
+        .with(
+            EntitiesMetaSystem,
+            "entities-meta",
+            &["physics", "falling-blocks", "projectiles"],
         )
-        .with(EntitiesMetaSystem, "entities-meta", &["physics"])
         .with(DataSavingSystem, "entities-saving", &["entities-meta"])
         .with(
             EntitiesSendingSystem::default(),
@@ -0,0 +1,395 @@
+//! Dynamic lights: torch light carried by entities instead of voxels.
+//!
+//! Each [`LightEmitterComp`] is flooded with the same rules as voxel torch
+//! light (`Lights::flood_light`), but into an overlay kept per emitter rather
+//! than into the chunks' light arrays, so a moving emitter costs one small
+//! flood per voxel it crosses and no chunk is ever relit or remeshed for it.
+//! [`World::light_level_at`] answers with the brighter of the two.
+
+use std::collections::VecDeque;
+
+use hashbrown::HashSet;
+
+use super::*;
+use crate::{LightColor, LightUtils};
+
+/// Dispatches an emitter's overlay is trusted while it stays put. Blocks placed
+/// or broken around a still emitter show up in its light within this long.
+pub const DYNAMIC_LIGHT_REFRESH_TICKS: u64 = 30;
+
+/// The light at a voxel: sunlight, and the brightest torch light of each
+/// color from voxels and light emitters alike.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LightLevel {
+    pub sunlight: u32,
+    pub red: u32,
+    pub green: u32,
+    pub blue: u32,
+}
+
+impl LightLevel {
+    /// The brightest torch light of any color.
+    pub fn torch(&self) -> u32 {
+        self.red.max(self.green).max(self.blue)
+    }
+
+    /// The brightest light of any kind, as used for "is it dark here" checks.
+    pub fn max(&self) -> u32 {
+        self.sunlight.max(self.torch())
+    }
+}
+
+/// One emitter's flooded light, and what it was flooded from.
+struct EmitterFlood {
+    voxel: Vec3<i32>,
+    emitter: LightEmitterComp,
+    flooded_at: u64,
+    lights: HashMap<Vec3<i32>, u32>,
+}
+
+impl EmitterFlood {
+    fn reaches(&self, vx: i32, vy: i32, vz: i32) -> bool {
+        let &Vec3(ex, ey, ez) = &self.voxel;
+        let distance = (vx - ex).abs() + (vy - ey).abs() + (vz - ez).abs();
+        let level = self
+            .emitter
+            .red
+            .max(self.emitter.green)
+            .max(self.emitter.blue);
+        (distance as u32) < level
+    }
+}
+
+/// The voxels of the world, with light read from and written to an overlay
+/// instead of the chunks.
+struct OverlaySpace<'a> {
+    space: &'a dyn VoxelAccess,
+    lights: HashMap<Vec3<i32>, u32>,
+}
+
+impl VoxelAccess for OverlaySpace<'_> {
+    fn waterlogging_rules(&self) -> Option<&WaterloggingRules> {
+        self.space.waterlogging_rules()
+    }
+
+    fn get_raw_voxel(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        self.space.get_raw_voxel(vx, vy, vz)
+    }
+
+    fn get_raw_light(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        self.lights.get(&Vec3(vx, vy, vz)).copied().unwrap_or(0)
+    }
+
+    fn set_raw_light(&mut self, vx: i32, vy: i32, vz: i32, level: u32) -> bool {
+        self.lights.insert(Vec3(vx, vy, vz), level);
+        true
+    }
+
+    fn contains(&self, vx: i32, vy: i32, vz: i32) -> bool {
+        self.space.contains(vx, vy, vz)
+    }
+}
+
+/// The light overlays of every light emitter, maintained by
+/// `DynamicLightsSystem`.
+#[derive(Default)]
+pub struct DynamicLights {
+    floods: HashMap<Entity, EmitterFlood>,
+}
+
+impl DynamicLights {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bring the overlays up to date with the emitters and the voxels they
+    /// are in as of dispatch `now`: emitters that moved to another voxel,
+    /// changed, or went stale are reflooded, and overlays of emitters that
+    /// are gone are dropped.
+    pub fn update(
+        &mut self,
+        emitters: &[(Entity, Vec3<i32>, LightEmitterComp)],
+        now: u64,
+        space: &dyn VoxelAccess,
+        registry: &Registry,
+        config: &WorldConfig,
+    ) {
+        let live: HashSet<Entity> = emitters.iter().map(|(entity, _, _)| *entity).collect();
+        self.floods.retain(|entity, _| live.contains(entity));
+
+        for (entity, voxel, emitter) in emitters {
+            let is_current = self.floods.get(entity).is_some_and(|flood| {
+                flood.voxel == *voxel
+                    && flood.emitter == *emitter
+                    && now < flood.flooded_at + DYNAMIC_LIGHT_REFRESH_TICKS
+            });
+            if is_current {
+                continue;
+            }
+
+            let lights = Self::flood(voxel, emitter, space, registry, config);
+            self.floods.insert(
+                *entity,
+                EmitterFlood {
+                    voxel: voxel.clone(),
+                    emitter: emitter.clone(),
+                    flooded_at: now,
+                    lights,
+                },
+            );
+        }
+    }
+
+    /// Flood one emitter's light from `voxel` into a fresh overlay.
+    fn flood(
+        voxel: &Vec3<i32>,
+        emitter: &LightEmitterComp,
+        space: &dyn VoxelAccess,
+        registry: &Registry,
+        config: &WorldConfig,
+    ) -> HashMap<Vec3<i32>, u32> {
+        let mut overlay = OverlaySpace {
+            space,
+            lights: HashMap::new(),
+        };
+        let &Vec3(vx, vy, vz) = voxel;
+
+        for (color, level) in [
+            (LightColor::Red, emitter.red),
+            (LightColor::Green, emitter.green),
+            (LightColor::Blue, emitter.blue),
+        ] {
+            let level = level.min(config.max_light_level);
+            if level == 0 || vy < 0 || vy >= config.max_height as i32 {
+                continue;
+            }
+
+            overlay.set_torch_light(vx, vy, vz, level, &color);
+            let queue = VecDeque::from([LightNode {
+                voxel: [vx, vy, vz],
+                level,
+            }]);
+            Lights::flood_light(&mut overlay, queue, &color, registry, config, None, None);
+        }
+
+        overlay.lights
+    }
+
+    /// The brightest dynamic torch light of `color` at a voxel.
+    pub fn get_torch_light(&self, vx: i32, vy: i32, vz: i32, color: &LightColor) -> u32 {
+        let voxel = Vec3(vx, vy, vz);
+        self.floods
+            .values()
+            .filter(|flood| flood.reaches(vx, vy, vz))
+            .filter_map(|flood| flood.lights.get(&voxel))
+            .map(|&light| match color {
+                LightColor::Red => LightUtils::extract_red_light(light),
+                LightColor::Green => LightUtils::extract_green_light(light),
+                LightColor::Blue => LightUtils::extract_blue_light(light),
+                LightColor::Sunlight => 0,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// How many emitters have an overlay.
+    pub fn len(&self) -> usize {
+        self.floods.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.floods.is_empty()
+    }
+}
+
+impl World {
+    /// The light at the voxel containing `position`: its sunlight, and for
+    /// each torch color the brighter of the voxel light and the dynamic light
+    /// of nearby emitters. Spawn light rules (`World::set_entity_spawn_light`)
+    /// and gameplay checks ("is it dark here?") ask this rather than the
+    /// chunks, which only know about voxel light.
+    pub fn light_level_at(&self, position: &Vec3<f32>) -> LightLevel {
+        let &Vec3(px, py, pz) = position;
+        let (vx, vy, vz) = (px.floor() as i32, py.floor() as i32, pz.floor() as i32);
+        let chunks = self.chunks();
+        let dynamic = self.read_resource::<DynamicLights>();
+
+        LightLevel {
+            sunlight: chunks.get_sunlight(vx, vy, vz),
+            red: chunks
+                .get_red_light(vx, vy, vz)
+                .max(dynamic.get_torch_light(vx, vy, vz, &LightColor::Red)),
+            green: chunks
+                .get_green_light(vx, vy, vz)
+                .max(dynamic.get_torch_light(vx, vy, vz, &LightColor::Green)),
+            blue: chunks
+                .get_blue_light(vx, vy, vz)
+                .max(dynamic.get_torch_light(vx, vy, vz, &LightColor::Blue)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::RunNow;
+
+    use super::*;
+    use crate::{Block, Chunk, DynamicLightsSystem, EntitiesMetaSystem};
+
+    const STONE: u32 = 1;
+
+    fn world() -> World {
+        let config = WorldConfig::new()
+            .min_chunk([0, 0])
+            .max_chunk([0, 0])
+            .build();
+        let mut world = World::new("dynamic-lights", &config);
+
+        let mut registry = Registry::new();
+        registry.register_block(&Block::new("Stone").id(STONE).build());
+        world.ecs_mut().insert(registry);
+
+        world
+            .write_resource::<Chunks>()
+            .add(Chunk::ready(0, 0, &config));
+        world
+    }
+
+    fn glow(world: &mut World, position: [f32; 3], emitter: LightEmitterComp) -> Entity {
+        let [px, py, pz] = position;
+        world
+            .create_entity("glow", "glow")
+            .with(PositionComp::new(px, py, pz))
+            .with(emitter)
+            .build()
+    }
+
+    fn blue_at(world: &World, position: [f32; 3]) -> u32 {
+        let [px, py, pz] = position;
+        world.light_level_at(&Vec3(px, py, pz)).blue
+    }
+
+    /// The stone shell two blocks out from the voxel (8, 20, 8).
+    fn seal(world: &mut World) {
+        let mut chunks = world.write_resource::<Chunks>();
+        for x in 6..=10 {
+            for y in 18..=22 {
+                for z in 6..=10 {
+                    if x == 6 || x == 10 || y == 18 || y == 22 || z == 6 || z == 10 {
+                        chunks.set_voxel(x, y, z, STONE);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn emitters_light_their_surroundings_without_touching_chunk_light() {
+        let mut world = world();
+        glow(
+            &mut world,
+            [8.5, 20.5, 8.5],
+            LightEmitterComp::new(0, 0, 12),
+        );
+        DynamicLightsSystem.run_now(world.ecs());
+
+        assert_eq!(blue_at(&world, [8.5, 20.5, 8.5]), 12);
+        assert_eq!(blue_at(&world, [8.5, 20.5, 11.5]), 9);
+        assert_eq!(blue_at(&world, [8.5, 20.5, 30.5]), 0);
+        assert_eq!(world.light_level_at(&Vec3(8.5, 20.5, 8.5)).red, 0);
+        assert_eq!(world.chunks().get_blue_light(8, 20, 8), 0);
+
+        // Voxel light and dynamic light combine as the brighter of the two.
+        world
+            .write_resource::<Chunks>()
+            .set_blue_light(8, 20, 11, 11);
+        assert_eq!(blue_at(&world, [8.5, 20.5, 11.5]), 11);
+        assert_eq!(blue_at(&world, [8.5, 20.5, 10.5]), 10);
+    }
+
+    #[test]
+    fn moving_emitters_carry_their_light_and_leave_none_behind() {
+        let mut world = world();
+        let glow = glow(
+            &mut world,
+            [8.5, 20.5, 8.5],
+            LightEmitterComp::new(10, 0, 0),
+        );
+        DynamicLightsSystem.run_now(world.ecs());
+        EntitiesMetaSystem.run_now(world.ecs());
+        assert!(world
+            .read_component::<MetadataComp>()
+            .get(glow)
+            .unwrap()
+            .map
+            .contains_key("lightEmitter"));
+
+        world
+            .write_component::<PositionComp>()
+            .get_mut(glow)
+            .unwrap()
+            .0
+            .set(3.5, 20.5, 8.5);
+        DynamicLightsSystem.run_now(world.ecs());
+        let red_at = |world: &World, x: f32| world.light_level_at(&Vec3(x, 20.5, 8.5)).red;
+        assert_eq!(red_at(&world, 3.5), 10);
+        assert_eq!(red_at(&world, 8.5), 5);
+
+        world.write_component::<LightEmitterComp>().remove(glow);
+        DynamicLightsSystem.run_now(world.ecs());
+        EntitiesMetaSystem.run_now(world.ecs());
+        assert_eq!(red_at(&world, 3.5), 0);
+        assert!(world.read_resource::<DynamicLights>().is_empty());
+        assert!(!world
+            .read_component::<MetadataComp>()
+            .get(glow)
+            .unwrap()
+            .map
+            .contains_key("lightEmitter"));
+    }
+
+    #[test]
+    fn still_emitters_pick_up_blocks_placed_around_them_once_stale() {
+        let mut world = world();
+        glow(
+            &mut world,
+            [8.5, 20.5, 8.5],
+            LightEmitterComp::new(0, 0, 12),
+        );
+        DynamicLightsSystem.run_now(world.ecs());
+        assert_eq!(blue_at(&world, [8.5, 20.5, 11.5]), 9);
+
+        seal(&mut world);
+        DynamicLightsSystem.run_now(world.ecs());
+        assert_eq!(blue_at(&world, [8.5, 20.5, 11.5]), 9);
+
+        for _ in 0..DYNAMIC_LIGHT_REFRESH_TICKS {
+            world.write_resource::<Stats>().advance_dispatch();
+        }
+        DynamicLightsSystem.run_now(world.ecs());
+        assert_eq!(blue_at(&world, [8.5, 20.5, 11.5]), 0);
+        assert_eq!(blue_at(&world, [8.5, 20.5, 9.5]), 11);
+    }
+
+    #[test]
+    fn spawn_light_rules_see_dynamic_light() {
+        let mut world = world();
+        world.set_entity_loader("lurker", |world, _| world.create_entity("lurker", "lurker"));
+        world.set_entity_spawn_light("lurker", 0..=7);
+
+        let spot = Vec3(8.5, 20.5, 8.5);
+        assert!(world.spawn_entity_at("lurker", &spot).is_some());
+
+        glow(
+            &mut world,
+            [8.5, 20.5, 10.5],
+            LightEmitterComp::new(0, 0, 12),
+        );
+        DynamicLightsSystem.run_now(world.ecs());
+        assert_eq!(world.light_level_at(&spot).max(), 10);
+        assert!(world.spawn_entity_at("lurker", &spot).is_none());
+        assert!(world
+            .spawn_entity_at("lurker", &Vec3(8.5, 20.5, 2.5))
+            .is_some());
+    }
+}
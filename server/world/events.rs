@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
-use crate::{ClientFilter, Vec2};
+use crate::{ClientFilter, MovementCorrection, SlotContent, Vec2};
 
 pub const VOXELIZE_BUILTIN_SOUND_EFFECT_EVENT: &str = "vox-builtin:sound-effect";
+pub const VOXELIZE_BUILTIN_ITEM_DROP_EVENT: &str = "vox-builtin:item-drop";
+pub const VOXELIZE_BUILTIN_MOVEMENT_CORRECTION_EVENT: &str = "vox-builtin:movement-correction";
+pub const VOXELIZE_BUILTIN_CARRY_EVENT: &str = "vox-builtin:carry";
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -59,6 +62,21 @@ impl SoundEffectEvent {
     }
 }
 
+/// Something the world let go of at a position, e.g. a falling block that
+/// landed where it could not be placed.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemDropEvent {
+    pub content: SlotContent,
+    pub position: [f32; 3],
+}
+
+impl ItemDropEvent {
+    pub fn new(content: SlotContent, position: [f32; 3]) -> Self {
+        Self { content, position }
+    }
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct Event {
     pub name: String,
@@ -75,6 +93,20 @@ impl Event {
     pub fn sound_effect(payload: SoundEffectEvent) -> EventBuilder {
         EventBuilder::new(VOXELIZE_BUILTIN_SOUND_EFFECT_EVENT).payload(payload)
     }
+
+    pub fn item_drop(payload: ItemDropEvent) -> EventBuilder {
+        EventBuilder::new(VOXELIZE_BUILTIN_ITEM_DROP_EVENT).payload(payload)
+    }
+
+    pub fn movement_correction(payload: MovementCorrection) -> EventBuilder {
+        EventBuilder::new(VOXELIZE_BUILTIN_MOVEMENT_CORRECTION_EVENT).payload(payload)
+    }
+
+    /// How far a client that moves itself was carried by the platform it
+    /// stands on, to move along by.
+    pub fn carry(delta: [f32; 3]) -> EventBuilder {
+        EventBuilder::new(VOXELIZE_BUILTIN_CARRY_EVENT).payload(delta)
+    }
 }
 
 #[derive(Default)]
@@ -0,0 +1,506 @@
+//! Explosions: blast rays that break voxels according to each block's blast
+//! resistance and push nearby bodies away.
+//!
+//! The crater is queued as a single [`BulkUpdate`], so the whole hole is
+//! committed, unlit and remeshed in one update pass no matter how large it is.
+
+use std::collections::HashSet;
+
+use super::*;
+
+/// Blast rays advance this far per step.
+const BLAST_STEP: f32 = 0.3;
+
+/// Strength a ray loses per step even through air, which bounds its reach.
+const BLAST_AIR_DECAY: f32 = BLAST_STEP * 0.75;
+
+/// Added to every solid block's resistance, so even a zero-resistance block
+/// shortens the ray that breaks it.
+const BLAST_BASE_RESISTANCE: f32 = 0.3;
+
+/// The strongest blast there is. Stronger ones are clamped to it, which keeps
+/// a blast's reach, and so the space it is cast through, to about 85 blocks.
+pub const MAX_BLAST_POWER: f32 = 64.0;
+
+/// How an explosion behaves beyond its center and power.
+#[derive(Debug, Clone)]
+pub struct ExplosionOptions {
+    /// Rays are cast toward every cell on the surface of a cube this many
+    /// cells wide. Higher is smoother and slower.
+    pub resolution: usize,
+
+    /// Whether blast rays break blocks at all.
+    pub destroys_blocks: bool,
+
+    /// Scales the impulse pushed into nearby rigid bodies. Zero disables it.
+    pub knockback: f32,
+
+    /// Bodies within `power` times this factor of the center are knocked back.
+    pub knockback_radius: f32,
+}
+
+impl Default for ExplosionOptions {
+    fn default() -> Self {
+        Self {
+            resolution: 16,
+            destroys_blocks: true,
+            knockback: 1.0,
+            knockback_radius: 2.0,
+        }
+    }
+}
+
+impl ExplosionOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn resolution(mut self, resolution: usize) -> Self {
+        self.resolution = resolution.max(2);
+        self
+    }
+
+    pub fn destroys_blocks(mut self, destroys_blocks: bool) -> Self {
+        self.destroys_blocks = destroys_blocks;
+        self
+    }
+
+    pub fn knockback(mut self, knockback: f32) -> Self {
+        self.knockback = knockback;
+        self
+    }
+
+    pub fn knockback_radius(mut self, knockback_radius: f32) -> Self {
+        self.knockback_radius = knockback_radius;
+        self
+    }
+}
+
+/// What an explosion did to the world.
+#[derive(Debug, Clone, Default)]
+pub struct Explosion {
+    /// Voxels the blast broke, sorted. They commit together on the next
+    /// update pass.
+    pub destroyed: Vec<Vec3<i32>>,
+
+    /// Entities that were pushed, including clients.
+    pub knocked_back: Vec<Entity>,
+}
+
+/// The unit directions of the rays cast from an explosion's center: one
+/// toward each surface cell of a `resolution`-wide cube.
+fn blast_directions(resolution: usize) -> Vec<Vec3<f32>> {
+    let last = resolution - 1;
+    let mut directions = Vec::new();
+
+    for x in 0..resolution {
+        for y in 0..resolution {
+            for z in 0..resolution {
+                let is_surface = x == 0 || y == 0 || z == 0 || x == last || y == last || z == last;
+                if !is_surface {
+                    continue;
+                }
+
+                let scale = |v: usize| v as f32 / last as f32 * 2.0 - 1.0;
+                let (dx, dy, dz) = (scale(x), scale(y), scale(z));
+                let length = (dx * dx + dy * dy + dz * dz).sqrt();
+                directions.push(Vec3(dx / length, dy / length, dz / length));
+            }
+        }
+    }
+
+    directions
+}
+
+/// How far a blast ray of strength `power` can travel, through air alone.
+/// Powers beyond [`MAX_BLAST_POWER`] reach as far as it does.
+pub fn blast_reach(power: f32) -> f32 {
+    if power.is_nan() {
+        return 0.0;
+    }
+
+    power.clamp(0.0, MAX_BLAST_POWER) / BLAST_AIR_DECAY * BLAST_STEP
+}
+
+/// Cast blast rays of strength `power` from `center` and collect the voxels
+/// they break. No randomness is involved, so lockstep peers and replays agree
+/// on the crater. `power` is clamped to [`MAX_BLAST_POWER`], and NaN breaks
+/// nothing.
+pub fn blast_voxels(
+    center: &Vec3<f32>,
+    power: f32,
+    resolution: usize,
+    space: &dyn VoxelAccess,
+    registry: &Registry,
+) -> Vec<Vec3<i32>> {
+    if power.is_nan() || power <= 0.0 {
+        return Vec::new();
+    }
+    let power = power.min(MAX_BLAST_POWER);
+
+    let mut destroyed = HashSet::new();
+
+    for direction in blast_directions(resolution.max(2)) {
+        let mut strength = power;
+        let Vec3(mut px, mut py, mut pz) = center.clone();
+
+        while strength > 0.0 {
+            let voxel = Vec3(px.floor() as i32, py.floor() as i32, pz.floor() as i32);
+            let Vec3(vx, vy, vz) = voxel;
+
+            if space.contains(vx, vy, vz) {
+                let id = space.get_voxel(vx, vy, vz);
+                if !registry.is_air(id) {
+                    let block = registry.get_block_by_id(id);
+                    strength -= (block.blast_resistance + BLAST_BASE_RESISTANCE) * BLAST_STEP;
+
+                    // Fluids soak up the blast but are never removed by it.
+                    if strength > 0.0 && !block.is_fluid {
+                        destroyed.insert(voxel);
+                    }
+                }
+            }
+
+            px += direction.0 * BLAST_STEP;
+            py += direction.1 * BLAST_STEP;
+            pz += direction.2 * BLAST_STEP;
+            strength -= BLAST_AIR_DECAY;
+        }
+    }
+
+    let mut destroyed: Vec<_> = destroyed.into_iter().collect();
+    destroyed.sort_by_key(|voxel| (voxel.0, voxel.1, voxel.2));
+    destroyed
+}
+
+impl World {
+    /// Blow up the world at `center`.
+    ///
+    /// Blast rays are cast through a [`Space`] around the center, losing
+    /// strength to each block's `blast_resistance`. Broken voxels are queued
+    /// as one [`BulkUpdate`], so the next update pass relights and remeshes
+    /// the whole crater at once. Rigid bodies nearby are pushed away from the
+    /// center; clients, whose bodies live on their side, receive the impulse
+    /// as an event.
+    ///
+    /// A `power` that is not positive and finite sets off nothing. One beyond
+    /// [`MAX_BLAST_POWER`] is clamped to it.
+    pub fn explode(
+        &mut self,
+        center: &Vec3<f32>,
+        power: f32,
+        options: &ExplosionOptions,
+    ) -> Explosion {
+        let mut explosion = Explosion::default();
+        if !power.is_finite() || power <= 0.0 {
+            return explosion;
+        }
+        let power = power.min(MAX_BLAST_POWER);
+
+        if options.destroys_blocks {
+            let chunk_size = self.config().chunk_size;
+            let Vec3(cx, cy, cz) = center;
+            let coords = ChunkUtils::map_voxel_to_chunk(
+                cx.floor() as i32,
+                cy.floor() as i32,
+                cz.floor() as i32,
+                chunk_size,
+            );
+
+            let destroyed = {
+                let chunks = self.chunks();
+                // The center can sit anywhere in its chunk; the margin covers
+                // the farthest any ray can get from there.
+                let margin = blast_reach(power).ceil() as usize + 1;
+                let space = chunks.make_space(&coords, margin).needs_voxels().build();
+                blast_voxels(center, power, options.resolution, &space, &self.registry())
+            };
+
+            self.chunks_mut().update_voxels_bulk(BulkUpdate(
+                destroyed.iter().map(|voxel| (voxel.clone(), 0)).collect(),
+            ));
+            explosion.destroyed = destroyed;
+        }
+
+        if options.knockback > 0.0 {
+            explosion.knocked_back = self.knock_back(center, power, options);
+        }
+
+        explosion
+    }
+
+    fn knock_back(
+        &mut self,
+        center: &Vec3<f32>,
+        power: f32,
+        options: &ExplosionOptions,
+    ) -> Vec<Entity> {
+        let radius = power * options.knockback_radius;
+        let mut knocked_back = Vec::new();
+        let mut client_impulses = Vec::new();
+
+        {
+            let entities = self.ecs().entities();
+            let positions = self.ecs().read_storage::<PositionComp>();
+            let ids = self.ecs().read_storage::<IDComp>();
+            let client_flags = self.ecs().read_storage::<ClientFlag>();
+            let mut bodies = self.ecs().write_storage::<RigidBodyComp>();
+
+            for (entity, position, body) in (&entities, &positions, &mut bodies).join() {
+                let dx = position.0 .0 - center.0;
+                let dy = position.0 .1 - center.1;
+                let dz = position.0 .2 - center.2;
+                let distance = (dx * dx + dy * dy + dz * dz).sqrt();
+                if distance >= radius {
+                    continue;
+                }
+
+                // A body sitting on the center is thrown straight up.
+                let (nx, ny, nz) = if distance > f32::EPSILON {
+                    (dx / distance, dy / distance, dz / distance)
+                } else {
+                    (0.0, 1.0, 0.0)
+                };
+                let strength = (1.0 - distance / radius) * power * options.knockback;
+                let impulse = [nx * strength, ny * strength, nz * strength];
+
+                if client_flags.get(entity).is_some() {
+                    if let Some(id) = ids.get(entity) {
+                        client_impulses.push((id.0.clone(), impulse));
+                    }
+                } else {
+                    body.0.apply_impulse(impulse[0], impulse[1], impulse[2]);
+                }
+                knocked_back.push(entity);
+            }
+        }
+
+        for (client_id, impulse) in client_impulses {
+            let event = EventBuilder::new("vox-builtin:impulse")
+                .payload(impulse.to_vec())
+                .filter(ClientFilter::Direct(client_id))
+                .build();
+            self.events_mut().dispatch(event);
+        }
+
+        knocked_back
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STONE: u32 = 1;
+    const OBSIDIAN: u32 = 2;
+    const WATER: u32 = 3;
+
+    fn world() -> World {
+        let config = WorldConfig::new()
+            .saving(false)
+            .min_chunk([-1, -1])
+            .max_chunk([1, 1])
+            .build();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Stone").id(STONE).blast_resistance(1.0).build(),
+            Block::new("Obsidian")
+                .id(OBSIDIAN)
+                .blast_resistance(1200.0)
+                .build(),
+            Block::new("Water")
+                .id(WATER)
+                .is_fluid(true)
+                .blast_resistance(100.0)
+                .build(),
+        ]);
+
+        let mut world = World::new("explosions", &config);
+        world.ecs_mut().insert(registry);
+
+        for cx in -1..=1 {
+            for cz in -1..=1 {
+                world.chunks_mut().add(Chunk::ready(cx, cz, &config));
+            }
+        }
+
+        world
+    }
+
+    fn fill(world: &mut World, id: u32, min: Vec3<i32>, max: Vec3<i32>) {
+        let mut chunks = world.chunks_mut();
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                for z in min.2..=max.2 {
+                    chunks.set_voxel(x, y, z, id);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn a_blast_in_stone_leaves_a_crater_queued_as_one_bulk_update() {
+        let mut world = world();
+        fill(&mut world, STONE, Vec3(-8, 0, -8), Vec3(8, 16, 8));
+
+        let explosion = world.explode(&Vec3(0.5, 8.5, 0.5), 4.0, &ExplosionOptions::new());
+
+        assert!(explosion.destroyed.contains(&Vec3(0, 8, 0)));
+        assert!(explosion.destroyed.contains(&Vec3(1, 8, 0)));
+        assert!(!explosion.destroyed.contains(&Vec3(8, 8, 0)));
+
+        let chunks = world.chunks();
+        assert_eq!(chunks.bulk_updates.len(), 1);
+        assert_eq!(chunks.bulk_updates[0].0.len(), explosion.destroyed.len());
+        // Nothing is written until the update pass commits the crater.
+        assert_eq!(chunks.get_voxel(0, 8, 0), STONE);
+    }
+
+    #[test]
+    fn a_powerful_blast_reaches_as_far_as_its_rays_do() {
+        let mut world = world();
+        fill(&mut world, STONE, Vec3(20, 0, -16), Vec3(20, 16, 31));
+
+        let options = ExplosionOptions::new().knockback(0.0);
+        let explosion = world.explode(&Vec3(-15.5, 8.5, 0.5), 30.0, &options);
+
+        assert!(blast_reach(30.0) > 35.5);
+        assert!(explosion.destroyed.iter().any(|voxel| voxel.0 == 20));
+    }
+
+    #[test]
+    fn non_finite_power_sets_off_nothing() {
+        let mut world = world();
+        fill(&mut world, STONE, Vec3(-8, 0, -8), Vec3(8, 16, 8));
+        let body =
+            RigidBody::new(&AABB::new().scale_x(0.5).scale_y(0.5).scale_z(0.5).build()).build();
+        world
+            .ecs_mut()
+            .create_entity()
+            .with(PositionComp::new(2.5, 8.5, 0.5))
+            .with(RigidBodyComp::new(&body))
+            .build();
+
+        for power in [f32::INFINITY, f32::NEG_INFINITY, f32::NAN] {
+            let explosion = world.explode(&Vec3(0.5, 8.5, 0.5), power, &ExplosionOptions::new());
+
+            assert!(explosion.destroyed.is_empty(), "{power}");
+            assert!(explosion.knocked_back.is_empty(), "{power}");
+        }
+        assert!(world.chunks().bulk_updates.is_empty());
+    }
+
+    #[test]
+    fn power_beyond_the_maximum_is_clamped_to_it() {
+        let mut world = world();
+        fill(&mut world, STONE, Vec3(-8, 0, -8), Vec3(8, 16, 8));
+
+        let options = ExplosionOptions::new().resolution(4).knockback(0.0);
+        let center = Vec3(0.5, 8.5, 0.5);
+        let clamped = world.explode(&center, f32::MAX, &options);
+        let strongest = world.explode(&center, MAX_BLAST_POWER, &options);
+
+        assert!(!clamped.destroyed.is_empty());
+        assert_eq!(clamped.destroyed, strongest.destroyed);
+        assert_eq!(blast_reach(f32::INFINITY), blast_reach(MAX_BLAST_POWER));
+
+        let chunks = world.chunks();
+        let space = chunks.make_space(&Vec2(0, 0), 1).needs_voxels().build();
+        let registry = world.registry();
+        let unbounded = blast_voxels(&center, f32::INFINITY, 4, &space, &registry);
+        assert_eq!(
+            unbounded,
+            blast_voxels(&center, MAX_BLAST_POWER, 4, &space, &registry)
+        );
+    }
+
+    #[test]
+    fn resistant_blocks_survive_and_shield_what_is_behind_them() {
+        let mut world = world();
+        fill(&mut world, OBSIDIAN, Vec3(2, 8, -1), Vec3(2, 8, 1));
+        fill(&mut world, STONE, Vec3(3, 8, 0), Vec3(3, 8, 0));
+
+        let explosion = world.explode(&Vec3(0.5, 8.5, 0.5), 4.0, &ExplosionOptions::new());
+
+        assert!(!explosion.destroyed.contains(&Vec3(2, 8, 0)));
+        assert!(!explosion.destroyed.contains(&Vec3(3, 8, 0)));
+    }
+
+    #[test]
+    fn fluids_absorb_the_blast_without_being_removed() {
+        let mut world = world();
+        fill(&mut world, WATER, Vec3(-3, 5, -3), Vec3(3, 11, 3));
+
+        let explosion = world.explode(&Vec3(0.5, 8.5, 0.5), 4.0, &ExplosionOptions::new());
+
+        assert!(explosion.destroyed.is_empty());
+    }
+
+    #[test]
+    fn the_blast_is_deterministic() {
+        let mut world = world();
+        fill(&mut world, STONE, Vec3(-8, 0, -8), Vec3(8, 16, 8));
+
+        let options = ExplosionOptions::new().knockback(0.0);
+        let first = world.explode(&Vec3(0.3, 8.7, 0.1), 3.0, &options);
+        let second = world.explode(&Vec3(0.3, 8.7, 0.1), 3.0, &options);
+
+        assert_eq!(first.destroyed, second.destroyed);
+    }
+
+    #[test]
+    fn nearby_bodies_are_pushed_away_from_the_center() {
+        let mut world = world();
+        let body =
+            RigidBody::new(&AABB::new().scale_x(0.5).scale_y(0.5).scale_z(0.5).build()).build();
+        let near = world
+            .ecs_mut()
+            .create_entity()
+            .with(PositionComp::new(2.5, 8.5, 0.5))
+            .with(RigidBodyComp::new(&body))
+            .build();
+        let far = world
+            .ecs_mut()
+            .create_entity()
+            .with(PositionComp::new(40.5, 8.5, 0.5))
+            .with(RigidBodyComp::new(&body))
+            .build();
+
+        let options = ExplosionOptions::new().destroys_blocks(false);
+        let explosion = world.explode(&Vec3(0.5, 8.5, 0.5), 4.0, &options);
+
+        assert_eq!(explosion.knocked_back, vec![near]);
+        let bodies = world.ecs().read_storage::<RigidBodyComp>();
+        let pushed = &bodies.get(near).unwrap().0;
+        assert!(pushed.impulses.0 > 0.0);
+        assert_eq!(pushed.impulses.1, 0.0);
+        assert_eq!(bodies.get(far).unwrap().0.impulses.0, 0.0);
+    }
+
+    #[test]
+    fn clients_receive_their_knockback_as_an_impulse_event() {
+        let mut world = world();
+        let body =
+            RigidBody::new(&AABB::new().scale_x(0.5).scale_y(0.5).scale_z(0.5).build()).build();
+        world
+            .ecs_mut()
+            .create_entity()
+            .with(IDComp::new("blasted-client"))
+            .with(ClientFlag)
+            .with(PositionComp::new(0.5, 10.5, 0.5))
+            .with(RigidBodyComp::new(&body))
+            .build();
+
+        let options = ExplosionOptions::new().destroys_blocks(false);
+        world.explode(&Vec3(0.5, 8.5, 0.5), 4.0, &options);
+
+        let events = world.events();
+        assert_eq!(events.queue.len(), 1);
+        assert_eq!(events.queue[0].name, "vox-builtin:impulse");
+        assert!(matches!(
+            &events.queue[0].filter,
+            Some(ClientFilter::Direct(id)) if id == "blasted-client"
+        ));
+    }
+}
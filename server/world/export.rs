@@ -0,0 +1,131 @@
+//! Region export: any box of the world meshed into a glTF or OBJ asset, for
+//! bringing builds into a modelling tool.
+//!
+//! The region is meshed a chunk at a time through [`Chunks::make_space`],
+//! the same way the chunk mesher sees it, and stitched into one
+//! [`ExportMesh`] with positions relative to the region's corner.
+
+use super::*;
+
+pub use voxelize_mesher::{
+    ExportAlpha, ExportMaterial, ExportMesh, ExportOptions, ExportPrimitive,
+};
+
+impl World {
+    /// Mesh the voxels from `min` (inclusive) to `max` (exclusive) for
+    /// export, with positions relative to `min`. Chunks that are not loaded
+    /// and ready are left out. Write the result with
+    /// [`ExportMesh::to_glb`] or [`ExportMesh::to_obj`].
+    pub fn export_region(
+        &self,
+        min: &Vec3<i32>,
+        max: &Vec3<i32>,
+        options: ExportOptions,
+    ) -> ExportMesh {
+        let config = self.config();
+        let chunks = self.chunks();
+        let mut registry = self.registry().to_mesher_registry();
+        registry.build_cache();
+
+        let mut mesh = ExportMesh::new(options);
+        let chunk_size = config.chunk_size as i32;
+        let min_y = min.1.max(0);
+        let max_y = max.1.min(config.max_height as i32);
+        if min.0 >= max.0 || min.2 >= max.2 || min_y >= max_y {
+            return mesh;
+        }
+
+        let Vec2(min_cx, min_cz) =
+            ChunkUtils::map_voxel_to_chunk(min.0, 0, min.2, config.chunk_size);
+        let Vec2(max_cx, max_cz) =
+            ChunkUtils::map_voxel_to_chunk(max.0 - 1, 0, max.2 - 1, config.chunk_size);
+
+        for cx in min_cx..=max_cx {
+            for cz in min_cz..=max_cz {
+                let coords = Vec2(cx, cz);
+                if !chunks.is_chunk_ready(&coords) {
+                    continue;
+                }
+
+                let space = chunks
+                    .make_space(&coords, 1)
+                    .needs_voxels()
+                    .needs_lights()
+                    .build();
+
+                let tile_min = [
+                    min.0.max(cx * chunk_size),
+                    min_y,
+                    min.2.max(cz * chunk_size),
+                ];
+                let tile_max = [
+                    max.0.min((cx + 1) * chunk_size),
+                    max_y,
+                    max.2.min((cz + 1) * chunk_size),
+                ];
+
+                let geometries =
+                    voxelize_mesher::mesh_space_greedy(&tile_min, &tile_max, &space, &registry);
+                mesh.add_geometries(
+                    &geometries,
+                    [
+                        (tile_min[0] - min.0) as f32,
+                        (tile_min[1] - min.1) as f32,
+                        (tile_min[2] - min.2) as f32,
+                    ],
+                    &registry,
+                );
+            }
+        }
+
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Block, Chunk};
+
+    const STONE: u32 = 1;
+
+    #[test]
+    fn regions_are_exported_across_chunks_relative_to_their_corner() {
+        let config = WorldConfig::new()
+            .min_chunk([0, 0])
+            .max_chunk([1, 0])
+            .build();
+        let mut world = World::new("export", &config);
+
+        let mut registry = Registry::new();
+        registry.register_block(&Block::new("Stone").id(STONE).build());
+        world.ecs_mut().insert(registry);
+
+        for cx in 0..=1 {
+            let mut chunk = Chunk::ready(cx, 0, &config);
+            chunk.set_voxel(15 + cx, 10, 0, STONE);
+            world.write_resource::<Chunks>().add(chunk);
+        }
+
+        let mesh = world.export_region(&Vec3(8, 8, 0), &Vec3(24, 12, 4), ExportOptions::default());
+
+        // Each chunk meshes its own stone; the face between them is culled.
+        assert_eq!(mesh.triangle_count(), 20);
+        let positions: Vec<_> = mesh
+            .primitives
+            .iter()
+            .flat_map(|primitive| primitive.positions.iter())
+            .collect();
+        assert!(positions
+            .iter()
+            .all(|p| (7.0..=9.0).contains(&p[0]) && (2.0..=3.0).contains(&p[1])));
+        assert!(mesh
+            .materials
+            .iter()
+            .all(|material| material.block == STONE));
+
+        assert!(world
+            .export_region(&Vec3(40, 8, 0), &Vec3(48, 12, 4), ExportOptions::default())
+            .is_empty());
+    }
+}
@@ -293,6 +293,12 @@ impl DetRng {
         Self::from_seed((seed ^ salt).wrapping_add(floor))
     }
 
+    /// The generator's current state, for hashing sim state. Two generators
+    /// with equal state produce identical streams.
+    pub fn state(&self) -> u32 {
+        self.state
+    }
+
     /// Advance the state and return the next 32-bit value.
     pub fn next_u32(&mut self) -> u32 {
         self.state = self.state.wrapping_add(0x6D2B_79F5);
@@ -217,10 +217,12 @@ impl Mesher {
                                     voxel: g.voxel,
                                     at: g.at.map(|[x, y, z]| vec![x, y, z]).unwrap_or_default(),
                                     face_name: g.face_name,
+                                    layer: g.layer,
                                     positions: g.positions,
                                     indices: g.indices,
                                     uvs: g.uvs,
                                     lights: g.lights,
+                                    ..Default::default()
                                 })
                                 .collect();
 
@@ -63,6 +63,15 @@ impl World {
         self.command_handle = Some(Arc::new(handle));
     }
 
+    /// Set the handler called with every projectile hit, after the dispatch
+    /// that recorded it.
+    pub fn set_projectile_hit_handle<F: Fn(&mut World, &ProjectileHit) + Send + Sync + 'static>(
+        &mut self,
+        handle: F,
+    ) {
+        self.projectile_hit_handle = Some(Arc::new(handle));
+    }
+
     pub fn set_extra_init_data(&mut self, key: &str, value: serde_json::Value) {
         self.extra_init_data.insert(key.to_owned(), value);
     }
@@ -86,6 +95,15 @@ impl World {
             .insert(etype.to_lowercase(), Arc::new(loader));
     }
 
+    /// Only spawn entities of type where the light at the spawn position,
+    /// voxel and dynamic alike (see `World::light_level_at`), is within
+    /// `levels`; `spawn_entity_at` refuses anywhere else. Revived entities
+    /// are not checked.
+    pub fn set_entity_spawn_light(&mut self, etype: &str, levels: RangeInclusive<u32>) {
+        self.entity_spawn_lights
+            .insert(etype.to_lowercase(), levels);
+    }
+
     pub fn has_entity_loader(&self, etype: &str) -> bool {
         self.entity_loaders.contains_key(&etype.to_lowercase())
     }
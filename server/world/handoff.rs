@@ -0,0 +1,297 @@
+//! Moving a live world from one server process to another.
+//!
+//! A drained world is captured as a [`WorldSnapshot`] — its generated chunks
+//! in the chunk file encoding, its persistent entities, and its clock — and
+//! restored into a freshly created world of the same name and shape on the
+//! receiving server. Connected clients are not part of the snapshot: they
+//! reconnect and join the world again wherever it now lives.
+
+use super::*;
+
+/// Bumped whenever the [`WorldSnapshot`] layout changes incompatibly.
+pub const WORLD_SNAPSHOT_VERSION: u32 = 1;
+
+/// Everything needed to resume a world on another server.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorldSnapshot {
+    pub version: u32,
+    pub name: String,
+    /// Shape of the captured chunks; the receiving world must match it.
+    pub chunk_size: usize,
+    pub max_height: usize,
+    pub stats: StatsJson,
+    pub chunks: Vec<ChunkSnapshot>,
+    pub entities: Vec<EntitySnapshot>,
+}
+
+/// A persistent entity as it would be written to disk.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntitySnapshot {
+    pub id: String,
+    pub etype: String,
+    pub metadata: MetadataComp,
+}
+
+/// Capture the world's state for a handoff. Runs on the world's own thread,
+/// FIFO after any in-flight tick.
+#[derive(ActixMessage)]
+#[rtype(result = "WorldSnapshot")]
+pub(crate) struct SnapshotWorld;
+
+/// Load a handed-off world's state into this (freshly created) world.
+#[derive(ActixMessage)]
+#[rtype(result = "()")]
+pub(crate) struct RestoreWorld(pub WorldSnapshot);
+
+impl World {
+    /// Capture this world's generated chunks, persistent entities, and clock.
+    /// Entities flagged [`DoNotPersistComp`] are left behind, exactly as they
+    /// are left out of saves.
+    pub fn snapshot(&self) -> WorldSnapshot {
+        let entities = {
+            let ids = self.read_component::<IDComp>();
+            let etypes = self.read_component::<ETypeComp>();
+            let metadatas = self.read_component::<MetadataComp>();
+            let do_not_persist = self.read_component::<DoNotPersistComp>();
+            let client_flags = self.read_component::<ClientFlag>();
+
+            (&ids, &etypes, &metadatas, !&do_not_persist, !&client_flags)
+                .join()
+                .map(|(id, etype, metadata, ..)| EntitySnapshot {
+                    id: id.0.to_owned(),
+                    etype: etype.0.to_owned(),
+                    metadata: metadata.to_owned(),
+                })
+                .collect()
+        };
+
+        let config = self.config();
+
+        WorldSnapshot {
+            version: WORLD_SNAPSHOT_VERSION,
+            name: self.name.to_owned(),
+            chunk_size: config.chunk_size,
+            max_height: config.max_height,
+            stats: self.stats().get_stats(),
+            chunks: self.chunks().snapshot(self.stats().tick),
+            entities,
+        }
+    }
+
+    /// Restore a snapshot taken by [`World::snapshot`]. Chunks come back parked
+    /// like chunks loaded from disk, and entities are revived through the same
+    /// loaders. A chunk or entity that cannot be restored is logged and
+    /// skipped; the rest of the world still moves.
+    pub fn restore(&mut self, snapshot: WorldSnapshot) {
+        let WorldSnapshot {
+            version,
+            name,
+            chunks,
+            entities,
+            stats,
+            ..
+        } = snapshot;
+
+        if version != WORLD_SNAPSHOT_VERSION {
+            warn!(
+                "World {:?} received a snapshot of {:?} with version {} (expected {}); ignoring it",
+                self.name, name, version, WORLD_SNAPSHOT_VERSION
+            );
+            return;
+        }
+
+        // The clock comes first: restored chunks schedule their pending
+        // ticks relative to it.
+        {
+            let mut world_stats = self.stats_mut();
+            world_stats.tick = stats.tick;
+            world_stats.time = stats.time;
+        }
+
+        let chunk_count = chunks.len();
+        let restored_chunks = self.restore_chunks(chunks);
+
+        let entity_count = entities.len();
+        let mut revived = Vec::new();
+        for EntitySnapshot {
+            id,
+            etype,
+            metadata,
+        } in entities
+        {
+            match self.revive_entity(&id, &etype, metadata.to_owned()) {
+                Some(ent) => revived.push((id, etype, ent, metadata.to_string())),
+                None => warn!(
+                    "World {:?} could not revive handed-off entity {:?} of type {}",
+                    self.name, id, etype
+                ),
+            }
+        }
+
+        for (_, _, ent, _) in &revived {
+            let position = self
+                .read_component::<PositionComp>()
+                .get(*ent)
+                .map(|position| position.0.clone());
+            if let (Some(Vec3(x, y, z)), Some(body)) = (
+                position,
+                self.write_component::<RigidBodyComp>().get_mut(*ent),
+            ) {
+                body.0.set_position(x, y, z);
+            }
+        }
+
+        let revived_entities = revived.len();
+        {
+            let mut bookkeeping = self.bookkeeping_mut();
+            for (id, etype, ent, metadata) in revived {
+                bookkeeping
+                    .entities
+                    .insert(id, (etype, ent, metadata, true));
+            }
+        }
+
+        info!(
+            "World {:?} restored from a handoff: {}/{} chunks, {}/{} entities.",
+            self.name, restored_chunks, chunk_count, revived_entities, entity_count
+        );
+    }
+
+    /// Put captured chunks in place, parked like chunks loaded from disk, and
+    /// schedule their pending ticks from the world's current tick. Chunks that
+    /// cannot be restored are logged and skipped; returns how many were
+    /// restored.
+    pub(crate) fn restore_chunks(&mut self, chunks: Vec<ChunkSnapshot>) -> usize {
+        let restored: Vec<(Chunk, Vec<ScheduledTick>)> = {
+            let registry = self.registry();
+            let chunks_resource = self.chunks();
+            chunks
+                .into_iter()
+                .filter_map(|chunk| match chunks_resource.restore(chunk, &registry) {
+                    Ok(chunk) => Some(chunk),
+                    Err(reason) => {
+                        warn!("World {:?} skipped a captured chunk: {}", self.name, reason);
+                        None
+                    }
+                })
+                .collect()
+        };
+        let restored_chunks = restored.len();
+        let current_tick = self.stats().tick;
+        let mut chunks = self.chunks_mut();
+        for (chunk, scheduled_ticks) in restored {
+            let coords = chunk.coords.clone();
+            chunks.renew(chunk, ChunkRenewal::Full);
+            chunks.restore_scheduled_ticks(&coords, &scheduled_ticks, current_tick);
+        }
+        restored_chunks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_world(name: &str) -> World {
+        let config = WorldConfig::new()
+            .chunk_size(8)
+            .max_height(16)
+            .min_chunk([-1, -1])
+            .max_chunk([1, 1])
+            .build();
+        let mut world = World::new(name, &config);
+        world.ecs_mut().insert(Registry::new());
+        world
+    }
+
+    fn options() -> ChunkOptions {
+        ChunkOptions {
+            size: 8,
+            max_height: 16,
+            sub_chunks: 1,
+        }
+    }
+
+    #[test]
+    fn generated_chunks_and_clock_survive_a_handoff() {
+        let mut source = small_world("source");
+        {
+            let mut chunks = source.chunks_mut();
+            let mut ready = Chunk::new("ready", 0, 1, &options());
+            ready.set_raw_voxel(3, 5, 10, 7);
+            ready.status = ChunkStatus::Ready;
+            chunks.renew(ready, ChunkRenewal::Full);
+
+            let generating = Chunk::new("generating", 1, 0, &options());
+            chunks.renew(generating, ChunkRenewal::Full);
+        }
+        source.stats_mut().time = 321.0;
+
+        let snapshot = source.snapshot();
+        let encoded = serde_json::to_vec(&snapshot).unwrap();
+        let snapshot: WorldSnapshot = serde_json::from_slice(&encoded).unwrap();
+        assert_eq!(snapshot.chunks.len(), 1);
+
+        let mut target = small_world("source");
+        target.restore(snapshot);
+
+        let chunks = target.chunks();
+        let chunk = chunks.raw(&Vec2(0, 1)).expect("the ready chunk moves");
+        assert_eq!(chunk.id, "ready");
+        assert!(matches!(chunk.status, ChunkStatus::Meshing));
+        assert_eq!(chunk.get_raw_voxel(3, 5, 10), 7);
+        assert!(chunks.raw(&Vec2(1, 0)).is_none());
+        assert_eq!(target.stats().time, 321.0);
+    }
+
+    #[test]
+    fn pending_block_ticks_survive_a_handoff() {
+        let mut source = small_world("ticking");
+        {
+            let mut chunks = source.chunks_mut();
+            let mut ready = Chunk::new("ready", 0, 1, &options());
+            ready.status = ChunkStatus::Ready;
+            chunks.renew(ready, ChunkRenewal::Full);
+            chunks.mark_voxel_active(&Vec3(3, 5, 10), 57);
+        }
+        source.stats_mut().tick = 50;
+
+        let snapshot = source.snapshot();
+        let encoded = serde_json::to_vec(&snapshot).unwrap();
+        let snapshot: WorldSnapshot = serde_json::from_slice(&encoded).unwrap();
+
+        let mut target = small_world("ticking");
+        target.restore(snapshot);
+
+        assert_eq!(
+            target.chunks().active_voxel_deadline(&Vec3(3, 5, 10)),
+            Some(57)
+        );
+    }
+
+    #[test]
+    fn chunks_outside_the_receiving_world_are_skipped() {
+        let mut source = small_world("wide");
+        {
+            let mut chunks = source.chunks_mut();
+            let mut edge = Chunk::new("edge", 1, 1, &options());
+            edge.status = ChunkStatus::Ready;
+            chunks.renew(edge, ChunkRenewal::Full);
+        }
+        let snapshot = source.snapshot();
+
+        let config = WorldConfig::new()
+            .chunk_size(8)
+            .max_height(16)
+            .min_chunk([0, 0])
+            .max_chunk([0, 0])
+            .build();
+        let mut target = World::new("wide", &config);
+        target.ecs_mut().insert(Registry::new());
+        target.restore(snapshot);
+
+        assert!(target.chunks().raw(&Vec2(1, 1)).is_none());
+    }
+}
@@ -18,6 +18,18 @@ struct OnEventRequest {
     payload: Value,
 }
 
+/// What an INIT tells a client about its own session: the body state it left
+/// off with and, for resumable sessions, its token and starting sequence.
+#[derive(Default)]
+pub(super) struct InitSession<'a> {
+    pub saved_position: Option<[f32; 3]>,
+    pub saved_direction: Option<[f32; 3]>,
+    pub saved_is_flying: Option<bool>,
+    pub saved_is_ghost: Option<bool>,
+    pub saved_is_swimming: Option<bool>,
+    pub resume: Option<(&'a str, u64)>,
+}
+
 impl World {
     /// Handler for protobuf requests from clients.
     pub(crate) fn on_request(&mut self, client_id: &str, data: Message) {
@@ -37,6 +49,17 @@ impl World {
                 }
             }
         }
+        self.record_input(|| RecordedInput::Request {
+            client_id: client_id.to_owned(),
+            message: encode_message(&data),
+        });
+
+        // Replay worlds are read-only: viewers may move around and steer
+        // playback, nothing else.
+        if self.is_replay() && !Self::is_replay_viewer_message(&data) {
+            return;
+        }
+
         let msg_type = MessageType::from_i32(data.r#type).unwrap();
 
         match msg_type {
@@ -335,13 +358,18 @@ impl World {
     pub(super) fn generate_init_message(
         &self,
         id: &str,
-        saved_position: Option<[f32; 3]>,
-        saved_direction: Option<[f32; 3]>,
-        saved_is_flying: Option<bool>,
-        saved_is_ghost: Option<bool>,
-        saved_is_swimming: Option<bool>,
+        session: InitSession,
         is_for_transport: bool,
     ) -> (Message, Vec<String>) {
+        let InitSession {
+            saved_position,
+            saved_direction,
+            saved_is_flying,
+            saved_is_ghost,
+            saved_is_swimming,
+            resume,
+        } = session;
+
         let config = (*self.config()).to_owned();
         let mut json = HashMap::new();
 
@@ -369,6 +397,13 @@ impl World {
             json.insert("savedIsSwimming".to_owned(), json!(is_swimming));
         }
 
+        // Resumable sessions learn their token and the sequence they start
+        // from; see `SessionLogs`.
+        if let Some((token, seq)) = resume {
+            json.insert("resumeToken".to_owned(), json!(token));
+            json.insert("seq".to_owned(), json!(seq));
+        }
+
         if let Some(items) = &self.items {
             json.insert("items".to_owned(), items.to_client_json());
         }
@@ -0,0 +1,790 @@
+//! Input recording and lockstep replay for fixed-step worlds.
+//!
+//! A fixed-step world is a pure function of its seed and the ordered stream of
+//! inputs applied between steps (see [`crate::FixedStepConfig`]). Recording
+//! that stream — every join, resume, leave, client request and inbound
+//! position packet, each tagged with the step it was applied after — plus a
+//! hash of the sim state after every step is enough to reproduce a real
+//! session headlessly: [`World::replay_inputs`] rebuilds nothing but the
+//! inputs, reapplies them to a fresh world built with the same setup, and
+//! stops at the first step whose state hash differs from the recorded one.
+//!
+//! The state hash ([`World::sim_state_hash`]) covers the sim clock, the seeded
+//! roller, and every identified entity's type, position, velocity and
+//! metadata. Voxels are not hashed: chunk generation runs off the sim thread
+//! and finishes on no particular step.
+//!
+//! File layout: the magic `VXINPUTS`, a little-endian `u32` version, a
+//! varint-length-prefixed JSON [`InputLogHeader`], then entries of
+//! `varint(step delta) varint(length) payload`, where the payload is a tag
+//! byte followed by the entry's length-prefixed fields. As with replays, a
+//! log cut off mid-entry still opens.
+
+use std::io::{self, BufWriter, Write as _};
+use std::path::Path;
+
+use prost::encoding::{decode_varint, encode_varint};
+use tokio::sync::mpsc;
+
+use super::*;
+use crate::decode_message;
+
+/// First bytes of every input log.
+pub const INPUT_LOG_MAGIC: &[u8; 8] = b"VXINPUTS";
+
+/// Bumped whenever the input log layout changes incompatibly.
+pub const INPUT_LOG_VERSION: u32 = 1;
+
+const TAG_JOIN: u8 = 0;
+const TAG_LEAVE: u8 = 1;
+const TAG_REQUEST: u8 = 2;
+const TAG_STATE: u8 = 3;
+const TAG_STEP: u8 = 4;
+const TAG_RESUME: u8 = 5;
+
+/// Why an input log could not be recorded or replayed.
+#[derive(Debug, thiserror::Error)]
+pub enum LockstepError {
+    #[error("input log i/o failed: {0}")]
+    Io(#[from] io::Error),
+    #[error("world '{0}' does not run a fixed step")]
+    NotFixedStep(String),
+    #[error("world has already run {0} steps; lockstep needs it from step 0")]
+    AlreadyStarted(u64),
+    #[error("not an input log")]
+    NotAnInputLog,
+    #[error("input log version {0} is not supported (expected {INPUT_LOG_VERSION})")]
+    UnsupportedVersion(u32),
+    #[error("corrupt input log: {0}")]
+    Corrupt(String),
+    #[error("input log was recorded with {recorded:?}, but the world runs {world:?}")]
+    ConfigMismatch {
+        recorded: FixedStepConfig,
+        world: FixedStepConfig,
+    },
+    #[error("desync at step {step}: recorded state hash {expected:016x}, replayed {actual:016x}")]
+    Desync {
+        step: u64,
+        expected: u64,
+        actual: u64,
+    },
+}
+
+/// What the recorded world looked like when recording started.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputLogHeader {
+    /// Name of the recorded world.
+    pub world: String,
+    pub fixed_step: FixedStepConfig,
+}
+
+/// One input applied to the sim, or the state hash after a step.
+#[derive(Clone, Debug)]
+pub enum RecordedInput {
+    /// A client joined the world.
+    Join {
+        client_id: String,
+        username: String,
+        preferences: ClientPreferencesPatch,
+        compact_motion: bool,
+    },
+    /// A dropped client resumed its session, re-sending its preferences.
+    Resume {
+        client_id: String,
+        preferences: ClientPreferencesPatch,
+    },
+    /// A client left the world.
+    Leave { client_id: String },
+    /// A client request, as the encoded [`Message`] the world handled.
+    Request { client_id: String, message: Vec<u8> },
+    /// An inbound position packet, as the encoded [`Message`] applied at the
+    /// start of a tick.
+    State { client_id: String, message: Vec<u8> },
+    /// [`World::sim_state_hash`] right after the step was committed.
+    StepHash(u64),
+}
+
+/// An input, tagged with the number of steps the world had committed when it
+/// was applied. A [`RecordedInput::StepHash`] carries the step it hashes.
+#[derive(Clone, Debug)]
+pub struct InputEntry {
+    pub step: u64,
+    pub input: RecordedInput,
+}
+
+/// An input log read back into memory.
+pub struct InputLog {
+    pub header: InputLogHeader,
+    pub entries: Vec<InputEntry>,
+}
+
+/// Outcome of a lockstep replay that matched the recording.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LockstepReport {
+    /// Steps replayed, every one of them hash-checked.
+    pub steps: u64,
+    /// Inputs reapplied between them.
+    pub inputs: usize,
+}
+
+fn put_bytes(bytes: &[u8], buf: &mut Vec<u8>) {
+    encode_varint(bytes.len() as u64, buf);
+    buf.extend_from_slice(bytes);
+}
+
+fn take_bytes(buf: &mut &[u8]) -> Option<Vec<u8>> {
+    let len = decode_varint(buf).ok()? as usize;
+    if len > buf.len() {
+        return None;
+    }
+    let (bytes, rest) = buf.split_at(len);
+    *buf = rest;
+    Some(bytes.to_vec())
+}
+
+fn take_string(buf: &mut &[u8]) -> Option<String> {
+    String::from_utf8(take_bytes(buf)?).ok()
+}
+
+impl RecordedInput {
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            RecordedInput::Join {
+                client_id,
+                username,
+                preferences,
+                compact_motion,
+            } => {
+                buf.push(TAG_JOIN);
+                put_bytes(client_id.as_bytes(), &mut buf);
+                put_bytes(username.as_bytes(), &mut buf);
+                put_bytes(&serde_json::to_vec(preferences).unwrap(), &mut buf);
+                buf.push(*compact_motion as u8);
+            }
+            RecordedInput::Resume {
+                client_id,
+                preferences,
+            } => {
+                buf.push(TAG_RESUME);
+                put_bytes(client_id.as_bytes(), &mut buf);
+                put_bytes(&serde_json::to_vec(preferences).unwrap(), &mut buf);
+            }
+            RecordedInput::Leave { client_id } => {
+                buf.push(TAG_LEAVE);
+                put_bytes(client_id.as_bytes(), &mut buf);
+            }
+            RecordedInput::Request { client_id, message } => {
+                buf.push(TAG_REQUEST);
+                put_bytes(client_id.as_bytes(), &mut buf);
+                put_bytes(message, &mut buf);
+            }
+            RecordedInput::State { client_id, message } => {
+                buf.push(TAG_STATE);
+                put_bytes(client_id.as_bytes(), &mut buf);
+                put_bytes(message, &mut buf);
+            }
+            RecordedInput::StepHash(hash) => {
+                buf.push(TAG_STEP);
+                buf.extend_from_slice(&hash.to_le_bytes());
+            }
+        }
+        buf
+    }
+
+    fn decode(mut buf: &[u8]) -> Option<Self> {
+        let (&tag, rest) = buf.split_first()?;
+        buf = rest;
+        let input = match tag {
+            TAG_JOIN => RecordedInput::Join {
+                client_id: take_string(&mut buf)?,
+                username: take_string(&mut buf)?,
+                preferences: serde_json::from_slice(&take_bytes(&mut buf)?).ok()?,
+                compact_motion: *buf.first()? != 0,
+            },
+            TAG_RESUME => RecordedInput::Resume {
+                client_id: take_string(&mut buf)?,
+                preferences: serde_json::from_slice(&take_bytes(&mut buf)?).ok()?,
+            },
+            TAG_LEAVE => RecordedInput::Leave {
+                client_id: take_string(&mut buf)?,
+            },
+            TAG_REQUEST => RecordedInput::Request {
+                client_id: take_string(&mut buf)?,
+                message: take_bytes(&mut buf)?,
+            },
+            TAG_STATE => RecordedInput::State {
+                client_id: take_string(&mut buf)?,
+                message: take_bytes(&mut buf)?,
+            },
+            TAG_STEP => RecordedInput::StepHash(u64::from_le_bytes(buf.get(..8)?.try_into().ok()?)),
+            _ => return None,
+        };
+        Some(input)
+    }
+}
+
+impl InputLog {
+    /// Read an input log file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, LockstepError> {
+        Self::decode(&fs::read(path)?)
+    }
+
+    /// Parse the bytes of an input log.
+    pub fn decode(bytes: &[u8]) -> Result<Self, LockstepError> {
+        if bytes.len() < INPUT_LOG_MAGIC.len() + 4
+            || &bytes[..INPUT_LOG_MAGIC.len()] != INPUT_LOG_MAGIC
+        {
+            return Err(LockstepError::NotAnInputLog);
+        }
+        let mut rest = &bytes[INPUT_LOG_MAGIC.len()..];
+        let version = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+        if version != INPUT_LOG_VERSION {
+            return Err(LockstepError::UnsupportedVersion(version));
+        }
+        rest = &rest[4..];
+
+        let header = take_bytes(&mut rest)
+            .ok_or_else(|| LockstepError::Corrupt("truncated header".to_owned()))?;
+        let header: InputLogHeader = serde_json::from_slice(&header)
+            .map_err(|error| LockstepError::Corrupt(error.to_string()))?;
+
+        let mut entries = Vec::new();
+        let mut step = 0;
+        while !rest.is_empty() {
+            let Ok(delta) = decode_varint(&mut rest) else {
+                break;
+            };
+            let Some(payload) = take_bytes(&mut rest) else {
+                break;
+            };
+            step += delta;
+            let Some(input) = RecordedInput::decode(&payload) else {
+                return Err(LockstepError::Corrupt(format!(
+                    "unreadable entry at step {}",
+                    step
+                )));
+            };
+            entries.push(InputEntry { step, input });
+        }
+        if !rest.is_empty() {
+            warn!(
+                "Input log of {:?} ends in a partial entry; keeping the {} whole entries before it",
+                header.world,
+                entries.len()
+            );
+        }
+
+        Ok(Self { header, entries })
+    }
+
+    /// Number of steps the recording covers.
+    pub fn steps(&self) -> u64 {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.input, RecordedInput::StepHash(_)))
+            .count() as u64
+    }
+}
+
+struct InputLogWriter {
+    file: BufWriter<File>,
+    last_step: u64,
+}
+
+/// The world's running input recording, if any. See
+/// [`World::start_input_recording`].
+#[derive(Default)]
+pub struct InputRecorder {
+    writer: Option<InputLogWriter>,
+}
+
+impl InputRecorder {
+    pub fn is_recording(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    /// Append an input applied after `step` committed steps. A failed write
+    /// ends the recording: a log with a gap in it can only desync.
+    pub fn record(&mut self, step: u64, input: &RecordedInput) {
+        let Some(writer) = self.writer.as_mut() else {
+            return;
+        };
+        let mut bytes = Vec::new();
+        encode_varint(step.saturating_sub(writer.last_step), &mut bytes);
+        put_bytes(&input.encode(), &mut bytes);
+        writer.last_step = writer.last_step.max(step);
+        if let Err(error) = writer.file.write_all(&bytes) {
+            error!("Input recording stopped: {}", error);
+            self.writer = None;
+        }
+    }
+}
+
+/// Start recording a world's inputs; see [`World::start_input_recording`].
+/// Send it to a [`WorldHandle`]'s address right after creating the world, as
+/// the recording has to begin before its first step.
+///
+/// [`WorldHandle`]: crate::WorldHandle
+#[derive(ActixMessage)]
+#[rtype(result = "Result<(), LockstepError>")]
+pub struct StartInputRecording {
+    pub path: PathBuf,
+}
+
+/// Finish a world's running input recording; see
+/// [`World::stop_input_recording`].
+#[derive(ActixMessage)]
+#[rtype(result = "io::Result<()>")]
+pub struct StopInputRecording;
+
+impl World {
+    /// Start recording every input this fixed-step world applies, and its
+    /// state hash after every step, to `path`. Lockstep replays start from
+    /// the seed, so this has to be called before the world runs its first
+    /// step — typically right after building it.
+    pub fn start_input_recording<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LockstepError> {
+        let Some(fixed_step) = self.config().fixed_timestep else {
+            return Err(LockstepError::NotFixedStep(self.name.clone()));
+        };
+        let step = self.read_resource::<FixedStepState>().clock.step_count();
+        if step != 0 {
+            return Err(LockstepError::AlreadyStarted(step));
+        }
+
+        let path = path.as_ref();
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let header = InputLogHeader {
+            world: self.name.clone(),
+            fixed_step,
+        };
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(INPUT_LOG_MAGIC);
+        bytes.extend_from_slice(&INPUT_LOG_VERSION.to_le_bytes());
+        put_bytes(
+            &serde_json::to_vec(&header).map_err(io::Error::from)?,
+            &mut bytes,
+        );
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&bytes)?;
+
+        info!(
+            "World {:?} is recording its inputs to {:?} (seed {})",
+            self.name, path, fixed_step.seed
+        );
+        self.write_resource::<InputRecorder>().writer = Some(InputLogWriter { file, last_step: 0 });
+
+        Ok(())
+    }
+
+    /// Finish the running input recording, if any, flushing it to disk.
+    pub fn stop_input_recording(&mut self) -> io::Result<()> {
+        let Some(mut writer) = self.write_resource::<InputRecorder>().writer.take() else {
+            return Ok(());
+        };
+        writer.file.flush()?;
+        info!(
+            "World {:?} finished recording its inputs ({} steps)",
+            self.name, writer.last_step
+        );
+        Ok(())
+    }
+
+    pub fn is_recording_inputs(&self) -> bool {
+        self.read_resource::<InputRecorder>().is_recording()
+    }
+
+    /// Hash of the deterministic sim state: the step count, the seeded
+    /// roller, and every identified entity (in id order) with its type,
+    /// position, body velocity and metadata. FNV-1a/64 over a fixed
+    /// little-endian layout, so equal state hashes equally on every machine.
+    pub fn sim_state_hash(&self) -> u64 {
+        use specs::LendJoin;
+
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        if let Some(state) = self.ecs.try_fetch::<FixedStepState>() {
+            fnv_mix(&mut hash, &state.clock.step_count().to_le_bytes());
+            fnv_mix(&mut hash, &state.rng.state().to_le_bytes());
+        }
+
+        let ids = self.read_component::<IDComp>();
+        let etypes = self.read_component::<ETypeComp>();
+        let positions = self.read_component::<PositionComp>();
+        let bodies = self.read_component::<RigidBodyComp>();
+        let metadatas = self.read_component::<MetadataComp>();
+
+        let mut entities: Vec<_> = (
+            &ids,
+            etypes.maybe(),
+            positions.maybe(),
+            bodies.maybe(),
+            metadatas.maybe(),
+        )
+            .join()
+            .collect();
+        entities.sort_by(|a, b| a.0 .0.cmp(&b.0 .0));
+
+        for (id, etype, position, body, metadata) in entities {
+            fnv_mix_str(&mut hash, &id.0);
+            fnv_mix_str(&mut hash, etype.map_or("", |etype| etype.0.as_str()));
+            if let Some(position) = position {
+                for axis in [position.0 .0, position.0 .1, position.0 .2] {
+                    fnv_mix(&mut hash, &axis.to_bits().to_le_bytes());
+                }
+            }
+            if let Some(body) = body {
+                let velocity = &body.0.velocity;
+                for axis in [velocity.0, velocity.1, velocity.2] {
+                    fnv_mix(&mut hash, &axis.to_bits().to_le_bytes());
+                }
+            }
+            if let Some(metadata) = metadata {
+                let mut keys: Vec<&String> = metadata.map.keys().collect();
+                keys.sort();
+                for key in keys {
+                    fnv_mix_str(&mut hash, key);
+                    fnv_mix_str(&mut hash, &metadata.map[key].to_string());
+                }
+            }
+        }
+
+        hash
+    }
+
+    /// Reapply a recorded input log to this world in lockstep, checking the
+    /// state hash after every step. The world must be freshly built with the
+    /// same setup (registry, stages, handles) and config as the recorded one,
+    /// and not yet prepared or stepped; it runs headlessly, with joined
+    /// clients' traffic discarded.
+    pub fn replay_inputs(&mut self, log: &InputLog) -> Result<LockstepReport, LockstepError> {
+        let Some(fixed_step) = self.config().fixed_timestep else {
+            return Err(LockstepError::NotFixedStep(self.name.clone()));
+        };
+        if fixed_step != log.header.fixed_step {
+            return Err(LockstepError::ConfigMismatch {
+                recorded: log.header.fixed_step,
+                world: fixed_step,
+            });
+        }
+        let step = self.read_resource::<FixedStepState>().clock.step_count();
+        if step != 0 {
+            return Err(LockstepError::AlreadyStarted(step));
+        }
+
+        // Clients get a sender nobody reads; sends to it fail and are ignored.
+        let (control, _) = mpsc::unbounded_channel();
+        let (bulk, _) = mpsc::unbounded_channel();
+        let sender = WsSender::new(control, bulk);
+
+        self.prepare();
+        self.started = true;
+
+        let mut report = LockstepReport {
+            steps: 0,
+            inputs: 0,
+        };
+        for entry in &log.entries {
+            match &entry.input {
+                RecordedInput::StepHash(expected) => {
+                    self.run_fixed_step();
+                    let actual = self.sim_state_hash();
+                    if actual != *expected {
+                        return Err(LockstepError::Desync {
+                            step: entry.step,
+                            expected: *expected,
+                            actual,
+                        });
+                    }
+                    report.steps += 1;
+                    continue;
+                }
+                RecordedInput::Join {
+                    client_id,
+                    username,
+                    preferences,
+                    compact_motion,
+                } => {
+                    let motion_protocol = if *compact_motion {
+                        MotionProtocol::CompactV1
+                    } else {
+                        MotionProtocol::LegacyJson
+                    };
+                    self.add_client(
+                        client_id,
+                        username,
+                        &sender,
+                        *preferences,
+                        motion_protocol,
+                        // Mesh encoding never feeds back into the simulation.
+                        MeshFormat::Legacy,
+                        None,
+                    );
+                }
+                RecordedInput::Resume {
+                    client_id,
+                    preferences,
+                } => {
+                    let ent = self.clients().get(client_id).map(|client| client.entity);
+                    if let Some(ent) = ent {
+                        apply_client_preferences_patch(self, ent, preferences);
+                    }
+                }
+                RecordedInput::Leave { client_id } => self.remove_client(client_id),
+                RecordedInput::Request { client_id, message } => {
+                    let message = decode_message(message)
+                        .map_err(|error| LockstepError::Corrupt(error.to_string()))?;
+                    self.on_request(client_id, message);
+                }
+                RecordedInput::State { client_id, message } => {
+                    let message = decode_message(message)
+                        .map_err(|error| LockstepError::Corrupt(error.to_string()))?;
+                    self.on_peer(client_id, message);
+                }
+            }
+            report.inputs += 1;
+        }
+
+        Ok(report)
+    }
+
+    /// Log an input about to be applied, if this world is recording them.
+    pub(crate) fn record_input(&self, input: impl FnOnce() -> RecordedInput) {
+        let Some(mut recorder) = self.ecs.try_fetch_mut::<InputRecorder>() else {
+            return;
+        };
+        if !recorder.is_recording() {
+            return;
+        }
+        let step = self.read_resource::<FixedStepState>().clock.step_count();
+        recorder.record(step, &input());
+    }
+}
+
+fn fnv_mix(hash: &mut u64, bytes: &[u8]) {
+    for &byte in bytes {
+        *hash ^= byte as u64;
+        *hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+}
+
+fn fnv_mix_str(hash: &mut u64, value: &str) {
+    fnv_mix(hash, &(value.len() as u64).to_le_bytes());
+    fnv_mix(hash, value.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u64 = 0x5eed;
+
+    /// The same setup on both sides of a lockstep replay: a fixed-step world
+    /// with a method that mixes the seeded roller into the sender's metadata.
+    fn build_world(seed: u64) -> World {
+        let config = WorldConfig::new()
+            .saving(false)
+            .min_chunk([0, 0])
+            .max_chunk([0, 0])
+            .fixed_timestep(Some(FixedStepConfig {
+                hz: 20,
+                max_catchup_steps: 5,
+                seed,
+            }))
+            .build();
+        let mut world = World::new("lockstep", &config);
+        world.ecs_mut().insert(Registry::new());
+        world.set_method_handle("roll", |world, client_id, _| {
+            let roll = world.write_resource::<FixedStepState>().rng.next_below(100);
+            let Some(ent) = world.clients().get(client_id).map(|client| client.entity) else {
+                return;
+            };
+            if let Some(metadata) = world.write_component::<MetadataComp>().get_mut(ent) {
+                metadata.map.insert("roll".to_owned(), json!(roll));
+            }
+        });
+        world
+    }
+
+    fn sender() -> WsSender {
+        let (control, _) = mpsc::unbounded_channel();
+        let (bulk, _) = mpsc::unbounded_channel();
+        WsSender::new(control, bulk)
+    }
+
+    fn peer(x: f32) -> Message {
+        Message::new(&MessageType::Peer)
+            .peers(&[PeerProtocol {
+                id: "alice".to_owned(),
+                username: "alice".to_owned(),
+                metadata: format!(r#"{{"position":[{},40,0],"direction":[0,0,1]}}"#, x),
+            }])
+            .build()
+    }
+
+    fn roll() -> Message {
+        Message::new(&MessageType::Method)
+            .method(MethodProtocol {
+                name: "roll".to_owned(),
+                payload: "{}".to_owned(),
+            })
+            .build()
+    }
+
+    /// Record a short session: a join, movement and rolls between steps.
+    fn record_session(path: &Path) -> Vec<u64> {
+        let mut world = build_world(SEED);
+        world.start_input_recording(path).unwrap();
+        world.prepare();
+
+        let mut hashes = Vec::new();
+        world.add_client(
+            "alice",
+            "alice",
+            &sender(),
+            ClientPreferencesPatch::default(),
+            MotionProtocol::LegacyJson,
+            MeshFormat::Legacy,
+            None,
+        );
+        for step in 0..24 {
+            if step % 3 == 0 {
+                world.on_request("alice", peer(step as f32 * 0.5));
+            }
+            if step % 4 == 1 {
+                world.on_request("alice", roll());
+            }
+            world.run_fixed_step();
+            hashes.push(world.sim_state_hash());
+        }
+        world.remove_client("alice");
+        world.run_fixed_step();
+        world.stop_input_recording().unwrap();
+
+        hashes
+    }
+
+    fn temp_log() -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("voxelize-inputs-test-{}", nanoid!()))
+            .join("session.vxin")
+    }
+
+    #[test]
+    fn a_recorded_session_replays_in_lockstep() {
+        let path = temp_log();
+        let hashes = record_session(&path);
+        assert_ne!(hashes[0], hashes[23], "the session changes sim state");
+
+        let log = InputLog::open(&path).unwrap();
+        assert_eq!(log.header.fixed_step.seed, SEED);
+        assert_eq!(log.steps(), 25);
+        let recorded: Vec<u64> = log
+            .entries
+            .iter()
+            .filter_map(|entry| match entry.input {
+                RecordedInput::StepHash(hash) => Some(hash),
+                _ => None,
+            })
+            .take(24)
+            .collect();
+        assert_eq!(recorded, hashes);
+
+        let mut replayed = build_world(SEED);
+        let report = replayed.replay_inputs(&log).unwrap();
+        assert_eq!(report.steps, 25);
+        // One join, one leave, eight moves and six rolls.
+        assert_eq!(report.inputs, 16);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn lockstep_replay_reports_the_first_diverging_step() {
+        let path = temp_log();
+        record_session(&path);
+        let mut log = InputLog::open(&path).unwrap();
+
+        let mut other_seed = build_world(SEED + 1);
+        assert!(matches!(
+            other_seed.replay_inputs(&log),
+            Err(LockstepError::ConfigMismatch { .. })
+        ));
+
+        // Drop the second roll: everything up to it still matches.
+        let second_roll = log
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| match &entry.input {
+                RecordedInput::Request { message, .. } => {
+                    decode_message(message).unwrap().r#type == MessageType::Method as i32
+                }
+                _ => false,
+            })
+            .nth(1)
+            .map(|(index, entry)| (index, entry.step))
+            .unwrap();
+        log.entries.remove(second_roll.0);
+
+        let mut replayed = build_world(SEED);
+        match replayed.replay_inputs(&log) {
+            Err(LockstepError::Desync { step, .. }) => assert_eq!(step, second_roll.1 + 1),
+            other => panic!("expected a desync, got {:?}", other),
+        }
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn resumed_preferences_round_trip_through_the_log() {
+        let input = RecordedInput::Resume {
+            client_id: "alice".to_owned(),
+            preferences: ClientPreferencesPatch {
+                client_only_meshing: Some(true),
+            },
+        };
+
+        match RecordedInput::decode(&input.encode()) {
+            Some(RecordedInput::Resume {
+                client_id,
+                preferences,
+            }) => {
+                assert_eq!(client_id, "alice");
+                assert_eq!(preferences.client_only_meshing, Some(true));
+            }
+            other => panic!("expected a resume, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn recording_needs_a_fresh_fixed_step_world() {
+        let path = temp_log();
+
+        let mut wall_clock = World::new("wall-clock", &WorldConfig::new().build());
+        assert!(matches!(
+            wall_clock.start_input_recording(&path),
+            Err(LockstepError::NotFixedStep(_))
+        ));
+
+        let mut stepped = build_world(SEED);
+        stepped.run_fixed_step();
+        assert!(matches!(
+            stepped.start_input_recording(&path),
+            Err(LockstepError::AlreadyStarted(1))
+        ));
+
+        record_session(&path);
+        let mut bytes = fs::read(&path).unwrap();
+        bytes.truncate(bytes.len() - 3);
+        let cut = InputLog::decode(&bytes).unwrap();
+        assert_eq!(cut.steps(), 24, "the cut-off final step hash is dropped");
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -44,6 +44,8 @@ use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
 
+use crate::{CompoundShape, Vec3};
+
 /// Default smoothing factor for the server's per-client RTT EWMA. Passed as a
 /// constructor parameter to [`LagComp::new`] so it can be overridden per world;
 /// this documented default is used by the world wiring. A moderate value tracks
@@ -114,6 +116,9 @@ pub struct Pose {
     pub position: [f32; 3],
     /// Look/orientation vector at record time.
     pub direction: [f32; 3],
+    /// Body yaw at record time, turning the entity's [`CompoundShape`] for
+    /// rewound queries.
+    pub yaw: f32,
 }
 
 impl Pose {
@@ -122,6 +127,7 @@ impl Pose {
         Self {
             position,
             direction: [0.0, 0.0, 0.0],
+            yaw: 0.0,
         }
     }
 }
@@ -140,6 +146,7 @@ impl Frame {
         pose: Pose {
             position: [0.0; 3],
             direction: [0.0; 3],
+            yaw: 0.0,
         },
         occupied: false,
     };
@@ -234,6 +241,9 @@ pub struct Ray {
 pub struct PositionHistory {
     capacity: usize,
     rings: BTreeMap<u64, EntityRing>,
+    /// Compound shapes of the entities that have one. Shapes are not
+    /// historical: a rewound query uses the latest shape at the rewound pose.
+    shapes: BTreeMap<u64, CompoundShape>,
 }
 
 impl PositionHistory {
@@ -242,6 +252,7 @@ impl PositionHistory {
         Self {
             capacity: capacity_ticks.max(1),
             rings: BTreeMap::new(),
+            shapes: BTreeMap::new(),
         }
     }
 
@@ -265,6 +276,20 @@ impl PositionHistory {
     /// removed). Keeps the ring set from growing without bound.
     pub fn forget(&mut self, entity: u64) {
         self.rings.remove(&entity);
+        self.shapes.remove(&entity);
+    }
+
+    /// Set the compound shape rewound queries resolve `entity` against, or
+    /// `None` to fall back to the box or point the query itself describes.
+    pub fn set_shape(&mut self, entity: u64, shape: Option<CompoundShape>) {
+        match shape {
+            Some(shape) => {
+                self.shapes.insert(entity, shape);
+            }
+            None => {
+                self.shapes.remove(&entity);
+            }
+        }
     }
 
     /// The rewound pose of `entity` at `tick`, or `None` if that tick is no
@@ -289,8 +314,10 @@ impl PositionHistory {
 
     /// Resolve a ray against every recorded entity's pose at `tick`, treating
     /// each entity as an axis-aligned box of `half_extents` centered on its
-    /// rewound position. Returns the nearest hit within `max_distance` (in units
-    /// of `ray.direction`'s length), skipping `exclude` (typically the shooter).
+    /// rewound position, or as its compound shape turned by the rewound yaw
+    /// when it has one (see [`PositionHistory::set_shape`]). Returns the
+    /// nearest hit within `max_distance` (in units of `ray.direction`'s
+    /// length), skipping `exclude` (typically the shooter).
     ///
     /// This is the engine's whole "hit" contribution: a spatial query against
     /// historical positions. It does not know what a shot is or what a hit
@@ -302,16 +329,45 @@ impl PositionHistory {
         tick: u64,
         max_distance: f32,
         exclude: Option<u64>,
+    ) -> Option<RewindHit> {
+        self.resolve_ray_at_tick_excluding(
+            ray,
+            half_extents,
+            tick,
+            max_distance,
+            exclude.as_slice(),
+        )
+    }
+
+    /// [`PositionHistory::resolve_ray_at_tick`], skipping every entity in
+    /// `exclude`: a ray that passes through its first hit asks again without
+    /// it to find the next.
+    pub fn resolve_ray_at_tick_excluding(
+        &self,
+        ray: &Ray,
+        half_extents: [f32; 3],
+        tick: u64,
+        max_distance: f32,
+        exclude: &[u64],
     ) -> Option<RewindHit> {
         let mut best: Option<RewindHit> = None;
         for (&entity, ring) in self.rings.iter() {
-            if exclude == Some(entity) {
+            if exclude.contains(&entity) {
                 continue;
             }
             let Some(pose) = ring.get(tick) else {
                 continue;
             };
-            let Some(distance) = ray_box_entry(ray, pose.position, half_extents) else {
+            let entry = match self.shapes.get(&entity) {
+                Some(shape) => shape.ray_entry(
+                    &Vec3::from_arr(pose.position),
+                    pose.yaw,
+                    &Vec3::from_arr(ray.origin),
+                    &Vec3::from_arr(ray.direction),
+                ),
+                None => ray_box_entry(ray, pose.position, half_extents),
+            };
+            let Some(distance) = entry else {
                 continue;
             };
             if distance < 0.0 || distance > max_distance {
@@ -330,10 +386,12 @@ impl PositionHistory {
 
     /// Resolve a spherical volume (center + radius) against every recorded
     /// entity's pose at `tick`. Returns the nearest entity whose rewound
-    /// position lies within `radius`, skipping `exclude`. Used for melee, where
-    /// both actors are evaluated at the same anchored tick: the caller passes
-    /// the attacker's swing-start position as `center` and this answers against
-    /// the target's rewound position.
+    /// position lies within `radius`, skipping `exclude`. An entity with a
+    /// compound shape is hit when any part of it lies within `radius`, and
+    /// its distance is measured to the nearest part's surface. Used for
+    /// melee, where both actors are evaluated at the same anchored tick: the
+    /// caller passes the attacker's swing-start position as `center` and this
+    /// answers against the target's rewound position.
     pub fn resolve_volume_at_tick(
         &self,
         center: [f32; 3],
@@ -350,11 +408,23 @@ impl PositionHistory {
             let Some(pose) = ring.get(tick) else {
                 continue;
             };
-            let d_sq = distance_sq(center, pose.position);
-            if d_sq > radius_sq {
+            let distance = match self.shapes.get(&entity) {
+                Some(shape) => shape.distance_to(
+                    &Vec3::from_arr(pose.position),
+                    pose.yaw,
+                    &Vec3::from_arr(center),
+                ),
+                None => {
+                    let d_sq = distance_sq(center, pose.position);
+                    if d_sq > radius_sq {
+                        continue;
+                    }
+                    d_sq.sqrt()
+                }
+            };
+            if distance > radius {
                 continue;
             }
-            let distance = d_sq.sqrt();
             if best.map_or(true, |b| distance < b.distance) {
                 best = Some(RewindHit {
                     entity,
@@ -1163,6 +1233,48 @@ mod resolve_tests {
             history.resolve_volume_at_tick(attacker_swing_pose.position, 0.5, born, Some(1));
         assert!(short.is_none());
     }
+
+    // A long body is resolved against its shape turned by the rewound yaw,
+    // not against the query's box around its center.
+    #[test]
+    fn compound_shapes_resolve_at_the_rewound_yaw() {
+        let mut history = PositionHistory::new(8);
+        history.set_shape(
+            2,
+            Some(CompoundShape::new().with_box([0.0, 0.0, 0.0], [0.5, 0.5, 2.0])),
+        );
+        let turned = Pose {
+            yaw: std::f32::consts::FRAC_PI_2,
+            ..Pose::at([0.0, 0.0, 0.0])
+        };
+        history.record(2, 0, turned);
+        history.record(2, 1, Pose::at([0.0, 0.0, 0.0]));
+
+        // Down onto the stern, which lay along +x at tick 0 only.
+        let ray = Ray {
+            origin: [1.8, 5.0, 0.0],
+            direction: [0.0, -1.0, 0.0],
+        };
+        let hit = history.resolve_ray_at_tick(&ray, [0.5, 0.5, 0.5], 0, 100.0, None);
+        assert!((hit.expect("the turned hull is hit").distance - 4.5).abs() < 1e-4);
+        assert!(history
+            .resolve_ray_at_tick(&ray, [0.5, 0.5, 0.5], 1, 100.0, None)
+            .is_none());
+
+        // Volumes measure to the hull's surface, not its center.
+        let near = history.resolve_volume_at_tick([2.8, 0.0, 0.0], 1.0, 0, None);
+        assert!((near.expect("within reach of the stern").distance - 0.8).abs() < 1e-4);
+        assert!(history
+            .resolve_volume_at_tick([2.8, 0.0, 0.0], 1.0, 1, None)
+            .is_none());
+
+        // Forgetting the entity drops its shape with its history.
+        history.forget(2);
+        history.record(2, 2, turned);
+        assert!(history
+            .resolve_ray_at_tick(&ray, [0.5, 0.5, 0.5], 2, 100.0, None)
+            .is_none());
+    }
 }
 
 #[cfg(test)]
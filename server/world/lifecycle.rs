@@ -18,6 +18,10 @@ impl World {
         }
         for (client_id, messages) in self.inbound_state.drain() {
             for message in messages {
+                self.record_input(|| RecordedInput::State {
+                    client_id: client_id.clone(),
+                    message: encode_message(&message),
+                });
                 self.on_peer(&client_id, message);
             }
         }
@@ -79,10 +83,11 @@ impl World {
         // Snapshot the marked entities' poses first (immutable storage borrows),
         // then commit them into the resource (mutable borrow) — the two borrows
         // never overlap.
-        let poses: Vec<(u64, Pose)> = {
+        let poses: Vec<(u64, Pose, Option<CompoundShape>)> = {
             let entities = self.ecs.entities();
             let positions = self.ecs.read_storage::<PositionComp>();
             let directions = self.ecs.read_storage::<DirectionComp>();
+            let bodies = self.ecs.read_storage::<RigidBodyComp>();
             let eligible = self.ecs.read_storage::<RewindEligibleComp>();
 
             (&entities, &positions, &eligible)
@@ -91,20 +96,24 @@ impl World {
                     let direction = directions
                         .get(entity)
                         .map_or([0.0, 0.0, 0.0], |dir| [dir.0 .0, dir.0 .1, dir.0 .2]);
+                    let body = bodies.get(entity).map(|body| &body.0);
                     (
                         entity.id() as u64,
                         Pose {
                             position: [position.0 .0, position.0 .1, position.0 .2],
                             direction,
+                            yaw: body.map_or(0.0, |body| body.yaw),
                         },
+                        body.and_then(|body| body.shape.clone()),
                     )
                 })
                 .collect()
         };
 
         let mut lag = self.write_resource::<LagComp>();
-        for (entity, pose) in poses {
+        for (entity, pose, shape) in poses {
             lag.history_mut().record(entity, tick, pose);
+            lag.history_mut().set_shape(entity, shape);
         }
     }
 
@@ -122,6 +131,18 @@ impl World {
         for id in ids {
             self.remove_client(&id);
         }
+        if let Err(error) = self.stop_recording() {
+            error!(
+                "World {:?} could not finish its replay recording: {}",
+                self.name, error
+            );
+        }
+        if let Err(error) = self.stop_input_recording() {
+            error!(
+                "World {:?} could not finish its input recording: {}",
+                self.name, error
+            );
+        }
         self.inbound_state.reset();
         self.ecs.maintain();
     }
@@ -154,12 +175,26 @@ impl World {
         *self.write_resource::<Bookkeeping>() = Bookkeeping::new();
         *self.write_resource::<ChunkInterests>() = ChunkInterests::new();
         *self.write_resource::<ReplicatedStateBuffer>() = ReplicatedStateBuffer::new();
+        *self.write_resource::<SessionLogs>() = SessionLogs::default();
         *self.write_resource::<MessageQueues>() = MessageQueues::new();
         *self.write_resource::<EncodedMessageQueue>() = EncodedMessageQueue::new();
         *self.write_resource::<Events>() = Events::new();
         *self.write_resource::<KdTree>() = KdTree::new();
         *self.write_resource::<Physics>() = Physics::new();
         *self.write_resource::<Mesher>() = Mesher::new();
+        if let Err(error) = self.stop_recording() {
+            error!(
+                "World {:?} could not finish its replay recording: {}",
+                self.name, error
+            );
+        }
+        *self.write_resource::<ReplayPlayer>() = ReplayPlayer::default();
+        if let Err(error) = self.stop_input_recording() {
+            error!(
+                "World {:?} could not finish its input recording: {}",
+                self.name, error
+            );
+        }
 
         self.inbound_state.reset();
         self.ecs.maintain();
@@ -274,6 +309,8 @@ impl World {
             maintain_timer.elapsed_ms()
         };
 
+        self.handle_projectile_hits();
+
         (dispatch_time, maintain_time)
     }
 
@@ -301,15 +338,7 @@ impl World {
         let mut dispatch_time = 0.0;
         let mut maintain_time = 0.0;
         for _ in 0..plan.steps {
-            // Commit the step (advancing the sole time source) *before* the
-            // dispatch, so systems read the correct per-step sim time.
-            let tick = self.write_resource::<FixedStepState>().clock.commit_step();
-            // Record rewind-eligible poses at the *start* of the step, before
-            // the dispatch mutates positions — the newest ring frame is thus the
-            // pose a client would most recently have rendered. Records at the
-            // full sim rate, independent of the coarser snapshot cadence.
-            self.record_rewind_poses(tick);
-            let (dispatch, maintain) = self.run_dispatch();
+            let (dispatch, maintain) = self.run_fixed_step();
             dispatch_time += dispatch;
             maintain_time += maintain;
         }
@@ -317,6 +346,22 @@ impl World {
         (dispatch_time, maintain_time)
     }
 
+    /// Run exactly one fixed step: commit it, historize rewind poses, and
+    /// dispatch. Recording worlds log the resulting state hash.
+    pub(super) fn run_fixed_step(&mut self) -> (f64, f64) {
+        // Commit the step (advancing the sole time source) *before* the
+        // dispatch, so systems read the correct per-step sim time.
+        let tick = self.write_resource::<FixedStepState>().clock.commit_step();
+        // Record rewind-eligible poses at the *start* of the step, before
+        // the dispatch mutates positions — the newest ring frame is thus the
+        // pose a client would most recently have rendered. Records at the
+        // full sim rate, independent of the coarser snapshot cadence.
+        self.record_rewind_poses(tick);
+        let times = self.run_dispatch();
+        self.record_input(|| RecordedInput::StepHash(self.sim_state_hash()));
+        times
+    }
+
     pub(crate) fn tick(&mut self) {
         if !self.started {
             self.started = true;
@@ -329,6 +374,10 @@ impl World {
         // in an actor mailbox.
         self.apply_inbound_state();
 
+        // Replay worlds queue the recorded traffic due this tick, so it ships
+        // with this dispatch.
+        self.advance_replay();
+
         if self.preloading {
             let light_padding = (self.config().max_light_level as f32
                 / self.config().chunk_size as f32)
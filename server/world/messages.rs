@@ -3,16 +3,16 @@ use std::sync::Arc;
 use crossbeam_channel::{Receiver, Sender};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-use crate::{
-    common::ClientFilter, encode_message, perf, server::Message, EntityOperation, MessageType,
-};
+use crate::{common::ClientFilter, encode_message, perf, server::Message, MessageType};
 
 #[derive(Clone)]
 pub struct EncodedMessage {
     pub data: Vec<u8>,
     pub msg_type: i32,
-    pub is_rtc_eligible: bool,
     pub perf: Option<perf::OutboundPerf>,
+    /// Session sequence already encoded into `data` (see `SessionLogs`), or
+    /// `0` when the bytes still need per-client stamping.
+    pub seq: u64,
 }
 
 /// The RELIABLE ORDERED EVENTS channel of the state replication split (see
@@ -114,13 +114,12 @@ impl EncodedMessageQueue {
                 .into_par_iter()
                 .map(|(message, filter)| {
                     let msg_type = message.r#type;
-                    let is_rtc_eligible = Self::compute_rtc_eligibility(&message);
                     let outbound_perf = perf::outbound(&message);
                     let encoded = EncodedMessage {
                         data: encode_message(&message),
                         msg_type,
-                        is_rtc_eligible,
                         perf: outbound_perf,
+                        seq: message.seq,
                     };
                     (encoded, filter)
                 })
@@ -136,17 +135,4 @@ impl EncodedMessageQueue {
         }
         result
     }
-
-    fn compute_rtc_eligibility(message: &Message) -> bool {
-        match MessageType::try_from(message.r#type) {
-            Ok(MessageType::Entity) => {
-                !message.entities.is_empty()
-                    && message.entities.iter().all(|e| {
-                        EntityOperation::try_from(e.operation) == Ok(EntityOperation::Update)
-                    })
-            }
-            Ok(MessageType::Peer) => true,
-            _ => false,
-        }
-    }
 }
@@ -1,3 +1,4 @@
+mod atlas;
 mod bookkeeping;
 mod clients;
 mod components;
@@ -13,10 +14,13 @@ pub mod items;
 mod lag_comp;
 mod messages;
 mod metadata;
+mod movement;
 mod physics;
 mod profiler;
+mod projectiles;
 mod registry;
 mod replication;
+mod session_log;
 pub(crate) mod shared_pools;
 mod stats;
 pub mod system_profiler;
@@ -46,6 +50,7 @@ use std::sync::Arc;
 use std::sync::{Mutex, RwLock};
 use std::{
     fs::{self, File},
+    ops::RangeInclusive,
     time::{Duration, Instant},
 };
 use system_profiler::{record_timing, SystemTimer, TimedDispatcherBuilder, WorldTimingContext};
@@ -60,6 +65,7 @@ use crate::{
 
 use super::common::ClientFilter;
 
+pub use atlas::*;
 pub use bookkeeping::*;
 pub use clients::*;
 pub use components::*;
@@ -74,9 +80,12 @@ pub use interests::*;
 pub use items::*;
 pub use lag_comp::*;
 pub use messages::*;
+pub use movement::*;
 pub use physics::*;
+pub use projectiles::*;
 pub use registry::*;
 pub use replication::*;
+pub use session_log::*;
 pub use stats::*;
 pub use system_profiler::*;
 pub use systems::*;
@@ -89,19 +98,33 @@ pub type Transports = HashMap<String, WsSender>;
 mod accessors;
 mod client_body;
 mod dispatcher;
+mod dynamic_lights;
+mod explosion;
+mod export;
 mod handles;
+mod handoff;
 mod inbound;
+mod input_log;
 #[cfg(test)]
 mod lag_comp_wiring_tests;
 #[cfg(test)]
 mod mesher_readiness_tests;
 mod lifecycle;
+mod replay;
+mod riding;
 mod sessions;
 mod spawning;
 mod sync;
 
 pub use client_body::*;
 use dispatcher::dispatcher;
+pub use dynamic_lights::*;
+pub use explosion::*;
+pub use export::*;
+pub use handoff::*;
+pub use input_log::*;
+pub use replay::*;
+pub use riding::*;
 pub use sync::*;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -114,6 +137,7 @@ pub struct PeerUpdate {
     is_ghost: Option<bool>,
     is_swimming: Option<bool>,
     is_swim_pose_active: Option<bool>,
+    inputs: Option<Vec<MovementInput>>,
 }
 
 /// Wrapper to make a non-Send/Sync type safely usable in contexts that require it.
@@ -182,10 +206,16 @@ pub struct World {
     /// The handler for commands.
     command_handle: Option<Arc<dyn Fn(&mut World, &str, &str) + Send + Sync>>,
 
+    /// The handler for projectile hits.
+    projectile_hit_handle: Option<ProjectileHitHandle>,
+
     /// A map to spawn and create entities.
     entity_loaders:
         HashMap<String, Arc<dyn Fn(&mut World, MetadataComp) -> EntityBuilder + Send + Sync>>,
 
+    /// The light levels each entity type may be spawned in, by type.
+    entity_spawn_lights: HashMap<String, RangeInclusive<u32>>,
+
     extra_init_data: HashMap<String, serde_json::Value>,
 
     items: Option<ItemRegistry>,
@@ -272,6 +302,21 @@ pub(crate) struct ClientJoinRequest {
     pub sender: WsSender,
     pub preferences: ClientPreferencesPatch,
     pub motion_protocol: MotionProtocol,
+    pub mesh_format: MeshFormat,
+    /// Issued when the server allows session resume; see [`SessionLogs`].
+    pub resume_token: Option<String>,
+}
+
+/// A suspended session reconnecting within its grace window. Carries the
+/// JOIN that would otherwise be sent, so the world can fall back to it.
+#[derive(ActixMessage)]
+#[rtype(result = "()")]
+pub(crate) struct ClientResumeRequest {
+    pub join: ClientJoinRequest,
+    /// Highest contiguous sequence the client applied before dropping.
+    pub last_seq: u64,
+    /// Chunks the client still holds and does not need re-sent.
+    pub chunks: Vec<Vec2<i32>>,
 }
 
 #[derive(ActixMessage)]
@@ -348,6 +393,7 @@ impl World {
         let mut ecs = ECSWorld::new();
 
         ecs.register::<AddrComp>();
+        ecs.register::<AttachmentComp>();
         ecs.register::<BrainComp>();
         ecs.register::<ChunkRequestsComp>();
         ecs.register::<ClientFlag>();
@@ -357,15 +403,21 @@ impl World {
         ecs.register::<DirectionComp>();
         ecs.register::<EntityFlag>();
         ecs.register::<ETypeComp>();
+        ecs.register::<FallingBlockComp>();
         ecs.register::<IDComp>();
         ecs.register::<InteractorComp>();
         ecs.register::<JsonComp>();
+        ecs.register::<LightEmitterComp>();
         ecs.register::<MetadataComp>();
+        ecs.register::<MovementInputsComp>();
         ecs.register::<NameComp>();
         ecs.register::<PathComp>();
+        ecs.register::<PlatformFlag>();
         ecs.register::<PositionComp>();
+        ecs.register::<ProjectileComp>();
         ecs.register::<RewindEligibleComp>();
         ecs.register::<RigidBodyComp>();
+        ecs.register::<StandingOnComp>();
         ecs.register::<TargetComp>();
         ecs.register::<VoxelComp>();
         ecs.register::<DoNotPersistComp>();
@@ -394,15 +446,21 @@ impl World {
         ecs.insert(MessageQueues::new());
         ecs.insert(Physics::new());
         ecs.insert(Events::new());
+        ecs.insert(ProjectileHits::default());
+        ecs.insert(DynamicLights::new());
         ecs.insert(Transports::new());
         ecs.insert(ChunkInterests::new());
         ecs.insert(Bookkeeping::new());
         ecs.insert(KdTree::new());
         ecs.insert(EncodedMessageQueue::new());
         ecs.insert(ReplicatedStateBuffer::new());
+        ecs.insert(SessionLogs::default());
         ecs.insert(Profiler::new(Duration::from_secs_f64(0.001)));
         ecs.insert(EntityIDs::new());
         ecs.insert(WorldPerfMetrics::new());
+        ecs.insert(ReplayRecorder::default());
+        ecs.insert(ReplayPlayer::default());
+        ecs.insert(InputRecorder::default());
 
         // Deterministic worlds carry their fixed-step clock + seeded PRNG as a
         // resource so every sim system reads sim time and randomness from one
@@ -438,11 +496,13 @@ impl World {
             method_handles: HashMap::default(),
             event_handles: HashMap::default(),
             entity_loaders: HashMap::default(),
+            entity_spawn_lights: HashMap::default(),
             client_parser: Arc::new(default_client_parser),
             client_modifier: None,
             client_leave_modifier: None,
             transport_handle: None,
             command_handle: None,
+            projectile_hit_handle: None,
             extra_init_data: HashMap::default(),
             items: None,
             addr: None,
@@ -608,6 +668,25 @@ impl World {
             }
         });
 
+        world.set_replay_method_handles();
+
+        // Blocks saved mid-fall revive with their body and finish the fall.
+        world.set_entity_loader(FALLING_BLOCK_ETYPE, |world, metadata| {
+            let position = metadata.get::<PositionComp>("position").unwrap_or_default();
+            let mut body = FallingBlockComp::body();
+            body.set_position(position.0 .0, position.0 .1, position.0 .2);
+
+            world
+                .create_entity(&nanoid!(), FALLING_BLOCK_ETYPE)
+                .with(position)
+                .with(RigidBodyComp::new(&body))
+                .with(
+                    metadata
+                        .get::<FallingBlockComp>("fallingBlock")
+                        .unwrap_or_default(),
+                )
+        });
+
         world
     }
 
@@ -0,0 +1,203 @@
+//! Server-authoritative client movement.
+//!
+//! By default a client owns its body: the positions it reports are applied
+//! as-is, clamped only to a plausible per-packet distance. A world that opts
+//! into [`AuthoritativeMovementConfig`] instead has clients send the
+//! [`MovementInput`] of every fixed step they simulate. The server replays
+//! each input through the same [`BrainComp`] and [`Physics::iterate_body`]
+//! logic its own walking entities use, compares the result with the position
+//! the client predicted for that input, and sends a [`MovementCorrection`]
+//! when the two diverge by more than the threshold. The client snaps to the
+//! corrected state and replays its inputs newer than the corrected sequence.
+//!
+//! One input is simulated per fixed step no matter how many arrive, so
+//! sending inputs faster does not move a client faster, and a client can no
+//! longer claim to fly or to stand inside a wall. A step with no input left
+//! to simulate runs a neutral one, so an idle client still falls and stops.
+//!
+//! Every client on such a world is simulated from the moment it joins. The
+//! position, flying and ghost state it reports are ignored, so a client that
+//! leaves out its inputs only stands idle. The stock `RigidControls` record
+//! and send inputs, and replay them on corrections, whenever the world's
+//! options enable this.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{BrainComp, Physics, Registry, RigidBody, Vec3, VoxelAccess, WorldConfig};
+
+/// Per-world knob enabling server-authoritative client movement. `None` on a
+/// [`crate::WorldConfig`] (the default) keeps applying client positions as
+/// reported. Inputs are per fixed step, so `Some(..)` requires a
+/// `fixed_timestep` and is rejected at config-build time without one.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthoritativeMovementConfig {
+    /// Distance in blocks between the server's simulated position and the
+    /// client's predicted one beyond which the client is corrected. Must be
+    /// positive; too small a value corrects honest clients over float drift.
+    pub correction_threshold: f32,
+
+    /// Inputs buffered per client awaiting simulation. Beyond this the oldest
+    /// are dropped, bounding how far a lagging client's body trails its
+    /// inputs. Must be > 0.
+    pub max_buffered_inputs: u32,
+}
+
+impl Default for AuthoritativeMovementConfig {
+    fn default() -> Self {
+        Self {
+            correction_threshold: 0.25,
+            max_buffered_inputs: 32,
+        }
+    }
+}
+
+impl AuthoritativeMovementConfig {
+    /// Validate the tunables. Called at world-config build time.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.correction_threshold.is_finite() || self.correction_threshold <= 0.0 {
+            return Err(
+                "AuthoritativeMovementConfig.correction_threshold must be positive".to_owned(),
+            );
+        }
+        if self.max_buffered_inputs == 0 {
+            return Err(
+                "AuthoritativeMovementConfig.max_buffered_inputs must be greater than 0".to_owned(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// What a client did during one fixed step, sent in the `inputs` list of its
+/// peer update on authoritative worlds.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MovementInput {
+    /// Strictly increasing per client. Corrections name the input they were
+    /// computed after, so the client knows which inputs to replay.
+    pub seq: u64,
+
+    /// Facing in radians around the y axis, measured like
+    /// [`crate::BrainState::heading`].
+    pub heading: f32,
+
+    pub running: bool,
+    pub sprinting: bool,
+    pub jumping: bool,
+
+    /// Where the client's own simulation put its body after this input.
+    pub position: Option<Vec3<f32>>,
+}
+
+impl MovementInput {
+    /// Drive `brain` with this input and advance `body` by one step of `dt`.
+    pub fn simulate(
+        &self,
+        brain: &mut BrainComp,
+        body: &mut RigidBody,
+        dt: f32,
+        space: &dyn VoxelAccess,
+        registry: &Registry,
+        config: &WorldConfig,
+    ) {
+        brain.state.running = self.running || self.sprinting;
+        brain.state.sprinting = self.sprinting;
+        brain.state.jumping = self.jumping;
+        brain.operate_heading(self.heading, body, dt);
+
+        Physics::iterate_body(body, dt, space, registry, config);
+    }
+
+    /// How far the position the client predicted for this input is from
+    /// `body`, or `None` when it did not predict one.
+    pub fn divergence(&self, body: &RigidBody) -> Option<f32> {
+        let Vec3(cx, cy, cz) = self.position.as_ref()?;
+        let Vec3(px, py, pz) = body.get_position();
+        let (dx, dy, dz) = (cx - px, cy - py, cz - pz);
+        Some((dx * dx + dy * dy + dz * dz).sqrt())
+    }
+}
+
+/// The server's state for a client's body after simulating input `seq`, sent
+/// when the client's prediction strayed too far from it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MovementCorrection {
+    pub seq: u64,
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+}
+
+impl MovementCorrection {
+    pub fn new(seq: u64, body: &RigidBody) -> Self {
+        let Vec3(px, py, pz) = body.get_position();
+        let Vec3(vx, vy, vz) = body.velocity;
+        Self {
+            seq,
+            position: [px, py, pz],
+            velocity: [vx, vy, vz],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BrainOptions, AABB};
+
+    fn body() -> RigidBody {
+        RigidBody::new(&AABB::new().scale_x(0.8).scale_y(1.8).scale_z(0.8).build()).build()
+    }
+
+    #[test]
+    fn divergence_measures_the_predicted_position() {
+        let mut body = body();
+        body.set_position(1.0, 2.0, 3.0);
+
+        let honest = MovementInput {
+            position: Some(Vec3(1.0, 2.0, 3.0)),
+            ..Default::default()
+        };
+        let flying = MovementInput {
+            position: Some(Vec3(1.0, 6.0, 3.0)),
+            ..Default::default()
+        };
+
+        assert_eq!(honest.divergence(&body), Some(0.0));
+        assert_eq!(flying.divergence(&body), Some(4.0));
+        assert_eq!(MovementInput::default().divergence(&body), None);
+    }
+
+    #[test]
+    fn a_running_input_pushes_the_body_along_its_heading() {
+        let config = WorldConfig::new().build();
+        let registry = Registry::new();
+        let space = crate::Chunks::new(&config);
+
+        let mut brain = BrainComp::new(BrainOptions::default());
+        let mut body = body();
+        body.set_position(0.5, 80.0, 0.5);
+
+        let input = MovementInput {
+            heading: std::f32::consts::FRAC_PI_2,
+            running: true,
+            ..Default::default()
+        };
+        for _ in 0..10 {
+            input.simulate(
+                &mut brain,
+                &mut body,
+                1.0 / 60.0,
+                &space,
+                &registry,
+                &config,
+            );
+        }
+
+        let Vec3(px, _, pz) = body.get_position();
+        assert!(px > 0.5, "moved toward +x, got {}", px);
+        assert!((pz - 0.5).abs() < 1e-3);
+        assert_eq!(body.yaw, std::f32::consts::FRAC_PI_2);
+    }
+}
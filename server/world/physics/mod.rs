@@ -1,15 +1,16 @@
 use crossbeam_channel::Receiver;
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use log::info;
 use nalgebra::Vector3;
 use rapier3d::{
     geometry::DefaultBroadPhase,
     prelude::{
-        vector, ActiveEvents, BroadPhase, CCDSolver, ChannelEventCollector, ColliderBuilder,
-        ColliderHandle, ColliderSet, CollisionEvent, ImpulseJointSet, IntegrationParameters,
-        IslandManager, MultibodyJointSet, NarrowPhase, PhysicsHooks, PhysicsPipeline,
-        RigidBody as RapierBody, RigidBodyBuilder as RapierBodyBuilder,
-        RigidBodyHandle as RapierBodyHandle, RigidBodySet as RapierBodySet,
+        point, vector, ActiveEvents, ActiveHooks, BroadPhase, CCDSolver, ChannelEventCollector,
+        ColliderBuilder, ColliderHandle, ColliderSet, CollisionEvent, ImpulseJointSet,
+        IntegrationParameters, IslandManager, Isometry, MultibodyJointSet, NarrowPhase,
+        PairFilterContext, PhysicsHooks, PhysicsPipeline, RigidBody as RapierBody,
+        RigidBodyBuilder as RapierBodyBuilder, RigidBodyHandle as RapierBodyHandle,
+        RigidBodySet as RapierBodySet, Rotation, SharedShape, SolverFlags,
     },
 };
 use specs::Entity;
@@ -21,13 +22,29 @@ use super::{registry::Registry, WorldConfig};
 mod aabb;
 mod raycast;
 mod rigidbody;
+mod shape;
 mod sweep;
 
 pub use aabb::*;
 pub use raycast::*;
 pub use rigidbody::*;
+pub use shape::*;
 pub use sweep::*;
 
+/// Keeps rapier from pushing the colliders of each listed pair apart, while
+/// still reporting their collisions.
+struct UnsolvedPairs<'a>(&'a HashSet<(ColliderHandle, ColliderHandle)>);
+
+impl PhysicsHooks for UnsolvedPairs<'_> {
+    fn filter_contact_pair(&self, context: &PairFilterContext) -> Option<SolverFlags> {
+        if self.0.contains(&(context.collider1, context.collider2)) {
+            Some(SolverFlags::empty())
+        } else {
+            Some(SolverFlags::COMPUTE_IMPULSES)
+        }
+    }
+}
+
 pub struct Physics {
     body_set: RapierBodySet,
     collider_set: ColliderSet,
@@ -42,6 +59,7 @@ pub struct Physics {
     collision_recv: Receiver<CollisionEvent>,
     event_handler: ChannelEventCollector,
     gravity: Vector3<f32>,
+    unsolved_pairs: HashSet<(ColliderHandle, ColliderHandle)>,
     pub entity_to_handlers: HashMap<Entity, (ColliderHandle, RapierBodyHandle)>,
 }
 
@@ -65,6 +83,7 @@ impl Physics {
             pipeline: PhysicsPipeline::default(),
             event_handler,
             gravity: vector![0.0, 0.0, 0.0],
+            unsolved_pairs: HashSet::new(),
             entity_to_handlers: HashMap::new(),
         }
     }
@@ -72,7 +91,7 @@ impl Physics {
     pub fn step(&mut self, dt: f32) -> Vec<CollisionEvent> {
         self.integration_options.dt = dt;
 
-        let physics_hooks = ();
+        let physics_hooks = UnsolvedPairs(&self.unsolved_pairs);
 
         self.pipeline.step(
             &self.gravity,
@@ -100,6 +119,20 @@ impl Physics {
         collisions
     }
 
+    /// Collider pairs that overlap by design, e.g. a rider and its mount, and
+    /// so are not pushed apart by the following steps. Replaces the pairs
+    /// set before.
+    pub fn set_unsolved_pairs(
+        &mut self,
+        pairs: impl IntoIterator<Item = (ColliderHandle, ColliderHandle)>,
+    ) {
+        self.unsolved_pairs.clear();
+        for (a, b) in pairs {
+            self.unsolved_pairs.insert((a, b));
+            self.unsolved_pairs.insert((b, a));
+        }
+    }
+
     pub fn register(&mut self, body: &RigidBody) -> (RapierBodyHandle, ColliderHandle) {
         let Vec3(px, py, pz) = body.get_position();
 
@@ -109,13 +142,39 @@ impl Physics {
             .gravity_scale(0.0)
             .lock_rotations()
             .build();
-        let mut collider = ColliderBuilder::capsule_y(
-            body.aabb.height() / 2.0,
-            (body.aabb.width() / 2.0).min(body.aabb.depth() / 2.0),
-        )
+        let mut collider = match &body.shape {
+            Some(shape) => ColliderBuilder::compound(
+                shape
+                    .parts
+                    .iter()
+                    .map(|part| match part {
+                        ShapePart::Box {
+                            center: [cx, cy, cz],
+                            half_extents: [hx, hy, hz],
+                        } => (
+                            Isometry::translation(*cx, *cy, *cz),
+                            SharedShape::cuboid(*hx, *hy, *hz),
+                        ),
+                        ShapePart::Capsule { start, end, radius } => (
+                            Isometry::identity(),
+                            SharedShape::capsule(
+                                point![start[0], start[1], start[2]],
+                                point![end[0], end[1], end[2]],
+                                *radius,
+                            ),
+                        ),
+                    })
+                    .collect(),
+            ),
+            None => ColliderBuilder::capsule_y(
+                body.aabb.height() / 2.0,
+                (body.aabb.width() / 2.0).min(body.aabb.depth() / 2.0),
+            ),
+        }
         .build();
 
         collider.set_active_events(ActiveEvents::COLLISION_EVENTS);
+        collider.set_active_hooks(ActiveHooks::FILTER_CONTACT_PAIRS);
 
         let body_handle = self.body_set.insert(rapier_body);
         let collider_handle =
@@ -161,6 +220,52 @@ impl Physics {
         body.reset_torques(false);
     }
 
+    /// Turn a rapier body to `yaw` around the y axis, so a compound collider
+    /// follows its [`RigidBody::yaw`].
+    pub fn rotate_rapier_body(&mut self, body_handle: &RapierBodyHandle, yaw: f32) {
+        let body = self.get_mut(body_handle);
+        body.set_rotation(Rotation::from_axis_angle(&Vector3::y_axis(), yaw), false);
+    }
+
+    /// Sweep a body's collision volume by `velocity`: the parts of `shape`
+    /// placed around the center of `aabb` when there is one, `aabb` itself
+    /// otherwise. `aabb` is carried along when `translate`, just as [`sweep`]
+    /// moves its target.
+    pub fn sweep_body(
+        space: &dyn VoxelAccess,
+        registry: &Registry,
+        aabb: &mut AABB,
+        shape: Option<TurnedShape>,
+        velocity: &Vec3<f32>,
+        callback: &mut SweepCallback,
+        translate: bool,
+    ) {
+        let Some(TurnedShape { shape, yaw }) = shape else {
+            sweep(space, registry, aabb, velocity, callback, translate, 10);
+            return;
+        };
+
+        let center = Vec3(
+            (aabb.min_x + aabb.max_x) / 2.0,
+            (aabb.min_y + aabb.max_y) / 2.0,
+            (aabb.min_z + aabb.max_z) / 2.0,
+        );
+        let mut parts = shape.world_aabbs(&center, yaw);
+        let before = parts[0].clone();
+
+        sweep_boxes(
+            space, registry, &mut parts, velocity, callback, translate, 10,
+        );
+
+        if translate {
+            aabb.translate(
+                parts[0].min_x - before.min_x,
+                parts[0].min_y - before.min_y,
+                parts[0].min_z - before.min_z,
+            );
+        }
+    }
+
     /// Lift a body's box clear of any solid volumes it overlaps, once, on
     /// its first physics tick against ready terrain. Swept-AABB collision
     /// only stops a body entering a face from outside, so a body that starts
@@ -352,7 +457,14 @@ impl Physics {
         };
 
         // sweeps aabb along dx and accounts for collisions
-        Physics::process_collisions(space, registry, &mut body.aabb, &dx, &mut body.resting);
+        Physics::process_collisions(
+            space,
+            registry,
+            &mut body.aabb,
+            TurnedShape::new(body.shape.as_ref(), body.yaw),
+            &dx,
+            &mut body.resting,
+        );
 
         // if autostep, and on ground, run collisions again with stepped up aabb
         if body.auto_step {
@@ -442,7 +554,14 @@ impl Physics {
     ) -> Vec3<f32> {
         let before = body.get_position();
         let mut resting = Vec3::default();
-        Physics::process_collisions(space, registry, &mut body.aabb, delta, &mut resting);
+        Physics::process_collisions(
+            space,
+            registry,
+            &mut body.aabb,
+            TurnedShape::new(body.shape.as_ref(), body.yaw),
+            delta,
+            &mut resting,
+        );
         body.resting = resting;
         for i in 0..3 {
             if body.resting[i] != 0 {
@@ -484,17 +603,17 @@ impl Physics {
 
         let mut is_resting = false;
 
-        sweep(
+        Physics::sweep_body(
             space,
             registry,
             &mut body.aabb,
+            TurnedShape::new(body.shape.as_ref(), body.yaw),
             &sleep_vec,
             &mut |_, _, _, _| {
                 is_resting = true;
                 true
             },
             false,
-            10,
         );
 
         is_resting
@@ -733,15 +852,17 @@ impl Physics {
         space: &dyn VoxelAccess,
         registry: &Registry,
         aabb: &mut AABB,
+        shape: Option<TurnedShape>,
         velocity: &Vec3<f32>,
         resting: &mut Vec3<i32>,
     ) {
         resting.set(0, 0, 0);
 
-        sweep(
+        Physics::sweep_body(
             space,
             registry,
             aabb,
+            shape,
             velocity,
             &mut |_, axis, dir, vec| -> bool {
                 resting[axis] = dir;
@@ -749,7 +870,6 @@ impl Physics {
                 false
             },
             true,
-            10,
         );
     }
 
@@ -789,10 +909,11 @@ impl Physics {
         // stays at its post-collision position unless the step commits.
 
         // move towards the target until the first x/z collision
-        sweep(
+        Physics::sweep_body(
             space,
             registry,
             old_aabb,
+            TurnedShape::new(body.shape.as_ref(), body.yaw),
             dx,
             &mut |_, axis, _, vec| {
                 if axis == 1 {
@@ -802,7 +923,6 @@ impl Physics {
                 true
             },
             true,
-            10,
         );
 
         let y = old_aabb.min_y;
@@ -812,17 +932,17 @@ impl Physics {
         let up_vec = Vec3(0.0, y_dist, 0.0);
         let mut is_blocked_above = false;
 
-        sweep(
+        Physics::sweep_body(
             space,
             registry,
             old_aabb,
+            TurnedShape::new(body.shape.as_ref(), body.yaw),
             &up_vec,
             &mut |_, _, _, _| {
                 is_blocked_above = true;
                 true
             },
             true,
-            10,
         );
 
         if is_blocked_above {
@@ -837,7 +957,14 @@ impl Physics {
         );
         leftover[1] = 0.0;
         let mut tmp_resting = Vec3::default();
-        Physics::process_collisions(space, registry, old_aabb, &leftover, &mut tmp_resting);
+        Physics::process_collisions(
+            space,
+            registry,
+            old_aabb,
+            TurnedShape::new(body.shape.as_ref(), body.yaw),
+            &leftover,
+            &mut tmp_resting,
+        );
 
         // bail unless the step made it past the obstruction on a blocked axis,
         // so a failed trial cannot lift the body straight up against a wall
@@ -933,6 +1060,85 @@ mod displace_body_tests {
     }
 }
 
+#[cfg(test)]
+mod compound_shape_tests {
+    use super::*;
+    use crate::{Block, Chunk, ChunkOptions, Registry};
+
+    // The same wall column at x=12 the prow tests use.
+    fn walled_chunk() -> (Chunk, Registry) {
+        let mut registry = Registry::new();
+        registry.register_block(&Block::new("Stone").id(5).build());
+        let opts = ChunkOptions {
+            size: 16,
+            max_height: 64,
+            sub_chunks: 4,
+        };
+        let mut chunk = Chunk::new("compound", 0, 0, &opts);
+        for y in 11..=13 {
+            for z in 0..16 {
+                chunk.set_voxel(12, y, z, 5);
+            }
+        }
+        (chunk, registry)
+    }
+
+    // A boat: a small reference box around a hull 3 blocks long along its
+    // local z.
+    fn boat(yaw: f32) -> RigidBody {
+        let aabb = AABB::new().scale_x(0.8).scale_y(0.8).scale_z(0.8).build();
+        let hull = CompoundShape::new().with_box([0.0, 0.0, 0.0], [0.4, 0.4, 1.5]);
+        let mut body = RigidBody::new(&aabb).shape(hull).build();
+        body.yaw = yaw;
+        body.set_position(6.5, 12.5, 8.0);
+        body
+    }
+
+    #[test]
+    fn voxel_sweeps_follow_the_turned_shape() {
+        let (chunk, registry) = walled_chunk();
+
+        // Bow first: the hull's far end meets the wall 1.5 before the center.
+        let mut bow_on = boat(std::f32::consts::FRAC_PI_2);
+        Physics::displace_body(&mut bow_on, &Vec3(6.0, 0.0, 0.0), &chunk, &registry);
+        let Vec3(x, _, _) = bow_on.get_position();
+        assert!((x - 10.5).abs() < 1e-3, "bow stops at the wall, center {x}");
+        assert_eq!(bow_on.resting[0], 1);
+
+        // Broadside: only the hull's beam stands between center and wall.
+        let mut broadside = boat(0.0);
+        Physics::displace_body(&mut broadside, &Vec3(6.0, 0.0, 0.0), &chunk, &registry);
+        let Vec3(x, _, _) = broadside.get_position();
+        assert!(
+            (x - 11.6).abs() < 1e-3,
+            "beam stops at the wall, center {x}"
+        );
+    }
+
+    #[test]
+    fn shaped_bodies_register_compound_colliders() {
+        let mut physics = Physics::new();
+        let body = RigidBody::new(&AABB::new().scale_x(0.8).scale_y(0.8).scale_z(0.8).build())
+            .shape(
+                CompoundShape::new()
+                    .with_box([0.0, 0.0, 0.0], [0.4, 0.4, 1.5])
+                    .with_capsule([0.0, 0.4, 1.5], [0.0, 1.2, 2.0], 0.2),
+            )
+            .build();
+
+        let (body_handle, collider_handle) = physics.register(&body);
+        let compound = physics.collider_set[collider_handle]
+            .shape()
+            .as_compound()
+            .expect("a compound collider");
+        assert_eq!(compound.shapes().len(), 2);
+
+        physics.rotate_rapier_body(&body_handle, std::f32::consts::FRAC_PI_2);
+        let rotation = physics.get(&body_handle).rotation();
+        assert!((rotation.angle() - std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+    }
+}
+
 #[cfg(test)]
 mod prow_standoff_tests {
     use super::*;
@@ -158,6 +158,15 @@ pub fn trace(
     )
 }
 
+/// The first solid a ray reaches: how far along the ray, which voxel, and the
+/// normal of the face it entered through (zero when the ray starts inside).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolidHit {
+    pub distance: f32,
+    pub voxel: Vec3<i32>,
+    pub normal: Vec3<i32>,
+}
+
 /// Distance along `direction` to the first face of anything a swept body
 /// collides with, or `None` when `max_d` of open space lies ahead. The cell
 /// filter is [`sweep`](super::sweep::sweep)'s own — fluid, empty, and
@@ -174,6 +183,17 @@ pub fn trace_solids(
     direction: &Vec3<f32>,
     max_d: f32,
 ) -> Option<f32> {
+    trace_solid_hit(space, registry, origin, direction, max_d).map(|hit| hit.distance)
+}
+
+/// [`trace_solids`], also reporting the voxel hit and the face entered.
+pub fn trace_solid_hit(
+    space: &dyn VoxelAccess,
+    registry: &Registry,
+    origin: &Vec3<f32>,
+    direction: &Vec3<f32>,
+    max_d: f32,
+) -> Option<SolidHit> {
     let Vec3(dx, dy, dz) = direction;
     let ds = (dx * dx + dy * dy + dz * dz).sqrt();
 
@@ -183,11 +203,11 @@ pub fn trace_solids(
 
     let unit = Vec3(dx / ds, dy / ds, dz / ds);
 
-    // Written by the cell test, read after the walk: per-cell AABB entry
-    // distances travel out of the `Fn` callback through interior
-    // mutability. Boxes stay inside their own cell, so the first cell that
-    // reports a hit holds the globally nearest one.
-    let nearest_t = Cell::new(f32::MAX);
+    // Written by the cell test, read after the walk: per-cell AABB entries
+    // travel out of the `Fn` callback through interior mutability. Boxes
+    // stay inside their own cell, so the first cell that reports a hit holds
+    // the globally nearest one.
+    let nearest = Cell::new(None);
 
     let test_cell = |vx: i32, vy: i32, vz: i32| -> bool {
         let block = registry.get_block_by_id(space.get_voxel(vx, vy, vz));
@@ -201,19 +221,29 @@ pub fn trace_solids(
         }
 
         let rotation = space.get_voxel_rotation(vx, vy, vz);
-        let mut cell_nearest = f32::MAX;
+        let mut cell_nearest: Option<(f32, Option<usize>)> = None;
         for aabb in &aabbs {
             let mut solid = rotation.rotate_aabb(aabb, true, true);
             solid.translate(vx as f32, vy as f32, vz as f32);
-            if let Some(t) = ray_box_entry(origin, &unit, &solid, max_d) {
-                cell_nearest = cell_nearest.min(t);
+            if let Some(entry) = ray_box_entry_face(origin, &unit, &solid, max_d) {
+                if cell_nearest.is_none_or(|(t, _)| entry.0 < t) {
+                    cell_nearest = Some(entry);
+                }
             }
         }
 
-        if cell_nearest == f32::MAX {
+        let Some((distance, axis)) = cell_nearest else {
             return false;
+        };
+        let mut normal = Vec3(0, 0, 0);
+        if let Some(axis) = axis {
+            normal[axis] = if unit[axis] > 0.0 { -1 } else { 1 };
         }
-        nearest_t.set(cell_nearest);
+        nearest.set(Some(SolidHit {
+            distance,
+            voxel: Vec3(vx, vy, vz),
+            normal,
+        }));
         true
     };
 
@@ -222,29 +252,52 @@ pub fn trace_solids(
     let mut hit_pos = Vec3(0.0, 0.0, 0.0);
     let mut hit_norm = Vec3(0, 0, 0);
 
-    trace(
+    if trace(
         max_d,
         &test_cell,
         &mut trace_origin,
         &mut trace_direction,
         &mut hit_pos,
         &mut hit_norm,
-    )
-    .then(|| nearest_t.get())
+    ) {
+        nearest.take()
+    } else {
+        None
+    }
 }
 
 /// Entry distance of a unit-direction ray into a box within `[0, max_d]`,
 /// `0.0` when the origin already sits inside. `None` when the ray misses
 /// within the reach.
-fn ray_box_entry(origin: &Vec3<f32>, unit: &Vec3<f32>, solid: &AABB, max_d: f32) -> Option<f32> {
+pub(crate) fn ray_box_entry(
+    origin: &Vec3<f32>,
+    unit: &Vec3<f32>,
+    solid: &AABB,
+    max_d: f32,
+) -> Option<f32> {
+    ray_box_entry_face(origin, unit, solid, max_d).map(|(t, _)| t)
+}
+
+/// [`ray_box_entry`], also reporting the axis of the face the ray entered
+/// through, or `None` when the origin already sits inside.
+fn ray_box_entry_face(
+    origin: &Vec3<f32>,
+    unit: &Vec3<f32>,
+    solid: &AABB,
+    max_d: f32,
+) -> Option<(f32, Option<usize>)> {
     let mut t_enter = 0.0_f32;
     let mut t_exit = max_d;
+    let mut face = None;
 
-    for (o, d, min, max) in [
+    for (axis, (o, d, min, max)) in [
         (origin.0, unit.0, solid.min_x, solid.max_x),
         (origin.1, unit.1, solid.min_y, solid.max_y),
         (origin.2, unit.2, solid.min_z, solid.max_z),
-    ] {
+    ]
+    .into_iter()
+    .enumerate()
+    {
         if d == 0.0 {
             if o < min || o > max {
                 return None;
@@ -254,14 +307,17 @@ fn ray_box_entry(origin: &Vec3<f32>, unit: &Vec3<f32>, solid: &AABB, max_d: f32)
 
         let (t0, t1) = ((min - o) / d, (max - o) / d);
         let (near, far) = if t0 <= t1 { (t0, t1) } else { (t1, t0) };
-        t_enter = t_enter.max(near);
+        if near > t_enter {
+            t_enter = near;
+            face = Some(axis);
+        }
         t_exit = t_exit.min(far);
         if t_enter > t_exit {
             return None;
         }
     }
 
-    Some(t_enter)
+    Some((t_enter, face))
 }
 
 #[cfg(test)]
@@ -358,6 +414,26 @@ mod trace_solids_tests {
         assert_eq!(hit, Some(0.0), "a body inside a solid is blocked now");
     }
 
+    #[test]
+    fn solid_hits_report_the_voxel_and_the_face_entered() {
+        let (chunk, registry) = decorated_chunk();
+        let hit = trace_solid_hit(
+            &chunk,
+            &registry,
+            &Vec3(12.5, 14.0, 8.5),
+            &Vec3(0.0, -1.0, 0.0),
+            4.0,
+        );
+        assert_eq!(
+            hit,
+            Some(SolidHit {
+                distance: 1.0,
+                voxel: Vec3(12, 12, 8),
+                normal: Vec3(0, 1, 0),
+            })
+        );
+    }
+
     #[test]
     fn passable_decor_never_blocks() {
         let (chunk, registry) = decorated_chunk();
@@ -1,4 +1,4 @@
-use crate::{Vec3, AABB};
+use crate::{CompoundShape, Vec3, AABB};
 
 /// A one-shot modifier for a body's contact response, consumed by the next
 /// physics tick like `forces` and `impulses`: game systems set it before
@@ -61,9 +61,20 @@ pub struct RigidBody {
     /// Counts how many frames this rigid body is static.
     pub sleep_frame_count: i32,
 
-    /// AABB of this rigid body, describing its collision box.
+    /// AABB of this rigid body, describing its collision box. Its center is
+    /// the body's position, and fluid, climbing and placement checks use it
+    /// even when `shape` is set.
     pub aabb: AABB,
 
+    /// Compound collision shape around the body's position, replacing `aabb`
+    /// for voxel sweeps, entity-entity contact and lag-compensated queries.
+    pub shape: Option<CompoundShape>,
+
+    /// Rotation in radians around the y axis that `shape` is turned by,
+    /// measured like [`crate::BrainState::heading`]: 0 faces +z. A brain
+    /// turns its body to its heading, and a rider takes its mount's yaw.
+    pub yaw: f32,
+
     /// Mass of this rigid body.
     pub mass: f32,
 
@@ -216,6 +227,7 @@ pub struct RigidBodyBuilder {
     gravity_multiplier: f32,
     auto_step: bool,
     prow_clearance: f32,
+    shape: Option<CompoundShape>,
 }
 
 impl RigidBodyBuilder {
@@ -268,6 +280,13 @@ impl RigidBodyBuilder {
         self
     }
 
+    /// Collide with a compound shape instead of the body's box (see
+    /// [`RigidBody::shape`]). Default is none.
+    pub fn shape(mut self, shape: CompoundShape) -> Self {
+        self.shape = Some(shape);
+        self
+    }
+
     pub fn build(self) -> RigidBody {
         RigidBody {
             collision: None,
@@ -287,6 +306,8 @@ impl RigidBodyBuilder {
             sleep_frame_count: 10 | 0,
 
             aabb: self.aabb,
+            shape: self.shape,
+            yaw: 0.0,
             mass: self.mass,
             friction: self.friction,
             restitution: self.restitution,
@@ -0,0 +1,346 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Vec3;
+
+use super::aabb::AABB;
+
+/// One solid piece of a [`CompoundShape`], in the body's local frame: offsets
+/// are from the body's center, with local +z facing the body's yaw.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ShapePart {
+    /// A box around `center`, turning with the body.
+    #[serde(rename_all = "camelCase")]
+    Box {
+        center: [f32; 3],
+        half_extents: [f32; 3],
+    },
+
+    /// Every point within `radius` of the segment from `start` to `end`.
+    Capsule {
+        start: [f32; 3],
+        end: [f32; 3],
+        radius: f32,
+    },
+}
+
+/// A collision shape made of several boxes and capsules, so that a long body
+/// like a boat or a horse is not treated as one big box around all of it.
+///
+/// Voxel sweeps move every part's world-space bounding box together, rapier
+/// collides entities against the parts themselves, and lag-compensated
+/// queries test rays and volumes against them exactly. Parts turn with the
+/// body's yaw around the y axis.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompoundShape {
+    pub parts: Vec<ShapePart>,
+}
+
+/// A [`CompoundShape`] turned by its body's yaw, as swept against voxels.
+#[derive(Clone, Copy, Debug)]
+pub struct TurnedShape<'a> {
+    pub shape: &'a CompoundShape,
+    pub yaw: f32,
+}
+
+impl<'a> TurnedShape<'a> {
+    /// `shape` turned by `yaw`, or `None` when there is no shape or it has
+    /// no parts, so the body's box is swept instead.
+    pub fn new(shape: Option<&'a CompoundShape>, yaw: f32) -> Option<Self> {
+        shape
+            .filter(|shape| !shape.parts.is_empty())
+            .map(|shape| Self { shape, yaw })
+    }
+}
+
+impl CompoundShape {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a box part around `center`, relative to the body's center.
+    pub fn with_box(mut self, center: [f32; 3], half_extents: [f32; 3]) -> Self {
+        self.parts.push(ShapePart::Box {
+            center,
+            half_extents,
+        });
+        self
+    }
+
+    /// Add a capsule part from `start` to `end`, relative to the body's center.
+    pub fn with_capsule(mut self, start: [f32; 3], end: [f32; 3], radius: f32) -> Self {
+        self.parts.push(ShapePart::Capsule { start, end, radius });
+        self
+    }
+
+    /// The world-space bounding box of each part of a body centered at
+    /// `center` and turned by `yaw`. A turned box is bounded by the box around
+    /// its corners, so it collides slightly early at diagonal yaws.
+    pub fn world_aabbs(&self, center: &Vec3<f32>, yaw: f32) -> Vec<AABB> {
+        let (sin, cos) = yaw.sin_cos();
+
+        self.parts
+            .iter()
+            .map(|part| match part {
+                ShapePart::Box {
+                    center: local,
+                    half_extents: [hx, hy, hz],
+                } => {
+                    let Vec3(cx, cy, cz) = to_world(local, center, yaw);
+                    let ex = cos.abs() * hx + sin.abs() * hz;
+                    let ez = sin.abs() * hx + cos.abs() * hz;
+                    AABB::create(cx - ex, cy - hy, cz - ez, cx + ex, cy + hy, cz + ez)
+                }
+                ShapePart::Capsule { start, end, radius } => {
+                    let start = to_world(start, center, yaw);
+                    let end = to_world(end, center, yaw);
+                    let Vec3(min_x, min_y, min_z) = start.min(&end);
+                    let Vec3(max_x, max_y, max_z) = start.max(&end);
+                    AABB::create(
+                        min_x - radius,
+                        min_y - radius,
+                        min_z - radius,
+                        max_x + radius,
+                        max_y + radius,
+                        max_z + radius,
+                    )
+                }
+            })
+            .collect()
+    }
+
+    /// The ray parameter at which a ray first enters any part of a body
+    /// centered at `center` and turned by `yaw`, in units of `direction`'s
+    /// length. `0.0` if the origin is inside a part, `None` on a miss.
+    pub fn ray_entry(
+        &self,
+        center: &Vec3<f32>,
+        yaw: f32,
+        origin: &Vec3<f32>,
+        direction: &Vec3<f32>,
+    ) -> Option<f32> {
+        // Work in the body's frame, where boxes are axis-aligned again.
+        let origin = origin.sub(center).rotate_y(&Vec3::default(), -yaw);
+        let direction = direction.rotate_y(&Vec3::default(), -yaw);
+
+        self.parts
+            .iter()
+            .filter_map(|part| match part {
+                ShapePart::Box {
+                    center,
+                    half_extents,
+                } => ray_box_entry(&origin, &direction, center, half_extents),
+                ShapePart::Capsule { start, end, radius } => ray_capsule_entry(
+                    &origin,
+                    &direction,
+                    &Vec3::from_arr(*start),
+                    &Vec3::from_arr(*end),
+                    *radius,
+                ),
+            })
+            .min_by(|a, b| a.total_cmp(b))
+    }
+
+    /// Distance from `point` to the nearest surface of a body centered at
+    /// `center` and turned by `yaw`, or `0.0` if the point is inside a part.
+    /// `f32::INFINITY` for a shape without parts.
+    pub fn distance_to(&self, center: &Vec3<f32>, yaw: f32, point: &Vec3<f32>) -> f32 {
+        let point = point.sub(center).rotate_y(&Vec3::default(), -yaw);
+
+        self.parts
+            .iter()
+            .map(|part| match part {
+                ShapePart::Box {
+                    center,
+                    half_extents,
+                } => {
+                    let mut sq = 0.0;
+                    for axis in 0..3 {
+                        let outside = (point[axis] - center[axis]).abs() - half_extents[axis];
+                        if outside > 0.0 {
+                            sq += outside * outside;
+                        }
+                    }
+                    sq.sqrt()
+                }
+                ShapePart::Capsule { start, end, radius } => {
+                    let closest =
+                        closest_on_segment(&point, &Vec3::from_arr(*start), &Vec3::from_arr(*end));
+                    (point.sq_distance(&closest).sqrt() - radius).max(0.0)
+                }
+            })
+            .fold(f32::INFINITY, f32::min)
+    }
+}
+
+fn to_world(local: &[f32; 3], center: &Vec3<f32>, yaw: f32) -> Vec3<f32> {
+    Vec3::from_arr(*local)
+        .rotate_y(&Vec3::default(), yaw)
+        .add(center)
+}
+
+fn dot(a: &Vec3<f32>, b: &Vec3<f32>) -> f32 {
+    a.0 * b.0 + a.1 * b.1 + a.2 * b.2
+}
+
+fn closest_on_segment(point: &Vec3<f32>, start: &Vec3<f32>, end: &Vec3<f32>) -> Vec3<f32> {
+    let axis = end.sub(start);
+    let len_sq = dot(&axis, &axis);
+    if len_sq <= f32::EPSILON {
+        return start.clone();
+    }
+    let t = (dot(&point.sub(start), &axis) / len_sq).clamp(0.0, 1.0);
+    start.scale_and_add(&axis, t)
+}
+
+/// Slab test against a box given by its center and half-extents.
+fn ray_box_entry(
+    origin: &Vec3<f32>,
+    direction: &Vec3<f32>,
+    center: &[f32; 3],
+    half_extents: &[f32; 3],
+) -> Option<f32> {
+    let mut t_min = f32::NEG_INFINITY;
+    let mut t_max = f32::INFINITY;
+    for axis in 0..3 {
+        let min = center[axis] - half_extents[axis];
+        let max = center[axis] + half_extents[axis];
+        if direction[axis].abs() < f32::EPSILON {
+            if origin[axis] < min || origin[axis] > max {
+                return None;
+            }
+            continue;
+        }
+        let inv = 1.0 / direction[axis];
+        let t1 = (min - origin[axis]) * inv;
+        let t2 = (max - origin[axis]) * inv;
+        t_min = t_min.max(t1.min(t2));
+        t_max = t_max.min(t1.max(t2));
+        if t_min > t_max {
+            return None;
+        }
+    }
+    if t_max < 0.0 {
+        return None;
+    }
+    Some(t_min.max(0.0))
+}
+
+/// A capsule is a cylinder capped by two spheres: the entry is the nearest of
+/// the cylinder wall (within the segment) and either cap.
+fn ray_capsule_entry(
+    origin: &Vec3<f32>,
+    direction: &Vec3<f32>,
+    start: &Vec3<f32>,
+    end: &Vec3<f32>,
+    radius: f32,
+) -> Option<f32> {
+    let closest = closest_on_segment(origin, start, end);
+    if origin.sq_distance(&closest) <= radius * radius {
+        return Some(0.0);
+    }
+
+    let length = direction.len();
+    if length < f32::EPSILON {
+        return None;
+    }
+    let unit = direction.scale(1.0 / length);
+    let radius_sq = radius * radius;
+
+    let sphere_entry = |sphere: &Vec3<f32>| {
+        let offset = origin.sub(sphere);
+        let b = dot(&offset, &unit);
+        let h = b * b - (dot(&offset, &offset) - radius_sq);
+        let t = -b - h.sqrt();
+        (h >= 0.0 && t >= 0.0).then_some(t)
+    };
+
+    let mut best = [sphere_entry(start), sphere_entry(end)]
+        .into_iter()
+        .flatten()
+        .min_by(|a, b| a.total_cmp(b));
+
+    let axis = end.sub(start);
+    let axis_len = axis.len();
+    if axis_len > f32::EPSILON {
+        let axis = axis.scale(1.0 / axis_len);
+        let offset = origin.sub(start);
+        let d_axis = dot(&unit, &axis);
+        let o_axis = dot(&offset, &axis);
+        let a = 1.0 - d_axis * d_axis;
+        if a > f32::EPSILON {
+            let b = dot(&unit, &offset) - d_axis * o_axis;
+            let c = dot(&offset, &offset) - o_axis * o_axis - radius_sq;
+            let h = b * b - a * c;
+            if h >= 0.0 {
+                let t = (-b - h.sqrt()) / a;
+                let along = o_axis + t * d_axis;
+                if t >= 0.0 && (0.0..=axis_len).contains(&along) {
+                    best = Some(best.map_or(t, |best| best.min(t)));
+                }
+            }
+        }
+    }
+
+    best.map(|t| t / length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A horse-like body: a long torso box and a capsule neck ahead of it.
+    fn horse() -> CompoundShape {
+        CompoundShape::new()
+            .with_box([0.0, 0.0, 0.0], [0.4, 0.5, 1.0])
+            .with_capsule([0.0, 0.3, 1.0], [0.0, 1.0, 1.5], 0.2)
+    }
+
+    #[test]
+    fn yaw_turns_parts_around_the_center() {
+        let shape = CompoundShape::new().with_box([0.0, 0.0, 1.0], [0.25, 0.5, 0.5]);
+        let center = Vec3(10.0, 5.0, 10.0);
+
+        let facing_z = &shape.world_aabbs(&center, 0.0)[0];
+        assert!((facing_z.min_z - 10.5).abs() < 1e-5 && (facing_z.max_z - 11.5).abs() < 1e-5);
+        assert!((facing_z.width() - 0.5).abs() < 1e-5);
+
+        let facing_x = &shape.world_aabbs(&center, std::f32::consts::FRAC_PI_2)[0];
+        assert!((facing_x.min_x - 10.5).abs() < 1e-5 && (facing_x.max_x - 11.5).abs() < 1e-5);
+        assert!((facing_x.depth() - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn rays_hit_the_parts_not_their_bounds() {
+        let shape = horse();
+        let center = Vec3(0.0, 0.0, 0.0);
+
+        // Straight down onto the torso.
+        let torso = shape.ray_entry(&center, 0.0, &Vec3(0.0, 5.0, 0.0), &Vec3(0.0, -1.0, 0.0));
+        assert!((torso.unwrap() - 4.5).abs() < 1e-4);
+
+        // Down onto the neck's upper cap.
+        let neck = shape.ray_entry(&center, 0.0, &Vec3(0.0, 5.0, 1.5), &Vec3(0.0, -2.0, 0.0));
+        assert!((neck.unwrap() - 1.9).abs() < 1e-4, "got {neck:?}");
+
+        // Beside the neck, inside the box around both parts, is empty.
+        let gap = shape.ray_entry(&center, 0.0, &Vec3(0.35, 5.0, 1.4), &Vec3(0.0, -1.0, 0.0));
+        assert_eq!(gap, None);
+    }
+
+    #[test]
+    fn turned_shapes_are_queried_in_their_own_frame() {
+        let shape = horse();
+        let center = Vec3(0.0, 0.0, 0.0);
+        let yaw = std::f32::consts::FRAC_PI_2;
+
+        // Turned to face +x, the torso spans x in -1..1 but only z in -0.4..0.4.
+        let hit = shape.ray_entry(&center, yaw, &Vec3(0.9, 5.0, 0.0), &Vec3(0.0, -1.0, 0.0));
+        assert!(hit.is_some());
+        let miss = shape.ray_entry(&center, yaw, &Vec3(0.0, 5.0, 0.9), &Vec3(0.0, -1.0, 0.0));
+        assert_eq!(miss, None);
+
+        assert!((shape.distance_to(&center, yaw, &Vec3(0.0, 0.0, 1.4)) - 1.0).abs() < 1e-4);
+        assert_eq!(shape.distance_to(&center, yaw, &Vec3(0.9, 0.0, 0.0)), 0.0);
+    }
+}
@@ -121,21 +121,82 @@ fn sweep_aabb(target: &AABB, other: &AABB, vector: &Vec3<f32>) -> SweepResults {
     SweepResults { h, nx, ny, nz }
 }
 
+/// The boxes of every solid voxel from `min` to `max`, one voxel at a time,
+/// for spaces without collision meshes.
+fn voxel_aabbs(
+    space: &dyn VoxelAccess,
+    registry: &Registry,
+    min: &Vec3<i32>,
+    max: &Vec3<i32>,
+) -> Vec<AABB> {
+    let mut aabbs = Vec::new();
+
+    for vx in min.0..=max.0 {
+        for vz in min.2..=max.2 {
+            for vy in min.1..=max.1 {
+                let id = space.get_voxel(vx, vy, vz);
+                let rotation = space.get_voxel_rotation(vx, vy, vz);
+                let block = registry.get_block_by_id(id);
+
+                if block.is_fluid || block.is_empty || block.is_passable {
+                    continue;
+                }
+
+                for aabb in block.get_aabbs(&Vec3(vx, vy, vz), space, registry) {
+                    let mut block_aabb = rotation.rotate_aabb(&aabb, true, true);
+                    block_aabb.translate(vx as f32, vy as f32, vz as f32);
+                    aabbs.push(block_aabb);
+                }
+            }
+        }
+    }
+
+    aabbs
+}
+
+/// Called on each collision with the distance travelled, the axis and
+/// direction hit, and the leftover movement, which it may edit. Returning
+/// true stops the sweep.
+pub type SweepCallback<'a> = dyn FnMut(f32, usize, i32, &mut [f32; 3]) -> bool + 'a;
+
 pub fn sweep(
     space: &dyn VoxelAccess,
     registry: &Registry,
     target: &mut AABB,
     velocity: &Vec3<f32>,
-    callback: &mut dyn FnMut(f32, usize, i32, &mut [f32; 3]) -> bool,
+    callback: &mut SweepCallback,
     translate: bool,
     max_iterations: usize,
 ) {
-    if max_iterations == 0 {
+    sweep_boxes(
+        space,
+        registry,
+        std::slice::from_mut(target),
+        velocity,
+        callback,
+        translate,
+        max_iterations,
+    );
+}
+
+/// Sweep several boxes that move as one, such as the parts of a compound
+/// shape, stopping all of them at the first collision of any.
+pub fn sweep_boxes(
+    space: &dyn VoxelAccess,
+    registry: &Registry,
+    targets: &mut [AABB],
+    velocity: &Vec3<f32>,
+    callback: &mut SweepCallback,
+    translate: bool,
+    max_iterations: usize,
+) {
+    if max_iterations == 0 || targets.is_empty() {
         return;
     }
 
     let &Vec3(vx, vy, vz) = velocity;
     let mag = (vx * vx + vy * vy + vz * vz).sqrt();
+    let target = AABB::union_all(targets);
 
     // Calculate the broadphase of the target (with -1/+1 padding to match client)
     let min_x = (if vx > 0.0 {
@@ -188,32 +249,18 @@ pub fn sweep(
         nz: 0.0,
     };
 
-    for vx in (min_x as i32)..=(max_x as i32) {
-        for vz in (min_z as i32)..=(max_z as i32) {
-            for vy in (min_y as i32)..=(max_y as i32) {
-                let id = space.get_voxel(vx, vy, vz);
-                let rotation = space.get_voxel_rotation(vx, vy, vz);
-                let block = registry.get_block_by_id(id);
-
-                if block.is_fluid || block.is_empty || block.is_passable {
-                    continue;
-                }
-
-                let aabbs = block.get_aabbs(&Vec3(vx, vy, vz), space, registry);
+    let min = Vec3(min_x as i32, min_y as i32, min_z as i32);
+    let max = Vec3(max_x as i32, max_y as i32, max_z as i32);
+    let block_aabbs = space
+        .get_collision_boxes(&min, &max, registry)
+        .unwrap_or_else(|| voxel_aabbs(space, registry, &min, &max));
 
-                if aabbs.is_empty() {
-                    continue;
-                }
+    for block_aabb in &block_aabbs {
+        for target in targets.iter() {
+            let result = sweep_aabb(target, block_aabb, &velocity);
 
-                aabbs.iter().for_each(|aabb| {
-                    let mut block_aabb = rotation.rotate_aabb(aabb, true, true);
-                    block_aabb.translate(vx as f32, vy as f32, vz as f32);
-                    let result = sweep_aabb(target, &block_aabb, &velocity);
-
-                    if result.h < closest.h {
-                        closest = result;
-                    }
-                })
+            if result.h < closest.h {
+                closest = result;
             }
         }
     }
@@ -225,7 +272,9 @@ pub fn sweep(
     let dz = closest.h * vz + epsilon * closest.nz;
 
     if translate {
-        target.translate(dx, dy, dz);
+        for target in targets.iter_mut() {
+            target.translate(dx, dy, dz);
+        }
     }
 
     // No collision
@@ -253,10 +302,10 @@ pub fn sweep(
 
     // More to go
     if leftover[0] * leftover[0] + leftover[1] * leftover[1] + leftover[2] * leftover[2] != 0.0 {
-        sweep(
+        sweep_boxes(
             space,
             registry,
-            target,
+            targets,
             &Vec3::from(&leftover),
             callback,
             translate,
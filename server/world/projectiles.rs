@@ -0,0 +1,354 @@
+//! Projectiles: ballistic entities swept against voxels and entity hitboxes
+//! by `ProjectilesSystem`, with their hits handed to game code.
+//!
+//! The system only records hits into [`ProjectileHits`]; the world drains them
+//! after each dispatch into the handle set by
+//! [`World::set_projectile_hit_handle`], where damage, sounds and item drops
+//! belong.
+
+use super::*;
+
+/// What a projectile hit.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProjectileTarget {
+    /// A solid voxel, and the normal of the face it was hit on.
+    Voxel { voxel: Vec3<i32>, normal: Vec3<i32> },
+
+    /// An entity or client, with its ID when it has one.
+    Entity { entity: Entity, id: Option<String> },
+}
+
+/// One hit of one projectile, recorded in the tick it happened.
+#[derive(Debug, Clone)]
+pub struct ProjectileHit {
+    pub projectile: Entity,
+    pub projectile_id: String,
+
+    /// ID of the entity or client that fired the projectile.
+    pub shooter: Option<String>,
+
+    pub target: ProjectileTarget,
+
+    /// Where along its path the projectile hit.
+    pub position: Vec3<f32>,
+
+    /// The projectile's velocity after the hit: reflected by a bounce,
+    /// unchanged by a pierce.
+    pub velocity: Vec3<f32>,
+
+    /// Whether the hit stopped the projectile, which is then despawned,
+    /// rather than it piercing or bouncing on.
+    pub stopped: bool,
+
+    /// The historical tick a rewound entity hit was resolved at.
+    pub rewound_tick: Option<u64>,
+}
+
+/// Hits recorded by `ProjectilesSystem` during a dispatch.
+#[derive(Debug, Default)]
+pub struct ProjectileHits(pub Vec<ProjectileHit>);
+
+/// Game code called with each projectile hit, set by
+/// [`World::set_projectile_hit_handle`].
+pub type ProjectileHitHandle = Arc<dyn Fn(&mut World, &ProjectileHit) + Send + Sync>;
+
+impl World {
+    /// Spawn a projectile entity of `etype` at `position`. It is not
+    /// persisted, and its launch state is replicated once as the
+    /// `projectile` metadata so clients can extrapolate its arc between
+    /// motion updates.
+    pub fn spawn_projectile(
+        &mut self,
+        etype: &str,
+        position: &Vec3<f32>,
+        projectile: ProjectileComp,
+    ) -> Entity {
+        let Vec3(px, py, pz) = position.clone();
+        let Vec3(dx, dy, dz) = projectile.heading().unwrap_or_default();
+
+        let mut metadata = MetadataComp::new();
+        metadata.set("projectile", &projectile);
+
+        self.create_entity(&nanoid!(), etype)
+            .with(metadata)
+            .with(PositionComp::new(px, py, pz))
+            .with(DirectionComp::new(dx, dy, dz))
+            .with(DoNotPersistComp)
+            .with(projectile)
+            .build()
+    }
+
+    /// Hand the hits recorded during the last dispatch to the projectile hit
+    /// handle, if one is set.
+    pub(super) fn handle_projectile_hits(&mut self) {
+        let hits = std::mem::take(&mut self.write_resource::<ProjectileHits>().0);
+        let Some(handle) = self.projectile_hit_handle.clone() else {
+            return;
+        };
+
+        for hit in &hits {
+            handle(self, hit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::RunNow;
+
+    use super::*;
+    use crate::{Block, Chunk, EntityTreeSystem, FixedStepConfig, ProjectilesSystem};
+
+    const STONE: u32 = 1;
+
+    fn world(config: &WorldConfig, walls: &[[i32; 3]]) -> World {
+        let mut world = World::new("projectiles", config);
+
+        let mut registry = Registry::new();
+        registry.register_block(&Block::new("Stone").id(STONE).build());
+        world.ecs_mut().insert(registry);
+
+        let mut chunk = Chunk::ready(0, 0, config);
+        for &[vx, vy, vz] in walls {
+            chunk.set_voxel(vx, vy, vz, STONE);
+        }
+        world.write_resource::<Chunks>().add(chunk);
+        world.write_resource::<Stats>().delta = 1.0 / 60.0;
+        world
+    }
+
+    fn bounded() -> WorldConfig {
+        WorldConfig::new()
+            .min_chunk([0, 0])
+            .max_chunk([0, 0])
+            .build()
+    }
+
+    fn target(world: &mut World, id: &str, position: [f32; 3]) -> Entity {
+        let [px, py, pz] = position;
+        let mut body =
+            RigidBody::new(&AABB::new().scale_x(0.6).scale_y(1.8).scale_z(0.6).build()).build();
+        body.set_position(px, py, pz);
+
+        world
+            .create_entity(id, "target")
+            .with(PositionComp::new(px, py, pz))
+            .with(RigidBodyComp::new(&body))
+            .build()
+    }
+
+    /// Fly projectiles until `done` holds of the hits so far, at most `ticks`.
+    fn fly(world: &mut World, ticks: usize, done: impl Fn(&[ProjectileHit]) -> bool) {
+        for _ in 0..ticks {
+            EntityTreeSystem.run_now(world.ecs());
+            ProjectilesSystem.run_now(world.ecs());
+            world.ecs_mut().maintain();
+            if done(&world.read_resource::<ProjectileHits>().0) {
+                return;
+            }
+        }
+    }
+
+    #[test]
+    fn fast_projectiles_stop_at_the_first_voxel_they_cross() {
+        let mut world = world(&bounded(), &[[10, 20, 8]]);
+        let seen = Arc::new(Mutex::new(vec![]));
+        let handled = seen.clone();
+        world.set_projectile_hit_handle(move |_, hit| handled.lock().unwrap().push(hit.clone()));
+
+        // Two blocks a tick: a per-tick position check would skip the wall.
+        let arrow = ProjectileBuilder::new(&Vec3(120.0, 0.0, 0.0))
+            .gravity_multiplier(0.0)
+            .build();
+        let arrow = world.spawn_projectile("arrow", &Vec3(2.5, 20.5, 8.5), arrow);
+
+        fly(&mut world, 10, |hits| !hits.is_empty());
+        world.handle_projectile_hits();
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert_eq!(
+            seen[0].target,
+            ProjectileTarget::Voxel {
+                voxel: Vec3(10, 20, 8),
+                normal: Vec3(-1, 0, 0),
+            }
+        );
+        assert!(seen[0].stopped && (seen[0].position.0 - 10.0).abs() < 1e-3);
+        assert!(!world.ecs().is_alive(arrow));
+        assert!(world.read_resource::<ProjectileHits>().0.is_empty());
+    }
+
+    #[test]
+    fn projectiles_fall_and_slow_along_their_arc() {
+        let mut world = world(&bounded(), &[]);
+        let arrow = ProjectileBuilder::new(&Vec3(10.0, 0.0, 0.0))
+            .drag(0.5)
+            .build();
+        let arrow = world.spawn_projectile("arrow", &Vec3(2.5, 40.5, 8.5), arrow);
+
+        fly(&mut world, 30, |_| false);
+
+        let projectiles = world.read_component::<ProjectileComp>();
+        let projectile = projectiles.get(arrow).unwrap();
+        let Vec3(vx, vy, _) = projectile.velocity;
+        assert!(vx < 10.0 * (-0.5f32 * 0.49).exp() && vy < -4.0);
+
+        let Vec3(px, py, _) = world.read_component::<PositionComp>().get(arrow).unwrap().0;
+        assert!(px > 6.0 && px < 7.5 && py < 40.5);
+
+        // The replicated heading follows the arc.
+        let direction = world
+            .read_component::<DirectionComp>()
+            .get(arrow)
+            .unwrap()
+            .0
+            .clone();
+        assert!(direction.sub(&projectile.heading().unwrap()).len() < 1e-6);
+    }
+
+    #[test]
+    fn projectiles_pierce_entities_and_bounce_off_voxels() {
+        let mut world = world(&bounded(), &[[12, 20, 8], [12, 21, 8]]);
+        target(&mut world, "archer", [2.5, 20.9, 8.5]);
+        let first = target(&mut world, "first", [6.5, 20.9, 8.5]);
+        let second = target(&mut world, "second", [9.5, 20.9, 8.5]);
+
+        let bolt = ProjectileBuilder::new(&Vec3(60.0, 0.0, 0.0))
+            .gravity_multiplier(0.0)
+            .pierce(2)
+            .bounces(1)
+            .shooter("archer")
+            .build();
+        world.spawn_projectile("bolt", &Vec3(2.5, 21.0, 8.5), bolt);
+
+        fly(&mut world, 30, |hits| hits.len() >= 3);
+        let hits = world.read_resource::<ProjectileHits>();
+        let targets: Vec<_> = hits
+            .0
+            .iter()
+            .map(|hit| (&hit.target, hit.stopped))
+            .collect();
+
+        // Fired from inside the archer, it never hits them; each pierced
+        // target is hit once, even as the bolt flies back past it.
+        assert_eq!(
+            targets,
+            vec![
+                (
+                    &ProjectileTarget::Entity {
+                        entity: first,
+                        id: Some("first".to_owned()),
+                    },
+                    false
+                ),
+                (
+                    &ProjectileTarget::Entity {
+                        entity: second,
+                        id: Some("second".to_owned()),
+                    },
+                    false
+                ),
+                (
+                    &ProjectileTarget::Voxel {
+                        voxel: Vec3(12, 21, 8),
+                        normal: Vec3(-1, 0, 0),
+                    },
+                    false
+                ),
+            ]
+        );
+        assert_eq!(hits.0[2].velocity, Vec3(-30.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn projectiles_hit_long_bodies_far_from_their_centers() {
+        let mut world = world(&bounded(), &[]);
+        let boat = target(&mut world, "boat", [8.5, 21.0, 12.5]);
+        world
+            .write_component::<RigidBodyComp>()
+            .get_mut(boat)
+            .unwrap()
+            .0
+            .shape = Some(CompoundShape::new().with_box([0.0, 0.0, 0.0], [0.4, 0.4, 4.5]));
+
+        let bolt = ProjectileBuilder::new(&Vec3(60.0, 0.0, 0.0))
+            .gravity_multiplier(0.0)
+            .build();
+        world.spawn_projectile("bolt", &Vec3(2.5, 21.0, 8.5), bolt);
+
+        // The hull's tip crosses the path four blocks from the boat's center.
+        fly(&mut world, 10, |hits| !hits.is_empty());
+        let hits = world.read_resource::<ProjectileHits>();
+        assert!(matches!(
+            hits.0[0].target,
+            ProjectileTarget::Entity { entity, .. } if entity == boat
+        ));
+    }
+
+    #[test]
+    fn rewound_projectiles_hit_targets_where_they_were() {
+        let config = WorldConfig::new()
+            .min_chunk([0, 0])
+            .max_chunk([0, 0])
+            .fixed_timestep(Some(FixedStepConfig {
+                hz: 60,
+                max_catchup_steps: 5,
+                seed: 1,
+            }))
+            .lag_comp(Some(LagCompConfig {
+                window_ms: 300,
+                max_ticks: 18,
+                dly_floor_ms: 0,
+                dly_ceil_ms: 250,
+            }))
+            .build();
+        let mut world = world(&config, &[]);
+
+        let runner = target(&mut world, "runner", [8.5, 20.9, 8.5]);
+        world
+            .write_component::<RewindEligibleComp>()
+            .insert(runner, RewindEligibleComp)
+            .unwrap();
+        let tick = world.write_resource::<FixedStepState>().clock.commit_step();
+        world.record_rewind_poses(tick);
+
+        // The runner has since moved out of the line of fire.
+        world
+            .write_component::<RigidBodyComp>()
+            .get_mut(runner)
+            .unwrap()
+            .0
+            .set_position(8.5, 20.9, 14.5);
+        for _ in 0..4 {
+            world.write_resource::<FixedStepState>().clock.commit_step();
+        }
+
+        let shot = |rewind: Option<RewindAnchor>| {
+            let projectile = ProjectileBuilder::new(&Vec3(60.0, 0.0, 0.0)).gravity_multiplier(0.0);
+            match rewind {
+                Some(anchor) => projectile.rewind(anchor),
+                None => projectile,
+            }
+            .build()
+        };
+        world.spawn_projectile("bolt", &Vec3(2.5, 21.0, 8.5), shot(None));
+        world.spawn_projectile(
+            "bolt",
+            &Vec3(2.5, 21.0, 8.5),
+            shot(Some(RewindAnchor {
+                born_tick: tick + 4,
+                lag_rewind: 4,
+            })),
+        );
+
+        fly(&mut world, 20, |_| false);
+        let hits = world.read_resource::<ProjectileHits>();
+        assert_eq!(hits.0.len(), 1, "only the rewound shot hits: {:?}", hits.0);
+        assert_eq!(hits.0[0].rewound_tick, Some(tick));
+        assert!(matches!(
+            hits.0[0].target,
+            ProjectileTarget::Entity { entity, .. } if entity == runner
+        ));
+    }
+}
@@ -4,7 +4,10 @@ use hashbrown::{HashMap, HashSet};
 use log::info;
 use serde::{Deserialize, Serialize};
 
-use crate::{BlockFace, Vec3, VoxelAccess, VoxelUpdate};
+use crate::{
+    apply_emissives, BlockFace, BlockModels, FluidInteraction, ModelError, Vec3, VoxelAccess,
+    VoxelUpdate,
+};
 
 use super::voxels::Block;
 
@@ -36,6 +39,17 @@ impl Default for UV {
     }
 }
 
+impl UV {
+    pub fn to_mesher_uv(&self) -> voxelize_mesher::UV {
+        voxelize_mesher::UV {
+            start_u: self.start_u,
+            end_u: self.end_u,
+            start_v: self.start_v,
+            end_v: self.end_v,
+        }
+    }
+}
+
 /// Compact per-block facts consulted for every voxel in the lighting hot
 /// paths. Indexed by block ID so `Lights` can skip the full `Block` hashmap
 /// lookup, rotation decode, and dynamic-pattern probing for the overwhelming
@@ -84,6 +98,13 @@ pub struct Registry {
     /// The block waterlogged voxels are filled with, claimed by whichever
     /// block sets `is_waterlogging_fluid`.
     waterlogging_fluid_id: Option<u32>,
+
+    /// What fluids turn into when they meet other blocks, in the order the
+    /// interactions were added.
+    fluid_interactions: Vec<FluidInteraction>,
+
+    /// Map of tag -> IDs of the blocks carrying it.
+    block_tags: HashMap<String, HashSet<u32>>,
 }
 
 impl Registry {
@@ -121,6 +142,41 @@ impl Registry {
         self.record_block(&air);
     }
 
+    /// Compile the block model of every block built with
+    /// [`BlockBuilder::model_name`](crate::BlockBuilder::model_name) from
+    /// `models`, replacing its faces and bounding boxes. Returns how many
+    /// blocks were given their model. Call this before [`Registry::generate`],
+    /// which lays the compiled faces out in the atlas.
+    pub fn load_models(&mut self, models: &BlockModels) -> Result<usize, ModelError> {
+        let mut blocks: Vec<Block> = self
+            .blocks_by_id
+            .values()
+            .filter(|block| block.model.is_some())
+            .cloned()
+            .collect();
+        blocks.sort_by_key(|block| block.id);
+
+        for block in blocks.iter_mut() {
+            let Some(source) = block.model.clone() else {
+                continue;
+            };
+            let geometry = models.compile(&source.name)?;
+
+            block.faces = geometry
+                .faces
+                .iter()
+                .map(BlockFace::from_mesher_face)
+                .collect();
+            block.aabbs = geometry.aabbs;
+            apply_emissives(&mut block.faces, source.emissive, &source.face_emissives);
+
+            self.textures.retain(|(id, _, _)| *id != block.id);
+            self.record_block(block);
+        }
+
+        Ok(blocks.len())
+    }
+
     /// Generate the UV coordinates of the blocks. Call this before the server starts!
     pub fn generate(&mut self) {
         let all_blocks = self.blocks_by_id.values_mut().collect::<Vec<_>>();
@@ -140,6 +196,14 @@ impl Registry {
                     ungrouped_faces += 1;
                 }
             }
+
+            // Textures swapped in by texture rules get slots of their own,
+            // shared with any texture group of the same name.
+            for rule in block.texture_rules.iter() {
+                for texture in rule.variants.iter().chain(rule.connected.iter()) {
+                    texture_groups.insert(texture.name.clone());
+                }
+            }
         }
 
         let total_slots = texture_groups.len() + ungrouped_faces;
@@ -204,6 +268,14 @@ impl Registry {
                 }
             }
 
+            for rule in block.texture_rules.iter_mut() {
+                for texture in rule.textures_mut() {
+                    if let Some(uv) = group_uvs.get(&texture.name) {
+                        texture.range = uv.to_mesher_uv();
+                    }
+                }
+            }
+
             if let Some(dynamic_patterns) = block.dynamic_patterns.as_mut() {
                 for pattern in dynamic_patterns {
                     for part in &mut pattern.parts {
@@ -226,6 +298,7 @@ impl Registry {
                 .unwrap();
             block_by_name.faces = block.faces.clone();
             block_by_name.dynamic_patterns = block.dynamic_patterns.clone();
+            block_by_name.texture_rules = block.texture_rules.clone();
         });
     }
 
@@ -378,6 +451,35 @@ impl Registry {
         self.waterlogging_fluid_id
     }
 
+    /// Add a row to the fluid interaction table. Interactions for the same
+    /// fluid are tried in the order they were added.
+    pub fn add_fluid_interaction(&mut self, interaction: FluidInteraction) {
+        self.fluid_interactions.push(interaction);
+    }
+
+    /// The interactions a fluid's tick evaluates, in the order they were added.
+    pub fn fluid_interactions(&self, fluid: u32) -> impl Iterator<Item = &FluidInteraction> {
+        self.fluid_interactions
+            .iter()
+            .filter(move |interaction| interaction.fluid == fluid)
+    }
+
+    /// Tag a block by id, so it can be matched as a group, e.g. by a
+    /// [`crate::FluidContact::Tag`].
+    pub fn tag_block(&mut self, id: u32, tag: &str) {
+        self.block_tags
+            .entry(tag.to_owned())
+            .or_default()
+            .insert(id);
+    }
+
+    /// Check if block carries a tag by id.
+    pub fn has_tag(&self, id: u32, tag: &str) -> bool {
+        self.block_tags
+            .get(tag)
+            .is_some_and(|ids| ids.contains(&id))
+    }
+
     /// Build waterlogging rules from registered blocks, or `None` when no
     /// waterlogging fluid is declared.
     pub fn waterlogging_rules(&self) -> Option<super::voxels::WaterloggingRules> {
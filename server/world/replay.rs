@@ -0,0 +1,1129 @@
+//! Recording a world's traffic and serving it back as a replay.
+//!
+//! A recording captures what the world tells everyone, not what any one client
+//! was sent: chat, events, join/leave and the other non-direct broadcasts,
+//! plus world-wide feeds of peer state, entity state, and applied voxel
+//! updates (the interest-filtered per-client copies are left out). Every frame
+//! is the encoded [`Message`] tagged with the world's dispatch tick, after a
+//! header holding the generated chunks and clock at the moment recording
+//! started.
+//!
+//! File layout: the magic `VXREPLAY`, a little-endian `u32` version, then a
+//! varint-length-prefixed JSON [`ReplayHeader`], then frames of
+//! `varint(tick delta) varint(length) bytes`. A file cut off mid-frame (a
+//! crashed server) still opens; playback ends at the last whole frame.
+//!
+//! A replay is played in a world created for it with [`OpenReplay`]. That
+//! world is read-only for its viewers: it streams chunks and tracks their
+//! position like any world, but ignores edits, chat and events, and only
+//! answers the `vox-builtin:replay-*` methods, which pause, resume, seek and
+//! change the speed of playback. Voxel updates are replayed through the
+//! world's own update pipeline, so seeking backwards undoes them the same way.
+//! The replay world still runs its own simulation, so give it a config
+//! without active voxel updaters or spawners if playback should be exact.
+//!
+//! [`OpenReplay`]: crate::OpenReplay
+
+use std::collections::BTreeMap;
+use std::io::{self, BufWriter, Write as _};
+use std::path::Path;
+
+use hashbrown::HashSet;
+use prost::encoding::{decode_varint, encode_varint};
+
+use super::*;
+use crate::decode_message;
+
+/// First bytes of every replay file.
+pub const REPLAY_MAGIC: &[u8; 8] = b"VXREPLAY";
+
+/// Bumped whenever the replay file layout changes incompatibly.
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Resume playback.
+pub const REPLAY_PLAY_METHOD: &str = "vox-builtin:replay-play";
+/// Pause playback.
+pub const REPLAY_PAUSE_METHOD: &str = "vox-builtin:replay-pause";
+/// Jump to `{ "tick": n }`, a recorded tick between the status' `startTick`
+/// and `endTick`.
+pub const REPLAY_SEEK_METHOD: &str = "vox-builtin:replay-seek";
+/// Play at `{ "speed": x }` recorded ticks per world tick.
+pub const REPLAY_SPEED_METHOD: &str = "vox-builtin:replay-speed";
+/// Sent to viewers with a [`ReplayStatus`] when they join and whenever
+/// playback changes.
+pub const REPLAY_STATUS_METHOD: &str = "vox-builtin:replay-status";
+
+/// Playback speed bounds, in recorded ticks per world tick.
+pub const MIN_REPLAY_SPEED: f32 = 0.1;
+pub const MAX_REPLAY_SPEED: f32 = 16.0;
+
+/// Methods a replay world answers; every other inbound method is dropped.
+const REPLAY_VIEWER_METHODS: [&str; 6] = [
+    REPLAY_PLAY_METHOD,
+    REPLAY_PAUSE_METHOD,
+    REPLAY_SEEK_METHOD,
+    REPLAY_SPEED_METHOD,
+    "vox-builtin:get-stats",
+    "vox-builtin:ping",
+];
+
+/// Why a replay file could not be read.
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("could not read replay: {0}")]
+    Io(#[from] io::Error),
+    #[error("not a replay file")]
+    NotAReplay,
+    #[error("replay version {0} is not supported (expected {REPLAY_VERSION})")]
+    UnsupportedVersion(u32),
+    #[error("corrupt replay header: {0}")]
+    Corrupt(String),
+}
+
+/// The state a recording starts from.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayHeader {
+    /// Name of the recorded world.
+    pub world: String,
+    /// Shape of the captured chunks; the playback world must match it.
+    pub chunk_size: usize,
+    pub max_height: usize,
+    /// Dispatch tick recording started at; frame ticks count on from it.
+    pub start_tick: u64,
+    pub stats: StatsJson,
+    pub chunks: Vec<ChunkSnapshot>,
+}
+
+/// One recorded message.
+pub struct ReplayFrame {
+    pub tick: u64,
+    pub data: Vec<u8>,
+}
+
+/// A replay file read back into memory.
+pub struct Replay {
+    pub header: ReplayHeader,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    /// Read a replay file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ReplayError> {
+        Self::decode(&fs::read(path)?)
+    }
+
+    /// Parse the bytes of a replay file.
+    pub fn decode(bytes: &[u8]) -> Result<Self, ReplayError> {
+        if bytes.len() < REPLAY_MAGIC.len() + 4 || &bytes[..REPLAY_MAGIC.len()] != REPLAY_MAGIC {
+            return Err(ReplayError::NotAReplay);
+        }
+        let mut rest = &bytes[REPLAY_MAGIC.len()..];
+        let version = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        rest = &rest[4..];
+
+        let header_len = decode_varint(&mut rest)
+            .map_err(|error| ReplayError::Corrupt(error.to_string()))?
+            as usize;
+        if header_len > rest.len() {
+            return Err(ReplayError::Corrupt("truncated header".to_owned()));
+        }
+        let header: ReplayHeader = serde_json::from_slice(&rest[..header_len])
+            .map_err(|error| ReplayError::Corrupt(error.to_string()))?;
+        rest = &rest[header_len..];
+
+        let mut frames = Vec::new();
+        let mut tick = header.start_tick;
+        while !rest.is_empty() {
+            let (Ok(delta), Ok(len)) = (decode_varint(&mut rest), decode_varint(&mut rest)) else {
+                break;
+            };
+            let len = len as usize;
+            if len > rest.len() {
+                break;
+            }
+            tick = tick
+                .checked_add(delta)
+                .ok_or_else(|| ReplayError::Corrupt("frame tick overflows".to_owned()))?;
+            frames.push(ReplayFrame {
+                tick,
+                data: rest[..len].to_vec(),
+            });
+            rest = &rest[len..];
+        }
+        if !rest.is_empty() {
+            warn!(
+                "Replay of {:?} ends in a partial frame; playing the {} whole frames before it",
+                header.world,
+                frames.len()
+            );
+        }
+
+        Ok(Self { header, frames })
+    }
+
+    /// Tick of the last frame, or the start tick of an empty recording.
+    pub fn end_tick(&self) -> u64 {
+        self.frames
+            .last()
+            .map_or(self.header.start_tick, |frame| frame.tick)
+    }
+}
+
+/// Fold an entity UPDATE into the metadata held for the entity. An update may
+/// carry only the keys that changed or only a motion payload, so it is merged
+/// into what is held rather than replacing it.
+fn merge_entity_update(held: &str, metadata: &str, motion: Option<&[u8]>) -> String {
+    let mut map: serde_json::Map<String, Value> = serde_json::from_str(held).unwrap_or_default();
+    if let Ok(Value::Object(changes)) = serde_json::from_str::<Value>(metadata) {
+        map.extend(changes);
+    }
+
+    if let Some(sample) = motion.and_then(decode_motion) {
+        map.insert("position".to_owned(), json!(sample.position));
+        if let Some(direction) = sample.direction {
+            map.insert("direction".to_owned(), json!(direction));
+        }
+        if let Some((in_fluid, ratio)) = sample.rigid_body {
+            map.insert(
+                "rigidBody".to_owned(),
+                json!({ "isInFluid": in_fluid, "fluidRatio": ratio }),
+            );
+        }
+        if let (Some(position), Some(Value::Object(target))) =
+            (sample.target, map.get_mut("target"))
+        {
+            target.insert("position".to_owned(), json!(position));
+        }
+    }
+
+    Value::Object(map).to_string()
+}
+
+struct ReplayWriter {
+    file: BufWriter<File>,
+    last_tick: u64,
+    frames: usize,
+}
+
+impl ReplayWriter {
+    fn create(path: &Path, header: &ReplayHeader) -> io::Result<Self> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_vec(header)?;
+
+        let mut bytes = Vec::with_capacity(json.len() + 24);
+        bytes.extend_from_slice(REPLAY_MAGIC);
+        bytes.extend_from_slice(&REPLAY_VERSION.to_le_bytes());
+        encode_varint(json.len() as u64, &mut bytes);
+        bytes.extend_from_slice(&json);
+
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&bytes)?;
+
+        Ok(Self {
+            file,
+            last_tick: header.start_tick,
+            frames: 0,
+        })
+    }
+
+    fn write_frame(&mut self, tick: u64, data: &[u8]) -> io::Result<()> {
+        let mut prefix = Vec::with_capacity(20);
+        encode_varint(tick.saturating_sub(self.last_tick), &mut prefix);
+        encode_varint(data.len() as u64, &mut prefix);
+        self.file.write_all(&prefix)?;
+        self.file.write_all(data)?;
+        self.last_tick = self.last_tick.max(tick);
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+/// The world's recording, if one is running. Systems that produce world-wide
+/// traffic append to it; see [`World::start_recording`].
+#[derive(Default)]
+pub struct ReplayRecorder {
+    writer: Option<ReplayWriter>,
+}
+
+impl ReplayRecorder {
+    pub fn is_recording(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    /// Append an encoded message. A failed write ends the recording rather
+    /// than leaving a file with a hole in it.
+    pub fn record(&mut self, tick: u64, data: &[u8]) {
+        let Some(writer) = self.writer.as_mut() else {
+            return;
+        };
+        if let Err(error) = writer.write_frame(tick, data) {
+            error!("Replay recording stopped: {}", error);
+            self.writer = None;
+        }
+    }
+
+    /// Encode and append a message, if recording.
+    pub fn record_message(&mut self, tick: u64, message: &Message) {
+        if self.is_recording() {
+            self.record(tick, &encode_message(message));
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<usize> {
+        match self.writer.take() {
+            Some(mut writer) => {
+                writer.file.flush()?;
+                Ok(writer.frames)
+            }
+            None => Ok(0),
+        }
+    }
+}
+
+/// Where playback is, as reported to viewers.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayStatus {
+    /// Name of the recorded world.
+    pub world: String,
+    pub start_tick: u64,
+    pub end_tick: u64,
+    pub tick: u64,
+    pub playing: bool,
+    pub speed: f32,
+}
+
+/// A voxel as it was before a replayed update, so a backwards seek can put
+/// it back.
+struct VoxelUndo {
+    frame: usize,
+    voxel: Vec3<i32>,
+    previous: u32,
+}
+
+/// Playback state of a replay world. Empty (and inert) in every other world.
+#[derive(Default)]
+pub struct ReplayPlayer {
+    replay: Option<Replay>,
+    /// Index of the next frame to play.
+    cursor: usize,
+    /// Recorded tick playback has reached.
+    position: f64,
+    playing: bool,
+    speed: f32,
+    /// Entities and peers as of `cursor`, for viewers joining mid-way and
+    /// for seeks.
+    entities: BTreeMap<String, (String, String)>,
+    peers: BTreeMap<String, PeerProtocol>,
+    viewers: HashSet<String>,
+    undo: Vec<VoxelUndo>,
+    /// Latest replayed value per voxel; updates are staged, so the chunks do
+    /// not show them until the updating system runs.
+    replayed_voxels: HashMap<Vec3<i32>, u32>,
+}
+
+impl ReplayPlayer {
+    pub fn is_loaded(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    pub fn status(&self) -> Option<ReplayStatus> {
+        let replay = self.replay.as_ref()?;
+        Some(ReplayStatus {
+            world: replay.header.world.clone(),
+            start_tick: replay.header.start_tick,
+            end_tick: replay.end_tick(),
+            tick: self.position as u64,
+            playing: self.playing,
+            speed: self.speed,
+        })
+    }
+
+    /// Entity id -> (type, metadata) as of the playback position.
+    pub fn entities(&self) -> &BTreeMap<String, (String, String)> {
+        &self.entities
+    }
+}
+
+/// Load a replay into this (freshly created) world and start playing it.
+#[derive(ActixMessage)]
+#[rtype(result = "()")]
+pub(crate) struct LoadReplay(pub Replay);
+
+/// Start recording a live world to a replay file; see
+/// [`World::start_recording`]. Send it to a [`WorldHandle`]'s address.
+///
+/// [`WorldHandle`]: crate::WorldHandle
+#[derive(ActixMessage)]
+#[rtype(result = "io::Result<()>")]
+pub struct StartRecording {
+    pub path: PathBuf,
+}
+
+/// Finish a world's running recording; see [`World::stop_recording`].
+#[derive(ActixMessage)]
+#[rtype(result = "io::Result<()>")]
+pub struct StopRecording;
+
+#[derive(Deserialize)]
+struct ReplaySeekPayload {
+    tick: u64,
+}
+
+#[derive(Deserialize)]
+struct ReplaySpeedPayload {
+    speed: f32,
+}
+
+/// What applying a frame should do besides advancing the entity and peer
+/// state.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FrameEffect {
+    /// Apply voxel updates and send the frame to viewers.
+    Play,
+    /// Apply voxel updates; viewers get a fresh snapshot afterwards.
+    FastForward,
+    /// Entity and peer state only.
+    StateOnly,
+}
+
+impl World {
+    /// Start recording this world's traffic to `path`, replacing any recording
+    /// already running. The file opens with the world's generated chunks,
+    /// clock, entities and connected peers.
+    pub fn start_recording<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        self.stop_recording()?;
+
+        let tick = self.stats().dispatch_count();
+        let header = {
+            let config = self.config();
+            ReplayHeader {
+                world: self.name.clone(),
+                chunk_size: config.chunk_size,
+                max_height: config.max_height,
+                start_tick: tick,
+                stats: self.stats().get_stats(),
+                chunks: self.chunks().snapshot(self.stats().tick),
+            }
+        };
+        let mut writer = ReplayWriter::create(path.as_ref(), &header)?;
+
+        let entities: Vec<EntityProtocol> = self
+            .bookkeeping()
+            .entities
+            .iter()
+            .map(|(id, (etype, _, metadata, _))| EntityProtocol {
+                operation: EntityOperation::Create,
+                id: id.to_owned(),
+                r#type: etype.to_owned(),
+                metadata: Some(metadata.to_owned()),
+                motion: None,
+            })
+            .collect();
+        let peers: Vec<PeerProtocol> = {
+            let ids = self.read_component::<IDComp>();
+            let names = self.read_component::<NameComp>();
+            let metadatas = self.read_component::<MetadataComp>();
+            let flags = self.read_component::<ClientFlag>();
+            (&ids, &names, &metadatas, &flags)
+                .join()
+                .map(|(id, name, metadata, _)| PeerProtocol {
+                    id: id.0.to_owned(),
+                    username: name.0.to_owned(),
+                    metadata: metadata.to_string(),
+                })
+                .collect()
+        };
+
+        if !entities.is_empty() {
+            let message = Message::new(&MessageType::Entity)
+                .entities(&entities)
+                .tick(tick)
+                .build();
+            writer.write_frame(tick, &encode_message(&message))?;
+        }
+        if !peers.is_empty() {
+            let message = Message::new(&MessageType::Peer)
+                .peers(&peers)
+                .tick(tick)
+                .build();
+            writer.write_frame(tick, &encode_message(&message))?;
+        }
+
+        info!(
+            "World {:?} is recording a replay to {:?} ({} chunks, {} entities, {} peers)",
+            self.name,
+            path.as_ref(),
+            header.chunks.len(),
+            entities.len(),
+            peers.len()
+        );
+        self.write_resource::<ReplayRecorder>().writer = Some(writer);
+
+        Ok(())
+    }
+
+    /// Finish the running recording, if any, flushing it to disk.
+    pub fn stop_recording(&mut self) -> io::Result<()> {
+        if !self.is_recording() {
+            return Ok(());
+        }
+        let frames = self.write_resource::<ReplayRecorder>().finish()?;
+        info!(
+            "World {:?} finished recording a replay ({} frames)",
+            self.name, frames
+        );
+        Ok(())
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.read_resource::<ReplayRecorder>().is_recording()
+    }
+
+    /// Whether this world is playing a replay (and is read-only to clients).
+    pub fn is_replay(&self) -> bool {
+        self.read_resource::<ReplayPlayer>().is_loaded()
+    }
+
+    /// Where playback is, when this world is playing a replay.
+    pub fn replay_status(&self) -> Option<ReplayStatus> {
+        self.read_resource::<ReplayPlayer>().status()
+    }
+
+    /// Restore a replay's starting chunks and clock, then start playing it
+    /// from the beginning at normal speed.
+    pub fn load_replay(&mut self, mut replay: Replay) {
+        // Playback applies the voxel updates those ticks made as recorded
+        // frames; running the ticks as well would apply them twice.
+        let chunks: Vec<ChunkSnapshot> = std::mem::take(&mut replay.header.chunks)
+            .into_iter()
+            .map(ChunkSnapshot::without_scheduled_ticks)
+            .collect();
+        let chunk_count = chunks.len();
+        let restored_chunks = self.restore_chunks(chunks);
+        self.stats_mut().time = replay.header.stats.time;
+
+        info!(
+            "World {:?} is playing a replay of {:?}: {}/{} chunks, {} frames over {} ticks",
+            self.name,
+            replay.header.world,
+            restored_chunks,
+            chunk_count,
+            replay.frames.len(),
+            replay.end_tick() - replay.header.start_tick
+        );
+
+        *self.write_resource::<ReplayPlayer>() = ReplayPlayer {
+            position: replay.header.start_tick as f64,
+            replay: Some(replay),
+            playing: true,
+            speed: 1.0,
+            ..Default::default()
+        };
+    }
+
+    /// Resume or pause playback.
+    pub fn set_replay_playing(&mut self, playing: bool) {
+        let mut player = self.write_resource::<ReplayPlayer>();
+        if !player.is_loaded() {
+            return;
+        }
+        // Playing from the end starts over.
+        if playing && player.cursor >= player.replay.as_ref().map_or(0, |r| r.frames.len()) {
+            drop(player);
+            let start = self.replay_status().map_or(0, |status| status.start_tick);
+            self.seek_replay(start);
+            player = self.write_resource::<ReplayPlayer>();
+        }
+        player.playing = playing;
+        drop(player);
+        self.broadcast_replay_status(ClientFilter::All);
+    }
+
+    /// Set the playback speed, clamped to
+    /// [`MIN_REPLAY_SPEED`]..=[`MAX_REPLAY_SPEED`].
+    pub fn set_replay_speed(&mut self, speed: f32) {
+        if !speed.is_finite() {
+            return;
+        }
+        {
+            let mut player = self.write_resource::<ReplayPlayer>();
+            if !player.is_loaded() {
+                return;
+            }
+            player.speed = speed.clamp(MIN_REPLAY_SPEED, MAX_REPLAY_SPEED);
+        }
+        self.broadcast_replay_status(ClientFilter::All);
+    }
+
+    /// Jump playback to a recorded tick. Viewers drop what they were shown
+    /// and receive the entities and peers present at `tick`; voxel edits made
+    /// between the old and new position are applied or undone.
+    pub fn seek_replay(&mut self, tick: u64) {
+        let mut player = std::mem::take(&mut *self.write_resource::<ReplayPlayer>());
+        let Some(replay) = player.replay.take() else {
+            return;
+        };
+        let tick = tick.clamp(replay.header.start_tick, replay.end_tick());
+
+        self.send_replay_reset(&player, ClientFilter::All);
+
+        if (tick as f64) < player.position {
+            let cut = replay.frames.partition_point(|frame| frame.tick <= tick);
+            let keep = player.undo.partition_point(|undo| undo.frame < cut);
+            let undone: Vec<VoxelUndo> = player.undo.drain(keep..).collect();
+            {
+                let mut chunks = self.chunks_mut();
+                for undo in undone.into_iter().rev() {
+                    chunks.update_voxel(&undo.voxel, undo.previous);
+                    player.replayed_voxels.insert(undo.voxel, undo.previous);
+                }
+            }
+
+            player.entities.clear();
+            player.peers.clear();
+            player.cursor = 0;
+            while player.cursor < cut {
+                self.apply_replay_frame(&mut player, &replay, FrameEffect::StateOnly);
+                player.cursor += 1;
+            }
+        } else {
+            while player.cursor < replay.frames.len() && replay.frames[player.cursor].tick <= tick {
+                self.apply_replay_frame(&mut player, &replay, FrameEffect::FastForward);
+                player.cursor += 1;
+            }
+        }
+        player.position = tick as f64;
+
+        self.send_replay_snapshot(&player, ClientFilter::All);
+
+        player.replay = Some(replay);
+        *self.write_resource::<ReplayPlayer>() = player;
+        self.broadcast_replay_status(ClientFilter::All);
+    }
+
+    /// Advance playback by one world tick. Called at the start of every tick,
+    /// before the dispatch, so replayed traffic ships on the same tick.
+    pub(crate) fn advance_replay(&mut self) {
+        if !self.is_replay() {
+            return;
+        }
+        let mut player = std::mem::take(&mut *self.write_resource::<ReplayPlayer>());
+        let replay = player.replay.take().unwrap();
+
+        // Viewers joining mid-way are caught up to the playback position.
+        let viewers: Vec<String> = self.clients().keys().cloned().collect();
+        player.viewers.retain(|id| viewers.contains(id));
+        let joined: Vec<String> = viewers
+            .into_iter()
+            .filter(|id| !player.viewers.contains(id))
+            .collect();
+        for id in &joined {
+            self.send_replay_snapshot(&player, ClientFilter::Direct(id.to_owned()));
+            player.viewers.insert(id.to_owned());
+        }
+
+        let mut finished = false;
+        if player.playing {
+            player.position = (player.position + player.speed as f64).min(replay.end_tick() as f64);
+            while player.cursor < replay.frames.len()
+                && replay.frames[player.cursor].tick as f64 <= player.position
+            {
+                self.apply_replay_frame(&mut player, &replay, FrameEffect::Play);
+                player.cursor += 1;
+            }
+            if player.cursor >= replay.frames.len() {
+                player.playing = false;
+                finished = true;
+            }
+        }
+
+        player.replay = Some(replay);
+        *self.write_resource::<ReplayPlayer>() = player;
+
+        for id in joined {
+            self.broadcast_replay_status(ClientFilter::Direct(id));
+        }
+        if finished {
+            self.broadcast_replay_status(ClientFilter::All);
+        }
+    }
+
+    /// Whether a viewer's message may reach a replay world.
+    pub(crate) fn is_replay_viewer_message(message: &Message) -> bool {
+        match MessageType::try_from(message.r#type) {
+            Ok(MessageType::Peer) | Ok(MessageType::Load) | Ok(MessageType::Unload) => true,
+            Ok(MessageType::Method) => message.method.as_ref().is_some_and(|method| {
+                REPLAY_VIEWER_METHODS
+                    .iter()
+                    .any(|name| method.name.eq_ignore_ascii_case(name))
+            }),
+            _ => false,
+        }
+    }
+
+    pub(super) fn set_replay_method_handles(&mut self) {
+        self.set_method_handle(REPLAY_PLAY_METHOD, |world, _, _| {
+            world.set_replay_playing(true);
+        });
+
+        self.set_method_handle(REPLAY_PAUSE_METHOD, |world, _, _| {
+            world.set_replay_playing(false);
+        });
+
+        self.set_method_handle(
+            REPLAY_SEEK_METHOD,
+            |world, _, payload| match serde_json::from_str::<ReplaySeekPayload>(payload) {
+                Ok(payload) => world.seek_replay(payload.tick),
+                Err(error) => warn!("Invalid {} payload: {}", REPLAY_SEEK_METHOD, error),
+            },
+        );
+
+        self.set_method_handle(
+            REPLAY_SPEED_METHOD,
+            |world, _, payload| match serde_json::from_str::<ReplaySpeedPayload>(payload) {
+                Ok(payload) => world.set_replay_speed(payload.speed),
+                Err(error) => warn!("Invalid {} payload: {}", REPLAY_SPEED_METHOD, error),
+            },
+        );
+    }
+
+    fn apply_replay_frame(
+        &mut self,
+        player: &mut ReplayPlayer,
+        replay: &Replay,
+        effect: FrameEffect,
+    ) {
+        let frame = &replay.frames[player.cursor];
+        let mut message = match decode_message(&frame.data) {
+            Ok(message) => message,
+            Err(error) => {
+                warn!(
+                    "World {:?} skipped an unreadable replay frame: {}",
+                    self.name, error
+                );
+                return;
+            }
+        };
+
+        match MessageType::try_from(message.r#type) {
+            Ok(MessageType::Entity) => {
+                for entity in &message.entities {
+                    match EntityOperation::try_from(entity.operation) {
+                        Ok(EntityOperation::Create) => {
+                            player.entities.insert(
+                                entity.id.clone(),
+                                (entity.r#type.clone(), entity.metadata.clone()),
+                            );
+                        }
+                        Ok(EntityOperation::Update) => {
+                            let held = player
+                                .entities
+                                .get(&entity.id)
+                                .map_or("{}", |(_, metadata)| metadata.as_str());
+                            let metadata = merge_entity_update(
+                                held,
+                                &entity.metadata,
+                                entity.motion.as_deref(),
+                            );
+                            player
+                                .entities
+                                .insert(entity.id.clone(), (entity.r#type.clone(), metadata));
+                        }
+                        _ => {
+                            player.entities.remove(&entity.id);
+                        }
+                    }
+                }
+            }
+            Ok(MessageType::Peer) => {
+                for peer in &message.peers {
+                    player.peers.insert(
+                        peer.id.clone(),
+                        PeerProtocol {
+                            id: peer.id.clone(),
+                            username: peer.username.clone(),
+                            metadata: peer.metadata.clone(),
+                        },
+                    );
+                }
+            }
+            Ok(MessageType::Leave) => {
+                player.peers.remove(&message.text);
+            }
+            Ok(MessageType::Update) => {
+                // Replayed through the world's own update pipeline, which
+                // relights, remeshes and sends them to interested viewers.
+                if effect != FrameEffect::StateOnly {
+                    let mut chunks = self.chunks_mut();
+                    for update in &message.updates {
+                        let voxel = Vec3(update.vx, update.vy, update.vz);
+                        let previous = player
+                            .replayed_voxels
+                            .get(&voxel)
+                            .copied()
+                            .unwrap_or_else(|| chunks.get_raw_voxel(voxel.0, voxel.1, voxel.2));
+                        player.undo.push(VoxelUndo {
+                            frame: player.cursor,
+                            voxel: voxel.clone(),
+                            previous,
+                        });
+                        player.replayed_voxels.insert(voxel.clone(), update.voxel);
+                        chunks.update_voxel(&voxel, update.voxel);
+                    }
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        if effect == FrameEffect::Play {
+            // Restamped, so state ordering on the client follows playback
+            // rather than the recorded clock (which runs backwards on seeks).
+            if message.tick != 0 {
+                message.tick = self.stats().dispatch_count();
+            }
+            message.seq = 0;
+            self.write_resource::<MessageQueues>()
+                .push((message, ClientFilter::All));
+        }
+    }
+
+    /// Tell viewers to drop every replayed entity and peer they hold.
+    fn send_replay_reset(&mut self, player: &ReplayPlayer, filter: ClientFilter) {
+        if !player.entities.is_empty() {
+            let deletes: Vec<EntityProtocol> = player
+                .entities
+                .iter()
+                .map(|(id, (etype, metadata))| EntityProtocol {
+                    operation: EntityOperation::Delete,
+                    id: id.to_owned(),
+                    r#type: etype.to_owned(),
+                    metadata: Some(metadata.to_owned()),
+                    motion: None,
+                })
+                .collect();
+            self.write_resource::<MessageQueues>().push((
+                Message::new(&MessageType::Entity)
+                    .entities(&deletes)
+                    .build(),
+                filter.clone(),
+            ));
+        }
+
+        for id in player.peers.keys() {
+            self.write_resource::<MessageQueues>().push((
+                Message::new(&MessageType::Leave).text(id).build(),
+                filter.clone(),
+            ));
+        }
+    }
+
+    /// Send viewers the entities and peers present at the playback position.
+    fn send_replay_snapshot(&mut self, player: &ReplayPlayer, filter: ClientFilter) {
+        let tick = self.stats().dispatch_count();
+
+        if !player.entities.is_empty() {
+            let creates: Vec<EntityProtocol> = player
+                .entities
+                .iter()
+                .map(|(id, (etype, metadata))| EntityProtocol {
+                    operation: EntityOperation::Create,
+                    id: id.to_owned(),
+                    r#type: etype.to_owned(),
+                    metadata: Some(metadata.to_owned()),
+                    motion: None,
+                })
+                .collect();
+            self.write_resource::<MessageQueues>().push((
+                Message::new(&MessageType::Entity)
+                    .entities(&creates)
+                    .tick(tick)
+                    .build(),
+                filter.clone(),
+            ));
+        }
+
+        if !player.peers.is_empty() {
+            let peers: Vec<PeerProtocol> = player.peers.values().cloned().collect();
+            self.write_resource::<MessageQueues>().push((
+                Message::new(&MessageType::Peer)
+                    .peers(&peers)
+                    .tick(tick)
+                    .build(),
+                filter,
+            ));
+        }
+    }
+
+    fn broadcast_replay_status(&mut self, filter: ClientFilter) {
+        let Some(status) = self.replay_status() else {
+            return;
+        };
+        self.write_resource::<MessageQueues>().push((
+            Message::new(&MessageType::Method)
+                .method(MethodProtocol {
+                    name: REPLAY_STATUS_METHOD.to_owned(),
+                    payload: serde_json::to_string(&status).unwrap(),
+                })
+                .build(),
+            filter,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UpdateProtocol;
+
+    fn small_world(name: &str) -> World {
+        let config = WorldConfig::new()
+            .chunk_size(8)
+            .max_height(16)
+            .min_chunk([-1, -1])
+            .max_chunk([1, 1])
+            .build();
+        let mut world = World::new(name, &config);
+        world.ecs_mut().insert(Registry::new());
+        world
+    }
+
+    /// A world with one ready chunk whose voxel (3, 5, 10) is 7.
+    fn world_with_chunk(name: &str) -> World {
+        let mut world = small_world(name);
+        {
+            let options = ChunkOptions {
+                size: 8,
+                max_height: 16,
+                sub_chunks: 1,
+            };
+            let mut chunk = Chunk::new("ready", 0, 1, &options);
+            chunk.set_raw_voxel(3, 5, 10, 7);
+            chunk.status = ChunkStatus::Ready;
+            world.chunks_mut().renew(chunk, ChunkRenewal::Full);
+        }
+        world
+    }
+
+    fn entity_frame(tick: u64, operation: EntityOperation) -> ReplayFrame {
+        let message = Message::new(&MessageType::Entity)
+            .entities(&[EntityProtocol {
+                operation,
+                id: "cow".to_owned(),
+                r#type: "cow".to_owned(),
+                metadata: Some("{}".to_owned()),
+                motion: None,
+            }])
+            .tick(tick)
+            .build();
+        ReplayFrame {
+            tick,
+            data: encode_message(&message),
+        }
+    }
+
+    fn update_frame(tick: u64, voxel: u32) -> ReplayFrame {
+        let message = Message::new(&MessageType::Update)
+            .updates(&[UpdateProtocol {
+                vx: 3,
+                vy: 5,
+                vz: 10,
+                voxel,
+                light: 0,
+            }])
+            .tick(tick)
+            .build();
+        ReplayFrame {
+            tick,
+            data: encode_message(&message),
+        }
+    }
+
+    #[test]
+    fn recordings_round_trip_and_tolerate_a_cut_off_tail() {
+        let path = std::env::temp_dir()
+            .join(format!("voxelize-replay-test-{}", nanoid!()))
+            .join("recorded.vxr");
+
+        let mut world = world_with_chunk("recorded");
+        world.stats_mut().time = 420.0;
+        world.start_recording(&path).unwrap();
+        assert!(world.is_recording());
+        {
+            let chat = Message::new(&MessageType::Chat).text("hello").build();
+            let mut recorder = world.write_resource::<ReplayRecorder>();
+            recorder.record_message(3, &chat);
+            recorder.record_message(8, &chat);
+        }
+        world.stop_recording().unwrap();
+        assert!(!world.is_recording());
+
+        let replay = Replay::open(&path).unwrap();
+        assert_eq!(replay.header.world, "recorded");
+        assert_eq!(replay.header.chunks.len(), 1);
+        assert_eq!(replay.header.stats.time, 420.0);
+        let ticks: Vec<u64> = replay.frames.iter().map(|frame| frame.tick).collect();
+        assert_eq!(ticks, vec![3, 8]);
+        assert_eq!(
+            decode_message(&replay.frames[1].data).unwrap().text,
+            "hello"
+        );
+
+        let bytes = fs::read(&path).unwrap();
+        let cut = Replay::decode(&bytes[..bytes.len() - 2]).unwrap();
+        assert_eq!(cut.frames.len(), 1);
+        assert_eq!(cut.end_tick(), 3);
+
+        assert!(matches!(
+            Replay::decode(b"not a replay at all"),
+            Err(ReplayError::NotAReplay)
+        ));
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn frame_ticks_that_overflow_are_corrupt() {
+        let header = serde_json::to_vec(&ReplayHeader {
+            world: "overflow".to_owned(),
+            chunk_size: 8,
+            max_height: 16,
+            start_tick: u64::MAX - 1,
+            stats: small_world("overflow").stats().get_stats(),
+            chunks: vec![],
+        })
+        .unwrap();
+
+        let mut bytes = REPLAY_MAGIC.to_vec();
+        bytes.extend_from_slice(&REPLAY_VERSION.to_le_bytes());
+        encode_varint(header.len() as u64, &mut bytes);
+        bytes.extend_from_slice(&header);
+        encode_varint(5, &mut bytes);
+        encode_varint(0, &mut bytes);
+
+        assert!(matches!(
+            Replay::decode(&bytes),
+            Err(ReplayError::Corrupt(_))
+        ));
+    }
+
+    #[test]
+    fn motion_only_updates_merge_into_the_held_metadata() {
+        let held = json!({
+            "position": [0.0, 0.0, 0.0],
+            "health": 7,
+            "target": { "id": "abc", "position": [0.0, 0.0, 0.0] },
+        })
+        .to_string();
+        let motion = QuantizedMotion::from_sample(&MotionSample {
+            position: [1.0, 2.0, 3.0],
+            direction: None,
+            rigid_body: Some((true, 0.5)),
+            target: Some([4.0, 5.0, 6.0]),
+        })
+        .encode();
+
+        let merged: Value =
+            serde_json::from_str(&merge_entity_update(&held, "", Some(&motion))).unwrap();
+        assert_eq!(merged["health"], json!(7));
+        assert_eq!(merged["position"], json!([1.0, 2.0, 3.0]));
+        assert_eq!(merged["rigidBody"]["isInFluid"], json!(true));
+        assert_eq!(merged["target"]["id"], json!("abc"));
+        assert_eq!(merged["target"]["position"], json!([4.0, 5.0, 6.0]));
+
+        let merged: Value =
+            serde_json::from_str(&merge_entity_update(&held, r#"{"health":3}"#, None)).unwrap();
+        assert_eq!(merged["health"], json!(3));
+        assert_eq!(merged["position"], json!([0.0, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn playback_tracks_state_and_seeking_back_undoes_voxel_edits() {
+        let source = world_with_chunk("recorded");
+        let replay = Replay {
+            header: ReplayHeader {
+                world: "recorded".to_owned(),
+                chunk_size: 8,
+                max_height: 16,
+                start_tick: 10,
+                stats: source.stats().get_stats(),
+                chunks: source.chunks().snapshot(source.stats().tick),
+            },
+            frames: vec![
+                entity_frame(11, EntityOperation::Create),
+                update_frame(12, 9),
+                entity_frame(13, EntityOperation::Delete),
+            ],
+        };
+
+        let mut world = small_world("theater");
+        world.load_replay(replay);
+        assert!(world.is_replay());
+        assert_eq!(world.chunks().get_raw_voxel(3, 5, 10), 7);
+
+        world.advance_replay();
+        assert!(world
+            .read_resource::<ReplayPlayer>()
+            .entities()
+            .contains_key("cow"));
+
+        world.advance_replay();
+        assert_eq!(
+            world.chunks().updates_staging.get(&Vec3(3, 5, 10)),
+            Some(&9)
+        );
+
+        world.advance_replay();
+        assert!(world.read_resource::<ReplayPlayer>().entities().is_empty());
+        let status = world.replay_status().unwrap();
+        assert_eq!(status.tick, 13);
+        assert!(!status.playing, "playback pauses at the end");
+
+        world.seek_replay(11);
+        assert!(world
+            .read_resource::<ReplayPlayer>()
+            .entities()
+            .contains_key("cow"));
+        assert_eq!(
+            world.chunks().updates_staging.get(&Vec3(3, 5, 10)),
+            Some(&7)
+        );
+        assert_eq!(world.replay_status().unwrap().tick, 11);
+    }
+
+    #[test]
+    fn replay_worlds_only_accept_viewer_messages() {
+        let chat = Message::new(&MessageType::Chat).text("grief").build();
+        let edit = Message::new(&MessageType::Update).build();
+        let seek = Message::new(&MessageType::Method)
+            .method(MethodProtocol {
+                name: REPLAY_SEEK_METHOD.to_owned(),
+                payload: r#"{"tick":0}"#.to_owned(),
+            })
+            .build();
+        let set_time = Message::new(&MessageType::Method)
+            .method(MethodProtocol {
+                name: "vox-builtin:set-time".to_owned(),
+                payload: r#"{"time":0}"#.to_owned(),
+            })
+            .build();
+
+        assert!(!World::is_replay_viewer_message(&chat));
+        assert!(!World::is_replay_viewer_message(&edit));
+        assert!(!World::is_replay_viewer_message(&set_time));
+        assert!(World::is_replay_viewer_message(&seek));
+        assert!(World::is_replay_viewer_message(
+            &Message::new(&MessageType::Load).build()
+        ));
+    }
+}
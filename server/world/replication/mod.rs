@@ -26,6 +26,11 @@
 //! replays into `World::add_client`, which refreshes the live session instead
 //! of creating a duplicate entity).
 //!
+//! When the server enables session resume, this channel is also sequenced per
+//! client and retained in a bounded window ([`crate::SessionLogs`]), so a
+//! client whose socket drops can reconnect and have what it missed replayed
+//! instead of rejoining from scratch.
+//!
 //! ## 2. Unreliable latest-wins state (drop-old-ok)
 //!
 //! Entity motion/metadata and peer (player) positions/metadata are *samples
@@ -69,6 +74,13 @@
 //! matters most. If you need guaranteed delivery for a new kind of data, it
 //! is an *event* — route it through [`crate::MessageQueues`] instead.
 //!
+//! The channels map onto transports the same way. Reliable events always
+//! ride the WebSocket. Latest-wins state rides a client's WebRTC data channel
+//! when it has opened an UNORDERED one (see
+//! [`crate::webrtc::signaling::is_state_channel`]) and falls back to the
+//! WebSocket control lane otherwise; a dropped datagram costs nothing because
+//! the next flush carries a newer sample.
+//!
 //! The same logic applies inbound: a client's own position packets are state,
 //! so [`InboundStateBuffer`] hands them to the world for application at the
 //! start of the tick — before the system dispatch — so AI/pathfinding systems
@@ -0,0 +1,333 @@
+//! Riding: entities and clients attached to other entities (mounts, carts,
+//! seats), and bodies standing on moving platforms.
+//!
+//! Attached riders are placed by `PhysicsSystem` after their parents move,
+//! and bodies standing on a [`PlatformFlag`] entity are carried by however
+//! far it moved and turned that tick. Clients the server does not simulate
+//! are sent how far they were carried instead, as a `vox-builtin:carry`
+//! event.
+
+use super::*;
+
+/// How many riders can be stacked on one another, e.g. a saddlebag on a rider
+/// on a horse. Longer chains are not followed when placing riders.
+pub const MAX_ATTACHMENT_DEPTH: usize = 8;
+
+impl World {
+    /// Make `rider` ride `parent` at `offset` in the parent's frame (see
+    /// [`AttachmentComp`]), replacing any attachment it had. Returns false and
+    /// attaches nothing if the parent has no ID, is the rider, or already
+    /// rides the rider, directly or through others.
+    pub fn attach(&mut self, rider: Entity, parent: Entity, offset: [f32; 3]) -> bool {
+        let ids = self.ecs.read_storage::<IDComp>();
+        let Some(parent_id) = ids.get(parent).map(|id| id.0.clone()) else {
+            return false;
+        };
+
+        {
+            let entities = self.ecs.entities();
+            let attachments = self.ecs.read_storage::<AttachmentComp>();
+
+            let mut current = parent;
+            for _ in 0..=MAX_ATTACHMENT_DEPTH {
+                if current == rider {
+                    return false;
+                }
+                let Some(attachment) = attachments.get(current) else {
+                    break;
+                };
+                let Some((next, _)) = (&entities, &ids)
+                    .join()
+                    .find(|(_, id)| id.0 == attachment.parent)
+                else {
+                    break;
+                };
+                current = next;
+            }
+        }
+        drop(ids);
+
+        self.ecs
+            .write_storage::<AttachmentComp>()
+            .insert(rider, AttachmentComp::new(&parent_id, offset))
+            .is_ok()
+    }
+
+    /// Stop `rider` riding, leaving it where it is. Returns the attachment it
+    /// had, if any.
+    pub fn detach(&mut self, rider: Entity) -> Option<AttachmentComp> {
+        self.ecs.write_storage::<AttachmentComp>().remove(rider)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::RunNow;
+
+    use super::*;
+    use crate::{Chunk, EntitiesMetaSystem, PhysicsSystem};
+
+    fn world() -> World {
+        let config = WorldConfig::new()
+            .min_chunk([0, 0])
+            .max_chunk([0, 0])
+            .build();
+
+        let mut world = World::new("riding", &config);
+        world.ecs_mut().insert(Registry::new());
+        world
+            .write_resource::<Chunks>()
+            .add(Chunk::ready(0, 0, &config));
+        world
+            .write_resource::<ChunkInterests>()
+            .add("watcher", &Vec2(0, 0));
+        world.write_resource::<Stats>().delta = 1.0 / 60.0;
+        world
+    }
+
+    fn spawn(world: &mut World, id: &str, size: [f32; 3], position: [f32; 3]) -> Entity {
+        let [sx, sy, sz] = size;
+        let [px, py, pz] = position;
+        let mut body =
+            RigidBody::new(&AABB::new().scale_x(sx).scale_y(sy).scale_z(sz).build()).build();
+        body.set_position(px, py, pz);
+
+        world
+            .ecs_mut()
+            .create_entity()
+            .with(EntityFlag)
+            .with(IDComp::new(id))
+            .with(CurrentChunkComp::default())
+            .with(MetadataComp::new())
+            .with(PositionComp::new(px, py, pz))
+            .with(RigidBodyComp::new(&body))
+            .build()
+    }
+
+    fn collide(world: &mut World, entity: Entity) {
+        let body = world
+            .read_component::<RigidBodyComp>()
+            .get(entity)
+            .unwrap()
+            .0
+            .clone();
+        let interactor = world.physics_mut().register(&body);
+        world
+            .write_component::<InteractorComp>()
+            .insert(entity, InteractorComp::new(&interactor))
+            .unwrap();
+    }
+
+    fn position(world: &World, entity: Entity) -> Vec3<f32> {
+        world
+            .read_component::<PositionComp>()
+            .get(entity)
+            .unwrap()
+            .0
+            .clone()
+    }
+
+    fn assert_near(actual: Vec3<f32>, expected: [f32; 3]) {
+        for axis in 0..3 {
+            assert!(
+                (actual[axis] - expected[axis]).abs() < 1e-3,
+                "expected {:?}, got {:?}",
+                expected,
+                actual
+            );
+        }
+    }
+
+    #[test]
+    fn riders_follow_their_parents_transform() {
+        let mut world = world();
+        // The saddlebag rides the rider, and is created first, so it can
+        // only land right if parents are placed before their riders.
+        let saddlebag = spawn(&mut world, "saddlebag", [0.2, 0.2, 0.2], [0.0, 40.0, 0.0]);
+        let rider = spawn(&mut world, "rider", [0.6, 1.8, 0.6], [0.0, 40.0, 0.0]);
+        let horse = spawn(&mut world, "horse", [1.2, 1.4, 1.2], [4.5, 20.0, 4.5]);
+        {
+            let mut bodies = world.write_component::<RigidBodyComp>();
+            let horse = &mut bodies.get_mut(horse).unwrap().0;
+            horse.gravity_multiplier = 0.0;
+            horse.yaw = std::f32::consts::FRAC_PI_2;
+        }
+
+        assert!(world.attach(rider, horse, [0.0, 1.0, 0.5]));
+        assert!(world.attach(saddlebag, rider, [0.0, 0.0, -0.5]));
+        PhysicsSystem.run_now(world.ecs());
+
+        // Facing +x, the horse's forward offset points along +x.
+        assert_near(position(&world, rider), [5.0, 21.0, 4.5]);
+        assert_near(position(&world, saddlebag), [4.5, 21.0, 4.5]);
+
+        // Once the horse is gone its rider is dismounted and falls freely.
+        world.ecs_mut().delete_entity(horse).unwrap();
+        world.ecs_mut().maintain();
+        PhysicsSystem.run_now(world.ecs());
+        assert!(!world.read_component::<AttachmentComp>().contains(rider));
+        assert!(world.read_component::<AttachmentComp>().contains(saddlebag));
+    }
+
+    #[test]
+    fn attachments_never_form_cycles() {
+        let mut world = world();
+        let a = spawn(&mut world, "a", [1.0, 1.0, 1.0], [2.0, 20.0, 2.0]);
+        let b = spawn(&mut world, "b", [1.0, 1.0, 1.0], [2.0, 20.0, 2.0]);
+        let c = spawn(&mut world, "c", [1.0, 1.0, 1.0], [2.0, 20.0, 2.0]);
+
+        assert!(!world.attach(a, a, [0.0; 3]));
+        assert!(world.attach(a, b, [0.0; 3]));
+        assert!(world.attach(b, c, [0.0; 3]));
+        assert!(!world.attach(c, a, [0.0; 3]));
+
+        assert_eq!(world.detach(a), Some(AttachmentComp::new("b", [0.0; 3])));
+        assert!(world.attach(c, a, [0.0; 3]));
+    }
+
+    #[test]
+    fn attachments_are_replicated_as_metadata() {
+        let mut world = world();
+        let rider = spawn(&mut world, "rider", [0.6, 1.8, 0.6], [2.0, 20.0, 2.0]);
+        let cart = spawn(&mut world, "cart", [1.0, 1.0, 1.0], [2.0, 20.0, 2.0]);
+        world.attach(rider, cart, [0.0, 0.5, 0.0]);
+
+        EntitiesMetaSystem.run_now(world.ecs());
+        let attachment = world
+            .read_component::<MetadataComp>()
+            .get(rider)
+            .unwrap()
+            .get::<AttachmentComp>("attachment");
+        assert_eq!(
+            attachment,
+            Some(AttachmentComp::new("cart", [0.0, 0.5, 0.0]))
+        );
+
+        world.detach(rider);
+        EntitiesMetaSystem.run_now(world.ecs());
+        let metadatas = world.read_component::<MetadataComp>();
+        assert!(!metadatas.get(rider).unwrap().map.contains_key("attachment"));
+    }
+
+    #[test]
+    fn platforms_carry_the_bodies_standing_on_them() {
+        let mut world = world();
+        let platform = spawn(&mut world, "lift", [3.0, 0.5, 3.0], [4.5, 20.0, 4.5]);
+        let rider = spawn(&mut world, "rider", [0.6, 1.8, 0.6], [4.5, 21.3, 4.5]);
+        {
+            let mut bodies = world.write_component::<RigidBodyComp>();
+            let platform = &mut bodies.get_mut(platform).unwrap().0;
+            platform.gravity_multiplier = 0.0;
+            platform.air_drag = 0.0;
+            platform.velocity = Vec3(1.0, 0.5, 0.0);
+        }
+        world
+            .write_component::<PlatformFlag>()
+            .insert(platform, PlatformFlag)
+            .unwrap();
+
+        // The rider drops onto the lift, then rides it from there on.
+        for _ in 0..60 {
+            if world.read_component::<StandingOnComp>().contains(rider) {
+                break;
+            }
+            PhysicsSystem.run_now(world.ecs());
+        }
+        assert!(world.read_component::<StandingOnComp>().contains(rider));
+        let Vec3(boarded_x, _, _) = position(&world, rider);
+        let Vec3(lift_x, _, _) = position(&world, platform);
+
+        for _ in 0..60 {
+            PhysicsSystem.run_now(world.ecs());
+        }
+
+        // The lift rose and slid a block over a second; the rider stood on
+        // it the whole way instead of falling through.
+        let Vec3(lx, ly, _) = position(&world, platform);
+        assert!((lx - lift_x - 1.0).abs() < 0.05 && ly > 20.5);
+        let Vec3(rx, ry, _) = position(&world, rider);
+        assert!(
+            ((rx - boarded_x) - (lx - lift_x)).abs() < 0.01,
+            "carried along, {rx} vs {lx}"
+        );
+        assert!(
+            (ry - (ly + 0.25 + 0.9)).abs() < 0.02,
+            "standing on top, {ry}"
+        );
+        assert!(world.read_component::<StandingOnComp>().contains(rider));
+    }
+
+    #[test]
+    fn a_mount_is_pushed_by_everything_but_its_rider() {
+        let mut world = world();
+        let horse = spawn(&mut world, "horse", [1.2, 1.4, 1.2], [4.5, 20.0, 4.5]);
+        let rider = spawn(&mut world, "rider", [0.6, 1.8, 0.6], [4.5, 21.0, 4.5]);
+        let bystander = spawn(&mut world, "bystander", [0.6, 1.8, 0.6], [5.2, 20.0, 4.5]);
+        {
+            let mut bodies = world.write_component::<RigidBodyComp>();
+            for entity in [horse, rider, bystander] {
+                bodies.get_mut(entity).unwrap().0.gravity_multiplier = 0.0;
+            }
+        }
+        for entity in [horse, rider, bystander] {
+            collide(&mut world, entity);
+        }
+
+        // The rider sits half inside the horse, which only the bystander
+        // beside it should push.
+        assert!(world.attach(rider, horse, [0.0, 1.0, 0.0]));
+        PhysicsSystem.run_now(world.ecs());
+
+        let bodies = world.read_component::<RigidBodyComp>();
+        let Vec3(ix, iy, _) = bodies.get(horse).unwrap().0.impulses.clone();
+        assert!(ix < 0.0, "pushed away from the bystander, got {ix}");
+        assert!(
+            iy.abs() < ix.abs(),
+            "not pushed down by its rider, got {iy}"
+        );
+    }
+
+    #[test]
+    fn clients_that_move_themselves_are_told_how_far_they_were_carried() {
+        let mut world = world();
+        let platform = spawn(&mut world, "lift", [3.0, 0.5, 3.0], [4.5, 20.0, 4.5]);
+        let player = spawn(&mut world, "player", [0.6, 1.8, 0.6], [4.5, 21.15, 4.5]);
+        {
+            let mut bodies = world.write_component::<RigidBodyComp>();
+            let platform = &mut bodies.get_mut(platform).unwrap().0;
+            platform.gravity_multiplier = 0.0;
+            platform.air_drag = 0.0;
+            platform.velocity = Vec3(3.0, 0.0, 0.0);
+        }
+        world.write_component::<EntityFlag>().remove(player);
+        world
+            .write_component::<ClientFlag>()
+            .insert(player, ClientFlag)
+            .unwrap();
+        world
+            .write_component::<PlatformFlag>()
+            .insert(platform, PlatformFlag)
+            .unwrap();
+
+        // The first tick stands the player on the lift, the second carries it.
+        PhysicsSystem.run_now(world.ecs());
+        world.write_resource::<Events>().queue.clear();
+        PhysicsSystem.run_now(world.ecs());
+
+        let events = world.read_resource::<Events>();
+        let carry = events
+            .queue
+            .iter()
+            .find(|event| event.name == VOXELIZE_BUILTIN_CARRY_EVENT)
+            .expect("a carry event");
+        assert!(matches!(
+            carry.filter,
+            Some(ClientFilter::Direct(ref id)) if id == "player"
+        ));
+        let [dx, _, _]: [f32; 3] = serde_json::from_str(carry.payload.as_deref().unwrap()).unwrap();
+        assert!(
+            (dx - 3.0 / 60.0).abs() < 1e-3,
+            "carried with the lift, {dx}"
+        );
+    }
+}
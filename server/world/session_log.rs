@@ -0,0 +1,221 @@
+//! Per-client reliable sequence log backing session resume.
+//!
+//! Sessions issued a resume token get every queued reliable message stamped
+//! with a per-client sequence number (`Message.seq`), and the message is kept
+//! here until it ages out of a bounded window. When a dropped client
+//! reconnects within the server's grace period it reports the highest
+//! contiguous sequence it applied, and the world replays everything after it
+//! instead of sending a fresh INIT and streaming every chunk again.
+//!
+//! Chunk traffic is logged by coordinates only: replaying it sends the
+//! chunks' current contents, which is both smaller and fresher than the
+//! snapshots that were lost.
+
+use std::collections::BTreeMap;
+
+use hashbrown::HashMap;
+
+use crate::Vec2;
+
+/// Most entries a single client's log retains before the oldest are evicted.
+pub const SESSION_LOG_MAX_ENTRIES: usize = 4096;
+
+/// Most bytes of encoded messages a single client's log retains.
+pub const SESSION_LOG_MAX_BYTES: usize = 4 * 1024 * 1024;
+
+/// A reliable message as it was sent to one client.
+#[derive(Debug, Clone)]
+pub enum LoggedMessage {
+    /// Stamped bytes, replayed verbatim on the lane they were first sent on.
+    Encoded { data: Vec<u8>, bulk: bool },
+    /// A chunk load or update, replayed as a LOAD of the chunks' current data.
+    Chunks(Vec<Vec2<i32>>),
+}
+
+impl LoggedMessage {
+    fn byte_size(&self) -> usize {
+        match self {
+            LoggedMessage::Encoded { data, .. } => data.len(),
+            LoggedMessage::Chunks(coords) => coords.len() * std::mem::size_of::<Vec2<i32>>(),
+        }
+    }
+}
+
+/// The sequence counter and replay window of one resumable session.
+#[derive(Debug, Default)]
+pub struct SessionLog {
+    last_seq: u64,
+    entries: BTreeMap<u64, LoggedMessage>,
+    bytes: usize,
+    /// Highest sequence number that has been evicted from the window.
+    evicted_through: u64,
+}
+
+impl SessionLog {
+    /// Allocate the next sequence number for this session. Sequences start at 1;
+    /// 0 means "not sequenced" on the wire.
+    pub fn next_seq(&mut self) -> u64 {
+        self.last_seq += 1;
+        self.last_seq
+    }
+
+    /// The most recently allocated sequence number.
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
+    /// Keep a sent message for replay, evicting the oldest entries once the
+    /// window exceeds its entry or byte budget.
+    pub fn record(&mut self, seq: u64, message: LoggedMessage) {
+        self.bytes += message.byte_size();
+        if let Some(replaced) = self.entries.insert(seq, message) {
+            self.bytes -= replaced.byte_size();
+        }
+
+        while self.entries.len() > SESSION_LOG_MAX_ENTRIES || self.bytes > SESSION_LOG_MAX_BYTES {
+            let Some((seq, evicted)) = self.entries.pop_first() else {
+                break;
+            };
+            self.bytes -= evicted.byte_size();
+            self.evicted_through = self.evicted_through.max(seq);
+        }
+    }
+
+    /// Whether everything after `acked` is still in the window. A client
+    /// claiming a sequence the server never issued cannot be resumed either.
+    pub fn covers(&self, acked: u64) -> bool {
+        acked >= self.evicted_through && acked <= self.last_seq
+    }
+
+    /// Logged messages after `acked`, in sequence order.
+    pub fn replay_after(&self, acked: u64) -> impl Iterator<Item = (u64, &LoggedMessage)> {
+        self.entries
+            .range(acked.saturating_add(1)..)
+            .map(|(seq, message)| (*seq, message))
+    }
+}
+
+/// Sequence logs of every resumable client in a world, keyed by client id.
+/// Clients without an entry are not resumable and receive unstamped traffic.
+#[derive(Debug, Default)]
+pub struct SessionLogs(HashMap<String, SessionLog>);
+
+impl SessionLogs {
+    /// Start a fresh replay window for a client and return the sequence the
+    /// client should treat as already applied. The counter keeps running
+    /// across reopens, so messages stamped for a previous session that are
+    /// still in flight read as duplicates instead of colliding with new ones.
+    pub fn open(&mut self, client_id: &str) -> u64 {
+        let last_seq = self.0.get(client_id).map_or(0, SessionLog::last_seq);
+        self.0.insert(
+            client_id.to_owned(),
+            SessionLog {
+                last_seq,
+                evicted_through: last_seq,
+                ..Default::default()
+            },
+        );
+        last_seq
+    }
+
+    pub fn remove(&mut self, client_id: &str) {
+        self.0.remove(client_id);
+    }
+
+    pub fn get(&self, client_id: &str) -> Option<&SessionLog> {
+        self.0.get(client_id)
+    }
+
+    pub fn get_mut(&mut self, client_id: &str) -> Option<&mut SessionLog> {
+        self.0.get_mut(client_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(len: usize) -> LoggedMessage {
+        LoggedMessage::Encoded {
+            data: vec![0; len],
+            bulk: false,
+        }
+    }
+
+    #[test]
+    fn sequences_start_at_one_and_replay_in_order() {
+        let mut log = SessionLog::default();
+        for _ in 0..3 {
+            let seq = log.next_seq();
+            log.record(seq, encoded(8));
+        }
+
+        assert!(log.covers(0));
+        let replayed: Vec<u64> = log.replay_after(1).map(|(seq, _)| seq).collect();
+        assert_eq!(replayed, vec![2, 3]);
+    }
+
+    #[test]
+    fn out_of_order_records_replay_by_sequence() {
+        let mut log = SessionLog::default();
+        let chunk_seq = log.next_seq();
+        let event_seq = log.next_seq();
+        log.record(event_seq, encoded(8));
+        log.record(chunk_seq, LoggedMessage::Chunks(vec![Vec2(0, 0)]));
+
+        let replayed: Vec<u64> = log.replay_after(0).map(|(seq, _)| seq).collect();
+        assert_eq!(replayed, vec![chunk_seq, event_seq]);
+    }
+
+    #[test]
+    fn eviction_past_the_ack_makes_the_session_unresumable() {
+        let mut log = SessionLog::default();
+        for _ in 0..(SESSION_LOG_MAX_ENTRIES + 2) {
+            let seq = log.next_seq();
+            log.record(seq, encoded(1));
+        }
+
+        assert!(!log.covers(0));
+        assert!(!log.covers(1));
+        assert!(log.covers(2));
+        assert_eq!(log.replay_after(2).count(), SESSION_LOG_MAX_ENTRIES);
+    }
+
+    #[test]
+    fn byte_budget_evicts_oldest_entries() {
+        let mut log = SessionLog::default();
+        for _ in 0..3 {
+            let seq = log.next_seq();
+            log.record(seq, encoded(SESSION_LOG_MAX_BYTES / 2));
+        }
+
+        assert!(!log.covers(0));
+        assert!(log.covers(1));
+        assert_eq!(log.replay_after(1).count(), 2);
+    }
+
+    #[test]
+    fn reopening_keeps_the_counter_but_drops_the_window() {
+        let mut logs = SessionLogs::default();
+        assert_eq!(logs.open("a"), 0);
+        let log = logs.get_mut("a").unwrap();
+        let seq = log.next_seq();
+        log.record(seq, encoded(1));
+
+        assert_eq!(logs.open("a"), 1);
+        let log = logs.get("a").unwrap();
+        assert!(!log.covers(0));
+        assert!(log.covers(1));
+        assert_eq!(log.replay_after(1).count(), 0);
+    }
+
+    #[test]
+    fn acks_beyond_the_issued_sequence_are_rejected() {
+        let mut log = SessionLog::default();
+        let seq = log.next_seq();
+        log.record(seq, encoded(1));
+
+        assert!(log.covers(1));
+        assert!(!log.covers(2));
+    }
+}
@@ -1,9 +1,13 @@
+use hashbrown::HashSet;
+
+use super::inbound::InitSession;
 use super::*;
+use crate::ChunkProtocol;
 
 impl World {
     /// Add a transport sender to this world.
     pub(crate) fn add_transport(&mut self, id: &str, sender: &WsSender) {
-        let (init_message, _) = self.generate_init_message(id, None, None, None, None, None, true);
+        let (init_message, _) = self.generate_init_message(id, InitSession::default(), true);
         self.send(sender, &init_message);
         self.write_resource::<Transports>()
             .insert(id.to_owned(), sender.clone());
@@ -22,6 +26,11 @@ impl World {
     /// live entity refreshes the session (sender, username, preferences) and
     /// replays the INIT ack against that entity — it never creates a
     /// duplicate entity or a second session.
+    ///
+    /// With a `resume_token` the session becomes resumable: its queued
+    /// reliable traffic is sequenced and logged from here on (see
+    /// [`SessionLogs`]) and the INIT hands the client its token.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn add_client(
         &mut self,
         id: &str,
@@ -29,7 +38,16 @@ impl World {
         sender: &WsSender,
         preferences: ClientPreferencesPatch,
         motion_protocol: MotionProtocol,
+        mesh_format: MeshFormat,
+        resume_token: Option<&str>,
     ) {
+        self.record_input(|| RecordedInput::Join {
+            client_id: id.to_owned(),
+            username: username.to_owned(),
+            preferences,
+            compact_motion: motion_protocol.is_compact(),
+        });
+
         let existing_ent = self.clients().get(id).map(|client| client.entity);
         let is_rejoin = existing_ent.is_some();
 
@@ -74,6 +92,18 @@ impl World {
                 .with(CollisionsComp::new())
                 .build();
 
+            // Authoritative worlds walk client bodies from their inputs with
+            // the same brain entities walk with; `client_modifier` may swap in
+            // other brain options.
+            if self.config().authoritative_movement.is_some() {
+                self.write_component::<BrainComp>()
+                    .insert(ent, BrainComp::default())
+                    .ok();
+                self.write_component::<MovementInputsComp>()
+                    .insert(ent, MovementInputsComp::default())
+                    .ok();
+            }
+
             if let Some(modifier) = self.client_modifier.to_owned() {
                 modifier(self, ent);
             }
@@ -134,13 +164,24 @@ impl World {
         self.write_resource::<ReplicatedStateBuffer>()
             .remove_client(id);
 
+        let resume = resume_token.map(|token| {
+            let seq = self.write_resource::<SessionLogs>().open(id);
+            (token, seq)
+        });
+        if resume.is_none() {
+            self.write_resource::<SessionLogs>().remove(id);
+        }
+
         let (init_message, init_entity_ids) = self.generate_init_message(
             id,
-            saved_position,
-            saved_direction,
-            saved_is_flying,
-            saved_is_ghost,
-            saved_is_swimming,
+            InitSession {
+                saved_position,
+                saved_direction,
+                saved_is_flying,
+                saved_is_ghost,
+                saved_is_swimming,
+                resume,
+            },
             false,
         );
 
@@ -149,6 +190,7 @@ impl World {
                 client.username = username.to_owned();
                 client.sender = sender.clone();
                 client.motion_protocol = motion_protocol;
+                client.mesh_format = mesh_format;
             }
         } else {
             self.clients_mut().insert(
@@ -159,6 +201,7 @@ impl World {
                     username: username.to_owned(),
                     sender: sender.clone(),
                     motion_protocol,
+                    mesh_format,
                 },
             );
 
@@ -204,13 +247,202 @@ impl World {
         );
     }
 
+    /// Reattach a suspended session to its live entity after a transient
+    /// disconnect. The new sender replaces the dead one in place, a short
+    /// INIT marked `resumed` acknowledges the JOIN, and every logged message
+    /// after `last_seq` is replayed on its original lane. Chunks are re-sent
+    /// only if the client is still interested in them and no longer has them.
+    ///
+    /// Falls back to a full [`World::add_client`] rejoin when the session is
+    /// unknown here or its log no longer reaches back to `last_seq`.
+    pub(crate) fn resume_client(&mut self, request: ClientResumeRequest) {
+        let ClientResumeRequest {
+            join,
+            last_seq,
+            chunks,
+        } = request;
+        let id = join.id.as_str();
+
+        let existing_ent = self.clients().get(id).map(|client| client.entity);
+        let covered = self
+            .read_resource::<SessionLogs>()
+            .get(id)
+            .is_some_and(|log| log.covers(last_seq));
+
+        let (Some(ent), true, Some(token)) = (existing_ent, covered, join.resume_token.as_deref())
+        else {
+            perf::log(
+                "client_resume",
+                &self.name,
+                json!({ "clientId": id, "outcome": "rejoined", "lastSeq": last_seq }),
+            );
+            info!(
+                "Client at {} could not resume, rejoining: {}",
+                id, self.name
+            );
+            self.add_client(
+                id,
+                &join.username,
+                &join.sender,
+                join.preferences,
+                join.motion_protocol,
+                join.mesh_format,
+                join.resume_token.as_deref(),
+            );
+            return;
+        };
+
+        {
+            let mut names = self.write_component::<NameComp>();
+            if let Some(name) = names.get_mut(ent) {
+                name.0 = join.username.clone();
+            }
+        }
+        {
+            let mut addrs = self.write_component::<AddrComp>();
+            if let Some(addr) = addrs.get_mut(ent) {
+                *addr = AddrComp::new(&join.sender);
+            }
+        }
+        self.record_input(|| RecordedInput::Resume {
+            client_id: id.to_owned(),
+            preferences: join.preferences,
+        });
+        apply_client_preferences_patch(self, ent, &join.preferences);
+        if let Some(client) = self.clients_mut().get_mut(id) {
+            client.username = join.username.clone();
+            client.sender = join.sender.clone();
+            client.motion_protocol = join.motion_protocol;
+            client.mesh_format = join.mesh_format;
+        }
+
+        let init_message = Message::new(&MessageType::Init)
+            .world_name(&self.name)
+            .json(
+                &json!({
+                    "id": id,
+                    "resumed": true,
+                    "resumeToken": token,
+                    "seq": last_seq,
+                })
+                .to_string(),
+            )
+            .build();
+        self.send(&join.sender, &init_message);
+
+        let replay: Vec<(u64, LoggedMessage)> = self
+            .read_resource::<SessionLogs>()
+            .get(id)
+            .map(|log| {
+                log.replay_after(last_seq)
+                    .map(|(seq, message)| (seq, message.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let replayed = replay.len();
+
+        let mut resent: HashSet<Vec2<i32>> = HashSet::new();
+        for (seq, message) in replay {
+            match message {
+                LoggedMessage::Encoded { data, bulk } => {
+                    let _ = if bulk {
+                        join.sender.send_bulk(data)
+                    } else {
+                        join.sender.send(data)
+                    };
+                }
+                LoggedMessage::Chunks(coords) => {
+                    // Always sent, even if empty, so the client's contiguous
+                    // sequence advances past this entry.
+                    self.send_resumed_chunks(id, &join.sender, seq, &coords);
+                    resent.extend(coords);
+                }
+            }
+        }
+
+        let held: HashSet<Vec2<i32>> = chunks.into_iter().collect();
+        let missing: Vec<Vec2<i32>> = self
+            .chunk_interest()
+            .map
+            .iter()
+            .filter(|(coords, clients)| {
+                clients.contains(id) && !held.contains(*coords) && !resent.contains(*coords)
+            })
+            .map(|(coords, _)| coords.to_owned())
+            .collect();
+        if !missing.is_empty() {
+            let seq = {
+                let mut logs = self.write_resource::<SessionLogs>();
+                let log = logs.get_mut(id).unwrap();
+                let seq = log.next_seq();
+                log.record(seq, LoggedMessage::Chunks(missing.clone()));
+                seq
+            };
+            self.send_resumed_chunks(id, &join.sender, seq, &missing);
+        }
+
+        perf::log(
+            "client_resume",
+            &self.name,
+            json!({
+                "clientId": id,
+                "outcome": "resumed",
+                "lastSeq": last_seq,
+                "replayed": replayed,
+                "resentChunks": resent.len() + missing.len(),
+            }),
+        );
+        info!(
+            "Client at {} resumed in world: {} ({} replayed)",
+            id, self.name, replayed
+        );
+    }
+
+    /// Send the current contents of `coords` as one sequenced LOAD, skipping
+    /// chunks the client has since lost interest in or that are not ready
+    /// (the chunk pipeline streams those once they are).
+    fn send_resumed_chunks(&self, id: &str, sender: &WsSender, seq: u64, coords: &[Vec2<i32>]) {
+        let (mesh, sub_chunks) = {
+            let config = self.config();
+            (!config.client_only_meshing, config.sub_chunks as u32)
+        };
+        let mesh_format = self
+            .clients()
+            .get(id)
+            .map(|client| client.mesh_format)
+            .unwrap_or_default();
+
+        let models: Vec<ChunkProtocol> = {
+            let chunks = self.chunks();
+            let interests = self.chunk_interest();
+            coords
+                .iter()
+                .filter(|coords| interests.is_interested(id, coords))
+                .filter_map(|coords| chunks.get(coords))
+                .map(|chunk| chunk.to_model(mesh, true, 0..sub_chunks))
+                .collect()
+        };
+
+        let mut message = Message::new(&MessageType::Load)
+            .chunks(&models)
+            .mesh_format(mesh_format)
+            .build();
+        message.seq = seq;
+        let _ = sender.send_bulk(encode_message(&message));
+    }
+
     /// Remove a client from the world by endpoint.
     pub(crate) fn remove_client(&mut self, id: &str) {
+        self.record_input(|| RecordedInput::Leave {
+            client_id: id.to_owned(),
+        });
+
         let removed = self.clients_mut().remove(id);
         self.entity_ids_mut().remove(id);
         self.chunk_interest_mut().remove_client(id);
         self.bookkeeping_mut().remove_client(id);
         self.inbound_state.remove_client(id);
+        self.write_resource::<SessionLogs>().remove(id);
         {
             // Drop the client's pending outbound state and purge its peer
             // snapshots everywhere: the reliable LEAVE event below is what
@@ -117,6 +117,17 @@ impl World {
             return None;
         }
 
+        if let Some(levels) = self.entity_spawn_lights.get(&etype.to_lowercase()) {
+            let light = self.light_level_at(position).max();
+            if !levels.contains(&light) {
+                debug!(
+                    "Not spawning {} at {:?}: light {} is outside {:?}",
+                    etype, position, light, levels
+                );
+                return None;
+            }
+        }
+
         let loader = self
             .entity_loaders
             .get(&etype.to_lowercase())
@@ -207,6 +218,23 @@ impl World {
                     set_position(self.ecs_mut(), ent, pos.0, pos.1, pos.2);
                 }
 
+                // Riders are saved mounted; their parent may revive after
+                // them, so the attachment is restored as is rather than
+                // through `attach`.
+                if let Some(attachment) = metadata.get::<AttachmentComp>("attachment") {
+                    self.ecs_mut()
+                        .write_storage::<AttachmentComp>()
+                        .insert(ent, attachment)
+                        .ok();
+                }
+
+                if let Some(emitter) = metadata.get::<LightEmitterComp>("lightEmitter") {
+                    self.ecs_mut()
+                        .write_storage::<LightEmitterComp>()
+                        .insert(ent, emitter)
+                        .ok();
+                }
+
                 Some(ent)
             }
             Err(e) => {
@@ -94,10 +94,20 @@ impl Handler<ClientJoinRequest> for SyncWorld {
             &msg.sender,
             msg.preferences,
             msg.motion_protocol,
+            msg.mesh_format,
+            msg.resume_token.as_deref(),
         );
     }
 }
 
+impl Handler<ClientResumeRequest> for SyncWorld {
+    type Result = ();
+
+    fn handle(&mut self, msg: ClientResumeRequest, _: &mut SyncContext<Self>) {
+        self.0.write().unwrap().resume_client(msg);
+    }
+}
+
 impl Handler<ClientLeaveRequest> for SyncWorld {
     type Result = ();
 
@@ -146,3 +156,59 @@ impl Handler<ResetWorld> for SyncWorld {
         }
     }
 }
+
+impl Handler<SnapshotWorld> for SyncWorld {
+    type Result = MessageResult<SnapshotWorld>;
+
+    fn handle(&mut self, _: SnapshotWorld, _: &mut SyncContext<Self>) -> Self::Result {
+        MessageResult(self.0.read().unwrap().snapshot())
+    }
+}
+
+impl Handler<RestoreWorld> for SyncWorld {
+    type Result = ();
+
+    fn handle(&mut self, msg: RestoreWorld, _: &mut SyncContext<Self>) {
+        self.0.write().unwrap().restore(msg.0);
+    }
+}
+
+impl Handler<LoadReplay> for SyncWorld {
+    type Result = ();
+
+    fn handle(&mut self, msg: LoadReplay, _: &mut SyncContext<Self>) {
+        self.0.write().unwrap().load_replay(msg.0);
+    }
+}
+
+impl Handler<StartRecording> for SyncWorld {
+    type Result = std::io::Result<()>;
+
+    fn handle(&mut self, msg: StartRecording, _: &mut SyncContext<Self>) -> Self::Result {
+        self.0.write().unwrap().start_recording(msg.path)
+    }
+}
+
+impl Handler<StartInputRecording> for SyncWorld {
+    type Result = Result<(), LockstepError>;
+
+    fn handle(&mut self, msg: StartInputRecording, _: &mut SyncContext<Self>) -> Self::Result {
+        self.0.write().unwrap().start_input_recording(msg.path)
+    }
+}
+
+impl Handler<StopInputRecording> for SyncWorld {
+    type Result = std::io::Result<()>;
+
+    fn handle(&mut self, _: StopInputRecording, _: &mut SyncContext<Self>) -> Self::Result {
+        self.0.write().unwrap().stop_input_recording()
+    }
+}
+
+impl Handler<StopRecording> for SyncWorld {
+    type Result = std::io::Result<()>;
+
+    fn handle(&mut self, _: StopRecording, _: &mut SyncContext<Self>) -> Self::Result {
+        self.0.write().unwrap().stop_recording()
+    }
+}
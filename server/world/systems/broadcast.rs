@@ -8,13 +8,13 @@ use crate::{
     encode_message, fragment_message,
     perf::{self, OutboundPerfKind},
     server::Message,
-    state_flush_budget,
+    stamp_sequence, state_flush_budget,
     world::{
         profiler::Profiler, system_profiler::WorldTimingContext, Client, Clients, MessageQueues,
         Stats, WorldConfig,
     },
-    EncodedMessage, EncodedMessageQueue, EntityOperation, MessageType, ReplicatedStateBuffer,
-    RtcSenders, Transports,
+    EncodedMessage, EncodedMessageQueue, EntityOperation, LoggedMessage, MessageType,
+    ReplayRecorder, ReplicatedStateBuffer, RtcSenders, SessionLogs, Transports, Vec2,
 };
 
 /// How often (wall-clock ms) each client's motion-gap distribution is
@@ -82,14 +82,67 @@ fn is_bulk(msg_type: i32) -> bool {
     )
 }
 
-fn send_lane_routed(client: &Client, msg_type: i32, data: Vec<u8>) {
-    if is_bulk(msg_type) {
+/// Send a reliable message on its lane. For resumable sessions the bytes are
+/// stamped with the client's next sequence number and kept in its log, unless
+/// the message was already sequenced before encoding.
+fn send_reliable(
+    id: &str,
+    client: &Client,
+    encoded: &EncodedMessage,
+    session_logs: &mut SessionLogs,
+) {
+    let bulk = is_bulk(encoded.msg_type);
+    let data = match session_logs.get_mut(id) {
+        Some(log) if encoded.seq == 0 => {
+            let seq = log.next_seq();
+            let data = stamp_sequence(&encoded.data, seq);
+            log.record(
+                seq,
+                LoggedMessage::Encoded {
+                    data: data.clone(),
+                    bulk,
+                },
+            );
+            data
+        }
+        _ => encoded.data.clone(),
+    };
+
+    if bulk {
         let _ = client.sender.send_bulk(data);
     } else {
         let _ = client.sender.send(data);
     }
 }
 
+/// Chunk payloads always target a single client, so resumable sessions get
+/// them sequenced on the message itself before encoding and logged by
+/// coordinates only: a resume re-sends the chunks' current data instead of
+/// the log holding (and re-stamping) megabytes of compressed chunk frames.
+fn sequence_chunk_messages(
+    messages: &mut [(Message, ClientFilter)],
+    session_logs: &mut SessionLogs,
+) {
+    for (message, filter) in messages.iter_mut() {
+        let ClientFilter::Direct(id) = filter else {
+            continue;
+        };
+        if message.chunks.is_empty() {
+            continue;
+        }
+        let Some(log) = session_logs.get_mut(id) else {
+            continue;
+        };
+
+        let seq = log.next_seq();
+        message.seq = seq;
+        log.record(
+            seq,
+            LoggedMessage::Chunks(message.chunks.iter().map(|c| Vec2(c.x, c.z)).collect()),
+        );
+    }
+}
+
 fn should_send_to_transport(msg_type: i32) -> bool {
     matches!(
         MessageType::try_from(msg_type),
@@ -188,9 +241,15 @@ fn batch_messages(messages: Vec<(Message, ClientFilter)>) -> Vec<(Message, Clien
     result
 }
 
-/// Send encoded bytes to a client over its WebRTC data channel when one is
-/// available, otherwise over its WebSocket channel.
-fn send_to_client(client: &Client, rtc_sender: Option<&UnboundedSender<Vec<u8>>>, data: &[u8]) {
+/// Send latest-wins state bytes to a client over its unreliable WebRTC data
+/// channel when one is open, otherwise over its WebSocket control lane. A
+/// lost datagram is harmless here: the next flush carries a newer sample.
+/// Reliable events must never go through this function.
+fn send_state_to_client(
+    client: &Client,
+    rtc_sender: Option<&UnboundedSender<Vec<u8>>>,
+    data: &[u8],
+) {
     if let Some(rtc_sender) = rtc_sender {
         for fragment in fragment_message(data) {
             if rtc_sender.send(fragment).is_err() {
@@ -212,6 +271,8 @@ impl<'a> System<'a> for BroadcastSystem {
         WriteExpect<'a, MessageQueues>,
         WriteExpect<'a, EncodedMessageQueue>,
         WriteExpect<'a, ReplicatedStateBuffer>,
+        WriteExpect<'a, SessionLogs>,
+        WriteExpect<'a, ReplayRecorder>,
         WriteExpect<'a, Profiler>,
         Option<ReadExpect<'a, RtcSenders>>,
     );
@@ -226,6 +287,8 @@ impl<'a> System<'a> for BroadcastSystem {
             mut queues,
             mut encoded_queue,
             mut replicated_state,
+            mut session_logs,
+            mut replay_recorder,
             _profiler,
             rtc_senders_opt,
         ) = data;
@@ -240,9 +303,11 @@ impl<'a> System<'a> for BroadcastSystem {
             })
             .collect();
 
-        let (immediate_messages, deferred_messages): (Vec<_>, Vec<_>) = messages_with_world_name
-            .into_iter()
-            .partition(|(msg, _)| is_immediate(msg));
+        let (mut immediate_messages, deferred_messages): (Vec<_>, Vec<_>) =
+            messages_with_world_name
+                .into_iter()
+                .partition(|(msg, _)| is_immediate(msg));
+        sequence_chunk_messages(&mut immediate_messages, &mut session_logs);
 
         let immediate_encoded: Vec<(EncodedMessage, ClientFilter)> = immediate_messages
             .into_iter()
@@ -252,14 +317,17 @@ impl<'a> System<'a> for BroadcastSystem {
                 let encoded = EncodedMessage {
                     data: encode_message(&message),
                     msg_type,
-                    is_rtc_eligible: false,
                     perf: outbound_perf,
+                    seq: message.seq,
                 };
                 (encoded, filter)
             })
             .collect();
 
-        let batched_messages = batch_messages(deferred_messages);
+        // Sequenced after batching: a merge would otherwise swallow the
+        // sequence number of the message merged into another.
+        let mut batched_messages = batch_messages(deferred_messages);
+        sequence_chunk_messages(&mut batched_messages, &mut session_logs);
 
         encoded_queue.append(batched_messages);
         encoded_queue.process();
@@ -276,28 +344,19 @@ impl<'a> System<'a> for BroadcastSystem {
         // budget below: a burst of CREATEs/DELETEs must reach the client
         // ahead of (not interleaved with a full budget of) motion traffic on
         // the shared control lane.
+        //
+        // These always ride the WebSocket, even for clients with an open
+        // data channel: that channel is unordered and never retransmits, so
+        // it may only carry traffic a newer sample supersedes.
         let mut lifecycle_bytes_by_client: HashMap<String, usize> = HashMap::new();
 
         for (encoded, filter) in done_messages {
-            let use_rtc = encoded.is_rtc_eligible;
             let is_entity_lifecycle =
                 MessageType::try_from(encoded.msg_type) == Ok(MessageType::Entity);
 
             if let ClientFilter::Direct(id) = &filter {
                 if let Some(client) = clients.get(id) {
-                    if use_rtc {
-                        if let Some(ref rtc_map) = rtc_map {
-                            if let Some(rtc_sender) = rtc_map.get(id) {
-                                for fragment in fragment_message(&encoded.data) {
-                                    if rtc_sender.send(fragment).is_err() {
-                                        break;
-                                    }
-                                }
-                                continue;
-                            }
-                        }
-                    }
-                    send_lane_routed(client, encoded.msg_type, encoded.data.clone());
+                    send_reliable(id, client, &encoded, &mut session_logs);
                     if is_entity_lifecycle {
                         *lifecycle_bytes_by_client.entry(id.clone()).or_default() +=
                             encoded.data.len();
@@ -308,6 +367,12 @@ impl<'a> System<'a> for BroadcastSystem {
                 continue;
             }
 
+            // Recordings keep what the whole world is told; targeted
+            // subsets are as private as direct messages.
+            if !matches!(filter, ClientFilter::Include(_)) {
+                replay_recorder.record(stats.dispatch_count(), &encoded.data);
+            }
+
             clients.iter().for_each(|(id, client)| {
                 match &filter {
                     ClientFilter::All => {}
@@ -324,20 +389,7 @@ impl<'a> System<'a> for BroadcastSystem {
                     _ => {}
                 };
 
-                if use_rtc {
-                    if let Some(ref rtc_map) = rtc_map {
-                        if let Some(rtc_sender) = rtc_map.get(id) {
-                            for fragment in fragment_message(&encoded.data) {
-                                if rtc_sender.send(fragment).is_err() {
-                                    break;
-                                }
-                            }
-                            return;
-                        }
-                    }
-                }
-
-                send_lane_routed(client, encoded.msg_type, encoded.data.clone());
+                send_reliable(id, client, &encoded, &mut session_logs);
                 if is_entity_lifecycle {
                     *lifecycle_bytes_by_client.entry(id.clone()).or_default() += encoded.data.len();
                 }
@@ -381,6 +433,16 @@ impl<'a> System<'a> for BroadcastSystem {
         for (client_id, client) in clients.iter() {
             let rtc_sender = rtc_map.as_ref().and_then(|rtc_map| rtc_map.get(client_id));
 
+            // A session suspended for resume keeps its entity but has no live
+            // socket: hold its state in the slots so the resumed connection
+            // gets one current snapshot instead of nothing for the window.
+            if client.sender.is_closed() {
+                if replicated_state.has_pending(client_id) {
+                    gated_clients += 1;
+                }
+                continue;
+            }
+
             // Gate on the CONTROL lane only: bulk chunk streaming must never
             // stall live state — that starvation is what made a newcomer
             // invisible to an existing client while it loaded chunks. RTC
@@ -426,7 +488,7 @@ impl<'a> System<'a> for BroadcastSystem {
                     message = message.json(&json!({ "townPerfTraceId": trace_id }).to_string());
                 }
                 let data = encode_message(&message.build());
-                send_to_client(client, rtc_sender, &data);
+                send_state_to_client(client, rtc_sender, &data);
 
                 if perf::is_enabled() {
                     let mut depths = Map::new();
@@ -479,7 +541,7 @@ impl<'a> System<'a> for BroadcastSystem {
                     .tick(tick)
                     .build();
                 let data = encode_message(&message);
-                send_to_client(client, rtc_sender, &data);
+                send_state_to_client(client, rtc_sender, &data);
 
                 if perf::is_enabled() {
                     perf::log(
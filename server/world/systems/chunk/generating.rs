@@ -278,14 +278,15 @@ impl<'a> System<'a> for ChunkGeneratingSystem {
         }
 
         // parallelize loading
-        let loaded_chunks: Vec<(Vec2<i32>, Option<Chunk>)> = to_load
+        let loaded_chunks: Vec<_> = to_load
             .into_par_iter()
-            .map(|coords| (coords.to_owned(), chunks.try_load(&coords, &registry)))
+            .map(|coords| (coords.to_owned(), chunks.read_chunk_file(&coords, &registry)))
             .collect();
 
         for (coords, loaded_chunk) in loaded_chunks.into_iter() {
-            if let Some(chunk) = loaded_chunk {
+            if let Some((chunk, scheduled_ticks)) = loaded_chunk {
                 chunks.renew(chunk, ChunkRenewal::Full);
+                chunks.restore_scheduled_ticks(&coords, &scheduled_ticks, stats.tick);
                 // A save loaded only as context serves its purpose by
                 // existing (voxel data for a neighbor); meshing it would pull
                 // its own ring off disk in turn, sweeping whole saved regions
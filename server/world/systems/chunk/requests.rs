@@ -2,8 +2,8 @@ use hashbrown::{HashMap, HashSet};
 use specs::{Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
 use crate::{
-    ChunkInterests, ChunkProtocol, ChunkRequestsComp, ChunkStatus, Chunks, ClientFilter, IDComp,
-    Mesher, Message, MessageQueues, MessageType, Pipeline, Vec2, WorldConfig,
+    ChunkInterests, ChunkProtocol, ChunkRequestsComp, ChunkStatus, Chunks, ClientFilter, Clients,
+    IDComp, Mesher, Message, MessageQueues, MessageType, Pipeline, Vec2, WorldConfig,
 };
 
 pub struct ChunkRequestsSystem;
@@ -12,6 +12,7 @@ impl<'a> System<'a> for ChunkRequestsSystem {
     type SystemData = (
         ReadExpect<'a, Chunks>,
         ReadExpect<'a, WorldConfig>,
+        ReadExpect<'a, Clients>,
         WriteExpect<'a, ChunkInterests>,
         WriteExpect<'a, Pipeline>,
         WriteExpect<'a, Mesher>,
@@ -21,8 +22,17 @@ impl<'a> System<'a> for ChunkRequestsSystem {
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (chunks, config, mut interests, mut pipeline, mut mesher, mut queue, ids, mut requests) =
-            data;
+        let (
+            chunks,
+            config,
+            clients,
+            mut interests,
+            mut pipeline,
+            mut mesher,
+            mut queue,
+            ids,
+            mut requests,
+        ) = data;
 
         let max_response_per_tick = config.max_response_per_tick;
 
@@ -107,7 +117,14 @@ impl<'a> System<'a> for ChunkRequestsSystem {
                 })
                 .collect();
 
-            let message = Message::new(&MessageType::Load).chunks(&chunks).build();
+            let mesh_format = clients
+                .get(&id)
+                .map(|client| client.mesh_format)
+                .unwrap_or_default();
+            let message = Message::new(&MessageType::Load)
+                .chunks(&chunks)
+                .mesh_format(mesh_format)
+                .build();
             queue.push((message, ClientFilter::Direct(id)));
         }
     }
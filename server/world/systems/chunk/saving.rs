@@ -1,24 +1,31 @@
 use specs::{ReadExpect, System, WriteExpect};
 
-use crate::{BackgroundChunkSaver, Chunks, WorldConfig};
+use crate::{BackgroundChunkSaver, Chunks, Stats, WorldConfig};
 
 pub struct ChunkSavingSystem;
 
 impl<'a> System<'a> for ChunkSavingSystem {
     type SystemData = (
         ReadExpect<'a, WorldConfig>,
+        ReadExpect<'a, Stats>,
         WriteExpect<'a, Chunks>,
         ReadExpect<'a, BackgroundChunkSaver>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (config, mut chunks, bg_saver) = data;
+        let (config, stats, mut chunks, bg_saver) = data;
 
         if !config.saving {
             return;
         }
 
-        for save in chunks.take_pending_saves(config.max_saves_per_tick, config.max_save_retries) {
+        let saves = chunks.take_pending_saves(
+            config.max_saves_per_tick,
+            config.max_save_retries,
+            stats.tick,
+        );
+
+        for save in saves {
             bg_saver.queue_save(save);
         }
     }
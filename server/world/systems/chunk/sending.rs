@@ -3,8 +3,8 @@ use specs::{ReadExpect, System, WriteExpect};
 use std::collections::VecDeque;
 
 use crate::{
-    ChunkInterests, ChunkProtocol, Chunks, ClientFilter, Message, MessageQueues, MessageType,
-    WorldConfig,
+    ChunkInterests, ChunkProtocol, Chunks, ClientFilter, Clients, Message, MessageQueues,
+    MessageType, WorldConfig,
 };
 
 #[derive(Default)]
@@ -20,17 +20,25 @@ impl<'a> System<'a> for ChunkSendingSystem {
     type SystemData = (
         ReadExpect<'a, WorldConfig>,
         ReadExpect<'a, ChunkInterests>,
+        ReadExpect<'a, Clients>,
         WriteExpect<'a, Chunks>,
         WriteExpect<'a, MessageQueues>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (config, interests, mut chunks, mut queue) = data;
+        let (config, interests, clients, mut chunks, mut queue) = data;
 
         if chunks.to_send.is_empty() {
             return;
         }
 
+        let mesh_format = |client_id: &str| {
+            clients
+                .get(client_id)
+                .map(|client| client.mesh_format)
+                .unwrap_or_default()
+        };
+
         let mut to_send = VecDeque::new();
         std::mem::swap(&mut chunks.to_send, &mut to_send);
 
@@ -103,6 +111,7 @@ impl<'a> System<'a> for ChunkSendingSystem {
                 queue.push((
                     Message::new(&MessageType::Load)
                         .chunks(&chunk_models)
+                        .mesh_format(mesh_format(&client_id))
                         .build(),
                     ClientFilter::Direct(client_id),
                 ));
@@ -125,6 +134,7 @@ impl<'a> System<'a> for ChunkSendingSystem {
                 queue.push((
                     Message::new(&MessageType::Update)
                         .chunks(&chunk_models)
+                        .mesh_format(mesh_format(&client_id))
                         .build(),
                     ClientFilter::Direct(client_id),
                 ));
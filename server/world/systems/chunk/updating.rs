@@ -1,15 +1,16 @@
-use std::{cmp::Reverse, collections::VecDeque};
+use std::collections::VecDeque;
 
 use hashbrown::{HashMap, HashSet};
 use nanoid::nanoid;
 use specs::{Entities, LazyUpdate, ReadExpect, System, WorldExt, WriteExpect, WriteStorage};
 
 use crate::{
-    beer_lambert_transmit, sample_random_ticks, BlockUtils, ChunkInterests, ChunkUtils, Chunks,
-    ClientFilter, CurrentChunkComp, ETypeComp, EntityFlag, IDComp, JsonComp, LightColor, LightNode,
-    Lights, Mesher, Message, MessageQueues, MessageType, MetadataComp, Registry, Stats,
-    UpdateProtocol, Vec2, Vec3, VoxelAccess, VoxelComp, VoxelPacker, WaterloggingRules,
-    WorldConfig,
+    beer_lambert_transmit, react_fluid, sample_random_ticks, BlockUtils, BulkUpdate,
+    ChunkInterests, ChunkUtils, Chunks, ClientFilter, CollisionsComp, CurrentChunkComp, ETypeComp,
+    EntityFlag, Event, Events, FallingBlockComp, FluidReaction, IDComp, JsonComp, LightColor,
+    LightNode, Lights, Mesher, Message, MessageQueues, MessageType, MetadataComp, PositionComp,
+    Registry, ReplayRecorder, RigidBodyComp, SoundEffectEvent, Stats, UpdateProtocol, Vec2, Vec3,
+    VoxelAccess, VoxelComp, VoxelPacker, WaterloggingRules, WorldConfig, FALLING_BLOCK_ETYPE,
 };
 
 pub const VOXEL_NEIGHBORS: [[i32; 3]; 6] = [
@@ -245,6 +246,8 @@ fn apply_active_updates(
     overlay: &mut HashMap<Vec3<i32>, u32>,
     registry: &Registry,
     voxel: &Vec3<i32>,
+    reactions: &mut Vec<FluidReaction>,
+    falling: &mut Vec<(Vec3<i32>, u32)>,
 ) {
     let updates = {
         let space = ActiveOverlay {
@@ -254,8 +257,20 @@ fn apply_active_updates(
         let id = space.get_voxel(voxel.0, voxel.1, voxel.2);
         let block = registry.get_block_by_id(id);
         let mut updates = Vec::new();
-        if let Some(updater) = &block.active_updater {
-            updates.extend(updater(voxel.clone(), &space, registry));
+        // A fluid that meets something in the interaction table reacts
+        // instead of flowing this tick.
+        if let Some(mut reaction) = react_fluid(voxel, &space, registry) {
+            updates.append(&mut reaction.updates);
+            reactions.push(reaction);
+        } else if let Some(updater) = &block.active_updater {
+            let own = updater(voxel.clone(), &space, registry);
+            // A gravity block clearing itself has lost its support: it
+            // leaves as a falling-block entity rather than vanishing.
+            if block.is_gravity_affected && own.iter().any(|(pos, raw)| pos == voxel && *raw == 0)
+            {
+                falling.push((voxel.clone(), space.get_raw_voxel(voxel.0, voxel.1, voxel.2)));
+            }
+            updates.extend(own);
         }
         if space.get_voxel_waterlogged(voxel.0, voxel.1, voxel.2) {
             if let Some(fluid) = registry.waterlogging_fluid() {
@@ -274,21 +289,54 @@ fn apply_active_updates(
     }
 }
 
-fn collect_due_active_voxels(chunks: &mut Chunks, current_tick: u64) -> Vec<Vec3<i32>> {
-    let mut due = Vec::new();
-    while let Some(Reverse(active)) = chunks.active_voxel_heap.peek() {
-        if active.tick > current_tick {
-            break;
-        }
-        let Reverse(active) = chunks.active_voxel_heap.pop().unwrap();
-        match chunks.active_voxel_set.get(&active.voxel).copied() {
-            Some(scheduled) if scheduled == active.tick => {
-                chunks.active_voxel_set.remove(&active.voxel);
-                due.push(active.voxel);
-            }
-            _ => {}
+fn spawn_falling_block(lazy: &LazyUpdate, entities: &Entities, voxel: &Vec3<i32>, raw: u32) {
+    let Vec3(vx, vy, vz) = *voxel;
+    let (px, py, pz) = (vx as f32 + 0.5, vy as f32 + 0.5, vz as f32 + 0.5);
+    let mut body = FallingBlockComp::body();
+    body.set_position(px, py, pz);
+
+    let entity = entities.create();
+    lazy.insert(entity, IDComp::new(&nanoid!()));
+    lazy.insert(entity, EntityFlag);
+    lazy.insert(entity, ETypeComp::new(FALLING_BLOCK_ETYPE, false));
+    lazy.insert(entity, MetadataComp::new());
+    lazy.insert(entity, CurrentChunkComp::default());
+    lazy.insert(entity, CollisionsComp::new());
+    lazy.insert(entity, PositionComp::new(px, py, pz));
+    lazy.insert(entity, RigidBodyComp::new(&body));
+    lazy.insert(entity, FallingBlockComp::new(raw));
+}
+
+/// Whether every chunk `bulk` writes to, and every chunk its light reaches,
+/// is ready, so the update pass can commit all of it at once.
+fn is_bulk_update_ready(
+    chunks: &Chunks,
+    config: &WorldConfig,
+    registry: &Registry,
+    bulk: &BulkUpdate,
+) -> bool {
+    let mut touched = HashSet::new();
+    for (Vec3(vx, vy, vz), raw) in &bulk.0 {
+        let is_valid = *vy >= 0
+            && *vy < config.max_height as i32
+            && registry.has_type(BlockUtils::extract_id(*raw));
+        if !is_valid {
+            continue;
         }
+        let coords = ChunkUtils::map_voxel_to_chunk(*vx, *vy, *vz, config.chunk_size);
+        touched.insert(coords);
     }
+
+    touched.iter().all(|coords| {
+        chunks
+            .light_traversed_chunks(coords)
+            .iter()
+            .all(|n| chunks.is_chunk_ready(n))
+    })
+}
+
+fn collect_due_active_voxels(chunks: &mut Chunks, current_tick: u64) -> Vec<Vec3<i32>> {
+    let mut due = chunks.take_due_active_voxels(current_tick);
     due.sort_by(|a, b| (a.0, a.1, a.2).cmp(&(b.0, b.1, b.2)));
     due
 }
@@ -374,17 +422,35 @@ fn process_pending_updates(
 
     chunks.flush_staged_updates();
 
-    if chunks.updates.is_empty() {
+    if chunks.updates.is_empty() && chunks.bulk_updates.is_empty() {
         return results;
     }
 
     let total_updates = chunks.updates.len();
     let num_to_process = max_updates.min(total_updates);
 
+    let mut batch = Vec::with_capacity(num_to_process);
+    for _ in 0..num_to_process {
+        batch.push(chunks.pop_update().unwrap());
+    }
+    // Bulk updates ride along whole, past the budget, and after the ordinary
+    // updates so they win any voxel both touch. One that touches a chunk not
+    // ready to take it waits for a later pass in its entirety.
+    let mut waiting_bulks = Vec::new();
+    for bulk in chunks.take_bulk_updates() {
+        if is_bulk_update_ready(chunks, config, registry, &bulk) {
+            batch.extend(bulk.0);
+        } else {
+            waiting_bulks.push(bulk);
+        }
+    }
+    for bulk in waiting_bulks.into_iter().rev() {
+        chunks.requeue_bulk_update(bulk);
+    }
+
     let mut updates_by_chunk: HashMap<Vec2<i32>, Vec<(Vec3<i32>, u32)>> = HashMap::new();
 
-    for _ in 0..num_to_process {
-        let (voxel, raw) = chunks.updates.pop_front().unwrap();
+    for (voxel, raw) in batch {
         let Vec3(vx, vy, vz) = voxel;
 
         let updated_id = BlockUtils::extract_id(raw);
@@ -412,7 +478,7 @@ fn process_pending_updates(
     for (coords, chunk_updates) in updates_by_chunk {
         if !chunks.is_chunk_ready(&coords) {
             for (voxel, raw) in chunk_updates.into_iter().rev() {
-                chunks.updates.push_front((voxel, raw));
+                chunks.requeue_update(voxel, raw);
             }
             continue;
         }
@@ -424,7 +490,7 @@ fn process_pending_updates(
 
         if !neighbors_ready {
             for (voxel, raw) in chunk_updates.into_iter().rev() {
-                chunks.updates.push_front((voxel, raw));
+                chunks.requeue_update(voxel, raw);
             }
             continue;
         }
@@ -1032,6 +1098,8 @@ impl<'a> System<'a> for ChunkUpdatingSystem {
         ReadExpect<'a, Stats>,
         ReadExpect<'a, ChunkInterests>,
         WriteExpect<'a, MessageQueues>,
+        WriteExpect<'a, ReplayRecorder>,
+        WriteExpect<'a, Events>,
         WriteExpect<'a, Chunks>,
         WriteExpect<'a, Mesher>,
         ReadExpect<'a, LazyUpdate>,
@@ -1046,6 +1114,8 @@ impl<'a> System<'a> for ChunkUpdatingSystem {
             stats,
             interests,
             mut message_queue,
+            mut replay_recorder,
+            mut events,
             mut chunks,
             mut mesher,
             lazy,
@@ -1062,9 +1132,18 @@ impl<'a> System<'a> for ChunkUpdatingSystem {
         // updaters see earlier state changes (water cascade semantics), while
         // lighting, persistence, replication, and remeshing flush once.
         let mut active_overlay = HashMap::new();
+        let mut reactions = Vec::new();
+        let mut falling = Vec::new();
         let due_voxels = collect_due_active_voxels(&mut chunks, current_tick);
         for voxel in &due_voxels {
-            apply_active_updates(&chunks, &mut active_overlay, &registry, voxel);
+            apply_active_updates(
+                &chunks,
+                &mut active_overlay,
+                &registry,
+                voxel,
+                &mut reactions,
+                &mut falling,
+            );
         }
 
         // Subchunk random-tick sampler (plants). Runs AFTER the
@@ -1076,13 +1155,38 @@ impl<'a> System<'a> for ChunkUpdatingSystem {
 
         let random_due = collect_due_active_voxels(&mut chunks, current_tick);
         for voxel in &random_due {
-            apply_active_updates(&chunks, &mut active_overlay, &registry, voxel);
+            apply_active_updates(
+                &chunks,
+                &mut active_overlay,
+                &registry,
+                voxel,
+                &mut reactions,
+                &mut falling,
+            );
         }
 
         let mut active_updates = active_overlay.into_iter().collect::<Vec<_>>();
         active_updates.sort_by_key(|(voxel, _)| (voxel.0, voxel.1, voxel.2));
         chunks.update_voxels(&active_updates);
 
+        for (voxel, raw) in &falling {
+            spawn_falling_block(&lazy, &entities, voxel, *raw);
+        }
+
+        for reaction in reactions {
+            let Some(sound) = reaction.sound else {
+                continue;
+            };
+            let Vec3(vx, vy, vz) = reaction.voxel;
+            let payload = SoundEffectEvent::new(&sound).position([
+                vx as f32 + 0.5,
+                vy as f32 + 0.5,
+                vz as f32 + 0.5,
+            ]);
+            let location = ChunkUtils::map_voxel_to_chunk(vx, vy, vz, config.chunk_size);
+            events.dispatch(Event::sound_effect(payload).location(location).build());
+        }
+
         let all_results = process_pending_updates(
             &mut chunks,
             &mut mesher,
@@ -1105,6 +1209,16 @@ impl<'a> System<'a> for ChunkUpdatingSystem {
                 (a.vx, a.vy, a.vz).cmp(&(b.vx, b.vy, b.vz))
             });
 
+            // Recordings keep every applied update, whoever could see it.
+            if replay_recorder.is_recording() {
+                let tick = stats.dispatch_count();
+                let message = Message::new(&MessageType::Update)
+                    .updates(&all_results)
+                    .tick(tick)
+                    .build();
+                replay_recorder.record_message(tick, &message);
+            }
+
             // Route each update only to clients whose chunk interest covers a
             // chunk the update can affect: the updated chunk itself or any
             // chunk within the light-spill ring, since an edge update can
@@ -1146,3 +1260,239 @@ impl<'a> System<'a> for ChunkUpdatingSystem {
         }
     }
 }
+
+#[cfg(test)]
+mod fluid_interaction_tests {
+    use specs::RunNow;
+
+    use crate::{
+        Block, Chunk, Chunks, Events, FluidConfig, FluidContact, FluidInteraction, Registry,
+        SoundEffectEvent, Vec3, VoxelAccess, VoxelPacker, World, WorldConfig,
+        VOXELIZE_BUILTIN_SOUND_EFFECT_EVENT,
+    };
+
+    use super::ChunkUpdatingSystem;
+
+    const WATER: u32 = 1;
+    const LAVA: u32 = 2;
+    const OBSIDIAN: u32 = 3;
+
+    #[test]
+    fn a_reacting_fluid_commits_its_result_and_queues_its_sound() {
+        let config = WorldConfig::new()
+            .min_chunk([0, 0])
+            .max_chunk([0, 0])
+            .build();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Water")
+                .id(WATER)
+                .fluid_simulation(FluidConfig::new())
+                .build(),
+            Block::new("Lava")
+                .id(LAVA)
+                .fluid_simulation(FluidConfig::new())
+                .build(),
+            Block::new("Obsidian").id(OBSIDIAN).build(),
+        ]);
+        registry.add_fluid_interaction(
+            FluidInteraction::new(LAVA, FluidContact::Block(WATER))
+                .result(OBSIDIAN)
+                .sound("fizz"),
+        );
+
+        let mut world = World::new("fluid-interactions", &config);
+        world.ecs_mut().insert(registry);
+
+        {
+            let mut chunks = world.write_resource::<Chunks>();
+            chunks.add(Chunk::ready(0, 0, &config));
+            chunks.set_raw_voxel(4, 10, 4, VoxelPacker::new().with_id(LAVA).pack());
+            chunks.set_raw_voxel(5, 10, 4, VoxelPacker::new().with_id(WATER).pack());
+            chunks.mark_voxel_active(&Vec3(4, 10, 4), 0);
+        }
+
+        ChunkUpdatingSystem.run_now(world.ecs());
+
+        assert_eq!(world.chunks().get_voxel(4, 10, 4), OBSIDIAN);
+        assert_eq!(world.chunks().get_voxel(5, 10, 4), WATER);
+
+        let events = world.read_resource::<Events>();
+        assert_eq!(events.queue.len(), 1);
+        let event = &events.queue[0];
+        assert_eq!(event.name, VOXELIZE_BUILTIN_SOUND_EFFECT_EVENT);
+        let payload: SoundEffectEvent =
+            serde_json::from_str(event.payload.as_deref().unwrap()).unwrap();
+        assert_eq!(payload.id, "fizz");
+        assert_eq!(payload.position, Some([4.5, 10.5, 4.5]));
+    }
+}
+
+#[cfg(test)]
+mod falling_block_tests {
+    use specs::{Join, RunNow, WorldExt};
+
+    use crate::{
+        Block, Chunk, Chunks, FallingBlockComp, PositionComp, Registry, Vec3, VoxelAccess, World,
+        WorldConfig,
+    };
+
+    use super::ChunkUpdatingSystem;
+
+    const STONE: u32 = 1;
+    const SAND: u32 = 2;
+
+    fn world() -> World {
+        let config = WorldConfig::new()
+            .min_chunk([0, 0])
+            .max_chunk([0, 0])
+            .build();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Stone").id(STONE).build(),
+            Block::new("Sand").id(SAND).is_gravity_affected(true).build(),
+        ]);
+
+        let mut world = World::new("falling-blocks", &config);
+        world.ecs_mut().insert(registry);
+
+        world
+            .write_resource::<Chunks>()
+            .add(Chunk::ready(0, 0, &config));
+        world
+    }
+
+    fn falling_blocks(world: &World) -> Vec<(u32, Vec3<f32>)> {
+        let falling = world.ecs().read_storage::<FallingBlockComp>();
+        let positions = world.ecs().read_storage::<PositionComp>();
+        (&falling, &positions)
+            .join()
+            .map(|(falling, position)| (falling.raw, position.0.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn an_unsupported_gravity_block_leaves_as_a_falling_entity() {
+        let mut world = world();
+        {
+            let mut chunks = world.write_resource::<Chunks>();
+            chunks.set_voxel(4, 10, 4, SAND);
+            chunks.mark_voxel_active(&Vec3(4, 10, 4), 0);
+        }
+
+        ChunkUpdatingSystem.run_now(world.ecs());
+        world.ecs_mut().maintain();
+
+        assert_eq!(world.chunks().get_voxel(4, 10, 4), 0);
+        assert_eq!(falling_blocks(&world), vec![(SAND, Vec3(4.5, 10.5, 4.5))]);
+    }
+
+    #[test]
+    fn a_supported_gravity_block_stays_put() {
+        let mut world = world();
+        {
+            let mut chunks = world.write_resource::<Chunks>();
+            chunks.set_voxel(4, 9, 4, STONE);
+            chunks.set_voxel(4, 10, 4, SAND);
+            chunks.mark_voxel_active(&Vec3(4, 10, 4), 0);
+        }
+
+        ChunkUpdatingSystem.run_now(world.ecs());
+        world.ecs_mut().maintain();
+
+        assert_eq!(world.chunks().get_voxel(4, 10, 4), SAND);
+        assert!(falling_blocks(&world).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod bulk_update_tests {
+    use specs::RunNow;
+
+    use crate::{
+        Block, BulkUpdate, Chunk, ChunkStatus, Chunks, Registry, Vec2, Vec3, VoxelAccess, World,
+        WorldConfig,
+    };
+
+    use super::ChunkUpdatingSystem;
+
+    const STONE: u32 = 1;
+
+    #[test]
+    fn a_bulk_update_commits_whole_past_the_update_budget() {
+        let config = WorldConfig::new()
+            .min_chunk([0, 0])
+            .max_chunk([0, 0])
+            .max_updates_per_tick(4)
+            .build();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[Block::new("Stone").id(STONE).build()]);
+
+        let mut world = World::new("bulk-updates", &config);
+        world.ecs_mut().insert(registry);
+
+        {
+            let mut chunks = world.write_resource::<Chunks>();
+            chunks.add(Chunk::ready(0, 0, &config));
+            for x in 0..10 {
+                chunks.set_voxel(x, 10, 0, STONE);
+            }
+            chunks.update_voxels(&[(Vec3(0, 20, 0), STONE)]);
+            chunks.update_voxels_bulk(BulkUpdate((0..10).map(|x| (Vec3(x, 10, 0), 0)).collect()));
+        }
+
+        ChunkUpdatingSystem.run_now(world.ecs());
+
+        let chunks = world.chunks();
+        for x in 0..10 {
+            assert_eq!(chunks.get_voxel(x, 10, 0), 0, "voxel {x} was left standing");
+        }
+        assert_eq!(chunks.get_voxel(0, 20, 0), STONE);
+        assert_eq!(chunks.pending_updates_count(), 0);
+    }
+
+    #[test]
+    fn a_bulk_update_waits_whole_for_every_chunk_it_touches() {
+        let config = WorldConfig::new()
+            .min_chunk([0, 0])
+            .max_chunk([1, 0])
+            .build();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[Block::new("Stone").id(STONE).build()]);
+
+        let mut world = World::new("bulk-updates", &config);
+        world.ecs_mut().insert(registry);
+
+        let bulk: Vec<_> = (10..20).map(|x| (Vec3(x, 10, 0), STONE)).collect();
+        {
+            let mut chunks = world.write_resource::<Chunks>();
+            chunks.add(Chunk::ready(0, 0, &config));
+            let mut meshing = Chunk::ready(1, 0, &config);
+            meshing.status = ChunkStatus::Meshing;
+            chunks.add(meshing);
+            chunks.update_voxels_bulk(BulkUpdate(bulk.clone()));
+        }
+
+        ChunkUpdatingSystem.run_now(world.ecs());
+
+        {
+            let chunks = world.chunks();
+            assert_eq!(chunks.get_voxel(10, 10, 0), 0, "half the bulk landed early");
+            assert_eq!(chunks.bulk_updates.len(), 1);
+            assert_eq!(chunks.bulk_updates[0].0, bulk);
+        }
+
+        world.chunks_mut().raw_mut(&Vec2(1, 0)).unwrap().status = ChunkStatus::Ready;
+        ChunkUpdatingSystem.run_now(world.ecs());
+
+        let chunks = world.chunks();
+        for x in 10..20 {
+            assert_eq!(chunks.get_voxel(x, 10, 0), STONE, "voxel {x} was never placed");
+        }
+        assert_eq!(chunks.pending_updates_count(), 0);
+    }
+}
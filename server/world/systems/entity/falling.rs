@@ -0,0 +1,199 @@
+use specs::{Entities, Join, ReadExpect, ReadStorage, System, WriteExpect};
+
+use crate::{
+    BlockUtils, ChunkUtils, Chunks, Event, Events, FallingBlockComp, ItemDropEvent, Registry,
+    RigidBodyComp, SlotContent, Vec3, VoxelAccess, WorldConfig,
+};
+
+/// Whether a landing block may take this voxel's place.
+fn is_replaceable(raw: u32, registry: &Registry) -> bool {
+    let id = BlockUtils::extract_id(raw);
+    registry.is_air(id) || registry.get_block_by_id(id).is_fluid
+}
+
+/// Lands falling blocks once physics has them resting on the ground: the
+/// carried voxel is placed where the body came to rest, or dropped as an item
+/// when something already occupies that voxel.
+pub struct FallingBlocksSystem;
+
+impl<'a> System<'a> for FallingBlocksSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, WorldConfig>,
+        ReadExpect<'a, Registry>,
+        WriteExpect<'a, Chunks>,
+        WriteExpect<'a, Events>,
+        ReadStorage<'a, FallingBlockComp>,
+        ReadStorage<'a, RigidBodyComp>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, config, registry, mut chunks, mut events, falling_blocks, bodies) = data;
+
+        for (entity, falling_block, body) in (&entities, &falling_blocks, &bodies).join() {
+            let Vec3(vx, vy, vz) = body.0.get_voxel_position();
+
+            if vy < 0 {
+                entities.delete(entity).ok();
+                continue;
+            }
+
+            if body.0.at_rest_y() >= 0 {
+                continue;
+            }
+
+            // Blocks landing on a block placed this tick stack on top of it
+            // instead of overwriting it before it reaches the world.
+            let mut target = Vec3(vx, vy, vz);
+            while chunks
+                .pending_raw_voxel(&target)
+                .is_some_and(|raw| !is_replaceable(raw, &registry))
+            {
+                target.1 += 1;
+            }
+
+            let current = chunks
+                .pending_raw_voxel(&target)
+                .unwrap_or_else(|| chunks.get_raw_voxel(target.0, target.1, target.2));
+
+            if target.1 < config.max_height as i32 && is_replaceable(current, &registry) {
+                chunks.update_voxel(&target, falling_block.raw);
+            } else {
+                let Vec3(px, py, pz) = body.0.get_position();
+                let payload =
+                    ItemDropEvent::new(SlotContent::block(falling_block.id, 1), [px, py, pz]);
+                let location =
+                    ChunkUtils::map_voxel_to_chunk(target.0, target.1, target.2, config.chunk_size);
+                events.dispatch(Event::item_drop(payload).location(location).build());
+            }
+
+            entities.delete(entity).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, RunNow, WorldExt};
+
+    use super::*;
+    use crate::{Block, Chunk, World, VOXELIZE_BUILTIN_ITEM_DROP_EVENT};
+
+    const STONE: u32 = 1;
+    const SAND: u32 = 2;
+    const TORCH: u32 = 3;
+
+    fn world() -> World {
+        let config = WorldConfig::new()
+            .min_chunk([0, 0])
+            .max_chunk([0, 0])
+            .build();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Stone").id(STONE).build(),
+            Block::new("Sand")
+                .id(SAND)
+                .is_gravity_affected(true)
+                .build(),
+            Block::new("Torch").id(TORCH).is_passable(true).build(),
+        ]);
+
+        let mut world = World::new("falling-blocks-landing", &config);
+        world.ecs_mut().insert(registry);
+
+        world
+            .write_resource::<Chunks>()
+            .add(Chunk::ready(0, 0, &config));
+        world
+    }
+
+    fn land(world: &mut World, x: f32, y: f32, z: f32) {
+        let mut body = FallingBlockComp::body();
+        body.set_position(x, y, z);
+        body.resting = Vec3(0, -1, 0);
+        world
+            .ecs_mut()
+            .create_entity()
+            .with(RigidBodyComp::new(&body))
+            .with(FallingBlockComp::new(SAND))
+            .build();
+    }
+
+    fn falling_block_count(world: &World) -> usize {
+        (&world.ecs().read_storage::<FallingBlockComp>())
+            .join()
+            .count()
+    }
+
+    #[test]
+    fn a_resting_falling_block_places_its_voxel_and_despawns() {
+        let mut world = world();
+        world.write_resource::<Chunks>().set_voxel(4, 5, 4, STONE);
+        land(&mut world, 4.5, 6.5, 4.5);
+
+        FallingBlocksSystem.run_now(world.ecs());
+        world.ecs_mut().maintain();
+
+        assert_eq!(world.chunks().pending_raw_voxel(&Vec3(4, 6, 4)), Some(SAND));
+        assert_eq!(falling_block_count(&world), 0);
+        assert!(world.read_resource::<Events>().queue.is_empty());
+    }
+
+    #[test]
+    fn two_blocks_landing_together_stack() {
+        let mut world = world();
+        world.write_resource::<Chunks>().set_voxel(4, 5, 4, STONE);
+        land(&mut world, 4.5, 6.5, 4.5);
+        land(&mut world, 4.5, 6.5, 4.5);
+
+        FallingBlocksSystem.run_now(world.ecs());
+
+        let chunks = world.chunks();
+        assert_eq!(chunks.pending_raw_voxel(&Vec3(4, 6, 4)), Some(SAND));
+        assert_eq!(chunks.pending_raw_voxel(&Vec3(4, 7, 4)), Some(SAND));
+    }
+
+    #[test]
+    fn a_block_landing_in_an_occupied_voxel_drops_an_item() {
+        let mut world = world();
+        {
+            let mut chunks = world.write_resource::<Chunks>();
+            chunks.set_voxel(4, 5, 4, STONE);
+            chunks.set_voxel(4, 6, 4, TORCH);
+        }
+        land(&mut world, 4.5, 6.5, 4.5);
+
+        FallingBlocksSystem.run_now(world.ecs());
+        world.ecs_mut().maintain();
+
+        assert_eq!(world.chunks().pending_raw_voxel(&Vec3(4, 6, 4)), None);
+        assert_eq!(world.chunks().get_voxel(4, 6, 4), TORCH);
+        assert_eq!(falling_block_count(&world), 0);
+
+        let events = world.read_resource::<Events>();
+        assert_eq!(events.queue.len(), 1);
+        assert_eq!(events.queue[0].name, VOXELIZE_BUILTIN_ITEM_DROP_EVENT);
+        let payload: ItemDropEvent =
+            serde_json::from_str(events.queue[0].payload.as_deref().unwrap()).unwrap();
+        assert_eq!(payload.content, SlotContent::block(SAND, 1));
+    }
+
+    #[test]
+    fn a_block_still_in_the_air_keeps_falling() {
+        let mut world = world();
+        let mut body = FallingBlockComp::body();
+        body.set_position(4.5, 20.5, 4.5);
+        world
+            .ecs_mut()
+            .create_entity()
+            .with(RigidBodyComp::new(&body))
+            .with(FallingBlockComp::new(SAND))
+            .build();
+
+        FallingBlocksSystem.run_now(world.ecs());
+        world.ecs_mut().maintain();
+
+        assert_eq!(falling_block_count(&world), 1);
+    }
+}
@@ -0,0 +1,46 @@
+use std::ops::Deref;
+
+use specs::{Entities, Join, ReadExpect, ReadStorage, System, WriteExpect};
+
+use crate::{
+    Chunks, DynamicLights, LightEmitterComp, PositionComp, Registry, Stats, Vec3, WorldConfig,
+};
+
+/// Keeps the dynamic light overlays in step with the light emitters, after
+/// physics and projectiles have moved them for the tick.
+pub struct DynamicLightsSystem;
+
+impl<'a> System<'a> for DynamicLightsSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, WorldConfig>,
+        ReadExpect<'a, Registry>,
+        ReadExpect<'a, Chunks>,
+        ReadExpect<'a, Stats>,
+        WriteExpect<'a, DynamicLights>,
+        ReadStorage<'a, PositionComp>,
+        ReadStorage<'a, LightEmitterComp>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, config, registry, chunks, stats, mut lights, positions, emitters) = data;
+
+        let emitters: Vec<_> = (&entities, &positions, &emitters)
+            .join()
+            .filter(|(_, _, emitter)| !emitter.is_dark())
+            .map(|(entity, position, emitter)| {
+                let Vec3(px, py, pz) = position.0;
+                let voxel = Vec3(px.floor() as i32, py.floor() as i32, pz.floor() as i32);
+                (entity, voxel, emitter.clone())
+            })
+            .collect();
+
+        lights.update(
+            &emitters,
+            stats.dispatch_count(),
+            chunks.deref(),
+            &registry,
+            &config,
+        );
+    }
+}
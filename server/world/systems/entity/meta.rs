@@ -2,7 +2,8 @@ use serde_json::json;
 use specs::{ReadStorage, System, WriteStorage};
 
 use crate::world::components::{
-    DirectionComp, EntityFlag, JsonComp, MetadataComp, PositionComp, RigidBodyComp, VoxelComp,
+    AttachmentComp, DirectionComp, EntityFlag, FallingBlockComp, JsonComp, LightEmitterComp,
+    MetadataComp, PositionComp, RigidBodyComp, VoxelComp,
 };
 
 pub struct EntitiesMetaSystem;
@@ -15,14 +16,28 @@ impl<'a> System<'a> for EntitiesMetaSystem {
         ReadStorage<'a, RigidBodyComp>,
         ReadStorage<'a, VoxelComp>,
         ReadStorage<'a, JsonComp>,
+        ReadStorage<'a, FallingBlockComp>,
+        ReadStorage<'a, AttachmentComp>,
+        ReadStorage<'a, LightEmitterComp>,
         WriteStorage<'a, MetadataComp>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         use rayon::prelude::*;
-        use specs::ParJoin;
+        use specs::{LendJoin, ParJoin};
 
-        let (flag, positions, directions, rigid_bodies, voxels, jsons, mut metadatas) = data;
+        let (
+            flag,
+            positions,
+            directions,
+            rigid_bodies,
+            voxels,
+            jsons,
+            falling_blocks,
+            attachments,
+            emitters,
+            mut metadatas,
+        ) = data;
 
         (&positions, &mut metadatas, &flag)
             .par_join()
@@ -54,5 +69,29 @@ impl<'a> System<'a> for EntitiesMetaSystem {
                 metadata.set("voxel", voxel);
                 metadata.set("json", json);
             });
+
+        (&falling_blocks, &mut metadatas, &flag)
+            .par_join()
+            .for_each(|(falling_block, metadata, _)| {
+                metadata.set("fallingBlock", falling_block);
+            });
+
+        (&mut metadatas, (&attachments).maybe(), &flag)
+            .par_join()
+            .for_each(|(metadata, attachment, _)| match attachment {
+                Some(attachment) => metadata.set("attachment", attachment),
+                None => {
+                    metadata.remove("attachment");
+                }
+            });
+
+        (&mut metadatas, (&emitters).maybe(), &flag)
+            .par_join()
+            .for_each(|(metadata, emitter, _)| match emitter {
+                Some(emitter) => metadata.set("lightEmitter", emitter),
+                None => {
+                    metadata.remove("lightEmitter");
+                }
+            });
     }
 }
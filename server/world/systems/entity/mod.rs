@@ -1,5 +1,11 @@
+mod falling;
+mod lights;
 mod meta;
+mod projectile;
 mod sending;
 
+pub use falling::*;
+pub use lights::*;
 pub use meta::*;
+pub use projectile::*;
 pub use sending::*;
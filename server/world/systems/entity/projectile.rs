@@ -0,0 +1,312 @@
+use std::ops::Deref;
+
+use hashbrown::HashMap;
+use specs::{Entities, Join, LendJoin, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
+
+use crate::{
+    ray_box_entry, trace_solid_hit, Chunks, CurrentChunkComp, DirectionComp, FixedStepState,
+    IDComp, KdTree, LagComp, PositionComp, ProjectileComp, ProjectileHit, ProjectileHits,
+    ProjectileTarget, Ray, Registry, RigidBody, RigidBodyComp, Stats, Vec3, WorldConfig,
+};
+
+/// Most voxel bounces resolved within one tick, so a projectile wedged in a
+/// corner cannot spin the system.
+const MAX_BOUNCES_PER_TICK: usize = 4;
+
+/// How far off a face a bounced projectile restarts, so its next sweep does
+/// not hit the same face at zero distance.
+const BOUNCE_SEAM: f32 = 1e-3;
+
+/// How far the entity tree may place a body from where it is: the tree only
+/// moves a body once it strays this far from its indexed position.
+const TREE_SLACK: f32 = 0.1;
+
+/// How far a live entity's hitbox reaches from its position: around its
+/// compound shape when it has one, its box otherwise.
+fn hitbox_radius(body: &RigidBody) -> f32 {
+    let center = body.get_position();
+    let boxes = match &body.shape {
+        Some(shape) if !shape.parts.is_empty() => shape.world_aabbs(&center, body.yaw),
+        _ => vec![body.aabb.clone()],
+    };
+
+    boxes
+        .iter()
+        .map(|aabb| {
+            let x = (aabb.max_x - center.0).max(center.0 - aabb.min_x);
+            let y = (aabb.max_y - center.1).max(center.1 - aabb.min_y);
+            let z = (aabb.max_z - center.2).max(center.2 - aabb.min_z);
+            (x * x + y * y + z * z).sqrt()
+        })
+        .fold(0.0, f32::max)
+}
+
+/// Entry distance of a unit ray into a live entity's hitbox: its compound
+/// shape when it has one, its box otherwise.
+fn hitbox_entry(body: &RigidBody, origin: &Vec3<f32>, unit: &Vec3<f32>, max: f32) -> Option<f32> {
+    match &body.shape {
+        Some(shape) if !shape.parts.is_empty() => shape
+            .ray_entry(&body.get_position(), body.yaw, origin, unit)
+            .filter(|&t| t <= max),
+        _ => ray_box_entry(origin, unit, &body.aabb, max),
+    }
+}
+
+/// Flies projectiles: integrates gravity and drag, then sweeps the segment
+/// each one travels this tick against voxels and entity hitboxes, recording
+/// every hit in [`ProjectileHits`] for the world's hit handle. Entity hits
+/// are checked up to the first voxel in the way, so nothing is hit through
+/// walls, and only against the bodies the [`KdTree`] finds near the path.
+pub struct ProjectilesSystem;
+
+impl<'a> System<'a> for ProjectilesSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, WorldConfig>,
+        ReadExpect<'a, Registry>,
+        ReadExpect<'a, Chunks>,
+        ReadExpect<'a, Stats>,
+        ReadExpect<'a, KdTree>,
+        Option<ReadExpect<'a, LagComp>>,
+        Option<ReadExpect<'a, FixedStepState>>,
+        WriteExpect<'a, ProjectileHits>,
+        ReadStorage<'a, IDComp>,
+        ReadStorage<'a, CurrentChunkComp>,
+        ReadStorage<'a, RigidBodyComp>,
+        WriteStorage<'a, ProjectileComp>,
+        WriteStorage<'a, PositionComp>,
+        WriteStorage<'a, DirectionComp>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            config,
+            registry,
+            chunks,
+            stats,
+            tree,
+            lag_comp,
+            fixed_step,
+            mut hits,
+            ids,
+            curr_chunks,
+            bodies,
+            mut projectiles,
+            mut positions,
+            mut directions,
+        ) = data;
+
+        let delta = stats.delta;
+        let [gx, gy, gz] = config.gravity;
+        let current_tick = fixed_step.as_ref().map(|state| state.clock.step_count());
+
+        // Shooters are skipped by their entity index, the same key rewound
+        // queries and the pierced set use.
+        let shooter_ids: Vec<_> = (&projectiles)
+            .join()
+            .filter_map(|projectile| projectile.shooter.as_deref())
+            .collect();
+        let shooters: HashMap<_, _> = if shooter_ids.is_empty() {
+            HashMap::new()
+        } else {
+            (&entities, &ids)
+                .join()
+                .filter(|(_, id)| shooter_ids.contains(&id.0.as_str()))
+                .map(|(entity, id)| (id.0.clone(), entity.id() as u64))
+                .collect()
+        };
+
+        // The tree indexes bodies by position, as of the last time it ran, so
+        // a path's search reaches as far as the largest hitbox plus how far
+        // any body could have moved since.
+        let body_reach = if (&projectiles).join().next().is_none() {
+            0.0
+        } else {
+            (&bodies)
+                .join()
+                .map(|body| hitbox_radius(&body.0) + body.0.velocity.len() * delta)
+                .fold(0.0, f32::max)
+                + TREE_SLACK
+        };
+
+        for (entity, id, curr_chunk, projectile, position, direction) in (
+            &entities,
+            &ids,
+            &curr_chunks,
+            &mut projectiles,
+            &mut positions,
+            (&mut directions).maybe(),
+        )
+            .join()
+        {
+            if !chunks.is_chunk_ready(&curr_chunk.coords) {
+                continue;
+            }
+
+            projectile.age += delta;
+            if projectile.age >= projectile.lifetime {
+                entities.delete(entity).ok();
+                continue;
+            }
+
+            let multiplier = projectile.gravity_multiplier * delta;
+            projectile.velocity = projectile
+                .velocity
+                .add(&Vec3(gx * multiplier, gy * multiplier, gz * multiplier))
+                .scale((-projectile.drag * delta).exp());
+
+            let mut exclude: Vec<u64> = projectile.pierced.iter().copied().collect();
+            if let Some(shooter) = projectile
+                .shooter
+                .as_ref()
+                .and_then(|shooter| shooters.get(shooter))
+            {
+                exclude.push(*shooter);
+            }
+
+            let mut origin = position.0.clone();
+            let mut remaining = delta;
+            let mut stopped = false;
+
+            for _ in 0..MAX_BOUNCES_PER_TICK {
+                let travel = projectile.velocity.scale(remaining);
+                let length = travel.len();
+                if length <= f32::EPSILON {
+                    break;
+                }
+                let unit = travel.scale(1.0 / length);
+
+                let voxel_hit = trace_solid_hit(chunks.deref(), &registry, &origin, &unit, length);
+                let reach = voxel_hit.as_ref().map_or(length, |hit| hit.distance);
+
+                // Entities along the way, nearest first, until one stops it.
+                loop {
+                    let entity_hit = match (&projectile.rewind, &lag_comp, current_tick) {
+                        (Some(anchor), Some(lag_comp), Some(tick)) => lag_comp
+                            .history()
+                            .resolve_ray_at_tick_excluding(
+                                &Ray {
+                                    origin: origin.to_arr(),
+                                    direction: unit.to_arr(),
+                                },
+                                projectile.hitbox,
+                                anchor.anchored_tick(tick),
+                                reach,
+                                &exclude,
+                            )
+                            .and_then(|hit| {
+                                let target = entities.entity(hit.entity as u32);
+                                entities.is_alive(target).then_some((
+                                    target,
+                                    hit.distance,
+                                    Some(hit.tick),
+                                ))
+                            }),
+                        _ => tree
+                            .all_within_radius(
+                                &origin.scale_and_add(&unit, reach / 2.0),
+                                reach / 2.0 + body_reach,
+                            )
+                            .into_iter()
+                            .map(|(_, target)| *target)
+                            .filter(|target| !exclude.contains(&(target.id() as u64)))
+                            .filter_map(|target| {
+                                let body = bodies.get(target)?;
+                                hitbox_entry(&body.0, &origin, &unit, reach)
+                                    .map(|distance| (target, distance, None))
+                            })
+                            .min_by(|a, b| a.1.total_cmp(&b.1)),
+                    };
+
+                    let Some((target, distance, rewound_tick)) = entity_hit else {
+                        break;
+                    };
+
+                    exclude.push(target.id() as u64);
+                    projectile.pierced.insert(target.id() as u64);
+
+                    stopped = projectile.pierce == 0;
+                    projectile.pierce = projectile.pierce.saturating_sub(1);
+
+                    hits.0.push(ProjectileHit {
+                        projectile: entity,
+                        projectile_id: id.0.clone(),
+                        shooter: projectile.shooter.clone(),
+                        target: ProjectileTarget::Entity {
+                            entity: target,
+                            id: ids.get(target).map(|id| id.0.clone()),
+                        },
+                        position: origin.scale_and_add(&unit, distance),
+                        velocity: projectile.velocity.clone(),
+                        stopped,
+                        rewound_tick,
+                    });
+
+                    if stopped {
+                        origin = origin.scale_and_add(&unit, distance);
+                        break;
+                    }
+                }
+
+                if stopped {
+                    break;
+                }
+
+                let Some(voxel_hit) = voxel_hit else {
+                    origin = origin.add(&travel);
+                    break;
+                };
+
+                let Vec3(nx, ny, nz) = voxel_hit.normal;
+                let normal = Vec3(nx as f32, ny as f32, nz as f32);
+                let at = origin.scale_and_add(&unit, voxel_hit.distance);
+
+                stopped = projectile.bounces == 0;
+                if !stopped {
+                    projectile.bounces -= 1;
+                    // Reflect the speed along the face's normal, losing what
+                    // restitution does not keep.
+                    let along = projectile.velocity.0 * normal.0
+                        + projectile.velocity.1 * normal.1
+                        + projectile.velocity.2 * normal.2;
+                    projectile.velocity = projectile
+                        .velocity
+                        .scale_and_add(&normal, -(1.0 + projectile.restitution) * along);
+                }
+
+                hits.0.push(ProjectileHit {
+                    projectile: entity,
+                    projectile_id: id.0.clone(),
+                    shooter: projectile.shooter.clone(),
+                    target: ProjectileTarget::Voxel {
+                        voxel: voxel_hit.voxel,
+                        normal: voxel_hit.normal,
+                    },
+                    position: at.clone(),
+                    velocity: projectile.velocity.clone(),
+                    stopped,
+                    rewound_tick: None,
+                });
+
+                origin = at.scale_and_add(&normal, BOUNCE_SEAM);
+                if stopped {
+                    break;
+                }
+                remaining *= 1.0 - voxel_hit.distance / length;
+            }
+
+            let Vec3(px, py, pz) = origin;
+            position.0.set(px, py, pz);
+
+            if stopped {
+                entities.delete(entity).ok();
+                continue;
+            }
+
+            if let (Some(direction), Some(heading)) = (direction, projectile.heading()) {
+                direction.0 = heading;
+            }
+        }
+    }
+}
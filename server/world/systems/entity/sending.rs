@@ -8,8 +8,8 @@ use crate::{
     BackgroundEntitiesSaver, Bookkeeping, ClientFilter, Clients, DirectionComp, DoNotPersistComp,
     ETypeComp, EntityFlag, EntityIDs, EntityOperation, EntityProtocol, IDComp, InteractorComp,
     InterestTransition, KdTree, Message, MessageQueues, MessageType, MetadataComp, MotionSample,
-    Physics, PositionComp, QuantizedMotion, ReplicatedStateBuffer, RigidBodyComp, Stats,
-    TargetComp, Vec3, VoxelComp, WorldConfig, METADATA_MAX_AGE_MS,
+    Physics, PositionComp, QuantizedMotion, ReplayRecorder, ReplicatedStateBuffer, RigidBodyComp,
+    Stats, TargetComp, Vec3, VoxelComp, WorldConfig, METADATA_MAX_AGE_MS,
 };
 
 const BLOCK_ENTITY_PREFIX: &str = "block::";
@@ -29,6 +29,43 @@ struct ClientUpdates {
     staged_bytes: usize,
 }
 
+/// This tick's entity changes as the whole world sees them, with full
+/// metadata and no interest filtering: what a replay records.
+fn world_entity_changes<'a>(
+    deleted: &[(String, String, String)],
+    records: &HashMap<String, (String, Entity, String, bool)>,
+    new_ids: &HashSet<String>,
+    changed_ids: impl Iterator<Item = &'a String>,
+) -> Vec<EntityProtocol> {
+    let mut operations: Vec<EntityProtocol> = deleted
+        .iter()
+        .map(|(id, etype, metadata)| EntityProtocol {
+            operation: EntityOperation::Delete,
+            id: id.clone(),
+            r#type: etype.clone(),
+            metadata: Some(metadata.clone()),
+            motion: None,
+        })
+        .collect();
+
+    let changed: HashSet<&String> = changed_ids.filter(|id| !new_ids.contains(*id)).collect();
+    let created = new_ids.iter().map(|id| (id, EntityOperation::Create));
+    let updated = changed.into_iter().map(|id| (id, EntityOperation::Update));
+    for (id, operation) in created.chain(updated) {
+        if let Some((etype, _, json, _)) = records.get(id) {
+            operations.push(EntityProtocol {
+                operation,
+                id: id.clone(),
+                r#type: etype.clone(),
+                metadata: Some(json.clone()),
+                motion: None,
+            });
+        }
+    }
+
+    operations
+}
+
 impl<'a> System<'a> for EntitiesSendingSystem {
     type SystemData = (
         Entities<'a>,
@@ -43,6 +80,7 @@ impl<'a> System<'a> for EntitiesSendingSystem {
         WriteExpect<'a, Bookkeeping>,
         WriteExpect<'a, Physics>,
         WriteExpect<'a, EntityIDs>,
+        WriteExpect<'a, ReplayRecorder>,
         ReadStorage<'a, EntityFlag>,
         ReadStorage<'a, IDComp>,
         ReadStorage<'a, ETypeComp>,
@@ -70,6 +108,7 @@ impl<'a> System<'a> for EntitiesSendingSystem {
             mut bookkeeping,
             mut physics,
             mut entity_ids,
+            mut replay_recorder,
             flags,
             ids,
             etypes,
@@ -235,6 +274,25 @@ impl<'a> System<'a> for EntitiesSendingSystem {
             );
         }
 
+        if replay_recorder.is_recording() {
+            let operations = world_entity_changes(
+                &deleted_entities,
+                &new_bookkeeping_records,
+                &self.new_entity_ids_buffer,
+                changed_motion
+                    .keys()
+                    .chain(changed_non_motion.keys())
+                    .chain(changed_metadata_ids.iter()),
+            );
+            if !operations.is_empty() {
+                let message = Message::new(&MessageType::Entity)
+                    .entities(&operations)
+                    .tick(tick)
+                    .build();
+                replay_recorder.record_message(tick, &message);
+            }
+        }
+
         let mut client_updates: HashMap<String, ClientUpdates> = HashMap::new();
 
         for (id, etype, metadata) in &deleted_entities {
@@ -591,7 +649,7 @@ fn log_state_batch_queue(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{decode_motion, Client, MotionProtocol, WorldConfig, WsSender};
+    use crate::{decode_motion, Client, MeshFormat, MotionProtocol, WorldConfig, WsSender};
     use serde_json::json;
     use specs::{Builder, RunNow, World, WorldExt};
 
@@ -632,6 +690,7 @@ mod tests {
         world.insert(Bookkeeping::new());
         world.insert(Physics::new());
         world.insert(EntityIDs::new());
+        world.insert(ReplayRecorder::default());
         world.insert(config);
 
         let client_entity = world
@@ -650,6 +709,7 @@ mod tests {
                 entity: client_entity,
                 sender: WsSender::new(control, bulk),
                 motion_protocol,
+                mesh_format: MeshFormat::Legacy,
             },
         );
         world.insert(clients);
@@ -3,6 +3,7 @@ mod chunk;
 mod cleanup;
 mod entity;
 mod events;
+mod movement;
 mod path;
 mod peers;
 mod physics;
@@ -14,6 +15,7 @@ pub use chunk::*;
 pub use cleanup::*;
 pub use entity::*;
 pub use events::*;
+pub use movement::AuthoritativeMovementSystem;
 pub use path::*;
 pub use peers::*;
 pub use physics::PhysicsSystem;
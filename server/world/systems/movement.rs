@@ -0,0 +1,361 @@
+use std::ops::Deref;
+
+use specs::{Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
+
+use crate::{
+    BrainComp, Chunks, ClientFilter, ClientFlag, CurrentChunkComp, Event, Events, IDComp,
+    MovementCorrection, MovementInput, MovementInputsComp, PositionComp, Registry, RigidBodyComp,
+    Stats, Vec3, WorldConfig,
+};
+
+/// Simulates one buffered movement input per client per step on worlds with
+/// authoritative movement, and corrects clients whose own prediction of that
+/// input strayed past the configured threshold. A client with nothing
+/// buffered runs a neutral input facing its last heading, so it still falls
+/// and slows to a stop while idle.
+pub struct AuthoritativeMovementSystem;
+
+impl<'a> System<'a> for AuthoritativeMovementSystem {
+    type SystemData = (
+        ReadExpect<'a, Stats>,
+        ReadExpect<'a, Registry>,
+        ReadExpect<'a, WorldConfig>,
+        ReadExpect<'a, Chunks>,
+        WriteExpect<'a, Events>,
+        ReadStorage<'a, ClientFlag>,
+        ReadStorage<'a, IDComp>,
+        ReadStorage<'a, CurrentChunkComp>,
+        WriteStorage<'a, MovementInputsComp>,
+        WriteStorage<'a, BrainComp>,
+        WriteStorage<'a, RigidBodyComp>,
+        WriteStorage<'a, PositionComp>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            stats,
+            registry,
+            config,
+            chunks,
+            mut events,
+            client_flags,
+            ids,
+            curr_chunks,
+            mut inputs,
+            mut brains,
+            mut bodies,
+            mut positions,
+        ) = data;
+
+        let Some(movement) = config.authoritative_movement else {
+            return;
+        };
+
+        if stats.preloading {
+            return;
+        }
+
+        // Inputs are per fixed step, which `authoritative_movement` requires.
+        let dt = config
+            .fixed_timestep
+            .map_or(stats.delta, |fixed| fixed.dt_secs() as f32);
+
+        for (_, id, curr_chunk, inputs, brain, body, position) in (
+            &client_flags,
+            &ids,
+            &curr_chunks,
+            &mut inputs,
+            &mut brains,
+            &mut bodies,
+            &mut positions,
+        )
+            .join()
+        {
+            // Hold inputs until the terrain under the client exists, rather
+            // than letting it fall through a chunk that is still loading.
+            if !chunks.is_chunk_ready(&curr_chunk.coords) {
+                continue;
+            }
+
+            let input = inputs.pop().unwrap_or_else(|| MovementInput {
+                heading: brain.state.heading,
+                ..Default::default()
+            });
+
+            input.simulate(brain, &mut body.0, dt, chunks.deref(), &registry, &config);
+
+            let Vec3(px, py, pz) = body.0.get_position();
+            position.0.set(px, py, pz);
+
+            if input
+                .divergence(&body.0)
+                .is_some_and(|divergence| divergence > movement.correction_threshold)
+            {
+                let correction = MovementCorrection::new(input.seq, &body.0);
+                events.dispatch(
+                    Event::movement_correction(correction)
+                        .filter(ClientFilter::Direct(id.0.clone()))
+                        .build(),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, Entity, RunNow, WorldExt};
+
+    use super::*;
+    use crate::{
+        AuthoritativeMovementConfig, Block, BrainOptions, Chunk, FixedStepConfig, RigidBody,
+        VoxelAccess, World, AABB, VOXELIZE_BUILTIN_MOVEMENT_CORRECTION_EVENT,
+    };
+
+    const STONE: u32 = 1;
+
+    fn world() -> World {
+        let config = WorldConfig::new()
+            .min_chunk([0, 0])
+            .max_chunk([0, 0])
+            .fixed_timestep(Some(FixedStepConfig {
+                hz: 60,
+                max_catchup_steps: 5,
+                seed: 1,
+            }))
+            .authoritative_movement(Some(AuthoritativeMovementConfig::default()))
+            .build();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[Block::new("Stone").id(STONE).build()]);
+
+        let mut world = World::new("authoritative-movement", &config);
+        world.ecs_mut().insert(registry);
+
+        let mut chunks = world.write_resource::<Chunks>();
+        chunks.add(Chunk::ready(0, 0, &config));
+        for x in 0..config.chunk_size as i32 {
+            for z in 0..config.chunk_size as i32 {
+                chunks.set_voxel(x, 9, z, STONE);
+            }
+        }
+        drop(chunks);
+
+        world
+    }
+
+    fn body() -> RigidBody {
+        let mut body =
+            RigidBody::new(&AABB::new().scale_x(0.8).scale_y(1.8).scale_z(0.8).build()).build();
+        body.set_position(4.5, 10.9, 4.5);
+        body
+    }
+
+    fn client(world: &mut World, id: &str) -> Entity {
+        let body = body();
+        world
+            .ecs_mut()
+            .create_entity()
+            .with(ClientFlag)
+            .with(IDComp::new(id))
+            .with(CurrentChunkComp::default())
+            .with(MovementInputsComp::default())
+            .with(BrainComp::new(BrainOptions::default()))
+            .with(PositionComp::new(4.5, 10.9, 4.5))
+            .with(RigidBodyComp::new(&body))
+            .build()
+    }
+
+    fn send(world: &mut World, client: Entity, input: MovementInput) {
+        world
+            .write_component::<MovementInputsComp>()
+            .get_mut(client)
+            .unwrap()
+            .push(input, 32);
+    }
+
+    fn corrections(world: &World) -> Vec<MovementCorrection> {
+        world
+            .read_resource::<Events>()
+            .queue
+            .iter()
+            .filter(|event| event.name == VOXELIZE_BUILTIN_MOVEMENT_CORRECTION_EVENT)
+            .map(|event| serde_json::from_str(event.payload.as_deref().unwrap()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn an_honest_client_is_never_corrected() {
+        let mut world = world();
+        let client = client(&mut world, "honest");
+
+        // The client predicts with the very same simulation the server runs.
+        let config = world.config().make_copy();
+        let registry = (*world.registry()).clone();
+        let mut brain = BrainComp::new(BrainOptions::default());
+        let mut predicted = body();
+        for seq in 0..30 {
+            let mut input = MovementInput {
+                seq,
+                running: true,
+                jumping: seq == 5,
+                ..Default::default()
+            };
+            input.simulate(
+                &mut brain,
+                &mut predicted,
+                1.0 / 60.0,
+                world.chunks().deref(),
+                &registry,
+                &config,
+            );
+            input.position = Some(predicted.get_position());
+            send(&mut world, client, input);
+        }
+
+        for _ in 0..30 {
+            AuthoritativeMovementSystem.run_now(world.ecs());
+        }
+
+        assert!(corrections(&world).is_empty());
+        let position = world
+            .read_component::<PositionComp>()
+            .get(client)
+            .unwrap()
+            .0
+            .clone();
+        assert!(position.2 > 4.5, "the client walked, got {:?}", position);
+    }
+
+    #[test]
+    fn a_client_claiming_to_fly_is_corrected_back_to_the_ground() {
+        let mut world = world();
+        let client = client(&mut world, "flyer");
+
+        send(
+            &mut world,
+            client,
+            MovementInput {
+                seq: 7,
+                position: Some(Vec3(4.5, 20.0, 4.5)),
+                ..Default::default()
+            },
+        );
+        AuthoritativeMovementSystem.run_now(world.ecs());
+
+        let corrections = corrections(&world);
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].seq, 7);
+        assert!(corrections[0].position[1] < 11.0);
+        assert!(matches!(
+            world.read_resource::<Events>().queue[0].filter,
+            Some(ClientFilter::Direct(ref id)) if id == "flyer"
+        ));
+    }
+
+    #[test]
+    fn one_input_is_simulated_per_step_however_many_arrive() {
+        let mut world = world();
+        let client = client(&mut world, "speeder");
+
+        for seq in 0..10 {
+            send(
+                &mut world,
+                client,
+                MovementInput {
+                    seq,
+                    running: true,
+                    ..Default::default()
+                },
+            );
+        }
+        AuthoritativeMovementSystem.run_now(world.ecs());
+
+        let inputs = world.read_component::<MovementInputsComp>();
+        assert_eq!(inputs.get(client).unwrap().len(), 9);
+    }
+
+    #[test]
+    fn reported_positions_are_ignored_and_inputs_buffered() {
+        let mut world = world();
+        let client = client(&mut world, "teleporter");
+
+        crate::default_client_parser(
+            &mut world,
+            r#"{"position":[4.5,40,4.5],"isFlying":true,"inputs":[{"seq":1,"running":true}]}"#,
+            client,
+        );
+
+        let position = world
+            .read_component::<PositionComp>()
+            .get(client)
+            .unwrap()
+            .0
+            .clone();
+        assert_eq!(position, Vec3(4.5, 10.9, 4.5));
+        let bodies = world.read_component::<RigidBodyComp>();
+        assert_eq!(bodies.get(client).unwrap().0.gravity_multiplier, 1.0);
+        let inputs = world.read_component::<MovementInputsComp>();
+        assert_eq!(inputs.get(client).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn an_idle_client_still_falls() {
+        let mut world = world();
+        let client = client(&mut world, "idler");
+        world
+            .write_component::<RigidBodyComp>()
+            .get_mut(client)
+            .unwrap()
+            .0
+            .set_position(4.5, 14.0, 4.5);
+
+        send(&mut world, client, MovementInput::default());
+        for _ in 0..60 {
+            AuthoritativeMovementSystem.run_now(world.ecs());
+        }
+
+        let position = world
+            .read_component::<PositionComp>()
+            .get(client)
+            .unwrap()
+            .0
+            .clone();
+        assert!(position.1 < 11.0, "the client landed, got {:?}", position);
+        assert!(corrections(&world).is_empty());
+    }
+
+    #[test]
+    fn a_client_that_never_sends_inputs_is_still_simulated() {
+        let mut world = world();
+        let client = client(&mut world, "silent");
+        world
+            .write_component::<RigidBodyComp>()
+            .get_mut(client)
+            .unwrap()
+            .0
+            .set_position(4.5, 14.0, 4.5);
+
+        for _ in 0..60 {
+            crate::default_client_parser(
+                &mut world,
+                r#"{"position":[6.5,14,6.5],"isFlying":true,"isGhost":true}"#,
+                client,
+            );
+            AuthoritativeMovementSystem.run_now(world.ecs());
+        }
+
+        let position = world
+            .read_component::<PositionComp>()
+            .get(client)
+            .unwrap()
+            .0
+            .clone();
+        assert_eq!((position.0, position.2), (4.5, 4.5));
+        assert!(position.1 < 11.0, "the client fell, got {:?}", position);
+        let bodies = world.read_component::<RigidBodyComp>();
+        let body = &bodies.get(client).unwrap().0;
+        assert_eq!(body.gravity_multiplier, 1.0);
+        assert!(body.aabb.width() > 0.0, "the client is not a ghost");
+    }
+}
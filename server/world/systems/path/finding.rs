@@ -1,6 +1,3 @@
-use std::cell::RefCell;
-use std::collections::HashMap;
-
 use crate::{
     find_path, Chunks, PathComp, Registry, RigidBodyComp, TargetComp, Vec3, VoxelAccess,
     WorldConfig,
@@ -32,19 +29,10 @@ impl<'a> System<'a> for PathFindingSystem {
                     return;
                 };
 
-                // Per-entity voxel passability cache: local and lock-free, so
-                // parallel searches never contend on a shared lock.
-                let voxel_cache: RefCell<HashMap<(i32, i32, i32), bool>> =
-                    RefCell::new(HashMap::new());
+                // Read from the chunks' collision meshes, which are shared by
+                // every search and only rebuilt where voxels change.
                 let is_passable = |vx: i32, vy: i32, vz: i32| -> bool {
-                    *voxel_cache
-                        .borrow_mut()
-                        .entry((vx, vy, vz))
-                        .or_insert_with(|| {
-                            let voxel = chunks.get_voxel(vx, vy, vz);
-                            let block = registry.get_block_by_id(voxel);
-                            block.is_passable || block.is_fluid
-                        })
+                    !chunks.is_voxel_solid(vx, vy, vz, &registry)
                 };
 
                 // Lenient start check: allow standing on edges and non-full blocks.
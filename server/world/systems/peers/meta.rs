@@ -1,6 +1,9 @@
 use specs::{ReadStorage, System, WriteStorage};
 
-use crate::world::components::{ClientFlag, DirectionComp, MetadataComp, NameComp, PositionComp};
+use crate::world::components::{
+    AttachmentComp, ClientFlag, DirectionComp, LightEmitterComp, MetadataComp, NameComp,
+    PositionComp,
+};
 
 pub struct PeersMetaSystem;
 
@@ -10,14 +13,16 @@ impl<'a> System<'a> for PeersMetaSystem {
         ReadStorage<'a, PositionComp>,
         ReadStorage<'a, DirectionComp>,
         ReadStorage<'a, NameComp>,
+        ReadStorage<'a, AttachmentComp>,
+        ReadStorage<'a, LightEmitterComp>,
         WriteStorage<'a, MetadataComp>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         use rayon::prelude::*;
-        use specs::ParJoin;
+        use specs::{LendJoin, ParJoin};
 
-        let (flag, positions, directions, names, mut metadatas) = data;
+        let (flag, positions, directions, names, attachments, emitters, mut metadatas) = data;
 
         // Combine all updates into a single parallel iteration to optimize performance
         (&positions, &directions, &names, &mut metadatas, &flag)
@@ -27,5 +32,23 @@ impl<'a> System<'a> for PeersMetaSystem {
                 metadata.set("direction", direction);
                 metadata.set("username", name);
             });
+
+        (&mut metadatas, (&attachments).maybe(), &flag)
+            .par_join()
+            .for_each(|(metadata, attachment, _)| match attachment {
+                Some(attachment) => metadata.set("attachment", attachment),
+                None => {
+                    metadata.remove("attachment");
+                }
+            });
+
+        (&mut metadatas, (&emitters).maybe(), &flag)
+            .par_join()
+            .for_each(|(metadata, emitter, _)| match emitter {
+                Some(emitter) => metadata.set("lightEmitter", emitter),
+                None => {
+                    metadata.remove("lightEmitter");
+                }
+            });
     }
 }
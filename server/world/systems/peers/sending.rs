@@ -2,8 +2,8 @@ use specs::{ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
 use crate::{
     encode_message, is_peer_relevant, ClientFlag, Clients, IDComp, Message, MessageType,
-    MetadataComp, NameComp, PeerProtocol, PositionComp, ReplicatedStateBuffer, Stats, Transports,
-    WorldConfig,
+    MetadataComp, NameComp, PeerProtocol, PositionComp, ReplayPlayer, ReplayRecorder,
+    ReplicatedStateBuffer, Stats, Transports, WorldConfig,
 };
 
 pub struct PeersSendingSystem;
@@ -15,6 +15,8 @@ impl<'a> System<'a> for PeersSendingSystem {
         ReadExpect<'a, WorldConfig>,
         ReadExpect<'a, Stats>,
         WriteExpect<'a, ReplicatedStateBuffer>,
+        ReadExpect<'a, ReplayPlayer>,
+        WriteExpect<'a, ReplayRecorder>,
         ReadStorage<'a, ClientFlag>,
         ReadStorage<'a, IDComp>,
         ReadStorage<'a, NameComp>,
@@ -31,6 +33,8 @@ impl<'a> System<'a> for PeersSendingSystem {
             config,
             stats,
             mut replicated_state,
+            replay_player,
+            mut replay_recorder,
             flag,
             ids,
             names,
@@ -66,12 +70,15 @@ impl<'a> System<'a> for PeersSendingSystem {
             return;
         }
 
+        // Replay viewers only see the recorded peers, never each other.
+        let stage_for_clients = !replay_player.is_loaded();
+
         // Peer positions/metadata are latest-wins STATE (see
         // `world::replication`): each snapshot lands in a per-client,
         // per-peer slot where a newer value overwrites an undelivered older
         // one. Never append peer positions to a queue — a backlog of old
         // positions is what makes other players rubber-band.
-        for (client_id, client) in clients.iter() {
+        for (client_id, client) in clients.iter().filter(|_| stage_for_clients) {
             let client_pos = positions
                 .get(client.entity)
                 .map(|p| [p.0 .0, p.0 .1, p.0 .2]);
@@ -102,15 +109,17 @@ impl<'a> System<'a> for PeersSendingSystem {
             }
         }
 
-        // Transports observe the whole world, so they receive the full
-        // change set directly (no interest filtering, no coalescing).
-        if !transports.is_empty() {
+        // Transports and recordings observe the whole world, so they receive
+        // the full change set directly (no interest filtering, no coalescing).
+        if !transports.is_empty() || replay_recorder.is_recording() {
+            let tick = stats.dispatch_count();
             let peers: Vec<PeerProtocol> = changed.into_iter().map(|(peer, _)| peer).collect();
             let message = Message::new(&MessageType::Peer)
                 .peers(&peers)
-                .tick(stats.dispatch_count())
+                .tick(tick)
                 .build();
             let encoded = encode_message(&message);
+            replay_recorder.record(tick, &encoded);
             transports.values().for_each(|sender| {
                 let _ = sender.send(encoded.clone());
             });
@@ -1,6 +1,6 @@
 use std::ops::Deref;
 
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use log::info;
 use rapier3d::prelude::CollisionEvent;
 use specs::{Entities, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
@@ -15,10 +15,26 @@ use crate::{
         voxels::Chunks,
         WorldConfig,
     },
-    ClientFilter, ClientFlag, CollisionsComp, Event, EventBuilder, Events, IDComp, InteractorComp,
-    Vec2, Vec3,
+    AttachmentComp, ClientFilter, ClientFlag, CollisionsComp, Event, EventBuilder, Events, IDComp,
+    InteractorComp, PlatformFlag, RigidBody, StandingOnComp, Vec2, Vec3, AABB,
+    MAX_ATTACHMENT_DEPTH,
 };
 
+/// How far a body may have sunk into a platform's top since the last tick and
+/// still be stood back up on it, rather than falling through.
+const PLATFORM_SNAP_BELOW: f32 = 0.25;
+
+/// How far above a platform's top a body still counts as standing on it.
+const PLATFORM_SNAP_ABOVE: f32 = 0.05;
+
+/// The boxes a platform can be stood on: the parts of its shape, or its box.
+fn platform_boxes(body: &RigidBody) -> Vec<AABB> {
+    match &body.shape {
+        Some(shape) => shape.world_aabbs(&body.get_position(), body.yaw),
+        None => vec![body.aabb.clone()],
+    }
+}
+
 #[derive(Default)]
 pub struct PhysicsSystem;
 
@@ -36,6 +52,9 @@ impl<'a> System<'a> for PhysicsSystem {
         ReadStorage<'a, CurrentChunkComp>,
         ReadStorage<'a, InteractorComp>,
         ReadStorage<'a, ClientFlag>,
+        ReadStorage<'a, PlatformFlag>,
+        WriteStorage<'a, AttachmentComp>,
+        WriteStorage<'a, StandingOnComp>,
         WriteStorage<'a, CollisionsComp>,
         WriteStorage<'a, RigidBodyComp>,
         WriteStorage<'a, PositionComp>,
@@ -43,7 +62,7 @@ impl<'a> System<'a> for PhysicsSystem {
 
     fn run(&mut self, data: Self::SystemData) {
         use rayon::prelude::*;
-        use specs::{Join, ParJoin};
+        use specs::{Join, LendJoin, ParJoin};
 
         let (
             entities,
@@ -58,6 +77,9 @@ impl<'a> System<'a> for PhysicsSystem {
             curr_chunks,
             interactors,
             client_flag,
+            platforms,
+            mut attachments,
+            mut standing_ons,
             mut collisions,
             mut bodies,
             mut positions,
@@ -69,11 +91,25 @@ impl<'a> System<'a> for PhysicsSystem {
 
         let mut collision_map = HashMap::new();
 
-        // Tick the voxel physics of all entities (non-clients).
+        // Where each platform stood before this tick, to carry its riders by
+        // however far it moves and turns.
+        let platforms_before: HashMap<_, _> = (&entities, &platforms, &bodies)
+            .join()
+            .map(|(entity, _, body)| (entity, (body.0.get_position(), body.0.yaw)))
+            .collect();
+
+        // Tick the voxel physics of all entities (non-clients). Attached
+        // riders are placed after their parents below instead.
         // Skip entities in chunks with no interested players.
-        (&curr_chunks, &mut bodies, &mut positions, !&client_flag)
+        (
+            &curr_chunks,
+            &mut bodies,
+            &mut positions,
+            !&client_flag,
+            !&attachments,
+        )
             .par_join()
-            .for_each(|(curr_chunk, body, position, _)| {
+            .for_each(|(curr_chunk, body, position, _, _)| {
                 if !chunks.is_chunk_ready(&curr_chunk.coords) {
                     return;
                 }
@@ -125,11 +161,229 @@ impl<'a> System<'a> for PhysicsSystem {
                 position.0.set(px, py, pz);
             });
 
-        // Move the clients' rigid bodies to their positions
-        (&entities, &interactors, &positions)
+        // Clients the server does not simulate move themselves, so they are
+        // sent how far they were carried and stood up instead.
+        let is_self_moving =
+            |entity| client_flag.contains(entity) && config.authoritative_movement.is_none();
+        let mut self_moved: HashMap<_, Vec3<f32>> = HashMap::new();
+
+        // Carry bodies by the platforms they stood on last tick, sweeping so a
+        // platform cannot drag its riders through walls.
+        let platforms_now: HashMap<_, _> = (&entities, &platforms, &bodies)
+            .join()
+            .map(|(entity, _, body)| (entity, (body.0.get_position(), body.0.yaw)))
+            .collect();
+
+        for (entity, standing_on, body, position, _) in (
+            &entities,
+            &standing_ons,
+            &mut bodies,
+            &mut positions,
+            !&attachments,
+        )
             .join()
-            .for_each(|(ent, interactor, position)| {
+        {
+            let (Some((before, before_yaw)), Some((now, now_yaw))) = (
+                platforms_before.get(&standing_on.0),
+                platforms_now.get(&standing_on.0),
+            ) else {
+                continue;
+            };
+
+            let turn = now_yaw - before_yaw;
+            let rider = body.0.get_position();
+            let carried = rider.sub(before).rotate_y(&Vec3::default(), turn).add(now);
+            let moved = Physics::displace_body(
+                &mut body.0,
+                &carried.sub(&rider),
+                chunks.deref(),
+                &registry,
+            );
+            body.0.yaw += turn;
+
+            let Vec3(px, py, pz) = body.0.get_position();
+            position.0.set(px, py, pz);
+
+            if is_self_moving(entity) {
+                self_moved.insert(entity, moved);
+            }
+        }
+
+        // Stand bodies up on the platforms they landed on this tick.
+        // Platforms are not voxels, so gravity pulls riders a hair into them
+        // every tick and this lifts them back onto the top.
+        let platform_tops: Vec<_> = (&entities, &platforms, &bodies)
+            .join()
+            .map(|(entity, _, body)| (entity, platform_boxes(&body.0)))
+            .collect();
+
+        for (entity, body, position, _, _) in (
+            &entities,
+            &mut bodies,
+            &mut positions,
+            !&attachments,
+            !&platforms,
+        )
+            .join()
+        {
+            // Velocities of bodies on a platform are relative to it, as the
+            // carry above moves them with it. One rising, e.g. jumping, is not
+            // standing on it.
+            let aabb = &body.0.aabb;
+            let landed = if body.0.velocity.1 > 0.0 {
+                None
+            } else {
+                platform_tops.iter().find_map(|(platform, boxes)| {
+                    boxes
+                        .iter()
+                        .find(|top| {
+                            aabb.min_x < top.max_x
+                                && aabb.max_x > top.min_x
+                                && aabb.min_z < top.max_z
+                                && aabb.max_z > top.min_z
+                                && aabb.min_y >= top.max_y - PLATFORM_SNAP_BELOW
+                                && aabb.min_y <= top.max_y + PLATFORM_SNAP_ABOVE
+                        })
+                        .map(|top| (*platform, top.max_y))
+                })
+            };
+
+            let Some((platform, top)) = landed else {
+                standing_ons.remove(entity);
+                continue;
+            };
+
+            let lift = top - aabb.min_y;
+            if lift > 0.0 {
+                let moved = Physics::displace_body(
+                    &mut body.0,
+                    &Vec3(0.0, lift + 1e-4, 0.0),
+                    chunks.deref(),
+                    &registry,
+                );
+                if is_self_moving(entity) {
+                    let total = self_moved.entry(entity).or_default();
+                    *total = total.add(&moved);
+                }
+            }
+            body.0.velocity.1 = 0.0;
+            body.0.resting.1 = -1;
+
+            let Vec3(px, py, pz) = body.0.get_position();
+            position.0.set(px, py, pz);
+            standing_ons.insert(entity, StandingOnComp(platform)).ok();
+        }
+
+        for (entity, Vec3(dx, dy, dz)) in self_moved {
+            if let Some(id) = ids.get(entity) {
+                events.dispatch(
+                    Event::carry([dx, dy, dz])
+                        .filter(ClientFilter::Direct(id.0.clone()))
+                        .build(),
+                );
+            }
+        }
+
+        // Place attached riders at their parents' transforms, parents first
+        // so riders of riders land on their already placed parents. Riders
+        // whose parent is gone are dismounted.
+        let attached: Vec<_> = (&entities, &attachments)
+            .join()
+            .map(|(rider, attachment)| (rider, attachment.clone()))
+            .collect();
+
+        // Riders and what they ride, which overlap by design; pushing them
+        // apart would fling mounts and platforms out from under them.
+        let mut riding = Vec::new();
+
+        if !attached.is_empty() {
+            let parent_ids: HashSet<_> = attached
+                .iter()
+                .map(|(_, attachment)| attachment.parent.as_str())
+                .collect();
+            let by_id: HashMap<_, _> = (&entities, &ids)
+                .join()
+                .filter(|(_, id)| parent_ids.contains(id.0.as_str()))
+                .map(|(entity, id)| (id.0.as_str(), entity))
+                .collect();
+            let parent_of: HashMap<_, _> = attached
+                .iter()
+                .filter_map(|(rider, attachment)| {
+                    by_id
+                        .get(attachment.parent.as_str())
+                        .map(|parent| (*rider, *parent))
+                })
+                .collect();
+
+            let depth = |mut entity| {
+                let mut depth = 0;
+                while let Some(parent) = parent_of.get(&entity) {
+                    depth += 1;
+                    entity = *parent;
+                    if depth >= MAX_ATTACHMENT_DEPTH {
+                        break;
+                    }
+                }
+                depth
+            };
+
+            let mut ordered: Vec<_> = attached
+                .iter()
+                .map(|(rider, attachment)| (depth(*rider), *rider, attachment))
+                .collect();
+            ordered.sort_by_key(|(depth, _, _)| *depth);
+
+            for (_, rider, attachment) in ordered {
+                let Some(parent) = parent_of.get(&rider).copied() else {
+                    attachments.remove(rider);
+                    continue;
+                };
+                let Some(parent_position) = positions.get(parent).map(|p| p.0.clone()) else {
+                    continue;
+                };
+                let (yaw, velocity) = bodies.get(parent).map_or((0.0, Vec3::default()), |body| {
+                    (body.0.yaw, body.0.velocity.clone())
+                });
+
+                let Vec3(px, py, pz) = Vec3::from_arr(attachment.offset)
+                    .rotate_y(&Vec3::default(), yaw)
+                    .add(&parent_position);
+
+                if let Some(position) = positions.get_mut(rider) {
+                    position.0.set(px, py, pz);
+                }
+                if let Some(body) = bodies.get_mut(rider) {
+                    body.0.set_position(px, py, pz);
+                    body.0.yaw = yaw;
+                    // Kept so a dismounted rider leaves with its mount's momentum.
+                    body.0.velocity = velocity;
+                }
+
+                riding.push((rider, parent));
+            }
+        }
+
+        riding.extend(
+            (&entities, &standing_ons)
+                .join()
+                .map(|(rider, standing_on)| (rider, standing_on.0)),
+        );
+        physics.set_unsolved_pairs(riding.into_iter().filter_map(|(rider, parent)| {
+            Some((
+                *interactors.get(rider)?.collider_handle(),
+                *interactors.get(parent)?.collider_handle(),
+            ))
+        }));
+
+        // Move the clients' rigid bodies to their positions, turning compound
+        // colliders with their bodies' yaw.
+        (&entities, &interactors, &positions, (&bodies).maybe())
+            .join()
+            .for_each(|(ent, interactor, position, body)| {
                 physics.move_rapier_body(interactor.body_handle(), &position.0);
+                if let Some(body) = body.filter(|body| body.0.shape.is_some()) {
+                    physics.rotate_rapier_body(interactor.body_handle(), body.0.yaw);
+                }
                 collision_map.insert(interactor.collider_handle().clone(), ent);
             });
 
@@ -3,3 +3,8 @@ use crate::Vec3;
 pub use voxelize_core::LightColor;
 
 pub type VoxelUpdate = (Vec3<i32>, u32);
+
+/// Voxel updates that commit together in a single pass, past the per-tick
+/// update budget, so their light removals and remeshes are batched once.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BulkUpdate(pub Vec<VoxelUpdate>);
@@ -1,4 +1,4 @@
-use crate::{BlockUtils, LightColor, LightUtils, Ndarray, Registry};
+use crate::{BlockUtils, LightColor, LightUtils, Ndarray, Registry, Vec3, AABB};
 
 use super::block::BlockRotation;
 use super::waterlogging::WaterloggingRules;
@@ -227,6 +227,18 @@ pub trait VoxelAccess {
         todo!("Voxel assess `get_lights` is not implemented.");
     }
 
+    /// The world space collision boxes of the voxels from `min` to `max`
+    /// (inclusive), for accesses that keep collision meshes. `None` leaves the
+    /// caller to collect them voxel by voxel.
+    fn get_collision_boxes(
+        &self,
+        min: &Vec3<i32>,
+        max: &Vec3<i32>,
+        registry: &Registry,
+    ) -> Option<Vec<AABB>> {
+        None
+    }
+
     fn contains(&self, vx: i32, vy: i32, vz: i32) -> bool {
         todo!("Voxel access `contains` is not implemented.");
     }
@@ -12,22 +12,41 @@ use std::sync::Arc;
 use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
 
-use crate::Vec2;
+use crate::{Vec2, Vec3};
 
 /// Bumped whenever a chunk file needs a one-time migration on load.
 ///
 /// - `0`: files written before per-voxel waterlogging existed.
 /// - `1`: waterlogged bit is authoritative in the voxel word.
-pub const CHUNK_FILE_VERSION: u32 = 1;
+/// - `2`: pending scheduled ticks are stored alongside the voxels.
+pub const CHUNK_FILE_VERSION: u32 = 2;
 
+/// The first version whose waterlogged bits can be trusted; older files are
+/// backfilled from the registry's waterlogging rules on load.
+pub const WATERLOGGED_BIT_VERSION: u32 = 1;
+
+/// A voxel's pending active update, as written to its chunk file.
+///
+/// The deadline is stored relative to the tick the chunk was saved at, so a
+/// chunk loaded any number of ticks later resumes with the same delay left
+/// instead of firing everything it missed at once.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledTick {
+    pub voxel: Vec3<i32>,
+    pub delay: u64,
+}
+
+/// A chunk as written to its file, and as carried in a chunk snapshot.
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct ChunkFileData {
-    id: String,
-    voxels: String,
-    height_map: String,
+pub(crate) struct ChunkFileData {
+    pub(crate) id: String,
+    pub(crate) voxels: String,
+    pub(crate) height_map: String,
     #[serde(default)]
-    version: u32,
+    pub(crate) version: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) scheduled_ticks: Vec<ScheduledTick>,
 }
 
 pub struct ChunkSaveData {
@@ -36,6 +55,7 @@ pub struct ChunkSaveData {
     pub chunk_id: String,
     pub voxels: Vec<u32>,
     pub height_map: Vec<u32>,
+    pub scheduled_ticks: Vec<ScheduledTick>,
 }
 
 pub struct BackgroundChunkSaver {
@@ -129,6 +149,7 @@ impl BackgroundChunkSaver {
             voxels: Self::to_base_64(&data.voxels),
             height_map: Self::to_base_64(&data.height_map),
             version: CHUNK_FILE_VERSION,
+            scheduled_ticks: data.scheduled_ticks.clone(),
         };
 
         let json_data = match serde_json::to_string(&file_data) {
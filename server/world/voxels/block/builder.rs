@@ -1,10 +1,16 @@
 use std::sync::Arc;
 
-use crate::{BlockFace, BlockFaces, FluidConfig, Registry, Vec3, VoxelAccess, VoxelUpdate, AABB};
+use crate::{
+    BlockFace, BlockFaces, FluidConfig, ModelGeometry, Registry, Vec3, VoxelAccess, VoxelUpdate,
+    AABB,
+};
 
 use super::super::fluids::create_fluid_active_fn;
 use super::rules::{attached_support_fns, solid_below_support_fns};
-use super::{Block, BlockDynamicPattern, SupportRequirement, YRotatableSegments};
+use super::{
+    Block, BlockDynamicPattern, BlockModelSource, SupportRequirement, TextureVariantRule,
+    YRotatableSegments,
+};
 
 #[derive(Default)]
 pub struct BlockBuilder {
@@ -33,6 +39,8 @@ pub struct BlockBuilder {
     stack_group: u16,
     is_random_tickable: bool,
     requires_support: SupportRequirement,
+    is_gravity_affected: bool,
+    blast_resistance: f32,
     is_px_transparent: bool,
     is_py_transparent: bool,
     is_pz_transparent: bool,
@@ -44,6 +52,8 @@ pub struct BlockBuilder {
     light_attenuation: u8,
     emissive: f32,
     face_emissives: Vec<(String, f32)>,
+    model_name: Option<String>,
+    texture_rules: Vec<TextureVariantRule>,
     dynamic_patterns: Option<Vec<BlockDynamicPattern>>,
     dynamic_fn: Option<
         Arc<
@@ -190,6 +200,27 @@ impl BlockBuilder {
         self
     }
 
+    /// Make this block fall when unsupported, like sand or gravel. Shares the
+    /// active-voxel neighbor activation with [`Self::requires_support_below`],
+    /// but the voxel becomes a falling-block entity instead of vanishing.
+    pub fn is_gravity_affected(mut self, is_gravity_affected: bool) -> Self {
+        self.is_gravity_affected = is_gravity_affected;
+        if is_gravity_affected {
+            // The updater only clears the voxel; `ChunkUpdatingSystem` sees a
+            // gravity block clearing itself and spawns the entity carrying it.
+            let (ticker, updater) = solid_below_support_fns();
+            self.active_ticker = Some(ticker);
+            self.active_updater = Some(updater);
+        }
+        self
+    }
+
+    /// Configure how strongly this block resists explosions. Default is 0.
+    pub fn blast_resistance(mut self, blast_resistance: f32) -> Self {
+        self.blast_resistance = blast_resistance;
+        self
+    }
+
     /// Configure whether or not this block can be climbed. Default is false.
     pub fn is_climbable(mut self, is_climbable: bool) -> Self {
         self.is_climbable = is_climbable;
@@ -259,6 +290,27 @@ impl BlockBuilder {
         self
     }
 
+    /// Configure the faces and bounding boxes from a block model compiled by
+    /// [`crate::BlockModels::compile`], replacing both.
+    pub fn model(mut self, model: &ModelGeometry) -> Self {
+        self.faces = model
+            .faces
+            .iter()
+            .map(BlockFace::from_mesher_face)
+            .collect();
+        self.aabbs = model.aabbs.clone();
+        self
+    }
+
+    /// Take the faces and bounding boxes from the block model `name`, compiled
+    /// when the registry loads its models ([`Registry::load_models`], or
+    /// `ServerBuilder::models` for a server's registry). Until then the block
+    /// keeps the faces and boxes set here.
+    pub fn model_name(mut self, name: &str) -> Self {
+        self.model_name = Some(name.to_owned());
+        self
+    }
+
     /// Is this block a see-through block? Should it be sorted to the transparent meshes?
     pub fn is_see_through(mut self, is_see_through: bool) -> Self {
         self.is_see_through = is_see_through;
@@ -278,6 +330,14 @@ impl BlockBuilder {
         self
     }
 
+    /// Add a rule varying the texture of this block's faces from voxel to
+    /// voxel: random variants, connected textures, or random turns. The
+    /// registry fills in where the rule's textures sit in the atlas.
+    pub fn texture_rule(mut self, rule: TextureVariantRule) -> Self {
+        self.texture_rules.push(rule);
+        self
+    }
+
     /// Does this block prevent fluids from rendering faces against it?
     pub fn occludes_fluid(mut self, occludes_fluid: bool) -> Self {
         self.occludes_fluid = occludes_fluid;
@@ -423,17 +483,13 @@ impl BlockBuilder {
 
     /// Construct a block instance, ready to be added into the registry.
     pub fn build(self) -> Block {
+        let model = self.model_name.map(|name| BlockModelSource {
+            name,
+            emissive: self.emissive,
+            face_emissives: self.face_emissives.clone(),
+        });
         let mut faces = self.faces;
-        if self.emissive > 0.0 {
-            for face in faces.iter_mut() {
-                face.emissive = self.emissive;
-            }
-        }
-        for (face_name, strength) in &self.face_emissives {
-            for face in faces.iter_mut().filter(|face| &face.name == face_name) {
-                face.emissive = *strength;
-            }
-        }
+        apply_emissives(&mut faces, self.emissive, &self.face_emissives);
 
         Block {
             id: self.id,
@@ -464,11 +520,15 @@ impl BlockBuilder {
             transparent_standalone: self.transparent_standalone,
             faces,
             aabbs: self.aabbs,
+            model,
             is_see_through: self.is_see_through,
             occludes_fluid: self.occludes_fluid,
             is_plant: self.is_plant,
             stack_group: self.stack_group,
+            texture_rules: self.texture_rules,
             requires_support: self.requires_support,
+            is_gravity_affected: self.is_gravity_affected,
+            blast_resistance: self.blast_resistance,
             is_transparent: [
                 self.is_px_transparent,
                 self.is_py_transparent,
@@ -491,6 +551,25 @@ impl BlockBuilder {
     }
 }
 
+/// Render `faces` emissive at `emissive`, then each face named in
+/// `face_emissives` at its own strength.
+pub(crate) fn apply_emissives(
+    faces: &mut [BlockFace],
+    emissive: f32,
+    face_emissives: &[(String, f32)],
+) {
+    if emissive > 0.0 {
+        for face in faces.iter_mut() {
+            face.emissive = emissive;
+        }
+    }
+    for (face_name, strength) in face_emissives {
+        for face in faces.iter_mut().filter(|face| &face.name == face_name) {
+            face.emissive = *strength;
+        }
+    }
+}
+
 #[cfg(test)]
 mod support_requirement_tests {
     use super::*;
@@ -509,6 +588,17 @@ mod support_requirement_tests {
         assert!(block.active_ticker.is_some());
     }
 
+    #[test]
+    fn is_gravity_affected_wires_active_fn() {
+        let sand = Block::new("TestSand")
+            .id(9002)
+            .is_gravity_affected(true)
+            .build();
+        assert!(sand.is_gravity_affected);
+        assert!(sand.is_active);
+        assert_eq!(sand.requires_support, SupportRequirement::None);
+    }
+
     #[test]
     fn solid_below_updater_clears_when_unsupported() {
         let plant = Block::new("TestPlant")
@@ -521,3 +611,85 @@ mod support_requirement_tests {
         let _ = updater; // compiled wiring covered above
     }
 }
+
+#[cfg(test)]
+mod model_tests {
+    use super::*;
+    use crate::BlockModels;
+
+    #[test]
+    fn model_blocks_mesh_with_the_compiled_faces() {
+        let mut models = BlockModels::new();
+        models
+            .parse(
+                "slab",
+                r##"{
+                    "textures": { "all": "planks" },
+                    "elements": [{
+                        "from": [0, 0, 0], "to": [16, 8, 16],
+                        "faces": { "up": { "texture": "#all" }, "down": { "texture": "#all" } }
+                    }]
+                }"##,
+            )
+            .unwrap();
+
+        let slab = Block::new("Slab")
+            .id(9003)
+            .model(&models.compile("slab").unwrap())
+            .build();
+
+        assert_eq!(slab.faces.len(), 2);
+        assert_eq!(slab.faces[0].name, "py");
+        assert_eq!(slab.faces[0].texture_group.as_deref(), Some("planks"));
+        assert_eq!(slab.aabbs[0].max_y, 0.5);
+
+        let mesher_block = slab.to_mesher_block();
+        assert_eq!(mesher_block.faces[0].corners[0].pos, [0.0, 0.5, 1.0]);
+        assert_eq!(mesher_block.faces[0].name_lower, "py");
+    }
+
+    #[test]
+    fn named_models_are_compiled_when_the_registry_loads_them() {
+        let mut models = BlockModels::new();
+        models
+            .parse(
+                "lamp",
+                r##"{
+                    "textures": { "all": "glowstone" },
+                    "elements": [{
+                        "from": [4, 0, 4], "to": [12, 8, 12],
+                        "faces": { "up": { "texture": "#all" }, "down": { "texture": "#all" } }
+                    }]
+                }"##,
+            )
+            .unwrap();
+
+        let mut registry = Registry::new();
+        registry.register_block(
+            &Block::new("Lamp")
+                .id(9004)
+                .model_name("lamp")
+                .emissive(0.5)
+                .face_emissive("ny", 0.0)
+                .build(),
+        );
+        assert_eq!(registry.get_block_by_name("lamp").faces.len(), 6);
+
+        assert_eq!(registry.load_models(&models).unwrap(), 1);
+        let lamp = registry.get_block_by_name("lamp");
+        let faces: Vec<_> = lamp
+            .faces
+            .iter()
+            .map(|face| (face.name.as_str(), face.emissive))
+            .collect();
+        assert_eq!(faces, [("py", 0.5), ("ny", 0.0)]);
+        assert_eq!(lamp.aabbs[0].max_y, 0.5);
+        assert_eq!(registry.get_block_by_id(9004).faces.len(), 2);
+
+        registry.register_block(&Block::new("Chair").id(9005).model_name("chair").build());
+        assert!(matches!(
+            registry.load_models(&models),
+            Err(crate::ModelError::Missing(name)) if name == "chair"
+        ));
+    }
+}
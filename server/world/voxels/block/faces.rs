@@ -56,6 +56,24 @@ impl BlockFace {
         self.texture_group = Some(group.to_string());
     }
 
+    pub fn from_mesher_face(face: &voxelize_mesher::BlockFace) -> Self {
+        Self {
+            name: face.name.clone(),
+            independent: face.independent,
+            isolated: face.isolated,
+            texture_group: face.texture_group.clone(),
+            dir: face.dir,
+            corners: face.corners.clone(),
+            range: UV {
+                start_u: face.range.start_u,
+                end_u: face.range.end_u,
+                start_v: face.range.start_v,
+                end_v: face.range.end_v,
+            },
+            emissive: face.emissive,
+        }
+    }
+
     pub fn to_mesher_face(&self) -> voxelize_mesher::BlockFace {
         voxelize_mesher::BlockFace {
             name: self.name.clone(),
@@ -13,8 +13,13 @@ pub use faces::*;
 pub use rules::*;
 
 pub use voxelize_core::{
-    BlockRotation, CornerData, NX_ROTATION, NY_ROTATION, NZ_ROTATION, PX_ROTATION, PY_ROTATION,
-    PZ_ROTATION, ROTATION_MASK, STAGE_MASK, Y_ROTATION_MASK, Y_ROT_SEGMENTS,
+    BlockRotation, CornerData, TextureVariantRule, VariantTexture, NX_ROTATION, NY_ROTATION,
+    NZ_ROTATION, PX_ROTATION, PY_ROTATION, PZ_ROTATION, ROTATION_MASK, STAGE_MASK, Y_ROTATION_MASK,
+    Y_ROT_SEGMENTS,
+};
+pub use voxelize_mesher::{
+    BlockModel, BlockModels, CollisionMesh, ElementFace, ElementRotation, ModelAxis, ModelElement,
+    ModelError, ModelGeometry, ModelSide, VoxelCollider,
 };
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -90,6 +95,11 @@ pub struct Block {
     /// Bounding boxes of this block.
     pub aabbs: Vec<AABB>,
 
+    /// The block model `faces` and `aabbs` are compiled from when the
+    /// registry loads its models. See [`BlockBuilder::model_name`].
+    #[serde(skip)]
+    pub model: Option<BlockModelSource>,
+
     /// Is the block overall see-through? Opacity equals 0.1 or something?
     pub is_see_through: bool,
 
@@ -103,11 +113,27 @@ pub struct Block {
     /// run. Zero means the block never stacks.
     pub stack_group: u16,
 
+    /// Per-voxel variation of this block's face textures, applied by the
+    /// mesher. See [`TextureVariantRule`].
+    #[serde(default)]
+    pub texture_rules: Vec<TextureVariantRule>,
+
     /// Declares whether this block auto-clears when its support is removed.
     /// Wired through [`BlockBuilder::requires_support`] into `active_fn`.
     #[serde(default)]
     pub requires_support: SupportRequirement,
 
+    /// Does this block fall when the voxel below stops supporting it (sand,
+    /// gravel)? Unsupported voxels turn into falling-block entities that
+    /// re-place the block where they land.
+    #[serde(default)]
+    pub is_gravity_affected: bool,
+
+    /// How much of an explosion's strength this block soaks up per step a
+    /// blast ray takes through it. Zero lets blasts pass as through air.
+    #[serde(default)]
+    pub blast_resistance: f32,
+
     /// Is this block transparent from looking from all 6 sides?
     /// The order is: px, py, pz, nx, ny, nz.
     pub is_transparent: [bool; 6],
@@ -158,6 +184,16 @@ pub struct Block {
     pub is_random_tickable: bool,
 }
 
+/// A block model waiting to be compiled into a block's faces and bounding
+/// boxes, with the emissive strengths the block was built with so the
+/// compiled faces get them too.
+#[derive(Debug, Clone, Default)]
+pub struct BlockModelSource {
+    pub name: String,
+    pub emissive: f32,
+    pub face_emissives: Vec<(String, f32)>,
+}
+
 impl Block {
     pub fn new(name: &str) -> BlockBuilder {
         BlockBuilder::new(name)
@@ -215,6 +251,33 @@ impl Block {
         }
     }
 
+    /// What this block adds to a collision mesh when turned by `rotation`.
+    /// Dynamic blocks are left to `get_aabbs` at query time.
+    pub fn collider(&self, rotation: &BlockRotation) -> VoxelCollider {
+        if self.is_fluid || self.is_empty || self.is_passable {
+            return VoxelCollider::None;
+        }
+
+        if self.is_dynamic {
+            return VoxelCollider::Dynamic;
+        }
+
+        match self.aabbs.as_slice() {
+            [aabb]
+                if [aabb.min_x, aabb.min_y, aabb.min_z] == [0.0; 3]
+                    && [aabb.max_x, aabb.max_y, aabb.max_z] == [1.0; 3] =>
+            {
+                VoxelCollider::Cube
+            }
+            aabbs => VoxelCollider::Boxes(
+                aabbs
+                    .iter()
+                    .map(|aabb| rotation.rotate_aabb(aabb, true, true))
+                    .collect(),
+            ),
+        }
+    }
+
     pub fn get_faces(
         &self,
         pos: &Vec3<i32>,
@@ -382,6 +445,7 @@ impl Block {
             occludes_fluid: self.occludes_fluid,
             is_plant: self.is_plant,
             stack_group: self.stack_group,
+            texture_rules: self.texture_rules.clone(),
             faces: self.faces.iter().map(|f| f.to_mesher_face()).collect(),
             aabbs: self.aabbs.clone(),
             dynamic_patterns: self
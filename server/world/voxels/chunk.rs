@@ -1,9 +1,12 @@
 use std::ops::Range;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 use hashbrown::{HashMap, HashSet};
 
-use crate::{ChunkProtocol, ChunkUtils, MeshProtocol, Ndarray, Registry, Vec2, Vec3, VoxelUpdate};
+use crate::{
+    ChunkProtocol, ChunkUtils, CollisionMesh, MeshProtocol, Ndarray, Registry, Vec2, Vec3,
+    VoxelUpdate, AABB,
+};
 
 use super::access::VoxelAccess;
 
@@ -88,6 +91,16 @@ pub struct Chunk {
     /// persisted form and clears it.
     pub(crate) is_save_dirty: bool,
 
+    /// Collision mesh of each sub-chunk level, built on first query and
+    /// dropped by `set_raw_voxel` when a voxel of that level changes. The
+    /// levels are laid out on first query, from `options`.
+    ///
+    /// Only `set_raw_voxel` keeps these in step with `voxels`. Anything that
+    /// writes `voxels` another way, such as assigning `Arc::make_mut(&mut
+    /// chunk.voxels).data` wholesale, must call `invalidate_collision` after,
+    /// or sweeps and pathfinding keep colliding with the old voxels.
+    pub(crate) collision: OnceLock<Vec<OnceLock<Arc<CollisionMesh>>>>,
+
     pub waterlogging_rules: Option<Arc<super::waterlogging::WaterloggingRules>>,
 }
 
@@ -209,6 +222,122 @@ impl Chunk {
         self.updated_levels.insert(level as u32);
     }
 
+    /// The collision mesh of sub-chunk `level`, built from this chunk's voxels
+    /// the first time it's asked for.
+    pub fn collision_mesh(&self, level: usize, registry: &Registry) -> Arc<CollisionMesh> {
+        let build = || {
+            let (min_y, max_y) = self.collision_level_range(level);
+            let min = [self.min.0, min_y, self.min.2];
+            let max = [self.max.0, max_y, self.max.2];
+
+            Arc::new(CollisionMesh::build(&min, &max, |vx, vy, vz| {
+                registry
+                    .get_block_by_id(self.get_voxel(vx, vy, vz))
+                    .collider(&self.get_voxel_rotation(vx, vy, vz))
+            }))
+        };
+
+        let levels = self.collision.get_or_init(|| {
+            (0..self.options.sub_chunks.max(1))
+                .map(|_| OnceLock::new())
+                .collect()
+        });
+
+        match levels.get(level) {
+            Some(cell) => cell.get_or_init(build).clone(),
+            None => build(),
+        }
+    }
+
+    /// Drop every cached collision mesh, for writes to `voxels` that bypass
+    /// `set_raw_voxel`.
+    pub fn invalidate_collision(&mut self) {
+        self.collision.take();
+    }
+
+    /// Whether the voxel holds anything to collide with.
+    pub fn is_voxel_solid(&self, vx: i32, vy: i32, vz: i32, registry: &Registry) -> bool {
+        self.contains(vx, vy, vz)
+            && self
+                .collision_mesh(self.collision_level(vy), registry)
+                .is_solid(vx, vy, vz)
+    }
+
+    /// Push the collision boxes of this chunk's voxels from `min` to `max`
+    /// (inclusive) onto `boxes`. Dynamic shapes are resolved against `space`.
+    pub fn collect_collision_boxes(
+        &self,
+        min: &Vec3<i32>,
+        max: &Vec3<i32>,
+        space: &dyn VoxelAccess,
+        registry: &Registry,
+        boxes: &mut Vec<AABB>,
+    ) {
+        let min_y = min.1.max(0);
+        let max_y = max.1.min(self.options.max_height as i32 - 1);
+        if min_y > max_y {
+            return;
+        }
+
+        let region = AABB::create(
+            min.0 as f32,
+            min.1 as f32,
+            min.2 as f32,
+            (max.0 + 1) as f32,
+            (max.1 + 1) as f32,
+            (max.2 + 1) as f32,
+        );
+
+        for level in self.collision_level(min_y)..=self.collision_level(max_y) {
+            let mesh = self.collision_mesh(level, registry);
+            boxes.extend(mesh.boxes_within(&region).cloned());
+
+            for &[vx, vy, vz] in &mesh.dynamic {
+                if vx < min.0 || vx > max.0 || vy < min.1 || vy > max.1 || vz < min.2 || vz > max.2
+                {
+                    continue;
+                }
+
+                let rotation = self.get_voxel_rotation(vx, vy, vz);
+                let block = registry.get_block_by_id(self.get_voxel(vx, vy, vz));
+                boxes.extend(
+                    block
+                        .get_aabbs(&Vec3(vx, vy, vz), space, registry)
+                        .iter()
+                        .map(|aabb| {
+                            let mut aabb = rotation.rotate_aabb(aabb, true, true);
+                            aabb.translate(vx as f32, vy as f32, vz as f32);
+                            aabb
+                        }),
+                );
+            }
+        }
+    }
+
+    /// The collision level holding `vy`. Rows left over when the height
+    /// doesn't split evenly belong to the top level.
+    fn collision_level(&self, vy: i32) -> usize {
+        let levels = self.options.sub_chunks.max(1);
+        let partition = (self.options.max_height / levels).max(1);
+
+        (vy.max(0) as usize / partition).min(levels - 1)
+    }
+
+    /// The rows of collision level `level`, from inclusive to exclusive.
+    fn collision_level_range(&self, level: usize) -> (i32, i32) {
+        let levels = self.options.sub_chunks.max(1);
+        let partition = (self.options.max_height / levels).max(1);
+
+        let min_y = (level * partition).min(self.options.max_height);
+        let max_y = if level + 1 >= levels {
+            self.options.max_height
+        } else {
+            (level + 1) * partition
+        };
+
+        (min_y as i32, max_y as i32)
+    }
+
     /// Convert voxel coordinates to local chunk coordinates.
     fn to_local(&self, vx: i32, vy: i32, vz: i32) -> Vec3<usize> {
         let Vec3(mx, my, mz) = self.min;
@@ -216,6 +345,25 @@ impl Chunk {
     }
 }
 
+#[cfg(test)]
+impl Chunk {
+    /// An empty chunk at `(cx, cz)` shaped by `config`, already `Ready`.
+    pub(crate) fn ready(cx: i32, cz: i32, config: &crate::WorldConfig) -> Self {
+        let mut chunk = Chunk::new(
+            "test",
+            cx,
+            cz,
+            &ChunkOptions {
+                size: config.chunk_size,
+                max_height: config.max_height,
+                sub_chunks: config.sub_chunks,
+            },
+        );
+        chunk.status = ChunkStatus::Ready;
+        chunk
+    }
+}
+
 impl VoxelAccess for Chunk {
     fn waterlogging_rules(&self) -> Option<&super::waterlogging::WaterloggingRules> {
         self.waterlogging_rules.as_deref()
@@ -260,6 +408,15 @@ impl VoxelAccess for Chunk {
         if self.voxels[&index] != val {
             Arc::make_mut(&mut self.voxels)[&index] = val;
             self.is_save_dirty = true;
+
+            let level = self.collision_level(vy);
+            if let Some(cell) = self
+                .collision
+                .get_mut()
+                .and_then(|levels| levels.get_mut(level))
+            {
+                cell.take();
+            }
         }
 
         true
@@ -332,6 +489,17 @@ impl VoxelAccess for Chunk {
         Some(&self.voxels)
     }
 
+    fn get_collision_boxes(
+        &self,
+        min: &Vec3<i32>,
+        max: &Vec3<i32>,
+        registry: &Registry,
+    ) -> Option<Vec<AABB>> {
+        let mut boxes = Vec::new();
+        self.collect_collision_boxes(min, max, self, registry, &mut boxes);
+        Some(boxes)
+    }
+
     /// Check if chunk contains this voxel coordinate.
     fn contains(&self, vx: i32, vy: i32, vz: i32) -> bool {
         let ChunkOptions {
@@ -15,13 +15,15 @@ use std::{
 };
 
 use crate::{
-    BlockUtils, ChunkOptions, ChunkStatus, ChunkUtils, LightUtils, MessageType, Registry, Vec2,
-    Vec3, VoxelUpdate, WaterloggingRules, WorldConfig,
+    BlockUtils, BulkUpdate, ChunkOptions, ChunkStatus, ChunkUtils, LightUtils, MessageType,
+    Registry, Vec2, Vec3, VoxelUpdate, WaterloggingRules, WorldConfig, AABB,
 };
 
 use super::{
     access::VoxelAccess,
-    background_chunk_saver::{ChunkSaveData, CHUNK_FILE_VERSION},
+    background_chunk_saver::{
+        ChunkFileData, ChunkSaveData, ScheduledTick, CHUNK_FILE_VERSION, WATERLOGGED_BIT_VERSION,
+    },
     chunk::{Chunk, ChunkRenewal},
     space::{SpaceBuilder, SpaceOptions},
 };
@@ -52,15 +54,61 @@ pub(crate) struct PendingChunkSave {
     attempts: usize,
 }
 
-/// Prototype for chunk's internal data used to send to client
+/// A chunk's voxels and height map in the chunk file encoding, tagged with
+/// its coordinates. This is how chunks travel in a [`crate::WorldSnapshot`].
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct ChunkFileData {
-    id: String,
-    voxels: String,
-    height_map: String,
-    #[serde(default)]
-    version: u32,
+pub struct ChunkSnapshot {
+    pub x: i32,
+    pub z: i32,
+    #[serde(flatten)]
+    data: ChunkFileData,
+}
+
+impl ChunkSnapshot {
+    /// The same chunk with its pending ticks left behind, for a restore that
+    /// must not run the chunk's block ticks itself.
+    pub(crate) fn without_scheduled_ticks(mut self) -> Self {
+        self.data.scheduled_ticks.clear();
+        self
+    }
+}
+
+/// Pack `u32` words little-endian, zlib them, and base64 the result.
+fn encode_chunk_words(data: &[u32]) -> String {
+    let mut bytes = vec![0; data.len() * 4];
+    LittleEndian::write_u32_into(data, &mut bytes);
+
+    let mut encoder = Encoder::new(vec![]).unwrap();
+    encoder.write_all(bytes.as_slice()).unwrap();
+    let encoded = encoder.finish().into_result().unwrap();
+    base64::encode(&encoded)
+}
+
+/// Inverse of [`encode_chunk_words`].
+fn decode_chunk_words(base: &str) -> Result<Vec<u32>, String> {
+    if base.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let decoded = STANDARD
+        .decode(base)
+        .map_err(|err| format!("base64 decode failed: {err}"))?;
+    let mut decoder =
+        Decoder::new(&decoded[..]).map_err(|err| format!("zlib decoder failed: {err}"))?;
+    let mut buf = Vec::new();
+    decoder
+        .read_to_end(&mut buf)
+        .map_err(|err| format!("zlib decompress failed: {err}"))?;
+    if buf.len() % 4 != 0 {
+        return Err(format!(
+            "decoded byte length {} is not a multiple of 4",
+            buf.len()
+        ));
+    }
+    let mut data = vec![0; buf.len() / 4];
+    LittleEndian::read_u32_into(&buf, &mut data);
+    Ok(data)
 }
 
 /// Backfill the waterlogged bit on a chunk saved before waterlogging existed.
@@ -125,12 +173,22 @@ pub struct Chunks {
     /// A map of all the chunks, coords -> Chunk.
     pub map: HashMap<Vec2<i32>, Chunk>,
 
-    /// Voxel updates waiting to be processed.
+    /// Voxel updates waiting to be processed. Each voxel appears at most once.
     pub(crate) updates: VecDeque<VoxelUpdate>,
 
+    /// The value each voxel in `updates` is queued to take.
+    queued_raw: HashMap<Vec3<i32>, u32>,
+
     /// Staging area for new voxel updates (deduplicates before flushing to queue).
     pub(crate) updates_staging: HashMap<Vec3<i32>, u32>,
 
+    /// Bulk updates waiting to be committed whole on the next update pass.
+    pub(crate) bulk_updates: VecDeque<BulkUpdate>,
+
+    /// The value each voxel in `bulk_updates` is about to take, the latest
+    /// bulk winning.
+    bulk_raw: HashMap<Vec3<i32>, u32>,
+
     /// A list of chunks that are done meshing and ready to be sent.
     pub(crate) to_send: VecDeque<(Vec2<i32>, MessageType)>,
 
@@ -140,6 +198,10 @@ pub struct Chunks {
     pub(crate) active_voxel_heap: BinaryHeap<Reverse<ActiveVoxel>>,
     pub(crate) active_voxel_set: HashMap<Vec3<i32>, u64>,
 
+    /// The voxels in `active_voxel_set`, grouped by the chunk they lie in, so
+    /// saving a chunk does not scan every pending tick in the world.
+    pub(crate) active_voxels_by_chunk: HashMap<Vec2<i32>, HashSet<Vec3<i32>>>,
+
     /// A listener for when a chunk is done generating or meshing.
     pub(crate) listeners: HashMap<Vec2<i32>, Vec<Vec2<i32>>>,
 
@@ -210,11 +272,15 @@ impl Chunks {
 
         self.map.clear();
         self.updates.clear();
+        self.queued_raw.clear();
         self.updates_staging.clear();
+        self.bulk_updates.clear();
+        self.bulk_raw.clear();
         self.to_send.clear();
         self.to_save.clear();
         self.active_voxel_heap.clear();
         self.active_voxel_set.clear();
+        self.active_voxels_by_chunk.clear();
         self.listeners.clear();
         self.cache.clear();
         self.freshly_created.clear();
@@ -267,7 +333,26 @@ impl Chunks {
 
     // Try to load the data of a chunk, returns whether successful or not.
     // On corrupt/empty/invalid saves, removes the file so the chunk can regenerate.
-    pub fn try_load(&self, coords: &Vec2<i32>, registry: &Registry) -> Option<Chunk> {
+    // Ticks the chunk had pending when it was saved resume from `current_tick`.
+    pub fn try_load(
+        &mut self,
+        coords: &Vec2<i32>,
+        registry: &Registry,
+        current_tick: u64,
+    ) -> Option<Chunk> {
+        let (chunk, scheduled_ticks) = self.read_chunk_file(coords, registry)?;
+        self.restore_scheduled_ticks(coords, &scheduled_ticks, current_tick);
+        Some(chunk)
+    }
+
+    /// The read-only half of [`Chunks::try_load`], safe to run for many chunks
+    /// in parallel. The pending ticks it returns have not been scheduled yet;
+    /// hand them to [`Chunks::restore_scheduled_ticks`].
+    pub(crate) fn read_chunk_file(
+        &self,
+        coords: &Vec2<i32>,
+        registry: &Registry,
+    ) -> Option<(Chunk, Vec<ScheduledTick>)> {
         if !self.config.saving {
             return None;
         }
@@ -289,7 +374,7 @@ impl Chunks {
         };
         let chunk_data = BufReader::new(file);
 
-        let data: ChunkFileData = match serde_json::from_reader(chunk_data) {
+        let mut data: ChunkFileData = match serde_json::from_reader(chunk_data) {
             Ok(data) => data,
             Err(err) => {
                 self.remove_corrupt_chunk_file(&path, &format!("invalid JSON ({err})"));
@@ -297,50 +382,34 @@ impl Chunks {
             }
         };
 
-        let decode_base64 = |base: &str| -> Result<Vec<u32>, String> {
-            if base.is_empty() {
-                return Ok(vec![]);
-            }
+        let scheduled_ticks = std::mem::take(&mut data.scheduled_ticks);
 
-            let decoded = STANDARD
-                .decode(base)
-                .map_err(|err| format!("base64 decode failed: {err}"))?;
-            let mut decoder =
-                Decoder::new(&decoded[..]).map_err(|err| format!("zlib decoder failed: {err}"))?;
-            let mut buf = Vec::new();
-            decoder
-                .read_to_end(&mut buf)
-                .map_err(|err| format!("zlib decompress failed: {err}"))?;
-            if buf.len() % 4 != 0 {
-                return Err(format!(
-                    "decoded byte length {} is not a multiple of 4",
-                    buf.len()
-                ));
+        match self.assemble_chunk(coords, data, registry, &path.display().to_string()) {
+            Ok(chunk) => Some((chunk, scheduled_ticks)),
+            Err(reason) => {
+                self.remove_corrupt_chunk_file(&path, &reason);
+                None
             }
-            let mut data = vec![0; buf.len() / 4];
-            LittleEndian::read_u32_into(&buf, &mut data);
-            Ok(data)
-        };
+        }
+    }
 
+    /// Decode file-format chunk data into a chunk parked at `Meshing`,
+    /// validating its shape against this world's config. `source` names where
+    /// the data came from in warnings.
+    fn assemble_chunk(
+        &self,
+        coords: &Vec2<i32>,
+        data: ChunkFileData,
+        registry: &Registry,
+        source: &str,
+    ) -> Result<Chunk, String> {
         let (voxels_result, height_map_result) = rayon::join(
-            || decode_base64(&data.voxels),
-            || decode_base64(&data.height_map),
+            || decode_chunk_words(&data.voxels),
+            || decode_chunk_words(&data.height_map),
         );
 
-        let voxels = match voxels_result {
-            Ok(voxels) => voxels,
-            Err(err) => {
-                self.remove_corrupt_chunk_file(&path, &format!("voxels: {err}"));
-                return None;
-            }
-        };
-        let height_map = match height_map_result {
-            Ok(height_map) => height_map,
-            Err(err) => {
-                self.remove_corrupt_chunk_file(&path, &format!("height_map: {err}"));
-                return None;
-            }
-        };
+        let voxels = voxels_result.map_err(|err| format!("voxels: {err}"))?;
+        let height_map = height_map_result.map_err(|err| format!("height_map: {err}"))?;
 
         let size = self.config.chunk_size;
         let max_height = self.config.max_height;
@@ -348,17 +417,13 @@ impl Chunks {
         let expected_height_map = size * size;
 
         if voxels.is_empty() || voxels.len() != expected_voxels {
-            self.remove_corrupt_chunk_file(
-                &path,
-                &format!(
-                    "voxels length {} does not match chunk_size={} max_height={} (expected {})",
-                    voxels.len(),
-                    size,
-                    max_height,
-                    expected_voxels
-                ),
-            );
-            return None;
+            return Err(format!(
+                "voxels length {} does not match chunk_size={} max_height={} (expected {})",
+                voxels.len(),
+                size,
+                max_height,
+                expected_voxels
+            ));
         }
 
         let mut chunk = Chunk::new(
@@ -373,6 +438,7 @@ impl Chunks {
         );
 
         Arc::make_mut(&mut chunk.voxels).data = voxels;
+        chunk.invalidate_collision();
         chunk.top_filled_y = None;
 
         let mut is_save_dirty = false;
@@ -381,8 +447,8 @@ impl Chunks {
         } else {
             if !height_map.is_empty() {
                 warn!(
-                    "Chunk save at {} has height_map length {} (expected {}); recalculating from voxels",
-                    path.display(),
+                    "Chunk data from {} has height_map length {} (expected {}); recalculating from voxels",
+                    source,
                     height_map.len(),
                     expected_height_map
                 );
@@ -395,14 +461,16 @@ impl Chunks {
         chunk.status = ChunkStatus::Meshing;
         chunk.is_save_dirty = is_save_dirty;
 
-        if data.version < CHUNK_FILE_VERSION && backfill_waterlogged_voxels(&mut chunk, registry) {
+        if data.version < WATERLOGGED_BIT_VERSION
+            && backfill_waterlogged_voxels(&mut chunk, registry)
+        {
             chunk.is_save_dirty = true;
         }
 
-        Some(chunk)
+        Ok(chunk)
     }
 
-    pub fn save(&self, coords: &Vec2<i32>) -> bool {
+    pub fn save(&self, coords: &Vec2<i32>, current_tick: u64) -> bool {
         if !self.config.saving {
             panic!("Calling `chunks.save` when saving mode is not on.");
         }
@@ -416,21 +484,12 @@ impl Chunks {
         let path = self.get_chunk_file_path(&chunk.name);
         let tmp_path = path.with_extension("json.tmp");
 
-        let to_base_64 = |data: &Vec<u32>| {
-            let mut bytes = vec![0; data.len() * 4];
-            LittleEndian::write_u32_into(data, &mut bytes);
-
-            let mut encoder = Encoder::new(vec![]).unwrap();
-            encoder.write_all(bytes.as_slice()).unwrap();
-            let encoded = encoder.finish().into_result().unwrap();
-            base64::encode(&encoded)
-        };
-
         let data = ChunkFileData {
             id: chunk.id.to_owned(),
-            voxels: to_base_64(&chunk.voxels.data),
-            height_map: to_base_64(&chunk.height_map.data),
+            voxels: encode_chunk_words(&chunk.voxels.data),
+            height_map: encode_chunk_words(&chunk.height_map.data),
             version: CHUNK_FILE_VERSION,
+            scheduled_ticks: self.scheduled_ticks_in(coords, current_tick),
         };
 
         let j = match serde_json::to_string(&data) {
@@ -463,7 +522,50 @@ impl Chunks {
         true
     }
 
-    pub fn prepare_save_data(&self, coords: &Vec2<i32>) -> Option<ChunkSaveData> {
+    /// Encode every chunk that has finished generating, along with its pending
+    /// ticks relative to `current_tick`. Chunks still in the pipeline are left
+    /// out; whoever restores the snapshot regenerates them.
+    pub fn snapshot(&self, current_tick: u64) -> Vec<ChunkSnapshot> {
+        self.map
+            .values()
+            .filter(|chunk| matches!(chunk.status, ChunkStatus::Meshing | ChunkStatus::Ready))
+            .map(|chunk| ChunkSnapshot {
+                x: chunk.coords.0,
+                z: chunk.coords.1,
+                data: ChunkFileData {
+                    id: chunk.id.to_owned(),
+                    voxels: encode_chunk_words(&chunk.voxels.data),
+                    height_map: encode_chunk_words(&chunk.height_map.data),
+                    version: CHUNK_FILE_VERSION,
+                    scheduled_ticks: self.scheduled_ticks_in(&chunk.coords, current_tick),
+                },
+            })
+            .collect()
+    }
+
+    /// Decode a chunk from a snapshot, parked at `Meshing` like a chunk loaded
+    /// from disk. Fails if the chunk lies outside this world or its shape does
+    /// not match this world's config. The pending ticks it returns have not
+    /// been scheduled yet; hand them to [`Chunks::restore_scheduled_ticks`].
+    pub fn restore(
+        &self,
+        mut snapshot: ChunkSnapshot,
+        registry: &Registry,
+    ) -> Result<(Chunk, Vec<ScheduledTick>), String> {
+        let coords = Vec2(snapshot.x, snapshot.z);
+        if !self.is_within_world(&coords) {
+            return Err(format!("chunk {:?} is outside of the world", coords));
+        }
+        let scheduled_ticks = std::mem::take(&mut snapshot.data.scheduled_ticks);
+        let chunk = self.assemble_chunk(&coords, snapshot.data, registry, "snapshot")?;
+        Ok((chunk, scheduled_ticks))
+    }
+
+    pub fn prepare_save_data(
+        &self,
+        coords: &Vec2<i32>,
+        current_tick: u64,
+    ) -> Option<ChunkSaveData> {
         let chunk = self.get(coords)?;
         Some(ChunkSaveData {
             coords: coords.to_owned(),
@@ -471,9 +573,57 @@ impl Chunks {
             chunk_id: chunk.id.clone(),
             voxels: chunk.voxels.data.clone(),
             height_map: chunk.height_map.data.clone(),
+            scheduled_ticks: self.scheduled_ticks_in(coords, current_tick),
         })
     }
 
+    /// Every voxel in the chunk at `coords` still waiting on its active
+    /// update, with deadlines made relative to `current_tick`. Sorted by
+    /// position so an unchanged schedule writes an unchanged file.
+    pub fn scheduled_ticks_in(&self, coords: &Vec2<i32>, current_tick: u64) -> Vec<ScheduledTick> {
+        let mut scheduled: Vec<ScheduledTick> = self
+            .active_voxels_by_chunk
+            .get(coords)
+            .into_iter()
+            .flatten()
+            .filter_map(|voxel| {
+                let deadline = self.active_voxel_set.get(voxel)?;
+                Some(ScheduledTick {
+                    voxel: voxel.clone(),
+                    delay: deadline.saturating_sub(current_tick),
+                })
+            })
+            .collect();
+
+        scheduled.sort_by_key(|tick| (tick.voxel.0, tick.voxel.1, tick.voxel.2));
+        scheduled
+    }
+
+    /// Put the pending ticks read from the chunk at `coords` back into the
+    /// active heap, `delay` ticks after `current_tick`. Entries that do not
+    /// lie inside that chunk are dropped: a chunk only ever schedules its own
+    /// voxels, so anything else came from a hand-edited or mismatched file.
+    pub fn restore_scheduled_ticks(
+        &mut self,
+        coords: &Vec2<i32>,
+        scheduled_ticks: &[ScheduledTick],
+        current_tick: u64,
+    ) {
+        let chunk_size = self.config.chunk_size;
+
+        for ScheduledTick { voxel, delay } in scheduled_ticks {
+            if ChunkUtils::map_voxel_to_chunk(voxel.0, voxel.1, voxel.2, chunk_size) != *coords {
+                warn!(
+                    "Ignoring scheduled tick at {:?} saved with chunk {:?}",
+                    voxel, coords
+                );
+                continue;
+            }
+
+            self.mark_voxel_active(voxel, current_tick.saturating_add(*delay));
+        }
+    }
+
     /// Take up to `max_saves` chunks off the save queue, prepared for the
     /// background saver.
     ///
@@ -487,6 +637,7 @@ impl Chunks {
         &mut self,
         max_saves: usize,
         max_retries: usize,
+        current_tick: u64,
     ) -> Vec<ChunkSaveData> {
         let mut prepared = Vec::new();
         let mut deferred = Vec::new();
@@ -499,7 +650,7 @@ impl Chunks {
                 break;
             };
 
-            if let Some(data) = self.prepare_save_data(&pending.coords) {
+            if let Some(data) = self.prepare_save_data(&pending.coords, current_tick) {
                 prepared.push(data);
                 continue;
             }
@@ -602,6 +753,13 @@ impl Chunks {
         self.raw_mut(&coords)
     }
 
+    /// Whether the voxel holds anything to collide with, read from the
+    /// collision mesh of its chunk. Voxels of missing chunks hold nothing.
+    pub fn is_voxel_solid(&self, vx: i32, vy: i32, vz: i32, registry: &Registry) -> bool {
+        self.raw_chunk_by_voxel(vx, vy, vz)
+            .is_some_and(|chunk| chunk.is_voxel_solid(vx, vy, vz, registry))
+    }
+
     /// Get neighboring coords of a voxel coordinate.
     pub fn voxel_affected_chunks(&self, vx: i32, vy: i32, vz: i32) -> Vec<Vec2<i32>> {
         let mut neighbors = vec![];
@@ -727,6 +885,16 @@ impl Chunks {
         self.updates_staging.insert(voxel.to_owned(), val);
     }
 
+    /// The value a voxel is about to take from an update that is staged or
+    /// queued but not yet applied, if any.
+    pub fn pending_raw_voxel(&self, voxel: &Vec3<i32>) -> Option<u32> {
+        self.bulk_raw
+            .get(voxel)
+            .or_else(|| self.updates_staging.get(voxel))
+            .or_else(|| self.queued_raw.get(voxel))
+            .copied()
+    }
+
     /// Flush staged updates into the processing queue. Called before processing updates.
     ///
     /// Staged updates commit in (y, x, z) order rather than HashMap order:
@@ -746,16 +914,66 @@ impl Chunks {
         staged.sort_by_key(|(voxel, _)| (voxel.1, voxel.0, voxel.2));
 
         for (voxel, val) in staged {
+            self.queued_raw.insert(voxel.clone(), val);
             self.updates.push_back((voxel, val));
         }
     }
 
+    /// Take the update at the front of the processing queue.
+    pub(crate) fn pop_update(&mut self) -> Option<VoxelUpdate> {
+        let (voxel, raw) = self.updates.pop_front()?;
+        self.queued_raw.remove(&voxel);
+        Some((voxel, raw))
+    }
+
+    /// Put an update taken off the queue back at its front, to retry on the
+    /// next pass. An update queued since for the same voxel overwrites it
+    /// anyway, so it is dropped instead.
+    pub(crate) fn requeue_update(&mut self, voxel: Vec3<i32>, raw: u32) {
+        if self.queued_raw.contains_key(&voxel) {
+            return;
+        }
+        self.queued_raw.insert(voxel.clone(), raw);
+        self.updates.push_front((voxel, raw));
+    }
+
+    /// Take every bulk update waiting to be committed, oldest first.
+    pub(crate) fn take_bulk_updates(&mut self) -> Vec<BulkUpdate> {
+        self.bulk_raw.clear();
+        self.bulk_updates.drain(..).collect()
+    }
+
+    /// Put a bulk update taken off the queue back at its front, whole, to
+    /// retry on the next pass. Bulks queued since still win any voxel both
+    /// touch.
+    pub(crate) fn requeue_bulk_update(&mut self, bulk: BulkUpdate) {
+        for (voxel, raw) in &bulk.0 {
+            self.bulk_raw.entry(voxel.clone()).or_insert(*raw);
+        }
+        self.bulk_updates.push_front(bulk);
+    }
+
     pub fn update_voxels(&mut self, voxels: &[(Vec3<i32>, u32)]) {
         for (voxel, val) in voxels {
             self.update_voxel(voxel, *val);
         }
     }
 
+    /// Queue a set of updates that must land together. Unlike
+    /// [`Self::update_voxels`], a bulk update is never split, neither by
+    /// `max_updates_per_tick` nor by a chunk that is not ready: the first
+    /// update pass in which every chunk it touches is ready commits all of
+    /// it, after any ordinary updates it covers, with one light removal per
+    /// color.
+    pub fn update_voxels_bulk(&mut self, bulk: BulkUpdate) {
+        if !bulk.0.is_empty() {
+            for (voxel, raw) in &bulk.0 {
+                self.bulk_raw.insert(voxel.clone(), *raw);
+            }
+            self.bulk_updates.push_back(bulk);
+        }
+    }
+
     /// Schedule `voxel` to become active at absolute tick `active_at`.
     ///
     /// Earliest-deadline upsert:
@@ -783,6 +1001,42 @@ impl Chunks {
             tick: active_at,
             voxel: voxel.clone(),
         }));
+        let coords =
+            ChunkUtils::map_voxel_to_chunk(voxel.0, voxel.1, voxel.2, self.config.chunk_size);
+        self.active_voxels_by_chunk
+            .entry(coords)
+            .or_default()
+            .insert(voxel.clone());
+    }
+
+    /// Pop every voxel whose active update is due by `current_tick`, in heap
+    /// order. Heap entries superseded by an earlier deadline are discarded.
+    pub fn take_due_active_voxels(&mut self, current_tick: u64) -> Vec<Vec3<i32>> {
+        let mut due = Vec::new();
+        while let Some(Reverse(active)) = self.active_voxel_heap.peek() {
+            if active.tick > current_tick {
+                break;
+            }
+            let Reverse(active) = self.active_voxel_heap.pop().unwrap();
+            if self.active_voxel_set.get(&active.voxel).copied() != Some(active.tick) {
+                continue;
+            }
+            self.active_voxel_set.remove(&active.voxel);
+            let coords = ChunkUtils::map_voxel_to_chunk(
+                active.voxel.0,
+                active.voxel.1,
+                active.voxel.2,
+                self.config.chunk_size,
+            );
+            if let Some(voxels) = self.active_voxels_by_chunk.get_mut(&coords) {
+                voxels.remove(&active.voxel);
+                if voxels.is_empty() {
+                    self.active_voxels_by_chunk.remove(&coords);
+                }
+            }
+            due.push(active.voxel);
+        }
+        due
     }
 
     /// Absolute tick currently scheduled for `voxel`, if any.
@@ -797,7 +1051,13 @@ impl Chunks {
 
     /// Number of voxel updates staged or queued but not yet committed.
     pub fn pending_updates_count(&self) -> usize {
-        self.updates.len() + self.updates_staging.len()
+        self.updates.len()
+            + self.updates_staging.len()
+            + self
+                .bulk_updates
+                .iter()
+                .map(|bulk| bulk.0.len())
+                .sum::<usize>()
     }
 
     /// Add a chunk to be saved. A world that does not save discards the request
@@ -963,6 +1223,28 @@ impl VoxelAccess for Chunks {
         false
     }
 
+    fn get_collision_boxes(
+        &self,
+        min: &Vec3<i32>,
+        max: &Vec3<i32>,
+        registry: &Registry,
+    ) -> Option<Vec<AABB>> {
+        let chunk_size = self.config.chunk_size;
+        let Vec2(min_cx, min_cz) = ChunkUtils::map_voxel_to_chunk(min.0, 0, min.2, chunk_size);
+        let Vec2(max_cx, max_cz) = ChunkUtils::map_voxel_to_chunk(max.0, 0, max.2, chunk_size);
+
+        let mut boxes = Vec::new();
+        for cx in min_cx..=max_cx {
+            for cz in min_cz..=max_cz {
+                if let Some(chunk) = self.raw(&Vec2(cx, cz)) {
+                    chunk.collect_collision_boxes(min, max, self, registry, &mut boxes);
+                }
+            }
+        }
+
+        Some(boxes)
+    }
+
     fn contains(&self, vx: i32, vy: i32, vz: i32) -> bool {
         self.raw_chunk_by_voxel(vx, vy, vz).is_some()
     }
@@ -1035,7 +1317,7 @@ mod pending_save_queue_tests {
         put_chunk(&mut chunks, &coords, ChunkStatus::Meshing);
         chunks.add_chunk_to_save(&coords, false);
 
-        assert!(chunks.take_pending_saves(8, max_retries).is_empty());
+        assert!(chunks.take_pending_saves(8, max_retries, 0).is_empty());
         assert_eq!(
             chunks.to_save.len(),
             1,
@@ -1044,7 +1326,7 @@ mod pending_save_queue_tests {
 
         put_chunk(&mut chunks, &coords, ChunkStatus::Ready);
 
-        let prepared = chunks.take_pending_saves(8, max_retries);
+        let prepared = chunks.take_pending_saves(8, max_retries, 0);
         assert_eq!(prepared.len(), 1);
         assert_eq!(prepared[0].coords, coords);
         assert!(chunks.to_save.is_empty());
@@ -1060,7 +1342,7 @@ mod pending_save_queue_tests {
         chunks.add_chunk_to_save(&coords, false);
 
         for tick in 1..max_retries {
-            assert!(chunks.take_pending_saves(8, max_retries).is_empty());
+            assert!(chunks.take_pending_saves(8, max_retries, 0).is_empty());
             assert_eq!(
                 chunks.to_save.len(),
                 1,
@@ -1068,7 +1350,7 @@ mod pending_save_queue_tests {
             );
         }
 
-        assert!(chunks.take_pending_saves(8, max_retries).is_empty());
+        assert!(chunks.take_pending_saves(8, max_retries, 0).is_empty());
         assert!(
             chunks.to_save.is_empty(),
             "the entry is dropped once the budget is spent, and only then"
@@ -1086,7 +1368,7 @@ mod pending_save_queue_tests {
         chunks.add_chunk_to_save(&stuck, false);
         chunks.add_chunk_to_save(&ready, false);
 
-        let prepared = chunks.take_pending_saves(8, 8);
+        let prepared = chunks.take_pending_saves(8, 8, 0);
 
         assert_eq!(prepared.len(), 1);
         assert_eq!(prepared[0].coords, ready);
@@ -1107,7 +1389,7 @@ mod pending_save_queue_tests {
             chunks.add_chunk_to_save(&coords, false);
         }
 
-        assert_eq!(chunks.take_pending_saves(2, 8).len(), 2);
+        assert_eq!(chunks.take_pending_saves(2, 8, 0).len(), 2);
         assert_eq!(chunks.to_save.len(), 3);
     }
 
@@ -1159,41 +1441,357 @@ mod active_voxel_upsert_tests {
         chunks.mark_voxel_active(&voxel, 100);
         chunks.mark_voxel_active(&voxel, 10);
 
-        // Simulate the ChunkUpdatingSystem pop loop at tick 10.
+        // The ChunkUpdatingSystem pop at tick 10.
         let current_tick = 10u64;
-        let mut due = Vec::new();
-        while let Some(Reverse(active)) = chunks.active_voxel_heap.peek() {
-            if active.tick > current_tick {
-                break;
-            }
-            let Reverse(active) = chunks.active_voxel_heap.pop().unwrap();
-            match chunks.active_voxel_set.get(&active.voxel).copied() {
-                Some(scheduled) if scheduled == active.tick => {
-                    chunks.active_voxel_set.remove(&active.voxel);
-                    due.push(active.voxel);
-                }
-                _ => {}
-            }
-        }
+        let due = chunks.take_due_active_voxels(current_tick);
         assert_eq!(due, vec![voxel.clone()]);
         assert!(chunks.active_voxel_deadline(&voxel).is_none());
 
         // Stale T+100 entry must not fire later.
         let current_tick = 100u64;
-        let mut due2 = Vec::new();
-        while let Some(Reverse(active)) = chunks.active_voxel_heap.peek() {
-            if active.tick > current_tick {
-                break;
+        let due2 = chunks.take_due_active_voxels(current_tick);
+        assert!(due2.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod pending_update_tests {
+    use super::*;
+    use crate::WorldConfig;
+
+    #[test]
+    fn pending_values_follow_the_queue_through_pops_and_requeues() {
+        let mut chunks = Chunks::new(&WorldConfig::new().build());
+        chunks.update_voxel(&Vec3(1, 2, 3), 5);
+        chunks.update_voxel(&Vec3(1, 3, 3), 6);
+        chunks.flush_staged_updates();
+        assert_eq!(chunks.pending_raw_voxel(&Vec3(1, 2, 3)), Some(5));
+
+        let (voxel, raw) = chunks.pop_update().unwrap();
+        assert_eq!(voxel, Vec3(1, 2, 3));
+        assert_eq!(chunks.pending_raw_voxel(&voxel), None);
+
+        chunks.requeue_update(voxel.clone(), raw);
+        assert_eq!(chunks.pending_raw_voxel(&voxel), Some(5));
+
+        // A later write to the same voxel supersedes the requeued one.
+        chunks.update_voxel(&voxel, 7);
+        chunks.flush_staged_updates();
+        chunks.requeue_update(voxel.clone(), raw);
+        assert_eq!(chunks.pending_raw_voxel(&voxel), Some(7));
+        assert_eq!(chunks.updates.len(), 2);
+
+        chunks.update_voxels_bulk(BulkUpdate(vec![(Vec3(1, 3, 3), 9)]));
+        assert_eq!(chunks.pending_raw_voxel(&Vec3(1, 3, 3)), Some(9));
+        chunks.take_bulk_updates();
+        assert_eq!(chunks.pending_raw_voxel(&Vec3(1, 3, 3)), Some(6));
+    }
+}
+
+#[cfg(test)]
+mod scheduled_tick_persistence_tests {
+    use nanoid::nanoid;
+
+    use super::*;
+    use crate::BackgroundChunkSaver;
+
+    fn saving_chunks(dir: &std::path::Path) -> Chunks {
+        Chunks::new(
+            &WorldConfig::new()
+                .saving(true)
+                .save_dir(dir.to_str().expect("utf-8 temp path"))
+                .build(),
+        )
+    }
+
+    fn put_ready_chunk(chunks: &mut Chunks, coords: &Vec2<i32>) {
+        let chunk = Chunk::ready(coords.0, coords.1, &chunks.config);
+        chunks.renew(chunk, ChunkRenewal::Full);
+    }
+
+    fn fresh_dir(label: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("voxelize-scheduled-ticks-{}-{}", label, nanoid!()))
+    }
+
+    #[test]
+    fn pending_ticks_survive_a_restart_with_their_remaining_delay() {
+        let dir = fresh_dir("restart");
+        let coords = Vec2(0, 0);
+
+        let mut chunks = saving_chunks(&dir);
+        put_ready_chunk(&mut chunks, &coords);
+        chunks.mark_voxel_active(&Vec3(1, 10, 1), 105);
+        chunks.mark_voxel_active(&Vec3(2, 10, 3), 130);
+        // Overdue when saved: it must fire right away after the load, not never.
+        chunks.mark_voxel_active(&Vec3(3, 10, 3), 90);
+        // Another chunk's schedule stays out of this chunk's file.
+        chunks.mark_voxel_active(&Vec3(-1, 10, 1), 101);
+        assert!(chunks.save(&coords, 100));
+
+        let mut restarted = saving_chunks(&dir);
+        let registry = Registry::new();
+        assert!(restarted.try_load(&coords, &registry, 5000).is_some());
+
+        assert_eq!(restarted.active_voxel_deadline(&Vec3(1, 10, 1)), Some(5005));
+        assert_eq!(restarted.active_voxel_deadline(&Vec3(2, 10, 3)), Some(5030));
+        assert_eq!(restarted.active_voxel_deadline(&Vec3(3, 10, 3)), Some(5000));
+        assert_eq!(restarted.active_voxel_deadline(&Vec3(-1, 10, 1)), None);
+        assert_eq!(restarted.active_voxel_count(), 3);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn the_background_saver_writes_the_same_schedule() {
+        let dir = fresh_dir("background");
+        let coords = Vec2(0, 0);
+
+        let mut chunks = saving_chunks(&dir);
+        put_ready_chunk(&mut chunks, &coords);
+        chunks.mark_voxel_active(&Vec3(4, 20, 4), 212);
+
+        let data = chunks
+            .prepare_save_data(&coords, 200)
+            .expect("chunk is ready");
+        assert_eq!(
+            data.scheduled_ticks,
+            vec![ScheduledTick {
+                voxel: Vec3(4, 20, 4),
+                delay: 12
+            }]
+        );
+
+        let saver = BackgroundChunkSaver::new(chunks.folder().cloned());
+        saver.queue_save(data);
+        drop(saver);
+
+        let mut restarted = saving_chunks(&dir);
+        assert!(restarted.try_load(&coords, &Registry::new(), 0).is_some());
+        assert_eq!(restarted.active_voxel_deadline(&Vec3(4, 20, 4)), Some(12));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn a_file_from_before_scheduled_ticks_still_loads() {
+        let dir = fresh_dir("legacy");
+        let coords = Vec2(0, 0);
+
+        let mut chunks = saving_chunks(&dir);
+        put_ready_chunk(&mut chunks, &coords);
+        let chunk = chunks.get(&coords).unwrap();
+        let legacy = serde_json::json!({
+            "id": chunk.id,
+            "voxels": encode_chunk_words(&chunk.voxels.data),
+            "heightMap": encode_chunk_words(&chunk.height_map.data),
+            "version": 1,
+        });
+        let path = chunks.get_chunk_file_path(&chunk.name);
+        fs::write(&path, legacy.to_string()).unwrap();
+
+        let mut restarted = saving_chunks(&dir);
+        assert!(restarted.try_load(&coords, &Registry::new(), 0).is_some());
+        assert_eq!(restarted.active_voxel_count(), 0);
+        assert!(
+            path.exists(),
+            "a valid old file must not be treated as corrupt"
+        );
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
+
+#[cfg(test)]
+mod collision_mesh_tests {
+    use super::*;
+    use crate::{sweep, Block, BlockRotation, Ndarray};
+
+    const STONE: u32 = 1;
+    const SLAB: u32 = 2;
+    const WEED: u32 = 3;
+
+    /// Reads voxels through `Chunks` but keeps no collision meshes, so sweeps
+    /// fall back to collecting boxes voxel by voxel.
+    struct PerVoxel<'a>(&'a Chunks);
+
+    impl VoxelAccess for PerVoxel<'_> {
+        fn get_raw_voxel(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+            self.0.get_raw_voxel(vx, vy, vz)
+        }
+
+        fn contains(&self, vx: i32, vy: i32, vz: i32) -> bool {
+            self.0.contains(vx, vy, vz)
+        }
+    }
+
+    fn test_registry() -> Registry {
+        let mut registry = Registry::new();
+        registry.register_block(&Block::new("Stone").id(STONE).build());
+        registry.register_block(
+            &Block::new("Slab")
+                .id(SLAB)
+                .rotatable(true)
+                .aabbs(&[AABB::create(0.0, 0.0, 0.0, 1.0, 0.5, 1.0)])
+                .build(),
+        );
+        registry.register_block(&Block::new("Weed").id(WEED).is_passable(true).build());
+        registry
+    }
+
+    fn terrain() -> Chunks {
+        let mut chunks = Chunks::new(
+            &WorldConfig::new()
+                .chunk_size(16)
+                .max_height(32)
+                .sub_chunks(2)
+                .build(),
+        );
+
+        for cx in -1..=0 {
+            for cz in -1..=0 {
+                chunks.renew(
+                    Chunk::new(
+                        "collision-test",
+                        cx,
+                        cz,
+                        &ChunkOptions {
+                            size: 16,
+                            max_height: 32,
+                            sub_chunks: 2,
+                        },
+                    ),
+                    ChunkRenewal::Full,
+                );
             }
-            let Reverse(active) = chunks.active_voxel_heap.pop().unwrap();
-            match chunks.active_voxel_set.get(&active.voxel).copied() {
-                Some(scheduled) if scheduled == active.tick => {
-                    chunks.active_voxel_set.remove(&active.voxel);
-                    due2.push(active.voxel);
+        }
+
+        for vx in -16..16 {
+            for vz in -16..16 {
+                for vy in 0..=15 {
+                    chunks.set_voxel(vx, vy, vz, STONE);
+                }
+                // A wall on each side of the chunk border, crossing the
+                // levels at y = 16.
+                if vx == 3 || vx == -4 {
+                    for vy in 16..=18 {
+                        chunks.set_voxel(vx, vy, vz, STONE);
+                    }
                 }
-                _ => {}
             }
         }
-        assert!(due2.is_empty());
+
+        for vz in -6..6 {
+            chunks.set_voxel(0, 16, vz, SLAB);
+            chunks.set_voxel(-1, 16, vz, SLAB);
+            chunks.set_voxel_rotation(-1, 16, vz, &BlockRotation::NY(0.0));
+            chunks.set_voxel(1, 16, vz, WEED);
+        }
+
+        chunks
+    }
+
+    fn sweep_through(space: &dyn VoxelAccess, registry: &Registry, velocity: &Vec3<f32>) -> AABB {
+        let mut aabb = AABB::create(-0.3, 17.2, -0.3, 0.3, 18.9, 0.3);
+        sweep(
+            space,
+            registry,
+            &mut aabb,
+            velocity,
+            &mut |_, axis, _, leftover| {
+                leftover[axis] = 0.0;
+                false
+            },
+            true,
+            10,
+        );
+        aabb
+    }
+
+    #[test]
+    fn sweeps_against_collision_meshes_match_per_voxel_sweeps() {
+        let registry = test_registry();
+        let chunks = terrain();
+
+        for velocity in [
+            Vec3(0.0, -3.0, 0.0),
+            Vec3(5.0, -0.5, 0.0),
+            Vec3(-5.0, -0.5, 1.0),
+            Vec3(2.0, -4.0, -7.0),
+            Vec3(-0.4, 2.0, 0.4),
+        ] {
+            let meshed = sweep_through(&chunks, &registry, &velocity);
+            let per_voxel = sweep_through(&PerVoxel(&chunks), &registry, &velocity);
+
+            for (a, b) in [
+                (meshed.min_x, per_voxel.min_x),
+                (meshed.min_y, per_voxel.min_y),
+                (meshed.min_z, per_voxel.min_z),
+            ] {
+                assert!(
+                    (a - b).abs() < 1e-4,
+                    "{velocity:?}: {meshed:?} vs {per_voxel:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn a_voxel_update_rebuilds_only_its_collision_level() {
+        let registry = test_registry();
+        let mut chunks = terrain();
+
+        assert!(chunks.is_voxel_solid(5, 10, 5, &registry));
+        assert!(!chunks.is_voxel_solid(5, 20, 5, &registry));
+        assert!(
+            !chunks.is_voxel_solid(1, 16, 0, &registry),
+            "weeds are passable"
+        );
+        assert!(
+            !chunks.is_voxel_solid(100, 10, 100, &registry),
+            "no chunk there"
+        );
+
+        let chunk = chunks.raw(&Vec2(0, 0)).unwrap();
+        let lower = chunk.collision_mesh(0, &registry);
+        let upper = chunk.collision_mesh(1, &registry);
+
+        chunks.set_voxel(5, 20, 5, STONE);
+
+        let chunk = chunks.raw(&Vec2(0, 0)).unwrap();
+        assert!(Arc::ptr_eq(&lower, &chunk.collision_mesh(0, &registry)));
+        assert!(!Arc::ptr_eq(&upper, &chunk.collision_mesh(1, &registry)));
+        assert!(chunks.is_voxel_solid(5, 20, 5, &registry));
+
+        let boxes = chunks
+            .get_collision_boxes(&Vec3(5, 20, 5), &Vec3(5, 20, 5), &registry)
+            .unwrap();
+        assert_eq!(boxes.len(), 1);
+        assert_eq!([boxes[0].min_y, boxes[0].max_y], [20.0, 21.0]);
+    }
+
+    #[test]
+    fn collision_meshes_are_cached_on_default_built_chunks_until_invalidated() {
+        let registry = test_registry();
+        let mut chunk = Chunk {
+            min: Vec3(0, 0, 0),
+            max: Vec3(16, 32, 16),
+            options: ChunkOptions {
+                size: 16,
+                max_height: 32,
+                sub_chunks: 2,
+            },
+            voxels: Arc::new(Ndarray::new(&[16, 32, 16], 0)),
+            ..Default::default()
+        };
+
+        let upper = chunk.collision_mesh(1, &registry);
+        assert!(Arc::ptr_eq(&upper, &chunk.collision_mesh(1, &registry)));
+        assert!(!chunk.is_voxel_solid(5, 20, 5, &registry));
+
+        Arc::make_mut(&mut chunk.voxels)[&[5, 20, 5]] = STONE;
+        chunk.invalidate_collision();
+
+        assert!(!Arc::ptr_eq(&upper, &chunk.collision_mesh(1, &registry)));
+        assert!(chunk.is_voxel_solid(5, 20, 5, &registry));
     }
 }
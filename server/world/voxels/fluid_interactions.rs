@@ -0,0 +1,374 @@
+use crate::{BlockUtils, Registry, Vec3, VoxelAccess, VoxelPacker, VoxelUpdate};
+
+/// Checked in this order, so a fluid falling onto something reacts with what
+/// it lands on before what it merely brushes against.
+const CONTACT_NEIGHBORS: [[i32; 3]; 6] = [
+    [0, -1, 0],
+    [-1, 0, 0],
+    [1, 0, 0],
+    [0, 0, -1],
+    [0, 0, 1],
+    [0, 1, 0],
+];
+
+/// What a fluid has to touch for a [`FluidInteraction`] to fire.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FluidContact {
+    /// A block with this id, typically another fluid. Matching the
+    /// waterlogging fluid also matches blocks waterlogged with it.
+    Block(u32),
+
+    /// Any block tagged with this name through [`Registry::tag_block`].
+    Tag(String),
+}
+
+/// One row of the registry's fluid interaction table: what happens when
+/// `fluid` flows into or sits next to something matching `contact`.
+///
+/// Lava cooling against water is `source_result` obsidian and
+/// `flowing_result` cobblestone; a sponge soaking up water is both results
+/// set to air (the water is consumed) and `contact_result` a wet sponge.
+#[derive(Debug, Clone)]
+pub struct FluidInteraction {
+    /// The fluid whose tick evaluates this interaction.
+    pub fluid: u32,
+
+    /// What the fluid has to be touching.
+    pub contact: FluidContact,
+
+    /// The block a source voxel of `fluid` turns into. `Some(0)` consumes the
+    /// source; `None` leaves it alone.
+    pub source_result: Option<u32>,
+
+    /// The block a flowing voxel of `fluid` turns into.
+    pub flowing_result: Option<u32>,
+
+    /// The block the touched voxel turns into. When the touched voxel is a
+    /// block waterlogged with the contact fluid, `Some(0)` only drains it.
+    pub contact_result: Option<u32>,
+
+    /// The sound effect played where the interaction happened.
+    pub sound: Option<String>,
+}
+
+impl FluidInteraction {
+    pub fn new(fluid: u32, contact: FluidContact) -> Self {
+        Self {
+            fluid,
+            contact,
+            source_result: None,
+            flowing_result: None,
+            contact_result: None,
+            sound: None,
+        }
+    }
+
+    pub fn source_result(mut self, id: u32) -> Self {
+        self.source_result = Some(id);
+        self
+    }
+
+    pub fn flowing_result(mut self, id: u32) -> Self {
+        self.flowing_result = Some(id);
+        self
+    }
+
+    /// Set both the source and the flowing result.
+    pub fn result(self, id: u32) -> Self {
+        self.source_result(id).flowing_result(id)
+    }
+
+    pub fn contact_result(mut self, id: u32) -> Self {
+        self.contact_result = Some(id);
+        self
+    }
+
+    pub fn sound(mut self, sound: &str) -> Self {
+        self.sound = Some(sound.to_owned());
+        self
+    }
+}
+
+/// The outcome of a fluid voxel meeting something in the interaction table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FluidReaction {
+    /// The fluid voxel that reacted.
+    pub voxel: Vec3<i32>,
+
+    /// The voxel it reacted with.
+    pub contact: Vec3<i32>,
+
+    /// Writes that replace the fluid's own simulation step for this tick.
+    pub updates: Vec<VoxelUpdate>,
+
+    /// The sound effect to play at `voxel`, if the interaction has one.
+    pub sound: Option<String>,
+}
+
+/// Run the registry's interaction table for the fluid at `voxel`.
+///
+/// The first interaction that matches a neighbor and actually changes
+/// something wins, and its writes stand in for the fluid's ordinary flow that
+/// tick. Waterlogged voxels never react on their own: the block they belong
+/// to is not a fluid.
+pub fn react_fluid(
+    voxel: &Vec3<i32>,
+    space: &dyn VoxelAccess,
+    registry: &Registry,
+) -> Option<FluidReaction> {
+    let Vec3(vx, vy, vz) = *voxel;
+    let id = space.get_voxel(vx, vy, vz);
+    if !registry.is_fluid(id) {
+        return None;
+    }
+
+    let is_source = space.get_voxel_stage(vx, vy, vz) == 0;
+
+    for interaction in registry.fluid_interactions(id) {
+        let own_result = if is_source {
+            interaction.source_result
+        } else {
+            interaction.flowing_result
+        };
+
+        for [dx, dy, dz] in CONTACT_NEIGHBORS {
+            let (nx, ny, nz) = (vx + dx, vy + dy, vz + dz);
+            if ny < 0 || !space.contains(nx, ny, nz) {
+                continue;
+            }
+
+            let neighbor_id = space.get_voxel(nx, ny, nz);
+            let is_direct = match &interaction.contact {
+                FluidContact::Block(block) => neighbor_id == *block,
+                FluidContact::Tag(tag) => registry.has_tag(neighbor_id, tag),
+            };
+            let is_waterlogged = !is_direct
+                && matches!(interaction.contact, FluidContact::Block(block)
+                    if registry.waterlogging_fluid_id() == Some(block))
+                && space.get_voxel_waterlogged(nx, ny, nz);
+
+            if !is_direct && !is_waterlogged {
+                continue;
+            }
+
+            let mut updates = Vec::new();
+            if let Some(result) = own_result {
+                updates.push((voxel.clone(), VoxelPacker::new().with_id(result).pack()));
+            }
+            if let Some(result) = interaction.contact_result {
+                let contact = Vec3(nx, ny, nz);
+                // A waterlogged block is not the fluid it holds. Consuming
+                // the fluid only drains the block and leaves it standing;
+                // turning the fluid into another block takes the whole voxel.
+                if !is_waterlogged {
+                    updates.push((contact, VoxelPacker::new().with_id(result).pack()));
+                } else if result == 0 {
+                    let raw = space.get_raw_voxel(nx, ny, nz);
+                    let drained = BlockUtils::insert_waterlog_level(
+                        BlockUtils::insert_waterlogged(raw, false),
+                        0,
+                    );
+                    updates.push((contact, drained));
+                } else if Some(result) != registry.waterlogging_fluid_id() {
+                    updates.push((contact, VoxelPacker::new().with_id(result).pack()));
+                }
+            }
+
+            if updates.is_empty() {
+                break;
+            }
+
+            return Some(FluidReaction {
+                voxel: voxel.clone(),
+                contact: Vec3(nx, ny, nz),
+                updates,
+                sound: interaction.sound.clone(),
+            });
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Block, Chunk, Chunks, FluidConfig, WorldConfig};
+
+    const STONE: u32 = 1;
+    const WATER: u32 = 2;
+    const LAVA: u32 = 3;
+    const OBSIDIAN: u32 = 4;
+    const COBBLESTONE: u32 = 5;
+    const SPONGE: u32 = 6;
+    const WET_SPONGE: u32 = 7;
+    const STAIRS: u32 = 8;
+
+    fn blocks() -> Registry {
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Stone").id(STONE).build(),
+            Block::new("Water")
+                .id(WATER)
+                .is_waterlogging_fluid(true)
+                .fluid_simulation(FluidConfig::new())
+                .build(),
+            Block::new("Lava")
+                .id(LAVA)
+                .fluid_simulation(FluidConfig::new().max_stage(3))
+                .build(),
+            Block::new("Obsidian").id(OBSIDIAN).build(),
+            Block::new("Cobblestone").id(COBBLESTONE).build(),
+            Block::new("Sponge").id(SPONGE).build(),
+            Block::new("Wet Sponge").id(WET_SPONGE).build(),
+            Block::new("Stairs")
+                .id(STAIRS)
+                .is_waterloggable(true)
+                .build(),
+        ]);
+        registry.tag_block(SPONGE, "absorbs_water");
+        registry
+    }
+
+    fn registry() -> Registry {
+        let mut registry = blocks();
+
+        registry.add_fluid_interaction(
+            FluidInteraction::new(LAVA, FluidContact::Block(WATER))
+                .source_result(OBSIDIAN)
+                .flowing_result(COBBLESTONE)
+                .sound("fizz"),
+        );
+        registry.add_fluid_interaction(
+            FluidInteraction::new(WATER, FluidContact::Tag("absorbs_water".to_owned()))
+                .result(0)
+                .contact_result(WET_SPONGE),
+        );
+
+        registry
+    }
+
+    fn chunks() -> Chunks {
+        let config = WorldConfig::new().build();
+        let mut chunks = Chunks::new(&config);
+        chunks.add(Chunk::ready(0, 0, &config));
+        chunks
+    }
+
+    fn fluid(id: u32, stage: u32) -> u32 {
+        VoxelPacker::new().with_id(id).with_stage(stage).pack()
+    }
+
+    #[test]
+    fn a_lava_source_touching_water_becomes_obsidian_and_fizzes() {
+        let registry = registry();
+        let mut chunks = chunks();
+        chunks.set_raw_voxel(4, 10, 4, fluid(LAVA, 0));
+        chunks.set_raw_voxel(5, 10, 4, fluid(WATER, 2));
+
+        let reaction = react_fluid(&Vec3(4, 10, 4), &chunks, &registry).expect("lava reacts");
+
+        assert_eq!(reaction.contact, Vec3(5, 10, 4));
+        assert_eq!(reaction.updates, vec![(Vec3(4, 10, 4), OBSIDIAN)]);
+        assert_eq!(reaction.sound.as_deref(), Some("fizz"));
+    }
+
+    #[test]
+    fn flowing_lava_touching_water_becomes_cobblestone() {
+        let registry = registry();
+        let mut chunks = chunks();
+        chunks.set_raw_voxel(4, 10, 4, fluid(LAVA, 2));
+        chunks.set_raw_voxel(4, 9, 4, fluid(WATER, 0));
+
+        let reaction = react_fluid(&Vec3(4, 10, 4), &chunks, &registry).expect("lava reacts");
+
+        assert_eq!(reaction.updates, vec![(Vec3(4, 10, 4), COBBLESTONE)]);
+    }
+
+    #[test]
+    fn water_reacting_only_through_its_own_table_leaves_lava_alone() {
+        let registry = registry();
+        let mut chunks = chunks();
+        chunks.set_raw_voxel(4, 10, 4, fluid(WATER, 0));
+        chunks.set_raw_voxel(5, 10, 4, fluid(LAVA, 0));
+
+        assert_eq!(react_fluid(&Vec3(4, 10, 4), &chunks, &registry), None);
+    }
+
+    #[test]
+    fn a_waterlogged_block_counts_as_water_but_keeps_its_block() {
+        let registry = registry();
+        let mut chunks = chunks();
+        chunks.set_raw_voxel(4, 10, 4, fluid(LAVA, 0));
+        let stairs =
+            BlockUtils::insert_waterlogged(VoxelPacker::new().with_id(STAIRS).pack(), true);
+        chunks.set_raw_voxel(4, 11, 4, stairs);
+
+        let reaction = react_fluid(&Vec3(4, 10, 4), &chunks, &registry).expect("lava reacts");
+
+        assert_eq!(reaction.updates, vec![(Vec3(4, 10, 4), OBSIDIAN)]);
+    }
+
+    #[test]
+    fn consuming_the_water_of_a_waterlogged_block_drains_it() {
+        let mut registry = blocks();
+        registry.add_fluid_interaction(
+            FluidInteraction::new(LAVA, FluidContact::Block(WATER)).contact_result(0),
+        );
+        let mut chunks = chunks();
+        chunks.set_raw_voxel(4, 10, 4, fluid(LAVA, 0));
+        let stairs =
+            BlockUtils::insert_waterlogged(VoxelPacker::new().with_id(STAIRS).pack(), true);
+        chunks.set_raw_voxel(4, 11, 4, stairs);
+
+        let reaction = react_fluid(&Vec3(4, 10, 4), &chunks, &registry).expect("lava reacts");
+
+        assert_eq!(
+            reaction.updates,
+            vec![(Vec3(4, 11, 4), VoxelPacker::new().with_id(STAIRS).pack())]
+        );
+    }
+
+    #[test]
+    fn turning_the_water_of_a_waterlogged_block_into_a_block_replaces_it() {
+        let mut registry = blocks();
+        registry.add_fluid_interaction(
+            FluidInteraction::new(LAVA, FluidContact::Block(WATER)).contact_result(STONE),
+        );
+        let mut chunks = chunks();
+        chunks.set_raw_voxel(4, 10, 4, fluid(LAVA, 0));
+        let stairs =
+            BlockUtils::insert_waterlogged(VoxelPacker::new().with_id(STAIRS).pack(), true);
+        chunks.set_raw_voxel(4, 11, 4, stairs);
+
+        let reaction = react_fluid(&Vec3(4, 10, 4), &chunks, &registry).expect("lava reacts");
+
+        assert_eq!(reaction.updates, vec![(Vec3(4, 11, 4), STONE)]);
+    }
+
+    #[test]
+    fn water_next_to_a_tagged_sponge_is_consumed_and_soaks_it() {
+        let registry = registry();
+        let mut chunks = chunks();
+        chunks.set_raw_voxel(4, 10, 4, fluid(WATER, 0));
+        chunks.set_voxel(4, 10, 5, SPONGE);
+
+        let reaction = react_fluid(&Vec3(4, 10, 4), &chunks, &registry).expect("water reacts");
+
+        assert_eq!(
+            reaction.updates,
+            vec![(Vec3(4, 10, 4), 0), (Vec3(4, 10, 5), WET_SPONGE)]
+        );
+        assert_eq!(reaction.sound, None);
+    }
+
+    #[test]
+    fn a_fluid_with_nothing_to_react_with_flows_as_usual() {
+        let registry = registry();
+        let mut chunks = chunks();
+        chunks.set_raw_voxel(4, 10, 4, fluid(LAVA, 0));
+        chunks.set_voxel(4, 9, 4, STONE);
+
+        assert_eq!(react_fluid(&Vec3(4, 10, 4), &chunks, &registry), None);
+    }
+}
@@ -1,4 +1,6 @@
-use std::sync::Arc;
+use std::{collections::VecDeque, sync::Arc};
+
+use hashbrown::HashSet;
 
 use crate::{BlockUtils, Registry, Vec3, VoxelAccess, VoxelPacker};
 
@@ -9,6 +11,11 @@ pub struct FluidConfig {
     pub infinite_source: bool,
     pub infinite_source_count: u32,
     pub flows_down_as_source: bool,
+    /// Conserve the fluid instead of spreading it from sources: each voxel
+    /// holds an amount that falls, evens out with its neighbours and is never
+    /// created or destroyed. `max_stage`, `infinite_source` and
+    /// `flows_down_as_source` do not apply.
+    pub finite_volume: bool,
 }
 
 impl Default for FluidConfig {
@@ -19,6 +26,7 @@ impl Default for FluidConfig {
             infinite_source: true,
             infinite_source_count: 2,
             flows_down_as_source: false,
+            finite_volume: false,
         }
     }
 }
@@ -48,8 +56,22 @@ impl FluidConfig {
         self.flows_down_as_source = enabled;
         self
     }
+
+    pub fn finite_volume(mut self, enabled: bool) -> Self {
+        self.finite_volume = enabled;
+        self
+    }
 }
 
+/// The most a finite-volume voxel can hold. Amounts map onto levels as
+/// `FINITE_VOLUME_MAX_AMOUNT - amount`, so a full voxel renders like a source
+/// and the level still fits the three waterlog level bits.
+pub const FINITE_VOLUME_MAX_AMOUNT: u32 = 8;
+
+/// How far sideways a thin finite-volume layer looks for somewhere to fall.
+/// Past this, a last unit of fluid is a puddle rather than part of a flow.
+const FINITE_VOLUME_DROP_RADIUS: u32 = 6;
+
 const HORIZONTAL_NEIGHBORS: [[i32; 2]; 4] = [[-1, 0], [1, 0], [0, -1], [0, 1]];
 
 /// The fluid's view of one voxel. A waterlogged block is water as far as the
@@ -152,6 +174,77 @@ impl FluidView<'_> {
         false
     }
 
+    /// How much finite-volume fluid this voxel holds, `0` if none.
+    fn amount_at(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        if !self.holds_fluid(vx, vy, vz) {
+            return 0;
+        }
+        FINITE_VOLUME_MAX_AMOUNT.saturating_sub(self.stage_at(vx, vy, vz))
+    }
+
+    /// How much more this voxel can take, `0` if it cannot take the fluid at
+    /// all. A solid block and a full voxel are the same to a flow.
+    fn room_at(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        if vy < 0 || !self.space.contains(vx, vy, vz) {
+            return 0;
+        }
+        if self.holds_fluid(vx, vy, vz) {
+            return FINITE_VOLUME_MAX_AMOUNT - self.amount_at(vx, vy, vz);
+        }
+        match self.target_at(vx, vy, vz) {
+            Some(_) => FINITE_VOLUME_MAX_AMOUNT,
+            None => 0,
+        }
+    }
+
+    /// How many horizontal steps from `(vx, vy, vz)` the nearest voxel with
+    /// room below it is, walking only through voxels that have room. `None`
+    /// if there is none within [`FINITE_VOLUME_DROP_RADIUS`].
+    fn drop_distance(&self, vx: i32, vy: i32, vz: i32) -> Option<u32> {
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::from([(vx, vz, 0)]);
+        visited.insert((vx, vz));
+
+        while let Some((x, z, distance)) = queue.pop_front() {
+            if self.room_at(x, vy - 1, z) > 0 {
+                return Some(distance);
+            }
+            if distance == FINITE_VOLUME_DROP_RADIUS {
+                continue;
+            }
+            for [dx, dz] in HORIZONTAL_NEIGHBORS {
+                let (nx, nz) = (x + dx, z + dz);
+                if self.room_at(nx, vy, nz) > 0 && visited.insert((nx, nz)) {
+                    queue.push_back((nx, nz, distance + 1));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The voxel word this position becomes once it holds `amount`.
+    fn with_amount(&self, vx: i32, vy: i32, vz: i32, amount: u32) -> u32 {
+        if amount == 0 {
+            return self.drained_voxel(vx, vy, vz);
+        }
+
+        let level = FINITE_VOLUME_MAX_AMOUNT - amount.min(FINITE_VOLUME_MAX_AMOUNT);
+        let raw = self.space.get_raw_voxel(vx, vy, vz);
+        if self.holds_fluid(vx, vy, vz) {
+            return if BlockUtils::extract_waterlogged(raw) {
+                BlockUtils::insert_waterlog_level(raw, level)
+            } else {
+                BlockUtils::insert_stage(raw, level)
+            };
+        }
+
+        match self.target_at(vx, vy, vz) {
+            Some(target) => self.filled_voxel(vx, vy, vz, level, target),
+            None => raw,
+        }
+    }
+
     fn is_at_fluid_edge(&self, vx: i32, vy: i32, vz: i32, max_stage: u32) -> bool {
         let curr_stage = self.stage_at(vx, vy, vz);
         if curr_stage >= max_stage {
@@ -174,6 +267,81 @@ pub type FluidTicker = Arc<dyn Fn(Vec3<i32>, &dyn VoxelAccess, &Registry) -> u64
 pub type FluidUpdater =
     Arc<dyn Fn(Vec3<i32>, &dyn VoxelAccess, &Registry) -> Vec<(Vec3<i32>, u32)> + Send + Sync>;
 
+/// One finite-volume step for the fluid at `(vx, vy, vz)`.
+///
+/// The fluid falls as far as the voxel below has room, and otherwise hands
+/// single units to horizontal neighbours holding at least two less, so
+/// neighbours settle to within one unit instead of sloshing forever. A
+/// neighbour nearer a drop takes fluid at any difference, which is what lets
+/// a last thin layer find its way down a hole. Every unit taken from this
+/// voxel is written to another, so the total never changes.
+fn finite_volume_step(view: &FluidView, vx: i32, vy: i32, vz: i32) -> Vec<(Vec3<i32>, u32)> {
+    let mut amount = view.amount_at(vx, vy, vz);
+    if amount == 0 {
+        return vec![];
+    }
+
+    let below_room = view.room_at(vx, vy - 1, vz);
+    if below_room > 0 {
+        let moved = amount.min(below_room);
+        let below = view.amount_at(vx, vy - 1, vz) + moved;
+        return vec![
+            (
+                Vec3(vx, vy, vz),
+                view.with_amount(vx, vy, vz, amount - moved),
+            ),
+            (
+                Vec3(vx, vy - 1, vz),
+                view.with_amount(vx, vy - 1, vz, below),
+            ),
+        ];
+    }
+
+    let own_distance = view.drop_distance(vx, vy, vz);
+    let mut neighbors: Vec<(i32, i32, u32, bool)> = HORIZONTAL_NEIGHBORS
+        .iter()
+        .filter(|[dx, dz]| view.room_at(vx + dx, vy, vz + dz) > 0)
+        .map(|[dx, dz]| {
+            let (nx, nz) = (vx + dx, vz + dz);
+            let is_downhill = own_distance.is_some_and(|own| {
+                view.drop_distance(nx, vy, nz)
+                    .is_some_and(|distance| distance < own)
+            });
+            (nx, nz, view.amount_at(nx, vy, nz), is_downhill)
+        })
+        .collect();
+
+    let mut gave = false;
+    loop {
+        let receiver = neighbors
+            .iter_mut()
+            .filter(|(_, _, neighbor, is_downhill)| {
+                neighbor + 1 < amount || (*is_downhill && *neighbor < amount)
+            })
+            .min_by_key(|(_, _, neighbor, is_downhill)| (!*is_downhill, *neighbor));
+
+        let Some((_, _, neighbor, _)) = receiver else {
+            break;
+        };
+
+        *neighbor += 1;
+        amount -= 1;
+        gave = true;
+    }
+
+    if !gave {
+        return vec![];
+    }
+
+    let mut updates = vec![(Vec3(vx, vy, vz), view.with_amount(vx, vy, vz, amount))];
+    for (nx, nz, neighbor, _) in neighbors {
+        if neighbor != view.amount_at(nx, vy, nz) {
+            updates.push((Vec3(nx, vy, nz), view.with_amount(nx, vy, nz, neighbor)));
+        }
+    }
+    updates
+}
+
 pub fn create_fluid_active_fn(fluid_id: u32, config: FluidConfig) -> (FluidTicker, FluidUpdater) {
     let tick_rate = config.tick_rate;
     let config_clone = config.clone();
@@ -189,6 +357,10 @@ pub fn create_fluid_active_fn(fluid_id: u32, config: FluidConfig) -> (FluidTicke
                 fluid_id,
                 is_waterlogging: registry.waterlogging_fluid_id() == Some(fluid_id),
             };
+            if config_clone.finite_volume {
+                return finite_volume_step(&view, vx, vy, vz);
+            }
+
             let curr_stage = view.stage_at(vx, vy, vz);
 
             if config_clone.infinite_source && curr_stage > 0 {
@@ -278,3 +450,223 @@ pub fn create_fluid_active_fn(fluid_id: u32, config: FluidConfig) -> (FluidTicke
 
     (ticker, updater)
 }
+
+#[cfg(test)]
+mod finite_volume_tests {
+    use super::*;
+    use crate::{Block, Chunk, Chunks, VoxelPacker, WorldConfig};
+
+    const STONE: u32 = 1;
+    const WATER: u32 = 2;
+
+    fn registry(config: FluidConfig) -> Registry {
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Stone").id(STONE).build(),
+            Block::new("Water")
+                .id(WATER)
+                .is_waterlogging_fluid(true)
+                .fluid_simulation(config)
+                .build(),
+        ]);
+        registry
+    }
+
+    fn chunks() -> Chunks {
+        let config = WorldConfig::new().build();
+        let mut chunks = Chunks::new(&config);
+        chunks.add(Chunk::ready(0, 0, &config));
+        chunks
+    }
+
+    /// A closed stone basin whose floor sits at `floor` and whose inside spans
+    /// `1..=size` on both horizontal axes, with walls up to `top`.
+    fn basin(chunks: &mut Chunks, floor: i32, size: i32, top: i32) {
+        for x in 0..=size + 1 {
+            for z in 0..=size + 1 {
+                chunks.set_voxel(x, floor, z, STONE);
+                let is_wall = x == 0 || z == 0 || x == size + 1 || z == size + 1;
+                if is_wall {
+                    for y in floor..=top {
+                        chunks.set_voxel(x, y, z, STONE);
+                    }
+                }
+            }
+        }
+    }
+
+    fn pour(chunks: &mut Chunks, vx: i32, vy: i32, vz: i32, amount: u32) {
+        let level = FINITE_VOLUME_MAX_AMOUNT - amount;
+        let raw = VoxelPacker::new().with_id(WATER).with_stage(level).pack();
+        chunks.set_raw_voxel(vx, vy, vz, raw);
+    }
+
+    fn amount(chunks: &Chunks, vx: i32, vy: i32, vz: i32) -> u32 {
+        let raw = chunks.get_raw_voxel(vx, vy, vz);
+        let holds_water =
+            BlockUtils::extract_id(raw) == WATER || BlockUtils::extract_waterlogged(raw);
+        if holds_water {
+            FINITE_VOLUME_MAX_AMOUNT - BlockUtils::extract_fluid_level(raw)
+        } else {
+            0
+        }
+    }
+
+    fn total(chunks: &Chunks) -> u32 {
+        let mut total = 0;
+        for x in 0..8 {
+            for y in 0..24 {
+                for z in 0..8 {
+                    total += amount(chunks, x, y, z);
+                }
+            }
+        }
+        total
+    }
+
+    /// Tick every wet voxel bottom-up, committing each update before the next
+    /// voxel reads, until nothing moves. Returns how many passes it took.
+    fn settle(chunks: &mut Chunks, registry: &Registry) -> usize {
+        let updater = registry
+            .get_block_by_id(WATER)
+            .active_updater
+            .clone()
+            .unwrap();
+
+        for pass in 0..500 {
+            let mut moved = false;
+            for y in 0..24 {
+                for x in 0..8 {
+                    for z in 0..8 {
+                        if amount(chunks, x, y, z) == 0 {
+                            continue;
+                        }
+                        for (voxel, raw) in updater(Vec3(x, y, z), &*chunks, registry) {
+                            chunks.set_raw_voxel(voxel.0, voxel.1, voxel.2, raw);
+                            moved = true;
+                        }
+                    }
+                }
+            }
+            if !moved {
+                return pass;
+            }
+        }
+
+        panic!("finite-volume fluid never settled");
+    }
+
+    fn finite_registry() -> Registry {
+        registry(FluidConfig::new().finite_volume(true))
+    }
+
+    #[test]
+    fn a_pour_spreads_level_and_conserves_its_volume() {
+        let registry = finite_registry();
+        let mut chunks = chunks();
+        basin(&mut chunks, 0, 4, 4);
+        pour(&mut chunks, 2, 3, 2, 8);
+        pour(&mut chunks, 2, 2, 2, 8);
+
+        settle(&mut chunks, &registry);
+
+        assert_eq!(total(&chunks), 16);
+        for x in 1..=4 {
+            for z in 1..=3 {
+                let (here, next) = (amount(&chunks, x, 1, z), amount(&chunks, x, 1, z + 1));
+                assert!(
+                    here.abs_diff(next) <= 1,
+                    "({x}, {z}) holds {here}, its neighbour {next}"
+                );
+                let (here, next) = (amount(&chunks, z, 1, x), amount(&chunks, z + 1, 1, x));
+                assert!(
+                    here.abs_diff(next) <= 1,
+                    "({z}, {x}) holds {here}, its neighbour {next}"
+                );
+            }
+        }
+        assert_eq!(amount(&chunks, 2, 2, 2), 0, "16 units fit in one layer");
+    }
+
+    #[test]
+    fn water_drains_through_a_hole_into_the_chamber_below() {
+        let registry = finite_registry();
+        let mut chunks = chunks();
+        basin(&mut chunks, 0, 4, 4);
+        basin(&mut chunks, 4, 4, 8);
+        // A hole in the upper floor, over the lower basin.
+        chunks.set_voxel(4, 4, 4, 0);
+        for x in 1..=3 {
+            pour(&mut chunks, x, 5, 1, 5);
+        }
+
+        settle(&mut chunks, &registry);
+
+        assert_eq!(total(&chunks), 15);
+        let upper: u32 = (1..=4)
+            .flat_map(|x| (1..=4).map(move |z| (x, z)))
+            .map(|(x, z)| amount(&chunks, x, 5, z))
+            .sum();
+        assert_eq!(upper, 0, "nothing may be left stranded above the hole");
+    }
+
+    #[test]
+    fn falling_water_fills_a_container_before_spilling() {
+        let registry = finite_registry();
+        let mut chunks = chunks();
+        // A one-wide well two deep, open at the top into a wide basin.
+        basin(&mut chunks, 0, 4, 6);
+        for x in 1..=4 {
+            for z in 1..=4 {
+                if (x, z) != (2, 2) {
+                    chunks.set_voxel(x, 1, z, STONE);
+                    chunks.set_voxel(x, 2, z, STONE);
+                }
+            }
+        }
+        pour(&mut chunks, 2, 5, 2, 8);
+        pour(&mut chunks, 2, 4, 2, 8);
+        pour(&mut chunks, 2, 3, 2, 4);
+
+        settle(&mut chunks, &registry);
+
+        assert_eq!(amount(&chunks, 2, 1, 2), 8);
+        assert_eq!(amount(&chunks, 2, 2, 2), 8);
+        assert_eq!(total(&chunks), 20);
+    }
+
+    #[test]
+    fn two_sources_with_a_gap_do_not_create_water() {
+        let registry = finite_registry();
+        let mut chunks = chunks();
+        basin(&mut chunks, 0, 3, 3);
+        for x in 1..=3 {
+            chunks.set_voxel(x, 1, 2, STONE);
+            chunks.set_voxel(x, 1, 3, STONE);
+        }
+        pour(&mut chunks, 1, 1, 1, 8);
+        pour(&mut chunks, 3, 1, 1, 8);
+
+        settle(&mut chunks, &registry);
+
+        // The classic rules would turn the gap into a third source.
+        assert_eq!(total(&chunks), 16);
+    }
+
+    #[test]
+    fn a_waterlogged_block_takes_water_without_losing_its_block() {
+        let mut registry = finite_registry();
+        registry.register_block(&Block::new("Stairs").id(3).is_waterloggable(true).build());
+        let mut chunks = chunks();
+        basin(&mut chunks, 0, 1, 3);
+        chunks.set_voxel(1, 1, 1, 3);
+        pour(&mut chunks, 1, 2, 1, 6);
+
+        settle(&mut chunks, &registry);
+
+        assert_eq!(chunks.get_voxel(1, 1, 1), 3);
+        assert!(chunks.get_voxel_waterlogged(1, 1, 1));
+        assert_eq!(amount(&chunks, 1, 1, 1), 6);
+        assert_eq!(total(&chunks), 6);
+    }
+}
@@ -0,0 +1,54 @@
+//! How a client receives chunk meshes, negotiated at JOIN time.
+//!
+//! Server-meshed chunks dominate the bytes a world sends. Clients that
+//! advertise [`MESH_V1_CAPABILITY`] receive each geometry's vertex buffers
+//! in the mesher's compact layout (see `voxelize_mesher::pack_vertices`):
+//! quantized positions, indices into a per-geometry tile table in place of
+//! float UVs and implied quad indices, under half the float buffers' size.
+
+/// The JOIN capability string a client sends to opt into [`MeshFormat::CompactV1`].
+pub const MESH_V1_CAPABILITY: &str = "mesh.v1";
+
+/// The vertex layout a client's chunk geometries ship in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MeshFormat {
+    /// LZ4-compressed float positions and UVs and integer indices and
+    /// lights, exactly as before the compact layout existed.
+    #[default]
+    Legacy,
+    /// A `voxelize_mesher::COMPACT_MESH_V1` payload in `Geometry.packed`.
+    /// Geometries the layout can't hold still ship in the legacy buffers.
+    CompactV1,
+}
+
+impl MeshFormat {
+    pub fn negotiate(capabilities: &[String]) -> Self {
+        if capabilities.iter().any(|c| c == MESH_V1_CAPABILITY) {
+            Self::CompactV1
+        } else {
+            Self::Legacy
+        }
+    }
+
+    pub fn is_compact(&self) -> bool {
+        matches!(self, Self::CompactV1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiation_requires_the_exact_capability() {
+        assert_eq!(
+            MeshFormat::negotiate(&["motion.v1".to_owned(), "mesh.v1".to_owned()]),
+            MeshFormat::CompactV1
+        );
+        assert_eq!(
+            MeshFormat::negotiate(&["mesh.v2".to_owned()]),
+            MeshFormat::Legacy
+        );
+        assert_eq!(MeshFormat::negotiate(&[]), MeshFormat::Legacy);
+    }
+}
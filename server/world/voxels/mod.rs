@@ -3,7 +3,9 @@ mod background_chunk_saver;
 mod block;
 mod chunk;
 mod chunks;
+mod fluid_interactions;
 mod fluids;
+mod mesh_format;
 mod space;
 mod waterlogging;
 
@@ -12,6 +14,8 @@ pub use waterlogging::WaterloggingRules;
 pub use background_chunk_saver::*;
 pub use block::*;
 pub use chunk::*;
-pub use chunks::Chunks;
+pub use chunks::{ChunkSnapshot, Chunks};
+pub use fluid_interactions::*;
 pub use fluids::*;
+pub use mesh_format::*;
 pub use space::*;
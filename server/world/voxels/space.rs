@@ -132,6 +132,23 @@ impl SpaceBuilder<'_> {
     }
 
     /// Create a `Space` instance with the instructed data loaded in.
+    /// Every chunk light can reach from the center chunk, widened to every
+    /// chunk the margin overlaps when the margin is wider than light travels.
+    fn gathered_chunks(&self) -> Vec<Vec2<i32>> {
+        let Vec2(cx, cz) = self.coords;
+        let mut gathered = self.chunks.light_traversed_chunks(&self.coords);
+        let reach = (self.options.margin as f32 / self.options.chunk_size as f32).ceil() as i32;
+        for x in -reach..=reach {
+            for z in -reach..=reach {
+                let n_coords = Vec2(cx + x, cz + z);
+                if !gathered.contains(&n_coords) {
+                    gathered.push(n_coords);
+                }
+            }
+        }
+        gathered
+    }
+
     pub fn build(self) -> Space {
         let SpaceOptions {
             margin,
@@ -149,8 +166,7 @@ impl SpaceBuilder<'_> {
         let width = chunk_size + margin * 2;
 
         let (voxels, lights, height_maps): (HashMap<_, _>, HashMap<_, _>, HashMap<_, _>) = self
-            .chunks
-            .light_traversed_chunks(&self.coords)
+            .gathered_chunks()
             .into_par_iter()
             .filter_map(|n_coords| {
                 if !self.chunks.is_within_world(&n_coords) {
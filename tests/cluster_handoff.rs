@@ -0,0 +1,261 @@
+//! End-to-end world handoff between two real server processes' worth of
+//! engine (two servers on loopback) behind a cluster router.
+
+use std::time::{Duration, Instant};
+
+use actix_web::{App, HttpServer};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use voxelize::{
+    decode_message, encode_message, ClusterNode, ClusterRouter, Message, MessageType,
+    MethodProtocol, Server, Voxelize, World, WorldConfig, CLUSTER_SECRET_HEADER,
+};
+
+const FRAME_LIMIT: usize = 16 * 1024 * 1024;
+const SECRET: &str = "handoff-test-secret";
+
+fn alpha_config(name: &str) -> Option<WorldConfig> {
+    (name == "alpha").then(|| {
+        WorldConfig::new()
+            .min_chunk([0, 0])
+            .max_chunk([0, 0])
+            .default_time(0.0)
+            .build()
+    })
+}
+
+/// One engine server with the cluster node routes mounted; returns its base
+/// URL.
+async fn start_node(worlds: &[&str]) -> String {
+    let mut server = Server::new().debug(false).port(0).build();
+    for name in worlds {
+        let config = alpha_config(name).expect("test worlds resolve");
+        server
+            .add_world(World::new(name, &config))
+            .expect("world should register");
+    }
+
+    let bound = Voxelize::bind_with(server, |voxelize| {
+        let node = ClusterNode::new(voxelize.server().clone(), SECRET, alpha_config)
+            .expect("the secret is not empty");
+        App::new()
+            .configure(voxelize.configure())
+            .configure(node.configure())
+    })
+    .await
+    .expect("node should bind");
+    let url = format!("http://127.0.0.1:{}", bound.addr().port());
+    actix_web::rt::spawn(bound.wait_until_stopped());
+    url
+}
+
+fn join_message(world: &str) -> Vec<u8> {
+    encode_message(
+        &Message::new(&MessageType::Join)
+            .json(&json!({ "world": world, "username": "tester" }).to_string())
+            .build(),
+    )
+}
+
+fn set_time_message(time: f32) -> Vec<u8> {
+    encode_message(
+        &Message::new(&MessageType::Method)
+            .method(MethodProtocol {
+                name: "vox-builtin:set-time".to_owned(),
+                payload: json!({ "time": time }).to_string(),
+            })
+            .build(),
+    )
+}
+
+async fn post_json(url: &str, body: Value) -> (u16, Value) {
+    let mut response = awc::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .finish()
+        .post(url)
+        .insert_header((CLUSTER_SECRET_HEADER, SECRET))
+        .send_json(&body)
+        .await
+        .expect("post should reach the server");
+    let status = response.status().as_u16();
+    let body = response.json::<Value>().await.expect("json body");
+    (status, body)
+}
+
+async fn get_json(url: &str) -> Value {
+    awc::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .finish()
+        .get(url)
+        .insert_header((CLUSTER_SECRET_HEADER, SECRET))
+        .send()
+        .await
+        .expect("get should reach the server")
+        .json::<Value>()
+        .await
+        .expect("json body")
+}
+
+/// Join `world` through the router and return the connection and the INIT
+/// message's JSON.
+async fn join_through(
+    router: &str,
+    world: &str,
+) -> (
+    impl futures_util::Stream<Item = Result<awc::ws::Frame, awc::error::WsProtocolError>>
+        + futures_util::Sink<awc::ws::Message, Error = awc::error::WsProtocolError>
+        + Unpin,
+    Value,
+) {
+    let (_, mut connection) = awc::Client::new()
+        .ws(format!("{}/ws/", router))
+        .max_frame_size(FRAME_LIMIT)
+        .connect()
+        .await
+        .expect("ws connect through the router");
+    connection
+        .send(awc::ws::Message::Binary(join_message(world).into()))
+        .await
+        .unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(20);
+    loop {
+        assert!(Instant::now() < deadline, "no INIT through the router");
+        match tokio::time::timeout(Duration::from_secs(20), connection.next()).await {
+            Ok(Some(Ok(awc::ws::Frame::Binary(bytes)))) => {
+                let message = decode_message(&bytes).expect("server frames decode");
+                assert_ne!(
+                    message.r#type,
+                    MessageType::Error as i32,
+                    "join failed: {}",
+                    message.text
+                );
+                if message.r#type == MessageType::Init as i32 {
+                    let init = serde_json::from_str(&message.json).expect("INIT json");
+                    return (connection, init);
+                }
+            }
+            Ok(Some(Ok(_))) => continue,
+            other => panic!("session ended before INIT: {:?}", other.map(|_| ())),
+        }
+    }
+}
+
+/// A world with a connected player moves from node A to node B: the player
+/// is told to reconnect, rejoins through the same router address, and finds
+/// the world's clock where it left it, now hosted on B.
+#[actix_web::test]
+async fn world_migrates_between_nodes_and_clients_rejoin_through_the_router() {
+    let node_a = start_node(&["alpha"]).await;
+    let node_b = start_node(&[]).await;
+
+    let router = ClusterRouter::new(SECRET)
+        .expect("the secret is not empty")
+        .node("a", &node_a)
+        .node("b", &node_b)
+        .assign("alpha", "a");
+    let app_router = router.clone();
+    let http = HttpServer::new(move || App::new().configure(app_router.configure()))
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .expect("router should bind");
+    let router_url = format!("http://127.0.0.1:{}", http.addrs()[0].port());
+    actix_web::rt::spawn(http.run());
+
+    let (mut connection, init) = join_through(&router_url, "alpha").await;
+    assert!(init["stats"]["time"].as_f64().unwrap() < 1000.0);
+
+    connection
+        .send(awc::ws::Message::Binary(set_time_message(9000.0).into()))
+        .await
+        .unwrap();
+    // The method is handled on A's next tick; give it a few.
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let unauthorized = awc::Client::new()
+        .post(format!("{}/cluster/migrate", router_url))
+        .send_json(&json!({ "world": "alpha", "to": "b" }))
+        .await
+        .expect("post should reach the router");
+    assert_eq!(unauthorized.status().as_u16(), 403);
+
+    let (status, body) = post_json(
+        &format!("{}/cluster/migrate", router_url),
+        json!({ "world": "alpha", "to": "b" }),
+    )
+    .await;
+    assert_eq!(status, 200, "migration failed: {}", body);
+
+    let close = loop {
+        match tokio::time::timeout(Duration::from_secs(10), connection.next()).await {
+            Ok(Some(Ok(awc::ws::Frame::Close(reason)))) => break reason,
+            Ok(Some(Ok(_))) => continue,
+            other => panic!(
+                "expected a close frame after migration: {:?}",
+                other.map(|_| ())
+            ),
+        }
+    };
+    assert_eq!(
+        close.map(|reason| reason.code),
+        Some(awc::ws::CloseCode::Restart)
+    );
+
+    let (_connection, init) = join_through(&router_url, "alpha").await;
+    let time = init["stats"]["time"].as_f64().unwrap();
+    assert!(
+        (9000.0..11000.0).contains(&time),
+        "the world clock moved with the world (time {})",
+        time
+    );
+
+    assert_eq!(
+        get_json(&format!("{}/cluster/worlds", node_a)).await,
+        json!([])
+    );
+    assert_eq!(
+        get_json(&format!("{}/cluster/worlds", node_b)).await,
+        json!(["alpha"])
+    );
+    assert_eq!(router.owner("alpha").as_deref(), Some("b"));
+    assert_eq!(
+        get_json(&format!("{}/cluster/assignments", router_url)).await["assignments"],
+        json!({ "alpha": "b" })
+    );
+}
+
+/// A target node that cannot build the world refuses it, and the world is
+/// handed back to the node it came from.
+#[actix_web::test]
+async fn refused_adoption_hands_the_world_back() {
+    let node_a = start_node(&["alpha"]).await;
+
+    let bound = Voxelize::bind_with(Server::new().debug(false).port(0).build(), |voxelize| {
+        let node = ClusterNode::new(voxelize.server().clone(), SECRET, |_: &str| None)
+            .expect("the secret is not empty");
+        App::new()
+            .configure(voxelize.configure())
+            .configure(node.configure())
+    })
+    .await
+    .expect("node should bind");
+    let node_b = format!("http://127.0.0.1:{}", bound.addr().port());
+    actix_web::rt::spawn(bound.wait_until_stopped());
+
+    let router = ClusterRouter::new(SECRET)
+        .expect("the secret is not empty")
+        .node("a", &node_a)
+        .node("b", &node_b)
+        .assign("alpha", "a");
+
+    let error = router
+        .migrate("alpha", "b")
+        .await
+        .expect_err("b has no config for alpha");
+    assert!(error.to_string().contains("404"), "{}", error);
+    assert_eq!(router.owner("alpha").as_deref(), Some("a"));
+    assert_eq!(
+        get_json(&format!("{}/cluster/worlds", node_a)).await,
+        json!(["alpha"])
+    );
+}
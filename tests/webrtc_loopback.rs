@@ -0,0 +1,378 @@
+//! Loopback harness for the hybrid WebSocket/WebRTC transport: a real
+//! server, real WebSocket sessions and a real in-process WebRTC peer per
+//! client, all on the local machine. Latest-wins state must ride the unordered data channel
+//! when a client has one open, while reliable events stay on the WebSocket.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use actix_web::{web, App};
+use awc::error::WsProtocolError;
+use futures_util::{Sink, SinkExt, Stream, StreamExt};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use voxelize::{
+    create_rtc_senders, decode_message, encode_message, rtc_candidate, rtc_offer,
+    ChatMessageProtocol, FragmentAssembler, Message, MessageType, PeerProtocol, RtcIceServers,
+    Server, Voxelize, WebRTCPeers, World, WorldConfig,
+};
+use webrtc::api::setting_engine::SettingEngine;
+use webrtc::api::{APIBuilder, API};
+use webrtc::data_channel::data_channel_init::RTCDataChannelInit;
+use webrtc::data_channel::RTCDataChannel;
+use webrtc::ice::network_type::NetworkType;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::RTCPeerConnection;
+
+const WORLD: &str = "loopback";
+
+/// A client WebSocket session as the helpers below drive it.
+trait WsConnection:
+    Sink<awc::ws::Message, Error = WsProtocolError>
+    + Stream<Item = Result<awc::ws::Frame, WsProtocolError>>
+    + Unpin
+{
+}
+
+impl<T> WsConnection for T where
+    T: Sink<awc::ws::Message, Error = WsProtocolError>
+        + Stream<Item = Result<awc::ws::Frame, WsProtocolError>>
+        + Unpin
+{
+}
+
+/// A WebRTC API that only gathers UDP host candidates, so both ends of the
+/// harness connect over the machine's own interfaces without STUN.
+fn local_api() -> Arc<API> {
+    let mut se = SettingEngine::default();
+    se.set_network_types(vec![NetworkType::Udp4]);
+    Arc::new(APIBuilder::new().with_setting_engine(se).build())
+}
+
+/// Boot a real server with one tiny world and the signaling routes mounted
+/// the way a game mounts them, and return its base `http://` url once ticks
+/// flow.
+async fn start_server() -> String {
+    let rtc_senders = create_rtc_senders();
+
+    let mut server = Server::new().debug(false).port(0).build();
+    server.set_rtc_senders(rtc_senders.clone());
+    let config = WorldConfig::new()
+        .min_chunk([0, 0])
+        .max_chunk([0, 0])
+        .build();
+    server
+        .add_world(World::new(WORLD, &config))
+        .expect("world should register");
+
+    let api = local_api();
+    let peers: WebRTCPeers = Default::default();
+    let bound = Voxelize::bind_with(server, move |voxelize| {
+        App::new()
+            .configure(voxelize.configure())
+            .app_data(web::Data::new(api.clone()))
+            .app_data(web::Data::new(peers.clone()))
+            .app_data(web::Data::new(rtc_senders.clone()))
+            .app_data(web::Data::new(RtcIceServers(vec![])))
+            .route("/rtc/offer", web::post().to(rtc_offer))
+            .route("/rtc/candidate", web::post().to(rtc_candidate))
+    })
+    .await
+    .expect("bind should succeed");
+    let base = format!("http://127.0.0.1:{}", bound.addr().port());
+    actix_web::rt::spawn(bound.wait_until_stopped());
+
+    let deadline = Instant::now() + Duration::from_secs(60);
+    loop {
+        let ready = awc::Client::new()
+            .get(format!("{}/health", base))
+            .send()
+            .await
+            .map(|response| response.status().as_u16() == 200)
+            .unwrap_or(false);
+        if ready {
+            return base;
+        }
+        assert!(Instant::now() < deadline, "server never became ready");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+async fn send(connection: &mut impl WsConnection, message: Message) {
+    connection
+        .send(awc::ws::Message::Binary(encode_message(&message).into()))
+        .await
+        .expect("ws send");
+}
+
+/// Open a WebSocket session under `client_id` and join the world, waiting
+/// for the INIT so the client is live in the world before returning.
+async fn join(base: &str, client_id: &str) -> impl WsConnection {
+    let url = format!("{}/ws/?client_id={}", base, client_id);
+    let (_, mut connection) = awc::Client::new()
+        .ws(url)
+        .max_frame_size(16 * 1024 * 1024)
+        .connect()
+        .await
+        .expect("ws connect");
+    send(
+        &mut connection,
+        Message::new(&MessageType::Join)
+            .json(&json!({ "world": WORLD, "username": client_id }).to_string())
+            .build(),
+    )
+    .await;
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        let messages = read_ws(&mut connection, Duration::from_millis(100)).await;
+        if messages
+            .iter()
+            .any(|message| message.r#type == MessageType::Init as i32)
+        {
+            return connection;
+        }
+    }
+    panic!("{} never received INIT", client_id);
+}
+
+/// Everything the WebSocket delivers within `window`.
+async fn read_ws(connection: &mut impl WsConnection, window: Duration) -> Vec<Message> {
+    let mut messages = vec![];
+    let deadline = tokio::time::Instant::now() + window;
+    while let Ok(Some(frame)) = tokio::time::timeout_at(deadline, connection.next()).await {
+        if let Ok(awc::ws::Frame::Binary(bytes)) = frame {
+            messages.extend(decode_message(&bytes).ok());
+        }
+    }
+    messages
+}
+
+/// Everything the data channel delivers within `window`.
+async fn read_rtc(rx: &mut mpsc::UnboundedReceiver<Vec<u8>>, window: Duration) -> Vec<Message> {
+    let mut messages = vec![];
+    let deadline = tokio::time::Instant::now() + window;
+    while let Ok(Some(bytes)) = tokio::time::timeout_at(deadline, rx.recv()).await {
+        messages.extend(decode_message(&bytes).ok());
+    }
+    messages
+}
+
+/// The client end of a negotiated data channel. The connection and channel
+/// are held so they stay open for the test's lifetime.
+struct RtcClient {
+    _pc: Arc<RTCPeerConnection>,
+    _channel: Arc<RTCDataChannel>,
+    rx: mpsc::UnboundedReceiver<Vec<u8>>,
+}
+
+/// Negotiate a data channel for `client_id` through `/rtc/offer`. Server
+/// messages arrive on the returned client's `rx`, fully reassembled.
+async fn connect_rtc(base: &str, client_id: &str, ordered: bool) -> RtcClient {
+    let pc = Arc::new(
+        local_api()
+            .new_peer_connection(RTCConfiguration::default())
+            .await
+            .expect("client peer connection"),
+    );
+    let dc = pc
+        .create_data_channel(
+            "voxelize",
+            Some(RTCDataChannelInit {
+                ordered: Some(ordered),
+                max_retransmits: if ordered { None } else { Some(0) },
+                ..Default::default()
+            }),
+        )
+        .await
+        .expect("client data channel");
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let assembler = Arc::new(tokio::sync::Mutex::new(FragmentAssembler::new()));
+    dc.on_message(Box::new(move |msg| {
+        let tx = tx.clone();
+        let assembler = assembler.clone();
+        Box::pin(async move {
+            if let Some(complete) = assembler.lock().await.process(&msg.data) {
+                let _ = tx.send(complete);
+            }
+        })
+    }));
+    let (open_tx, mut open_rx) = mpsc::unbounded_channel();
+    dc.on_open(Box::new(move || {
+        let _ = open_tx.send(());
+        Box::pin(async {})
+    }));
+
+    let offer = pc.create_offer(None).await.expect("offer");
+    let mut gathered = pc.gathering_complete_promise().await;
+    pc.set_local_description(offer).await.expect("local sdp");
+    let _ = gathered.recv().await;
+    let local = pc.local_description().await.expect("gathered offer");
+
+    let mut response = awc::Client::new()
+        .post(format!("{}/rtc/offer", base))
+        .timeout(Duration::from_secs(15))
+        .send_json(&json!({ "sdp": local.sdp, "client_id": client_id }))
+        .await
+        .expect("offer request");
+    assert_eq!(response.status().as_u16(), 200, "offer accepted");
+    let answer: Value = response.json().await.expect("answer body");
+    pc.set_remote_description(
+        RTCSessionDescription::answer(answer["sdp"].as_str().unwrap().to_owned())
+            .expect("answer sdp"),
+    )
+    .await
+    .expect("remote sdp");
+
+    tokio::time::timeout(Duration::from_secs(15), open_rx.recv())
+        .await
+        .expect("data channel should open over loopback");
+
+    RtcClient {
+        _pc: pc,
+        _channel: dc,
+        rx,
+    }
+}
+
+fn move_message(x: f32) -> Message {
+    Message::new(&MessageType::Peer)
+        .peers(&[PeerProtocol {
+            id: String::new(),
+            username: String::new(),
+            metadata: json!({ "position": [x, 80.0, 0.0] }).to_string(),
+        }])
+        .build()
+}
+
+/// The x positions `peer_id` was reported at across PEER messages.
+fn peer_xs(messages: &[Message], peer_id: &str) -> Vec<f64> {
+    messages
+        .iter()
+        .filter(|m| m.r#type == MessageType::Peer as i32)
+        .flat_map(|m| m.peers.iter())
+        .filter(|p| p.id == peer_id)
+        .filter_map(|p| {
+            serde_json::from_str::<Value>(&p.metadata)
+                .ok()?
+                .get("position")?
+                .get(0)?
+                .as_f64()
+        })
+        .collect()
+}
+
+/// Keep moving `mover` to `x` until the data channel reports that position
+/// for `peer_id`, since the server registers the channel asynchronously
+/// after it opens.
+async fn await_rtc_position(
+    mover: &mut impl WsConnection,
+    rtc: &mut RtcClient,
+    peer_id: &str,
+    x: f32,
+) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        send(mover, move_message(x)).await;
+        let seen = read_rtc(&mut rtc.rx, Duration::from_millis(150)).await;
+        if peer_xs(&seen, peer_id).contains(&(x as f64)) {
+            return true;
+        }
+        // Nudge away so the next send is a real change again.
+        send(mover, move_message(x + 0.5)).await;
+    }
+    false
+}
+
+/// Like [`await_rtc_position`], observing `observer`'s WebSocket instead.
+async fn await_ws_position(
+    mover: &mut impl WsConnection,
+    observer: &mut impl WsConnection,
+    peer_id: &str,
+    x: f32,
+) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        send(mover, move_message(x)).await;
+        let seen = read_ws(observer, Duration::from_millis(150)).await;
+        if peer_xs(&seen, peer_id).contains(&(x as f64)) {
+            return true;
+        }
+        send(mover, move_message(x + 0.5)).await;
+    }
+    false
+}
+
+#[actix_web::test]
+async fn state_rides_the_data_channel_and_events_stay_on_the_websocket() {
+    let base = start_server().await;
+
+    let mut rtc_client = join(&base, "rtc-client").await;
+    let mut ws_client = join(&base, "ws-client").await;
+    let mut rtc = connect_rtc(&base, "rtc-client", false).await;
+
+    // State path: the other client's motion reaches the RTC client over the
+    // data channel...
+    let reached_rtc = await_rtc_position(&mut ws_client, &mut rtc, "ws-client", 7.0).await;
+    assert!(reached_rtc, "peer state must arrive over the data channel");
+
+    // ...and, once the channel is live, no longer over its WebSocket.
+    read_ws(&mut rtc_client, Duration::from_millis(200)).await;
+    send(&mut ws_client, move_message(11.0)).await;
+    let rtc_state = read_rtc(&mut rtc.rx, Duration::from_millis(500)).await;
+    let ws_frames = read_ws(&mut rtc_client, Duration::from_millis(200)).await;
+    assert!(peer_xs(&rtc_state, "ws-client").contains(&11.0));
+    assert!(
+        peer_xs(&ws_frames, "ws-client").is_empty(),
+        "state must not be duplicated onto the WebSocket"
+    );
+
+    // Fallback path: a client without a data channel keeps getting state
+    // over its WebSocket.
+    let reached_ws = await_ws_position(&mut rtc_client, &mut ws_client, "rtc-client", 3.0).await;
+    assert!(reached_ws, "WebSocket-only clients still receive state");
+
+    // Reliable events never take the unreliable channel.
+    send(
+        &mut ws_client,
+        Message::new(&MessageType::Chat)
+            .chat(ChatMessageProtocol {
+                r#type: "chat".into(),
+                sender: "ws-client".into(),
+                body: "over the socket".into(),
+                ..Default::default()
+            })
+            .build(),
+    )
+    .await;
+    let ws_frames = read_ws(&mut rtc_client, Duration::from_millis(500)).await;
+    let rtc_frames = read_rtc(&mut rtc.rx, Duration::from_millis(100)).await;
+    let is_chat = |m: &Message| m.r#type == MessageType::Chat as i32;
+    assert!(
+        ws_frames.iter().any(is_chat),
+        "chat arrives on the WebSocket"
+    );
+    assert!(
+        !rtc_frames.iter().any(is_chat),
+        "chat never rides the data channel"
+    );
+}
+
+#[actix_web::test]
+async fn ordered_channels_never_take_over_the_state_lane() {
+    let base = start_server().await;
+
+    let mut rtc_client = join(&base, "ordered-client").await;
+    let mut ws_client = join(&base, "mover").await;
+    let mut rtc = connect_rtc(&base, "ordered-client", true).await;
+
+    let reached_ws = await_ws_position(&mut ws_client, &mut rtc_client, "mover", 5.0).await;
+    assert!(reached_ws, "state keeps flowing over the WebSocket");
+    assert!(
+        read_rtc(&mut rtc.rx, Duration::from_millis(200))
+            .await
+            .is_empty(),
+        "an ordered channel must carry nothing"
+    );
+}